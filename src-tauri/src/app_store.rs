@@ -21,11 +21,74 @@ fn update_cached_override(value: Option<PathBuf>) {
     }
 }
 
-/// 获取缓存中的 app_config_dir 覆盖路径
+/// 获取 app_config_dir 覆盖路径（启动时指定的数据目录优先于 Store 中的配置）
 pub fn get_app_config_dir_override() -> Option<PathBuf> {
+    if let Some(dir) = startup_data_dir() {
+        return Some(dir);
+    }
     override_cache().read().ok()?.clone()
 }
 
+// ===== 便携模式 / 启动参数指定数据目录 =====
+
+/// 指定数据目录的环境变量
+pub const DATA_DIR_ENV: &str = "CC_SWITCH_DATA_DIR";
+/// 可执行文件旁存在该文件时启用便携模式，数据保存在可执行文件旁的 `data` 目录
+const PORTABLE_MARKER: &str = "portable";
+
+static STARTUP_DATA_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+/// 从启动参数中读取 `--data-dir <path>` 或 `--data-dir=<path>`
+fn parse_data_dir_arg(args: &[String]) -> Option<String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if let Some(value) = arg.strip_prefix("--data-dir=") {
+            return Some(value.to_string());
+        }
+        if arg == "--data-dir" {
+            return iter.next().cloned();
+        }
+    }
+    None
+}
+
+fn portable_data_dir() -> Option<PathBuf> {
+    let exe_dir = std::env::current_exe().ok()?.parent()?.to_path_buf();
+    exe_dir
+        .join(PORTABLE_MARKER)
+        .exists()
+        .then(|| exe_dir.join("data"))
+}
+
+/// 解析启动时指定的数据目录（启动参数 > 环境变量 > 便携模式），应在读取任何配置前调用
+pub fn init_startup_data_dir(args: &[String]) -> Option<PathBuf> {
+    STARTUP_DATA_DIR
+        .get_or_init(|| {
+            let (dir, source) = if let Some(raw) = parse_data_dir_arg(args) {
+                (resolve_path(raw.trim()), "--data-dir")
+            } else if let Some(raw) = std::env::var(DATA_DIR_ENV)
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+            {
+                (resolve_path(raw.trim()), DATA_DIR_ENV)
+            } else {
+                (portable_data_dir()?, "portable")
+            };
+            if let Err(e) = std::fs::create_dir_all(&dir) {
+                log::warn!("创建数据目录失败 {}: {e}，将使用默认路径", dir.display());
+                return None;
+            }
+            log::info!("使用 {source} 指定的数据目录: {}", dir.display());
+            Some(dir)
+        })
+        .clone()
+}
+
+/// 启动时指定的数据目录（未指定时为 None）
+pub fn startup_data_dir() -> Option<PathBuf> {
+    STARTUP_DATA_DIR.get().cloned().flatten()
+}
+
 fn read_override_from_store(app: &tauri::AppHandle) -> Option<PathBuf> {
     let store = match app.store_builder("app_paths.json").build() {
         Ok(store) => store,
@@ -75,6 +138,12 @@ pub fn set_app_config_dir_to_store(
     app: &tauri::AppHandle,
     path: Option<&str>,
 ) -> Result<(), AppError> {
+    if let Some(dir) = startup_data_dir() {
+        return Err(AppError::Message(format!(
+            "数据目录已由启动参数或便携模式指定（{}），无法在设置中修改",
+            dir.display()
+        )));
+    }
     let store = app
         .store_builder("app_paths.json")
         .build()
@@ -133,3 +202,22 @@ pub fn migrate_app_config_dir_from_settings(app: &tauri::AppHandle) -> Result<()
     let _ = refresh_app_config_dir_override(app);
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_data_dir_arg_forms() {
+        let args = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            parse_data_dir_arg(&args(&["cc-switch", "--data-dir", "/mnt/usb"])).as_deref(),
+            Some("/mnt/usb")
+        );
+        assert_eq!(
+            parse_data_dir_arg(&args(&["cc-switch", "--data-dir=D:\\cc"])).as_deref(),
+            Some("D:\\cc")
+        );
+        assert!(parse_data_dir_arg(&args(&["cc-switch", "ccswitch://x"])).is_none());
+    }
+}
@@ -0,0 +1,234 @@
+//! 终端选择切换（`cc-switch pick`）
+//!
+//! 不打开窗口即可切换供应商，输出格式便于接入 fzf 等选择工具：
+//! - `cc-switch pick --list [app]`：每行一个供应商 `应用<TAB>ID<TAB>名称`，当前供应商行末带 `<TAB>*`
+//! - `cc-switch pick [app]`：从标准输入读取一行选择并切换，可以是 `--list` 输出的整行、ID、名称或序号；
+//!   标准输入是终端时先在标准错误输出带序号的列表
+//!
+//! 例：`cc-switch pick --list | fzf | cc-switch pick`
+//!
+//! 已有 cc-switch 窗口在运行时，切换交给该实例执行（包括代理接管中的热切换），保证其状态与托盘同步；
+//! 没有实例运行时才在本进程内切换，此时代理接管中的应用会拒绝切换并提示启动窗口。
+//! Windows 正式版为窗口程序，没有控制台输出。
+
+use std::io::{BufRead, IsTerminal, Write};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::app_config::AppType;
+use crate::database::Database;
+use crate::services::provider::SwitchOutcome;
+use crate::services::ProviderService;
+use crate::store::AppState;
+
+const APPS: [AppType; 3] = [AppType::Claude, AppType::Codex, AppType::Gemini];
+
+/// 可选择的供应商
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    app: String,
+    id: String,
+    name: String,
+    current: bool,
+}
+
+impl Entry {
+    fn line(&self) -> String {
+        let marker = if self.current { "\t*" } else { "" };
+        format!("{}\t{}\t{}{marker}", self.app, self.id, self.name)
+    }
+}
+
+/// 命令行为 `pick` 时执行并返回退出码，其他命令返回 None
+pub fn run(args: &[String]) -> Option<i32> {
+    if args.get(1).map(String::as_str) != Some("pick") {
+        return None;
+    }
+    let mut list_only = false;
+    let mut app = None;
+    let mut rest = args.iter().skip(2);
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--list" | "-l" => list_only = true,
+            // 数据目录已在启动时处理
+            "--data-dir" => {
+                rest.next();
+            }
+            other if other.starts_with("--data-dir=") => {}
+            other => match AppType::from_str(other) {
+                Ok(parsed) => app = Some(parsed),
+                Err(e) => {
+                    eprintln!("{e}");
+                    eprintln!("用法: cc-switch pick [--list] [claude|codex|gemini]");
+                    return Some(2);
+                }
+            },
+        }
+    }
+
+    match pick(app, list_only) {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("cc-switch: {e}");
+            Some(1)
+        }
+    }
+}
+
+fn load_entries(db: &Database, apps: &[AppType]) -> Result<Vec<Entry>, String> {
+    let mut entries = Vec::new();
+    for app in apps {
+        let current =
+            crate::settings::get_effective_current_provider(db, app).map_err(|e| e.to_string())?;
+        let providers = db
+            .get_all_providers(app.as_str())
+            .map_err(|e| e.to_string())?;
+        entries.extend(providers.into_values().map(|provider| Entry {
+            app: app.as_str().to_string(),
+            current: current.as_deref() == Some(provider.id.as_str()),
+            id: provider.id,
+            name: provider.name,
+        }));
+    }
+    Ok(entries)
+}
+
+/// 按整行、ID、序号、名称的顺序匹配选择
+fn resolve<'a>(entries: &'a [Entry], selection: &str) -> Result<&'a Entry, String> {
+    let selection = selection.trim_end_matches(['\r', '\n']);
+    if let Some((app, rest)) = selection.split_once('\t') {
+        let id = rest.split('\t').next().unwrap_or_default();
+        return entries
+            .iter()
+            .find(|e| e.app == app && e.id == id)
+            .ok_or_else(|| format!("找不到供应商 {app}/{id}"));
+    }
+
+    let selection = selection.trim();
+    if selection.is_empty() {
+        return Err("未选择供应商".to_string());
+    }
+    let unique = |matches: Vec<&'a Entry>| -> Option<Result<&'a Entry, String>> {
+        match matches.as_slice() {
+            [] => None,
+            [entry] => Some(Ok(*entry)),
+            _ => Some(Err(format!(
+                "{selection} 匹配到多个应用的供应商，请指定应用或使用 --list 输出的整行"
+            ))),
+        }
+    };
+    if let Some(found) = unique(entries.iter().filter(|e| e.id == selection).collect()) {
+        return found;
+    }
+    if let Some(entry) = selection
+        .parse::<usize>()
+        .ok()
+        .and_then(|index| entries.get(index.checked_sub(1)?))
+    {
+        return Ok(entry);
+    }
+    unique(entries.iter().filter(|e| e.name == selection).collect())
+        .unwrap_or_else(|| Err(format!("找不到供应商 {selection}")))
+}
+
+fn pick(app: Option<AppType>, list_only: bool) -> Result<(), String> {
+    let db = Arc::new(Database::init().map_err(|e| e.to_string())?);
+    let apps = app.map_or_else(|| APPS.to_vec(), |app| vec![app]);
+    let entries = load_entries(&db, &apps)?;
+
+    if list_only {
+        let mut stdout = std::io::stdout().lock();
+        for entry in &entries {
+            // 下游（如 fzf）提前退出时不再输出
+            if writeln!(stdout, "{}", entry.line()).is_err() {
+                break;
+            }
+        }
+        return Ok(());
+    }
+
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        for (index, entry) in entries.iter().enumerate() {
+            let marker = if entry.current { " *" } else { "" };
+            eprintln!(
+                "{:>3}. [{}] {} ({}){marker}",
+                index + 1,
+                entry.app,
+                entry.name,
+                entry.id
+            );
+        }
+        eprint!("选择供应商: ");
+    }
+    let mut selection = String::new();
+    stdin
+        .lock()
+        .read_line(&mut selection)
+        .map_err(|e| format!("读取标准输入失败: {e}"))?;
+    let entry = resolve(&entries, &selection)?;
+    let app_type = AppType::from_str(&entry.app).map_err(|e| e.to_string())?;
+
+    if let Some(result) = crate::instance_guard::request_switch(&entry.app, &entry.id) {
+        result?;
+        if entry.current {
+            eprintln!("{} 已是当前供应商", entry.name);
+        } else {
+            eprintln!("已切换 {} 到 {}", entry.app, entry.name);
+        }
+        return Ok(());
+    }
+
+    let taken_over = futures::executor::block_on(db.get_live_backup(app_type.as_str()))
+        .map_err(|e| e.to_string())?
+        .is_some();
+    if taken_over {
+        return Err(format!(
+            "{} 正由代理接管，请启动 cc-switch 后再切换",
+            entry.app
+        ));
+    }
+
+    let state = AppState::new(db);
+    match ProviderService::switch_exclusive(&state, app_type, &entry.id, "cli")
+        .map_err(|e| e.to_string())?
+    {
+        SwitchOutcome::Switched => eprintln!("已切换 {} 到 {}", entry.app, entry.name),
+        SwitchOutcome::AlreadyActive => eprintln!("{} 已是当前供应商", entry.name),
+        SwitchOutcome::Failed => return Err("切换失败".to_string()),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(app: &str, id: &str, name: &str) -> Entry {
+        Entry {
+            app: app.to_string(),
+            id: id.to_string(),
+            name: name.to_string(),
+            current: false,
+        }
+    }
+
+    #[test]
+    fn resolves_lines_ids_indexes_and_names() {
+        let entries = [
+            entry("claude", "relay", "Relay"),
+            entry("claude", "official", "Official"),
+            entry("codex", "relay", "Relay Codex"),
+        ];
+
+        let line = format!("{}\n", entries[2].line());
+        assert_eq!(resolve(&entries, &line).unwrap(), &entries[2]);
+        assert_eq!(resolve(&entries, "official").unwrap(), &entries[1]);
+        assert_eq!(resolve(&entries, "1").unwrap(), &entries[0]);
+        assert_eq!(resolve(&entries, " Relay Codex \n").unwrap(), &entries[2]);
+        // 不同应用下同名 ID 需要整行或指定应用
+        assert!(resolve(&entries, "relay").is_err());
+        assert!(resolve(&entries, "4").is_err());
+        assert!(resolve(&entries, "\n").is_err());
+    }
+}
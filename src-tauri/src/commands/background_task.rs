@@ -0,0 +1,63 @@
+//! 后台任务命令
+
+use tauri::State;
+
+use crate::database::BackgroundTask;
+use crate::error::AppError;
+use crate::services::{background_task, SpeedtestService};
+use crate::store::AppState;
+
+/// 默认返回的任务条数
+const DEFAULT_TASK_LIMIT: u32 = 50;
+
+/// 获取任务状态与已产生的结果（界面重新加载后用于重新获取）
+#[tauri::command]
+pub fn get_background_task(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Option<BackgroundTask>, AppError> {
+    state.db.get_background_task(&id)
+}
+
+/// 列出最近的后台任务
+#[tauri::command]
+pub fn list_background_tasks(
+    state: State<'_, AppState>,
+    limit: Option<u32>,
+) -> Result<Vec<BackgroundTask>, AppError> {
+    state
+        .db
+        .list_background_tasks(limit.unwrap_or(DEFAULT_TASK_LIMIT).clamp(1, 500))
+}
+
+/// 取消运行中的任务，返回任务是否在运行
+#[tauri::command]
+pub fn cancel_background_task(id: String) -> Result<bool, AppError> {
+    Ok(background_task::cancel(&id))
+}
+
+/// 以后台任务逐个测试端点延迟，每测完一个推送一次结果
+#[tauri::command]
+pub fn start_endpoint_speedtest_task(
+    state: State<'_, AppState>,
+    urls: Vec<String>,
+    timeout_secs: Option<u64>,
+) -> Result<BackgroundTask, AppError> {
+    background_task::spawn(
+        state.db.clone(),
+        "endpoint-speedtest",
+        move |task| async move {
+            task.set_total(urls.len() as u32);
+            for url in urls {
+                if task.is_cancelled() {
+                    break;
+                }
+                let results = SpeedtestService::test_endpoints(vec![url], timeout_secs).await?;
+                for result in results {
+                    task.push_result(&result)?;
+                }
+            }
+            Ok(())
+        },
+    )
+}
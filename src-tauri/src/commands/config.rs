@@ -136,6 +136,29 @@ pub async fn open_app_config_folder(handle: AppHandle) -> Result<bool, String> {
     Ok(true)
 }
 
+/// 列出代理访问日志文件（当前文件在前，其余从新到旧）
+#[tauri::command]
+pub async fn list_access_logs() -> Result<Vec<crate::services::access_log::AccessLogFile>, String> {
+    Ok(crate::services::access_log::list())
+}
+
+/// 打开代理访问日志目录
+#[tauri::command]
+pub async fn open_access_log_folder(handle: AppHandle) -> Result<bool, String> {
+    let log_dir = crate::services::access_log::log_dir();
+
+    if !log_dir.exists() {
+        std::fs::create_dir_all(&log_dir).map_err(|e| format!("创建目录失败: {e}"))?;
+    }
+
+    handle
+        .opener()
+        .open_path(log_dir.to_string_lossy().to_string(), None::<String>)
+        .map_err(|e| format!("打开文件夹失败: {e}"))?;
+
+    Ok(true)
+}
+
 /// 获取 Claude 通用配置片段（已废弃，使用 get_common_config_snippet）
 #[tauri::command]
 pub async fn get_claude_common_config_snippet(
@@ -219,3 +242,15 @@ pub async fn set_common_config_snippet(
         .map_err(|e| e.to_string())?;
     Ok(())
 }
+
+/// 校验指定应用已存储的配置，返回字段级问题
+#[tauri::command]
+pub async fn validate_stored_configs(
+    state: tauri::State<'_, crate::store::AppState>,
+    app: String,
+) -> Result<crate::config_validation::ConfigValidationReport, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    crate::config_validation::validate_stored(&state.db, app_type.as_str())
+        .await
+        .map_err(|e| e.to_string())
+}
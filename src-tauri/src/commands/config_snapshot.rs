@@ -0,0 +1,55 @@
+//! 配置快照命令
+
+use crate::services::config_snapshot::{
+    self, ConfigChangeReport, ConfigSnapshot, ConfigSnapshotEntry,
+};
+use crate::store::AppState;
+use tauri::State;
+
+/// 最近一段时间内的配置变化（默认 24 小时，即“从昨天到现在改了什么”）
+#[tauri::command]
+pub async fn get_config_changes(
+    state: State<'_, AppState>,
+    since_hours: Option<u32>,
+) -> Result<ConfigChangeReport, String> {
+    let hours = since_hours.unwrap_or(24).clamp(1, 24 * 30) as i64;
+    let since = chrono::Utc::now().timestamp() - hours * 3600;
+    let db = state.db.clone();
+    tokio::task::spawn_blocking(move || config_snapshot::changes_since(&db, since))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// 立即拍摄一份配置快照（配置未变化时返回 null）
+#[tauri::command]
+pub async fn take_config_snapshot(
+    state: State<'_, AppState>,
+) -> Result<Option<ConfigSnapshotEntry>, String> {
+    let db = state.db.clone();
+    tokio::task::spawn_blocking(move || config_snapshot::take_snapshot(&db))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// 快照列表（默认 50 条）
+#[tauri::command]
+pub async fn list_config_snapshots(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+) -> Result<Vec<ConfigSnapshotEntry>, String> {
+    state
+        .db
+        .list_config_snapshots(None, limit.unwrap_or(50).clamp(1, 500))
+        .map_err(|e| e.to_string())
+}
+
+/// 读取单份快照（含完整内容与差异）
+#[tauri::command]
+pub async fn get_config_snapshot(
+    state: State<'_, AppState>,
+    id: i64,
+) -> Result<Option<ConfigSnapshot>, String> {
+    state.db.get_config_snapshot(id).map_err(|e| e.to_string())
+}
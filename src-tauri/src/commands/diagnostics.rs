@@ -0,0 +1,29 @@
+//! 自检与诊断命令
+
+use crate::database::AuditEntry;
+use crate::services::diagnostics::{DiagnosticsReport, DiagnosticsService};
+use crate::store::AppState;
+use tauri::State;
+
+/// 审计日志默认返回条数
+const DEFAULT_AUDIT_LIMIT: usize = 200;
+
+/// 运行自检，返回结构化诊断报告
+#[tauri::command]
+pub async fn run_diagnostics(state: State<'_, AppState>) -> Result<DiagnosticsReport, String> {
+    let proxy_running = state.proxy_service.is_running().await;
+    Ok(DiagnosticsService::run(&state.db, proxy_running).await)
+}
+
+/// 审计日志（自动修复等后台操作记录），可按类别过滤
+#[tauri::command]
+pub fn get_audit_log(
+    state: State<'_, AppState>,
+    category: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<AuditEntry>, String> {
+    state
+        .db
+        .get_audit_log(category.as_deref(), limit.unwrap_or(DEFAULT_AUDIT_LIMIT))
+        .map_err(|e| e.to_string())
+}
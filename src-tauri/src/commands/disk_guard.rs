@@ -0,0 +1,27 @@
+//! 磁盘占用命令
+
+use crate::services::disk_guard::{self, DiskTargetUsage, DiskUsageWarning};
+use crate::store::AppState;
+use tauri::State;
+
+/// 数据库、日志与备份目录的当前占用及上限
+#[tauri::command]
+pub async fn get_disk_usage(state: State<'_, AppState>) -> Result<Vec<DiskTargetUsage>, String> {
+    let db = state.db.clone();
+    tokio::task::spawn_blocking(move || disk_guard::usage(&db))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// 立即检查并清理超限的对象，返回发出的告警
+#[tauri::command]
+pub async fn enforce_disk_limits(
+    state: State<'_, AppState>,
+) -> Result<Vec<DiskUsageWarning>, String> {
+    let db = state.db.clone();
+    tokio::task::spawn_blocking(move || disk_guard::enforce(&db))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,66 @@
+//! 事件订阅命令
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use tauri::ipc::Channel;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::events::{self, AppEvent, EventFilter};
+
+static NEXT_SUBSCRIPTION_ID: AtomicU64 = AtomicU64::new(1);
+static SUBSCRIPTIONS: Lazy<Mutex<HashMap<u64, tauri::async_runtime::JoinHandle<()>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 订阅后端事件，匹配过滤条件的事件通过 Channel 推送；返回订阅 ID
+#[tauri::command]
+pub fn subscribe_events(
+    filter: Option<EventFilter>,
+    on_event: Channel<AppEvent>,
+) -> Result<u64, String> {
+    let filter = filter.unwrap_or_default();
+    let id = NEXT_SUBSCRIPTION_ID.fetch_add(1, Ordering::Relaxed);
+    let mut rx = events::subscribe();
+
+    let handle = tauri::async_runtime::spawn(async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => {
+                    if filter.matches(&event) && on_event.send(event).is_err() {
+                        // 前端页面已关闭或重载
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(n)) => log::warn!("事件订阅 {id} 处理过慢，丢弃 {n} 条事件"),
+                Err(RecvError::Closed) => break,
+            }
+        }
+        if let Ok(mut subs) = SUBSCRIPTIONS.lock() {
+            subs.remove(&id);
+        }
+    });
+
+    SUBSCRIPTIONS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(id, handle);
+    Ok(id)
+}
+
+/// 取消事件订阅，返回订阅是否存在
+#[tauri::command]
+pub fn unsubscribe_events(subscription_id: u64) -> Result<bool, String> {
+    let handle = SUBSCRIPTIONS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .remove(&subscription_id);
+    match handle {
+        Some(handle) => {
+            handle.abort();
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
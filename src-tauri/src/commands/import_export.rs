@@ -2,9 +2,12 @@
 
 use serde_json::{json, Value};
 use std::path::PathBuf;
-use tauri::State;
+use tauri::{Manager, State};
 use tauri_plugin_dialog::DialogExt;
 
+use crate::database::{
+    Database, DbBackupEntry, DbEncryptionReport, DbEncryptionStatus, DbKeySource, SalvageReport,
+};
 use crate::error::AppError;
 use crate::services::provider::ProviderService;
 use crate::store::AppState;
@@ -64,6 +67,138 @@ pub async fn import_config_from_file(
     .map_err(|e: AppError| e.to_string())
 }
 
+/// 列出数据库快照备份（安全模式下用于选择恢复点）
+#[tauri::command]
+pub async fn list_db_backups() -> Result<Vec<DbBackupEntry>, String> {
+    Database::list_db_backups().map_err(|e| e.to_string())
+}
+
+/// 覆盖数据库的恢复操作仅在安全模式（数据库损坏）下开放
+fn require_safe_mode() -> Result<(), String> {
+    if crate::init_status::get_safe_mode().is_some() {
+        return Ok(());
+    }
+    Err(AppError::localized(
+        "database.safeModeRequired",
+        "仅在安全模式下可恢复或重新初始化数据库",
+        "Restoring or reinitializing the database is only available in safe mode",
+    )
+    .to_string())
+}
+
+/// 从快照备份恢复数据库（仅安全模式，需重启应用生效）
+#[tauri::command]
+pub async fn restore_db_backup(#[allow(non_snake_case)] backupId: String) -> Result<Value, String> {
+    require_safe_mode()?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let quarantined = Database::restore_db_backup(&backupId)?;
+        Ok::<_, AppError>(json!({
+            "success": true,
+            "quarantinedPath": quarantined.map(|p| p.display().to_string()),
+            "restartRequired": true
+        }))
+    })
+    .await
+    .map_err(|e| format!("恢复备份失败: {e}"))?
+    .map_err(|e: AppError| e.to_string())
+}
+
+/// 将损坏数据库中仍可读取的表导出为 SQL
+#[tauri::command]
+pub async fn export_salvaged_database(
+    #[allow(non_snake_case)] filePath: String,
+) -> Result<SalvageReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        Database::salvage_to_sql(&PathBuf::from(&filePath))
+    })
+    .await
+    .map_err(|e| format!("导出数据失败: {e}"))?
+    .map_err(|e: AppError| e.to_string())
+}
+
+/// 隔离损坏的数据库并在下次启动时重新初始化（仅安全模式，需重启应用生效）
+#[tauri::command]
+pub async fn reinitialize_database() -> Result<Value, String> {
+    require_safe_mode()?;
+    let quarantined = Database::reinitialize_db_file().map_err(|e| e.to_string())?;
+    Ok(json!({
+        "success": true,
+        "quarantinedPath": quarantined.map(|p| p.display().to_string()),
+        "restartRequired": true
+    }))
+}
+
+/// 获取数据库加密状态
+#[tauri::command]
+pub async fn get_db_encryption_status(
+    state: State<'_, AppState>,
+) -> Result<DbEncryptionStatus, String> {
+    state.db.encryption_status().map_err(|e| e.to_string())
+}
+
+/// 将数据库转换为 SQLCipher 加密库（口令或系统钥匙串）
+#[tauri::command]
+pub async fn enable_db_encryption(
+    state: State<'_, AppState>,
+    keySource: DbKeySource,
+    passphrase: Option<String>,
+    purgePlaintextBackups: bool,
+) -> Result<DbEncryptionReport, String> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        db.enable_encryption(keySource, passphrase.as_deref(), purgePlaintextBackups)
+    })
+    .await
+    .map_err(|e| format!("加密数据库失败: {e}"))?
+    .map_err(|e: AppError| e.to_string())
+}
+
+/// 将加密数据库转换回明文
+#[tauri::command]
+pub async fn disable_db_encryption(
+    state: State<'_, AppState>,
+) -> Result<DbEncryptionReport, String> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || db.disable_encryption())
+        .await
+        .map_err(|e| format!("解密数据库失败: {e}"))?
+        .map_err(|e: AppError| e.to_string())
+}
+
+/// 安全模式下用口令解锁加密数据库：在当前进程内打开主库并退出安全模式，
+/// 前端随后重新加载界面即可（口令经 IPC 传入，不写入环境变量）
+///
+/// 启动时因安全模式跳过的导入、后台任务与接管恢复在解锁成功后执行一次。
+#[tauri::command]
+pub async fn unlock_encrypted_database(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    passphrase: String,
+) -> Result<bool, String> {
+    require_safe_mode()?;
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || db.unlock_with_passphrase(&passphrase))
+        .await
+        .map_err(|e| format!("解锁数据库失败: {e}"))?
+        .map_err(|e: AppError| e.to_string())?;
+    crate::init_status::clear_safe_mode();
+
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::import_initial_data(&handle.state::<AppState>());
+    })
+    .await
+    .map_err(|e| format!("导入初始数据失败: {e}"))?;
+    crate::start_background_services(&app);
+
+    if let Ok(menu) = crate::tray::create_tray_menu(&app, state.inner()) {
+        if let Some(tray) = app.tray_by_id("main") {
+            let _ = tray.set_menu(Some(menu));
+        }
+    }
+    Ok(true)
+}
+
 #[tauri::command]
 pub async fn sync_current_providers_live(state: State<'_, AppState>) -> Result<Value, String> {
     let db = state.db.clone();
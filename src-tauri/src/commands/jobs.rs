@@ -0,0 +1,29 @@
+//! 外发任务队列命令
+
+use tauri::State;
+
+use crate::database::{JobStatus, PendingJob};
+use crate::error::AppError;
+use crate::services::job_queue;
+use crate::store::AppState;
+
+/// 默认返回的任务条数
+const DEFAULT_JOB_LIMIT: u32 = 100;
+
+/// 列出外发任务（可按状态过滤，如只看死信）
+#[tauri::command]
+pub fn list_pending_jobs(
+    state: State<'_, AppState>,
+    status: Option<JobStatus>,
+    limit: Option<u32>,
+) -> Result<Vec<PendingJob>, AppError> {
+    state
+        .db
+        .list_jobs(status, limit.unwrap_or(DEFAULT_JOB_LIMIT))
+}
+
+/// 重新投递死信任务
+#[tauri::command]
+pub fn retry_pending_job(state: State<'_, AppState>, id: i64) -> Result<bool, AppError> {
+    job_queue::retry_dead(&state.db, id)
+}
@@ -0,0 +1,51 @@
+//! 本地模型服务命令
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::proxy::providers::LocalModelConfig;
+use crate::services::local_model::{LocalModelService, LocalModelStatus};
+use crate::store::AppState;
+use tauri::State;
+
+/// 读取供应商的本地模型配置
+fn local_config(
+    state: &AppState,
+    app_type: &AppType,
+    provider_id: &str,
+) -> Result<LocalModelConfig, AppError> {
+    let provider = state
+        .db
+        .get_provider_by_id(provider_id, app_type.as_str())?
+        .ok_or_else(|| AppError::Message(format!("供应商 {provider_id} 不存在")))?;
+    LocalModelConfig::of(&provider)
+        .cloned()
+        .ok_or_else(|| AppError::Message(format!("供应商 {provider_id} 不是本地模型供应商")))
+}
+
+/// 启动本地模型服务进程，返回 pid
+#[tauri::command]
+pub async fn start_local_model(
+    state: State<'_, AppState>,
+    app_type: AppType,
+    provider_id: String,
+) -> Result<u32, AppError> {
+    let config = local_config(&state, &app_type, &provider_id)?;
+    LocalModelService::start(&provider_id, &config)
+}
+
+/// 停止由 cc-switch 启动的本地模型服务进程
+#[tauri::command]
+pub async fn stop_local_model(provider_id: String) -> Result<bool, AppError> {
+    LocalModelService::stop(&provider_id)
+}
+
+/// 获取本地模型服务状态
+#[tauri::command]
+pub async fn get_local_model_status(
+    state: State<'_, AppState>,
+    app_type: AppType,
+    provider_id: String,
+) -> Result<LocalModelStatus, AppError> {
+    let config = local_config(&state, &app_type, &provider_id)?;
+    Ok(LocalModelService::status(&provider_id, &config).await)
+}
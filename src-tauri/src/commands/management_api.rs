@@ -0,0 +1,42 @@
+//! 本地管理 API 令牌命令
+
+use std::str::FromStr;
+
+use crate::proxy::api_auth::{self, ApiScope, ApiToken, CreatedApiToken};
+use crate::store::AppState;
+use tauri::State;
+
+/// 全部令牌（不含明文）
+#[tauri::command]
+pub async fn list_api_tokens(state: State<'_, AppState>) -> Result<Vec<ApiToken>, String> {
+    state.db.list_api_tokens().map_err(|e| e.to_string())
+}
+
+/// 创建令牌，明文只在返回值中出现一次
+#[tauri::command]
+pub async fn create_api_token(
+    state: State<'_, AppState>,
+    name: String,
+    scopes: Vec<String>,
+) -> Result<CreatedApiToken, String> {
+    let scopes = scopes
+        .iter()
+        .map(|s| ApiScope::from_str(s))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    api_auth::create_token(&state.db, &name, &scopes, false).map_err(|e| e.to_string())
+}
+
+/// 吊销令牌
+#[tauri::command]
+pub async fn revoke_api_token(state: State<'_, AppState>, id: String) -> Result<bool, String> {
+    state.db.delete_api_token(&id).map_err(|e| e.to_string())
+}
+
+/// 生成新的管理员引导令牌（旧的引导令牌随即失效），明文只显示这一次
+#[tauri::command]
+pub async fn bootstrap_api_admin_token(
+    state: State<'_, AppState>,
+) -> Result<CreatedApiToken, String> {
+    api_auth::bootstrap_admin_token(&state.db).map_err(|e| e.to_string())
+}
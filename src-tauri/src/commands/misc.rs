@@ -1,6 +1,6 @@
 #![allow(non_snake_case)]
 
-use crate::init_status::InitErrorPayload;
+use crate::init_status::{InitErrorPayload, SafeModePayload};
 use tauri::AppHandle;
 use tauri_plugin_opener::OpenerExt;
 
@@ -58,6 +58,12 @@ pub async fn get_init_error() -> Result<Option<InitErrorPayload>, String> {
     Ok(crate::init_status::get_init_error())
 }
 
+/// 获取安全模式状态（数据库损坏时为 Some）。
+#[tauri::command]
+pub async fn get_safe_mode_status() -> Result<Option<SafeModePayload>, String> {
+    Ok(crate::init_status::get_safe_mode())
+}
+
 /// 获取 JSON→SQLite 迁移结果（若有）。
 /// 只返回一次 true，之后返回 false，用于前端显示一次性 Toast 通知。
 #[tauri::command]
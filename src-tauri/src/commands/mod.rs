@@ -1,14 +1,23 @@
 #![allow(non_snake_case)]
 
+mod background_task;
 mod config;
+mod config_snapshot;
 mod deeplink;
+mod diagnostics;
+mod disk_guard;
 mod env;
+mod events;
 mod failover;
 mod import_export;
+mod jobs;
+mod local_model;
+mod management_api;
 mod mcp;
 mod misc;
 mod plugin;
 mod prompt;
+mod prompt_compare;
 mod provider;
 mod proxy;
 mod settings;
@@ -17,15 +26,24 @@ mod stream_check;
 mod tps_test;
 mod usage;
 
+pub use background_task::*;
 pub use config::*;
+pub use config_snapshot::*;
 pub use deeplink::*;
+pub use diagnostics::*;
+pub use disk_guard::*;
 pub use env::*;
+pub use events::*;
 pub use failover::*;
 pub use import_export::*;
+pub use jobs::*;
+pub use local_model::*;
+pub use management_api::*;
 pub use mcp::*;
 pub use misc::*;
 pub use plugin::*;
 pub use prompt::*;
+pub use prompt_compare::*;
 pub use provider::*;
 pub use proxy::*;
 pub use settings::*;
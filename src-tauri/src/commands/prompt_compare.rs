@@ -0,0 +1,55 @@
+//! 多供应商提示词对比命令
+
+use crate::services::prompt_compare::{
+    PromptCompareRequest, PromptCompareService, PromptComparison,
+};
+use crate::store::AppState;
+use tauri::State;
+
+/// 把同一条提示词发给多个供应商并保存对比结果
+#[tauri::command]
+pub async fn compare_prompt(
+    state: State<'_, AppState>,
+    request: PromptCompareRequest,
+) -> Result<PromptComparison, String> {
+    PromptCompareService::run(state.db.clone(), request)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 最近的对比记录（默认 20 条）
+#[tauri::command]
+pub async fn list_prompt_comparisons(
+    state: State<'_, AppState>,
+    app: String,
+    limit: Option<usize>,
+) -> Result<Vec<PromptComparison>, String> {
+    state
+        .db
+        .list_prompt_comparisons(&app, limit.unwrap_or(20).clamp(1, 100))
+        .map_err(|e| e.to_string())
+}
+
+/// 读取单次对比记录
+#[tauri::command]
+pub async fn get_prompt_comparison(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<Option<PromptComparison>, String> {
+    state
+        .db
+        .get_prompt_comparison(&id)
+        .map_err(|e| e.to_string())
+}
+
+/// 删除对比记录
+#[tauri::command]
+pub async fn delete_prompt_comparison(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<bool, String> {
+    state
+        .db
+        .delete_prompt_comparison(&id)
+        .map_err(|e| e.to_string())
+}
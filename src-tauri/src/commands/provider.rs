@@ -2,20 +2,46 @@ use indexmap::IndexMap;
 use tauri::State;
 
 use crate::app_config::AppType;
+use crate::database::{RecentProvider, TimelineEntry, TimelineKind, TimelineQuery, VaultItem};
 use crate::error::AppError;
 use crate::provider::Provider;
+use crate::services::key_reveal::{self, RevealAction};
+use crate::services::provider::{
+    BulkImportReport, CapabilityWarning, ConflictResolution, DuplicateMatch, ExportedConfigFile,
+    ImportFormat, LiveDrift, LiveReconcileAction, ProviderMergeResult, SwitchOutcome,
+    SwitchPreview, SwitchQueueStatus, TransferredProvider,
+};
+use crate::services::vault::{VaultItemContent, VaultService};
 use crate::services::{EndpointLatency, ProviderService, ProviderSortUpdate, SpeedtestService};
 use crate::store::AppState;
+use std::path::Path;
 use std::str::FromStr;
 
-/// 获取所有供应商
+/// 获取所有供应商（Key 已隐藏，明文经 `reveal_provider_key` 认证后取回）
 #[tauri::command]
 pub fn get_providers(
     state: State<'_, AppState>,
     app: String,
 ) -> Result<IndexMap<String, Provider>, String> {
     let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
-    ProviderService::list(state.inner(), app_type).map_err(|e| e.to_string())
+    let mut providers =
+        ProviderService::list(state.inner(), app_type).map_err(|e| e.to_string())?;
+    providers.values_mut().for_each(key_reveal::redact_provider);
+    Ok(providers)
+}
+
+/// 将前端传回的隐藏 Key 还原为 `source_id` 供应商中保存的原值
+fn restore_provider_keys(
+    state: &AppState,
+    app_type: &AppType,
+    provider: &mut Provider,
+    source_id: Option<&str>,
+) -> Result<(), AppError> {
+    let source = match source_id {
+        Some(id) => state.db.get_provider_by_id(id, app_type.as_str())?,
+        None => None,
+    };
+    key_reveal::restore_redacted(provider, source.as_ref())
 }
 
 /// 获取当前供应商ID
@@ -25,15 +51,26 @@ pub fn get_current_provider(state: State<'_, AppState>, app: String) -> Result<S
     ProviderService::current(state.inner(), app_type).map_err(|e| e.to_string())
 }
 
-/// 添加供应商
+/// 添加供应商（复制已有供应商时由 `copy_secrets_from` 指定沿用其 Key）
 #[tauri::command]
 pub fn add_provider(
     state: State<'_, AppState>,
     app: String,
-    provider: Provider,
+    mut provider: Provider,
+    copy_secrets_from: Option<String>,
 ) -> Result<bool, String> {
     let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
-    ProviderService::add(state.inner(), app_type, provider).map_err(|e| e.to_string())
+    restore_provider_keys(
+        state.inner(),
+        &app_type,
+        &mut provider,
+        copy_secrets_from.as_deref(),
+    )
+    .map_err(|e| e.to_string())?;
+    let added = ProviderService::add(state.inner(), app_type.clone(), provider.clone())
+        .map_err(|e| e.to_string())?;
+    ProviderService::report_duplicates(state.inner(), &app_type, &provider);
+    Ok(added)
 }
 
 /// 更新供应商
@@ -41,9 +78,12 @@ pub fn add_provider(
 pub fn update_provider(
     state: State<'_, AppState>,
     app: String,
-    provider: Provider,
+    mut provider: Provider,
 ) -> Result<bool, String> {
     let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    let id = provider.id.clone();
+    restore_provider_keys(state.inner(), &app_type, &mut provider, Some(&id))
+        .map_err(|e| e.to_string())?;
     ProviderService::update(state.inner(), app_type, provider).map_err(|e| e.to_string())
 }
 
@@ -60,9 +100,14 @@ pub fn delete_provider(
         .map_err(|e| e.to_string())
 }
 
-/// 切换供应商
-fn switch_provider_internal(state: &AppState, app_type: AppType, id: &str) -> Result<(), AppError> {
-    ProviderService::switch(state, app_type, id)
+/// 切换供应商（经切换队列串行执行）
+fn switch_provider_internal(
+    state: &AppState,
+    app_type: AppType,
+    id: &str,
+    source: &str,
+) -> Result<SwitchOutcome, AppError> {
+    ProviderService::switch_exclusive(state, app_type, id, source)
 }
 
 #[cfg_attr(not(feature = "test-hooks"), doc(hidden))]
@@ -71,21 +116,401 @@ pub fn switch_provider_test_hook(
     app_type: AppType,
     id: &str,
 ) -> Result<(), AppError> {
-    switch_provider_internal(state, app_type, id)
+    switch_provider_internal(state, app_type, id, "test").map(|_| ())
+}
+
+/// 切换供应商
+///
+/// `confirm_overwrite` 为可选的覆盖确认：未传入时直接切换（与托盘、深链接、管理 API 一致）；
+/// 传入 `false` 时若切换会覆盖用户手动添加的配置键则拒绝，调用方预览并确认后再传入 `true`。
+///
+/// 切换在阻塞线程中排队执行，不占用主线程
+#[tauri::command]
+pub async fn switch_provider(
+    handle: AppHandle,
+    app: String,
+    id: String,
+    confirm_overwrite: Option<bool>,
+) -> Result<bool, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        use tauri::Manager;
+        let state = handle.state::<AppState>();
+        if confirm_overwrite == Some(false) {
+            let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+            // 预览失败（如目标配置无效）时交给切换流程报告具体错误
+            if let Ok(preview) = ProviderService::preview_switch(&state, app_type, &id) {
+                if preview.requires_confirmation {
+                    return Err(AppError::localized(
+                        "provider.switch.confirm_overwrite",
+                        "切换将覆盖配置文件中手动添加的设置，请预览变更并确认后再切换",
+                        "Switching would overwrite settings you added to the config files by hand; review the changes and confirm to continue",
+                    )
+                    .to_string());
+                }
+            }
+        }
+        switch_provider_from(&handle, &state, &app, id, "ui")
+    })
+    .await
+    .map_err(|e| format!("切换供应商失败: {e}"))?
+}
+
+/// 预览切换到指定供应商时将写入的配置变更
+#[tauri::command]
+pub fn preview_switch_provider(
+    state: State<'_, AppState>,
+    app: String,
+    id: String,
+) -> Result<SwitchPreview, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    ProviderService::preview_switch(state.inner(), app_type, &id).map_err(|e| e.to_string())
+}
+
+/// 切换供应商并在切换后检查健康状态；目标已是当前供应商时视为成功
+pub(crate) fn switch_provider_from(
+    handle: &AppHandle,
+    state: &AppState,
+    app: &str,
+    id: String,
+    source: &str,
+) -> Result<bool, String> {
+    let app_type = AppType::from_str(app).map_err(|e| e.to_string())?;
+    let outcome = switch_provider_internal(state, app_type.clone(), &id, source)
+        .map_err(|e| e.to_string())?;
+    if outcome == SwitchOutcome::Switched {
+        // 界面已在切换前提示过，这里只记录日志（托盘等入口没有确认步骤）
+        if let Ok(warnings) =
+            ProviderService::check_switch_capabilities(state, app_type.clone(), &id)
+        {
+            for warning in warnings {
+                log::warn!("切换到 {id} 的能力提示: {}", warning.message);
+            }
+        }
+        super::stream_check::spawn_switch_snapshot(handle, app_type, id);
+    }
+    Ok(true)
+}
+
+/// 切换前检查供应商能力（如不支持工具调用），返回需要提示用户的问题
+#[tauri::command]
+pub fn check_provider_capabilities(
+    state: State<'_, AppState>,
+    app: String,
+    id: String,
+) -> Result<Vec<CapabilityWarning>, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    ProviderService::check_switch_capabilities(state.inner(), app_type, &id)
+        .map_err(|e| e.to_string())
+}
+
+/// 为供应商添加加密备注
+#[tauri::command]
+pub fn add_provider_vault_note(
+    state: State<'_, AppState>,
+    app: String,
+    provider_id: String,
+    name: String,
+    text: String,
+) -> Result<i64, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    VaultService::add_note(state.inner(), app_type, &provider_id, &name, &text)
+        .map_err(|e| e.to_string())
+}
+
+/// 为供应商添加加密文件（内容为 base64）
+#[tauri::command]
+pub fn add_provider_vault_file(
+    state: State<'_, AppState>,
+    app: String,
+    provider_id: String,
+    name: String,
+    mime_type: Option<String>,
+    data: String,
+) -> Result<i64, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    VaultService::add_file(
+        state.inner(),
+        app_type,
+        &provider_id,
+        &name,
+        mime_type.as_deref(),
+        &data,
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// 列出供应商的备注与附件（不含内容）
+#[tauri::command]
+pub fn list_provider_vault_items(
+    state: State<'_, AppState>,
+    app: String,
+    provider_id: String,
+) -> Result<Vec<VaultItem>, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    VaultService::list(state.inner(), app_type, &provider_id).map_err(|e| e.to_string())
+}
+
+/// 解密读取备注或附件（需要系统钥匙串授权）
+#[tauri::command]
+pub fn read_provider_vault_item(
+    state: State<'_, AppState>,
+    id: i64,
+) -> Result<VaultItemContent, String> {
+    VaultService::read(state.inner(), id).map_err(|e| e.to_string())
+}
+
+/// 删除备注或附件
+#[tauri::command]
+pub fn delete_provider_vault_item(state: State<'_, AppState>, id: i64) -> Result<bool, String> {
+    VaultService::delete(state.inner(), id).map_err(|e| e.to_string())
+}
+
+/// 通过系统认证后返回供应商的 Key（用于"显示 Key"与"复制 Key"，记入审计日志）
+#[tauri::command]
+pub async fn reveal_provider_key(
+    state: State<'_, AppState>,
+    app: String,
+    id: String,
+    action: RevealAction,
+) -> Result<String, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    let db = state.db.clone();
+    tokio::task::spawn_blocking(move || key_reveal::reveal(&db, app_type, &id, action))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+/// 解析从剪贴板粘贴的供应商定义，返回预填的供应商（不保存）
+#[tauri::command]
+pub fn parse_provider_from_clipboard(app: String, text: String) -> Result<Provider, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    crate::deeplink::parse_provider_from_text(&app_type, &text).map_err(|e| e.to_string())
+}
+
+/// 导出供应商为可分享的文本（API Key 已遮蔽），由前端写入剪贴板
+#[tauri::command]
+pub fn export_provider_for_clipboard(
+    state: State<'_, AppState>,
+    app: String,
+    id: String,
+) -> Result<String, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    let provider = state
+        .db
+        .get_provider_by_id(&id, app_type.as_str())
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("供应商不存在: {id}"))?;
+    Ok(crate::deeplink::export_provider_to_text(
+        &app_type, &provider,
+    ))
 }
 
+/// 编码供应商用于设备间传输（前端渲染为二维码）；包含 API Key 时必须提供口令
 #[tauri::command]
-pub fn switch_provider(
+pub fn export_provider_transfer(
     state: State<'_, AppState>,
     app: String,
     id: String,
+    include_key: bool,
+    passphrase: Option<String>,
+) -> Result<String, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    ProviderService::export_transfer(
+        state.inner(),
+        app_type,
+        &id,
+        include_key,
+        passphrase.as_deref(),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// 按应用的原生文件格式导出任意供应商的配置；指定 `path` 时写入该文件（多个文件时为目录）
+#[tauri::command]
+pub fn export_provider_config(
+    state: State<'_, AppState>,
+    app: String,
+    id: String,
+    include_key: bool,
+    path: Option<String>,
+) -> Result<Vec<ExportedConfigFile>, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    ProviderService::export_config(
+        state.inner(),
+        app_type,
+        &id,
+        include_key,
+        path.as_deref().map(Path::new),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// 从 CSV / JSON 文件批量导入供应商，返回逐行报告；`dry_run` 时只校验不写入
+#[tauri::command]
+pub fn bulk_import_providers(
+    state: State<'_, AppState>,
+    path: String,
+    format: Option<ImportFormat>,
+    dry_run: bool,
+) -> Result<BulkImportReport, String> {
+    let path = Path::new(&path);
+    let text = std::fs::read_to_string(path).map_err(|e| format!("读取文件失败: {e}"))?;
+    let format = format.unwrap_or_else(|| ImportFormat::detect(Some(path), &text));
+    ProviderService::bulk_import(state.inner(), &text, format, dry_run).map_err(|e| e.to_string())
+}
+
+/// 解码扫描或粘贴的传输内容（导入前预览）
+#[tauri::command]
+pub fn decode_provider_transfer(
+    payload: String,
+    passphrase: Option<String>,
+) -> Result<TransferredProvider, String> {
+    ProviderService::decode_transfer(&payload, passphrase.as_deref()).map_err(|e| e.to_string())
+}
+
+/// 导入扫描或粘贴的传输内容为新供应商
+#[tauri::command]
+pub fn import_provider_transfer(
+    state: State<'_, AppState>,
+    payload: String,
+    passphrase: Option<String>,
+) -> Result<String, String> {
+    ProviderService::import_transfer(state.inner(), &payload, passphrase.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// 新建或导入前检测疑似重复的供应商（相同地址与 Key，或相同名称）
+#[tauri::command]
+pub fn find_duplicate_providers(
+    state: State<'_, AppState>,
+    app: String,
+    provider: Provider,
+) -> Result<Vec<DuplicateMatch>, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    ProviderService::find_duplicates(state.inner(), app_type, &provider).map_err(|e| e.to_string())
+}
+
+/// 合并重复供应商，历史记录归入保留的供应商
+#[tauri::command]
+pub fn merge_duplicate_providers(
+    state: State<'_, AppState>,
+    app: String,
+    keep_id: String,
+    remove_id: String,
 ) -> Result<bool, String> {
     let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
-    switch_provider_internal(&state, app_type, &id)
+    ProviderService::merge_duplicate(state.inner(), app_type, &keep_id, &remove_id)
         .map(|_| true)
         .map_err(|e| e.to_string())
 }
 
+/// 同步前按字段三方合并供应商列表，返回合并结果与冲突（base 为上次同步时的列表）
+#[tauri::command]
+pub fn preview_provider_sync_merge(
+    state: State<'_, AppState>,
+    app: String,
+    base: IndexMap<String, Provider>,
+    remote: IndexMap<String, Provider>,
+) -> Result<ProviderMergeResult, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    ProviderService::preview_sync_merge(state.inner(), app_type, &base, &remote)
+        .map_err(|e| e.to_string())
+}
+
+/// 按冲突选择写入同步合并结果
+#[tauri::command]
+pub fn apply_provider_sync_merge(
+    state: State<'_, AppState>,
+    app: String,
+    base: IndexMap<String, Provider>,
+    remote: IndexMap<String, Provider>,
+    resolutions: Vec<ConflictResolution>,
+) -> Result<ProviderMergeResult, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    ProviderService::apply_sync_merge(state.inner(), app_type, &base, &remote, &resolutions)
+        .map_err(|e| e.to_string())
+}
+
+/// 最近使用过的供应商（默认 5 个，按最近切换时间倒序）
+#[tauri::command]
+pub fn get_recent_providers(
+    state: State<'_, AppState>,
+    app: String,
+    limit: Option<usize>,
+) -> Result<Vec<RecentProvider>, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    ProviderService::recent_providers(state.inner(), app_type, limit.unwrap_or(5))
+        .map_err(|e| e.to_string())
+}
+
+/// 切换、故障与预算事件的统一时间线（默认 100 条，按时间倒序）
+#[tauri::command]
+pub fn get_timeline(
+    state: State<'_, AppState>,
+    query: Option<TimelineQuery>,
+) -> Result<Vec<TimelineEntry>, String> {
+    state
+        .db
+        .get_timeline(&query.unwrap_or_default(), 100)
+        .map_err(|e| e.to_string())
+}
+
+/// 为时间线条目附加备注（note 为空时清除），条目不存在时返回 false
+#[tauri::command]
+pub fn annotate_timeline_entry(
+    state: State<'_, AppState>,
+    kind: TimelineKind,
+    id: i64,
+    note: Option<String>,
+) -> Result<bool, String> {
+    state
+        .db
+        .set_timeline_note(kind, id, note.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// 切回上一个供应商，返回切换后的供应商 ID
+#[tauri::command]
+pub async fn switch_to_previous_provider(handle: AppHandle, app: String) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        use tauri::Manager;
+        let state = handle.state::<AppState>();
+        let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+        let target = ProviderService::previous_provider(state.inner(), app_type)
+            .map_err(|e| e.to_string())?;
+        switch_provider_from(&handle, &state, &app, target.clone(), "ui")?;
+        Ok(target)
+    })
+    .await
+    .map_err(|e| format!("切换供应商失败: {e}"))?
+}
+
+/// 检查当前供应商与 Live 配置文件是否一致
+#[tauri::command]
+pub fn check_live_consistency(state: State<'_, AppState>) -> Result<Vec<LiveDrift>, String> {
+    ProviderService::check_live_consistency(state.inner()).map_err(|e| e.to_string())
+}
+
+/// 解决当前供应商与 Live 配置的不一致（以当前供应商或 Live 配置为准）
+#[tauri::command]
+pub fn reconcile_live_config(
+    state: State<'_, AppState>,
+    app: String,
+    action: LiveReconcileAction,
+) -> Result<bool, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    ProviderService::reconcile_live(state.inner(), app_type, action)
+        .map(|_| true)
+        .map_err(|e| e.to_string())
+}
+
+/// 获取供应商切换队列状态
+#[tauri::command]
+pub fn get_switch_queue_status() -> SwitchQueueStatus {
+    ProviderService::switch_queue_status()
+}
+
 fn import_default_config_internal(state: &AppState, app_type: AppType) -> Result<bool, AppError> {
     ProviderService::import_default_config(state, app_type)
 }
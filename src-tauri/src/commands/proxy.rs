@@ -2,9 +2,30 @@
 //!
 //! 提供前端调用的 API 接口
 
+use crate::app_config::AppType;
+use crate::database::{
+    BackgroundTask, ShadowComparison, ShadowSummary, SubstitutionEvidence, SubstitutionSuspect,
+};
+use crate::provider::Provider;
+use crate::proxy::client_pool::{self, ClientPoolStats};
+use crate::proxy::fault_injection::{self, FaultInjection};
+use crate::proxy::keep_warm::KeepWarmEstimate;
+use crate::proxy::mdns::{self, DiscoveredProxy};
+use crate::proxy::mock_upstream;
+use crate::proxy::model_audit;
+use crate::proxy::offline::{self, NetworkStatus};
+use crate::proxy::outbound_redaction::{self, RedactionStats};
+use crate::proxy::output_validation::{self, OutputValidationStats};
+use crate::proxy::param_policy::{self, PolicyStats};
+use crate::proxy::session_override::{self, OverrideTarget, SessionOverride};
+use crate::proxy::sse_sanitize::{self, SanitizeStats};
 use crate::proxy::types::*;
 use crate::proxy::{CircuitBreakerConfig, CircuitBreakerStats};
+use crate::services::background_task;
+use crate::services::replay::{ReplayRequest, ReplayService};
+use crate::services::ProviderService;
 use crate::store::AppState;
+use std::str::FromStr;
 
 /// 启动代理服务器（仅启动服务，不接管 Live 配置）
 #[tauri::command]
@@ -47,6 +68,312 @@ pub async fn get_proxy_status(state: tauri::State<'_, AppState>) -> Result<Proxy
     state.proxy_service.get_status().await
 }
 
+/// 获取各应用的总览（当前供应商、健康、今日用量与 TPS），服务端缓存 1 秒
+#[tauri::command]
+pub async fn get_fleet_summary(
+    state: tauri::State<'_, AppState>,
+) -> Result<crate::services::fleet::FleetSummary, String> {
+    Ok(crate::services::fleet::FleetService::summary(&state.db, &state.proxy_service).await)
+}
+
+/// 获取代理 TPS 分桶历史（最近 1 小时）
+#[tauri::command]
+pub async fn get_proxy_tps_history(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<TpsHistoryPoint>, String> {
+    state.proxy_service.get_tps_history().await
+}
+
+/// 估算空闲保活的请求数与 token 开销
+#[tauri::command]
+pub async fn get_keep_warm_estimate(
+    state: tauri::State<'_, AppState>,
+) -> Result<KeepWarmEstimate, String> {
+    state.proxy_service.get_keep_warm_estimate().await
+}
+
+/// 获取网络状态（refresh 为 true 时立即重新探测）
+#[tauri::command]
+pub async fn get_network_status(refresh: Option<bool>) -> Result<NetworkStatus, String> {
+    if refresh.unwrap_or(false) {
+        offline::refresh().await;
+    }
+    Ok(offline::status())
+}
+
+/// 发现局域网中通过 mDNS 广播的 cc-switch 代理（默认等待 2 秒）
+#[tauri::command]
+pub async fn discover_lan_proxies(timeout_ms: Option<u64>) -> Result<Vec<DiscoveredProxy>, String> {
+    let timeout = std::time::Duration::from_millis(timeout_ms.unwrap_or(2000).clamp(200, 10_000));
+    tauri::async_runtime::spawn_blocking(move || mdns::discover(timeout))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 获取持久化的吞吐量样本（最近 N 小时，按时间升序）
+#[tauri::command]
+pub async fn get_tps_samples(
+    state: tauri::State<'_, AppState>,
+    app_type: Option<String>,
+    provider_id: Option<String>,
+    hours: u32,
+) -> Result<Vec<TpsSample>, String> {
+    let hours = hours.clamp(1, 24 * 90) as i64;
+    let since = chrono::Utc::now().timestamp() - hours * 3600;
+    state
+        .db
+        .get_tps_samples(app_type.as_deref(), provider_id.as_deref(), since)
+        .map_err(|e| e.to_string())
+}
+
+/// 获取影子镜像对比记录（按时间倒序，默认 100 条）
+#[tauri::command]
+pub async fn get_shadow_comparisons(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+    limit: Option<usize>,
+) -> Result<Vec<ShadowComparison>, String> {
+    state
+        .db
+        .get_shadow_comparisons(&app_type, limit.unwrap_or(100).clamp(1, 1000))
+        .map_err(|e| e.to_string())
+}
+
+/// 按影子供应商汇总对比结果
+#[tauri::command]
+pub async fn get_shadow_summary(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+) -> Result<Vec<ShadowSummary>, String> {
+    state
+        .db
+        .get_shadow_summary(&app_type)
+        .map_err(|e| e.to_string())
+}
+
+/// 清空影子镜像对比记录
+#[tauri::command]
+pub async fn clear_shadow_comparisons(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+) -> Result<usize, String> {
+    state
+        .db
+        .clear_shadow_comparisons(&app_type)
+        .map_err(|e| e.to_string())
+}
+
+/// 获取模型替换检测证据（按时间倒序，默认 100 条）
+#[tauri::command]
+pub async fn get_substitution_evidence(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+    provider_id: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<SubstitutionEvidence>, String> {
+    state
+        .db
+        .get_substitution_evidence(
+            &app_type,
+            provider_id.as_deref(),
+            limit.unwrap_or(100).clamp(1, 1000),
+        )
+        .map_err(|e| e.to_string())
+}
+
+/// 获取各供应商的模型替换嫌疑（近 7 天的线索汇总）
+#[tauri::command]
+pub async fn get_substitution_suspects(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+) -> Result<Vec<SubstitutionSuspect>, String> {
+    let since = chrono::Utc::now().timestamp() - model_audit::SUSPECT_WINDOW_SECS;
+    state
+        .db
+        .get_substitution_suspects(&app_type, since)
+        .map_err(|e| e.to_string())
+}
+
+/// 清除模型替换检测证据（确认供应商无问题后重置嫌疑）
+#[tauri::command]
+pub async fn clear_substitution_evidence(
+    state: tauri::State<'_, AppState>,
+    app_type: String,
+    provider_id: Option<String>,
+) -> Result<usize, String> {
+    state
+        .db
+        .clear_substitution_evidence(&app_type, provider_id.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// 持久化的请求（转发中或重启后重新发送的）
+#[tauri::command]
+pub async fn get_queued_requests(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<crate::database::QueuedRequest>, String> {
+    state.db.list_queued_requests().map_err(|e| e.to_string())
+}
+
+/// 清空持久化的请求（包括尚未重新发送的）
+#[tauri::command]
+pub async fn clear_queued_requests(state: tauri::State<'_, AppState>) -> Result<usize, String> {
+    state.db.clear_queued_requests().map_err(|e| e.to_string())
+}
+
+/// 为供应商注入模拟故障（持续 duration_secs 秒，默认 5 分钟），用于演练故障转移
+#[tauri::command]
+pub async fn set_fault_injection(
+    injection: FaultInjection,
+    duration_secs: Option<u64>,
+) -> Result<FaultInjection, String> {
+    let now = chrono::Utc::now().timestamp();
+    fault_injection::set(injection, duration_secs.unwrap_or(300), now)
+}
+
+/// 移除故障注入（provider_id 为空时移除全部），返回移除数量
+#[tauri::command]
+pub async fn clear_fault_injection(
+    app_type: Option<String>,
+    provider_id: Option<String>,
+) -> Result<usize, String> {
+    match (app_type, provider_id) {
+        (Some(app_type), Some(provider_id)) => {
+            Ok(fault_injection::clear(&app_type, &provider_id) as usize)
+        }
+        _ => Ok(fault_injection::clear_all()),
+    }
+}
+
+/// 当前生效的故障注入
+#[tauri::command]
+pub async fn list_fault_injections() -> Result<Vec<FaultInjection>, String> {
+    Ok(fault_injection::list(chrono::Utc::now().timestamp()))
+}
+
+/// 添加指向内置模拟上游的供应商，并开启模拟上游
+///
+/// 模拟上游挂载在代理监听地址下，需要启动代理后才能访问。
+#[tauri::command]
+pub async fn add_mock_provider(
+    state: tauri::State<'_, AppState>,
+    app: String,
+) -> Result<Provider, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    let config = state.proxy_service.get_config().await?;
+    let base_url = mock_upstream::base_url(&app_type, &config.listen_address, config.listen_port);
+
+    let mut settings = crate::settings::get_settings();
+    if !settings.mock_upstream.enabled {
+        settings.mock_upstream.enabled = true;
+        crate::settings::update_settings(settings).map_err(|e| e.to_string())?;
+    }
+
+    let provider = Provider::with_id(
+        format!("mock-upstream-{}", app_type.as_str()),
+        "Mock Upstream".to_string(),
+        mock_upstream::provider_settings(&app_type, &base_url),
+        None,
+    );
+    ProviderService::add(state.inner(), app_type, provider.clone()).map_err(|e| e.to_string())?;
+    Ok(provider)
+}
+
+/// 以后台任务回放一段时间内的请求日志，立即返回任务
+///
+/// 每完成一条推送一次 `task-progress` 事件，结果包含原始与回放的状态码和耗时。
+#[tauri::command]
+pub async fn start_request_replay(
+    state: tauri::State<'_, AppState>,
+    request: ReplayRequest,
+) -> Result<BackgroundTask, String> {
+    let entries = ReplayService::prepare(&state.db, &request).map_err(|e| e.to_string())?;
+    let proxy_service = state.proxy_service.clone();
+    background_task::spawn(state.db.clone(), "request-replay", move |task| async move {
+        ReplayService::run(&proxy_service, request, entries, task).await
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// 临时将某个会话、客户端密钥或客户端路由到指定供应商
+///
+/// 在 `minutes` 分钟或 `requests` 次请求后自动恢复，不修改当前供应商。
+#[tauri::command]
+pub async fn set_session_override(
+    state: tauri::State<'_, AppState>,
+    app: String,
+    target: OverrideTarget,
+    provider_id: String,
+    minutes: Option<u32>,
+    requests: Option<u32>,
+) -> Result<SessionOverride, String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    if state
+        .db
+        .get_provider_by_id(&provider_id, app_type.as_str())
+        .map_err(|e| e.to_string())?
+        .is_none()
+    {
+        return Err(format!("供应商不存在: {provider_id}"));
+    }
+    session_override::set(
+        app_type.as_str(),
+        target,
+        &provider_id,
+        minutes,
+        requests,
+        chrono::Utc::now().timestamp(),
+    )
+}
+
+/// 提前结束临时覆盖
+#[tauri::command]
+pub async fn clear_session_override(id: String) -> Result<bool, String> {
+    Ok(session_override::clear(&id))
+}
+
+/// 当前生效的临时覆盖
+#[tauri::command]
+pub async fn list_session_overrides() -> Result<Vec<SessionOverride>, String> {
+    Ok(session_override::list(chrono::Utc::now().timestamp()))
+}
+
+/// 各供应商的结构化输出校验统计
+#[tauri::command]
+pub async fn get_output_validation_stats() -> Result<Vec<OutputValidationStats>, String> {
+    Ok(output_validation::stats())
+}
+
+/// 各供应商的出站内容过滤统计
+#[tauri::command]
+pub async fn get_redaction_stats() -> Result<Vec<RedactionStats>, String> {
+    Ok(outbound_redaction::stats())
+}
+
+/// 各供应商流式响应的编码修复统计
+#[tauri::command]
+pub async fn get_sse_sanitize_stats() -> Result<Vec<SanitizeStats>, String> {
+    Ok(sse_sanitize::stats())
+}
+
+/// 各供应商的请求参数策略统计（改写、拒绝次数与最近一次违反）
+#[tauri::command]
+pub async fn get_parameter_policy_stats() -> Result<Vec<PolicyStats>, String> {
+    Ok(param_policy::stats())
+}
+
+/// 共享 HTTP 客户端的复用统计
+#[tauri::command]
+pub async fn get_http_client_pool_stats() -> Result<ClientPoolStats, String> {
+    Ok(client_pool::stats())
+}
+
+/// 丢弃共享 HTTP 客户端及其连接（网络环境变化后使用），返回回收的数量
+#[tauri::command]
+pub async fn recycle_http_clients() -> Result<usize, String> {
+    Ok(client_pool::recycle())
+}
+
 /// 获取代理配置
 #[tauri::command]
 pub async fn get_proxy_config(state: tauri::State<'_, AppState>) -> Result<ProxyConfig, String> {
@@ -132,16 +459,22 @@ pub async fn is_live_takeover_active(state: tauri::State<'_, AppState>) -> Resul
 }
 
 /// 代理模式下切换供应商（热切换）
+///
+/// 与普通切换共用同一流程（配置锁、切换队列、切换历史），接管中时只热切换不写 Live 配置
 #[tauri::command]
 pub async fn switch_proxy_provider(
-    state: tauri::State<'_, AppState>,
+    app: tauri::AppHandle,
     app_type: String,
     provider_id: String,
 ) -> Result<(), String> {
-    state
-        .proxy_service
-        .switch_proxy_target(&app_type, &provider_id)
-        .await
+    tauri::async_runtime::spawn_blocking(move || {
+        use tauri::Manager;
+        let state = app.state::<AppState>();
+        crate::commands::switch_provider_from(&app, &state, &app_type, provider_id, "proxy")
+    })
+    .await
+    .map_err(|e| format!("切换供应商失败: {e}"))?
+    .map(|_| ())
 }
 
 // ==================== 故障转移相关命令 ====================
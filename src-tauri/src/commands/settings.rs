@@ -2,6 +2,9 @@
 
 use tauri::AppHandle;
 
+use crate::services::log_level::{LogLevel, LogLevelConfig, LogModule};
+use crate::services::startup_profile::{self, Subsystem, SubsystemStatus};
+
 /// 获取设置
 #[tauri::command]
 pub async fn get_settings() -> Result<crate::settings::AppSettings, String> {
@@ -15,6 +18,61 @@ pub async fn save_settings(settings: crate::settings::AppSettings) -> Result<boo
     Ok(true)
 }
 
+/// 获取日志级别设置
+#[tauri::command]
+pub async fn get_log_levels() -> Result<LogLevelConfig, String> {
+    Ok(crate::settings::get_settings().log_levels)
+}
+
+/// 修改日志级别并立即生效：`module` 为空时修改默认级别，`level` 为空时移除该模块的覆盖
+#[tauri::command]
+pub async fn set_log_level(
+    module: Option<LogModule>,
+    level: Option<LogLevel>,
+) -> Result<LogLevelConfig, String> {
+    let mut settings = crate::settings::get_settings();
+    match (module, level) {
+        (None, level) => settings.log_levels.default = level.unwrap_or_default(),
+        (Some(module), Some(level)) => {
+            settings.log_levels.modules.insert(module, level);
+        }
+        (Some(module), None) => {
+            settings.log_levels.modules.remove(&module);
+        }
+    }
+    let levels = settings.log_levels.clone();
+    crate::settings::update_settings(settings).map_err(|e| e.to_string())?;
+    log::info!("日志级别已更新: {levels:?}");
+    Ok(levels)
+}
+
+/// 获取各子系统的开关状态
+#[tauri::command]
+pub async fn get_subsystem_status() -> Result<Vec<SubsystemStatus>, String> {
+    Ok(startup_profile::status())
+}
+
+/// 运行中开启 / 关闭子系统：关闭代理时停止代理并恢复 Live 配置（保留接管状态），
+/// 重新开启时按保存的状态恢复代理
+#[tauri::command]
+pub async fn set_subsystem_enabled(
+    state: tauri::State<'_, crate::store::AppState>,
+    subsystem: Subsystem,
+    enabled: bool,
+) -> Result<Vec<SubsystemStatus>, String> {
+    startup_profile::set_enabled(subsystem, enabled).map_err(|e| e.to_string())?;
+
+    if subsystem == Subsystem::Proxy {
+        if !enabled && state.proxy_service.is_running().await {
+            state.proxy_service.stop_with_restore_keep_state().await?;
+        } else if startup_profile::is_enabled(Subsystem::Proxy) {
+            crate::restore_proxy_state_on_startup(&state).await;
+            crate::services::proxy_runtime::restore(&state.db, &state.proxy_service).await;
+        }
+    }
+    Ok(startup_profile::status())
+}
+
 /// 重启应用程序（当 app_config_dir 变更后使用）
 #[tauri::command]
 pub async fn restart_app(app: AppHandle) -> Result<bool, String> {
@@ -26,6 +84,33 @@ pub async fn restart_app(app: AppHandle) -> Result<bool, String> {
     Ok(true)
 }
 
+/// 重置应用：恢复 Live 配置、删除全部数据与密钥后退出（用于交接设备）
+#[tauri::command]
+pub async fn reset_app(
+    app: AppHandle,
+    state: tauri::State<'_, crate::store::AppState>,
+    confirm: String,
+) -> Result<crate::services::reset::ResetReport, String> {
+    use tauri::Manager;
+
+    let extra_paths = app.path().app_log_dir().into_iter().collect();
+    let report = crate::services::reset::ResetService::reset(&state, &confirm, extra_paths)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // 清除 Store 中的数据目录覆盖；直接退出进程，跳过会重新写出数据的退出清理
+    if crate::app_store::startup_data_dir().is_none() {
+        if let Err(e) = crate::app_store::set_app_config_dir_to_store(&app, None) {
+            log::warn!("清除数据目录覆盖失败: {e}");
+        }
+    }
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
+        std::process::exit(0);
+    });
+    Ok(report)
+}
+
 /// 获取 app_config_dir 覆盖配置 (从 Store)
 #[tauri::command]
 pub async fn get_app_config_dir_override(app: AppHandle) -> Result<Option<String>, String> {
@@ -43,7 +128,26 @@ pub async fn set_app_config_dir_override(
     Ok(true)
 }
 
-/// 设置开机自启
+/// 将现有数据复制到新的数据目录，成功后更新数据目录配置（需重启生效）
+#[tauri::command]
+pub async fn migrate_data_dir(
+    app: AppHandle,
+    state: tauri::State<'_, crate::store::AppState>,
+    target: String,
+) -> Result<crate::services::data_dir::DataMigrationReport, String> {
+    if let Some(dir) = crate::app_store::startup_data_dir() {
+        return Err(format!(
+            "数据目录已由启动参数或便携模式指定（{}），无法迁移",
+            dir.display()
+        ));
+    }
+    let target = target.trim();
+    let report = crate::services::data_dir::migrate(&state.db, std::path::Path::new(target))
+        .map_err(|e| e.to_string())?;
+    crate::app_store::set_app_config_dir_to_store(&app, Some(target))?;
+    Ok(report)
+}
+
 #[tauri::command]
 pub async fn set_auto_launch(enabled: bool) -> Result<bool, String> {
     if enabled {
@@ -59,3 +163,36 @@ pub async fn set_auto_launch(enabled: bool) -> Result<bool, String> {
 pub async fn get_auto_launch_status() -> Result<bool, String> {
     crate::auto_launch::is_auto_launch_enabled().map_err(|e| format!("获取开机自启状态失败: {e}"))
 }
+
+/// 获取配置锁状态
+#[tauri::command]
+pub async fn get_config_lock_status(
+    state: tauri::State<'_, crate::store::AppState>,
+) -> Result<crate::services::config_lock::ConfigLockStatus, String> {
+    crate::services::config_lock::status(&state.db).map_err(|e| e.to_string())
+}
+
+/// 锁定配置（只读模式），可选设置解锁口令
+#[tauri::command]
+pub async fn lock_config(
+    state: tauri::State<'_, crate::store::AppState>,
+    passphrase: Option<String>,
+) -> Result<crate::services::config_lock::ConfigLockStatus, String> {
+    crate::services::config_lock::lock(&state.db, passphrase.as_deref()).map_err(|e| e.to_string())
+}
+
+/// 解锁配置
+#[tauri::command]
+pub async fn unlock_config(
+    state: tauri::State<'_, crate::store::AppState>,
+    passphrase: Option<String>,
+) -> Result<crate::services::config_lock::ConfigLockStatus, String> {
+    crate::services::config_lock::unlock(&state.db, passphrase.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// 获取当前实例状态（是否为只读附加实例）
+#[tauri::command]
+pub async fn get_instance_status() -> Result<crate::instance_guard::InstanceStatus, String> {
+    Ok(crate::instance_guard::status())
+}
@@ -1,20 +1,131 @@
 //! 流式健康检查命令
 
 use crate::app_config::AppType;
+use crate::database::{BackgroundTask, Database};
 use crate::error::AppError;
+use crate::events::{self, AppEvent, HealthChangedPayload};
+use crate::i18n;
+use crate::provider::Provider;
+use crate::services::background_task;
+use crate::services::cancellation::{self, CancelToken};
+use crate::services::health_probe;
+use crate::services::maintenance;
+use crate::services::onboarding::{self, OnboardingReport};
+use crate::services::probe_budget::{
+    BudgetState, ProbeBudgetService, ProbeBudgetStatus, THROTTLED_MIN_INTERVAL_SECS,
+    THROTTLE_FACTOR,
+};
+use crate::services::startup_profile::{self, Subsystem};
 use crate::services::stream_check::{
-    HealthStatus, StreamCheckConfig, StreamCheckResult, StreamCheckService,
+    is_stale, StreamCheckConfig, StreamCheckResult, StreamCheckService,
 };
+use crate::services::traffic_health::{self, BlendedHealth};
 use crate::store::AppState;
 use std::collections::HashSet;
-use tauri::State;
+use tauri::{AppHandle, Manager, State};
+
+/// 发布检查结果事件（维护窗口内的失败只记录，不告警）
+fn publish_health(app_type: &AppType, provider: &Provider, result: &StreamCheckResult) {
+    let in_maintenance = maintenance::is_in_maintenance(provider);
+    if in_maintenance && !result.success {
+        log::info!(
+            "[StreamCheck] {} 处于维护窗口内，检查失败不触发告警",
+            provider.name
+        );
+    }
+    events::publish(AppEvent::HealthChanged(HealthChangedPayload {
+        app_type: app_type.as_str().to_string(),
+        provider_id: provider.id.clone(),
+        result: result.clone(),
+        in_maintenance,
+    }));
+}
+
+/// 切换到最近未检查过的供应商时，在后台立即检查一次并发送 `health-changed` 事件，
+/// 避免切换后界面仍显示过期的健康状态
+pub(crate) fn spawn_switch_snapshot(app: &AppHandle, app_type: AppType, provider_id: String) {
+    if !startup_profile::is_enabled(Subsystem::HealthScheduler) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let config = state.db.get_stream_check_config().unwrap_or_default();
+        let latest = state
+            .db
+            .get_stream_check_latest(&provider_id, app_type.as_str(), None)
+            .ok()
+            .flatten();
+        let provider = match state.db.get_provider_by_id(&provider_id, app_type.as_str()) {
+            Ok(Some(p)) => p,
+            _ => return,
+        };
 
-/// 流式健康检查（单个供应商）
+        // 接近探测预算时放宽过期判定，用尽时不再自动检查（轻量探测不消耗预算）
+        let now = chrono::Utc::now().timestamp();
+        let kind = health_probe::scheduled_kind(app_type.as_str(), &provider, now);
+        let budget_state = if kind.consumes_tokens() {
+            ProbeBudgetService::state_or_normal(&state.db, app_type.as_str(), &provider, &config)
+        } else {
+            BudgetState::Normal
+        };
+        let stale_minutes = match budget_state {
+            BudgetState::Normal => config.snapshot_stale_minutes,
+            BudgetState::Throttled => config
+                .snapshot_stale_minutes
+                .saturating_mul(THROTTLE_FACTOR),
+            BudgetState::Exhausted => return,
+        };
+        if !is_stale(latest.as_ref(), now, stale_minutes) {
+            return;
+        }
+        log::info!(
+            "[StreamCheck] 切换后检查 {}/{}（上次检查已过期）",
+            app_type.as_str(),
+            provider.name
+        );
+
+        let probe = health_probe::probe_for(kind, &provider);
+        let result = StreamCheckService::check_with_probe(
+            &app_type,
+            &provider,
+            &config,
+            &*probe,
+            &CancelToken::new(),
+        )
+        .await
+        .unwrap_or_else(|e| StreamCheckResult::from_error(&e));
+        if kind.consumes_tokens() {
+            ProbeBudgetService::record_result(
+                &state.db,
+                app_type.as_str(),
+                &provider,
+                &config,
+                &result,
+            );
+        }
+        let _ = state.db.save_stream_check_log(
+            &provider_id,
+            &provider.name,
+            app_type.as_str(),
+            &result,
+        );
+        if result.is_conclusive() {
+            publish_health(&app_type, &provider, &result);
+        }
+    });
+}
+
+/// 流式健康检查（单个供应商，model 为空时使用默认测试模型）
+///
+/// 提供 `check_id` 时可通过 `cancel_check` 取消，取消的检查记录为 `cancelled`。
 #[tauri::command]
 pub async fn stream_check_provider(
     state: State<'_, AppState>,
     app_type: AppType,
     provider_id: String,
+    model: Option<String>,
+    check_id: Option<String>,
 ) -> Result<StreamCheckResult, AppError> {
     let config = state.db.get_stream_check_config()?;
 
@@ -23,46 +134,76 @@ pub async fn stream_check_provider(
         .get(&provider_id)
         .ok_or_else(|| AppError::Message(format!("供应商 {provider_id} 不存在")))?;
 
-    let result = match StreamCheckService::check_with_retry(&app_type, provider, &config).await {
+    let model = model.as_deref().map(str::trim).filter(|m| !m.is_empty());
+    let cancel = check_token(check_id.as_deref());
+    let result =
+        StreamCheckService::check_model_with_retry(&app_type, provider, &config, model, &cancel)
+            .await;
+    if let Some(id) = &check_id {
+        cancellation::unregister(id);
+    }
+    let result = match result {
         Ok(r) => r,
         Err(e) => StreamCheckResult {
-            status: HealthStatus::Failed,
-            success: false,
-            message: e.to_string(),
-            response_time_ms: None,
-            http_status: None,
-            model_used: String::new(),
-            tested_at: chrono::Utc::now().timestamp(),
-            retry_count: 0,
+            model_used: model.unwrap_or_default().to_string(),
+            ..StreamCheckResult::from_error(&e)
         },
     };
 
+    // 手动检查不受探测预算限制，但计入消耗
+    ProbeBudgetService::record_result(&state.db, app_type.as_str(), provider, &config, &result);
+
     // 记录日志
     let _ =
         state
             .db
             .save_stream_check_log(&provider_id, &provider.name, app_type.as_str(), &result);
+    if result.is_conclusive() {
+        publish_health(&app_type, provider, &result);
+    }
 
     Ok(result)
 }
 
-/// 批量流式健康检查
+/// 调用方提供 ID 时注册可取消的令牌
+fn check_token(check_id: Option<&str>) -> CancelToken {
+    check_id.map(cancellation::register).unwrap_or_default()
+}
+
+/// 取消进行中的健康检查或 TPS 测试，返回检查是否仍在进行
 #[tauri::command]
-pub async fn stream_check_all_providers(
+pub fn cancel_check(check_id: String) -> Result<bool, AppError> {
+    Ok(cancellation::cancel(&check_id))
+}
+
+/// 新供应商接入校验：对尚未保存的草稿依次检查地址、认证、模型列表与流式补全
+///
+/// 不写入检查日志，也不计入探测预算。
+#[tauri::command]
+pub async fn validate_provider_draft(
     state: State<'_, AppState>,
     app_type: AppType,
-    proxy_targets_only: bool,
-) -> Result<Vec<(String, StreamCheckResult)>, AppError> {
+    provider: Provider,
+    model: Option<String>,
+) -> Result<OnboardingReport, AppError> {
     let config = state.db.get_stream_check_config()?;
-    let providers = state.db.get_all_providers(app_type.as_str())?;
+    let model = model.as_deref().map(str::trim).filter(|m| !m.is_empty());
+    onboarding::validate_draft(&app_type, &provider, &config, model).await
+}
 
-    let mut results = Vec::new();
+/// 批量检查的目标供应商（proxy_targets_only 时只包含当前供应商与故障转移队列）
+fn batch_targets(
+    db: &Database,
+    app_type: &AppType,
+    proxy_targets_only: bool,
+) -> Result<Vec<(String, Provider)>, AppError> {
+    let providers = db.get_all_providers(app_type.as_str())?;
     let allowed_ids: Option<HashSet<String>> = if proxy_targets_only {
         let mut ids = HashSet::new();
-        if let Ok(Some(current_id)) = state.db.get_current_provider(app_type.as_str()) {
+        if let Ok(Some(current_id)) = db.get_current_provider(app_type.as_str()) {
             ids.insert(current_id);
         }
-        if let Ok(queue) = state.db.get_failover_queue(app_type.as_str()) {
+        if let Ok(queue) = db.get_failover_queue(app_type.as_str()) {
             for item in queue {
                 ids.insert(item.provider_id);
             }
@@ -72,36 +213,147 @@ pub async fn stream_check_all_providers(
         None
     };
 
-    for (id, provider) in providers {
-        if let Some(ids) = &allowed_ids {
-            if !ids.contains(&id) {
-                continue;
+    Ok(providers
+        .into_iter()
+        .filter(|(id, _)| allowed_ids.as_ref().is_none_or(|ids| ids.contains(id)))
+        .collect())
+}
+
+/// 批量检查中的单个供应商，返回默认模型的结果（预算用尽时返回跳过结果）
+async fn batch_check_one(
+    db: &Database,
+    app_type: &AppType,
+    config: &StreamCheckConfig,
+    id: &str,
+    provider: &Provider,
+    cancel: &CancelToken,
+) -> Option<StreamCheckResult> {
+    let now = chrono::Utc::now().timestamp();
+    let kind = health_probe::scheduled_kind(app_type.as_str(), provider, now);
+    if !kind.consumes_tokens() {
+        let probe = health_probe::probe_for(kind, provider);
+        let result =
+            StreamCheckService::check_with_probe(app_type, provider, config, &*probe, cancel)
+                .await
+                .unwrap_or_else(|e| StreamCheckResult::from_error(&e));
+        let _ = db.save_stream_check_log(id, &provider.name, app_type.as_str(), &result);
+        if result.is_conclusive() {
+            publish_health(app_type, provider, &result);
+        }
+        return Some(result);
+    }
+
+    match ProbeBudgetService::state_or_normal(db, app_type.as_str(), provider, config) {
+        BudgetState::Normal => {}
+        BudgetState::Throttled => {
+            let recent = db
+                .get_stream_check_latest(id, app_type.as_str(), None)
+                .ok()
+                .flatten()
+                .filter(|r| now - r.tested_at < THROTTLED_MIN_INTERVAL_SECS);
+            if recent.is_some() {
+                return recent;
             }
         }
+        BudgetState::Exhausted => {
+            log::info!(
+                "[StreamCheck] {} 本月探测预算已用尽，跳过检查",
+                provider.name
+            );
+            return Some(StreamCheckResult::skipped(i18n::tr_current(
+                "streamCheck.budgetExhausted",
+            )));
+        }
+    }
 
-        let result = StreamCheckService::check_with_retry(&app_type, &provider, &config)
-            .await
-            .unwrap_or_else(|e| StreamCheckResult {
-                status: HealthStatus::Failed,
-                success: false,
-                message: e.to_string(),
-                response_time_ms: None,
-                http_status: None,
-                model_used: String::new(),
-                tested_at: chrono::Utc::now().timestamp(),
-                retry_count: 0,
-            });
-
-        let _ = state
-            .db
-            .save_stream_check_log(&id, &provider.name, app_type.as_str(), &result);
+    let model_results =
+        StreamCheckService::check_all_models(app_type, provider, config, cancel).await;
+    for result in &model_results {
+        ProbeBudgetService::record_result(db, app_type.as_str(), provider, config, result);
+        let _ = db.save_stream_check_log(id, &provider.name, app_type.as_str(), result);
+    }
 
-        results.push((id, result));
+    let result = model_results.into_iter().next()?;
+    if result.is_conclusive() {
+        publish_health(app_type, provider, &result);
     }
+    Some(result)
+}
 
+/// 批量流式健康检查
+///
+/// 除默认测试模型外，还会检查供应商配置的附加模型（均记录日志），返回值仅包含默认模型的结果。
+/// 受探测 token 预算约束：接近预算时复用近期结果，用尽时返回跳过结果。
+/// 供应商选择了轻量探测时，完整检查到期前只运行轻量探测（不检查附加模型，不消耗预算）。
+/// 提供 `check_id` 时可通过 `cancel_check` 取消，返回已完成部分的结果。
+#[tauri::command]
+pub async fn stream_check_all_providers(
+    state: State<'_, AppState>,
+    app_type: AppType,
+    proxy_targets_only: bool,
+    check_id: Option<String>,
+) -> Result<Vec<(String, StreamCheckResult)>, AppError> {
+    let config = state.db.get_stream_check_config()?;
+    let targets = batch_targets(&state.db, &app_type, proxy_targets_only)?;
+    let cancel = check_token(check_id.as_deref());
+    let mut results = Vec::new();
+    for (id, provider) in targets {
+        if cancel.is_cancelled() {
+            break;
+        }
+        if let Some(result) =
+            batch_check_one(&state.db, &app_type, &config, &id, &provider, &cancel).await
+        {
+            results.push((id, result));
+        }
+    }
+    if let Some(id) = &check_id {
+        cancellation::unregister(id);
+    }
     Ok(results)
 }
 
+/// 以后台任务运行批量健康检查，立即返回任务
+///
+/// 每检查完一个供应商推送一次 `task-progress` 事件，结果为 `[providerId, result]`。
+#[tauri::command]
+pub async fn start_stream_check_all_task(
+    state: State<'_, AppState>,
+    app_type: AppType,
+    proxy_targets_only: bool,
+) -> Result<BackgroundTask, AppError> {
+    let db = state.db.clone();
+    let config = db.get_stream_check_config()?;
+    let targets = batch_targets(&db, &app_type, proxy_targets_only)?;
+    background_task::spawn(db.clone(), "stream-check-all", move |task| async move {
+        task.set_total(targets.len() as u32);
+        for (id, provider) in targets {
+            if task.is_cancelled() {
+                break;
+            }
+            let result =
+                batch_check_one(&db, &app_type, &config, &id, &provider, task.token()).await;
+            task.push_result(&(id, result))?;
+        }
+        Ok(())
+    })
+}
+
+/// 获取各供应商本月的健康检查 token 消耗与预算状态
+#[tauri::command]
+pub fn get_probe_budget_status(
+    state: State<'_, AppState>,
+    app_type: AppType,
+) -> Result<Vec<ProbeBudgetStatus>, AppError> {
+    let config = state.db.get_stream_check_config()?;
+    state
+        .db
+        .get_all_providers(app_type.as_str())?
+        .values()
+        .map(|provider| ProbeBudgetService::status(&state.db, app_type.as_str(), provider, &config))
+        .collect()
+}
+
 /// 获取流式检查配置
 #[tauri::command]
 pub fn get_stream_check_config(state: State<'_, AppState>) -> Result<StreamCheckConfig, AppError> {
@@ -117,28 +369,41 @@ pub fn save_stream_check_config(
     state.db.save_stream_check_config(&config)
 }
 
-/// 获取最近一次流式健康检查结果（来自日志）
+/// 获取最近一次流式健康检查结果（来自日志，model 为空时不限模型）
 #[tauri::command]
 pub fn get_stream_check_latest(
     state: State<'_, AppState>,
     app_type: AppType,
     provider_id: String,
+    model: Option<String>,
 ) -> Result<Option<StreamCheckResult>, AppError> {
     state
         .db
-        .get_stream_check_latest(&provider_id, app_type.as_str())
+        .get_stream_check_latest(&provider_id, app_type.as_str(), model.as_deref())
+}
+
+/// 获取混合真实流量后的健康状态（含最近一次检查结果与窗口内的真实请求统计）
+#[tauri::command]
+pub fn get_blended_health(
+    state: State<'_, AppState>,
+    app_type: AppType,
+    provider_id: String,
+) -> Result<BlendedHealth, AppError> {
+    let config = state.db.get_stream_check_config()?;
+    traffic_health::blended_health(&state.db, &provider_id, app_type.as_str(), &config)
 }
 
-/// 获取最近 N 次流式健康检查结果（来自日志，按时间倒序）
+/// 获取最近 N 次流式健康检查结果（来自日志，按时间倒序，model 为空时不限模型）
 #[tauri::command]
 pub fn get_stream_check_history(
     state: State<'_, AppState>,
     app_type: AppType,
     provider_id: String,
+    model: Option<String>,
     limit: u32,
 ) -> Result<Vec<StreamCheckResult>, AppError> {
     let limit = limit.clamp(1, 200);
     state
         .db
-        .get_stream_check_history(&provider_id, app_type.as_str(), limit)
+        .get_stream_check_history(&provider_id, app_type.as_str(), model.as_deref(), limit)
 }
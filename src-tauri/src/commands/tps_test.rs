@@ -2,16 +2,18 @@
 
 use crate::app_config::AppType;
 use crate::error::AppError;
+use crate::services::cancellation;
 use crate::services::tps_test::{TpsTestResult, TpsTestService};
 use crate::store::AppState;
 use tauri::State;
 
-/// TPS 测试（单个供应商）
+/// TPS 测试（单个供应商，提供 `check_id` 时可通过 `cancel_check` 取消）
 #[tauri::command]
 pub async fn tps_test_provider(
     state: State<'_, AppState>,
     app_type: AppType,
     provider_id: String,
+    check_id: Option<String>,
 ) -> Result<TpsTestResult, AppError> {
     let config = state.db.get_stream_check_config()?;
     let providers = state.db.get_all_providers(app_type.as_str())?;
@@ -19,6 +21,15 @@ pub async fn tps_test_provider(
         .get(&provider_id)
         .ok_or_else(|| AppError::Message(format!("供应商 {provider_id} 不存在")))?;
 
-    Ok(TpsTestService::test_once(&app_type, provider, config.timeout_secs).await)
+    let cancel = check_id
+        .as_deref()
+        .map(cancellation::register)
+        .unwrap_or_default();
+    let result =
+        TpsTestService::test_cancellable(&app_type, provider, config.timeout_secs, &cancel).await;
+    if let Some(id) = &check_id {
+        cancellation::unregister(id);
+    }
+    Ok(result)
 }
 
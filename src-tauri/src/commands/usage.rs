@@ -1,10 +1,16 @@
 //! 使用统计相关命令
 
+use crate::database::{ProviderActivity, ProviderActivitySort};
 use crate::error::AppError;
+use crate::services::latency_slo::SloStatus;
+use crate::services::usage_forecast::{ForecastMethod, UsageForecast};
 use crate::services::usage_stats::*;
 use crate::store::AppState;
 use tauri::State;
 
+/// 判定为闲置供应商的默认天数
+const DEFAULT_IDLE_DAYS: u32 = 60;
+
 /// 获取使用量汇总
 #[tauri::command]
 pub fn get_usage_summary(
@@ -31,12 +37,67 @@ pub fn get_provider_stats(state: State<'_, AppState>) -> Result<Vec<ProviderStat
     state.db.get_provider_stats()
 }
 
+/// 供应商用量排行：累计 token、最近使用时间，闲置 `idle_days`（默认 60）天以上的标记为闲置
+#[tauri::command]
+pub fn get_provider_activity(
+    state: State<'_, AppState>,
+    app_type: Option<String>,
+    sort: Option<ProviderActivitySort>,
+    idle_days: Option<u32>,
+) -> Result<Vec<ProviderActivity>, AppError> {
+    state.db.get_provider_activity(
+        app_type.as_deref(),
+        sort.unwrap_or_default(),
+        idle_days.unwrap_or(DEFAULT_IDLE_DAYS),
+        chrono::Utc::now().timestamp(),
+    )
+}
+
 /// 获取模型统计
 #[tauri::command]
 pub fn get_model_stats(state: State<'_, AppState>) -> Result<Vec<ModelStats>, AppError> {
     state.db.get_model_stats()
 }
 
+/// 获取流式输出速度分位统计（按 Provider）
+#[tauri::command]
+pub fn get_streaming_speed_stats(
+    state: State<'_, AppState>,
+    app_type: Option<String>,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+) -> Result<Vec<StreamingSpeedStats>, AppError> {
+    state
+        .db
+        .get_streaming_speed_stats(app_type.as_deref(), start_date, end_date)
+}
+
+/// 获取每日流量统计（按 Provider）
+#[tauri::command]
+pub fn get_bandwidth_usage(
+    state: State<'_, AppState>,
+    app_type: Option<String>,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+) -> Result<Vec<DailyBandwidth>, AppError> {
+    state
+        .db
+        .get_bandwidth_usage(app_type.as_deref(), start_date, end_date)
+}
+
+/// 按请求标签汇总用量
+#[tauri::command]
+pub fn get_tag_stats(
+    state: State<'_, AppState>,
+    app_type: Option<String>,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+) -> Result<Vec<TagStats>, AppError> {
+    state
+        .db
+        .get_tag_stats(app_type.as_deref(), start_date, end_date)
+}
+
 /// 获取请求日志列表
 #[tauri::command]
 pub fn get_request_logs(
@@ -150,6 +211,47 @@ pub fn check_provider_limits(
     state.db.check_provider_limits(&provider_id, &app_type)
 }
 
+/// 获取延迟热力图（最近 N 周，按星期几 × 小时分组，默认 4 周）
+#[tauri::command]
+pub fn get_latency_heatmap(
+    state: State<'_, AppState>,
+    app_type: Option<String>,
+    provider_id: Option<String>,
+    weeks: Option<u32>,
+    utc_offset_minutes: Option<i32>,
+) -> Result<Vec<LatencyHeatmapCell>, AppError> {
+    state.db.get_latency_heatmap(
+        app_type.as_deref(),
+        provider_id.as_deref(),
+        weeks.unwrap_or(4),
+        utc_offset_minutes,
+    )
+}
+
+/// 获取各 Provider 的月底用量预测（未指定方式时使用设置中的方式）
+#[tauri::command]
+pub fn get_usage_forecast(
+    state: State<'_, AppState>,
+    app_type: Option<String>,
+    method: Option<ForecastMethod>,
+) -> Result<Vec<UsageForecast>, AppError> {
+    let config = crate::settings::get_settings().usage_forecast;
+    state.db.get_usage_forecast(
+        app_type.as_deref(),
+        method.unwrap_or(config.method),
+        config.threshold_usd,
+    )
+}
+
+/// 获取声明了延迟 SLO 的 Provider 的达标率、剩余错误预算与燃烧率
+#[tauri::command]
+pub fn get_latency_slo_status(
+    state: State<'_, AppState>,
+    app_type: Option<String>,
+) -> Result<Vec<SloStatus>, AppError> {
+    crate::services::latency_slo::get_statuses(&state.db, app_type.as_deref())
+}
+
 /// 删除模型定价
 #[tauri::command]
 pub fn delete_model_pricing(state: State<'_, AppState>, model_id: String) -> Result<(), AppError> {
@@ -177,3 +279,27 @@ pub struct ModelPricingInfo {
     pub cache_read_cost_per_million: String,
     pub cache_creation_cost_per_million: String,
 }
+
+/// 查询会话记录（可按会话过滤、按关键字搜索）
+#[tauri::command]
+pub fn get_transcripts(
+    state: State<'_, AppState>,
+    session_id: Option<String>,
+    query: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<crate::database::Transcript>, AppError> {
+    state.db.list_transcripts(
+        session_id.as_deref(),
+        query.as_deref(),
+        limit.unwrap_or(100),
+    )
+}
+
+/// 删除会话记录（未指定会话时删除全部）
+#[tauri::command]
+pub fn delete_transcripts(
+    state: State<'_, AppState>,
+    session_id: Option<String>,
+) -> Result<usize, AppError> {
+    state.db.delete_transcripts(session_id.as_deref())
+}
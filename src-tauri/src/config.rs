@@ -242,6 +242,17 @@ pub fn delete_file(path: &Path) -> Result<(), AppError> {
     Ok(())
 }
 
+/// 覆写后删除文件，尽量避免密钥或明文数据残留在磁盘上
+pub fn shred_file(path: &Path) {
+    if let Ok(meta) = fs::metadata(path) {
+        let zeros = vec![0u8; meta.len().min(64 * 1024 * 1024) as usize];
+        let _ = fs::write(path, zeros);
+    }
+    if let Err(e) = fs::remove_file(path) {
+        log::warn!("删除文件 {} 失败: {e}", path.display());
+    }
+}
+
 /// 检查 Claude Code 配置状态
 #[derive(Serialize, Deserialize)]
 pub struct ConfigStatus {
@@ -0,0 +1,405 @@
+//! 配置校验
+//!
+//! 对流式检查配置、代理路由配置（重试 / 超时 / 熔断）和供应商定义做字段级校验，
+//! 保存时拒绝非法值，加载时给出具体字段的错误，而不是笼统的“解析配置失败”。
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::database::Database;
+use crate::error::{AppError, FieldError};
+use crate::provider::Provider;
+use crate::proxy::endpoint_template::EndpointTemplates;
+use crate::proxy::types::{AppProxyConfig, GlobalProxyConfig};
+use crate::services::stream_check::StreamCheckConfig;
+
+/// 字段错误收集器
+#[derive(Debug, Default)]
+struct Errors(Vec<FieldError>);
+
+impl Errors {
+    fn push(&mut self, field: &str, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn range_u64(&mut self, field: &str, value: u64, min: u64, max: u64) {
+        if value < min || value > max {
+            self.push(
+                field,
+                format!("必须在 {min} 到 {max} 之间（当前为 {value}）"),
+            );
+        }
+    }
+
+    fn non_empty(&mut self, field: &str, value: &str) {
+        if value.trim().is_empty() {
+            self.push(field, "不能为空");
+        }
+    }
+}
+
+/// 无错误时返回 Ok，否则返回 `AppError::Validation`
+pub fn into_result(errors: Vec<FieldError>) -> Result<(), AppError> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::Validation(errors))
+    }
+}
+
+/// 校验流式检查配置的取值范围
+pub fn validate_stream_check_config(config: &StreamCheckConfig) -> Vec<FieldError> {
+    let mut e = Errors::default();
+    e.range_u64("timeoutSecs", config.timeout_secs, 1, 600);
+    e.range_u64("maxRetries", config.max_retries as u64, 0, 10);
+    e.range_u64(
+        "degradedThresholdMs",
+        config.degraded_threshold_ms,
+        1,
+        config.timeout_secs.max(1) * 1000,
+    );
+    e.non_empty("claudeModel", &config.claude_model);
+    e.non_empty("codexModel", &config.codex_model);
+    e.non_empty("geminiModel", &config.gemini_model);
+    e.range_u64(
+        "snapshotStaleMinutes",
+        config.snapshot_stale_minutes as u64,
+        0,
+        7 * 24 * 60,
+    );
+    e.range_u64(
+        "retryBackoffBaseMs",
+        config.retry_backoff_base_ms,
+        0,
+        60_000,
+    );
+    e.range_u64(
+        "retryBackoffMaxMs",
+        config.retry_backoff_max_ms,
+        config.retry_backoff_base_ms,
+        300_000,
+    );
+    if let Some(status) = config
+        .retryable_statuses
+        .iter()
+        .find(|s| !(100..=599).contains(*s))
+    {
+        e.push("retryableStatuses", format!("无效的 HTTP 状态码: {status}"));
+    }
+    let traffic = &config.traffic_health;
+    e.range_u64(
+        "trafficHealth.windowMinutes",
+        traffic.window_minutes as u64,
+        1,
+        24 * 60,
+    );
+    e.range_u64(
+        "trafficHealth.probeWeight",
+        traffic.probe_weight as u64,
+        0,
+        100,
+    );
+    e.range_u64(
+        "trafficHealth.trafficWeight",
+        traffic.traffic_weight as u64,
+        0,
+        100,
+    );
+    if traffic.probe_weight + traffic.traffic_weight == 0 {
+        e.push(
+            "trafficHealth.probeWeight",
+            "探测与真实流量的权重不能同时为 0",
+        );
+    }
+    e.range_u64(
+        "trafficHealth.degradedPercent",
+        traffic.degraded_percent as u64,
+        1,
+        100,
+    );
+    e.range_u64(
+        "trafficHealth.failedPercent",
+        traffic.failed_percent as u64,
+        traffic.degraded_percent as u64,
+        100,
+    );
+    e.0
+}
+
+/// 校验已存储的流式检查配置 JSON 的结构（缺失字段、类型错误）
+pub fn validate_stream_check_config_value(value: &Value) -> Vec<FieldError> {
+    let mut e = Errors::default();
+    let Some(obj) = value.as_object() else {
+        e.push("", "必须是 JSON 对象");
+        return e.0;
+    };
+
+    for field in ["timeoutSecs", "maxRetries", "degradedThresholdMs"] {
+        match obj.get(field) {
+            None => e.push(field, "缺少字段"),
+            Some(v) if !v.is_u64() => e.push(field, format!("必须是非负整数（当前为 {v}）")),
+            _ => {}
+        }
+    }
+    for field in ["claudeModel", "codexModel", "geminiModel"] {
+        match obj.get(field) {
+            None => e.push(field, "缺少字段"),
+            Some(v) if !v.is_string() => e.push(field, format!("必须是字符串（当前为 {v}）")),
+            _ => {}
+        }
+    }
+    e.0
+}
+
+/// 校验应用级代理路由配置（重试、超时、熔断参数；请求超时为 0 表示不限制，
+/// 熔断超时为 0 表示打开后立即进入半开）
+pub fn validate_app_proxy_config(config: &AppProxyConfig) -> Vec<FieldError> {
+    let mut e = Errors::default();
+    if !matches!(config.app_type.as_str(), "claude" | "codex" | "gemini") {
+        e.push("appType", format!("不支持的应用类型: {}", config.app_type));
+    }
+    e.range_u64("maxRetries", config.max_retries as u64, 0, 10);
+    e.range_u64(
+        "streamingFirstByteTimeout",
+        config.streaming_first_byte_timeout as u64,
+        0,
+        600,
+    );
+    e.range_u64(
+        "streamingIdleTimeout",
+        config.streaming_idle_timeout as u64,
+        0,
+        3600,
+    );
+    e.range_u64(
+        "nonStreamingTimeout",
+        config.non_streaming_timeout as u64,
+        0,
+        3600,
+    );
+    e.range_u64(
+        "circuitFailureThreshold",
+        config.circuit_failure_threshold as u64,
+        1,
+        100,
+    );
+    e.range_u64(
+        "circuitSuccessThreshold",
+        config.circuit_success_threshold as u64,
+        1,
+        100,
+    );
+    e.range_u64(
+        "circuitTimeoutSeconds",
+        config.circuit_timeout_seconds as u64,
+        0,
+        86_400,
+    );
+    if !(0.0..=1.0).contains(&config.circuit_error_rate_threshold) {
+        e.push(
+            "circuitErrorRateThreshold",
+            format!(
+                "必须在 0 到 1 之间（当前为 {}）",
+                config.circuit_error_rate_threshold
+            ),
+        );
+    }
+    e.range_u64(
+        "circuitMinRequests",
+        config.circuit_min_requests as u64,
+        1,
+        10_000,
+    );
+    e.0
+}
+
+/// 校验全局代理配置（监听地址与端口）
+pub fn validate_global_proxy_config(config: &GlobalProxyConfig) -> Vec<FieldError> {
+    let mut e = Errors::default();
+    let address = config.listen_address.trim();
+    if address != "localhost" && address.parse::<std::net::IpAddr>().is_err() {
+        e.push(
+            "listenAddress",
+            format!("不是有效的 IP 地址: {}", config.listen_address),
+        );
+    }
+    if config.listen_port == 0 {
+        e.push("listenPort", "端口不能为 0");
+    }
+    e.0
+}
+
+/// 校验供应商定义的通用字段（应用相关的 settingsConfig 细节由 ProviderService 校验）
+pub fn validate_provider(provider: &Provider) -> Vec<FieldError> {
+    let mut e = Errors::default();
+    e.non_empty("id", &provider.id);
+    e.non_empty("name", &provider.name);
+    if !provider.settings_config.is_object() {
+        e.push("settingsConfig", "必须是 JSON 对象");
+    }
+    if let Some(url) = provider.website_url.as_deref().filter(|u| !u.is_empty()) {
+        // 允许省略协议（如 example.com）
+        let parsed = url::Url::parse(url).or_else(|_| url::Url::parse(&format!("https://{url}")));
+        if parsed.is_err() {
+            e.push("websiteUrl", format!("不是有效的 URL: {url}"));
+        }
+    }
+    if let Some(color) = provider.icon_color.as_deref().filter(|c| !c.is_empty()) {
+        let hex = color.strip_prefix('#').unwrap_or("");
+        if !matches!(hex.len(), 3 | 6) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            e.push(
+                "iconColor",
+                format!("必须是十六进制颜色（如 #00A67E）: {color}"),
+            );
+        }
+    }
+    if let Some(meta) = &provider.meta {
+        for (i, window) in meta.maintenance_windows.iter().enumerate() {
+            if let Err(message) = window.validate() {
+                e.push(&format!("meta.maintenanceWindows[{i}]"), message);
+            }
+        }
+        if let Some(Err(message)) = meta.latency_slo.as_ref().map(|slo| slo.validate()) {
+            e.push("meta.latencySlo", message);
+        }
+    }
+    if let Some(templates) = EndpointTemplates::of(provider) {
+        for (field, message) in templates.validate() {
+            e.push(&field, message);
+        }
+    }
+    if let Some(tls) = provider.meta.as_ref().and_then(|m| m.tls.as_ref()) {
+        if let Some(path) = tls
+            .ca_bundle_path
+            .as_deref()
+            .filter(|p| !p.trim().is_empty())
+        {
+            if !std::path::Path::new(path).is_file() {
+                e.push(
+                    "meta.tls.caBundlePath",
+                    format!("CA 证书文件不存在: {path}"),
+                );
+            }
+        }
+    }
+    e.0
+}
+
+/// 单个供应商的校验问题
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderValidationIssue {
+    pub provider_id: String,
+    pub provider_name: String,
+    pub errors: Vec<FieldError>,
+}
+
+/// 已存储配置的校验报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigValidationReport {
+    pub stream_check: Vec<FieldError>,
+    pub proxy: Vec<FieldError>,
+    pub providers: Vec<ProviderValidationIssue>,
+}
+
+/// 校验指定应用已存储的配置（加载时使用，不修改任何数据）
+pub async fn validate_stored(
+    db: &Database,
+    app_type: &str,
+) -> Result<ConfigValidationReport, AppError> {
+    let stream_check = match db.get_stream_check_config() {
+        Ok(config) => validate_stream_check_config(&config),
+        Err(AppError::Validation(errors)) => errors,
+        Err(e) => return Err(e),
+    };
+
+    let proxy = validate_app_proxy_config(&db.get_proxy_config_for_app(app_type).await?);
+
+    let providers = db
+        .get_all_providers(app_type)?
+        .values()
+        .filter_map(|p| {
+            let errors = validate_provider(p);
+            (!errors.is_empty()).then(|| ProviderValidationIssue {
+                provider_id: p.id.clone(),
+                provider_name: p.name.clone(),
+                errors,
+            })
+        })
+        .collect();
+
+    Ok(ConfigValidationReport {
+        stream_check,
+        proxy,
+        providers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn stream_check_config_ranges() {
+        assert!(validate_stream_check_config(&StreamCheckConfig::default()).is_empty());
+
+        let config = StreamCheckConfig {
+            timeout_secs: 0,
+            claude_model: " ".to_string(),
+            ..StreamCheckConfig::default()
+        };
+        let fields: Vec<_> = validate_stream_check_config(&config)
+            .into_iter()
+            .map(|e| e.field)
+            .collect();
+        assert!(fields.contains(&"timeoutSecs".to_string()));
+        assert!(fields.contains(&"claudeModel".to_string()));
+
+        let mut config = StreamCheckConfig::default();
+        config.traffic_health.probe_weight = 0;
+        config.traffic_health.traffic_weight = 0;
+        config.traffic_health.failed_percent = 5;
+        let fields: Vec<_> = validate_stream_check_config(&config)
+            .into_iter()
+            .map(|e| e.field)
+            .collect();
+        assert!(fields.contains(&"trafficHealth.probeWeight".to_string()));
+        assert!(fields.contains(&"trafficHealth.failedPercent".to_string()));
+    }
+
+    #[test]
+    fn stream_check_value_reports_missing_and_wrong_types() {
+        let value = json!({
+            "timeoutSecs": "45",
+            "maxRetries": 2,
+            "degradedThresholdMs": 6000,
+            "claudeModel": "a",
+            "codexModel": "b"
+        });
+        let errors = validate_stream_check_config_value(&value);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].field, "timeoutSecs");
+        assert_eq!(errors[1].field, "geminiModel");
+        assert_eq!(errors[1].message, "缺少字段");
+    }
+
+    #[test]
+    fn global_proxy_config_address() {
+        let mut config = GlobalProxyConfig {
+            proxy_enabled: true,
+            listen_address: "127.0.0.1".to_string(),
+            listen_port: 15721,
+            enable_logging: true,
+        };
+        assert!(validate_global_proxy_config(&config).is_empty());
+
+        config.listen_address = "not-an-ip".to_string();
+        config.listen_port = 0;
+        assert_eq!(validate_global_proxy_config(&config).len(), 2);
+    }
+}
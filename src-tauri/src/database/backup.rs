@@ -2,18 +2,18 @@
 //!
 //! 提供 SQL 导出/导入和二进制快照备份功能。
 
-use super::{lock_conn, Database, DB_BACKUP_RETAIN};
+use super::{encryption, lock_conn, Database, DB_BACKUP_RETAIN};
 use crate::config::get_app_config_dir;
 use crate::error::AppError;
 use chrono::Utc;
 use rusqlite::backup::Backup;
 use rusqlite::types::ValueRef;
-use rusqlite::Connection;
+use rusqlite::{Connection, DatabaseName};
 use std::fs;
 use std::path::{Path, PathBuf};
 use tempfile::NamedTempFile;
 
-const CC_SWITCH_SQL_EXPORT_HEADER: &str = "-- CC Switch SQLite 导出";
+pub(super) const CC_SWITCH_SQL_EXPORT_HEADER: &str = "-- CC Switch SQLite 导出";
 
 impl Database {
     /// 导出为 SQLite 兼容的 SQL 文本
@@ -50,8 +50,8 @@ impl Database {
             source: e,
         })?;
         let temp_path = temp_file.path().to_path_buf();
-        let temp_conn =
-            Connection::open(&temp_path).map_err(|e| AppError::Database(e.to_string()))?;
+        let temp_conn = Connection::open(&temp_path).map_err(AppError::from)?;
+        encryption::key_connection(&temp_conn)?;
 
         temp_conn
             .execute_batch(sql_content)
@@ -65,11 +65,8 @@ impl Database {
         // 使用 Backup 将临时库原子写回主库
         {
             let mut main_conn = lock_conn!(self.conn);
-            let backup = Backup::new(&temp_conn, &mut main_conn)
-                .map_err(|e| AppError::Database(e.to_string()))?;
-            backup
-                .step(-1)
-                .map_err(|e| AppError::Database(e.to_string()))?;
+            let backup = Backup::new(&temp_conn, &mut main_conn).map_err(AppError::from)?;
+            backup.step(-1).map_err(AppError::from)?;
         }
 
         let backup_id = backup_path
@@ -79,18 +76,44 @@ impl Database {
         Ok(backup_id)
     }
 
+    /// 将当前数据库完整复制到指定文件（用于迁移数据目录）
+    pub(crate) fn backup_to_file(&self, target_path: &Path) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut dest_conn = Connection::open(target_path).map_err(AppError::from)?;
+        encryption::key_connection(&dest_conn)?;
+        let backup = Backup::new(&conn, &mut dest_conn).map_err(AppError::from)?;
+        backup.step(-1).map_err(AppError::from)?;
+        Ok(())
+    }
+
     /// 创建内存快照以避免长时间持有数据库锁
     pub(crate) fn snapshot_to_memory(&self) -> Result<Connection, AppError> {
         let conn = lock_conn!(self.conn);
-        let mut snapshot =
-            Connection::open_in_memory().map_err(|e| AppError::Database(e.to_string()))?;
-
-        {
-            let backup =
-                Backup::new(&conn, &mut snapshot).map_err(|e| AppError::Database(e.to_string()))?;
-            backup
-                .step(-1)
-                .map_err(|e| AppError::Database(e.to_string()))?;
+        let mut snapshot = Connection::open_in_memory().map_err(AppError::from)?;
+
+        if encryption::is_encrypted() {
+            // SQLCipher 不允许加密库直接 Backup 到明文库：先导出到附加的内存库再复制
+            conn.execute_batch("ATTACH DATABASE ':memory:' AS cc_switch_snapshot KEY '';")
+                .map_err(AppError::from)?;
+            let copied = conn
+                .query_row("SELECT sqlcipher_export('cc_switch_snapshot')", [], |_| {
+                    Ok(())
+                })
+                .and_then(|_| {
+                    let backup = Backup::new_with_names(
+                        &conn,
+                        DatabaseName::Attached("cc_switch_snapshot"),
+                        &mut snapshot,
+                        DatabaseName::Main,
+                    )?;
+                    backup.step(-1)
+                });
+            conn.execute_batch("DETACH DATABASE cc_switch_snapshot;")
+                .map_err(AppError::from)?;
+            copied.map_err(AppError::from)?;
+        } else {
+            let backup = Backup::new(&conn, &mut snapshot).map_err(AppError::from)?;
+            backup.step(-1).map_err(AppError::from)?;
         }
 
         Ok(snapshot)
@@ -135,13 +158,10 @@ impl Database {
 
         {
             let conn = lock_conn!(self.conn);
-            let mut dest_conn =
-                Connection::open(&backup_path).map_err(|e| AppError::Database(e.to_string()))?;
-            let backup = Backup::new(&conn, &mut dest_conn)
-                .map_err(|e| AppError::Database(e.to_string()))?;
-            backup
-                .step(-1)
-                .map_err(|e| AppError::Database(e.to_string()))?;
+            let mut dest_conn = Connection::open(&backup_path).map_err(AppError::from)?;
+            encryption::key_connection(&dest_conn)?;
+            let backup = Backup::new(&conn, &mut dest_conn).map_err(AppError::from)?;
+            backup.step(-1).map_err(AppError::from)?;
         }
 
         Self::cleanup_db_backups(&backup_dir)?;
@@ -184,10 +204,10 @@ impl Database {
     fn validate_basic_state(conn: &Connection) -> Result<(), AppError> {
         let provider_count: i64 = conn
             .query_row("SELECT COUNT(*) FROM providers", [], |row| row.get(0))
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
         let mcp_count: i64 = conn
             .query_row("SELECT COUNT(*) FROM mcp_servers", [], |row| row.get(0))
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
         if provider_count == 0 && mcp_count == 0 {
             return Err(AppError::Config(
@@ -220,16 +240,14 @@ impl Database {
                  WHERE sql NOT NULL AND type IN ('table','index','trigger','view')
                  ORDER BY type='table' DESC, name",
             )
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
         let mut tables = Vec::new();
-        let mut rows = stmt
-            .query([])
-            .map_err(|e| AppError::Database(e.to_string()))?;
-        while let Some(row) = rows.next().map_err(|e| AppError::Database(e.to_string()))? {
-            let obj_type: String = row.get(0).map_err(|e| AppError::Database(e.to_string()))?;
-            let name: String = row.get(1).map_err(|e| AppError::Database(e.to_string()))?;
-            let sql: String = row.get(3).map_err(|e| AppError::Database(e.to_string()))?;
+        let mut rows = stmt.query([]).map_err(AppError::from)?;
+        while let Some(row) = rows.next().map_err(AppError::from)? {
+            let obj_type: String = row.get(0).map_err(AppError::from)?;
+            let name: String = row.get(1).map_err(AppError::from)?;
+            let sql: String = row.get(3).map_err(AppError::from)?;
 
             // 跳过 SQLite 内部对象（如 sqlite_sequence）
             if name.starts_with("sqlite_") {
@@ -253,17 +271,13 @@ impl Database {
 
             let mut stmt = conn
                 .prepare(&format!("SELECT * FROM \"{table}\""))
-                .map_err(|e| AppError::Database(e.to_string()))?;
-            let mut rows = stmt
-                .query([])
-                .map_err(|e| AppError::Database(e.to_string()))?;
+                .map_err(AppError::from)?;
+            let mut rows = stmt.query([]).map_err(AppError::from)?;
 
-            while let Some(row) = rows.next().map_err(|e| AppError::Database(e.to_string()))? {
+            while let Some(row) = rows.next().map_err(AppError::from)? {
                 let mut values = Vec::with_capacity(columns.len());
                 for idx in 0..columns.len() {
-                    let value = row
-                        .get_ref(idx)
-                        .map_err(|e| AppError::Database(e.to_string()))?;
+                    let value = row.get_ref(idx).map_err(AppError::from)?;
                     values.push(Self::format_sql_value(value)?);
                 }
 
@@ -284,23 +298,26 @@ impl Database {
     }
 
     /// 获取表的列名列表
-    fn get_table_columns(conn: &Connection, table: &str) -> Result<Vec<String>, AppError> {
+    pub(super) fn get_table_columns(
+        conn: &Connection,
+        table: &str,
+    ) -> Result<Vec<String>, AppError> {
         let mut stmt = conn
             .prepare(&format!("PRAGMA table_info(\"{table}\")"))
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
         let iter = stmt
             .query_map([], |row| row.get::<_, String>(1))
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
         let mut columns = Vec::new();
         for col in iter {
-            columns.push(col.map_err(|e| AppError::Database(e.to_string()))?);
+            columns.push(col.map_err(AppError::from)?);
         }
         Ok(columns)
     }
 
     /// 格式化 SQL 值
-    fn format_sql_value(value: ValueRef<'_>) -> Result<String, AppError> {
+    pub(super) fn format_sql_value(value: ValueRef<'_>) -> Result<String, AppError> {
         match value {
             ValueRef::Null => Ok("NULL".to_string()),
             ValueRef::Integer(i) => Ok(i.to_string()),
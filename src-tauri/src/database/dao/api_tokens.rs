@@ -0,0 +1,103 @@
+//! 管理 API 令牌 DAO
+//!
+//! 只保存令牌摘要；权限以逗号分隔保存。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::proxy::api_auth::{ApiScope, ApiToken};
+use rusqlite::{params, OptionalExtension, Row};
+
+fn row_to_token(row: &Row) -> rusqlite::Result<ApiToken> {
+    let scopes: String = row.get(2)?;
+    Ok(ApiToken {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        scopes: scopes
+            .split(',')
+            .filter_map(|s| s.parse::<ApiScope>().ok())
+            .collect(),
+        bootstrap: row.get(3)?,
+        created_at: row.get(4)?,
+        last_used_at: row.get(5)?,
+    })
+}
+
+impl Database {
+    /// 保存令牌
+    pub fn insert_api_token(&self, token: &ApiToken, token_hash: &str) -> Result<(), AppError> {
+        let scopes = token
+            .scopes
+            .iter()
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO api_tokens (id, name, token_hash, scopes, bootstrap, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                token.id,
+                token.name,
+                token_hash,
+                scopes,
+                token.bootstrap,
+                token.created_at
+            ],
+        )
+        .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    /// 全部令牌（按创建时间排序）
+    pub fn list_api_tokens(&self) -> Result<Vec<ApiToken>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, name, scopes, bootstrap, created_at, last_used_at
+                 FROM api_tokens ORDER BY created_at, id",
+            )
+            .map_err(AppError::from)?;
+        let rows = stmt.query_map([], row_to_token).map_err(AppError::from)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+
+    /// 按摘要查找令牌
+    pub fn find_api_token_by_hash(&self, token_hash: &str) -> Result<Option<ApiToken>, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row(
+            "SELECT id, name, scopes, bootstrap, created_at, last_used_at
+             FROM api_tokens WHERE token_hash = ?1",
+            params![token_hash],
+            row_to_token,
+        )
+        .optional()
+        .map_err(AppError::from)
+    }
+
+    /// 记录令牌最近使用时间
+    pub fn touch_api_token(&self, id: &str, used_at: i64) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "UPDATE api_tokens SET last_used_at = ?2 WHERE id = ?1",
+            params![id, used_at],
+        )
+        .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    /// 吊销令牌
+    pub fn delete_api_token(&self, id: &str) -> Result<bool, AppError> {
+        let conn = lock_conn!(self.conn);
+        let deleted = conn
+            .execute("DELETE FROM api_tokens WHERE id = ?1", params![id])
+            .map_err(AppError::from)?;
+        Ok(deleted > 0)
+    }
+
+    /// 吊销全部管理员引导令牌
+    pub fn delete_bootstrap_api_tokens(&self) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute("DELETE FROM api_tokens WHERE bootstrap = 1", [])
+            .map_err(AppError::from)
+    }
+}
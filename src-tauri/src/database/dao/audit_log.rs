@@ -0,0 +1,98 @@
+//! 审计日志 DAO
+//!
+//! 记录 cc-switch 在用户未直接操作时自动执行的修改（如修复被改写的 Live 配置），
+//! 便于事后确认发生了什么。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// 最多保留的审计记录数
+const MAX_AUDIT_ENTRIES: i64 = 1000;
+
+/// 审计记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: i64,
+    pub created_at: i64,
+    /// 操作类别，如 "codexAuthRepair"
+    pub category: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_id: Option<String>,
+    pub message: String,
+    /// 附加信息（不含凭据）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<Value>,
+}
+
+impl Database {
+    /// 写入一条审计记录，并清理超出上限的旧记录
+    pub fn record_audit(
+        &self,
+        category: &str,
+        app_type: Option<&str>,
+        provider_id: Option<&str>,
+        message: &str,
+        detail: Option<&Value>,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO audit_log (created_at, category, app_type, provider_id, message, detail)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                chrono::Utc::now().timestamp(),
+                category,
+                app_type,
+                provider_id,
+                message,
+                detail.map(Value::to_string)
+            ],
+        )
+        .map_err(AppError::from)?;
+        conn.execute(
+            "DELETE FROM audit_log WHERE id NOT IN (
+                SELECT id FROM audit_log ORDER BY id DESC LIMIT ?1
+             )",
+            params![MAX_AUDIT_ENTRIES],
+        )
+        .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    /// 最近的审计记录（按时间倒序），可按类别过滤
+    pub fn get_audit_log(
+        &self,
+        category: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<AuditEntry>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, created_at, category, app_type, provider_id, message, detail
+                 FROM audit_log
+                 WHERE ?1 IS NULL OR category = ?1
+                 ORDER BY id DESC LIMIT ?2",
+            )
+            .map_err(AppError::from)?;
+        let rows = stmt
+            .query_map(params![category, limit as i64], |row| {
+                let detail: Option<String> = row.get(6)?;
+                Ok(AuditEntry {
+                    id: row.get(0)?,
+                    created_at: row.get(1)?,
+                    category: row.get(2)?,
+                    app_type: row.get(3)?,
+                    provider_id: row.get(4)?,
+                    message: row.get(5)?,
+                    detail: detail.and_then(|text| serde_json::from_str(&text).ok()),
+                })
+            })
+            .map_err(AppError::from)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+}
@@ -0,0 +1,180 @@
+//! 后台任务 DAO
+//!
+//! 耗时命令以后台任务运行，进度与已产生的结果写入 background_tasks，
+//! 界面重新加载后可以按任务 ID 重新获取状态。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// 任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+    /// 应用退出时仍在运行
+    Interrupted,
+}
+
+impl TaskStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+            Self::Interrupted => "interrupted",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "completed" => Self::Completed,
+            "failed" => Self::Failed,
+            "cancelled" => Self::Cancelled,
+            "interrupted" => Self::Interrupted,
+            _ => Self::Running,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        *self != Self::Running
+    }
+}
+
+/// 后台任务
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackgroundTask {
+    pub id: String,
+    /// 任务类型（如 `stream-check-all`）
+    pub kind: String,
+    pub status: TaskStatus,
+    pub done: u32,
+    pub total: u32,
+    /// 已产生的部分结果（按产生顺序）
+    pub results: Vec<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+const TASK_COLUMNS: &str = "id, kind, status, done, total, results, error, created_at, updated_at";
+
+fn map_task_row(row: &rusqlite::Row) -> rusqlite::Result<BackgroundTask> {
+    let status: String = row.get(2)?;
+    let results: String = row.get(5)?;
+    Ok(BackgroundTask {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        status: TaskStatus::parse(&status),
+        done: row.get::<_, i64>(3)? as u32,
+        total: row.get::<_, i64>(4)? as u32,
+        results: serde_json::from_str(&results).unwrap_or_default(),
+        error: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+impl Database {
+    pub fn insert_background_task(&self, task: &BackgroundTask) -> Result<(), AppError> {
+        let results = serde_json::to_string(&task.results)
+            .map_err(|e| AppError::JsonSerialize { source: e })?;
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO background_tasks (id, kind, status, done, total, results, error,
+                created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                task.id,
+                task.kind,
+                task.status.as_str(),
+                task.done,
+                task.total,
+                results,
+                task.error,
+                task.created_at,
+                task.updated_at
+            ],
+        )
+        .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    /// 保存任务的进度、结果与状态
+    pub fn update_background_task(&self, task: &BackgroundTask) -> Result<(), AppError> {
+        let results = serde_json::to_string(&task.results)
+            .map_err(|e| AppError::JsonSerialize { source: e })?;
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "UPDATE background_tasks
+             SET status = ?2, done = ?3, total = ?4, results = ?5, error = ?6, updated_at = ?7
+             WHERE id = ?1",
+            params![
+                task.id,
+                task.status.as_str(),
+                task.done,
+                task.total,
+                results,
+                task.error,
+                task.updated_at
+            ],
+        )
+        .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    pub fn get_background_task(&self, id: &str) -> Result<Option<BackgroundTask>, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row(
+            &format!("SELECT {TASK_COLUMNS} FROM background_tasks WHERE id = ?1"),
+            params![id],
+            map_task_row,
+        )
+        .optional()
+        .map_err(AppError::from)
+    }
+
+    /// 最近的任务（按创建时间倒序）
+    pub fn list_background_tasks(&self, limit: u32) -> Result<Vec<BackgroundTask>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {TASK_COLUMNS} FROM background_tasks
+                 ORDER BY created_at DESC, id LIMIT ?1"
+            ))
+            .map_err(AppError::from)?;
+        let rows = stmt
+            .query_map(params![limit], map_task_row)
+            .map_err(AppError::from)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+
+    /// 启动时把上次退出时仍在运行的任务标记为中断，返回数量
+    pub fn mark_interrupted_background_tasks(&self, now: i64) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "UPDATE background_tasks SET status = 'interrupted', updated_at = ?1
+             WHERE status = 'running'",
+            params![now],
+        )
+        .map_err(AppError::from)
+    }
+
+    /// 删除 `before` 之前结束的任务
+    pub fn prune_background_tasks(&self, before: i64) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM background_tasks WHERE status != 'running' AND updated_at < ?1",
+            params![before],
+        )
+        .map_err(AppError::from)
+    }
+}
@@ -0,0 +1,142 @@
+//! 配置快照 DAO
+//!
+//! 只在配置发生变化时保存快照，每份快照同时保存与上一份的差异。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::services::config_snapshot::{ConfigChange, ConfigSnapshot, ConfigSnapshotEntry};
+use rusqlite::{params, OptionalExtension, Row};
+use serde_json::Value;
+
+const SNAPSHOT_COLUMNS: &str = "id, taken_at, content, changes";
+
+fn row_to_snapshot(row: &Row) -> rusqlite::Result<ConfigSnapshot> {
+    let content: String = row.get(2)?;
+    let changes: String = row.get(3)?;
+    Ok(ConfigSnapshot {
+        id: row.get(0)?,
+        taken_at: row.get(1)?,
+        content: serde_json::from_str(&content).unwrap_or(Value::Null),
+        changes: serde_json::from_str::<Vec<ConfigChange>>(&changes).unwrap_or_default(),
+    })
+}
+
+impl Database {
+    /// 保存一份快照，返回其 ID
+    pub fn insert_config_snapshot(
+        &self,
+        taken_at: i64,
+        content: &Value,
+        changes: &[ConfigChange],
+    ) -> Result<i64, AppError> {
+        let content =
+            serde_json::to_string(content).map_err(|e| AppError::JsonSerialize { source: e })?;
+        let changes_json =
+            serde_json::to_string(changes).map_err(|e| AppError::JsonSerialize { source: e })?;
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO config_snapshots (taken_at, content, changes, change_count)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![taken_at, content, changes_json, changes.len() as i64],
+        )
+        .map_err(AppError::from)?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 最新的快照
+    pub fn get_latest_config_snapshot(&self) -> Result<Option<ConfigSnapshot>, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row(
+            &format!("SELECT {SNAPSHOT_COLUMNS} FROM config_snapshots ORDER BY id DESC LIMIT 1"),
+            [],
+            row_to_snapshot,
+        )
+        .optional()
+        .map_err(AppError::from)
+    }
+
+    /// 不晚于 `at` 的最近一份快照
+    pub fn get_config_snapshot_at_or_before(
+        &self,
+        at: i64,
+    ) -> Result<Option<ConfigSnapshot>, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row(
+            &format!(
+                "SELECT {SNAPSHOT_COLUMNS} FROM config_snapshots
+                 WHERE taken_at <= ?1 ORDER BY taken_at DESC, id DESC LIMIT 1"
+            ),
+            params![at],
+            row_to_snapshot,
+        )
+        .optional()
+        .map_err(AppError::from)
+    }
+
+    /// 晚于 `at` 的最早一份快照
+    pub fn get_first_config_snapshot_after(
+        &self,
+        at: i64,
+    ) -> Result<Option<ConfigSnapshot>, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row(
+            &format!(
+                "SELECT {SNAPSHOT_COLUMNS} FROM config_snapshots
+                 WHERE taken_at > ?1 ORDER BY taken_at, id LIMIT 1"
+            ),
+            params![at],
+            row_to_snapshot,
+        )
+        .optional()
+        .map_err(AppError::from)
+    }
+
+    /// 按 ID 读取快照
+    pub fn get_config_snapshot(&self, id: i64) -> Result<Option<ConfigSnapshot>, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row(
+            &format!("SELECT {SNAPSHOT_COLUMNS} FROM config_snapshots WHERE id = ?1"),
+            params![id],
+            row_to_snapshot,
+        )
+        .optional()
+        .map_err(AppError::from)
+    }
+
+    /// 快照列表（按时间倒序），`since` 为空时不限制时间
+    pub fn list_config_snapshots(
+        &self,
+        since: Option<i64>,
+        limit: usize,
+    ) -> Result<Vec<ConfigSnapshotEntry>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, taken_at, change_count FROM config_snapshots
+                 WHERE (?1 IS NULL OR taken_at > ?1)
+                 ORDER BY taken_at DESC, id DESC LIMIT ?2",
+            )
+            .map_err(AppError::from)?;
+        let rows = stmt
+            .query_map(params![since, limit as i64], |row| {
+                Ok(ConfigSnapshotEntry {
+                    id: row.get(0)?,
+                    taken_at: row.get(1)?,
+                    change_count: row.get::<_, i64>(2)? as usize,
+                })
+            })
+            .map_err(AppError::from)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+
+    /// 删除 `before` 之前的快照（保留最新一份作为基线）
+    pub fn prune_config_snapshots(&self, before: i64) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM config_snapshots
+             WHERE taken_at < ?1 AND id != (SELECT MAX(id) FROM config_snapshots)",
+            params![before],
+        )
+        .map_err(AppError::from)
+    }
+}
@@ -28,7 +28,7 @@ impl Database {
                  WHERE app_type = ?1 AND in_failover_queue = 1
                  ORDER BY COALESCE(sort_index, 999999), id ASC",
             )
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
         let items = stmt
             .query_map([app_type], |row| {
@@ -38,9 +38,9 @@ impl Database {
                     sort_index: row.get(2)?,
                 })
             })
-            .map_err(|e| AppError::Database(e.to_string()))?
+            .map_err(AppError::from)?
             .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
         Ok(items)
     }
@@ -65,7 +65,7 @@ impl Database {
             "UPDATE providers SET in_failover_queue = 1 WHERE id = ?1 AND app_type = ?2",
             rusqlite::params![provider_id, app_type],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         Ok(())
     }
@@ -83,14 +83,14 @@ impl Database {
             "UPDATE providers SET in_failover_queue = 0 WHERE id = ?1 AND app_type = ?2",
             rusqlite::params![provider_id, app_type],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         // 2. 清除该供应商的健康状态（退出队列后不再需要健康监控）
         conn.execute(
             "DELETE FROM provider_health WHERE provider_id = ?1 AND app_type = ?2",
             rusqlite::params![provider_id, app_type],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         log::info!("已从故障转移队列移除供应商 {provider_id} ({app_type}), 并清除其健康状态");
 
@@ -105,7 +105,7 @@ impl Database {
             "UPDATE providers SET in_failover_queue = 0 WHERE app_type = ?1",
             [app_type],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         Ok(())
     }
@@ -0,0 +1,36 @@
+//! 延迟 SLO 统计 DAO
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::services::latency_slo::SloCounts;
+use rusqlite::params;
+
+impl Database {
+    /// 统计 `since` 之后某个供应商计入 SLO 的请求数与达标请求数
+    ///
+    /// 达标：2xx 且延迟低于阈值（流式请求按首 token 时间）。
+    /// 除 429 外的 4xx 多为请求本身的问题，不计入统计，也不消耗错误预算；回放请求同样不计入
+    pub fn get_slo_counts(
+        &self,
+        provider_id: &str,
+        app_type: &str,
+        since: i64,
+        threshold_ms: u64,
+    ) -> Result<SloCounts, AppError> {
+        let conn = lock_conn!(self.conn);
+        let (total, good) = conn.query_row(
+            "SELECT COUNT(*),
+                COALESCE(SUM(CASE WHEN status_code >= 200 AND status_code < 300
+                    AND COALESCE(first_token_ms, latency_ms) < ?4 THEN 1 ELSE 0 END), 0)
+             FROM proxy_request_logs
+             WHERE provider_id = ?1 AND app_type = ?2 AND created_at >= ?3 AND is_replay = 0
+                AND NOT (status_code >= 400 AND status_code < 500 AND status_code != 429)",
+            params![provider_id, app_type, since, threshold_ms as i64],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )?;
+        Ok(SloCounts {
+            total: total as u64,
+            good: good as u64,
+        })
+    }
+}
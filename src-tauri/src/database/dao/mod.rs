@@ -2,16 +2,44 @@
 //!
 //! Database access operations for each domain
 
+pub mod api_tokens;
+pub mod audit_log;
+pub mod background_tasks;
+pub mod config_snapshots;
 pub mod failover;
+pub mod latency_slo;
 pub mod mcp;
+pub mod model_audit;
+pub mod pending_jobs;
+pub mod probe_usage;
+pub mod prompt_comparisons;
 pub mod prompts;
+pub mod provider_usage;
 pub mod providers;
 pub mod proxy;
+pub mod queued_requests;
 pub mod settings;
+pub mod shadow;
 pub mod skills;
 pub mod stream_check;
+pub mod switch_history;
+pub mod timeline;
+pub mod tps_samples;
+pub mod transcripts;
 pub mod universal_providers;
+pub mod vault;
 
 // 所有 DAO 方法都通过 Database impl 提供，无需单独导出
 // 导出 FailoverQueueItem 供外部使用
+pub use audit_log::AuditEntry;
+pub use background_tasks::{BackgroundTask, TaskStatus};
 pub use failover::FailoverQueueItem;
+pub use model_audit::{SubstitutionEvidence, SubstitutionSuspect};
+pub use pending_jobs::{JobStatus, PendingJob};
+pub use provider_usage::{ProviderActivity, ProviderActivitySort};
+pub use queued_requests::{QueuedRequest, QueuedRequestStatus};
+pub use shadow::{ShadowComparison, ShadowSummary};
+pub use switch_history::RecentProvider;
+pub use timeline::{TimelineEntry, TimelineKind, TimelineQuery};
+pub use transcripts::Transcript;
+pub use vault::{VaultItem, VaultItemKind};
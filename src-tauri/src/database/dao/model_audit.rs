@@ -0,0 +1,236 @@
+//! 模型替换检测证据 DAO
+//!
+//! 每条记录是一次检测线索（模型不符、结束原因异常、速度异常），按供应商汇总后得到信任状态。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::proxy::model_audit::SUSPECT_THRESHOLD;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// 每个供应商最多保留的证据数
+const MAX_EVIDENCE_PER_PROVIDER: i64 = 200;
+
+/// 一条检测线索
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubstitutionEvidence {
+    #[serde(default)]
+    pub id: i64,
+    pub app_type: String,
+    pub provider_id: String,
+    /// model_mismatch / tier_downgrade / foreign_stop_reason / speed_outlier
+    pub signal: String,
+    pub requested_model: String,
+    /// 经模型映射后应得的模型
+    pub expected_model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reported_model: Option<String>,
+    pub detail: String,
+    pub created_at: i64,
+}
+
+/// 供应商的信任状态（统计窗口内的线索）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubstitutionSuspect {
+    pub app_type: String,
+    pub provider_id: String,
+    pub evidence_count: u64,
+    /// 出现过的线索类型
+    pub signals: Vec<String>,
+    /// 最近一次报告的非预期模型
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_reported_model: Option<String>,
+    pub last_seen_at: i64,
+    /// 线索数达到阈值，疑似替换模型
+    pub suspected: bool,
+}
+
+const SUSPECT_SELECT: &str = "SELECT app_type, provider_id, COUNT(*),
+        GROUP_CONCAT(DISTINCT signal), MAX(created_at),
+        (SELECT reported_model FROM model_substitution_evidence i
+         WHERE i.app_type = e.app_type AND i.provider_id = e.provider_id
+           AND i.reported_model IS NOT NULL
+         ORDER BY i.id DESC LIMIT 1)
+     FROM model_substitution_evidence e";
+
+fn suspect_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<SubstitutionSuspect> {
+    let evidence_count = row.get::<_, i64>(2)? as u64;
+    let mut signals: Vec<String> = row
+        .get::<_, Option<String>>(3)?
+        .unwrap_or_default()
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect();
+    signals.sort();
+    Ok(SubstitutionSuspect {
+        app_type: row.get(0)?,
+        provider_id: row.get(1)?,
+        evidence_count,
+        signals,
+        last_seen_at: row.get(4)?,
+        last_reported_model: row.get(5)?,
+        suspected: evidence_count >= SUSPECT_THRESHOLD,
+    })
+}
+
+impl Database {
+    /// 写入一条证据，并清理该供应商超出上限的旧记录
+    pub fn insert_substitution_evidence(
+        &self,
+        evidence: &SubstitutionEvidence,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO model_substitution_evidence
+             (app_type, provider_id, signal, requested_model, expected_model,
+              reported_model, detail, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                evidence.app_type,
+                evidence.provider_id,
+                evidence.signal,
+                evidence.requested_model,
+                evidence.expected_model,
+                evidence.reported_model,
+                evidence.detail,
+                evidence.created_at
+            ],
+        )
+        .map_err(AppError::from)?;
+        conn.execute(
+            "DELETE FROM model_substitution_evidence
+             WHERE app_type = ?1 AND provider_id = ?2 AND id NOT IN (
+                SELECT id FROM model_substitution_evidence
+                WHERE app_type = ?1 AND provider_id = ?2 ORDER BY id DESC LIMIT ?3
+             )",
+            params![
+                evidence.app_type,
+                evidence.provider_id,
+                MAX_EVIDENCE_PER_PROVIDER
+            ],
+        )
+        .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    /// 最近的证据（按时间倒序），`provider_id` 为空时返回该应用的全部供应商
+    pub fn get_substitution_evidence(
+        &self,
+        app_type: &str,
+        provider_id: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<SubstitutionEvidence>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, app_type, provider_id, signal, requested_model, expected_model,
+                        reported_model, detail, created_at
+                 FROM model_substitution_evidence
+                 WHERE app_type = ?1 AND (?2 IS NULL OR provider_id = ?2)
+                 ORDER BY id DESC LIMIT ?3",
+            )
+            .map_err(AppError::from)?;
+        let rows = stmt
+            .query_map(params![app_type, provider_id, limit as i64], |row| {
+                Ok(SubstitutionEvidence {
+                    id: row.get(0)?,
+                    app_type: row.get(1)?,
+                    provider_id: row.get(2)?,
+                    signal: row.get(3)?,
+                    requested_model: row.get(4)?,
+                    expected_model: row.get(5)?,
+                    reported_model: row.get(6)?,
+                    detail: row.get(7)?,
+                    created_at: row.get(8)?,
+                })
+            })
+            .map_err(AppError::from)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+
+    /// 按供应商汇总 `since` 之后的证据
+    pub fn get_substitution_suspects(
+        &self,
+        app_type: &str,
+        since: i64,
+    ) -> Result<Vec<SubstitutionSuspect>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let sql = format!(
+            "{SUSPECT_SELECT} WHERE app_type = ?1 AND created_at >= ?2
+             GROUP BY app_type, provider_id ORDER BY COUNT(*) DESC, provider_id"
+        );
+        let mut stmt = conn.prepare(&sql).map_err(AppError::from)?;
+        let rows = stmt
+            .query_map(params![app_type, since], suspect_from_row)
+            .map_err(AppError::from)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+
+    /// 单个供应商的信任状态（窗口内没有证据时为 None）
+    pub fn get_substitution_suspect(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        since: i64,
+    ) -> Result<Option<SubstitutionSuspect>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let sql = format!(
+            "{SUSPECT_SELECT} WHERE app_type = ?1 AND provider_id = ?2 AND created_at >= ?3
+             GROUP BY app_type, provider_id"
+        );
+        conn.query_row(
+            &sql,
+            params![app_type, provider_id, since],
+            suspect_from_row,
+        )
+        .optional()
+        .map_err(AppError::from)
+    }
+
+    /// 清除证据（`provider_id` 为空时清除该应用的全部证据）
+    pub fn clear_substitution_evidence(
+        &self,
+        app_type: &str,
+        provider_id: Option<&str>,
+    ) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM model_substitution_evidence
+             WHERE app_type = ?1 AND (?2 IS NULL OR provider_id = ?2)",
+            params![app_type, provider_id],
+        )
+        .map_err(AppError::from)
+    }
+
+    /// 其他供应商同一模型的平均流式输出速度（平均值, 样本数），没有样本时为 None
+    pub fn get_model_tps_baseline(
+        &self,
+        app_type: &str,
+        model: &str,
+        exclude_provider_id: &str,
+        min_output_tokens: u64,
+        since: i64,
+    ) -> Result<Option<(f64, u64)>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let (avg, samples): (Option<f64>, i64) = conn
+            .query_row(
+                "SELECT AVG(output_tps), COUNT(*) FROM proxy_request_logs
+                 WHERE app_type = ?1 AND model = ?2 AND provider_id != ?3
+                   AND output_tps IS NOT NULL AND output_tokens >= ?4 AND created_at >= ?5
+                   AND is_replay = 0",
+                params![
+                    app_type,
+                    model,
+                    exclude_provider_id,
+                    min_output_tokens as i64,
+                    since
+                ],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(AppError::from)?;
+        Ok(avg.map(|avg| (avg, samples as u64)))
+    }
+}
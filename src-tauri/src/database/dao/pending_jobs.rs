@@ -0,0 +1,182 @@
+//! 待投递任务 DAO
+//!
+//! 外发的 webhook 等任务先写入 pending_jobs，再由后台任务投递；
+//! 投递失败按退避时间重试，超过最大次数后标记为 dead，等待手动重试。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+/// 任务状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Pending,
+    Done,
+    /// 超过最大重试次数（死信）
+    Dead,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Done => "done",
+            Self::Dead => "dead",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "done" => Self::Done,
+            "dead" => Self::Dead,
+            _ => Self::Pending,
+        }
+    }
+}
+
+/// 待投递任务
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingJob {
+    pub id: i64,
+    pub kind: String,
+    pub payload: String,
+    pub status: JobStatus,
+    pub attempts: u32,
+    pub next_attempt_at: i64,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+const JOB_COLUMNS: &str = "id, kind, payload, status, attempts, next_attempt_at, last_error,
+     created_at, updated_at";
+
+fn map_job_row(row: &rusqlite::Row) -> rusqlite::Result<PendingJob> {
+    let status: String = row.get(3)?;
+    Ok(PendingJob {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        payload: row.get(2)?,
+        status: JobStatus::parse(&status),
+        attempts: row.get::<_, i64>(4)? as u32,
+        next_attempt_at: row.get(5)?,
+        last_error: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+impl Database {
+    /// 写入一个待投递任务，返回任务 ID
+    pub fn enqueue_job(&self, kind: &str, payload: &str, now: i64) -> Result<i64, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO pending_jobs (kind, payload, status, attempts, next_attempt_at,
+                created_at, updated_at)
+             VALUES (?1, ?2, 'pending', 0, ?3, ?3, ?3)",
+            params![kind, payload, now],
+        )
+        .map_err(AppError::from)?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 获取已到投递时间的任务（按计划时间排序）
+    pub fn get_due_jobs(&self, now: i64, limit: u32) -> Result<Vec<PendingJob>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {JOB_COLUMNS} FROM pending_jobs
+                 WHERE status = 'pending' AND next_attempt_at <= ?1
+                 ORDER BY next_attempt_at ASC, id ASC LIMIT ?2"
+            ))
+            .map_err(AppError::from)?;
+        let jobs = stmt
+            .query_map(params![now, limit], map_job_row)
+            .map_err(AppError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(AppError::from)?;
+        Ok(jobs)
+    }
+
+    /// 列出任务（status 为空时返回全部），最新的在前
+    pub fn list_jobs(
+        &self,
+        status: Option<JobStatus>,
+        limit: u32,
+    ) -> Result<Vec<PendingJob>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {JOB_COLUMNS} FROM pending_jobs
+                 WHERE (?1 IS NULL OR status = ?1)
+                 ORDER BY id DESC LIMIT ?2"
+            ))
+            .map_err(AppError::from)?;
+        let jobs = stmt
+            .query_map(params![status.map(|s| s.as_str()), limit], map_job_row)
+            .map_err(AppError::from)?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(AppError::from)?;
+        Ok(jobs)
+    }
+
+    /// 标记任务投递成功
+    pub fn mark_job_done(&self, id: i64, now: i64) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "UPDATE pending_jobs SET status = 'done', attempts = attempts + 1,
+                last_error = NULL, updated_at = ?2
+             WHERE id = ?1",
+            params![id, now],
+        )
+        .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    /// 记录一次投递失败：安排下次重试，或在 next_attempt_at 为空时标记为死信
+    pub fn mark_job_failed(
+        &self,
+        id: i64,
+        error: &str,
+        next_attempt_at: Option<i64>,
+        now: i64,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "UPDATE pending_jobs SET attempts = attempts + 1, last_error = ?2,
+                status = CASE WHEN ?3 IS NULL THEN 'dead' ELSE 'pending' END,
+                next_attempt_at = COALESCE(?3, next_attempt_at), updated_at = ?4
+             WHERE id = ?1",
+            params![id, error, next_attempt_at, now],
+        )
+        .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    /// 将死信任务重新放回队列（重置重试次数），返回任务是否存在
+    pub fn retry_job(&self, id: i64, now: i64) -> Result<bool, AppError> {
+        let conn = lock_conn!(self.conn);
+        let changed = conn
+            .execute(
+                "UPDATE pending_jobs SET status = 'pending', attempts = 0,
+                    next_attempt_at = ?2, updated_at = ?2
+                 WHERE id = ?1 AND status = 'dead'",
+                params![id, now],
+            )
+            .map_err(AppError::from)?;
+        Ok(changed > 0)
+    }
+
+    /// 清理早于 cutoff 的已完成任务，返回删除条数
+    pub fn prune_done_jobs(&self, cutoff: i64) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM pending_jobs WHERE status = 'done' AND updated_at < ?1",
+            params![cutoff],
+        )
+        .map_err(AppError::from)
+    }
+}
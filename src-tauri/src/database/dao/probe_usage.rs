@@ -0,0 +1,51 @@
+//! 健康检查 token 消耗 DAO
+//!
+//! 按供应商、按月累计自动探测消耗的 token，与请求日志的用量统计分开存储。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use rusqlite::params;
+
+impl Database {
+    /// 累加一次探测消耗
+    pub fn record_probe_usage(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        month: &str,
+        tokens: u64,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO probe_token_usage (app_type, provider_id, month, tokens, probes)
+             VALUES (?1, ?2, ?3, ?4, 1)
+             ON CONFLICT(app_type, provider_id, month) DO UPDATE SET
+                tokens = tokens + excluded.tokens,
+                probes = probes + 1",
+            params![app_type, provider_id, month, tokens as i64],
+        )
+        .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    /// 查询某月的探测消耗，返回（token 数, 探测次数）
+    pub fn get_probe_usage(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        month: &str,
+    ) -> Result<(u64, u64), AppError> {
+        let conn = lock_conn!(self.conn);
+        let row = conn.query_row(
+            "SELECT tokens, probes FROM probe_token_usage
+             WHERE app_type = ?1 AND provider_id = ?2 AND month = ?3",
+            params![app_type, provider_id, month],
+            |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64)),
+        );
+        match row {
+            Ok(usage) => Ok(usage),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok((0, 0)),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
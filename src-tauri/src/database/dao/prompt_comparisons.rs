@@ -0,0 +1,101 @@
+//! 多供应商提示词对比结果 DAO
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::services::prompt_compare::{PromptCompareOutput, PromptComparison};
+use rusqlite::{params, Row};
+
+/// 每个应用最多保留的对比记录数
+const MAX_COMPARISONS_PER_APP: i64 = 100;
+
+fn row_to_comparison(row: &Row) -> rusqlite::Result<PromptComparison> {
+    let outputs: String = row.get(5)?;
+    Ok(PromptComparison {
+        id: row.get(0)?,
+        app_type: row.get(1)?,
+        prompt: row.get(2)?,
+        system: row.get(3)?,
+        parallel: row.get(4)?,
+        outputs: serde_json::from_str::<Vec<PromptCompareOutput>>(&outputs).unwrap_or_default(),
+        created_at: row.get(6)?,
+    })
+}
+
+impl Database {
+    /// 保存一次对比，并清理超出上限的旧记录
+    pub fn save_prompt_comparison(&self, comparison: &PromptComparison) -> Result<(), AppError> {
+        let outputs = serde_json::to_string(&comparison.outputs)
+            .map_err(|e| AppError::JsonSerialize { source: e })?;
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT OR REPLACE INTO prompt_comparisons
+             (id, app_type, prompt, system_prompt, parallel, outputs, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                comparison.id,
+                comparison.app_type,
+                comparison.prompt,
+                comparison.system,
+                comparison.parallel,
+                outputs,
+                comparison.created_at
+            ],
+        )
+        .map_err(AppError::from)?;
+        conn.execute(
+            "DELETE FROM prompt_comparisons
+             WHERE app_type = ?1 AND id NOT IN (
+                SELECT id FROM prompt_comparisons
+                WHERE app_type = ?1 ORDER BY created_at DESC, rowid DESC LIMIT ?2
+             )",
+            params![comparison.app_type, MAX_COMPARISONS_PER_APP],
+        )
+        .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    /// 最近的对比记录（按时间倒序）
+    pub fn list_prompt_comparisons(
+        &self,
+        app_type: &str,
+        limit: usize,
+    ) -> Result<Vec<PromptComparison>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, app_type, prompt, system_prompt, parallel, outputs, created_at
+                 FROM prompt_comparisons WHERE app_type = ?1
+                 ORDER BY created_at DESC, rowid DESC LIMIT ?2",
+            )
+            .map_err(AppError::from)?;
+        let rows = stmt
+            .query_map(params![app_type, limit as i64], row_to_comparison)
+            .map_err(AppError::from)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+
+    /// 按 ID 读取对比记录
+    pub fn get_prompt_comparison(&self, id: &str) -> Result<Option<PromptComparison>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let row = conn.query_row(
+            "SELECT id, app_type, prompt, system_prompt, parallel, outputs, created_at
+             FROM prompt_comparisons WHERE id = ?1",
+            params![id],
+            row_to_comparison,
+        );
+        match row {
+            Ok(comparison) => Ok(Some(comparison)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 删除对比记录
+    pub fn delete_prompt_comparison(&self, id: &str) -> Result<bool, AppError> {
+        let conn = lock_conn!(self.conn);
+        let deleted = conn
+            .execute("DELETE FROM prompt_comparisons WHERE id = ?1", params![id])
+            .map_err(AppError::from)?;
+        Ok(deleted > 0)
+    }
+}
@@ -0,0 +1,125 @@
+//! 供应商累计用量 DAO
+//!
+//! 按供应商累计经代理成功请求的次数、token 数与最近使用时间。请求日志会按保留策略清理，
+//! 累计值不受影响，用于用量排行与闲置供应商识别。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+/// 排序方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProviderActivitySort {
+    /// 最近使用在前（从未使用的排在最后）
+    #[default]
+    Recent,
+    /// 累计 token 多的在前
+    Tokens,
+    /// 累计请求多的在前
+    Requests,
+}
+
+/// 供应商的使用情况
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderActivity {
+    pub app_type: String,
+    pub provider_id: String,
+    pub provider_name: String,
+    pub is_current: bool,
+    pub request_count: u64,
+    pub total_tokens: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_used_at: Option<i64>,
+    /// 最近一次经代理成功请求的时间（秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<i64>,
+    /// 距最近使用（从未使用时为距创建）的天数，创建时间未知时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idle_days: Option<i64>,
+    /// 闲置超过阈值，可考虑归档
+    pub idle: bool,
+}
+
+impl Database {
+    /// 累加一次成功请求
+    pub fn record_provider_usage(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        tokens: u64,
+        used_at: i64,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO provider_usage_totals
+             (app_type, provider_id, request_count, total_tokens, first_used_at, last_used_at)
+             VALUES (?1, ?2, 1, ?3, ?4, ?4)
+             ON CONFLICT(app_type, provider_id) DO UPDATE SET
+                request_count = request_count + 1,
+                total_tokens = total_tokens + excluded.total_tokens,
+                first_used_at = MIN(COALESCE(first_used_at, excluded.first_used_at), excluded.first_used_at),
+                last_used_at = MAX(COALESCE(last_used_at, excluded.last_used_at), excluded.last_used_at)",
+            params![app_type, provider_id, tokens as i64, used_at],
+        )
+        .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    /// 所有供应商的使用情况，闲置 `idle_days` 天以上的标记为闲置
+    pub fn get_provider_activity(
+        &self,
+        app_type: Option<&str>,
+        sort: ProviderActivitySort,
+        idle_days: u32,
+        now: i64,
+    ) -> Result<Vec<ProviderActivity>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT p.app_type, p.id, p.name, p.is_current, p.created_at,
+                        COALESCE(u.request_count, 0), COALESCE(u.total_tokens, 0),
+                        u.first_used_at, u.last_used_at
+                 FROM providers p
+                 LEFT JOIN provider_usage_totals u
+                   ON u.app_type = p.app_type AND u.provider_id = p.id
+                 WHERE ?1 IS NULL OR p.app_type = ?1",
+            )
+            .map_err(AppError::from)?;
+        let rows = stmt
+            .query_map(params![app_type], |row| {
+                let created_at_ms: Option<i64> = row.get(4)?;
+                let last_used_at: Option<i64> = row.get(8)?;
+                let idle_days_value = last_used_at
+                    .or(created_at_ms.map(|ms| ms / 1000))
+                    .map(|since| (now - since).max(0) / 86_400);
+                Ok(ProviderActivity {
+                    app_type: row.get(0)?,
+                    provider_id: row.get(1)?,
+                    provider_name: row.get(2)?,
+                    is_current: row.get(3)?,
+                    request_count: row.get::<_, i64>(5)? as u64,
+                    total_tokens: row.get::<_, i64>(6)? as u64,
+                    first_used_at: row.get(7)?,
+                    last_used_at,
+                    idle_days: idle_days_value,
+                    // 从未使用且创建时间未知：同样视为闲置
+                    idle: idle_days_value.is_none_or(|days| days >= i64::from(idle_days)),
+                })
+            })
+            .map_err(AppError::from)?;
+
+        let mut activity = rows
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(AppError::from)?;
+        match sort {
+            ProviderActivitySort::Recent => activity.sort_by_key(|a| Reverse(a.last_used_at)),
+            ProviderActivitySort::Tokens => activity.sort_by_key(|a| Reverse(a.total_tokens)),
+            ProviderActivitySort::Requests => activity.sort_by_key(|a| Reverse(a.request_count)),
+        }
+        Ok(activity)
+    }
+}
@@ -20,7 +20,7 @@ impl Database {
             "SELECT id, name, settings_config, website_url, category, created_at, sort_index, notes, icon, icon_color, meta, in_failover_queue
              FROM providers WHERE app_type = ?1
              ORDER BY COALESCE(sort_index, 999999), created_at ASC, id ASC"
-        ).map_err(|e| AppError::Database(e.to_string()))?;
+        ).map_err(AppError::from)?;
 
         let provider_iter = stmt
             .query_map(params![app_type], |row| {
@@ -59,17 +59,17 @@ impl Database {
                     },
                 ))
             })
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
         let mut providers = IndexMap::new();
         for provider_res in provider_iter {
-            let (id, mut provider) = provider_res.map_err(|e| AppError::Database(e.to_string()))?;
+            let (id, mut provider) = provider_res.map_err(AppError::from)?;
             provider.id = id.clone();
 
             // 加载 endpoints
             let mut stmt_endpoints = conn.prepare(
                 "SELECT url, added_at FROM provider_endpoints WHERE provider_id = ?1 AND app_type = ?2 ORDER BY added_at ASC, url ASC"
-            ).map_err(|e| AppError::Database(e.to_string()))?;
+            ).map_err(AppError::from)?;
 
             let endpoints_iter = stmt_endpoints
                 .query_map(params![id, app_type], |row| {
@@ -84,11 +84,11 @@ impl Database {
                         },
                     ))
                 })
-                .map_err(|e| AppError::Database(e.to_string()))?;
+                .map_err(AppError::from)?;
 
             let mut custom_endpoints = HashMap::new();
             for ep_res in endpoints_iter {
-                let (url, mut ep) = ep_res.map_err(|e| AppError::Database(e.to_string()))?;
+                let (url, mut ep) = ep_res.map_err(AppError::from)?;
                 ep.url = url.clone();
                 custom_endpoints.insert(url, ep);
             }
@@ -108,16 +108,12 @@ impl Database {
         let conn = lock_conn!(self.conn);
         let mut stmt = conn
             .prepare("SELECT id FROM providers WHERE app_type = ?1 AND is_current = 1 LIMIT 1")
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
-        let mut rows = stmt
-            .query(params![app_type])
-            .map_err(|e| AppError::Database(e.to_string()))?;
+        let mut rows = stmt.query(params![app_type]).map_err(AppError::from)?;
 
-        if let Some(row) = rows.next().map_err(|e| AppError::Database(e.to_string()))? {
-            Ok(Some(
-                row.get(0).map_err(|e| AppError::Database(e.to_string()))?,
-            ))
+        if let Some(row) = rows.next().map_err(AppError::from)? {
+            Ok(Some(row.get(0).map_err(AppError::from)?))
         } else {
             Ok(None)
         }
@@ -170,7 +166,7 @@ impl Database {
         match result {
             Ok(provider) => Ok(Some(provider)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(AppError::Database(e.to_string())),
+            Err(e) => Err(e.into()),
         }
     }
 
@@ -180,100 +176,20 @@ impl Database {
     /// （add_custom_endpoint / remove_custom_endpoint），避免覆盖用户的修改。
     pub fn save_provider(&self, app_type: &str, provider: &Provider) -> Result<(), AppError> {
         let mut conn = lock_conn!(self.conn);
-        let tx = conn
-            .transaction()
-            .map_err(|e| AppError::Database(e.to_string()))?;
-
-        // 处理 meta：取出 endpoints 以便单独处理
-        let mut meta_clone = provider.meta.clone().unwrap_or_default();
-        let endpoints = std::mem::take(&mut meta_clone.custom_endpoints);
-
-        // 检查是否存在（用于判断新增/更新，以及保留 is_current 和 in_failover_queue）
-        let existing: Option<(bool, bool)> = tx
-            .query_row(
-                "SELECT is_current, in_failover_queue FROM providers WHERE id = ?1 AND app_type = ?2",
-                params![provider.id, app_type],
-                |row| Ok((row.get(0)?, row.get(1)?)),
-            )
-            .ok();
-
-        let is_update = existing.is_some();
-        let (is_current, in_failover_queue) =
-            existing.unwrap_or((false, provider.in_failover_queue));
+        let tx = conn.transaction().map_err(AppError::from)?;
+        save_provider_in(&tx, app_type, provider)?;
+        tx.commit().map_err(AppError::from)?;
+        Ok(())
+    }
 
-        if is_update {
-            // 更新模式：使用 UPDATE 避免触发 ON DELETE CASCADE
-            tx.execute(
-                "UPDATE providers SET
-                    name = ?1,
-                    settings_config = ?2,
-                    website_url = ?3,
-                    category = ?4,
-                    created_at = ?5,
-                    sort_index = ?6,
-                    notes = ?7,
-                    icon = ?8,
-                    icon_color = ?9,
-                    meta = ?10,
-                    is_current = ?11,
-                    in_failover_queue = ?12
-                WHERE id = ?13 AND app_type = ?14",
-                params![
-                    provider.name,
-                    serde_json::to_string(&provider.settings_config).unwrap(),
-                    provider.website_url,
-                    provider.category,
-                    provider.created_at,
-                    provider.sort_index,
-                    provider.notes,
-                    provider.icon,
-                    provider.icon_color,
-                    serde_json::to_string(&meta_clone).unwrap(),
-                    is_current,
-                    in_failover_queue,
-                    provider.id,
-                    app_type,
-                ],
-            )
-            .map_err(|e| AppError::Database(e.to_string()))?;
-        } else {
-            // 新增模式：使用 INSERT
-            tx.execute(
-                "INSERT INTO providers (
-                    id, app_type, name, settings_config, website_url, category,
-                    created_at, sort_index, notes, icon, icon_color, meta, is_current, in_failover_queue
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
-                params![
-                    provider.id,
-                    app_type,
-                    provider.name,
-                    serde_json::to_string(&provider.settings_config).unwrap(),
-                    provider.website_url,
-                    provider.category,
-                    provider.created_at,
-                    provider.sort_index,
-                    provider.notes,
-                    provider.icon,
-                    provider.icon_color,
-                    serde_json::to_string(&meta_clone).unwrap(),
-                    is_current,
-                    in_failover_queue,
-                ],
-            )
-            .map_err(|e| AppError::Database(e.to_string()))?;
-
-            // 只有新增时才同步 endpoints
-            for (url, endpoint) in endpoints {
-                tx.execute(
-                    "INSERT INTO provider_endpoints (provider_id, app_type, url, added_at)
-                     VALUES (?1, ?2, ?3, ?4)",
-                    params![provider.id, app_type, url, endpoint.added_at],
-                )
-                .map_err(|e| AppError::Database(e.to_string()))?;
-            }
+    /// 在同一事务中保存多个供应商（`(app_type, provider)`），任一失败时全部回滚
+    pub fn save_providers(&self, providers: &[(String, Provider)]) -> Result<(), AppError> {
+        let mut conn = lock_conn!(self.conn);
+        let tx = conn.transaction().map_err(AppError::from)?;
+        for (app_type, provider) in providers {
+            save_provider_in(&tx, app_type, provider)?;
         }
-
-        tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
+        tx.commit().map_err(AppError::from)?;
         Ok(())
     }
 
@@ -284,32 +200,136 @@ impl Database {
             "DELETE FROM providers WHERE id = ?1 AND app_type = ?2",
             params![id, app_type],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
+        conn.execute(
+            "DELETE FROM provider_vault_items WHERE provider_id = ?1 AND app_type = ?2",
+            params![id, app_type],
+        )
+        .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    /// 合并重复供应商：把 `from_id` 的历史记录（请求日志、检查记录、吞吐量与探测用量、
+    /// 会话记录、附件、自定义端点、切换历史、影子对比、模型替换证据、时间线与审计日志）
+    /// 归入 `into_id`，然后删除 `from_id`
+    pub fn merge_provider_history(
+        &self,
+        app_type: &str,
+        from_id: &str,
+        into_id: &str,
+    ) -> Result<(), AppError> {
+        let mut conn = lock_conn!(self.conn);
+        let tx = conn.transaction().map_err(AppError::from)?;
+
+        for (table, column) in [
+            ("proxy_request_logs", "provider_id"),
+            ("stream_check_logs", "provider_id"),
+            ("proxy_transcripts", "provider_id"),
+            ("provider_vault_items", "provider_id"),
+            ("provider_switch_history", "provider_id"),
+            ("provider_switch_history", "previous_provider_id"),
+            ("shadow_comparisons", "primary_provider_id"),
+            ("shadow_comparisons", "shadow_provider_id"),
+            ("model_substitution_evidence", "provider_id"),
+            ("timeline_events", "provider_id"),
+            ("audit_log", "provider_id"),
+        ] {
+            tx.execute(
+                &format!("UPDATE {table} SET {column} = ?1 WHERE {column} = ?2 AND app_type = ?3"),
+                params![into_id, from_id, app_type],
+            )
+            .map_err(AppError::from)?;
+        }
+
+        // 按时间桶聚合的表：同一桶累加
+        tx.execute(
+            "INSERT INTO tps_samples (app_type, provider_id, bucket_start, output_tokens, request_count, active_ms)
+             SELECT app_type, ?1, bucket_start, output_tokens, request_count, active_ms
+             FROM tps_samples WHERE provider_id = ?2 AND app_type = ?3
+             ON CONFLICT(app_type, provider_id, bucket_start) DO UPDATE SET
+                output_tokens = output_tokens + excluded.output_tokens,
+                request_count = request_count + excluded.request_count,
+                active_ms = active_ms + excluded.active_ms",
+            params![into_id, from_id, app_type],
+        )
+        .map_err(AppError::from)?;
+        tx.execute(
+            "INSERT INTO probe_token_usage (app_type, provider_id, month, tokens, probes)
+             SELECT app_type, ?1, month, tokens, probes
+             FROM probe_token_usage WHERE provider_id = ?2 AND app_type = ?3
+             ON CONFLICT(app_type, provider_id, month) DO UPDATE SET
+                tokens = tokens + excluded.tokens,
+                probes = probes + excluded.probes",
+            params![into_id, from_id, app_type],
+        )
+        .map_err(AppError::from)?;
+
+        // 自定义端点：只迁移保留方还没有的地址
+        tx.execute(
+            "UPDATE provider_endpoints SET provider_id = ?1
+             WHERE provider_id = ?2 AND app_type = ?3
+               AND url NOT IN (SELECT url FROM provider_endpoints WHERE provider_id = ?1 AND app_type = ?3)",
+            params![into_id, from_id, app_type],
+        )
+        .map_err(AppError::from)?;
+
+        tx.execute(
+            "INSERT INTO provider_usage_totals
+                (app_type, provider_id, request_count, total_tokens, first_used_at, last_used_at)
+             SELECT app_type, ?1, request_count, total_tokens, first_used_at, last_used_at
+             FROM provider_usage_totals WHERE provider_id = ?2 AND app_type = ?3
+             ON CONFLICT(app_type, provider_id) DO UPDATE SET
+                request_count = request_count + excluded.request_count,
+                total_tokens = total_tokens + excluded.total_tokens,
+                first_used_at = MIN(COALESCE(first_used_at, excluded.first_used_at), COALESCE(excluded.first_used_at, first_used_at)),
+                last_used_at = MAX(COALESCE(last_used_at, excluded.last_used_at), COALESCE(excluded.last_used_at, last_used_at))",
+            params![into_id, from_id, app_type],
+        )
+        .map_err(AppError::from)?;
+
+        for table in [
+            "tps_samples",
+            "probe_token_usage",
+            "provider_usage_totals",
+            "provider_endpoints",
+            "provider_health",
+        ] {
+            tx.execute(
+                &format!("DELETE FROM {table} WHERE provider_id = ?1 AND app_type = ?2"),
+                params![from_id, app_type],
+            )
+            .map_err(AppError::from)?;
+        }
+        tx.execute(
+            "DELETE FROM providers WHERE id = ?1 AND app_type = ?2",
+            params![from_id, app_type],
+        )
+        .map_err(AppError::from)?;
+
+        tx.commit().map_err(AppError::from)?;
         Ok(())
     }
 
     /// 设置当前供应商
     pub fn set_current_provider(&self, app_type: &str, id: &str) -> Result<(), AppError> {
         let mut conn = lock_conn!(self.conn);
-        let tx = conn
-            .transaction()
-            .map_err(|e| AppError::Database(e.to_string()))?;
+        let tx = conn.transaction().map_err(AppError::from)?;
 
         // 重置所有为 0
         tx.execute(
             "UPDATE providers SET is_current = 0 WHERE app_type = ?1",
             params![app_type],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         // 设置新的当前供应商
         tx.execute(
             "UPDATE providers SET is_current = 1 WHERE id = ?1 AND app_type = ?2",
             params![id, app_type],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
-        tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
+        tx.commit().map_err(AppError::from)?;
         Ok(())
     }
 
@@ -329,7 +349,7 @@ impl Database {
                 app_type
             ],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
         Ok(())
     }
 
@@ -345,7 +365,7 @@ impl Database {
         conn.execute(
             "INSERT INTO provider_endpoints (provider_id, app_type, url, added_at) VALUES (?1, ?2, ?3, ?4)",
             params![provider_id, app_type, url, added_at],
-        ).map_err(|e| AppError::Database(e.to_string()))?;
+        ).map_err(AppError::from)?;
         Ok(())
     }
 
@@ -361,7 +381,103 @@ impl Database {
             "DELETE FROM provider_endpoints WHERE provider_id = ?1 AND app_type = ?2 AND url = ?3",
             params![provider_id, app_type, url],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
         Ok(())
     }
 }
+
+/// 在事务中新增或更新供应商（见 [`Database::save_provider`]）
+fn save_provider_in(
+    tx: &rusqlite::Transaction<'_>,
+    app_type: &str,
+    provider: &Provider,
+) -> Result<(), AppError> {
+    // 处理 meta：取出 endpoints 以便单独处理
+    let mut meta_clone = provider.meta.clone().unwrap_or_default();
+    let endpoints = std::mem::take(&mut meta_clone.custom_endpoints);
+
+    // 检查是否存在（用于判断新增/更新，以及保留 is_current 和 in_failover_queue）
+    let existing: Option<(bool, bool)> = tx
+        .query_row(
+            "SELECT is_current, in_failover_queue FROM providers WHERE id = ?1 AND app_type = ?2",
+            params![provider.id, app_type],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .ok();
+
+    let is_update = existing.is_some();
+    let (is_current, in_failover_queue) = existing.unwrap_or((false, provider.in_failover_queue));
+
+    if is_update {
+        // 更新模式：使用 UPDATE 避免触发 ON DELETE CASCADE
+        tx.execute(
+            "UPDATE providers SET
+                name = ?1,
+                settings_config = ?2,
+                website_url = ?3,
+                category = ?4,
+                created_at = ?5,
+                sort_index = ?6,
+                notes = ?7,
+                icon = ?8,
+                icon_color = ?9,
+                meta = ?10,
+                is_current = ?11,
+                in_failover_queue = ?12
+            WHERE id = ?13 AND app_type = ?14",
+            params![
+                provider.name,
+                serde_json::to_string(&provider.settings_config).unwrap(),
+                provider.website_url,
+                provider.category,
+                provider.created_at,
+                provider.sort_index,
+                provider.notes,
+                provider.icon,
+                provider.icon_color,
+                serde_json::to_string(&meta_clone).unwrap(),
+                is_current,
+                in_failover_queue,
+                provider.id,
+                app_type,
+            ],
+        )
+        .map_err(AppError::from)?;
+    } else {
+        // 新增模式：使用 INSERT
+        tx.execute(
+            "INSERT INTO providers (
+                id, app_type, name, settings_config, website_url, category,
+                created_at, sort_index, notes, icon, icon_color, meta, is_current, in_failover_queue
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                provider.id,
+                app_type,
+                provider.name,
+                serde_json::to_string(&provider.settings_config).unwrap(),
+                provider.website_url,
+                provider.category,
+                provider.created_at,
+                provider.sort_index,
+                provider.notes,
+                provider.icon,
+                provider.icon_color,
+                serde_json::to_string(&meta_clone).unwrap(),
+                is_current,
+                in_failover_queue,
+            ],
+        )
+        .map_err(AppError::from)?;
+
+        // 只有新增时才同步 endpoints
+        for (url, endpoint) in endpoints {
+            tx.execute(
+                "INSERT INTO provider_endpoints (provider_id, app_type, url, added_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![provider.id, app_type, url, endpoint.added_at],
+            )
+            .map_err(AppError::from)?;
+        }
+    }
+    Ok(())
+}
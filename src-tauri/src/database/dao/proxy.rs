@@ -2,6 +2,7 @@
 //!
 //! 处理代理配置、Provider健康状态和使用统计的数据库操作
 
+use crate::config_validation;
 use crate::error::AppError;
 use crate::proxy::types::*;
 
@@ -45,7 +46,7 @@ impl Database {
                     enable_logging: true,
                 })
             }
-            Err(e) => Err(AppError::Database(e.to_string())),
+            Err(e) => Err(e.into()),
         }
     }
 
@@ -54,6 +55,7 @@ impl Database {
         &self,
         config: GlobalProxyConfig,
     ) -> Result<(), AppError> {
+        config_validation::into_result(config_validation::validate_global_proxy_config(&config))?;
         let conn = lock_conn!(self.conn);
 
         conn.execute(
@@ -70,7 +72,7 @@ impl Database {
                 if config.enable_logging { 1 } else { 0 },
             ],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         Ok(())
     }
@@ -131,7 +133,7 @@ impl Database {
                     circuit_min_requests: 10,
                 })
             }
-            Err(e) => Err(AppError::Database(e.to_string())),
+            Err(e) => Err(e.into()),
         }
     }
 
@@ -140,6 +142,7 @@ impl Database {
         &self,
         config: AppProxyConfig,
     ) -> Result<(), AppError> {
+        config_validation::into_result(config_validation::validate_app_proxy_config(&config))?;
         let conn = lock_conn!(self.conn);
 
         conn.execute(
@@ -172,7 +175,7 @@ impl Database {
                 config.circuit_min_requests as i32,
             ],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         Ok(())
     }
@@ -186,7 +189,7 @@ impl Database {
                 "INSERT OR IGNORE INTO proxy_config (app_type) VALUES (?1)",
                 [app_type],
             )
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
         }
 
         Ok(())
@@ -229,7 +232,7 @@ impl Database {
                 self.init_proxy_config_rows().await?;
                 Ok(ProxyConfig::default())
             }
-            Err(e) => Err(AppError::Database(e.to_string())),
+            Err(e) => Err(e.into()),
         }
     }
 
@@ -258,7 +261,7 @@ impl Database {
                 config.non_streaming_timeout as i32,
             ],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         Ok(())
     }
@@ -281,7 +284,7 @@ impl Database {
                 [],
                 |row| row.get(0),
             )
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
         Ok(count > 0)
     }
 
@@ -330,7 +333,7 @@ impl Database {
                 last_error: None,
                 updated_at: chrono::Utc::now().to_rfc3339(),
             }),
-            Err(e) => Err(AppError::Database(e.to_string())),
+            Err(e) => Err(e.into()),
         }
     }
 
@@ -412,7 +415,7 @@ impl Database {
                 &now,
             ],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         Ok(())
     }
@@ -429,7 +432,7 @@ impl Database {
             "DELETE FROM provider_health WHERE provider_id = ?1 AND app_type = ?2",
             rusqlite::params![provider_id, app_type],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         log::debug!("Reset health status for provider {provider_id} (app: {app_type})");
 
@@ -444,7 +447,7 @@ impl Database {
             "DELETE FROM provider_health WHERE app_type = ?1",
             [app_type],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         log::debug!("Cleared provider health records for app {app_type}");
         Ok(())
@@ -455,7 +458,7 @@ impl Database {
         let conn = lock_conn!(self.conn);
 
         conn.execute("DELETE FROM provider_health", [])
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
         log::debug!("Cleared all provider health records");
         Ok(())
@@ -498,7 +501,7 @@ impl Database {
                 self.init_proxy_config_rows().await?;
                 Ok(crate::proxy::circuit_breaker::CircuitBreakerConfig::default())
             }
-            Err(e) => Err(AppError::Database(e.to_string())),
+            Err(e) => Err(e.into()),
         }
     }
 
@@ -529,7 +532,7 @@ impl Database {
                 config.min_requests as i32,
             ],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         Ok(())
     }
@@ -550,7 +553,7 @@ impl Database {
              VALUES (?1, ?2, ?3)",
             rusqlite::params![app_type, config_json, now],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         log::info!("已备份 {app_type} Live 配置");
         Ok(())
@@ -563,7 +566,7 @@ impl Database {
             .query_row("SELECT COUNT(*) FROM proxy_live_backup", [], |row| {
                 row.get(0)
             })
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
         Ok(count > 0)
     }
 
@@ -586,7 +589,7 @@ impl Database {
         match result {
             Ok(backup) => Ok(Some(backup)),
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(AppError::Database(e.to_string())),
+            Err(e) => Err(e.into()),
         }
     }
 
@@ -598,7 +601,7 @@ impl Database {
             "DELETE FROM proxy_live_backup WHERE app_type = ?1",
             rusqlite::params![app_type],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         log::info!("已删除 {app_type} Live 配置备份");
         Ok(())
@@ -609,7 +612,7 @@ impl Database {
         let conn = lock_conn!(self.conn);
 
         conn.execute("DELETE FROM proxy_live_backup", [])
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
         log::info!("已删除所有 Live 配置备份");
         Ok(())
@@ -0,0 +1,235 @@
+//! 持久化请求 DAO
+//!
+//! 非流式请求在转发前写入 queued_requests，收到响应后删除；
+//! 代理异常退出时残留的记录在下次启动时重新发送，结果保留供客户端按幂等键取回。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+/// 请求状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueuedRequestStatus {
+    /// 已接收，尚未得到响应
+    Pending,
+    /// 重启后正在重新发送
+    Resuming,
+    /// 重新发送完成（保存了响应）
+    Completed,
+    /// 重新发送失败
+    Failed,
+    /// 超过最长保留时间，未重新发送
+    Expired,
+}
+
+impl QueuedRequestStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Resuming => "resuming",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Expired => "expired",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "resuming" => Self::Resuming,
+            "completed" => Self::Completed,
+            "failed" => Self::Failed,
+            "expired" => Self::Expired,
+            _ => Self::Pending,
+        }
+    }
+}
+
+/// 持久化的请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedRequest {
+    /// 客户端提供的幂等键，未提供时为随机 ID
+    pub id: String,
+    pub endpoint: String,
+    /// 需要原样转发的请求头（不含认证信息）
+    pub headers: Vec<(String, String)>,
+    #[serde(skip_serializing)]
+    pub body: String,
+    pub status: QueuedRequestStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_body: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+const REQUEST_COLUMNS: &str =
+    "id, endpoint, headers, body, status, response_status, response_body, created_at, updated_at";
+
+fn map_request_row(row: &rusqlite::Row) -> rusqlite::Result<QueuedRequest> {
+    let headers: String = row.get(2)?;
+    let status: String = row.get(4)?;
+    Ok(QueuedRequest {
+        id: row.get(0)?,
+        endpoint: row.get(1)?,
+        headers: serde_json::from_str(&headers).unwrap_or_default(),
+        body: row.get(3)?,
+        status: QueuedRequestStatus::parse(&status),
+        response_status: row.get::<_, Option<i64>>(5)?.map(|s| s as u16),
+        response_body: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+impl Database {
+    /// 记录一个已接收的请求；幂等键已存在时返回 false
+    pub fn insert_queued_request(&self, request: &QueuedRequest) -> Result<bool, AppError> {
+        let headers = serde_json::to_string(&request.headers)
+            .map_err(|e| AppError::JsonSerialize { source: e })?;
+        let conn = lock_conn!(self.conn);
+        let inserted = conn
+            .execute(
+                "INSERT OR IGNORE INTO queued_requests (id, endpoint, headers, body, status,
+                    created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+                params![
+                    request.id,
+                    request.endpoint,
+                    headers,
+                    request.body,
+                    request.status.as_str(),
+                    request.created_at
+                ],
+            )
+            .map_err(AppError::from)?;
+        Ok(inserted > 0)
+    }
+
+    pub fn get_queued_request(&self, id: &str) -> Result<Option<QueuedRequest>, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row(
+            &format!("SELECT {REQUEST_COLUMNS} FROM queued_requests WHERE id = ?1"),
+            params![id],
+            map_request_row,
+        )
+        .optional()
+        .map_err(AppError::from)
+    }
+
+    /// 全部记录（按接收时间倒序）
+    pub fn list_queued_requests(&self) -> Result<Vec<QueuedRequest>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {REQUEST_COLUMNS} FROM queued_requests ORDER BY created_at DESC, id"
+            ))
+            .map_err(AppError::from)?;
+        let rows = stmt
+            .query_map([], map_request_row)
+            .map_err(AppError::from)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+
+    /// `before` 之前接收、仍未得到响应的请求（按接收顺序）
+    ///
+    /// 包含上次重新发送时中断的 resuming 记录。
+    pub fn get_unanswered_queued_requests(
+        &self,
+        before: i64,
+    ) -> Result<Vec<QueuedRequest>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {REQUEST_COLUMNS} FROM queued_requests
+                 WHERE status IN ('pending', 'resuming') AND created_at < ?1
+                 ORDER BY created_at, id"
+            ))
+            .map_err(AppError::from)?;
+        let rows = stmt
+            .query_map(params![before], map_request_row)
+            .map_err(AppError::from)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+
+    pub fn set_queued_request_status(
+        &self,
+        id: &str,
+        status: QueuedRequestStatus,
+        now: i64,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "UPDATE queued_requests SET status = ?2, updated_at = ?3 WHERE id = ?1",
+            params![id, status.as_str(), now],
+        )
+        .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    /// 保存重新发送的结果
+    pub fn finish_queued_request(
+        &self,
+        id: &str,
+        status: QueuedRequestStatus,
+        response_status: Option<u16>,
+        response_body: Option<&str>,
+        now: i64,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "UPDATE queued_requests
+             SET status = ?2, response_status = ?3, response_body = ?4, updated_at = ?5
+             WHERE id = ?1",
+            params![
+                id,
+                status.as_str(),
+                response_status.map(i64::from),
+                response_body,
+                now
+            ],
+        )
+        .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    pub fn delete_queued_request(&self, id: &str) -> Result<bool, AppError> {
+        let conn = lock_conn!(self.conn);
+        let deleted = conn
+            .execute("DELETE FROM queued_requests WHERE id = ?1", params![id])
+            .map_err(AppError::from)?;
+        Ok(deleted > 0)
+    }
+
+    /// 将 `cutoff` 之前接收、仍未得到响应的请求标记为过期，返回条数
+    pub fn expire_queued_requests(&self, cutoff: i64, now: i64) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "UPDATE queued_requests SET status = 'expired', updated_at = ?2
+             WHERE status IN ('pending', 'resuming') AND created_at < ?1",
+            params![cutoff, now],
+        )
+        .map_err(AppError::from)
+    }
+
+    /// 删除 `cutoff` 之前结束的记录
+    pub fn prune_queued_requests(&self, cutoff: i64) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM queued_requests
+             WHERE status IN ('completed', 'failed', 'expired') AND updated_at < ?1",
+            params![cutoff],
+        )
+        .map_err(AppError::from)
+    }
+
+    /// 清空全部记录
+    pub fn clear_queued_requests(&self) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute("DELETE FROM queued_requests", [])
+            .map_err(AppError::from)
+    }
+}
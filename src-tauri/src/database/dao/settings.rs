@@ -5,6 +5,43 @@
 use crate::database::{lock_conn, Database};
 use crate::error::AppError;
 use rusqlite::params;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// 带版本 JSON 设置中记录版本号的字段
+pub(crate) const SETTING_VERSION_KEY: &str = "_version";
+
+/// JSON 设置迁移步骤：`migrations[i]` 将 v{i} 迁移为 v{i+1}（无版本号的旧数据视为 v0）
+pub(crate) type JsonSettingMigration = fn(&mut Map<String, Value>);
+
+/// 依次执行迁移，并用默认值补齐缺失字段，返回是否有改动
+pub(crate) fn migrate_json_setting(
+    obj: &mut Map<String, Value>,
+    migrations: &[JsonSettingMigration],
+    defaults: &Value,
+) -> bool {
+    let version = obj
+        .remove(SETTING_VERSION_KEY)
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as usize;
+    let mut changed = version != migrations.len();
+
+    for migration in migrations.iter().skip(version) {
+        migration(obj);
+    }
+
+    // 新增字段取默认值，而不是让整个配置反序列化失败后被重置
+    if let Some(defaults) = defaults.as_object() {
+        for (key, value) in defaults {
+            if !obj.contains_key(key) {
+                obj.insert(key.clone(), value.clone());
+                changed = true;
+            }
+        }
+    }
+
+    changed
+}
 
 impl Database {
     /// 获取设置值
@@ -12,16 +49,12 @@ impl Database {
         let conn = lock_conn!(self.conn);
         let mut stmt = conn
             .prepare("SELECT value FROM settings WHERE key = ?1")
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
-        let mut rows = stmt
-            .query(params![key])
-            .map_err(|e| AppError::Database(e.to_string()))?;
+        let mut rows = stmt.query(params![key]).map_err(AppError::from)?;
 
-        if let Some(row) = rows.next().map_err(|e| AppError::Database(e.to_string()))? {
-            Ok(Some(
-                row.get(0).map_err(|e| AppError::Database(e.to_string()))?,
-            ))
+        if let Some(row) = rows.next().map_err(AppError::from)? {
+            Ok(Some(row.get(0).map_err(AppError::from)?))
         } else {
             Ok(None)
         }
@@ -34,10 +67,56 @@ impl Database {
             "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
             params![key, value],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
         Ok(())
     }
 
+    /// 读取带版本的 JSON 设置：按需迁移、补齐默认字段并写回，返回迁移后的 JSON
+    ///
+    /// 最终的类型反序列化由调用方完成，以便给出字段级错误。
+    pub(crate) fn load_versioned_setting<T: Serialize + Default>(
+        &self,
+        key: &str,
+        migrations: &[JsonSettingMigration],
+    ) -> Result<Option<Value>, AppError> {
+        let Some(raw) = self.get_setting(key)? else {
+            return Ok(None);
+        };
+        let mut value: Value = serde_json::from_str(&raw)
+            .map_err(|e| AppError::Message(format!("解析设置 {key} 失败: {e}")))?;
+        let Some(obj) = value.as_object_mut() else {
+            return Ok(Some(value));
+        };
+
+        let defaults = serde_json::to_value(T::default())
+            .map_err(|e| AppError::JsonSerialize { source: e })?;
+        if migrate_json_setting(obj, migrations, &defaults) {
+            log::info!("设置 {key} 已迁移到 v{}", migrations.len());
+            let mut stored = obj.clone();
+            stored.insert(SETTING_VERSION_KEY.to_string(), migrations.len().into());
+            let json = serde_json::to_string(&stored)
+                .map_err(|e| AppError::JsonSerialize { source: e })?;
+            self.set_setting(key, &json)?;
+        }
+
+        Ok(Some(value))
+    }
+
+    /// 写入带版本的 JSON 设置（附带当前版本号）
+    pub(crate) fn save_versioned_setting<T: Serialize>(
+        &self,
+        key: &str,
+        value: &T,
+        version: usize,
+    ) -> Result<(), AppError> {
+        let mut json =
+            serde_json::to_value(value).map_err(|e| AppError::JsonSerialize { source: e })?;
+        if let Some(obj) = json.as_object_mut() {
+            obj.insert(SETTING_VERSION_KEY.to_string(), version.into());
+        }
+        self.set_setting(key, &json.to_string())
+    }
+
     // --- Config Snippets 辅助方法 ---
 
     /// 获取通用配置片段
@@ -58,7 +137,7 @@ impl Database {
             // 如果为 None 则删除
             let conn = lock_conn!(self.conn);
             conn.execute("DELETE FROM settings WHERE key = ?1", params![key])
-                .map_err(|e| AppError::Database(e.to_string()))?;
+                .map_err(AppError::from)?;
             Ok(())
         }
     }
@@ -107,7 +186,7 @@ impl Database {
                 [],
                 |row| row.get(0),
             )
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
         Ok(count > 0)
     }
 
@@ -124,7 +203,7 @@ impl Database {
             "UPDATE settings SET value = 'false' WHERE key LIKE 'proxy_takeover_%'",
             [],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
         log::info!("已清除所有代理接管状态");
         Ok(())
     }
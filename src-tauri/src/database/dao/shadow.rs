@@ -0,0 +1,175 @@
+//! 影子镜像对比结果 DAO
+//!
+//! 每条记录对应一次被采样的请求：实际响应与影子供应商响应的延迟、输出 token 与文本相似度。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+/// 每个应用最多保留的对比记录数
+const MAX_COMPARISONS_PER_APP: i64 = 1000;
+
+/// 一次影子对比
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShadowComparison {
+    #[serde(default)]
+    pub id: i64,
+    pub app_type: String,
+    pub primary_provider_id: String,
+    pub shadow_provider_id: String,
+    pub model: String,
+    pub primary_status: u16,
+    /// 影子请求未拿到响应时为 0
+    pub shadow_status: u16,
+    pub primary_latency_ms: u64,
+    pub shadow_latency_ms: u64,
+    pub primary_output_tokens: u64,
+    pub shadow_output_tokens: u64,
+    /// 回复文本相似度（0~1）
+    pub similarity: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shadow_error: Option<String>,
+    pub created_at: i64,
+}
+
+/// 按影子供应商汇总的对比结果（平均值只统计影子请求成功的样本）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShadowSummary {
+    pub shadow_provider_id: String,
+    pub samples: u64,
+    pub shadow_failures: u64,
+    pub avg_primary_latency_ms: f64,
+    pub avg_shadow_latency_ms: f64,
+    pub avg_primary_output_tokens: f64,
+    pub avg_shadow_output_tokens: f64,
+    pub avg_similarity: f64,
+}
+
+impl Database {
+    /// 写入一条对比记录，并清理超出上限的旧记录
+    pub fn insert_shadow_comparison(&self, record: &ShadowComparison) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO shadow_comparisons
+             (app_type, primary_provider_id, shadow_provider_id, model, primary_status,
+              shadow_status, primary_latency_ms, shadow_latency_ms, primary_output_tokens,
+              shadow_output_tokens, similarity, shadow_error, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                record.app_type,
+                record.primary_provider_id,
+                record.shadow_provider_id,
+                record.model,
+                record.primary_status,
+                record.shadow_status,
+                record.primary_latency_ms as i64,
+                record.shadow_latency_ms as i64,
+                record.primary_output_tokens as i64,
+                record.shadow_output_tokens as i64,
+                record.similarity,
+                record.shadow_error,
+                record.created_at
+            ],
+        )
+        .map_err(AppError::from)?;
+        conn.execute(
+            "DELETE FROM shadow_comparisons
+             WHERE app_type = ?1 AND id NOT IN (
+                SELECT id FROM shadow_comparisons
+                WHERE app_type = ?1 ORDER BY id DESC LIMIT ?2
+             )",
+            params![record.app_type, MAX_COMPARISONS_PER_APP],
+        )
+        .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    /// 最近的对比记录（按时间倒序）
+    pub fn get_shadow_comparisons(
+        &self,
+        app_type: &str,
+        limit: usize,
+    ) -> Result<Vec<ShadowComparison>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, app_type, primary_provider_id, shadow_provider_id, model,
+                        primary_status, shadow_status, primary_latency_ms, shadow_latency_ms,
+                        primary_output_tokens, shadow_output_tokens, similarity, shadow_error,
+                        created_at
+                 FROM shadow_comparisons WHERE app_type = ?1
+                 ORDER BY id DESC LIMIT ?2",
+            )
+            .map_err(AppError::from)?;
+        let rows = stmt
+            .query_map(params![app_type, limit as i64], |row| {
+                Ok(ShadowComparison {
+                    id: row.get(0)?,
+                    app_type: row.get(1)?,
+                    primary_provider_id: row.get(2)?,
+                    shadow_provider_id: row.get(3)?,
+                    model: row.get(4)?,
+                    primary_status: row.get(5)?,
+                    shadow_status: row.get(6)?,
+                    primary_latency_ms: row.get::<_, i64>(7)? as u64,
+                    shadow_latency_ms: row.get::<_, i64>(8)? as u64,
+                    primary_output_tokens: row.get::<_, i64>(9)? as u64,
+                    shadow_output_tokens: row.get::<_, i64>(10)? as u64,
+                    similarity: row.get(11)?,
+                    shadow_error: row.get(12)?,
+                    created_at: row.get(13)?,
+                })
+            })
+            .map_err(AppError::from)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+
+    /// 按影子供应商汇总对比结果
+    pub fn get_shadow_summary(&self, app_type: &str) -> Result<Vec<ShadowSummary>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT shadow_provider_id, COUNT(*),
+                        SUM(CASE WHEN ok THEN 0 ELSE 1 END),
+                        COALESCE(AVG(CASE WHEN ok THEN primary_latency_ms END), 0),
+                        COALESCE(AVG(CASE WHEN ok THEN shadow_latency_ms END), 0),
+                        COALESCE(AVG(CASE WHEN ok THEN primary_output_tokens END), 0),
+                        COALESCE(AVG(CASE WHEN ok THEN shadow_output_tokens END), 0),
+                        COALESCE(AVG(CASE WHEN ok THEN similarity END), 0)
+                 FROM (
+                    SELECT *, (shadow_error IS NULL AND shadow_status BETWEEN 200 AND 299) AS ok
+                    FROM shadow_comparisons WHERE app_type = ?1
+                 )
+                 GROUP BY shadow_provider_id ORDER BY shadow_provider_id",
+            )
+            .map_err(AppError::from)?;
+        let rows = stmt
+            .query_map(params![app_type], |row| {
+                Ok(ShadowSummary {
+                    shadow_provider_id: row.get(0)?,
+                    samples: row.get::<_, i64>(1)? as u64,
+                    shadow_failures: row.get::<_, i64>(2)? as u64,
+                    avg_primary_latency_ms: row.get(3)?,
+                    avg_shadow_latency_ms: row.get(4)?,
+                    avg_primary_output_tokens: row.get(5)?,
+                    avg_shadow_output_tokens: row.get(6)?,
+                    avg_similarity: row.get(7)?,
+                })
+            })
+            .map_err(AppError::from)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+
+    /// 清空指定应用的对比记录
+    pub fn clear_shadow_comparisons(&self, app_type: &str) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM shadow_comparisons WHERE app_type = ?1",
+            params![app_type],
+        )
+        .map_err(AppError::from)
+    }
+}
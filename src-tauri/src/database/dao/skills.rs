@@ -14,7 +14,7 @@ impl Database {
         let conn = lock_conn!(self.conn);
         let mut stmt = conn
             .prepare("SELECT directory, app_type, installed, installed_at FROM skills ORDER BY directory ASC, app_type ASC")
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
         let skill_iter = stmt
             .query_map([], |row| {
@@ -37,11 +37,11 @@ impl Database {
                     },
                 ))
             })
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
         let mut skills = IndexMap::new();
         for skill_res in skill_iter {
-            let (key, skill) = skill_res.map_err(|e| AppError::Database(e.to_string()))?;
+            let (key, skill) = skill_res.map_err(AppError::from)?;
             skills.insert(key, skill);
         }
         Ok(skills)
@@ -64,7 +64,7 @@ impl Database {
             "INSERT OR REPLACE INTO skills (directory, app_type, installed, installed_at) VALUES (?1, ?2, ?3, ?4)",
             params![directory, app_type, state.installed, state.installed_at.timestamp()],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
         Ok(())
     }
 
@@ -75,7 +75,7 @@ impl Database {
             .prepare(
                 "SELECT owner, name, branch, enabled FROM skill_repos ORDER BY owner ASC, name ASC",
             )
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
         let repo_iter = stmt
             .query_map([], |row| {
@@ -86,11 +86,11 @@ impl Database {
                     enabled: row.get(3)?,
                 })
             })
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
         let mut repos = Vec::new();
         for repo_res in repo_iter {
-            repos.push(repo_res.map_err(|e| AppError::Database(e.to_string()))?);
+            repos.push(repo_res.map_err(AppError::from)?);
         }
         Ok(repos)
     }
@@ -101,7 +101,7 @@ impl Database {
         conn.execute(
             "INSERT OR REPLACE INTO skill_repos (owner, name, branch, enabled) VALUES (?1, ?2, ?3, ?4)",
             params![repo.owner, repo.name, repo.branch, repo.enabled],
-        ).map_err(|e| AppError::Database(e.to_string()))?;
+        ).map_err(AppError::from)?;
         Ok(())
     }
 
@@ -112,7 +112,7 @@ impl Database {
             "DELETE FROM skill_repos WHERE owner = ?1 AND name = ?2",
             params![owner, name],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
         Ok(())
     }
 
@@ -1,11 +1,37 @@
 //! 流式健康检查日志 DAO
 
+use super::settings::JsonSettingMigration;
+use crate::config_validation;
 use crate::database::{lock_conn, Database};
 use crate::error::AppError;
-use crate::services::stream_check::{HealthStatus, StreamCheckConfig, StreamCheckResult};
+use crate::proxy::upstream_hint::UpstreamErrorCode;
+use crate::services::stream_check::{
+    HealthStatus, StreamCheckAttempt, StreamCheckConfig, StreamCheckResult,
+};
+
+const STREAM_CHECK_CONFIG_KEY: &str = "stream_check_config";
+
+/// stream_check_config 迁移链（新增字段无需迁移，会自动取默认值；重命名/改类型时在此追加）
+const STREAM_CHECK_CONFIG_MIGRATIONS: &[JsonSettingMigration] = &[
+    // v0 -> v1：兼容以 snake_case 字段名写入的数据
+    |obj| {
+        for (old, new) in [
+            ("timeout_secs", "timeoutSecs"),
+            ("max_retries", "maxRetries"),
+            ("degraded_threshold_ms", "degradedThresholdMs"),
+            ("claude_model", "claudeModel"),
+            ("codex_model", "codexModel"),
+            ("gemini_model", "geminiModel"),
+        ] {
+            if let Some(v) = obj.remove(old) {
+                obj.entry(new).or_insert(v);
+            }
+        }
+    },
+];
 
 impl Database {
-    /// 保存流式检查日志
+    /// 保存流式检查日志（发生重试时同时保存每次尝试的结果）
     pub fn save_stream_check_log(
         &self,
         provider_id: &str,
@@ -18,8 +44,8 @@ impl Database {
         conn.execute(
             "INSERT INTO stream_check_logs 
              (provider_id, provider_name, app_type, status, success, message, 
-              response_time_ms, http_status, model_used, retry_count, tested_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+              response_time_ms, http_status, model_used, retry_count, tested_at, endpoint)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
             rusqlite::params![
                 provider_id,
                 provider_name,
@@ -32,136 +58,183 @@ impl Database {
                 result.model_used,
                 result.retry_count as i64,
                 result.tested_at,
+                result.endpoint,
             ],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
+        let log_id = conn.last_insert_rowid();
+
+        for attempt in &result.attempts {
+            conn.execute(
+                "INSERT INTO stream_check_attempts
+                 (log_id, attempt, success, http_status, message, response_time_ms, tested_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                rusqlite::params![
+                    log_id,
+                    attempt.attempt as i64,
+                    attempt.success,
+                    attempt.http_status.map(|s| s as i64),
+                    attempt.message,
+                    attempt.response_time_ms.map(|t| t as i64),
+                    attempt.tested_at,
+                ],
+            )
+            .map_err(AppError::from)?;
+        }
 
-        Ok(conn.last_insert_rowid())
+        Ok(log_id)
     }
 
     /// 获取流式检查配置
     pub fn get_stream_check_config(&self) -> Result<StreamCheckConfig, AppError> {
-        match self.get_setting("stream_check_config")? {
-            Some(json) => serde_json::from_str(&json)
-                .map_err(|e| AppError::Message(format!("解析配置失败: {e}"))),
+        let value = self.load_versioned_setting::<StreamCheckConfig>(
+            STREAM_CHECK_CONFIG_KEY,
+            STREAM_CHECK_CONFIG_MIGRATIONS,
+        )?;
+        match value {
+            Some(value) => serde_json::from_value(value.clone()).map_err(|e| {
+                // 尽量给出具体字段的错误
+                let errors = config_validation::validate_stream_check_config_value(&value);
+                if errors.is_empty() {
+                    AppError::Message(format!("解析配置失败: {e}"))
+                } else {
+                    AppError::Validation(errors)
+                }
+            }),
             None => Ok(StreamCheckConfig::default()),
         }
     }
 
     /// 保存流式检查配置
     pub fn save_stream_check_config(&self, config: &StreamCheckConfig) -> Result<(), AppError> {
-        let json = serde_json::to_string(config)
-            .map_err(|e| AppError::Message(format!("序列化配置失败: {e}")))?;
-        self.set_setting("stream_check_config", &json)
+        config_validation::into_result(config_validation::validate_stream_check_config(config))?;
+        self.save_versioned_setting(
+            STREAM_CHECK_CONFIG_KEY,
+            config,
+            STREAM_CHECK_CONFIG_MIGRATIONS.len(),
+        )
     }
 
-    /// 获取某个 Provider 最近一次流式检查结果（来自日志）
+    /// 获取某个 Provider 最近一次流式检查结果（来自日志，可按模型筛选，不含已取消或跳过的检查）
     pub fn get_stream_check_latest(
         &self,
         provider_id: &str,
         app_type: &str,
+        model: Option<&str>,
     ) -> Result<Option<StreamCheckResult>, AppError> {
         let conn = lock_conn!(self.conn);
 
         let row = conn.query_row(
-            "SELECT status, success, message, response_time_ms, http_status, model_used, retry_count, tested_at
+            "SELECT status, success, message, response_time_ms, http_status, model_used, retry_count, tested_at, endpoint, id
              FROM stream_check_logs
-             WHERE provider_id = ?1 AND app_type = ?2
+             WHERE provider_id = ?1 AND app_type = ?2 AND (?3 IS NULL OR model_used = ?3)
+               AND status NOT IN ('cancelled', 'skipped')
              ORDER BY tested_at DESC, id DESC
              LIMIT 1",
-            rusqlite::params![provider_id, app_type],
-            |row| {
-                let status_str: String = row.get(0)?;
-                let success: bool = row.get(1)?;
-                let message: String = row.get(2)?;
-                let response_time_ms: Option<i64> = row.get(3)?;
-                let http_status: Option<i64> = row.get(4)?;
-                let model_used: Option<String> = row.get(5)?;
-                let retry_count: Option<i64> = row.get(6)?;
-                let tested_at: i64 = row.get(7)?;
-
-                let status = match status_str.as_str() {
-                    "operational" => HealthStatus::Operational,
-                    "degraded" => HealthStatus::Degraded,
-                    _ => HealthStatus::Failed,
-                };
-
-                Ok(StreamCheckResult {
-                    status,
-                    success,
-                    message,
-                    response_time_ms: response_time_ms.map(|v| v as u64),
-                    http_status: http_status.map(|v| v as u16),
-                    model_used: model_used.unwrap_or_default(),
-                    tested_at,
-                    retry_count: retry_count.unwrap_or(0) as u32,
-                })
-            },
+            rusqlite::params![provider_id, app_type, model],
+            |row| Ok((map_stream_check_row(row)?, row.get::<_, i64>(9)?)),
         );
 
-        match row {
-            Ok(r) => Ok(Some(r)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(AppError::Database(e.to_string())),
+        let (mut result, log_id) = match row {
+            Ok(r) => r,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        // 最近一次结果附带每次尝试的明细，便于排查重试原因
+        let mut stmt = conn
+            .prepare(
+                "SELECT attempt, success, http_status, message, response_time_ms, tested_at
+                 FROM stream_check_attempts WHERE log_id = ?1 ORDER BY attempt",
+            )
+            .map_err(AppError::from)?;
+        let attempts = stmt
+            .query_map([log_id], |row| {
+                let http_status: Option<i64> = row.get(2)?;
+                let response_time_ms: Option<i64> = row.get(4)?;
+                Ok(StreamCheckAttempt {
+                    attempt: row.get::<_, i64>(0)? as u32,
+                    success: row.get(1)?,
+                    http_status: http_status.map(|v| v as u16),
+                    message: row.get(3)?,
+                    response_time_ms: response_time_ms.map(|v| v as u64),
+                    tested_at: row.get(5)?,
+                })
+            })
+            .map_err(AppError::from)?;
+        for attempt in attempts {
+            result.attempts.push(attempt.map_err(AppError::from)?);
         }
+
+        Ok(Some(result))
     }
 
-    /// 获取某个 Provider 最近 N 次流式检查结果（来自日志，按时间倒序）
+    /// 获取某个 Provider 最近 N 次流式检查结果（来自日志，按时间倒序，可按模型筛选）
     pub fn get_stream_check_history(
         &self,
         provider_id: &str,
         app_type: &str,
+        model: Option<&str>,
         limit: u32,
     ) -> Result<Vec<StreamCheckResult>, AppError> {
         let conn = lock_conn!(self.conn);
         let mut stmt = conn
             .prepare(
-                "SELECT status, success, message, response_time_ms, http_status, model_used, retry_count, tested_at
+                "SELECT status, success, message, response_time_ms, http_status, model_used, retry_count, tested_at, endpoint
                  FROM stream_check_logs
-                 WHERE provider_id = ?1 AND app_type = ?2
+                 WHERE provider_id = ?1 AND app_type = ?2 AND (?3 IS NULL OR model_used = ?3)
                  ORDER BY tested_at DESC, id DESC
-                 LIMIT ?3",
+                 LIMIT ?4",
             )
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
         let rows = stmt
             .query_map(
-                rusqlite::params![provider_id, app_type, limit as i64],
-                |row| {
-                    let status_str: String = row.get(0)?;
-                    let success: bool = row.get(1)?;
-                    let message: String = row.get(2)?;
-                    let response_time_ms: Option<i64> = row.get(3)?;
-                    let http_status: Option<i64> = row.get(4)?;
-                    let model_used: Option<String> = row.get(5)?;
-                    let retry_count: Option<i64> = row.get(6)?;
-                    let tested_at: i64 = row.get(7)?;
-
-                    let status = match status_str.as_str() {
-                        "operational" => HealthStatus::Operational,
-                        "degraded" => HealthStatus::Degraded,
-                        _ => HealthStatus::Failed,
-                    };
-
-                    Ok(StreamCheckResult {
-                        status,
-                        success,
-                        message,
-                        response_time_ms: response_time_ms.map(|v| v as u64),
-                        http_status: http_status.map(|v| v as u16),
-                        model_used: model_used.unwrap_or_default(),
-                        tested_at,
-                        retry_count: retry_count.unwrap_or(0) as u32,
-                    })
-                },
+                rusqlite::params![provider_id, app_type, model, limit as i64],
+                map_stream_check_row,
             )
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
         let mut results = Vec::new();
         for row in rows {
-            results.push(row.map_err(|e| AppError::Database(e.to_string()))?);
+            results.push(row.map_err(AppError::from)?);
         }
 
         Ok(results)
     }
 }
+
+/// 将 stream_check_logs 查询行映射为检查结果（列顺序见上方查询）
+fn map_stream_check_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<StreamCheckResult> {
+    let status_str: String = row.get(0)?;
+    let response_time_ms: Option<i64> = row.get(3)?;
+    let http_status: Option<i64> = row.get(4)?;
+    let model_used: Option<String> = row.get(5)?;
+    let retry_count: Option<i64> = row.get(6)?;
+    let endpoint: Option<String> = row.get(8)?;
+
+    let status = match status_str.as_str() {
+        "operational" => HealthStatus::Operational,
+        "degraded" => HealthStatus::Degraded,
+        "cancelled" => HealthStatus::Cancelled,
+        "skipped" => HealthStatus::Skipped,
+        _ => HealthStatus::Failed,
+    };
+
+    let message: String = row.get(2)?;
+
+    Ok(StreamCheckResult {
+        status,
+        success: row.get(1)?,
+        error_code: UpstreamErrorCode::from_message(&message),
+        message,
+        response_time_ms: response_time_ms.map(|v| v as u64),
+        http_status: http_status.map(|v| v as u16),
+        model_used: model_used.unwrap_or_default(),
+        tested_at: row.get(7)?,
+        retry_count: retry_count.unwrap_or(0) as u32,
+        endpoint: endpoint.unwrap_or_default(),
+        attempts: Vec::new(),
+        tokens_used: None,
+    })
+}
@@ -0,0 +1,104 @@
+//! 供应商切换历史 DAO
+//!
+//! 按应用记录每次成功切换的当前供应商，用于"最近使用"列表和"切回上一个"。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+/// 每个应用最多保留的切换记录数（附加了备注的记录不清理）
+const MAX_HISTORY_PER_APP: i64 = 200;
+
+/// 最近使用过的供应商
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecentProvider {
+    pub provider_id: String,
+    /// 最近一次切换到该供应商的发起方（"ui" / "tray" / "deeplink" / "failover"）
+    pub source: String,
+    pub switched_at: i64,
+}
+
+impl Database {
+    /// 记录一次切换，并清理超出上限的旧记录（保留附加了备注的记录）
+    pub fn record_provider_switch(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        previous_provider_id: Option<&str>,
+        source: &str,
+        switched_at: i64,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO provider_switch_history
+             (app_type, provider_id, previous_provider_id, source, switched_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                app_type,
+                provider_id,
+                previous_provider_id,
+                source,
+                switched_at
+            ],
+        )
+        .map_err(AppError::from)?;
+        conn.execute(
+            "DELETE FROM provider_switch_history
+             WHERE app_type = ?1 AND note IS NULL AND id NOT IN (
+                SELECT id FROM provider_switch_history
+                WHERE app_type = ?1 ORDER BY id DESC LIMIT ?2
+             )",
+            params![app_type, MAX_HISTORY_PER_APP],
+        )
+        .map_err(AppError::from)?;
+        Ok(())
+    }
+
+    /// 最近使用过的供应商（每个供应商只保留最近一次，按时间倒序）
+    pub fn get_recent_providers(
+        &self,
+        app_type: &str,
+        limit: usize,
+    ) -> Result<Vec<RecentProvider>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT h.provider_id, h.source, h.switched_at
+                 FROM provider_switch_history h
+                 JOIN (
+                    SELECT MAX(id) AS id FROM provider_switch_history
+                    WHERE app_type = ?1 GROUP BY provider_id
+                 ) latest ON latest.id = h.id
+                 ORDER BY h.id DESC LIMIT ?2",
+            )
+            .map_err(AppError::from)?;
+        let rows = stmt
+            .query_map(params![app_type, limit as i64], |row| {
+                Ok(RecentProvider {
+                    provider_id: row.get(0)?,
+                    source: row.get(1)?,
+                    switched_at: row.get(2)?,
+                })
+            })
+            .map_err(AppError::from)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+
+    /// 最近一次切换前的供应商
+    pub fn get_previous_provider(&self, app_type: &str) -> Result<Option<String>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let row = conn.query_row(
+            "SELECT previous_provider_id FROM provider_switch_history
+             WHERE app_type = ?1 ORDER BY id DESC LIMIT 1",
+            params![app_type],
+            |row| row.get::<_, Option<String>>(0),
+        );
+        match row {
+            Ok(previous) => Ok(previous),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
@@ -0,0 +1,199 @@
+//! 时间线 DAO
+//!
+//! 记录供应商故障、恢复与预算事件，并与切换历史按时间合并成统一的时间线。
+//! 切换与事件都可以附加备注（如"因 #12 故障切换"），附加了备注的记录不会被清理。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+/// 每个应用最多保留的事件数（不含切换历史）
+const MAX_EVENTS_PER_APP: i64 = 1000;
+
+/// 时间线条目类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TimelineKind {
+    /// 供应商切换（来自切换历史）
+    Switch,
+    /// 健康检查开始失败
+    Incident,
+    /// 失败后恢复正常
+    Recovery,
+    /// 探测预算或费用预测告警
+    Budget,
+}
+
+impl TimelineKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Switch => "switch",
+            Self::Incident => "incident",
+            Self::Recovery => "recovery",
+            Self::Budget => "budget",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "switch" => Some(Self::Switch),
+            "incident" => Some(Self::Incident),
+            "recovery" => Some(Self::Recovery),
+            "budget" => Some(Self::Budget),
+            _ => None,
+        }
+    }
+}
+
+/// 时间线条目
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineEntry {
+    pub kind: TimelineKind,
+    /// 条目 ID（切换与其他事件分别编号，附加备注时需同时提供 kind）
+    pub id: i64,
+    pub app_type: String,
+    pub provider_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_provider_id: Option<String>,
+    /// 切换的发起方（仅切换）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    pub occurred_at: i64,
+}
+
+/// 时间线查询条件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineQuery {
+    #[serde(default)]
+    pub app_type: Option<String>,
+    /// 起始时间（含，秒）
+    #[serde(default)]
+    pub since: Option<i64>,
+    /// 截止时间（含，秒）
+    #[serde(default)]
+    pub until: Option<i64>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+impl Database {
+    /// 记录一条时间线事件（切换由切换历史记录），并清理超出上限的旧事件
+    pub fn record_timeline_event(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        kind: TimelineKind,
+        message: &str,
+        occurred_at: i64,
+    ) -> Result<i64, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO timeline_events (app_type, provider_id, kind, message, occurred_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![app_type, provider_id, kind.as_str(), message, occurred_at],
+        )
+        .map_err(AppError::from)?;
+        let id = conn.last_insert_rowid();
+        conn.execute(
+            "DELETE FROM timeline_events
+             WHERE app_type = ?1 AND note IS NULL AND id NOT IN (
+                SELECT id FROM timeline_events
+                WHERE app_type = ?1 ORDER BY id DESC LIMIT ?2
+             )",
+            params![app_type, MAX_EVENTS_PER_APP],
+        )
+        .map_err(AppError::from)?;
+        Ok(id)
+    }
+
+    /// 按时间倒序合并切换历史与事件
+    pub fn get_timeline(
+        &self,
+        query: &TimelineQuery,
+        default_limit: usize,
+    ) -> Result<Vec<TimelineEntry>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT 'switch', id, app_type, provider_id, previous_provider_id, source,
+                        NULL, note, switched_at
+                 FROM provider_switch_history
+                 WHERE (?1 IS NULL OR app_type = ?1)
+                   AND (?2 IS NULL OR switched_at >= ?2)
+                   AND (?3 IS NULL OR switched_at <= ?3)
+                 UNION ALL
+                 SELECT kind, id, app_type, provider_id, NULL, NULL, message, note, occurred_at
+                 FROM timeline_events
+                 WHERE (?1 IS NULL OR app_type = ?1)
+                   AND (?2 IS NULL OR occurred_at >= ?2)
+                   AND (?3 IS NULL OR occurred_at <= ?3)
+                 ORDER BY 9 DESC, 2 DESC LIMIT ?4",
+            )
+            .map_err(AppError::from)?;
+        let limit = query.limit.unwrap_or(default_limit) as i64;
+        let rows = stmt
+            .query_map(
+                params![query.app_type, query.since, query.until, limit],
+                |row| {
+                    let kind: String = row.get(0)?;
+                    Ok((
+                        kind,
+                        TimelineEntry {
+                            kind: TimelineKind::Switch,
+                            id: row.get(1)?,
+                            app_type: row.get(2)?,
+                            provider_id: row.get(3)?,
+                            previous_provider_id: row.get(4)?,
+                            source: row.get(5)?,
+                            message: row.get(6)?,
+                            note: row.get(7)?,
+                            occurred_at: row.get(8)?,
+                        },
+                    ))
+                },
+            )
+            .map_err(AppError::from)?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            let (kind, mut entry) = row.map_err(AppError::from)?;
+            // 忽略新版本写入的未知类型
+            let Some(kind) = TimelineKind::parse(&kind) else {
+                continue;
+            };
+            entry.kind = kind;
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// 设置或清除条目的备注，条目不存在时返回 false
+    pub fn set_timeline_note(
+        &self,
+        kind: TimelineKind,
+        id: i64,
+        note: Option<&str>,
+    ) -> Result<bool, AppError> {
+        let note = note.map(str::trim).filter(|note| !note.is_empty());
+        let conn = lock_conn!(self.conn);
+        let updated = match kind {
+            TimelineKind::Switch => conn.execute(
+                "UPDATE provider_switch_history SET note = ?1 WHERE id = ?2",
+                params![note, id],
+            ),
+            _ => conn.execute(
+                "UPDATE timeline_events SET note = ?1 WHERE id = ?2 AND kind = ?3",
+                params![note, id, kind.as_str()],
+            ),
+        }
+        .map_err(AppError::from)?;
+        Ok(updated > 0)
+    }
+}
@@ -0,0 +1,96 @@
+//! 吞吐量采样 DAO
+//!
+//! 存储按供应商、按分钟聚合的输出 token 样本。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::proxy::types::TpsSample;
+use rusqlite::params;
+
+impl Database {
+    /// 批量写入吞吐量样本（同一分钟桶累加）
+    pub fn save_tps_samples(&self, samples: &[TpsSample]) -> Result<(), AppError> {
+        if samples.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = lock_conn!(self.conn);
+        let tx = conn.transaction().map_err(AppError::from)?;
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO tps_samples
+                     (app_type, provider_id, bucket_start, output_tokens, request_count, active_ms)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                     ON CONFLICT(app_type, provider_id, bucket_start) DO UPDATE SET
+                        output_tokens = output_tokens + excluded.output_tokens,
+                        request_count = request_count + excluded.request_count,
+                        active_ms = active_ms + excluded.active_ms",
+                )
+                .map_err(AppError::from)?;
+
+            for sample in samples {
+                stmt.execute(params![
+                    sample.app_type,
+                    sample.provider_id,
+                    sample.bucket_start,
+                    sample.output_tokens as i64,
+                    sample.request_count as i64,
+                    sample.active_ms as i64,
+                ])
+                .map_err(AppError::from)?;
+            }
+        }
+        tx.commit().map_err(AppError::from)?;
+        Ok(())
+    }
+
+    /// 查询吞吐量样本（按时间升序）
+    pub fn get_tps_samples(
+        &self,
+        app_type: Option<&str>,
+        provider_id: Option<&str>,
+        since: i64,
+    ) -> Result<Vec<TpsSample>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT app_type, provider_id, bucket_start, output_tokens, request_count, active_ms
+                 FROM tps_samples
+                 WHERE bucket_start >= ?1
+                   AND (?2 IS NULL OR app_type = ?2)
+                   AND (?3 IS NULL OR provider_id = ?3)
+                 ORDER BY bucket_start ASC",
+            )
+            .map_err(AppError::from)?;
+
+        let rows = stmt
+            .query_map(params![since, app_type, provider_id], |row| {
+                Ok(TpsSample {
+                    app_type: row.get(0)?,
+                    provider_id: row.get(1)?,
+                    bucket_start: row.get(2)?,
+                    output_tokens: row.get::<_, i64>(3)? as u64,
+                    request_count: row.get::<_, i64>(4)? as u64,
+                    active_ms: row.get::<_, i64>(5)? as u64,
+                })
+            })
+            .map_err(AppError::from)?;
+
+        let mut samples = Vec::new();
+        for row in rows {
+            samples.push(row.map_err(AppError::from)?);
+        }
+        Ok(samples)
+    }
+
+    /// 清理早于指定时间的样本，返回删除条数
+    pub fn prune_tps_samples(&self, before: i64) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM tps_samples WHERE bucket_start < ?1",
+            params![before],
+        )
+        .map_err(AppError::from)
+    }
+}
@@ -0,0 +1,115 @@
+//! 会话记录 DAO
+//!
+//! 保存经代理转发的完整请求与回复文本（需在设置中开启），按会话查询与全文搜索。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+/// 一次请求的完整记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transcript {
+    pub id: i64,
+    pub session_id: String,
+    pub app_type: String,
+    pub provider_id: String,
+    pub model: String,
+    /// 原始请求体 JSON
+    pub prompt: String,
+    /// 拼接后的回复文本
+    pub completion: String,
+    pub created_at: i64,
+}
+
+impl Database {
+    /// 写入一条会话记录
+    #[allow(clippy::too_many_arguments)]
+    pub fn insert_transcript(
+        &self,
+        session_id: &str,
+        app_type: &str,
+        provider_id: &str,
+        model: &str,
+        prompt: &str,
+        completion: &str,
+        created_at: i64,
+    ) -> Result<i64, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO proxy_transcripts
+             (session_id, app_type, provider_id, model, prompt, completion, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                session_id,
+                app_type,
+                provider_id,
+                model,
+                prompt,
+                completion,
+                created_at
+            ],
+        )
+        .map_err(AppError::from)?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 查询会话记录（按时间倒序），可按会话过滤、按关键字搜索请求与回复内容
+    pub fn list_transcripts(
+        &self,
+        session_id: Option<&str>,
+        query: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<Transcript>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let pattern = query
+            .filter(|q| !q.trim().is_empty())
+            .map(|q| format!("%{}%", q.trim()));
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, session_id, app_type, provider_id, model, prompt, completion, created_at
+                 FROM proxy_transcripts
+                 WHERE (?1 IS NULL OR session_id = ?1)
+                   AND (?2 IS NULL OR prompt LIKE ?2 OR completion LIKE ?2)
+                 ORDER BY created_at DESC, id DESC
+                 LIMIT ?3",
+            )
+            .map_err(AppError::from)?;
+        let rows = stmt
+            .query_map(params![session_id, pattern, limit.max(1) as i64], |row| {
+                Ok(Transcript {
+                    id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    app_type: row.get(2)?,
+                    provider_id: row.get(3)?,
+                    model: row.get(4)?,
+                    prompt: row.get(5)?,
+                    completion: row.get(6)?,
+                    created_at: row.get(7)?,
+                })
+            })
+            .map_err(AppError::from)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+
+    /// 删除某会话的记录（为空时删除全部），返回删除条数
+    pub fn delete_transcripts(&self, session_id: Option<&str>) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM proxy_transcripts WHERE ?1 IS NULL OR session_id = ?1",
+            params![session_id],
+        )
+        .map_err(AppError::from)
+    }
+
+    /// 清理早于 cutoff（Unix 秒）的记录
+    pub fn prune_transcripts(&self, cutoff: i64) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM proxy_transcripts WHERE created_at < ?1",
+            params![cutoff],
+        )
+        .map_err(AppError::from)
+    }
+}
@@ -0,0 +1,140 @@
+//! 供应商附件 DAO
+//!
+//! 每个供应商可附带加密的备注与小文件（邀请信息、付款凭据等），密文由 `services::vault` 生成。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use rusqlite::{params, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+/// 附件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VaultItemKind {
+    Note,
+    File,
+}
+
+impl VaultItemKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Note => "note",
+            Self::File => "file",
+        }
+    }
+}
+
+/// 附件元数据（不含内容）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultItem {
+    pub id: i64,
+    pub app_type: String,
+    pub provider_id: String,
+    pub kind: VaultItemKind,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// 明文字节数
+    pub size: u64,
+    pub created_at: i64,
+}
+
+const ITEM_COLUMNS: &str = "id, app_type, provider_id, kind, name, mime_type, size, created_at";
+
+fn item_from_row(row: &Row<'_>) -> rusqlite::Result<VaultItem> {
+    let kind: String = row.get(3)?;
+    Ok(VaultItem {
+        id: row.get(0)?,
+        app_type: row.get(1)?,
+        provider_id: row.get(2)?,
+        kind: if kind == "file" {
+            VaultItemKind::File
+        } else {
+            VaultItemKind::Note
+        },
+        name: row.get(4)?,
+        mime_type: row.get(5)?,
+        size: row.get::<_, i64>(6)? as u64,
+        created_at: row.get(7)?,
+    })
+}
+
+impl Database {
+    /// 保存一条加密附件，返回 ID
+    #[allow(clippy::too_many_arguments)]
+    pub fn insert_vault_item(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        kind: VaultItemKind,
+        name: &str,
+        mime_type: Option<&str>,
+        size: u64,
+        ciphertext: &[u8],
+        created_at: i64,
+    ) -> Result<i64, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO provider_vault_items
+             (app_type, provider_id, kind, name, mime_type, size, ciphertext, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                app_type,
+                provider_id,
+                kind.as_str(),
+                name,
+                mime_type,
+                size as i64,
+                ciphertext,
+                created_at
+            ],
+        )
+        .map_err(AppError::from)?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 列出某供应商的附件（按创建时间倒序）
+    pub fn list_vault_items(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+    ) -> Result<Vec<VaultItem>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT {ITEM_COLUMNS} FROM provider_vault_items
+                 WHERE app_type = ?1 AND provider_id = ?2
+                 ORDER BY created_at DESC, id DESC"
+            ))
+            .map_err(AppError::from)?;
+        let rows = stmt
+            .query_map(params![app_type, provider_id], item_from_row)
+            .map_err(AppError::from)?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+
+    /// 读取附件元数据与密文
+    pub fn get_vault_item(&self, id: i64) -> Result<Option<(VaultItem, Vec<u8>)>, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row(
+            &format!("SELECT {ITEM_COLUMNS}, ciphertext FROM provider_vault_items WHERE id = ?1"),
+            params![id],
+            |row| Ok((item_from_row(row)?, row.get::<_, Vec<u8>>(8)?)),
+        )
+        .optional()
+        .map_err(AppError::from)
+    }
+
+    /// 删除附件，返回是否存在
+    pub fn delete_vault_item(&self, id: i64) -> Result<bool, AppError> {
+        let conn = lock_conn!(self.conn);
+        let affected = conn
+            .execute(
+                "DELETE FROM provider_vault_items WHERE id = ?1",
+                params![id],
+            )
+            .map_err(AppError::from)?;
+        Ok(affected > 0)
+    }
+}
@@ -0,0 +1,539 @@
+//! 数据库静态加密（SQLCipher）
+//!
+//! 以 `sqlcipher` feature（发布构建开启）构建时可将主库转换为 SQLCipher 加密库。密钥来源二选一：
+//! - 口令：由 SQLCipher 自身以 PBKDF2-HMAC-SHA512 派生密钥。启动时以安全模式打开，
+//!   由前端提示输入口令后在进程内解锁（口令不写入环境变量或文件，避免被子进程继承）；
+//! - 系统钥匙串：随机生成 256 位原始密钥保存在钥匙串中，启动时自动读取。
+//!
+//! 加密状态记录在 `~/.cc-switch/db-encryption.json`（不含任何密钥）。
+//!
+//! 恢复语义：口令遗忘或钥匙串条目丢失后加密库**无法解密**，只能从启用加密前
+//! 的明文快照备份恢复（若启用时选择清除则不存在）或重新初始化。
+
+use super::{lock_conn, Database, SCHEMA_VERSION};
+use crate::config::{get_app_config_dir, shred_file};
+use crate::error::AppError;
+use crate::services::vault;
+use chrono::Utc;
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// 口令最短长度
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+pub(crate) const KEYCHAIN_ACCOUNT: &str = "database-key";
+
+/// 当前连接使用的密钥（PRAGMA key 的取值），快照备份需以同一密钥加密
+static ACTIVE_KEY: RwLock<Option<String>> = RwLock::new(None);
+
+/// 加密密钥来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DbKeySource {
+    Passphrase,
+    Keychain,
+}
+
+/// 加密状态文件内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EncryptionConfig {
+    key_source: DbKeySource,
+    enabled_at: i64,
+}
+
+/// 加密状态（供设置页展示）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DbEncryptionStatus {
+    /// 当前构建是否包含 SQLCipher
+    pub supported: bool,
+    pub enabled: bool,
+    pub key_source: Option<DbKeySource>,
+    pub enabled_at: Option<i64>,
+    /// 仍以明文保存的快照备份数量
+    pub plaintext_backups: usize,
+}
+
+/// 加密 / 解密迁移结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DbEncryptionReport {
+    pub enabled: bool,
+    pub key_source: Option<DbKeySource>,
+    /// 已清除的明文快照备份数量
+    pub purged_backups: usize,
+}
+
+fn config_path() -> PathBuf {
+    get_app_config_dir().join("db-encryption.json")
+}
+
+fn db_file_path() -> PathBuf {
+    get_app_config_dir().join("cc-switch.db")
+}
+
+fn read_config() -> Option<EncryptionConfig> {
+    let content = fs::read_to_string(config_path()).ok()?;
+    serde_json::from_str(&content)
+        .map_err(|e| log::warn!("数据库加密状态文件无法解析: {e}"))
+        .ok()
+}
+
+fn locked_error() -> AppError {
+    AppError::localized(
+        "database.locked",
+        "数据库已加密，请输入口令解锁",
+        "The database is encrypted; enter the passphrase to unlock it",
+    )
+}
+
+fn wrong_key_error() -> AppError {
+    AppError::localized(
+        "database.wrong_key",
+        "数据库密钥不正确（口令错误或钥匙串条目已变更），无法解密",
+        "Wrong database key (incorrect passphrase or changed keychain entry); cannot decrypt",
+    )
+}
+
+fn unsupported_error() -> AppError {
+    AppError::localized(
+        "database.encryption_unsupported",
+        "当前版本未包含 SQLCipher，无法使用数据库加密",
+        "This build does not include SQLCipher; database encryption is unavailable",
+    )
+}
+
+/// 口令密钥直接交给 SQLCipher 派生；原始密钥使用 `x'<hex>'` 形式跳过派生
+fn passphrase_pragma(passphrase: &str) -> String {
+    passphrase.to_string()
+}
+
+fn raw_key_pragma(hex_key: &str) -> String {
+    format!("x'{hex_key}'")
+}
+
+/// 链接的 SQLite 是否为 SQLCipher（普通 SQLite 会静默忽略 `PRAGMA key`）
+fn cipher_available(conn: &Connection) -> bool {
+    conn.query_row("PRAGMA cipher_version", [], |row| row.get::<_, String>(0))
+        .map(|v| !v.is_empty())
+        .unwrap_or(false)
+}
+
+/// 对连接应用密钥并确认能读取
+fn apply_key(conn: &Connection, key: &str) -> Result<(), AppError> {
+    if !cipher_available(conn) {
+        return Err(unsupported_error());
+    }
+    conn.pragma_update(None, "key", key)
+        .map_err(AppError::from)?;
+    conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| {
+        row.get::<_, i64>(0)
+    })
+    .map_err(|_| wrong_key_error())?;
+    Ok(())
+}
+
+/// 按加密状态文件解析启动密钥；未加密时返回 None，口令加密时需由用户解锁
+pub(super) fn resolve_startup_key() -> Result<Option<String>, AppError> {
+    let Some(config) = read_config() else {
+        return Ok(None);
+    };
+    match config.key_source {
+        DbKeySource::Keychain => vault::keychain_get(KEYCHAIN_ACCOUNT)
+            .map(|hex_key| Some(raw_key_pragma(&hex_key)))
+            .ok_or_else(|| {
+                AppError::localized(
+                    "database.keychain_missing",
+                    "系统钥匙串中找不到数据库密钥，无法解密数据库",
+                    "The database key is missing from the system keychain; cannot decrypt",
+                )
+            }),
+        DbKeySource::Passphrase => Err(locked_error()),
+    }
+}
+
+/// 打开主库时调用：数据库已加密则应用密钥并记住，供后续快照使用
+pub(super) fn unlock_on_open(conn: &Connection, key: Option<String>) -> Result<(), AppError> {
+    if let Some(key) = key {
+        apply_key(conn, &key)?;
+        set_active_key(Some(key));
+    }
+    Ok(())
+}
+
+fn set_active_key(key: Option<String>) {
+    if let Ok(mut guard) = ACTIVE_KEY.write() {
+        *guard = key;
+    }
+}
+
+fn active_key() -> Option<String> {
+    ACTIVE_KEY.read().ok()?.clone()
+}
+
+/// 主库已加密时对另一个连接（快照、备份文件）应用同一密钥
+pub(crate) fn key_connection(conn: &Connection) -> Result<(), AppError> {
+    match active_key() {
+        Some(key) => apply_key(conn, &key),
+        None => Ok(()),
+    }
+}
+
+/// 只读打开主库以外的数据库文件（快照备份等），非明文文件应用当前密钥
+pub(super) fn open_file_read_only(path: &Path) -> Result<Connection, AppError> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(AppError::from)?;
+    if !is_plaintext_db(path) {
+        key_connection(&conn)?;
+    }
+    Ok(conn)
+}
+
+/// 主库被明文文件替换（恢复明文备份 / 重新初始化）后清除加密状态，
+/// 钥匙串中的密钥保留，以便日后仍能打开被隔离的加密文件
+pub(super) fn forget_if_plaintext(db_path: &Path) -> Result<(), AppError> {
+    let path = config_path();
+    if path.exists() && (!db_path.exists() || is_plaintext_db(db_path)) {
+        fs::remove_file(&path).map_err(|e| AppError::io(&path, e))?;
+        log::info!("主库已替换为明文数据库，清除加密状态");
+    }
+    Ok(())
+}
+
+/// 是否已启用加密（以状态文件为准，主库未解锁时同样返回 true）
+pub(crate) fn is_configured() -> bool {
+    read_config().is_some()
+}
+
+/// 当前主库连接是否已用密钥打开
+pub(crate) fn is_encrypted() -> bool {
+    active_key().is_some()
+}
+
+/// 将 `source` 的全部内容通过 `sqlcipher_export` 写入新文件 `target`（`key` 为空串表示明文）
+fn export_to(source: &Connection, target: &Path, key: &str) -> Result<(), AppError> {
+    source
+        .execute(
+            "ATTACH DATABASE ?1 AS cc_switch_export KEY ?2",
+            rusqlite::params![target.to_string_lossy(), key],
+        )
+        .map_err(AppError::from)?;
+    let result = source
+        .query_row(
+            "SELECT sqlcipher_export('cc_switch_export')",
+            [],
+            |_| Ok(()),
+        )
+        .and_then(|_| {
+            source.execute_batch(&format!(
+                "PRAGMA cc_switch_export.user_version = {SCHEMA_VERSION};"
+            ))
+        })
+        .map_err(|e| AppError::Database(format!("导出数据库失败: {e}")));
+    let detached = source
+        .execute("DETACH DATABASE cc_switch_export", [])
+        .map_err(AppError::from);
+    result.and(detached.map(|_| ()))
+}
+
+/// 校验新文件可用指定密钥打开且完整，返回供应商数量用于与原库比对
+fn verify_export(path: &Path, key: Option<&str>) -> Result<i64, AppError> {
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(AppError::from)?;
+    if let Some(key) = key {
+        apply_key(&conn, key)?;
+    }
+    let check: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(AppError::from)?;
+    if check != "ok" {
+        return Err(AppError::Database(format!(
+            "转换后的数据库校验失败: {check}"
+        )));
+    }
+    conn.query_row("SELECT COUNT(*) FROM providers", [], |row| row.get(0))
+        .map_err(AppError::from)
+}
+
+fn backup_files() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(get_app_config_dir().join("backups")) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "db"))
+        .collect()
+}
+
+/// 快照备份是否为明文（明文 SQLite 文件以固定头部开头，加密文件头部为随机盐）
+fn is_plaintext_db(path: &Path) -> bool {
+    let mut header = [0u8; 16];
+    fs::File::open(path)
+        .and_then(|mut f| std::io::Read::read_exact(&mut f, &mut header))
+        .map(|_| &header == b"SQLite format 3\0")
+        .unwrap_or(false)
+}
+
+impl Database {
+    /// 查询数据库加密状态
+    pub fn encryption_status(&self) -> Result<DbEncryptionStatus, AppError> {
+        let supported = {
+            let conn = lock_conn!(self.conn);
+            cipher_available(&conn)
+        };
+        let config = read_config();
+        Ok(DbEncryptionStatus {
+            supported,
+            enabled: config.is_some(),
+            key_source: config.as_ref().map(|c| c.key_source),
+            enabled_at: config.as_ref().map(|c| c.enabled_at),
+            plaintext_backups: backup_files().iter().filter(|p| is_plaintext_db(p)).count(),
+        })
+    }
+
+    /// 将明文主库转换为加密库
+    ///
+    /// 导出到临时文件并校验后替换主库，原明文文件覆写删除；
+    /// `purge_plaintext_backups` 为 true 时同时删除明文快照备份（此后遗失密钥将无法恢复）。
+    pub fn enable_encryption(
+        &self,
+        key_source: DbKeySource,
+        passphrase: Option<&str>,
+        purge_plaintext_backups: bool,
+    ) -> Result<DbEncryptionReport, AppError> {
+        if read_config().is_some() {
+            return Err(AppError::InvalidInput("数据库已处于加密状态".to_string()));
+        }
+
+        let key = match key_source {
+            DbKeySource::Passphrase => {
+                let passphrase = passphrase.unwrap_or_default();
+                if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+                    return Err(AppError::localized(
+                        "database.passphrase_too_short",
+                        format!("口令至少需要 {MIN_PASSPHRASE_LEN} 个字符"),
+                        format!("The passphrase must be at least {MIN_PASSPHRASE_LEN} characters"),
+                    ));
+                }
+                passphrase_pragma(passphrase)
+            }
+            DbKeySource::Keychain => {
+                let hex_key = hex::encode(vault::generate_key()?);
+                // 不退回到密钥文件：与数据库放在同一目录的密钥起不到保护作用
+                if !vault::keychain_set(KEYCHAIN_ACCOUNT, "CC Switch database key", &hex_key) {
+                    return Err(AppError::localized(
+                        "database.keychain_unavailable",
+                        "系统钥匙串不可用，请改用口令加密",
+                        "The system keychain is unavailable; use a passphrase instead",
+                    ));
+                }
+                raw_key_pragma(&hex_key)
+            }
+        };
+
+        self.swap_main_file(Some(&key))?;
+
+        let config = EncryptionConfig {
+            key_source,
+            enabled_at: Utc::now().timestamp(),
+        };
+        let content = serde_json::to_vec_pretty(&config)
+            .map_err(|source| AppError::JsonSerialize { source })?;
+        crate::config::atomic_write(&config_path(), &content)?;
+        set_active_key(Some(key));
+
+        let purged_backups = if purge_plaintext_backups {
+            let plaintext: Vec<_> = backup_files()
+                .into_iter()
+                .filter(|p| is_plaintext_db(p))
+                .collect();
+            plaintext.iter().for_each(|p| shred_file(p));
+            plaintext.len()
+        } else {
+            0
+        };
+
+        log::info!("数据库已加密（密钥来源: {key_source:?}）");
+        Ok(DbEncryptionReport {
+            enabled: true,
+            key_source: Some(key_source),
+            purged_backups,
+        })
+    }
+
+    /// 将加密主库转换回明文
+    pub fn disable_encryption(&self) -> Result<DbEncryptionReport, AppError> {
+        let Some(config) = read_config() else {
+            return Err(AppError::InvalidInput("数据库未加密".to_string()));
+        };
+
+        self.swap_main_file(None)?;
+
+        let path = config_path();
+        fs::remove_file(&path).map_err(|e| AppError::io(&path, e))?;
+        set_active_key(None);
+        if config.key_source == DbKeySource::Keychain {
+            vault::keychain_delete(KEYCHAIN_ACCOUNT);
+        }
+
+        log::info!("数据库已恢复为明文");
+        Ok(DbEncryptionReport {
+            enabled: false,
+            key_source: None,
+            purged_backups: 0,
+        })
+    }
+
+    /// 校验口令能否解开加密主库（解锁流程在重启前调用）
+    pub fn verify_passphrase(passphrase: &str) -> Result<(), AppError> {
+        match read_config() {
+            Some(config) if config.key_source == DbKeySource::Passphrase => {}
+            _ => return Err(AppError::InvalidInput("数据库未使用口令加密".to_string())),
+        }
+        let conn = Connection::open_with_flags(db_file_path(), OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(AppError::from)?;
+        apply_key(&conn, &passphrase_pragma(passphrase))
+    }
+
+    /// 安全模式下用口令解锁：打开加密主库并替换当前的内存连接，无需重启
+    pub fn unlock_with_passphrase(&self, passphrase: &str) -> Result<(), AppError> {
+        Self::verify_passphrase(passphrase)?;
+        let unlocked = Self::open_main(Some(passphrase_pragma(passphrase)))?;
+        let problems = unlocked.integrity_check()?;
+        if !problems.is_empty() {
+            return Err(AppError::Database(format!(
+                "完整性检查失败: {}",
+                problems.join("; ")
+            )));
+        }
+        let conn = unlocked
+            .conn
+            .into_inner()
+            .map_err(|e| AppError::Database(format!("Mutex lock failed: {e}")))?;
+        let old = std::mem::replace(&mut *lock_conn!(self.conn), conn);
+        drop(old);
+        Ok(())
+    }
+
+    /// 导出主库到同目录临时文件（`key` 为 None 表示明文），校验后替换主库并重新打开连接
+    fn swap_main_file(&self, key: Option<&str>) -> Result<(), AppError> {
+        let db_path = db_file_path();
+        let temp_path = db_path.with_extension("db.converting");
+        if temp_path.exists() {
+            shred_file(&temp_path);
+        }
+
+        let mut conn = lock_conn!(self.conn);
+        if !cipher_available(&conn) {
+            return Err(unsupported_error());
+        }
+
+        let expected: i64 = conn
+            .query_row("SELECT COUNT(*) FROM providers", [], |row| row.get(0))
+            .map_err(AppError::from)?;
+        let converted = export_to(&conn, &temp_path, key.unwrap_or(""))
+            .and_then(|_| verify_export(&temp_path, key));
+        match converted {
+            Ok(count) if count == expected => {}
+            Ok(count) => {
+                shred_file(&temp_path);
+                return Err(AppError::Database(format!(
+                    "转换后的供应商数量不一致（{count} / {expected}），已放弃"
+                )));
+            }
+            Err(e) => {
+                shred_file(&temp_path);
+                return Err(e);
+            }
+        }
+
+        // 先关闭旧连接再替换文件（Windows 不允许替换已打开的文件）
+        let old = std::mem::replace(
+            &mut *conn,
+            Connection::open_in_memory().map_err(AppError::from)?,
+        );
+        drop(old);
+
+        let retired =
+            db_path.with_extension(format!("db.retired-{}", Utc::now().format("%Y%m%d_%H%M%S")));
+        fs::rename(&db_path, &retired).map_err(|e| AppError::io(&db_path, e))?;
+        if let Err(e) = fs::rename(&temp_path, &db_path) {
+            // 回滚到原文件，保证主库仍可用
+            let _ = fs::rename(&retired, &db_path);
+            *conn = Connection::open(&db_path).map_err(AppError::from)?;
+            if let Some(key) = active_key() {
+                apply_key(&conn, &key)?;
+            }
+            return Err(AppError::io(&db_path, e));
+        }
+        for ext in ["db-wal", "db-shm"] {
+            let side = db_path.with_extension(ext);
+            if side.exists() {
+                shred_file(&side);
+            }
+        }
+        shred_file(&retired);
+
+        let reopened = Connection::open(&db_path).map_err(AppError::from)?;
+        if let Some(key) = key {
+            apply_key(&reopened, key)?;
+        }
+        reopened
+            .execute("PRAGMA foreign_keys = ON;", [])
+            .map_err(AppError::from)?;
+        *conn = reopened;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_plaintext_database_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let plain = dir.path().join("plain.db");
+        Connection::open(&plain)
+            .unwrap()
+            .execute_batch("CREATE TABLE t (id INTEGER);")
+            .unwrap();
+        assert!(is_plaintext_db(&plain));
+
+        let random = dir.path().join("random.db");
+        fs::write(&random, [7u8; 64]).unwrap();
+        assert!(!is_plaintext_db(&random));
+        assert!(!is_plaintext_db(&dir.path().join("missing.db")));
+    }
+
+    #[cfg(feature = "sqlcipher")]
+    #[test]
+    fn exported_copy_requires_the_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = Connection::open(dir.path().join("plain.db")).unwrap();
+        assert!(cipher_available(&source));
+        source
+            .execute_batch("CREATE TABLE providers (id TEXT); INSERT INTO providers VALUES ('p1');")
+            .unwrap();
+
+        let encrypted = dir.path().join("encrypted.db");
+        let key = passphrase_pragma("correct horse");
+        export_to(&source, &encrypted, &key).unwrap();
+        assert!(!is_plaintext_db(&encrypted));
+        assert_eq!(verify_export(&encrypted, Some(&key)).unwrap(), 1);
+
+        let conn = Connection::open(&encrypted).unwrap();
+        assert!(apply_key(&conn, &passphrase_pragma("wrong horse")).is_err());
+    }
+
+    #[test]
+    fn raw_keys_use_blob_literal() {
+        assert_eq!(raw_key_pragma("00ff"), "x'00ff'");
+        assert_eq!(passphrase_pragma("correct horse"), "correct horse");
+    }
+}
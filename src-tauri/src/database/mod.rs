@@ -14,7 +14,9 @@
 //! ├── mod.rs        - Database 结构体 + 初始化
 //! ├── schema.rs     - 表结构定义 + Schema 迁移
 //! ├── backup.rs     - SQL 导入导出 + 快照备份
+//! ├── encryption.rs - SQLCipher 静态加密（口令 / 系统钥匙串）
 //! ├── migration.rs  - JSON → SQLite 数据迁移
+//! ├── recovery.rs   - 安全模式恢复（备份还原 / 数据抢救 / 重新初始化）
 //! └── dao/          - 数据访问对象
 //!     ├── providers.rs
 //!     ├── mcp.rs
@@ -25,7 +27,9 @@
 
 mod backup;
 mod dao;
+pub(crate) mod encryption;
 mod migration;
+mod recovery;
 mod schema;
 
 #[cfg(test)]
@@ -33,6 +37,14 @@ mod tests;
 
 // DAO 类型导出供外部使用
 pub use dao::FailoverQueueItem;
+pub use dao::{
+    AuditEntry, BackgroundTask, JobStatus, PendingJob, ProviderActivity, ProviderActivitySort,
+    QueuedRequest, QueuedRequestStatus, RecentProvider, ShadowComparison, ShadowSummary,
+    SubstitutionEvidence, SubstitutionSuspect, TaskStatus, TimelineEntry, TimelineKind,
+    TimelineQuery, Transcript, VaultItem, VaultItemKind,
+};
+pub use encryption::{DbEncryptionReport, DbEncryptionStatus, DbKeySource};
+pub use recovery::{DbBackupEntry, SalvageReport};
 
 use crate::config::get_app_config_dir;
 use crate::error::AppError;
@@ -47,7 +59,7 @@ const DB_BACKUP_RETAIN: usize = 10;
 
 /// 当前 Schema 版本号
 /// 每次修改表结构时递增，并在 schema.rs 中添加相应的迁移逻辑
-pub(crate) const SCHEMA_VERSION: i32 = 2;
+pub(crate) const SCHEMA_VERSION: i32 = 9;
 
 /// 安全地序列化 JSON，避免 unwrap panic
 pub(crate) fn to_json_string<T: Serialize>(value: &T) -> Result<String, AppError> {
@@ -80,6 +92,11 @@ impl Database {
     ///
     /// 数据库文件位于 `~/.cc-switch/cc-switch.db`
     pub fn init() -> Result<Self, AppError> {
+        Self::open_main(encryption::resolve_startup_key()?)
+    }
+
+    /// 打开主库文件（`key` 为加密库的密钥），创建缺失的表并执行 Schema 迁移
+    fn open_main(key: Option<String>) -> Result<Self, AppError> {
         let db_path = get_app_config_dir().join("cc-switch.db");
 
         // 确保父目录存在
@@ -87,11 +104,14 @@ impl Database {
             std::fs::create_dir_all(parent).map_err(|e| AppError::io(parent, e))?;
         }
 
-        let conn = Connection::open(&db_path).map_err(|e| AppError::Database(e.to_string()))?;
+        let conn = Connection::open(&db_path).map_err(AppError::from)?;
+
+        // 已加密时应用密钥（口令缺失或错误时返回错误，由调用方进入安全模式）
+        encryption::unlock_on_open(&conn, key)?;
 
         // 启用外键约束
         conn.execute("PRAGMA foreign_keys = ON;", [])
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
         let db = Self {
             conn: Mutex::new(conn),
@@ -105,11 +125,11 @@ impl Database {
 
     /// 创建内存数据库（用于测试）
     pub fn memory() -> Result<Self, AppError> {
-        let conn = Connection::open_in_memory().map_err(|e| AppError::Database(e.to_string()))?;
+        let conn = Connection::open_in_memory().map_err(AppError::from)?;
 
         // 启用外键约束
         conn.execute("PRAGMA foreign_keys = ON;", [])
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
 
         let db = Self {
             conn: Mutex::new(conn),
@@ -120,12 +140,21 @@ impl Database {
         Ok(db)
     }
 
+    /// 关闭数据库文件（替换为空的内存库），用于删除数据目录前
+    pub(crate) fn release_file(&self) -> Result<(), AppError> {
+        let memory = Connection::open_in_memory().map_err(AppError::from)?;
+        Self::create_tables_on_conn(&memory)?;
+        let old = std::mem::replace(&mut *lock_conn!(self.conn), memory);
+        drop(old);
+        Ok(())
+    }
+
     /// 检查 MCP 服务器表是否为空
     pub fn is_mcp_table_empty(&self) -> Result<bool, AppError> {
         let conn = lock_conn!(self.conn);
         let count: i64 = conn
             .query_row("SELECT COUNT(*) FROM mcp_servers", [], |row| row.get(0))
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
         Ok(count == 0)
     }
 
@@ -134,7 +163,27 @@ impl Database {
         let conn = lock_conn!(self.conn);
         let count: i64 = conn
             .query_row("SELECT COUNT(*) FROM prompts", [], |row| row.get(0))
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
         Ok(count == 0)
     }
+
+    /// 执行 `PRAGMA integrity_check`，返回发现的问题（为空表示完整）
+    pub fn integrity_check(&self) -> Result<Vec<String>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare("PRAGMA integrity_check")
+            .map_err(AppError::from)?;
+        let rows = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(AppError::from)?;
+
+        let mut problems = Vec::new();
+        for row in rows {
+            let line = row.map_err(AppError::from)?;
+            if line != "ok" {
+                problems.push(line);
+            }
+        }
+        Ok(problems)
+    }
 }
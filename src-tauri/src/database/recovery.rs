@@ -0,0 +1,305 @@
+//! 数据库损坏时的安全模式恢复
+//!
+//! 主库无法打开或完整性检查失败时，应用以安全模式启动（使用内存数据库），
+//! 由用户选择：从快照备份恢复、导出仍可读取的表，或重新初始化。
+//! 以下操作均不依赖已打开的主库连接，执行后需重启应用生效。
+
+use super::backup::CC_SWITCH_SQL_EXPORT_HEADER;
+use super::encryption;
+use super::Database;
+use crate::config::get_app_config_dir;
+use crate::error::AppError;
+use chrono::Utc;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 数据库快照备份条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DbBackupEntry {
+    pub id: String,
+    pub path: String,
+    pub size_bytes: u64,
+    pub modified_at: i64,
+}
+
+/// 单表抢救结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SalvagedTable {
+    pub name: String,
+    pub rows: usize,
+    /// 读取中途出错（仅导出了出错前的行）
+    pub partial: bool,
+}
+
+/// 数据抢救报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SalvageReport {
+    pub file_path: String,
+    pub tables: Vec<SalvagedTable>,
+    /// 完全无法读取的表
+    pub failed_tables: Vec<String>,
+}
+
+fn db_file_path() -> PathBuf {
+    get_app_config_dir().join("cc-switch.db")
+}
+
+fn backup_dir() -> PathBuf {
+    get_app_config_dir().join("backups")
+}
+
+impl Database {
+    /// 列出可用于恢复的快照备份（按时间倒序）
+    pub fn list_db_backups() -> Result<Vec<DbBackupEntry>, AppError> {
+        let dir = backup_dir();
+        let entries = match fs::read_dir(&dir) {
+            Ok(iter) => iter,
+            Err(_) => return Ok(Vec::new()),
+        };
+
+        let mut backups = Vec::new();
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.extension().map(|ext| ext != "db").unwrap_or(true) {
+                continue;
+            }
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            let modified_at = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0);
+            backups.push(DbBackupEntry {
+                id: path
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                path: path.display().to_string(),
+                size_bytes: meta.len(),
+                modified_at,
+            });
+        }
+
+        backups.sort_by_key(|b| std::cmp::Reverse(b.modified_at));
+        Ok(backups)
+    }
+
+    /// 用指定快照备份替换主库（原文件先改名隔离），返回隔离文件路径
+    pub fn restore_db_backup(backup_id: &str) -> Result<Option<PathBuf>, AppError> {
+        if backup_id.contains(['/', '\\']) || backup_id.contains("..") {
+            return Err(AppError::InvalidInput(format!(
+                "无效的备份 ID: {backup_id}"
+            )));
+        }
+
+        let backup_path = backup_dir().join(format!("{backup_id}.db"));
+        if !backup_path.exists() {
+            return Err(AppError::InvalidInput(format!("备份不存在: {backup_id}")));
+        }
+
+        // 确认备份本身可用，避免用另一个坏文件替换
+        let problems = Self::integrity_check_file(&backup_path)?;
+        if !problems.is_empty() {
+            return Err(AppError::Database(format!(
+                "备份文件同样损坏: {}",
+                problems.join("; ")
+            )));
+        }
+
+        let db_path = db_file_path();
+        let quarantined = quarantine_db_file(&db_path)?;
+        fs::copy(&backup_path, &db_path).map_err(|e| AppError::io(&db_path, e))?;
+        encryption::forget_if_plaintext(&db_path)?;
+        log::info!("已从备份 {backup_id} 恢复数据库");
+        Ok(quarantined)
+    }
+
+    /// 将损坏主库中仍可读取的表导出为 SQL（格式与 `export_sql` 一致，可直接导入）
+    pub fn salvage_to_sql(target_path: &Path) -> Result<SalvageReport, AppError> {
+        let db_path = db_file_path();
+        let conn = encryption::open_file_read_only(&db_path)
+            .map_err(|e| AppError::Database(format!("无法以只读方式打开数据库: {e}")))?;
+
+        let mut output = format!(
+            "{CC_SWITCH_SQL_EXPORT_HEADER}\n-- 安全模式抢救导出\n-- 生成时间: {}\n",
+            Utc::now().format("%Y-%m-%d %H:%M:%S")
+        );
+        output.push_str("PRAGMA foreign_keys=OFF;\nBEGIN TRANSACTION;\n");
+
+        let schema: Vec<(String, String)> = {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT name, sql FROM sqlite_master
+                     WHERE type = 'table' AND sql NOT NULL AND name NOT LIKE 'sqlite_%'
+                     ORDER BY name",
+                )
+                .map_err(|e| AppError::Database(format!("无法读取表结构: {e}")))?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| AppError::Database(format!("无法读取表结构: {e}")))?;
+            rows.filter_map(|r| r.ok()).collect()
+        };
+
+        let mut report = SalvageReport {
+            file_path: target_path.display().to_string(),
+            tables: Vec::new(),
+            failed_tables: Vec::new(),
+        };
+
+        for (table, sql) in schema {
+            match salvage_table(&conn, &table) {
+                Ok((inserts, rows, partial)) => {
+                    output.push_str(&sql);
+                    output.push_str(";\n");
+                    output.push_str(&inserts);
+                    report.tables.push(SalvagedTable {
+                        name: table,
+                        rows,
+                        partial,
+                    });
+                }
+                Err(e) => {
+                    log::warn!("抢救表 {table} 失败: {e}");
+                    report.failed_tables.push(table);
+                }
+            }
+        }
+
+        output.push_str("COMMIT;\nPRAGMA foreign_keys=ON;\n");
+
+        if let Some(parent) = target_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| AppError::io(parent, e))?;
+        }
+        crate::config::atomic_write(target_path, output.as_bytes())?;
+        Ok(report)
+    }
+
+    /// 隔离损坏的主库，下次启动时重新创建空库，返回隔离文件路径
+    pub fn reinitialize_db_file() -> Result<Option<PathBuf>, AppError> {
+        let db_path = db_file_path();
+        let quarantined = quarantine_db_file(&db_path)?;
+        encryption::forget_if_plaintext(&db_path)?;
+        Ok(quarantined)
+    }
+
+    /// 对任意数据库文件执行完整性检查（无法打开视为错误）
+    fn integrity_check_file(path: &Path) -> Result<Vec<String>, AppError> {
+        let conn = encryption::open_file_read_only(path)?;
+        let db = Self {
+            conn: std::sync::Mutex::new(conn),
+        };
+        db.integrity_check()
+    }
+}
+
+/// 读取单表全部可读行，返回 (INSERT 语句, 行数, 是否中途出错)
+fn salvage_table(conn: &Connection, table: &str) -> Result<(String, usize, bool), AppError> {
+    let columns = Database::get_table_columns(conn, table)?;
+    let cols = columns
+        .iter()
+        .map(|c| format!("\"{c}\""))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut stmt = conn
+        .prepare(&format!("SELECT * FROM \"{table}\""))
+        .map_err(AppError::from)?;
+    let mut rows = stmt.query([]).map_err(AppError::from)?;
+
+    let mut output = String::new();
+    let mut count = 0;
+    loop {
+        let row = match rows.next() {
+            Ok(Some(row)) => row,
+            Ok(None) => return Ok((output, count, false)),
+            Err(e) if count == 0 => return Err(e.into()),
+            Err(_) => return Ok((output, count, true)),
+        };
+
+        let mut values = Vec::with_capacity(columns.len());
+        for idx in 0..columns.len() {
+            let value = row
+                .get_ref(idx)
+                .map_err(AppError::from)
+                .and_then(Database::format_sql_value);
+            match value {
+                Ok(v) => values.push(v),
+                // 单个字段无法读取时跳过该行
+                Err(_) => break,
+            }
+        }
+        if values.len() != columns.len() {
+            continue;
+        }
+
+        output.push_str(&format!(
+            "INSERT INTO \"{table}\" ({cols}) VALUES ({});\n",
+            values.join(", ")
+        ));
+        count += 1;
+    }
+}
+
+/// 将数据库文件（及 WAL/SHM）改名为 `*.corrupted-<时间戳>`，文件不存在时返回 None
+fn quarantine_db_file(db_path: &Path) -> Result<Option<PathBuf>, AppError> {
+    if !db_path.exists() {
+        return Ok(None);
+    }
+
+    let suffix = Utc::now().format("%Y%m%d_%H%M%S").to_string();
+    let target = db_path.with_extension(format!("db.corrupted-{suffix}"));
+    fs::rename(db_path, &target).map_err(|e| AppError::io(db_path, e))?;
+
+    for ext in ["db-wal", "db-shm"] {
+        let side = db_path.with_extension(ext);
+        if side.exists() {
+            let _ = fs::rename(
+                &side,
+                db_path.with_extension(format!("{ext}.corrupted-{suffix}")),
+            );
+        }
+    }
+
+    log::warn!("已隔离数据库文件: {}", target.display());
+    Ok(Some(target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn salvage_table_exports_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE t (id INTEGER, name TEXT);
+             INSERT INTO t VALUES (1, 'a'), (2, 'it''s');",
+        )
+        .unwrap();
+
+        let (sql, rows, partial) = salvage_table(&conn, "t").unwrap();
+        assert_eq!(rows, 2);
+        assert!(!partial);
+        assert!(sql.contains("VALUES (2, 'it''s')"));
+    }
+
+    #[test]
+    fn quarantine_renames_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("cc-switch.db");
+        fs::write(&db_path, b"garbage").unwrap();
+
+        let moved = quarantine_db_file(&db_path).unwrap().unwrap();
+        assert!(!db_path.exists());
+        assert!(moved.exists());
+        assert!(quarantine_db_file(&db_path).unwrap().is_none());
+    }
+}
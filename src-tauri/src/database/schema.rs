@@ -36,7 +36,7 @@ impl Database {
             )",
             [],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         // 2. Provider Endpoints 表
         conn.execute(
@@ -50,7 +50,7 @@ impl Database {
             )",
             [],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         // 3. MCP Servers 表
         conn.execute(
@@ -62,14 +62,14 @@ impl Database {
         )",
             [],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         // 4. Prompts 表
         conn.execute("CREATE TABLE IF NOT EXISTS prompts (
             id TEXT NOT NULL, app_type TEXT NOT NULL, name TEXT NOT NULL, content TEXT NOT NULL,
             description TEXT, enabled BOOLEAN NOT NULL DEFAULT 1, created_at INTEGER, updated_at INTEGER,
             PRIMARY KEY (id, app_type)
-        )", []).map_err(|e| AppError::Database(e.to_string()))?;
+        )", []).map_err(AppError::from)?;
 
         // 5. Skills 表
         conn.execute(
@@ -79,7 +79,7 @@ impl Database {
         )",
             [],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         // 6. Skill Repos 表
         conn.execute(
@@ -89,14 +89,14 @@ impl Database {
         )",
             [],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         // 7. Settings 表
         conn.execute(
             "CREATE TABLE IF NOT EXISTS settings (key TEXT PRIMARY KEY, value TEXT)",
             [],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         // 8. Proxy Config 表（三行结构，app_type 主键）
         conn.execute("CREATE TABLE IF NOT EXISTS proxy_config (
@@ -110,7 +110,7 @@ impl Database {
             circuit_timeout_seconds INTEGER NOT NULL DEFAULT 60, circuit_error_rate_threshold REAL NOT NULL DEFAULT 0.5,
             circuit_min_requests INTEGER NOT NULL DEFAULT 10,
             created_at TEXT NOT NULL DEFAULT (datetime('now')), updated_at TEXT NOT NULL DEFAULT (datetime('now'))
-        )", []).map_err(|e| AppError::Database(e.to_string()))?;
+        )", []).map_err(AppError::from)?;
 
         // 初始化三行数据（每应用不同默认值）
         //
@@ -126,7 +126,7 @@ impl Database {
                 VALUES ('claude', 6, 45, 90, 300, 8, 3, 90, 0.6, 15)",
                 [],
             )
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
             conn.execute(
                 "INSERT OR IGNORE INTO proxy_config (app_type, max_retries,
                 streaming_first_byte_timeout, streaming_idle_timeout, non_streaming_timeout,
@@ -135,7 +135,7 @@ impl Database {
                 VALUES ('codex', 3, 30, 60, 300, 5, 2, 60, 0.5, 10)",
                 [],
             )
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
             conn.execute(
                 "INSERT OR IGNORE INTO proxy_config (app_type, max_retries,
                 streaming_first_byte_timeout, streaming_idle_timeout, non_streaming_timeout,
@@ -144,7 +144,7 @@ impl Database {
                 VALUES ('gemini', 5, 30, 60, 300, 5, 2, 60, 0.5, 10)",
                 [],
             )
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
         }
 
         // 9. Provider Health 表
@@ -154,7 +154,7 @@ impl Database {
             last_error TEXT, updated_at TEXT NOT NULL,
             PRIMARY KEY (provider_id, app_type),
             FOREIGN KEY (provider_id, app_type) REFERENCES providers(id, app_type) ON DELETE CASCADE
-        )", []).map_err(|e| AppError::Database(e.to_string()))?;
+        )", []).map_err(AppError::from)?;
 
         // 10. Proxy Request Logs 表
         conn.execute("CREATE TABLE IF NOT EXISTS proxy_request_logs (
@@ -166,28 +166,31 @@ impl Database {
             total_cost_usd TEXT NOT NULL DEFAULT '0', latency_ms INTEGER NOT NULL, first_token_ms INTEGER,
             duration_ms INTEGER, status_code INTEGER NOT NULL, error_message TEXT, session_id TEXT,
             provider_type TEXT, is_streaming INTEGER NOT NULL DEFAULT 0,
-            cost_multiplier TEXT NOT NULL DEFAULT '1.0', created_at INTEGER NOT NULL
-        )", []).map_err(|e| AppError::Database(e.to_string()))?;
+            cost_multiplier TEXT NOT NULL DEFAULT '1.0', created_at INTEGER NOT NULL,
+            output_tps REAL, client_identity TEXT,
+            request_bytes INTEGER NOT NULL DEFAULT 0, response_bytes INTEGER NOT NULL DEFAULT 0,
+            tag TEXT, is_replay INTEGER NOT NULL DEFAULT 0
+        )", []).map_err(AppError::from)?;
 
         conn.execute("CREATE INDEX IF NOT EXISTS idx_request_logs_provider ON proxy_request_logs(provider_id, app_type)", [])
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
         conn.execute("CREATE INDEX IF NOT EXISTS idx_request_logs_created_at ON proxy_request_logs(created_at)", [])
-            .map_err(|e| AppError::Database(e.to_string()))?;
+            .map_err(AppError::from)?;
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_request_logs_model ON proxy_request_logs(model)",
             [],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_request_logs_session ON proxy_request_logs(session_id)",
             [],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_request_logs_status ON proxy_request_logs(status_code)",
             [],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         // 11. Model Pricing 表
         conn.execute(
@@ -199,25 +202,99 @@ impl Database {
         )",
             [],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
 
         // 12. Stream Check Logs 表
         conn.execute("CREATE TABLE IF NOT EXISTS stream_check_logs (
             id INTEGER PRIMARY KEY AUTOINCREMENT, provider_id TEXT NOT NULL, provider_name TEXT NOT NULL,
             app_type TEXT NOT NULL, status TEXT NOT NULL, success INTEGER NOT NULL, message TEXT NOT NULL,
             response_time_ms INTEGER, http_status INTEGER, model_used TEXT,
-            retry_count INTEGER DEFAULT 0, tested_at INTEGER NOT NULL
-        )", []).map_err(|e| AppError::Database(e.to_string()))?;
+            retry_count INTEGER DEFAULT 0, tested_at INTEGER NOT NULL, endpoint TEXT
+        )", []).map_err(AppError::from)?;
 
         conn.execute(
             "CREATE INDEX IF NOT EXISTS idx_stream_check_logs_provider
              ON stream_check_logs(app_type, provider_id, tested_at DESC)",
             [],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
+
+        // 流式检查每次尝试的明细（仅在发生重试时写入）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS stream_check_attempts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            log_id INTEGER NOT NULL REFERENCES stream_check_logs(id) ON DELETE CASCADE,
+            attempt INTEGER NOT NULL, success INTEGER NOT NULL, http_status INTEGER,
+            message TEXT NOT NULL, response_time_ms INTEGER, tested_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_stream_check_attempts_log
+             ON stream_check_attempts(log_id)",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_stream_check_logs_model
+             ON stream_check_logs(app_type, provider_id, model_used, tested_at DESC)",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        // 13. TPS Samples 表（按供应商、按分钟聚合的吞吐量样本）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS tps_samples (
+            app_type TEXT NOT NULL, provider_id TEXT NOT NULL, bucket_start INTEGER NOT NULL,
+            output_tokens INTEGER NOT NULL DEFAULT 0, request_count INTEGER NOT NULL DEFAULT 0,
+            active_ms INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (app_type, provider_id, bucket_start)
+        )",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_tps_samples_bucket ON tps_samples(bucket_start)",
+            [],
+        )
+        .map_err(AppError::from)?;
 
         // 注意：circuit_breaker_config 已合并到 proxy_config 表中
 
+        // 14. Probe Token Usage 表（健康检查 token 消耗，按供应商、按月累计）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS probe_token_usage (
+            app_type TEXT NOT NULL, provider_id TEXT NOT NULL, month TEXT NOT NULL,
+            tokens INTEGER NOT NULL DEFAULT 0, probes INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (app_type, provider_id, month)
+        )",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        // 15. Pending Jobs 表（待投递的 webhook 等外发任务，保证至少投递一次）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS pending_jobs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, kind TEXT NOT NULL, payload TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending', attempts INTEGER NOT NULL DEFAULT 0,
+            next_attempt_at INTEGER NOT NULL, last_error TEXT,
+            created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_pending_jobs_due
+             ON pending_jobs(status, next_attempt_at)",
+            [],
+        )
+        .map_err(AppError::from)?;
+
         // 16. Proxy Live Backup 表 (Live 配置备份)
         conn.execute(
             "CREATE TABLE IF NOT EXISTS proxy_live_backup (
@@ -225,7 +302,228 @@ impl Database {
         )",
             [],
         )
-        .map_err(|e| AppError::Database(e.to_string()))?;
+        .map_err(AppError::from)?;
+
+        // 17. Proxy Transcripts 表（开启会话记录后保存完整请求与回复）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS proxy_transcripts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, session_id TEXT NOT NULL, app_type TEXT NOT NULL,
+            provider_id TEXT NOT NULL, model TEXT NOT NULL, prompt TEXT NOT NULL,
+            completion TEXT NOT NULL, created_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_proxy_transcripts_session
+             ON proxy_transcripts(session_id, created_at DESC)",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        // 18. Provider Vault Items 表（供应商加密备注与附件）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS provider_vault_items (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, app_type TEXT NOT NULL, provider_id TEXT NOT NULL,
+            kind TEXT NOT NULL, name TEXT NOT NULL, mime_type TEXT, size INTEGER NOT NULL,
+            ciphertext BLOB NOT NULL, created_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_provider_vault_items_provider
+             ON provider_vault_items(app_type, provider_id)",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        // 19. Provider Switch History 表（当前供应商切换历史）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS provider_switch_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, app_type TEXT NOT NULL,
+            provider_id TEXT NOT NULL, previous_provider_id TEXT, source TEXT NOT NULL,
+            switched_at INTEGER NOT NULL, note TEXT
+        )",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_provider_switch_history_app
+             ON provider_switch_history(app_type, switched_at DESC)",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        // 20. Shadow Comparisons 表（影子镜像对比结果）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS shadow_comparisons (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, app_type TEXT NOT NULL,
+            primary_provider_id TEXT NOT NULL, shadow_provider_id TEXT NOT NULL,
+            model TEXT NOT NULL, primary_status INTEGER NOT NULL, shadow_status INTEGER NOT NULL,
+            primary_latency_ms INTEGER NOT NULL, shadow_latency_ms INTEGER NOT NULL,
+            primary_output_tokens INTEGER NOT NULL, shadow_output_tokens INTEGER NOT NULL,
+            similarity REAL NOT NULL, shadow_error TEXT, created_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_shadow_comparisons_app
+             ON shadow_comparisons(app_type, shadow_provider_id, created_at DESC)",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        // 21. Prompt Comparisons 表（多供应商提示词对比结果，输出以 JSON 保存）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS prompt_comparisons (
+            id TEXT PRIMARY KEY, app_type TEXT NOT NULL, prompt TEXT NOT NULL,
+            system_prompt TEXT, parallel INTEGER NOT NULL DEFAULT 0,
+            outputs TEXT NOT NULL, created_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_prompt_comparisons_app
+             ON prompt_comparisons(app_type, created_at DESC)",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        // 22. Model Substitution Evidence 表（中转站模型替换检测线索）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS model_substitution_evidence (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, app_type TEXT NOT NULL,
+            provider_id TEXT NOT NULL, signal TEXT NOT NULL, requested_model TEXT NOT NULL,
+            expected_model TEXT NOT NULL, reported_model TEXT, detail TEXT NOT NULL,
+            created_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_substitution_evidence_provider
+             ON model_substitution_evidence(app_type, provider_id, created_at DESC)",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        // 23. Config Snapshots 表（供应商表与设置的定期快照，只在变化时保存）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS config_snapshots (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, taken_at INTEGER NOT NULL,
+            content TEXT NOT NULL, changes TEXT NOT NULL DEFAULT '[]',
+            change_count INTEGER NOT NULL DEFAULT 0
+        )",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_config_snapshots_taken_at
+             ON config_snapshots(taken_at)",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        // 24. API Tokens 表（本地管理 API 令牌，只保存摘要）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS api_tokens (
+            id TEXT PRIMARY KEY, name TEXT NOT NULL, token_hash TEXT NOT NULL UNIQUE,
+            scopes TEXT NOT NULL, bootstrap INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL, last_used_at INTEGER
+        )",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        // 25. Queued Requests 表（转发中的非流式请求，代理重启后重新发送）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS queued_requests (
+            id TEXT PRIMARY KEY, endpoint TEXT NOT NULL, headers TEXT NOT NULL,
+            body TEXT NOT NULL, status TEXT NOT NULL,
+            response_status INTEGER, response_body TEXT,
+            created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        // 26. Background Tasks 表（耗时命令的进度与部分结果，界面重新加载后可重新获取）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS background_tasks (
+            id TEXT PRIMARY KEY, kind TEXT NOT NULL, status TEXT NOT NULL,
+            done INTEGER NOT NULL DEFAULT 0, total INTEGER NOT NULL DEFAULT 0,
+            results TEXT NOT NULL DEFAULT '[]', error TEXT,
+            created_at INTEGER NOT NULL, updated_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        // 27. Provider Usage Totals 表（经代理成功请求的累计用量，不随请求日志清理而减少）
+        let seed_usage_totals = !Self::table_exists(conn, "provider_usage_totals")?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS provider_usage_totals (
+            app_type TEXT NOT NULL, provider_id TEXT NOT NULL,
+            request_count INTEGER NOT NULL DEFAULT 0, total_tokens INTEGER NOT NULL DEFAULT 0,
+            first_used_at INTEGER, last_used_at INTEGER,
+            PRIMARY KEY (app_type, provider_id)
+        )",
+            [],
+        )
+        .map_err(AppError::from)?;
+        if seed_usage_totals {
+            // 首次创建时从现有请求日志补齐
+            conn.execute(
+                "INSERT INTO provider_usage_totals
+                    (app_type, provider_id, request_count, total_tokens, first_used_at, last_used_at)
+                 SELECT app_type, provider_id, COUNT(*), COALESCE(SUM(input_tokens + output_tokens), 0),
+                        MIN(created_at), MAX(created_at)
+                 FROM proxy_request_logs
+                 WHERE status_code >= 200 AND status_code < 300
+                 GROUP BY app_type, provider_id",
+                [],
+            )
+            .map_err(AppError::from)?;
+        }
+
+        // 28. Audit Log 表（cc-switch 自动执行的修复等操作记录）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, created_at INTEGER NOT NULL,
+            category TEXT NOT NULL, app_type TEXT, provider_id TEXT,
+            message TEXT NOT NULL, detail TEXT
+        )",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        // 29. Timeline Events 表（故障、恢复与预算事件，与切换历史合并为时间线）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS timeline_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, app_type TEXT NOT NULL,
+            provider_id TEXT NOT NULL, kind TEXT NOT NULL, message TEXT NOT NULL,
+            occurred_at INTEGER NOT NULL, note TEXT
+        )",
+            [],
+        )
+        .map_err(AppError::from)?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_timeline_events_app
+             ON timeline_events(app_type, occurred_at DESC)",
+            [],
+        )
+        .map_err(AppError::from)?;
 
         // 尝试添加 live_takeover_active 列到 proxy_config 表
         let _ = conn.execute(
@@ -331,6 +629,41 @@ impl Database {
                         Self::migrate_v1_to_v2(conn)?;
                         Self::set_user_version(conn, 2)?;
                     }
+                    2 => {
+                        log::info!("迁移数据库从 v2 到 v3（请求日志添加流式输出速度字段）");
+                        Self::migrate_v2_to_v3(conn)?;
+                        Self::set_user_version(conn, 3)?;
+                    }
+                    3 => {
+                        log::info!("迁移数据库从 v3 到 v4（流式检查日志添加端点字段）");
+                        Self::migrate_v3_to_v4(conn)?;
+                        Self::set_user_version(conn, 4)?;
+                    }
+                    4 => {
+                        log::info!("迁移数据库从 v4 到 v5（请求日志添加客户端身份字段）");
+                        Self::migrate_v4_to_v5(conn)?;
+                        Self::set_user_version(conn, 5)?;
+                    }
+                    5 => {
+                        log::info!("迁移数据库从 v5 到 v6（请求日志添加流量字段）");
+                        Self::migrate_v5_to_v6(conn)?;
+                        Self::set_user_version(conn, 6)?;
+                    }
+                    6 => {
+                        log::info!("迁移数据库从 v6 到 v7（切换历史添加备注字段）");
+                        Self::migrate_v6_to_v7(conn)?;
+                        Self::set_user_version(conn, 7)?;
+                    }
+                    7 => {
+                        log::info!("迁移数据库从 v7 到 v8（请求日志添加标签字段）");
+                        Self::migrate_v7_to_v8(conn)?;
+                        Self::set_user_version(conn, 8)?;
+                    }
+                    8 => {
+                        log::info!("迁移数据库从 v8 到 v9（请求日志添加回放标记）");
+                        Self::migrate_v8_to_v9(conn)?;
+                        Self::set_user_version(conn, 9)?;
+                    }
                     _ => {
                         return Err(AppError::Database(format!(
                             "未知的数据库版本 {version}，无法迁移到 {SCHEMA_VERSION}"
@@ -552,6 +885,73 @@ impl Database {
         Ok(())
     }
 
+    /// v2 -> v3 迁移：请求日志添加单请求流式输出速度（token/秒）
+    fn migrate_v2_to_v3(conn: &Connection) -> Result<(), AppError> {
+        Self::add_column_if_missing(conn, "proxy_request_logs", "output_tps", "REAL")?;
+        Ok(())
+    }
+
+    /// v3 -> v4 迁移：stream_check_logs 添加 endpoint 字段
+    fn migrate_v3_to_v4(conn: &Connection) -> Result<(), AppError> {
+        // 表不存在时由 create_tables 按新结构创建
+        if Self::table_exists(conn, "stream_check_logs")? {
+            Self::add_column_if_missing(conn, "stream_check_logs", "endpoint", "TEXT")?;
+        }
+        Ok(())
+    }
+
+    /// v4 -> v5 迁移：请求日志记录实际发送的客户端身份（User-Agent 等）
+    fn migrate_v4_to_v5(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_request_logs")? {
+            Self::add_column_if_missing(conn, "proxy_request_logs", "client_identity", "TEXT")?;
+        }
+        Ok(())
+    }
+
+    /// v5 -> v6 迁移：请求日志记录与上游之间传输的字节数
+    fn migrate_v5_to_v6(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_request_logs")? {
+            for column in ["request_bytes", "response_bytes"] {
+                Self::add_column_if_missing(
+                    conn,
+                    "proxy_request_logs",
+                    column,
+                    "INTEGER NOT NULL DEFAULT 0",
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// v6 -> v7 迁移：切换历史支持附加备注
+    fn migrate_v6_to_v7(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "provider_switch_history")? {
+            Self::add_column_if_missing(conn, "provider_switch_history", "note", "TEXT")?;
+        }
+        Ok(())
+    }
+
+    /// v7 -> v8 迁移：请求日志记录客户端指定的标签
+    fn migrate_v7_to_v8(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_request_logs")? {
+            Self::add_column_if_missing(conn, "proxy_request_logs", "tag", "TEXT")?;
+        }
+        Ok(())
+    }
+
+    /// v8 -> v9 迁移：请求日志标记回放请求
+    fn migrate_v8_to_v9(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_request_logs")? {
+            Self::add_column_if_missing(
+                conn,
+                "proxy_request_logs",
+                "is_replay",
+                "INTEGER NOT NULL DEFAULT 0",
+            )?;
+        }
+        Ok(())
+    }
+
     /// 将 proxy_config 迁移为三行结构（每应用独立配置）
     fn migrate_proxy_config_to_per_app(conn: &Connection) -> Result<(), AppError> {
         // 检查是否已经是新表结构（幂等性）
@@ -1133,7 +1533,7 @@ impl Database {
         let mut rows = stmt
             .query([])
             .map_err(|e| AppError::Database(format!("查询表名失败: {e}")))?;
-        while let Some(row) = rows.next().map_err(|e| AppError::Database(e.to_string()))? {
+        while let Some(row) = rows.next().map_err(AppError::from)? {
             let name: String = row
                 .get(0)
                 .map_err(|e| AppError::Database(format!("解析表名失败: {e}")))?;
@@ -1159,7 +1559,7 @@ impl Database {
         let mut rows = stmt
             .query([])
             .map_err(|e| AppError::Database(format!("查询表结构失败: {e}")))?;
-        while let Some(row) = rows.next().map_err(|e| AppError::Database(e.to_string()))? {
+        while let Some(row) = rows.next().map_err(AppError::from)? {
             let name: String = row
                 .get(1)
                 .map_err(|e| AppError::Database(format!("读取列名失败: {e}")))?;
@@ -378,3 +378,807 @@ fn model_pricing_is_seeded_on_init() {
         gemini_count
     );
 }
+
+#[test]
+fn tps_samples_accumulate_and_prune() {
+    use crate::proxy::types::TpsSample;
+
+    let db = Database::memory().expect("create memory db");
+    let sample = |bucket_start: i64, tokens: u64| TpsSample {
+        app_type: "claude".to_string(),
+        provider_id: "p1".to_string(),
+        bucket_start,
+        output_tokens: tokens,
+        request_count: 1,
+        active_ms: 1000,
+    };
+
+    db.save_tps_samples(&[sample(60, 100), sample(120, 10)])
+        .expect("save samples");
+    db.save_tps_samples(&[sample(60, 50)])
+        .expect("save samples");
+
+    let all = db
+        .get_tps_samples(Some("claude"), None, 0)
+        .expect("query samples");
+    assert_eq!(all.len(), 2);
+    assert_eq!(all[0].output_tokens, 150);
+    assert_eq!(all[0].request_count, 2);
+
+    assert_eq!(db.prune_tps_samples(120).expect("prune"), 1);
+    let remaining = db.get_tps_samples(None, None, 0).expect("query samples");
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].bucket_start, 120);
+}
+
+#[test]
+fn transcripts_search_and_prune() {
+    let db = Database::memory().expect("create memory db");
+    db.insert_transcript("s1", "claude", "p1", "m", r#"{"q":"hello"}"#, "world", 100)
+        .expect("insert transcript");
+    db.insert_transcript("s2", "codex", "p2", "m", r#"{"q":"other"}"#, "reply", 200)
+        .expect("insert transcript");
+
+    let found = db
+        .list_transcripts(None, Some("world"), 10)
+        .expect("search transcripts");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].session_id, "s1");
+    assert_eq!(
+        db.list_transcripts(Some("s2"), None, 10)
+            .expect("list by session")
+            .len(),
+        1
+    );
+
+    assert_eq!(db.prune_transcripts(150).expect("prune"), 1);
+    assert_eq!(db.delete_transcripts(None).expect("delete"), 1);
+}
+
+#[test]
+fn merge_provider_history_moves_records() {
+    use crate::proxy::types::TpsSample;
+
+    let db = Database::memory().expect("create memory db");
+    for id in ["keep", "dup"] {
+        let provider = Provider::with_id(id.into(), "Relay".into(), json!({}), None);
+        db.save_provider("claude", &provider)
+            .expect("save provider");
+    }
+    let sample = |provider_id: &str| TpsSample {
+        app_type: "claude".to_string(),
+        provider_id: provider_id.to_string(),
+        bucket_start: 60,
+        output_tokens: 100,
+        request_count: 1,
+        active_ms: 1000,
+    };
+    db.save_tps_samples(&[sample("keep"), sample("dup")])
+        .expect("save samples");
+    db.insert_transcript("s1", "claude", "dup", "m", "{}", "reply", 100)
+        .expect("insert transcript");
+    db.record_provider_switch("claude", "dup", Some("keep"), "manual", 100)
+        .expect("record switch");
+    db.record_timeline_event("claude", "dup", TimelineKind::Incident, "down", 100)
+        .expect("record timeline event");
+    db.record_audit("keyReveal", Some("claude"), Some("dup"), "revealed", None)
+        .expect("record audit");
+    {
+        let conn = db.conn.lock().expect("lock conn");
+        conn.execute(
+            "INSERT INTO shadow_comparisons (app_type, primary_provider_id, shadow_provider_id,
+                model, primary_status, shadow_status, primary_latency_ms, shadow_latency_ms,
+                primary_output_tokens, shadow_output_tokens, similarity, created_at)
+             VALUES ('claude', 'keep', 'dup', 'm', 200, 200, 1, 1, 1, 1, 1.0, 100)",
+            [],
+        )
+        .expect("insert shadow comparison");
+        conn.execute(
+            "INSERT INTO model_substitution_evidence (app_type, provider_id, signal,
+                requested_model, expected_model, detail, created_at)
+             VALUES ('claude', 'dup', 'tps', 'm', 'm', '', 100)",
+            [],
+        )
+        .expect("insert substitution evidence");
+    }
+
+    db.merge_provider_history("claude", "dup", "keep")
+        .expect("merge providers");
+
+    {
+        let conn = db.conn.lock().expect("lock conn");
+        for (table, column) in [
+            ("provider_switch_history", "provider_id"),
+            ("provider_switch_history", "previous_provider_id"),
+            ("shadow_comparisons", "shadow_provider_id"),
+            ("model_substitution_evidence", "provider_id"),
+            ("timeline_events", "provider_id"),
+            ("audit_log", "provider_id"),
+        ] {
+            let remaining: i64 = conn
+                .query_row(
+                    &format!("SELECT COUNT(*) FROM {table} WHERE {column} = 'dup'"),
+                    [],
+                    |row| row.get(0),
+                )
+                .expect("count remaining rows");
+            assert_eq!(remaining, 0, "{table}.{column} 仍引用被合并的供应商");
+        }
+        let moved: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM audit_log WHERE provider_id = 'keep'",
+                [],
+                |row| row.get(0),
+            )
+            .expect("count moved audit rows");
+        assert_eq!(moved, 1);
+    }
+
+    assert!(db
+        .get_provider_by_id("dup", "claude")
+        .expect("query provider")
+        .is_none());
+    let samples = db
+        .get_tps_samples(Some("claude"), None, 0)
+        .expect("query samples");
+    assert_eq!(samples.len(), 1);
+    assert_eq!(samples[0].provider_id, "keep");
+    assert_eq!(samples[0].output_tokens, 200);
+    let transcripts = db
+        .list_transcripts(Some("s1"), None, 10)
+        .expect("list transcripts");
+    assert_eq!(transcripts[0].provider_id, "keep");
+}
+
+#[test]
+fn stream_check_config_migrates_legacy_blob() {
+    let db = Database::memory().expect("create memory db");
+    // 无版本号、snake_case 字段、缺少 geminiModel
+    db.set_setting(
+        "stream_check_config",
+        r#"{"timeout_secs":30,"maxRetries":1,"degradedThresholdMs":5000,"claudeModel":"c","codexModel":"x"}"#,
+    )
+    .expect("seed legacy config");
+
+    let config = db.get_stream_check_config().expect("load migrated config");
+    assert_eq!(config.timeout_secs, 30);
+    assert_eq!(config.max_retries, 1);
+    assert_eq!(
+        config.gemini_model,
+        crate::services::stream_check::StreamCheckConfig::default().gemini_model
+    );
+
+    // 迁移结果已写回并带上版本号
+    let stored: serde_json::Value = serde_json::from_str(
+        &db.get_setting("stream_check_config")
+            .expect("read setting")
+            .expect("setting exists"),
+    )
+    .expect("stored json");
+    assert_eq!(stored["_version"], json!(1));
+    assert!(stored.get("timeout_secs").is_none());
+}
+
+#[test]
+fn stream_check_logs_filter_by_model() {
+    use crate::services::stream_check::StreamCheckResult;
+
+    let db = Database::memory().expect("create memory db");
+    for (model, success, tested_at) in [("haiku", true, 100), ("opus", false, 200)] {
+        let result = StreamCheckResult {
+            success,
+            model_used: model.to_string(),
+            tested_at,
+            endpoint: "https://api.example.com".to_string(),
+            ..StreamCheckResult::failed("")
+        };
+        db.save_stream_check_log("p1", "P1", "claude", &result)
+            .expect("save log");
+    }
+
+    let latest = db
+        .get_stream_check_latest("p1", "claude", None)
+        .expect("latest")
+        .expect("has log");
+    assert_eq!(latest.model_used, "opus");
+
+    let haiku = db
+        .get_stream_check_latest("p1", "claude", Some("haiku"))
+        .expect("latest haiku")
+        .expect("has haiku log");
+    assert!(haiku.success);
+    assert_eq!(haiku.endpoint, "https://api.example.com");
+
+    let history = db
+        .get_stream_check_history("p1", "claude", Some("opus"), 10)
+        .expect("history");
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].model_used, "opus");
+}
+
+#[test]
+fn stream_check_latest_includes_attempts() {
+    use crate::services::stream_check::{StreamCheckAttempt, StreamCheckResult};
+
+    let db = Database::memory().expect("create memory db");
+    let attempt = |attempt, success, http_status| StreamCheckAttempt {
+        attempt,
+        success,
+        http_status,
+        message: String::new(),
+        response_time_ms: Some(10),
+        tested_at: 100,
+    };
+    let result = StreamCheckResult {
+        success: true,
+        retry_count: 1,
+        tested_at: 100,
+        attempts: vec![attempt(0, false, Some(503)), attempt(1, true, Some(200))],
+        ..StreamCheckResult::failed("")
+    };
+    db.save_stream_check_log("p1", "P1", "claude", &result)
+        .expect("save log");
+
+    let latest = db
+        .get_stream_check_latest("p1", "claude", None)
+        .expect("latest")
+        .expect("has log");
+    assert_eq!(latest.attempts.len(), 2);
+    assert_eq!(latest.attempts[0].http_status, Some(503));
+    assert!(latest.attempts[1].success);
+}
+
+#[test]
+fn stream_check_latest_skips_cancelled() {
+    use crate::services::stream_check::{HealthStatus, StreamCheckResult};
+
+    let db = Database::memory().expect("create memory db");
+    let finished = StreamCheckResult {
+        success: true,
+        tested_at: 100,
+        ..StreamCheckResult::failed("")
+    };
+    let cancelled = StreamCheckResult {
+        tested_at: 200,
+        ..StreamCheckResult::cancelled("haiku".to_string())
+    };
+    for result in [&finished, &cancelled] {
+        db.save_stream_check_log("p1", "P1", "claude", result)
+            .expect("save log");
+    }
+
+    let latest = db
+        .get_stream_check_latest("p1", "claude", None)
+        .expect("latest")
+        .expect("has log");
+    assert!(latest.success);
+
+    let history = db
+        .get_stream_check_history("p1", "claude", None, 10)
+        .expect("history");
+    assert_eq!(history[0].status, HealthStatus::Cancelled);
+}
+
+#[test]
+fn pending_jobs_retry_until_dead_letter() {
+    let db = Database::memory().expect("create memory db");
+    let id = db.enqueue_job("webhook", "{}", 100).expect("enqueue");
+
+    assert!(db.get_due_jobs(99, 10).expect("due").is_empty());
+    assert_eq!(db.get_due_jobs(100, 10).expect("due").len(), 1);
+
+    // 失败后按退避时间重新排队
+    db.mark_job_failed(id, "HTTP 502", Some(200), 100)
+        .expect("fail");
+    assert!(db.get_due_jobs(150, 10).expect("due").is_empty());
+    let job = &db.get_due_jobs(200, 10).expect("due")[0];
+    assert_eq!(job.attempts, 1);
+    assert_eq!(job.last_error.as_deref(), Some("HTTP 502"));
+
+    // 超过重试次数进入死信，手动重试后恢复
+    db.mark_job_failed(id, "timeout", None, 200).expect("dead");
+    assert!(db.get_due_jobs(10_000, 10).expect("due").is_empty());
+    let dead = db.list_jobs(Some(JobStatus::Dead), 10).expect("list");
+    assert_eq!(dead.len(), 1);
+    assert!(db.retry_job(id, 300).expect("retry"));
+    assert_eq!(db.get_due_jobs(300, 10).expect("due")[0].attempts, 0);
+
+    db.mark_job_done(id, 300).expect("done");
+    assert_eq!(db.prune_done_jobs(301).expect("prune"), 1);
+}
+
+#[test]
+fn switch_history_lists_recent_providers() {
+    let db = Database::memory().expect("create memory db");
+    assert!(db.get_previous_provider("claude").expect("prev").is_none());
+
+    db.record_provider_switch("claude", "a", None, "ui", 100)
+        .expect("record");
+    db.record_provider_switch("claude", "b", Some("a"), "tray", 200)
+        .expect("record");
+    db.record_provider_switch("claude", "a", Some("b"), "ui", 300)
+        .expect("record");
+    db.record_provider_switch("codex", "c", None, "ui", 400)
+        .expect("record");
+
+    let recent = db.get_recent_providers("claude", 5).expect("recent");
+    let ids: Vec<_> = recent.iter().map(|r| r.provider_id.as_str()).collect();
+    assert_eq!(ids, vec!["a", "b"]);
+    assert_eq!(recent[0].switched_at, 300);
+    assert_eq!(recent[1].source, "tray");
+    assert_eq!(
+        db.get_previous_provider("claude").expect("prev").as_deref(),
+        Some("b")
+    );
+}
+
+#[test]
+fn timeline_interleaves_switches_and_events() {
+    let db = Database::memory().expect("create memory db");
+    db.record_provider_switch("claude", "a", None, "ui", 100)
+        .expect("record");
+    let incident = db
+        .record_timeline_event("claude", "a", TimelineKind::Incident, "HTTP 503", 200)
+        .expect("event");
+    db.record_provider_switch("claude", "b", Some("a"), "failover", 300)
+        .expect("record");
+    db.record_timeline_event("codex", "c", TimelineKind::Budget, "budget", 400)
+        .expect("event");
+
+    let query = TimelineQuery {
+        app_type: Some("claude".to_string()),
+        ..TimelineQuery::default()
+    };
+    let timeline = db.get_timeline(&query, 10).expect("timeline");
+    let kinds: Vec<_> = timeline.iter().map(|e| e.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            TimelineKind::Switch,
+            TimelineKind::Incident,
+            TimelineKind::Switch
+        ]
+    );
+    assert_eq!(timeline[0].source.as_deref(), Some("failover"));
+
+    let switch_id = timeline[0].id;
+    assert!(db
+        .set_timeline_note(TimelineKind::Switch, switch_id, Some("outage #12"))
+        .expect("note"));
+    assert!(db
+        .set_timeline_note(TimelineKind::Incident, incident, Some("relay down"))
+        .expect("note"));
+    // 类型与 ID 不匹配时不修改
+    assert!(!db
+        .set_timeline_note(TimelineKind::Budget, incident, Some("x"))
+        .expect("note"));
+
+    let since = TimelineQuery {
+        since: Some(200),
+        until: Some(300),
+        ..query
+    };
+    let timeline = db.get_timeline(&since, 10).expect("timeline");
+    let notes: Vec<_> = timeline.iter().map(|e| e.note.as_deref()).collect();
+    assert_eq!(notes, vec![Some("outage #12"), Some("relay down")]);
+}
+
+#[test]
+fn shadow_comparisons_summarize_by_shadow_provider() {
+    let db = Database::memory().expect("create memory db");
+    let record =
+        |shadow: &str, latency: u64, similarity: f64, error: Option<&str>| ShadowComparison {
+            id: 0,
+            app_type: "claude".to_string(),
+            primary_provider_id: "main".to_string(),
+            shadow_provider_id: shadow.to_string(),
+            model: "claude-sonnet".to_string(),
+            primary_status: 200,
+            shadow_status: if error.is_some() { 0 } else { 200 },
+            primary_latency_ms: 1000,
+            shadow_latency_ms: latency,
+            primary_output_tokens: 100,
+            shadow_output_tokens: 80,
+            similarity,
+            shadow_error: error.map(str::to_string),
+            created_at: 100,
+        };
+    db.insert_shadow_comparison(&record("relay", 800, 0.8, None))
+        .expect("insert");
+    db.insert_shadow_comparison(&record("relay", 1200, 0.6, None))
+        .expect("insert");
+    db.insert_shadow_comparison(&record("relay", 50, 0.0, Some("timeout")))
+        .expect("insert");
+
+    let summary = db.get_shadow_summary("claude").expect("summary");
+    assert_eq!(summary.len(), 1);
+    assert_eq!(summary[0].samples, 3);
+    assert_eq!(summary[0].shadow_failures, 1);
+    assert!((summary[0].avg_shadow_latency_ms - 1000.0).abs() < 1e-9);
+    assert!((summary[0].avg_similarity - 0.7).abs() < 1e-9);
+
+    let recent = db.get_shadow_comparisons("claude", 2).expect("list");
+    assert_eq!(recent.len(), 2);
+    assert_eq!(recent[0].shadow_error.as_deref(), Some("timeout"));
+    assert_eq!(db.clear_shadow_comparisons("claude").expect("clear"), 3);
+}
+
+#[test]
+fn prompt_comparisons_round_trip() {
+    use crate::services::prompt_compare::{PromptCompareOutput, PromptComparison};
+
+    let db = Database::memory().expect("create memory db");
+    let output = PromptCompareOutput {
+        provider_id: "relay".to_string(),
+        provider_name: "Relay".to_string(),
+        model_requested: "claude-sonnet".to_string(),
+        model_reported: Some("claude-haiku".to_string()),
+        success: true,
+        http_status: Some(200),
+        latency_ms: 900,
+        input_tokens: 10,
+        output_tokens: 20,
+        text: "hello".to_string(),
+        similarity: Some(1.0),
+        error: None,
+    };
+    for (id, at) in [("a", 100), ("b", 200)] {
+        db.save_prompt_comparison(&PromptComparison {
+            id: id.to_string(),
+            app_type: "claude".to_string(),
+            prompt: "hi".to_string(),
+            system: None,
+            parallel: false,
+            created_at: at,
+            outputs: vec![output.clone()],
+        })
+        .expect("save");
+    }
+
+    let list = db.list_prompt_comparisons("claude", 10).expect("list");
+    assert_eq!(
+        list.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(),
+        ["b", "a"]
+    );
+    let loaded = db.get_prompt_comparison("a").expect("get").expect("exists");
+    assert_eq!(
+        loaded.outputs[0].model_reported.as_deref(),
+        Some("claude-haiku")
+    );
+    assert!(db.delete_prompt_comparison("a").expect("delete"));
+    assert!(db.get_prompt_comparison("a").expect("get").is_none());
+}
+
+#[test]
+fn substitution_evidence_marks_suspects_at_threshold() {
+    let db = Database::memory().expect("memory db");
+    let evidence = |provider: &str, signal: &str, at: i64| SubstitutionEvidence {
+        id: 0,
+        app_type: "claude".to_string(),
+        provider_id: provider.to_string(),
+        signal: signal.to_string(),
+        requested_model: "claude-opus-4-1".to_string(),
+        expected_model: "claude-opus-4-1".to_string(),
+        reported_model: Some("claude-sonnet-4-5".to_string()),
+        detail: "test".to_string(),
+        created_at: at,
+    };
+    for (signal, at) in [
+        ("tier_downgrade", 100),
+        ("speed_outlier", 200),
+        ("tier_downgrade", 300),
+    ] {
+        db.insert_substitution_evidence(&evidence("relay", signal, at))
+            .expect("insert");
+    }
+    db.insert_substitution_evidence(&evidence("other", "model_mismatch", 300))
+        .expect("insert");
+
+    let suspects = db.get_substitution_suspects("claude", 0).expect("suspects");
+    assert_eq!(suspects.len(), 2);
+    assert_eq!(suspects[0].provider_id, "relay");
+    assert!(suspects[0].suspected);
+    assert_eq!(suspects[0].signals, ["speed_outlier", "tier_downgrade"]);
+    assert_eq!(suspects[0].last_seen_at, 300);
+    assert!(!suspects[1].suspected);
+
+    // 窗口之外的证据不计入
+    let recent = db
+        .get_substitution_suspect("claude", "relay", 150)
+        .expect("suspect")
+        .expect("exists");
+    assert_eq!(recent.evidence_count, 2);
+    assert!(!recent.suspected);
+
+    assert_eq!(
+        db.clear_substitution_evidence("claude", Some("relay"))
+            .expect("clear"),
+        3
+    );
+    assert_eq!(
+        db.get_substitution_evidence("claude", None, 10)
+            .expect("list")
+            .len(),
+        1
+    );
+}
+
+#[test]
+fn config_snapshots_keep_latest_as_baseline() {
+    use crate::services::config_snapshot::{ChangeKind, ConfigChange};
+
+    let db = Database::memory().expect("memory db");
+    let change = ConfigChange {
+        path: "settings/language".to_string(),
+        kind: ChangeKind::Modified,
+        before: Some(serde_json::json!("zh")),
+        after: Some(serde_json::json!("en")),
+    };
+    db.insert_config_snapshot(100, &serde_json::json!({ "v": 1 }), &[])
+        .expect("insert");
+    let id = db
+        .insert_config_snapshot(
+            200,
+            &serde_json::json!({ "v": 2 }),
+            std::slice::from_ref(&change),
+        )
+        .expect("insert");
+
+    let baseline = db
+        .get_config_snapshot_at_or_before(150)
+        .expect("query")
+        .expect("exists");
+    assert_eq!(baseline.taken_at, 100);
+    assert!(db
+        .get_config_snapshot_at_or_before(50)
+        .expect("query")
+        .is_none());
+    assert_eq!(
+        db.get_first_config_snapshot_after(50)
+            .expect("query")
+            .map(|s| s.taken_at),
+        Some(100)
+    );
+    let stored = db.get_config_snapshot(id).expect("get").expect("exists");
+    assert_eq!(stored.changes, vec![change]);
+    assert_eq!(
+        db.list_config_snapshots(Some(150), 10).expect("list")[0].change_count,
+        1
+    );
+
+    // 全部过期时仍保留最新一份
+    assert_eq!(db.prune_config_snapshots(1_000).expect("prune"), 1);
+    assert_eq!(
+        db.get_latest_config_snapshot()
+            .expect("latest")
+            .map(|s| s.id),
+        Some(id)
+    );
+}
+
+#[test]
+fn queued_requests_resume_lifecycle() {
+    use crate::database::{QueuedRequest, QueuedRequestStatus};
+
+    let db = Database::memory().expect("memory db");
+    let record = |id: &str, created_at: i64| QueuedRequest {
+        id: id.to_string(),
+        endpoint: "/v1/messages".to_string(),
+        headers: vec![("anthropic-version".to_string(), "2023-06-01".to_string())],
+        body: r#"{"model":"claude"}"#.to_string(),
+        status: QueuedRequestStatus::Pending,
+        response_status: None,
+        response_body: None,
+        created_at,
+        updated_at: 0,
+    };
+    assert!(db
+        .insert_queued_request(&record("old", 100))
+        .expect("insert"));
+    assert!(db
+        .insert_queued_request(&record("recent", 900))
+        .expect("insert"));
+    assert!(db
+        .insert_queued_request(&record("live", 1_000))
+        .expect("insert"));
+    // 幂等键重复时不覆盖
+    assert!(!db
+        .insert_queued_request(&record("old", 500))
+        .expect("insert"));
+
+    assert_eq!(db.expire_queued_requests(500, 1_000).expect("expire"), 1);
+    let unanswered: Vec<_> = db
+        .get_unanswered_queued_requests(1_000)
+        .expect("query")
+        .into_iter()
+        .map(|r| r.id)
+        .collect();
+    assert_eq!(unanswered, vec!["recent".to_string()]);
+
+    db.finish_queued_request(
+        "recent",
+        QueuedRequestStatus::Completed,
+        Some(200),
+        Some("{}"),
+        1_001,
+    )
+    .expect("finish");
+    let stored = db
+        .get_queued_request("recent")
+        .expect("get")
+        .expect("exists");
+    assert_eq!(stored.status, QueuedRequestStatus::Completed);
+    assert_eq!(stored.response_status, Some(200));
+    assert_eq!(stored.headers.len(), 1);
+
+    // 只清理已结束的记录
+    assert_eq!(db.prune_queued_requests(2_000).expect("prune"), 2);
+    assert!(db.delete_queued_request("live").expect("delete"));
+    assert!(db.list_queued_requests().expect("list").is_empty());
+}
+
+#[test]
+fn prune_oldest_request_logs_shrinks_database() {
+    let db = Database::memory().expect("create memory db");
+    {
+        let conn = db.conn.lock().expect("lock conn");
+        let padding = "x".repeat(4096);
+        for i in 0..50 {
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model, latency_ms, status_code,
+                    error_message, created_at
+                ) VALUES (?1, 'p1', 'claude', 'claude-3', 100, 500, ?2, ?3)",
+                rusqlite::params![format!("req{i:02}"), padding, i],
+            )
+            .expect("insert log");
+        }
+    }
+
+    let before = db.database_used_bytes().expect("size");
+    assert_eq!(db.prune_oldest_request_logs(40).expect("prune"), 40);
+    assert_eq!(db.count_request_logs().expect("count"), 10);
+    assert!(db.database_used_bytes().expect("size") < before);
+    db.vacuum().expect("vacuum");
+
+    // 保留的是最新的记录
+    let conn = db.conn.lock().expect("lock conn");
+    let oldest: String = conn
+        .query_row(
+            "SELECT request_id FROM proxy_request_logs ORDER BY created_at LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .expect("query");
+    assert_eq!(oldest, "req40");
+}
+
+#[test]
+fn save_providers_writes_batch_across_apps() {
+    let db = Database::memory().expect("create memory db");
+    let mut tagged = Provider::with_id("a".into(), "A".into(), json!({}), None);
+    tagged.meta = Some(crate::provider::ProviderMeta {
+        tags: vec!["team".to_string()],
+        ..Default::default()
+    });
+    let batch = vec![
+        ("claude".to_string(), tagged),
+        (
+            "codex".to_string(),
+            Provider::with_id("b".into(), "B".into(), json!({}), None),
+        ),
+    ];
+    db.save_providers(&batch).expect("save batch");
+
+    let saved = db
+        .get_provider_by_id("a", "claude")
+        .expect("query provider")
+        .expect("provider exists");
+    assert_eq!(saved.meta.expect("meta").tags, vec!["team"]);
+    assert!(db
+        .get_provider_by_id("b", "codex")
+        .expect("query provider")
+        .is_some());
+}
+
+#[test]
+fn provider_activity_tracks_usage_and_flags_idle() {
+    let db = Database::memory().expect("create memory db");
+    let now = 200 * 86_400;
+    for (id, created_days_ago) in [("busy", 100), ("dead", 90), ("fresh", 3)] {
+        let mut provider = Provider::with_id(id.into(), id.into(), json!({}), None);
+        provider.created_at = Some((now - created_days_ago * 86_400) * 1000);
+        db.save_provider("claude", &provider)
+            .expect("save provider");
+    }
+    db.record_provider_usage("claude", "busy", 300, now - 86_400)
+        .expect("record usage");
+    db.record_provider_usage("claude", "busy", 200, now - 2 * 86_400)
+        .expect("record usage");
+    db.record_provider_usage("claude", "dead", 5000, now - 70 * 86_400)
+        .expect("record usage");
+
+    let recent = db
+        .get_provider_activity(Some("claude"), ProviderActivitySort::Recent, 60, now)
+        .expect("query activity");
+    let ids: Vec<_> = recent.iter().map(|a| a.provider_id.as_str()).collect();
+    assert_eq!(ids, vec!["busy", "dead", "fresh"]);
+    assert_eq!(recent[0].request_count, 2);
+    assert_eq!(recent[0].total_tokens, 500);
+    assert_eq!(recent[0].first_used_at, Some(now - 2 * 86_400));
+    assert_eq!(recent[0].idle_days, Some(1));
+    assert!(!recent[0].idle);
+    assert!(recent[1].idle);
+    assert_eq!(recent[2].idle_days, Some(3));
+    assert!(!recent[2].idle);
+
+    let by_tokens = db
+        .get_provider_activity(None, ProviderActivitySort::Tokens, 60, now)
+        .expect("query activity");
+    assert_eq!(by_tokens[0].provider_id, "dead");
+}
+
+#[test]
+fn audit_log_filters_by_category_newest_first() {
+    let db = Database::memory().expect("create memory db");
+    db.record_audit(
+        "codexAuthRepair",
+        Some("codex"),
+        Some("relay"),
+        "restored key",
+        Some(&json!({ "liveFields": ["tokens"] })),
+    )
+    .expect("record audit");
+    db.record_audit("other", None, None, "second", None)
+        .expect("record audit");
+
+    let all = db.get_audit_log(None, 10).expect("query audit log");
+    assert_eq!(all.len(), 2);
+    assert_eq!(all[0].message, "second");
+
+    let repairs = db
+        .get_audit_log(Some("codexAuthRepair"), 10)
+        .expect("query audit log");
+    assert_eq!(repairs.len(), 1);
+    assert_eq!(repairs[0].provider_id.as_deref(), Some("relay"));
+    assert_eq!(
+        repairs[0].detail.as_ref().unwrap()["liveFields"][0],
+        "tokens"
+    );
+}
+
+#[test]
+fn slo_counts_skip_client_errors() {
+    use crate::services::latency_slo::SloCounts;
+
+    let db = Database::memory().expect("create memory db");
+    {
+        let conn = db.conn.lock().expect("lock conn");
+        // (状态码, 延迟, 首 token 时间, 时间)
+        let rows: [(i64, i64, Option<i64>, i64); 8] = [
+            (200, 800, None, 100),
+            (200, 9000, Some(900), 100),
+            (200, 4000, None, 100),
+            (500, 100, None, 100),
+            (429, 100, None, 100),
+            (400, 100, None, 100),
+            (401, 100, None, 100),
+            (200, 100, None, 10),
+        ];
+        for (i, (status, latency, first_token, created_at)) in rows.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model, latency_ms,
+                    first_token_ms, status_code, created_at
+                ) VALUES (?1, 'p1', 'claude', 'm1', ?2, ?3, ?4, ?5)",
+                rusqlite::params![format!("r{i}"), latency, first_token, status, created_at],
+            )
+            .expect("insert log");
+        }
+    }
+
+    assert_eq!(
+        db.get_slo_counts("p1", "claude", 50, 3000).expect("count"),
+        SloCounts { total: 5, good: 2 }
+    );
+}
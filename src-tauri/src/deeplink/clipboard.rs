@@ -0,0 +1,304 @@
+//! Provider import/export via clipboard text
+//!
+//! Parses provider definitions pasted by the user into a pre-filled `Provider`
+//! (not saved; the frontend opens it in the add-provider form). Supported inputs:
+//! - `ccswitch://` deep links
+//! - JSON snippets (Claude/Gemini `settings.json` env blocks or flat `baseUrl`/`apiKey` objects)
+//! - `key: value` / `KEY=value` lines, including `export` statements
+//! - Free-form relay share text (e.g. `接口地址：https://... 密钥：sk-...`)
+//!
+//! Export produces `key: value` lines with the API key masked, so it can be shared safely
+//! and pasted back (the key field is left empty on re-import).
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+
+use super::provider::{build_provider_from_request, parse_and_merge_config};
+use super::utils::infer_homepage_from_endpoint;
+use super::{parse_deeplink_url, DeepLinkImportRequest};
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::proxy::providers::get_adapter;
+use crate::AppType;
+
+static URL_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"https?://[^\s"'<>，。]+"#).unwrap());
+static KEY_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\b(?:sk|cr|ak)-[A-Za-z0-9_\-]{16,}").unwrap());
+static LINE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\s*([^:=：\s][^:=：]*?)\s*[:=：]\s*(.+?)\s*$").unwrap());
+
+/// Fields recognized from clipboard text
+#[derive(Debug, Default, Clone, PartialEq)]
+struct ParsedFields {
+    name: Option<String>,
+    endpoint: Option<String>,
+    api_key: Option<String>,
+    homepage: Option<String>,
+    model: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Name,
+    Endpoint,
+    ApiKey,
+    Homepage,
+    Model,
+}
+
+/// Classify a label such as `ANTHROPIC_BASE_URL`, `apiKey` or `接口地址`
+fn classify_label(label: &str) -> Option<Field> {
+    let normalized: String = label
+        .chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' ' | '.'))
+        .collect::<String>()
+        .to_lowercase();
+
+    if ["homepage", "website", "websiteurl", "官网", "主页"]
+        .iter()
+        .any(|k| normalized.contains(k))
+    {
+        Some(Field::Homepage)
+    } else if [
+        "baseurl", "endpoint", "apibase", "url", "host", "地址", "接口",
+    ]
+    .iter()
+    .any(|k| normalized.contains(k))
+    {
+        Some(Field::Endpoint)
+    } else if ["key", "token", "secret", "密钥", "令牌"]
+        .iter()
+        .any(|k| normalized.contains(k))
+    {
+        Some(Field::ApiKey)
+    } else if matches!(
+        normalized.as_str(),
+        "name" | "providername" | "名称" | "名字"
+    ) {
+        Some(Field::Name)
+    } else if normalized == "model"
+        || normalized == "模型"
+        || (normalized.ends_with("model")
+            && !["default", "small", "haiku", "sonnet", "opus", "reasoning"]
+                .iter()
+                .any(|k| normalized.contains(k)))
+    {
+        Some(Field::Model)
+    } else {
+        None
+    }
+}
+
+fn clean_value(raw: &str) -> String {
+    raw.trim()
+        .trim_end_matches([',', ';', '，', '；'])
+        .trim_matches(|c| c == '"' || c == '\'' || c == '`')
+        .trim()
+        .to_string()
+}
+
+/// Masked keys produced by export (`sk-a...wxyz`) are not real keys
+fn is_masked_key(key: &str) -> bool {
+    key.contains("...") || key == "***"
+}
+
+impl ParsedFields {
+    fn set(&mut self, field: Field, value: String) {
+        if value.is_empty() {
+            return;
+        }
+        let slot = match field {
+            Field::Name => &mut self.name,
+            Field::Endpoint => &mut self.endpoint,
+            Field::ApiKey => &mut self.api_key,
+            Field::Homepage => &mut self.homepage,
+            Field::Model => &mut self.model,
+        };
+        // First occurrence wins
+        if slot.is_none() {
+            *slot = Some(value);
+        }
+    }
+
+    fn from_json(value: &Value) -> Self {
+        let mut fields = Self::default();
+        let scopes = [
+            Some(value),
+            value.get("env"),
+            value.pointer("/settingsConfig/env"),
+            value.get("auth"),
+        ];
+        for scope in scopes.into_iter().flatten() {
+            let Some(map) = scope.as_object() else {
+                continue;
+            };
+            for (label, v) in map {
+                if let (Some(field), Some(text)) = (classify_label(label), v.as_str()) {
+                    fields.set(field, clean_value(text));
+                }
+            }
+        }
+        fields
+    }
+
+    fn from_lines(text: &str) -> Self {
+        let mut fields = Self::default();
+        for line in text.lines() {
+            let line = line.trim();
+            let line = line
+                .strip_prefix("export ")
+                .or_else(|| line.strip_prefix("set "))
+                .or_else(|| line.strip_prefix("- "))
+                .or_else(|| line.strip_prefix("* "))
+                .unwrap_or(line);
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(caps) = LINE_RE.captures(line) {
+                let label = &caps[1];
+                // `https://...` splits on the scheme colon; treat it as a bare URL
+                if !label.eq_ignore_ascii_case("http") && !label.eq_ignore_ascii_case("https") {
+                    if let Some(field) = classify_label(label) {
+                        let value = clean_value(&caps[2]);
+                        let value = match field {
+                            // Labels like `地址` may be followed by extra words
+                            Field::Endpoint | Field::Homepage => URL_RE
+                                .find(&value)
+                                .map(|m| m.as_str().to_string())
+                                .unwrap_or(value),
+                            _ => value,
+                        };
+                        fields.set(field, value);
+                        continue;
+                    }
+                }
+            }
+
+            // Bare URL / key lines
+            let bare = clean_value(line);
+            if URL_RE.find(&bare).is_some_and(|m| m.start() == 0) {
+                fields.set(Field::Endpoint, bare);
+            } else if !bare.contains(char::is_whitespace) && KEY_RE.is_match(&bare) {
+                fields.set(Field::ApiKey, bare);
+            }
+        }
+
+        // Free-form share text: fall back to the first URL and key-like token anywhere
+        if fields.endpoint.is_none() {
+            if let Some(m) = URL_RE.find(text) {
+                fields.set(Field::Endpoint, m.as_str().to_string());
+            }
+        }
+        if fields.api_key.is_none() {
+            if let Some(m) = KEY_RE.find(text) {
+                fields.set(Field::ApiKey, m.as_str().to_string());
+            }
+        }
+        fields
+    }
+}
+
+/// Parse a provider definition pasted from the clipboard into a pre-filled provider
+///
+/// The returned provider has an empty `id`; the caller decides whether to save it.
+pub fn parse_provider_from_text(app_type: &AppType, text: &str) -> Result<Provider, AppError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(AppError::InvalidInput("Clipboard is empty".to_string()));
+    }
+
+    if text.starts_with("ccswitch://") {
+        let request = parse_and_merge_config(&parse_deeplink_url(text)?)?;
+        if request.resource != "provider" {
+            return Err(AppError::InvalidInput(format!(
+                "Expected provider resource, got '{}'",
+                request.resource
+            )));
+        }
+        let app_type = match request.app.as_deref() {
+            Some(app) => app
+                .parse::<AppType>()
+                .map_err(|_| AppError::InvalidInput(format!("Invalid app type: {app}")))?,
+            None => app_type.clone(),
+        };
+        return build_provider_from_request(&app_type, &request);
+    }
+
+    let mut fields = match serde_json::from_str::<Value>(text) {
+        Ok(value) if value.is_object() => ParsedFields::from_json(&value),
+        _ => ParsedFields::from_lines(text),
+    };
+    if fields.api_key.as_deref().is_some_and(is_masked_key) {
+        fields.api_key = None;
+    }
+
+    let endpoint = fields
+        .endpoint
+        .map(|e| e.trim_end_matches('/').to_string())
+        .ok_or_else(|| {
+            AppError::localized(
+                "clipboard.no_endpoint",
+                "未在剪贴板内容中找到接口地址",
+                "No API endpoint found in the clipboard text",
+            )
+        })?;
+    let homepage = fields
+        .homepage
+        .or_else(|| infer_homepage_from_endpoint(&endpoint));
+    let name = fields.name.or_else(|| {
+        url::Url::parse(&endpoint)
+            .ok()
+            .and_then(|u| u.host_str().map(str::to_string))
+    });
+
+    let request = DeepLinkImportRequest {
+        version: "v1".to_string(),
+        resource: "provider".to_string(),
+        app: Some(app_type.as_str().to_string()),
+        name,
+        homepage,
+        endpoint: Some(endpoint),
+        api_key: fields.api_key,
+        model: fields.model,
+        ..Default::default()
+    };
+    build_provider_from_request(app_type, &request)
+}
+
+fn provider_model(app_type: &AppType, provider: &Provider) -> Option<String> {
+    let config = &provider.settings_config;
+    match app_type {
+        AppType::Claude => config.pointer("/env/ANTHROPIC_MODEL"),
+        AppType::Gemini => config.pointer("/env/GEMINI_MODEL"),
+        AppType::Codex => {
+            return config
+                .get("config")
+                .and_then(|c| c.as_str())
+                .and_then(|c| toml::from_str::<toml::Value>(c).ok())
+                .and_then(|t| t.get("model")?.as_str().map(str::to_string));
+        }
+    }
+    .and_then(|v| v.as_str())
+    .map(str::to_string)
+}
+
+/// Export a provider as shareable text with the API key masked
+pub fn export_provider_to_text(app_type: &AppType, provider: &Provider) -> String {
+    let adapter = get_adapter(app_type);
+    let mut lines = vec![format!("name: {}", provider.name)];
+    if let Ok(base_url) = adapter.extract_base_url(provider) {
+        lines.push(format!("base_url: {base_url}"));
+    }
+    if let Some(auth) = adapter.extract_auth(provider) {
+        lines.push(format!("api_key: {}", auth.masked_key()));
+    }
+    if let Some(model) = provider_model(app_type, provider) {
+        lines.push(format!("model: {model}"));
+    }
+    if let Some(website) = provider.website_url.as_deref().filter(|w| !w.is_empty()) {
+        lines.push(format!("homepage: {website}"));
+    }
+    lines.join("\n")
+}
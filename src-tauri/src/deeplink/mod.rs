@@ -9,6 +9,7 @@
 //!
 //! See docs/ccswitch-deeplink-design.md for detailed design.
 
+mod clipboard;
 mod mcp;
 mod parser;
 mod prompt;
@@ -22,9 +23,11 @@ mod tests;
 use serde::{Deserialize, Serialize};
 
 // Re-export public API
+pub use clipboard::{export_provider_to_text, parse_provider_from_text};
 pub use mcp::import_mcp_from_deeplink;
 pub use parser::parse_deeplink_url;
 pub use prompt::import_prompt_from_deeplink;
+pub(crate) use provider::build_provider_from_request;
 pub use provider::{import_provider_from_deeplink, parse_and_merge_config};
 pub use skill::import_skill_from_deeplink;
 
@@ -32,7 +35,7 @@ pub use skill::import_skill_from_deeplink;
 ///
 /// Represents a parsed ccswitch:// URL ready for processing.
 /// This struct contains all possible fields for all resource types.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DeepLinkImportRequest {
     /// Protocol version (e.g., "v1")
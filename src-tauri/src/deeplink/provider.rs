@@ -95,11 +95,12 @@ pub fn import_provider_from_deeplink(
     let provider_id = provider.id.clone();
 
     // Use ProviderService to add the provider
-    ProviderService::add(state, app_type.clone(), provider)?;
+    ProviderService::add(state, app_type.clone(), provider.clone())?;
+    ProviderService::report_duplicates(state, &app_type, &provider);
 
     // If enabled=true, set as current provider
     if merged_request.enabled.unwrap_or(false) {
-        ProviderService::switch(state, app_type.clone(), &provider_id)?;
+        ProviderService::switch_exclusive(state, app_type.clone(), &provider_id, "deeplink")?;
         log::info!("Provider '{provider_id}' set as current for {app_type:?}");
     }
 
@@ -427,7 +428,10 @@ fn merge_claude_config(
         && request.endpoint.is_some()
         && !request.endpoint.as_ref().unwrap().is_empty()
     {
-        request.homepage = infer_homepage_from_endpoint(request.endpoint.as_ref().unwrap());
+        request.homepage = request
+            .endpoint
+            .as_deref()
+            .and_then(infer_homepage_from_endpoint);
         if request.homepage.is_none() {
             request.homepage = Some("https://anthropic.com".to_string());
         }
@@ -503,7 +507,10 @@ fn merge_codex_config(
         && request.endpoint.is_some()
         && !request.endpoint.as_ref().unwrap().is_empty()
     {
-        request.homepage = infer_homepage_from_endpoint(request.endpoint.as_ref().unwrap());
+        request.homepage = request
+            .endpoint
+            .as_deref()
+            .and_then(infer_homepage_from_endpoint);
         if request.homepage.is_none() {
             request.homepage = Some("https://openai.com".to_string());
         }
@@ -542,7 +549,10 @@ fn merge_gemini_config(
         && request.endpoint.is_some()
         && !request.endpoint.as_ref().unwrap().is_empty()
     {
-        request.homepage = infer_homepage_from_endpoint(request.endpoint.as_ref().unwrap());
+        request.homepage = request
+            .endpoint
+            .as_deref()
+            .and_then(infer_homepage_from_endpoint);
         if request.homepage.is_none() {
             request.homepage = Some("https://ai.google.dev".to_string());
         }
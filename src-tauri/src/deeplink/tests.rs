@@ -6,6 +6,7 @@ use super::prompt::import_prompt_from_deeplink;
 use super::provider::parse_and_merge_config;
 use super::utils::{infer_homepage_from_endpoint, validate_url};
 use super::DeepLinkImportRequest;
+use super::{export_provider_to_text, parse_provider_from_text};
 use crate::AppType;
 use crate::{store::AppState, Database};
 use base64::prelude::*;
@@ -404,3 +405,73 @@ fn test_parse_skill_deeplink() {
     assert_eq!(request.directory.unwrap(), "skills");
     assert_eq!(request.branch.unwrap(), "dev");
 }
+
+// =============================================================================
+// Clipboard Tests
+// =============================================================================
+
+#[test]
+fn test_clipboard_parses_claude_settings_json() {
+    let text = r#"{"env":{"ANTHROPIC_BASE_URL":"https://api.relay.example/","ANTHROPIC_AUTH_TOKEN":"sk-relay-0123456789abcdef","ANTHROPIC_MODEL":"claude-sonnet-4"}}"#;
+    let provider = parse_provider_from_text(&AppType::Claude, text).unwrap();
+
+    let env = &provider.settings_config["env"];
+    assert_eq!(env["ANTHROPIC_BASE_URL"], "https://api.relay.example");
+    assert_eq!(env["ANTHROPIC_AUTH_TOKEN"], "sk-relay-0123456789abcdef");
+    assert_eq!(env["ANTHROPIC_MODEL"], "claude-sonnet-4");
+    assert_eq!(provider.name, "api.relay.example");
+    assert_eq!(
+        provider.website_url.as_deref(),
+        Some("https://relay.example")
+    );
+}
+
+#[test]
+fn test_clipboard_parses_lines_and_share_text() {
+    let lines = "name: My Relay\nexport OPENAI_BASE_URL=\"https://relay.example/v1\"\napi_key = sk-abcdefghijklmnop1234";
+    let provider = parse_provider_from_text(&AppType::Gemini, lines).unwrap();
+    assert_eq!(provider.name, "My Relay");
+    assert_eq!(
+        provider.settings_config["env"]["GOOGLE_GEMINI_BASE_URL"],
+        "https://relay.example/v1"
+    );
+    assert_eq!(
+        provider.settings_config["env"]["GEMINI_API_KEY"],
+        "sk-abcdefghijklmnop1234"
+    );
+
+    let share =
+        "🎉 新用户福利 接口地址：https://share.example.com 密钥：sk-share0123456789abcd 欢迎使用";
+    let provider = parse_provider_from_text(&AppType::Claude, share).unwrap();
+    let env = &provider.settings_config["env"];
+    assert_eq!(env["ANTHROPIC_BASE_URL"], "https://share.example.com");
+    assert_eq!(env["ANTHROPIC_AUTH_TOKEN"], "sk-share0123456789abcd");
+}
+
+#[test]
+fn test_clipboard_export_masks_key_and_round_trips() {
+    let text =
+        "base_url: https://api.relay.example\nkey: sk-relay-0123456789abcdef\nmodel: claude-opus-4";
+    let mut provider = parse_provider_from_text(&AppType::Claude, text).unwrap();
+    provider.name = "Relay".to_string();
+
+    let exported = export_provider_to_text(&AppType::Claude, &provider);
+    assert!(exported.contains("base_url: https://api.relay.example"));
+    assert!(exported.contains("api_key: sk-r...cdef"));
+    assert!(!exported.contains("sk-relay-0123456789abcdef"));
+    assert!(exported.contains("model: claude-opus-4"));
+
+    // A masked key is not imported as a real key
+    let reimported = parse_provider_from_text(&AppType::Claude, &exported).unwrap();
+    assert_eq!(reimported.name, "Relay");
+    assert_eq!(
+        reimported.settings_config["env"]["ANTHROPIC_AUTH_TOKEN"],
+        ""
+    );
+}
+
+#[test]
+fn test_clipboard_requires_endpoint() {
+    assert!(parse_provider_from_text(&AppType::Claude, "just some text").is_err());
+    assert!(parse_provider_from_text(&AppType::Claude, "  ").is_err());
+}
@@ -3,6 +3,8 @@ use std::sync::PoisonError;
 
 use thiserror::Error;
 
+use crate::i18n::{self, Locale};
+
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error("配置错误: {0}")]
@@ -52,10 +54,89 @@ pub enum AppError {
     },
     #[error("数据库错误: {0}")]
     Database(String),
+    /// SQLITE_BUSY / SQLITE_LOCKED：其他连接或进程正在写入，可稍后重试
+    #[error("数据库被占用: {0}")]
+    DatabaseLocked(String),
+    #[error("请求超时: {0}")]
+    UpstreamTimeout(String),
+    #[error("连接失败: {0}")]
+    UpstreamUnreachable(String),
+    /// 上游返回非 2xx 状态（401 / 403 归类为认证失败）
+    #[error("HTTP {status}: {body}")]
+    UpstreamStatus { status: u16, body: String },
+    #[error("请求失败: {0}")]
+    Http(String),
     #[error("所有供应商已熔断，无可用渠道")]
     AllProvidersCircuitOpen,
     #[error("未配置供应商")]
     NoProvidersConfigured,
+    /// 字段级校验失败（消息为 JSON，前端可解析出具体字段）
+    #[error("{}", format_validation_errors(.0))]
+    Validation(Vec<FieldError>),
+}
+
+/// 单个字段的校验错误
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldError {
+    /// 字段路径（camelCase，与前端表单字段一致）
+    pub field: String,
+    pub message: String,
+}
+
+/// 稳定的机器可读错误码，随错误一起序列化给前端
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    DbLocked,
+    DbError,
+    UpstreamTimeout,
+    UpstreamUnreachable,
+    UpstreamHttp,
+    UpstreamError,
+    AuthFailed,
+    ConfigParse,
+    ConfigInvalid,
+    InvalidInput,
+    IoError,
+    LockFailed,
+    McpInvalid,
+    ValidationFailed,
+    CircuitOpen,
+    NoProviders,
+    Unknown,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DbLocked => "DB_LOCKED",
+            Self::DbError => "DB_ERROR",
+            Self::UpstreamTimeout => "UPSTREAM_TIMEOUT",
+            Self::UpstreamUnreachable => "UPSTREAM_UNREACHABLE",
+            Self::UpstreamHttp => "UPSTREAM_HTTP",
+            Self::UpstreamError => "UPSTREAM_ERROR",
+            Self::AuthFailed => "AUTH_FAILED",
+            Self::ConfigParse => "CONFIG_PARSE",
+            Self::ConfigInvalid => "CONFIG_INVALID",
+            Self::InvalidInput => "INVALID_INPUT",
+            Self::IoError => "IO_ERROR",
+            Self::LockFailed => "LOCK_FAILED",
+            Self::McpInvalid => "MCP_INVALID",
+            Self::ValidationFailed => "VALIDATION_FAILED",
+            Self::CircuitOpen => "CIRCUIT_OPEN",
+            Self::NoProviders => "NO_PROVIDERS",
+            Self::Unknown => "UNKNOWN",
+        }
+    }
+}
+
+fn format_validation_errors(errors: &[FieldError]) -> String {
+    let error_obj = serde_json::json!({
+        "code": ErrorCode::ValidationFailed,
+        "errors": errors,
+    });
+    serde_json::to_string(&error_obj).unwrap_or_else(|_| "ERROR:VALIDATION_FAILED".to_string())
 }
 
 impl AppError {
@@ -80,6 +161,57 @@ impl AppError {
         }
     }
 
+    /// 错误对应的稳定错误码
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            Self::Config(_) => ErrorCode::ConfigInvalid,
+            Self::InvalidInput(_) => ErrorCode::InvalidInput,
+            Self::Io { .. } | Self::IoContext { .. } => ErrorCode::IoError,
+            Self::Json { .. } | Self::Toml { .. } => ErrorCode::ConfigParse,
+            Self::JsonSerialize { .. } => ErrorCode::Unknown,
+            Self::Lock(_) => ErrorCode::LockFailed,
+            Self::McpValidation(_) => ErrorCode::McpInvalid,
+            Self::Message(_) | Self::Localized { .. } => ErrorCode::Unknown,
+            Self::Database(_) => ErrorCode::DbError,
+            Self::DatabaseLocked(_) => ErrorCode::DbLocked,
+            Self::UpstreamTimeout(_) => ErrorCode::UpstreamTimeout,
+            Self::UpstreamUnreachable(_) => ErrorCode::UpstreamUnreachable,
+            Self::UpstreamStatus {
+                status: 401 | 403, ..
+            } => ErrorCode::AuthFailed,
+            Self::UpstreamStatus { .. } => ErrorCode::UpstreamHttp,
+            Self::Http(_) => ErrorCode::UpstreamError,
+            Self::AllProvidersCircuitOpen => ErrorCode::CircuitOpen,
+            Self::NoProvidersConfigured => ErrorCode::NoProviders,
+            Self::Validation(_) => ErrorCode::ValidationFailed,
+        }
+    }
+
+    /// 按语言渲染错误信息：错误类别取自消息目录，细节（路径、上游原文等）保持原样
+    pub fn localized_message(&self, locale: Locale) -> String {
+        let prefix = || i18n::tr(locale, &format!("error.{}", self.code().as_str()));
+        match self {
+            Self::Config(detail)
+            | Self::InvalidInput(detail)
+            | Self::Lock(detail)
+            | Self::McpValidation(detail)
+            | Self::Database(detail)
+            | Self::DatabaseLocked(detail)
+            | Self::UpstreamTimeout(detail)
+            | Self::UpstreamUnreachable(detail)
+            | Self::Http(detail) => format!("{}: {detail}", prefix()),
+            Self::Io { path, source } => format!("{}: {path}: {source}", prefix()),
+            Self::Json { path, source } => format!("{}: {path}: {source}", prefix()),
+            Self::Toml { path, source } => format!("{}: {path}: {source}", prefix()),
+            Self::AllProvidersCircuitOpen | Self::NoProvidersConfigured => prefix(),
+            Self::Localized { zh, en, .. } => match locale {
+                Locale::Zh => zh.clone(),
+                Locale::En | Locale::Ja => en.clone(),
+            },
+            _ => self.to_string(),
+        }
+    }
+
     pub fn localized(key: &'static str, zh: impl Into<String>, en: impl Into<String>) -> Self {
         Self::Localized {
             key,
@@ -97,22 +229,56 @@ impl<T> From<PoisonError<T>> for AppError {
 
 impl From<rusqlite::Error> for AppError {
     fn from(err: rusqlite::Error) -> Self {
-        Self::Database(err.to_string())
+        use rusqlite::ErrorCode as SqliteCode;
+        match err.sqlite_error_code() {
+            Some(SqliteCode::DatabaseBusy | SqliteCode::DatabaseLocked) => {
+                Self::DatabaseLocked(err.to_string())
+            }
+            _ => Self::Database(err.to_string()),
+        }
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Self::UpstreamTimeout(err.to_string())
+        } else if err.is_connect() {
+            Self::UpstreamUnreachable(err.to_string())
+        } else if let Some(status) = err.status() {
+            Self::UpstreamStatus {
+                status: status.as_u16(),
+                body: err.to_string(),
+            }
+        } else {
+            Self::Http(err.to_string())
+        }
     }
 }
 
 impl From<AppError> for String {
     fn from(err: AppError) -> Self {
-        err.to_string()
+        err.localized_message(Locale::current())
     }
 }
 
+/// 序列化为 `{ code, message }`，前端按 code 分支处理，message 按设置语言渲染用于展示
 impl serde::Serialize for AppError {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("AppError", 4)?;
+        state.serialize_field("code", &self.code())?;
+        state.serialize_field("message", &self.localized_message(Locale::current()))?;
+        match self {
+            Self::UpstreamStatus { status, .. } => state.serialize_field("status", status)?,
+            Self::Localized { key, .. } => state.serialize_field("key", key)?,
+            _ => {}
+        }
+        state.end()
     }
 }
 
@@ -140,3 +306,54 @@ pub fn format_skill_error(
         format!("ERROR:{code}")
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sqlite_busy_maps_to_db_locked() {
+        let busy = rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_BUSY),
+            None,
+        );
+        assert_eq!(AppError::from(busy).code(), ErrorCode::DbLocked);
+        assert_eq!(
+            AppError::from(rusqlite::Error::QueryReturnedNoRows).code(),
+            ErrorCode::DbError
+        );
+    }
+
+    #[test]
+    fn localized_message_uses_catalog_prefix() {
+        let err = AppError::UpstreamTimeout("operation timed out".to_string());
+        assert_eq!(
+            err.localized_message(Locale::En),
+            "Request timed out: operation timed out"
+        );
+        assert_eq!(err.localized_message(Locale::Zh), err.to_string());
+        assert_eq!(
+            AppError::localized("k", "中文", "English").localized_message(Locale::Ja),
+            "English"
+        );
+    }
+
+    #[test]
+    fn serializes_code_and_message() {
+        let err = AppError::UpstreamStatus {
+            status: 401,
+            body: "invalid key".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            serde_json::json!({
+                "code": "AUTH_FAILED",
+                "message": "HTTP 401: invalid key",
+                "status": 401,
+            })
+        );
+        assert!(AppError::Validation(vec![])
+            .to_string()
+            .contains("\"code\":\"VALIDATION_FAILED\""));
+    }
+}
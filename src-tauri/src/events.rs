@@ -0,0 +1,340 @@
+//! 服务与前端之间的结构化事件总线
+//!
+//! 所有后端事件统一定义为 [`AppEvent`]，名称与 payload 结构保持稳定：
+//! - 按事件名（如 `provider-switched`）通过 Tauri 事件发送 payload，兼容现有的 `listen` 监听
+//! - 同时广播给通过 `subscribe_events` 订阅的消费者（前端 Channel、对外 API），可按类型 / 应用 / 供应商过滤
+//!
+//! 总线在应用启动时通过 [`init`] 绑定 AppHandle；未绑定时（测试、CLI）只做广播。
+
+use std::sync::OnceLock;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::broadcast;
+
+use crate::database::SubstitutionSuspect;
+use crate::proxy::offline::NetworkStatus;
+use crate::proxy::types::TpsSample;
+use crate::services::background_task::TaskProgress;
+use crate::services::disk_guard::DiskUsageWarning;
+use crate::services::latency_slo::SloStatus;
+use crate::services::probe_budget::ProbeBudgetStatus;
+use crate::services::provider::{DuplicateMatch, LiveDrift};
+use crate::services::stream_check::StreamCheckResult;
+use crate::services::usage_forecast::UsageForecast;
+
+/// 广播缓冲区容量（订阅者处理过慢时丢弃最旧的事件）
+const BUS_CAPACITY: usize = 256;
+
+static BUS: Lazy<broadcast::Sender<AppEvent>> = Lazy::new(|| broadcast::channel(BUS_CAPACITY).0);
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+/// 事件类型（序列化值即 Tauri 事件名）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventKind {
+    HealthChanged,
+    ProviderSwitched,
+    TpsSample,
+    BudgetWarning,
+    SyncConflict,
+    NetworkStatusChanged,
+    LiveConfigDrift,
+    ModelSubstitutionSuspected,
+    DiskUsageWarning,
+    UsageForecastWarning,
+    TaskProgress,
+    SloBurnRateWarning,
+    DuplicateProviderDetected,
+}
+
+impl EventKind {
+    pub const ALL: [Self; 13] = [
+        Self::HealthChanged,
+        Self::ProviderSwitched,
+        Self::TpsSample,
+        Self::BudgetWarning,
+        Self::SyncConflict,
+        Self::NetworkStatusChanged,
+        Self::LiveConfigDrift,
+        Self::ModelSubstitutionSuspected,
+        Self::DiskUsageWarning,
+        Self::UsageForecastWarning,
+        Self::TaskProgress,
+        Self::SloBurnRateWarning,
+        Self::DuplicateProviderDetected,
+    ];
+
+    /// 按事件名查找
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == name)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::HealthChanged => "health-changed",
+            Self::ProviderSwitched => "provider-switched",
+            Self::TpsSample => "tps-sample",
+            Self::BudgetWarning => "budget-warning",
+            Self::SyncConflict => "sync-conflict",
+            Self::NetworkStatusChanged => "network-status-changed",
+            Self::LiveConfigDrift => "live-config-drift",
+            Self::ModelSubstitutionSuspected => "model-substitution-suspected",
+            Self::DiskUsageWarning => "disk-usage-warning",
+            Self::UsageForecastWarning => "usage-forecast-warning",
+            Self::TaskProgress => "task-progress",
+            Self::SloBurnRateWarning => "slo-burn-rate-warning",
+            Self::DuplicateProviderDetected => "duplicate-provider-detected",
+        }
+    }
+}
+
+/// 健康状态变化（检查完成后发送）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthChangedPayload {
+    pub app_type: String,
+    pub provider_id: String,
+    pub result: StreamCheckResult,
+    /// 检查时供应商处于维护窗口内（失败不告警）
+    #[serde(default)]
+    pub in_maintenance: bool,
+}
+
+/// 供应商已切换
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderSwitchedPayload {
+    pub app_type: String,
+    pub provider_id: String,
+    /// 切换来源（"failover" 表示故障转移，手动切换时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+/// 配置同步冲突
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncConflictPayload {
+    pub app_type: String,
+    pub provider_id: String,
+    pub message: String,
+}
+
+/// 新建或导入的供应商与已有供应商疑似重复
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateProviderPayload {
+    pub app_type: String,
+    pub provider_id: String,
+    pub name: String,
+    pub matches: Vec<DuplicateMatch>,
+}
+
+/// 后端事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "payload", rename_all = "kebab-case")]
+pub enum AppEvent {
+    HealthChanged(HealthChangedPayload),
+    ProviderSwitched(ProviderSwitchedPayload),
+    TpsSample(TpsSample),
+    /// 探测预算进入限流或用尽
+    BudgetWarning(ProbeBudgetStatus),
+    SyncConflict(SyncConflictPayload),
+    NetworkStatusChanged(NetworkStatus),
+    /// 当前供应商与 Live 配置文件不一致（启动时检查）
+    LiveConfigDrift(LiveDrift),
+    /// 供应商的模型替换检测线索达到阈值
+    ModelSubstitutionSuspected(SubstitutionSuspect),
+    /// 磁盘占用超过上限（已按策略清理）
+    DiskUsageWarning(DiskUsageWarning),
+    /// 供应商预计在本月内超出费用阈值
+    UsageForecastWarning(UsageForecast),
+    /// 后台任务的进度与部分结果
+    TaskProgress(TaskProgress),
+    /// 供应商的延迟 SLO 错误预算消耗过快或已用尽
+    SloBurnRateWarning(SloStatus),
+    /// 新建或导入的供应商疑似与已有供应商重复（可合并）
+    DuplicateProviderDetected(DuplicateProviderPayload),
+}
+
+impl AppEvent {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Self::HealthChanged(_) => EventKind::HealthChanged,
+            Self::ProviderSwitched(_) => EventKind::ProviderSwitched,
+            Self::TpsSample(_) => EventKind::TpsSample,
+            Self::BudgetWarning(_) => EventKind::BudgetWarning,
+            Self::SyncConflict(_) => EventKind::SyncConflict,
+            Self::NetworkStatusChanged(_) => EventKind::NetworkStatusChanged,
+            Self::LiveConfigDrift(_) => EventKind::LiveConfigDrift,
+            Self::ModelSubstitutionSuspected(_) => EventKind::ModelSubstitutionSuspected,
+            Self::DiskUsageWarning(_) => EventKind::DiskUsageWarning,
+            Self::UsageForecastWarning(_) => EventKind::UsageForecastWarning,
+            Self::TaskProgress(_) => EventKind::TaskProgress,
+            Self::SloBurnRateWarning(_) => EventKind::SloBurnRateWarning,
+            Self::DuplicateProviderDetected(_) => EventKind::DuplicateProviderDetected,
+        }
+    }
+
+    /// 是否属于告警（发送 webhook、外部事件流可只订阅告警）
+    pub fn is_alert(&self) -> bool {
+        match self {
+            Self::BudgetWarning(_)
+            | Self::UsageForecastWarning(_)
+            | Self::SloBurnRateWarning(_) => true,
+            Self::HealthChanged(p) => {
+                p.result.is_conclusive() && !p.result.success && !p.in_maintenance
+            }
+            _ => false,
+        }
+    }
+
+    /// 事件关联的（应用类型, 供应商 ID），用于过滤
+    pub(crate) fn scope(&self) -> Option<(&str, &str)> {
+        match self {
+            Self::HealthChanged(p) => Some((&p.app_type, &p.provider_id)),
+            Self::ProviderSwitched(p) => Some((&p.app_type, &p.provider_id)),
+            Self::TpsSample(p) => Some((&p.app_type, &p.provider_id)),
+            Self::BudgetWarning(p) => Some((&p.app_type, &p.provider_id)),
+            Self::SyncConflict(p) => Some((&p.app_type, &p.provider_id)),
+            Self::NetworkStatusChanged(_) => None,
+            Self::LiveConfigDrift(p) => Some((&p.app_type, &p.provider_id)),
+            Self::ModelSubstitutionSuspected(p) => Some((&p.app_type, &p.provider_id)),
+            Self::DiskUsageWarning(_) => None,
+            Self::UsageForecastWarning(p) => Some((&p.app_type, &p.provider_id)),
+            Self::TaskProgress(_) => None,
+            Self::SloBurnRateWarning(p) => Some((&p.app_type, &p.provider_id)),
+            Self::DuplicateProviderDetected(p) => Some((&p.app_type, &p.provider_id)),
+        }
+    }
+
+    fn emit_payload(&self, app: &AppHandle) -> tauri::Result<()> {
+        let name = self.kind().as_str();
+        match self {
+            Self::HealthChanged(p) => app.emit(name, p),
+            Self::ProviderSwitched(p) => app.emit(name, p),
+            Self::TpsSample(p) => app.emit(name, p),
+            Self::BudgetWarning(p) => app.emit(name, p),
+            Self::SyncConflict(p) => app.emit(name, p),
+            Self::NetworkStatusChanged(p) => app.emit(name, p),
+            Self::LiveConfigDrift(p) => app.emit(name, p),
+            Self::ModelSubstitutionSuspected(p) => app.emit(name, p),
+            Self::DiskUsageWarning(p) => app.emit(name, p),
+            Self::UsageForecastWarning(p) => app.emit(name, p),
+            Self::TaskProgress(p) => app.emit(name, p),
+            Self::SloBurnRateWarning(p) => app.emit(name, p),
+            Self::DuplicateProviderDetected(p) => app.emit(name, p),
+        }
+    }
+}
+
+/// 订阅过滤条件（字段为空表示不限制）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventFilter {
+    #[serde(default)]
+    pub kinds: Vec<EventKind>,
+    #[serde(default)]
+    pub app_type: Option<String>,
+    #[serde(default)]
+    pub provider_id: Option<String>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &AppEvent) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.contains(&event.kind()) {
+            return false;
+        }
+        if self.app_type.is_none() && self.provider_id.is_none() {
+            return true;
+        }
+        // 全局事件（如网络状态）不属于任何供应商，始终通过
+        let Some((app_type, provider_id)) = event.scope() else {
+            return true;
+        };
+        self.app_type.as_deref().is_none_or(|a| a == app_type)
+            && self.provider_id.as_deref().is_none_or(|p| p == provider_id)
+    }
+}
+
+/// 绑定 AppHandle（应用启动时调用一次）
+pub fn init(app: AppHandle) {
+    let _ = APP_HANDLE.set(app);
+}
+
+/// 发布事件
+pub fn publish(event: AppEvent) {
+    if let Some(app) = APP_HANDLE.get() {
+        if let Err(e) = event.emit_payload(app) {
+            log::warn!("发送事件 {} 失败: {e}", event.kind().as_str());
+        }
+    }
+    // 没有订阅者时发送失败是正常情况
+    let _ = BUS.send(event);
+}
+
+/// 订阅全部事件
+pub fn subscribe() -> broadcast::Receiver<AppEvent> {
+    BUS.subscribe()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn switched(app_type: &str, provider_id: &str) -> AppEvent {
+        AppEvent::ProviderSwitched(ProviderSwitchedPayload {
+            app_type: app_type.to_string(),
+            provider_id: provider_id.to_string(),
+            source: None,
+        })
+    }
+
+    #[test]
+    fn serializes_with_stable_type_name() {
+        let value = serde_json::to_value(switched("claude", "p1")).unwrap();
+        assert_eq!(value["type"], "provider-switched");
+        assert_eq!(value["payload"]["providerId"], "p1");
+    }
+
+    #[test]
+    fn kind_names_round_trip() {
+        for kind in EventKind::ALL {
+            assert_eq!(EventKind::from_name(kind.as_str()), Some(kind));
+        }
+        assert_eq!(EventKind::from_name("unknown"), None);
+        assert!(!switched("claude", "p1").is_alert());
+    }
+
+    #[test]
+    fn filter_by_kind_and_scope() {
+        let filter = EventFilter {
+            kinds: vec![EventKind::ProviderSwitched, EventKind::NetworkStatusChanged],
+            app_type: Some("claude".to_string()),
+            provider_id: None,
+        };
+        assert!(filter.matches(&switched("claude", "p1")));
+        assert!(!filter.matches(&switched("codex", "p1")));
+        assert!(
+            filter.matches(&AppEvent::NetworkStatusChanged(NetworkStatus {
+                offline: true,
+                changed_at: None,
+            }))
+        );
+        assert!(!EventFilter {
+            kinds: vec![EventKind::TpsSample],
+            ..EventFilter::default()
+        }
+        .matches(&switched("claude", "p1")));
+    }
+
+    #[tokio::test]
+    async fn publish_reaches_subscribers() {
+        let mut rx = subscribe();
+        publish(switched("gemini", "p2"));
+        let event = rx.recv().await.unwrap();
+        assert_eq!(event.kind(), EventKind::ProviderSwitched);
+    }
+}
@@ -0,0 +1,172 @@
+//! 后端消息目录
+//!
+//! 按消息键（错误码、状态键）查找中 / 英 / 日文案，语言取自设置中的 `language`。
+//! 命令返回的错误、写入数据库的检查结果都通过这里渲染，前端无需再维护一份原始字符串映射。
+
+use crate::settings;
+
+/// 消息语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    Zh,
+    En,
+    Ja,
+}
+
+impl Locale {
+    pub fn from_language(language: &str) -> Self {
+        match language {
+            "en" => Self::En,
+            "ja" => Self::Ja,
+            _ => Self::Zh,
+        }
+    }
+
+    /// 当前设置的语言（未设置时为中文，与托盘菜单一致）
+    pub fn current() -> Self {
+        Self::from_language(settings::get_settings().language.as_deref().unwrap_or("zh"))
+    }
+}
+
+/// 消息目录：键 -> [中文, 英文, 日文]
+fn entry(key: &str) -> Option<[&'static str; 3]> {
+    Some(match key {
+        "error.DB_LOCKED" => [
+            "数据库被占用",
+            "Database is busy",
+            "データベースが使用中です",
+        ],
+        "error.DB_ERROR" => ["数据库错误", "Database error", "データベースエラー"],
+        "error.UPSTREAM_TIMEOUT" => [
+            "请求超时",
+            "Request timed out",
+            "リクエストがタイムアウトしました",
+        ],
+        "error.UPSTREAM_UNREACHABLE" => ["连接失败", "Connection failed", "接続に失敗しました"],
+        "error.UPSTREAM_ERROR" => ["请求失败", "Request failed", "リクエストに失敗しました"],
+        "error.CONFIG_INVALID" => ["配置错误", "Configuration error", "設定エラー"],
+        "error.CONFIG_PARSE" => [
+            "配置解析失败",
+            "Failed to parse configuration",
+            "設定の解析に失敗しました",
+        ],
+        "error.INVALID_INPUT" => ["无效输入", "Invalid input", "無効な入力"],
+        "error.IO_ERROR" => ["IO 错误", "I/O error", "I/O エラー"],
+        "error.LOCK_FAILED" => [
+            "锁获取失败",
+            "Failed to acquire lock",
+            "ロックの取得に失敗しました",
+        ],
+        "error.MCP_INVALID" => [
+            "MCP 校验失败",
+            "MCP validation failed",
+            "MCP の検証に失敗しました",
+        ],
+        "error.CIRCUIT_OPEN" => [
+            "所有供应商已熔断，无可用渠道",
+            "All providers are circuit-broken, no channel available",
+            "すべてのプロバイダーが遮断されており、利用可能なチャネルがありません",
+        ],
+        "error.NO_PROVIDERS" => [
+            "未配置供应商",
+            "No providers configured",
+            "プロバイダーが設定されていません",
+        ],
+        "streamCheck.success" => ["检查成功", "Check passed", "チェック成功"],
+        "streamCheck.failed" => ["检查失败", "Check failed", "チェック失敗"],
+        "streamCheck.cancelled" => ["检查已取消", "Check cancelled", "チェックをキャンセルしました"],
+        "streamCheck.offlineSkipped" => [
+            "网络不可用（离线），已跳过检查",
+            "Network unavailable (offline), check skipped",
+            "ネットワークに接続できません（オフライン）。チェックをスキップしました",
+        ],
+        "streamCheck.budgetExhausted" => [
+            "本月健康检查 token 预算已用尽，已跳过检查",
+            "This month's health-check token budget is used up, check skipped",
+            "今月のヘルスチェック用トークン予算を使い切りました。チェックをスキップしました",
+        ],
+        "upstreamHint.QUOTA_EXCEEDED" => [
+            "额度已用尽，请充值或切换到其他供应商",
+            "Quota exhausted; top up the account or switch to another provider",
+            "クォータを使い切りました。チャージするか別のプロバイダーに切り替えてください",
+        ],
+        "upstreamHint.INVALID_KEY" => [
+            "API Key 无效或已过期，请检查供应商配置中的 Key",
+            "The API key is invalid or expired; check the key in the provider settings",
+            "API キーが無効か期限切れです。プロバイダー設定のキーを確認してください",
+        ],
+        "upstreamHint.REGION_BLOCKED" => [
+            "当前地区不受支持，请更换网络出口或使用中转供应商",
+            "Your region is not supported; change the network egress or use a relay provider",
+            "現在の地域はサポートされていません。ネットワークの出口を変更するか中継プロバイダーを使用してください",
+        ],
+        "upstreamHint.MODEL_DEPRECATED" => [
+            "模型已下线或不可用，请在供应商配置中更换模型",
+            "The model is retired or unavailable; choose another model in the provider settings",
+            "モデルは廃止されたか利用できません。プロバイダー設定で別のモデルを選択してください",
+        ],
+        "upstreamHint.RATE_LIMITED" => [
+            "请求过于频繁，请稍后重试或降低并发",
+            "Rate limited; retry later or reduce concurrency",
+            "レート制限に達しました。しばらくしてから再試行するか同時実行数を下げてください",
+        ],
+        "capability.toolUse" => [
+            "该供应商不支持工具调用，{app} 的代理功能将无法正常工作",
+            "This provider doesn't support tool calling; {app} agents will break",
+            "このプロバイダーはツール呼び出しに対応していないため、{app} のエージェント機能が動作しません",
+        ],
+        "capability.vision" => [
+            "该供应商不支持图片输入，{app} 中粘贴截图会失败",
+            "This provider doesn't support image input; pasting screenshots in {app} will fail",
+            "このプロバイダーは画像入力に対応していないため、{app} でのスクリーンショット貼り付けは失敗します",
+        ],
+        "capability.promptCaching" => [
+            "该供应商不支持提示缓存，{app} 的长会话费用会明显升高",
+            "This provider doesn't support prompt caching; long {app} sessions will cost noticeably more",
+            "このプロバイダーはプロンプトキャッシュに対応していないため、{app} の長いセッションのコストが大幅に増えます",
+        ],
+        "capability.contextWindow" => [
+            "该供应商的上下文窗口为 {actual} tokens，低于 {app} 建议的 {required} tokens，长会话可能被截断",
+            "This provider's context window is {actual} tokens, below the {required} tokens recommended for {app}; long sessions may be truncated",
+            "このプロバイダーのコンテキストウィンドウは {actual} トークンで、{app} の推奨値 {required} トークンを下回っています。長いセッションは切り詰められる可能性があります",
+        ],
+        _ => return None,
+    })
+}
+
+/// 查找消息，未收录的键原样返回
+pub fn tr(locale: Locale, key: &str) -> String {
+    match entry(key) {
+        Some(texts) => texts[locale as usize].to_string(),
+        None => key.to_string(),
+    }
+}
+
+/// 查找消息并替换 `{name}` 占位符
+pub fn tr_with(locale: Locale, key: &str, args: &[(&str, &str)]) -> String {
+    args.iter().fold(tr(locale, key), |text, (name, value)| {
+        text.replace(&format!("{{{name}}}"), value)
+    })
+}
+
+/// 使用当前语言查找消息
+pub fn tr_current(key: &str) -> String {
+    tr(Locale::current(), key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup_by_locale_and_fallback() {
+        assert_eq!(tr(Locale::Zh, "error.DB_LOCKED"), "数据库被占用");
+        assert_eq!(tr(Locale::En, "error.DB_LOCKED"), "Database is busy");
+        assert_eq!(Locale::from_language("fr"), Locale::Zh);
+        assert_eq!(tr(Locale::Ja, "unknown.key"), "unknown.key");
+        assert_eq!(
+            tr_with(Locale::En, "capability.toolUse", &[("app", "Codex")]),
+            "This provider doesn't support tool calling; Codex agents will break"
+        );
+    }
+}
@@ -25,6 +25,42 @@ pub fn get_init_error() -> Option<InitErrorPayload> {
     cell().read().ok()?.clone()
 }
 
+// ============================================================
+// 安全模式状态（数据库损坏时使用内存数据库启动）
+// ============================================================
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeModePayload {
+    pub db_path: String,
+    pub reason: String,
+    /// 数据库已启用加密（前端据此提示输入口令或说明密钥丢失后的恢复方式）
+    pub encrypted: bool,
+}
+
+static SAFE_MODE: OnceLock<RwLock<Option<SafeModePayload>>> = OnceLock::new();
+
+fn safe_mode_cell() -> &'static RwLock<Option<SafeModePayload>> {
+    SAFE_MODE.get_or_init(|| RwLock::new(None))
+}
+
+pub fn set_safe_mode(payload: SafeModePayload) {
+    if let Ok(mut guard) = safe_mode_cell().write() {
+        *guard = Some(payload);
+    }
+}
+
+pub fn get_safe_mode() -> Option<SafeModePayload> {
+    safe_mode_cell().read().ok()?.clone()
+}
+
+/// 加密数据库解锁后退出安全模式
+pub fn clear_safe_mode() {
+    if let Ok(mut guard) = safe_mode_cell().write() {
+        *guard = None;
+    }
+}
+
 // ============================================================
 // 迁移结果状态
 // ============================================================
@@ -0,0 +1,349 @@
+//! 跨构建的单实例保护
+//!
+//! `tauri-plugin-single-instance` 只能识别相同应用标识的实例，开发版与安装版同时运行时
+//! 仍会争抢代理端口和 Live 配置文件。这里在本机回环地址上监听一个按用户目录派生的端口：
+//! - 监听成功：作为主实例运行，收到 `focus` 请求时唤起主窗口
+//! - 端口已被其他 cc-switch 占用：通知其唤起窗口，然后按设置退出，或以只读附加模式运行
+//!
+//! 只读附加模式下不启动代理、不恢复接管状态，也不允许修改或切换供应商。
+//!
+//! 终端切换（`cc-switch pick`）通过 `switch` 请求交给主实例执行，避免主实例的设置缓存与数据库不一致。
+//! 请求需携带主实例写入配置目录（仅当前用户可读）的令牌。
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Manager};
+
+const HANDSHAKE: &str = "cc-switch-instance";
+const PORT_BASE: u16 = 20_000;
+const PORT_RANGE: u16 = 10_000;
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+/// 切换需要排队并写入配置，等待时间比握手长
+const SWITCH_TIMEOUT: Duration = Duration::from_secs(30);
+const TOKEN_FILE: &str = "instance.token";
+
+static ROLE: OnceLock<InstanceStatus> = OnceLock::new();
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+static TOKEN: OnceLock<String> = OnceLock::new();
+
+/// 检测到其他实例时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SecondaryInstanceMode {
+    /// 唤起已运行的实例后退出
+    #[default]
+    Focus,
+    /// 唤起已运行的实例，并以只读附加模式继续运行
+    Attach,
+}
+
+/// 当前实例的角色
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstanceStatus {
+    /// 是否为只读附加实例
+    pub attached: bool,
+    pub pid: u32,
+    /// 主实例的进程 ID 与版本（附加模式下）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary_pid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary_version: Option<String>,
+}
+
+impl InstanceStatus {
+    fn primary() -> Self {
+        Self {
+            attached: false,
+            pid: std::process::id(),
+            primary_pid: None,
+            primary_version: None,
+        }
+    }
+}
+
+/// 终端发给主实例的切换请求
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SwitchRequest {
+    token: String,
+    app: String,
+    provider_id: String,
+}
+
+/// 检测结果
+pub enum Acquired {
+    Primary,
+    /// 已有实例在运行，且设置为唤起后退出
+    ShouldExit,
+    Attached,
+}
+
+/// 按用户主目录派生监听端口（同一用户的不同构建使用同一端口）
+fn instance_port() -> u16 {
+    let home = dirs::home_dir().unwrap_or_default();
+    let digest = Sha256::digest(home.to_string_lossy().as_bytes());
+    PORT_BASE + u16::from_be_bytes([digest[0], digest[1]]) % PORT_RANGE
+}
+
+/// 解析主实例的应答：`cc-switch-instance <pid> <version>`
+fn parse_reply(line: &str) -> Option<(u32, String)> {
+    let mut parts = line.split_whitespace();
+    if parts.next()? != HANDSHAKE {
+        return None;
+    }
+    let pid = parts.next()?.parse().ok()?;
+    let version = parts.next().unwrap_or_default().to_string();
+    Some((pid, version))
+}
+
+/// 请求已运行的实例唤起窗口，返回其进程 ID 与版本；对端不是 cc-switch 时返回 None
+fn notify_primary(addr: SocketAddr) -> Option<(u32, String)> {
+    let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).ok()?;
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT)).ok()?;
+    writeln!(stream, "{HANDSHAKE} focus").ok()?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).ok()?;
+    parse_reply(&line)
+}
+
+/// 解析切换请求：`cc-switch-instance switch <json>`
+fn parse_switch(line: &str) -> Option<SwitchRequest> {
+    let payload = line
+        .trim_end()
+        .strip_prefix(HANDSHAKE)?
+        .strip_prefix(" switch ")?;
+    serde_json::from_str(payload).ok()
+}
+
+/// 解析切换结果：`ok` 或 `error <原因>`
+fn parse_switch_result(line: &str) -> Result<(), String> {
+    let line = line.trim_end();
+    if line == "ok" {
+        return Ok(());
+    }
+    Err(line
+        .strip_prefix("error ")
+        .filter(|msg| !msg.is_empty())
+        .unwrap_or("运行中的 cc-switch 未返回切换结果")
+        .to_string())
+}
+
+fn token_path() -> std::path::PathBuf {
+    crate::config::get_app_config_dir().join(TOKEN_FILE)
+}
+
+/// 生成本次运行的切换令牌，写入仅当前用户可读的文件
+fn publish_token() {
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    let path = token_path();
+    let written = path
+        .parent()
+        .map_or(Ok(()), |dir| {
+            std::fs::create_dir_all(dir).map_err(|e| crate::error::AppError::io(dir, e))
+        })
+        .and_then(|()| crate::services::vault::write_private(&path, token.as_bytes()));
+    if let Err(e) = written {
+        log::warn!("写入实例令牌失败，终端切换将不可用: {e}");
+        return;
+    }
+    let _ = TOKEN.set(token);
+}
+
+/// 将切换交给正在运行的主实例；没有 cc-switch 在运行时返回 None
+pub fn request_switch(app: &str, provider_id: &str) -> Option<Result<(), String>> {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, instance_port()));
+    let mut stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).ok()?;
+    stream.set_read_timeout(Some(CONNECT_TIMEOUT)).ok()?;
+    let request = SwitchRequest {
+        token: std::fs::read_to_string(token_path())
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+        app: app.to_string(),
+        provider_id: provider_id.to_string(),
+    };
+    let payload = serde_json::to_string(&request).ok()?;
+    writeln!(stream, "{HANDSHAKE} switch {payload}").ok()?;
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    parse_reply(&line)?;
+
+    // 已确认对端是 cc-switch，之后的失败都要报告，不能再回退到本进程切换
+    let _ = reader.get_ref().set_read_timeout(Some(SWITCH_TIMEOUT));
+    line.clear();
+    Some(match reader.read_line(&mut line) {
+        Ok(_) => parse_switch_result(&line),
+        Err(e) => Err(format!("等待运行中的 cc-switch 切换失败: {e}")),
+    })
+}
+
+/// 在主实例中执行终端发来的切换，行为与托盘切换一致
+fn handle_switch(request: SwitchRequest) -> Result<(), String> {
+    let authorized = TOKEN
+        .get()
+        .is_some_and(|token| !request.token.is_empty() && *token == request.token);
+    if !authorized {
+        return Err("令牌无效，请确认终端与运行中的 cc-switch 使用相同的数据目录".to_string());
+    }
+    let app = APP_HANDLE.get().ok_or("cc-switch 尚未就绪")?;
+    let state = app
+        .try_state::<crate::store::AppState>()
+        .ok_or("cc-switch 尚未就绪")?;
+    crate::commands::switch_provider_from(
+        app,
+        state.inner(),
+        &request.app,
+        request.provider_id,
+        "cli",
+    )?;
+    if let Ok(menu) = crate::tray::create_tray_menu(app, state.inner()) {
+        if let Some(tray) = app.tray_by_id("main") {
+            let _ = tray.set_menu(Some(menu));
+        }
+    }
+    Ok(())
+}
+
+fn focus_main_window() {
+    let Some(app) = APP_HANDLE.get() else {
+        return;
+    };
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn serve(listener: TcpListener) {
+    for stream in listener.incoming().flatten() {
+        let _ = stream.set_read_timeout(Some(CONNECT_TIMEOUT));
+        let mut line = String::new();
+        let Ok(mut reader) = stream.try_clone().map(BufReader::new) else {
+            continue;
+        };
+        if reader.read_line(&mut line).is_err() || !line.starts_with(HANDSHAKE) {
+            continue;
+        }
+        let mut stream = stream;
+        let _ = writeln!(
+            stream,
+            "{HANDSHAKE} {} {}",
+            std::process::id(),
+            env!("CARGO_PKG_VERSION")
+        );
+        if let Some(request) = parse_switch(&line) {
+            log::info!("终端请求切换 {} 到 {}", request.app, request.provider_id);
+            // 切换可能较慢，放到独立线程，避免阻塞其他实例的握手
+            std::thread::spawn(move || {
+                let reply = match handle_switch(request) {
+                    Ok(()) => "ok".to_string(),
+                    Err(e) => format!("error {}", e.replace(['\r', '\n'], " ")),
+                };
+                let _ = writeln!(stream, "{reply}");
+            });
+        } else if line.trim_end().ends_with(" focus") {
+            log::info!("其他 cc-switch 实例请求唤起窗口");
+            focus_main_window();
+        }
+    }
+}
+
+/// 启动时检测其他实例（在创建窗口前调用一次）
+pub fn acquire(mode: SecondaryInstanceMode) -> Acquired {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, instance_port()));
+    match TcpListener::bind(addr) {
+        Ok(listener) => {
+            publish_token();
+            std::thread::spawn(move || serve(listener));
+            let _ = ROLE.set(InstanceStatus::primary());
+            Acquired::Primary
+        }
+        Err(e) => {
+            let Some((primary_pid, primary_version)) = notify_primary(addr) else {
+                // 端口被无关程序占用，无法判断，按主实例运行
+                log::warn!("单实例检测端口 {addr} 不可用: {e}");
+                let _ = ROLE.set(InstanceStatus::primary());
+                return Acquired::Primary;
+            };
+            log::warn!("检测到已运行的 cc-switch（pid {primary_pid}，版本 {primary_version}）");
+            if mode == SecondaryInstanceMode::Focus {
+                return Acquired::ShouldExit;
+            }
+            let _ = ROLE.set(InstanceStatus {
+                attached: true,
+                pid: std::process::id(),
+                primary_pid: Some(primary_pid),
+                primary_version: Some(primary_version),
+            });
+            Acquired::Attached
+        }
+    }
+}
+
+/// 绑定 AppHandle，供收到唤起请求时使用
+pub fn init(app: AppHandle) {
+    let _ = APP_HANDLE.set(app);
+}
+
+/// 当前实例是否为只读附加实例
+pub fn is_attached() -> bool {
+    ROLE.get().is_some_and(|r| r.attached)
+}
+
+/// 当前实例状态
+pub fn status() -> InstanceStatus {
+    ROLE.get().cloned().unwrap_or_else(InstanceStatus::primary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_primary_reply() {
+        assert_eq!(
+            parse_reply("cc-switch-instance 4242 3.9.1\n"),
+            Some((4242, "3.9.1".to_string()))
+        );
+        assert!(parse_reply("HTTP/1.1 400 Bad Request").is_none());
+        assert!(parse_reply("cc-switch-instance abc").is_none());
+    }
+
+    #[test]
+    fn parses_switch_requests_and_results() {
+        let request = SwitchRequest {
+            token: "abc".to_string(),
+            app: "claude".to_string(),
+            provider_id: "relay one".to_string(),
+        };
+        let line = format!(
+            "{HANDSHAKE} switch {}\n",
+            serde_json::to_string(&request).unwrap()
+        );
+        assert_eq!(parse_switch(&line), Some(request));
+        assert!(parse_switch("cc-switch-instance focus").is_none());
+        assert!(parse_switch("cc-switch-instance switch not-json").is_none());
+
+        assert!(parse_switch_result("ok\n").is_ok());
+        assert_eq!(
+            parse_switch_result("error 供应商不存在\n"),
+            Err("供应商不存在".to_string())
+        );
+        assert!(parse_switch_result("").is_err());
+    }
+
+    #[test]
+    fn port_stays_in_range() {
+        let port = instance_port();
+        assert!((PORT_BASE..PORT_BASE + PORT_RANGE).contains(&port));
+    }
+}
@@ -3,15 +3,20 @@ mod app_store;
 mod auto_launch;
 mod claude_mcp;
 mod claude_plugin;
+mod cli;
 mod codex_config;
 mod commands;
 mod config;
+mod config_validation;
 mod database;
 mod deeplink;
 mod error;
+mod events;
 mod gemini_config;
 mod gemini_mcp;
+mod i18n;
 mod init_status;
+mod instance_guard;
 mod mcp;
 mod prompt;
 mod prompt_files;
@@ -22,6 +27,7 @@ mod services;
 mod settings;
 mod store;
 mod tray;
+mod tray_health;
 mod usage_script;
 
 pub use app_config::{AppType, McpApps, McpServer, MultiAppConfig};
@@ -47,6 +53,7 @@ pub use store::AppState;
 use tauri_plugin_deep_link::DeepLinkExt;
 use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
 
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 #[cfg(target_os = "macos")]
 use tauri::image::Image;
@@ -149,12 +156,273 @@ fn macos_tray_icon() -> Option<Image<'static>> {
     }
 }
 
+/// 启动会写入数据库、Live 配置或外发数据的后台任务（只读附加实例不调用）
+fn spawn_writer_tasks(app: &tauri::AppHandle) {
+    // 定期导出 status.json（是否写入由设置决定）
+    {
+        let state = app.state::<AppState>();
+        let db = state.db.clone();
+        let proxy_service = state.proxy_service.clone();
+        tauri::async_runtime::spawn(crate::services::status_export::run(db, proxy_service));
+    }
+
+    // Codex auth.json 被改写后自动补回中转站 Key
+    {
+        let state = app.state::<AppState>();
+        tauri::async_runtime::spawn(crate::services::codex_auth_repair::run(
+            state.db.clone(),
+            state.proxy_service.clone(),
+        ));
+    }
+
+    // 定期保存代理运行状态（异常退出后也能恢复）
+    {
+        let state = app.state::<AppState>();
+        tauri::async_runtime::spawn(crate::services::proxy_runtime::run(
+            state.db.clone(),
+            state.proxy_service.clone(),
+        ));
+    }
+
+    // 记录故障、恢复与预算事件到时间线
+    tauri::async_runtime::spawn(crate::services::timeline::run(
+        app.state::<AppState>().db.clone(),
+    ));
+
+    // 外发任务投递（断网期间积压的告警在恢复后补发）
+    {
+        let db = app.state::<AppState>().db.clone();
+        tauri::async_runtime::spawn(crate::services::job_queue::run(db.clone()));
+        tauri::async_runtime::spawn(crate::services::job_queue::run_alert_producer(db));
+    }
+
+    // 标记上次退出时中断的后台任务
+    if let Err(e) = crate::services::background_task::recover(&app.state::<AppState>().db) {
+        log::warn!("恢复后台任务状态失败: {e}");
+    }
+
+    // 定期拍摄配置快照（发现同步、导入等途径的改动）
+    {
+        let db = app.state::<AppState>().db.clone();
+        tauri::async_runtime::spawn(crate::services::config_snapshot::run(db));
+    }
+
+    // 磁盘占用保护（超限时清理最旧的日志与备份）
+    {
+        let db = app.state::<AppState>().db.clone();
+        tauri::async_runtime::spawn(crate::services::disk_guard::run(db));
+    }
+
+    // 月底用量预测告警
+    {
+        let db = app.state::<AppState>().db.clone();
+        tauri::async_runtime::spawn(crate::services::usage_forecast::run(db));
+    }
+
+    // 延迟 SLO 燃烧率告警
+    {
+        let db = app.state::<AppState>().db.clone();
+        tauri::async_runtime::spawn(crate::services::latency_slo::run(db));
+    }
+
+    // 清理过期的会话记录
+    {
+        let db = app.state::<AppState>().db.clone();
+        tauri::async_runtime::spawn(crate::proxy::transcript::run(db));
+    }
+
+    // 访问日志外发（是否发送由设置决定）
+    tauri::async_runtime::spawn(crate::services::log_shipper::run());
+    tauri::async_runtime::spawn(crate::proxy::trace::run());
+}
+
+/// 按表独立判断的导入逻辑（各类数据独立检查，互不影响）
+pub(crate) fn import_initial_data(app_state: &AppState) {
+    // 1. 初始化默认 Skills 仓库（已有内置检查：表非空则跳过）
+    match app_state.db.init_default_skill_repos() {
+        Ok(count) if count > 0 => {
+            log::info!("✓ Initialized {count} default skill repositories");
+        }
+        Ok(_) => {} // 表非空，静默跳过
+        Err(e) => log::warn!("✗ Failed to initialize default skill repos: {e}"),
+    }
+
+    // 2. 导入供应商配置（已有内置检查：该应用已有供应商则跳过）
+    for app in [
+        crate::app_config::AppType::Claude,
+        crate::app_config::AppType::Codex,
+        crate::app_config::AppType::Gemini,
+    ] {
+        match crate::services::provider::ProviderService::import_default_config(
+            app_state,
+            app.clone(),
+        ) {
+            Ok(true) => {
+                log::info!("✓ Imported default provider for {}", app.as_str());
+            }
+            Ok(false) => {} // 已有供应商，静默跳过
+            Err(e) => {
+                log::debug!(
+                    "○ No default provider to import for {}: {}",
+                    app.as_str(),
+                    e
+                );
+            }
+        }
+    }
+
+    // 3. 导入 MCP 服务器配置（表空时触发）
+    if app_state.db.is_mcp_table_empty().unwrap_or(false) {
+        log::info!("MCP table empty, importing from live configurations...");
+
+        match crate::services::mcp::McpService::import_from_claude(app_state) {
+            Ok(count) if count > 0 => {
+                log::info!("✓ Imported {count} MCP server(s) from Claude");
+            }
+            Ok(_) => log::debug!("○ No Claude MCP servers found to import"),
+            Err(e) => log::warn!("✗ Failed to import Claude MCP: {e}"),
+        }
+
+        match crate::services::mcp::McpService::import_from_codex(app_state) {
+            Ok(count) if count > 0 => {
+                log::info!("✓ Imported {count} MCP server(s) from Codex");
+            }
+            Ok(_) => log::debug!("○ No Codex MCP servers found to import"),
+            Err(e) => log::warn!("✗ Failed to import Codex MCP: {e}"),
+        }
+
+        match crate::services::mcp::McpService::import_from_gemini(app_state) {
+            Ok(count) if count > 0 => {
+                log::info!("✓ Imported {count} MCP server(s) from Gemini");
+            }
+            Ok(_) => log::debug!("○ No Gemini MCP servers found to import"),
+            Err(e) => log::warn!("✗ Failed to import Gemini MCP: {e}"),
+        }
+    }
+
+    // 4. 导入提示词文件（表空时触发）
+    if app_state.db.is_prompts_table_empty().unwrap_or(false) {
+        log::info!("Prompts table empty, importing from live configurations...");
+
+        for app in [
+            crate::app_config::AppType::Claude,
+            crate::app_config::AppType::Codex,
+            crate::app_config::AppType::Gemini,
+        ] {
+            match crate::services::prompt::PromptService::import_from_file_on_first_launch(
+                app_state,
+                app.clone(),
+            ) {
+                Ok(count) if count > 0 => {
+                    log::info!("✓ Imported {count} prompt(s) for {}", app.as_str());
+                }
+                Ok(_) => log::debug!("○ No prompt file found for {}", app.as_str()),
+                Err(e) => log::warn!("✗ Failed to import prompt for {}: {e}", app.as_str()),
+            }
+        }
+    }
+}
+
+/// 异常退出恢复 + Live 配置一致性检查 + 代理状态自动恢复
+async fn recover_on_startup(app_handle: tauri::AppHandle) {
+    let state = app_handle.state::<AppState>();
+
+    // 只读附加实例不接管 Live 配置，由主实例负责恢复
+    if crate::instance_guard::is_attached() {
+        log::warn!("以只读附加模式运行，跳过接管恢复与代理自动启动");
+        return;
+    }
+
+    // 检查是否有 Live 备份（表示上次异常退出时可能处于接管状态）
+    let has_backups = match state.db.has_any_live_backup().await {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("检查 Live 备份失败: {e}");
+            false
+        }
+    };
+    // 检查 Live 配置是否仍处于被接管状态（包含占位符）
+    let live_taken_over = state.proxy_service.detect_takeover_in_live_configs();
+
+    if has_backups || live_taken_over {
+        log::warn!("检测到上次异常退出（存在接管残留），正在恢复 Live 配置...");
+        if let Err(e) = state.proxy_service.recover_from_crash().await {
+            log::error!("恢复 Live 配置失败: {e}");
+        } else {
+            log::info!("Live 配置已恢复");
+        }
+    }
+
+    // 检查当前供应商与 Live 配置是否被外部修改
+    match crate::services::provider::ProviderService::check_live_consistency(&state) {
+        Ok(drifts) => {
+            for drift in drifts {
+                log::warn!(
+                    "{} 的 Live 配置与当前供应商 {} 不一致",
+                    drift.app_type,
+                    drift.provider_id
+                );
+                crate::events::publish(crate::events::AppEvent::LiveConfigDrift(drift));
+            }
+        }
+        Err(e) => log::warn!("检查 Live 配置一致性失败: {e}"),
+    }
+
+    if !crate::services::startup_profile::is_enabled(
+        crate::services::startup_profile::Subsystem::Proxy,
+    ) {
+        log::info!("代理子系统已关闭，跳过代理自动恢复");
+        return;
+    }
+    // 检查 settings 表中的代理状态，自动恢复代理服务
+    restore_proxy_state_on_startup(&state).await;
+    // 恢复未接管时的运行状态、临时路由覆盖与自适应并发上限
+    crate::services::proxy_runtime::restore(&state.db, &state.proxy_service).await;
+}
+
+/// 启动依赖真实数据库的后台任务与恢复流程
+///
+/// 安全模式下数据库只是内存占位，启动时跳过，由口令解锁成功后调用；只执行一次。
+pub(crate) fn start_background_services(app: &tauri::AppHandle) {
+    static STARTED: AtomicBool = AtomicBool::new(false);
+    if STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    // 只读附加实例不运行写入数据库或配置文件的后台任务，由主实例负责
+    if crate::instance_guard::is_attached() {
+        log::warn!("以只读附加模式运行，跳过写入数据库的后台任务");
+    } else {
+        spawn_writer_tasks(app);
+    }
+
+    tauri::async_runtime::spawn(recover_on_startup(app.clone()));
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // 便携模式 / --data-dir / CC_SWITCH_DATA_DIR（需在读取任何配置前确定）
+    let args: Vec<String> = std::env::args().collect();
+    crate::app_store::init_startup_data_dir(&args);
+    crate::services::startup_profile::init_from_args(&args);
+
+    // 终端选择切换（cc-switch pick），不启动窗口
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
+    // 跨构建的单实例检测（开发版与安装版的应用标识不同，单实例插件无法识别）
+    let secondary_mode = crate::settings::get_settings().secondary_instance_mode;
+    if let instance_guard::Acquired::ShouldExit = instance_guard::acquire(secondary_mode) {
+        log::info!("已唤起正在运行的 cc-switch，当前实例退出");
+        return;
+    }
+
     let mut builder = tauri::Builder::default();
 
+    // 只读附加实例需要与主实例并存，不注册单实例插件
     #[cfg(any(target_os = "macos", target_os = "windows", target_os = "linux"))]
-    {
+    if !instance_guard::is_attached() {
         builder = builder.plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
             log::info!("=== Single Instance Callback Triggered ===");
             log::info!("Args count: {}", args.len());
@@ -224,14 +492,18 @@ pub fn run() {
                     log::warn!("初始化 Updater 插件失败，已跳过：{e}");
                 }
             }
-            // 初始化日志
-            if cfg!(debug_assertions) {
-                app.handle().plugin(
-                    tauri_plugin_log::Builder::default()
-                        .level(log::LevelFilter::Info)
-                        .build(),
-                )?;
-            }
+            // 初始化日志：插件按 Trace 安装，实际级别由设置中的 logLevels 按模块控制（可运行时修改）
+            app.handle().plugin(
+                tauri_plugin_log::Builder::default()
+                    .level(log::LevelFilter::Trace)
+                    .filter(crate::services::log_level::enabled)
+                    .build(),
+            )?;
+            crate::services::log_level::apply(&crate::settings::get_settings().log_levels);
+
+            // 绑定事件总线
+            crate::events::init(app.handle().clone());
+            crate::instance_guard::init(app.handle().clone());
 
             // 预先刷新 Store 覆盖配置，确保 AppState 初始化时可读取到最新路径
             app_store::refresh_app_config_dir_override(app.handle());
@@ -275,16 +547,36 @@ pub fn run() {
             };
 
             // 现在创建数据库
-            let db = match crate::database::Database::init() {
-                Ok(db) => Arc::new(db),
+            // 打开失败或完整性检查失败时进入安全模式：使用内存数据库启动，由前端引导恢复
+            let open_result = crate::database::Database::init().and_then(|db| {
+                let problems = db.integrity_check()?;
+                if problems.is_empty() {
+                    Ok(db)
+                } else {
+                    Err(crate::error::AppError::Database(format!(
+                        "完整性检查失败: {}",
+                        problems.join("; ")
+                    )))
+                }
+            });
+            let (db, safe_mode) = match open_result {
+                Ok(db) => (Arc::new(db), false),
                 Err(e) => {
-                    log::error!("Failed to init database: {e}");
-                    return Err(Box::new(e));
+                    log::error!("Failed to init database, entering safe mode: {e}");
+                    crate::init_status::set_safe_mode(crate::init_status::SafeModePayload {
+                        db_path: db_path.display().to_string(),
+                        reason: e.to_string(),
+                        encrypted: crate::database::encryption::is_configured(),
+                    });
+                    match crate::database::Database::memory() {
+                        Ok(db) => (Arc::new(db), true),
+                        Err(e) => return Err(Box::new(e)),
+                    }
                 }
             };
 
-            // 如果有预加载的配置，执行迁移
-            if let Some(config) = migration_config {
+            // 如果有预加载的配置，执行迁移（安全模式下跳过，避免归档旧配置后数据只存在内存中）
+            if let Some(config) = migration_config.filter(|_| !safe_mode) {
                 log::info!("开始执行数据迁移...");
 
                 match db.migrate_from_json(&config) {
@@ -312,92 +604,9 @@ pub fn run() {
             // 设置 AppHandle 用于代理故障转移时的 UI 更新
             app_state.proxy_service.set_app_handle(app.handle().clone());
 
-            // ============================================================
-            // 按表独立判断的导入逻辑（各类数据独立检查，互不影响）
-            // ============================================================
-
-            // 1. 初始化默认 Skills 仓库（已有内置检查：表非空则跳过）
-            match app_state.db.init_default_skill_repos() {
-                Ok(count) if count > 0 => {
-                    log::info!("✓ Initialized {count} default skill repositories");
-                }
-                Ok(_) => {} // 表非空，静默跳过
-                Err(e) => log::warn!("✗ Failed to initialize default skill repos: {e}"),
-            }
-
-            // 2. 导入供应商配置（已有内置检查：该应用已有供应商则跳过）
-            for app in [
-                crate::app_config::AppType::Claude,
-                crate::app_config::AppType::Codex,
-                crate::app_config::AppType::Gemini,
-            ] {
-                match crate::services::provider::ProviderService::import_default_config(
-                    &app_state,
-                    app.clone(),
-                ) {
-                    Ok(true) => {
-                        log::info!("✓ Imported default provider for {}", app.as_str());
-                    }
-                    Ok(false) => {} // 已有供应商，静默跳过
-                    Err(e) => {
-                        log::debug!(
-                            "○ No default provider to import for {}: {}",
-                            app.as_str(),
-                            e
-                        );
-                    }
-                }
-            }
-
-            // 3. 导入 MCP 服务器配置（表空时触发）
-            if app_state.db.is_mcp_table_empty().unwrap_or(false) {
-                log::info!("MCP table empty, importing from live configurations...");
-
-                match crate::services::mcp::McpService::import_from_claude(&app_state) {
-                    Ok(count) if count > 0 => {
-                        log::info!("✓ Imported {count} MCP server(s) from Claude");
-                    }
-                    Ok(_) => log::debug!("○ No Claude MCP servers found to import"),
-                    Err(e) => log::warn!("✗ Failed to import Claude MCP: {e}"),
-                }
-
-                match crate::services::mcp::McpService::import_from_codex(&app_state) {
-                    Ok(count) if count > 0 => {
-                        log::info!("✓ Imported {count} MCP server(s) from Codex");
-                    }
-                    Ok(_) => log::debug!("○ No Codex MCP servers found to import"),
-                    Err(e) => log::warn!("✗ Failed to import Codex MCP: {e}"),
-                }
-
-                match crate::services::mcp::McpService::import_from_gemini(&app_state) {
-                    Ok(count) if count > 0 => {
-                        log::info!("✓ Imported {count} MCP server(s) from Gemini");
-                    }
-                    Ok(_) => log::debug!("○ No Gemini MCP servers found to import"),
-                    Err(e) => log::warn!("✗ Failed to import Gemini MCP: {e}"),
-                }
-            }
-
-            // 4. 导入提示词文件（表空时触发）
-            if app_state.db.is_prompts_table_empty().unwrap_or(false) {
-                log::info!("Prompts table empty, importing from live configurations...");
-
-                for app in [
-                    crate::app_config::AppType::Claude,
-                    crate::app_config::AppType::Codex,
-                    crate::app_config::AppType::Gemini,
-                ] {
-                    match crate::services::prompt::PromptService::import_from_file_on_first_launch(
-                        &app_state,
-                        app.clone(),
-                    ) {
-                        Ok(count) if count > 0 => {
-                            log::info!("✓ Imported {count} prompt(s) for {}", app.as_str());
-                        }
-                        Ok(_) => log::debug!("○ No prompt file found for {}", app.as_str()),
-                        Err(e) => log::warn!("✗ Failed to import prompt for {}: {e}", app.as_str()),
-                    }
-                }
+            // 安全模式下数据库只是内存占位，不导入，解锁后再执行
+            if !safe_mode {
+                import_initial_data(&app_state);
             }
 
             // 迁移旧的 app_config_dir 配置到 Store
@@ -577,34 +786,18 @@ pub fn run() {
                 }
             }
 
-            // 异常退出恢复 + 代理状态自动恢复
-            let app_handle = app.handle().clone();
-            tauri::async_runtime::spawn(async move {
-                let state = app_handle.state::<AppState>();
+            // 托盘图标与角标反映供应商健康状态
+            tauri::async_runtime::spawn(crate::tray_health::run(app.handle().clone()));
 
-                // 检查是否有 Live 备份（表示上次异常退出时可能处于接管状态）
-                let has_backups = match state.db.has_any_live_backup().await {
-                    Ok(v) => v,
-                    Err(e) => {
-                        log::error!("检查 Live 备份失败: {e}");
-                        false
-                    }
-                };
-                // 检查 Live 配置是否仍处于被接管状态（包含占位符）
-                let live_taken_over = state.proxy_service.detect_takeover_in_live_configs();
-
-                if has_backups || live_taken_over {
-                    log::warn!("检测到上次异常退出（存在接管残留），正在恢复 Live 配置...");
-                    if let Err(e) = state.proxy_service.recover_from_crash().await {
-                        log::error!("恢复 Live 配置失败: {e}");
-                    } else {
-                        log::info!("Live 配置已恢复");
-                    }
-                }
+            if let Ok(log_dir) = app.path().app_log_dir() {
+                crate::services::disk_guard::set_log_dir(log_dir);
+            }
 
-                // 检查 settings 表中的代理状态，自动恢复代理服务
-                restore_proxy_state_on_startup(&state).await;
-            });
+            if safe_mode {
+                log::warn!("安全模式下跳过后台任务与接管恢复，数据库解锁后再启动");
+            } else {
+                start_background_services(app.handle());
+            }
 
             Ok(())
         })
@@ -615,6 +808,32 @@ pub fn run() {
             commands::update_provider,
             commands::delete_provider,
             commands::switch_provider,
+            commands::preview_switch_provider,
+            commands::get_switch_queue_status,
+            commands::check_provider_capabilities,
+            commands::add_provider_vault_note,
+            commands::add_provider_vault_file,
+            commands::list_provider_vault_items,
+            commands::read_provider_vault_item,
+            commands::delete_provider_vault_item,
+            commands::reveal_provider_key,
+            commands::parse_provider_from_clipboard,
+            commands::export_provider_for_clipboard,
+            commands::export_provider_transfer,
+            commands::export_provider_config,
+            commands::bulk_import_providers,
+            commands::decode_provider_transfer,
+            commands::import_provider_transfer,
+            commands::find_duplicate_providers,
+            commands::merge_duplicate_providers,
+            commands::preview_provider_sync_merge,
+            commands::apply_provider_sync_merge,
+            commands::get_recent_providers,
+            commands::get_timeline,
+            commands::annotate_timeline_entry,
+            commands::switch_to_previous_provider,
+            commands::check_live_consistency,
+            commands::reconcile_live_config,
             commands::import_default_config,
             commands::get_claude_config_status,
             commands::get_config_status,
@@ -624,9 +843,20 @@ pub fn run() {
             commands::pick_directory,
             commands::open_external,
             commands::get_init_error,
+            commands::get_safe_mode_status,
+            commands::list_db_backups,
+            commands::restore_db_backup,
+            commands::export_salvaged_database,
+            commands::reinitialize_database,
+            commands::get_db_encryption_status,
+            commands::enable_db_encryption,
+            commands::disable_db_encryption,
+            commands::unlock_encrypted_database,
             commands::get_migration_result,
             commands::get_app_config_path,
             commands::open_app_config_folder,
+            commands::list_access_logs,
+            commands::open_access_log_folder,
             commands::get_claude_common_config_snippet,
             commands::set_claude_common_config_snippet,
             commands::get_common_config_snippet,
@@ -634,7 +864,12 @@ pub fn run() {
             commands::read_live_provider_settings,
             commands::get_settings,
             commands::save_settings,
+            commands::get_log_levels,
+            commands::set_log_level,
+            commands::get_subsystem_status,
+            commands::set_subsystem_enabled,
             commands::restart_app,
+            commands::reset_app,
             commands::check_for_updates,
             commands::is_portable_mode,
             commands::get_claude_plugin_status,
@@ -678,6 +913,7 @@ pub fn run() {
             // app_config_dir override via Store
             commands::get_app_config_dir_override,
             commands::set_app_config_dir_override,
+            commands::migrate_data_dir,
             // provider sort order management
             commands::update_providers_sort_order,
             // theirs: config import/export and dialogs
@@ -709,12 +945,55 @@ pub fn run() {
             // Auto launch
             commands::set_auto_launch,
             commands::get_auto_launch_status,
+            commands::get_config_lock_status,
+            commands::lock_config,
+            commands::unlock_config,
+            commands::get_instance_status,
             // Proxy server management
             commands::start_proxy_server,
             commands::stop_proxy_with_restore,
             commands::get_proxy_takeover_status,
             commands::set_proxy_takeover_for_app,
             commands::get_proxy_status,
+            commands::get_proxy_tps_history,
+            commands::get_fleet_summary,
+            commands::get_tps_samples,
+            commands::get_shadow_comparisons,
+            commands::get_shadow_summary,
+            commands::clear_shadow_comparisons,
+            commands::get_substitution_evidence,
+            commands::get_substitution_suspects,
+            commands::clear_substitution_evidence,
+            commands::get_queued_requests,
+            commands::clear_queued_requests,
+            commands::set_fault_injection,
+            commands::clear_fault_injection,
+            commands::list_fault_injections,
+            commands::add_mock_provider,
+            commands::start_request_replay,
+            commands::set_session_override,
+            commands::clear_session_override,
+            commands::list_session_overrides,
+            commands::get_output_validation_stats,
+            commands::get_redaction_stats,
+            commands::get_sse_sanitize_stats,
+            commands::get_parameter_policy_stats,
+            commands::get_http_client_pool_stats,
+            commands::recycle_http_clients,
+            commands::get_keep_warm_estimate,
+            commands::get_network_status,
+            commands::discover_lan_proxies,
+            commands::list_api_tokens,
+            commands::create_api_token,
+            commands::revoke_api_token,
+            commands::bootstrap_api_admin_token,
+            commands::run_diagnostics,
+            commands::get_audit_log,
+            commands::validate_stored_configs,
+            // Local model provider
+            commands::start_local_model,
+            commands::stop_local_model,
+            commands::get_local_model_status,
             commands::get_proxy_config,
             commands::update_proxy_config,
             // Global & Per-App Config
@@ -742,22 +1021,54 @@ pub fn run() {
             commands::get_usage_summary,
             commands::get_usage_trends,
             commands::get_provider_stats,
+            commands::get_provider_activity,
             commands::get_model_stats,
+            commands::get_streaming_speed_stats,
+            commands::get_bandwidth_usage,
+            commands::get_tag_stats,
+            commands::get_latency_heatmap,
             commands::get_request_logs,
             commands::get_request_detail,
+            commands::get_transcripts,
+            commands::delete_transcripts,
             commands::get_model_pricing,
             commands::update_model_pricing,
             commands::delete_model_pricing,
             commands::check_provider_limits,
+            commands::get_usage_forecast,
+            commands::get_latency_slo_status,
             // Stream health check
             commands::stream_check_provider,
             commands::stream_check_all_providers,
+            commands::cancel_check,
+            commands::start_stream_check_all_task,
+            commands::start_endpoint_speedtest_task,
+            commands::get_background_task,
+            commands::list_background_tasks,
+            commands::cancel_background_task,
+            commands::validate_provider_draft,
             commands::get_stream_check_config,
             commands::save_stream_check_config,
             commands::get_stream_check_latest,
+            commands::get_blended_health,
             commands::get_stream_check_history,
+            commands::get_probe_budget_status,
+            commands::subscribe_events,
+            commands::unsubscribe_events,
+            commands::list_pending_jobs,
+            commands::retry_pending_job,
             // Provider TPS test
             commands::tps_test_provider,
+            commands::compare_prompt,
+            commands::list_prompt_comparisons,
+            commands::get_prompt_comparison,
+            commands::delete_prompt_comparison,
+            commands::get_config_changes,
+            commands::take_config_snapshot,
+            commands::list_config_snapshots,
+            commands::get_config_snapshot,
+            commands::get_disk_usage,
+            commands::enforce_disk_limits,
             commands::get_tool_versions,
             // Universal Provider management
             commands::get_universal_providers,
@@ -881,9 +1192,15 @@ pub fn run() {
 /// 确保 Claude Code/Codex/Gemini 的配置不会处于损坏状态。
 /// 使用 stop_with_restore_keep_state 保留 settings 表中的代理状态，下次启动时自动恢复。
 pub async fn cleanup_before_exit(app_handle: &tauri::AppHandle) {
+    // 停止由 cc-switch 启动的本地模型服务
+    crate::services::local_model::LocalModelService::stop_all();
+
     if let Some(state) = app_handle.try_state::<store::AppState>() {
         let proxy_service = &state.proxy_service;
 
+        // 停止代理前保存运行状态，下次启动时恢复
+        crate::services::proxy_runtime::persist(&state.db, proxy_service).await;
+
         // 退出时也需要兜底：代理可能已崩溃/未运行，但 Live 接管残留仍在（占位符/备份）。
         let has_backups = match state.db.has_any_live_backup().await {
             Ok(v) => v,
@@ -925,7 +1242,7 @@ pub async fn cleanup_before_exit(app_handle: &tauri::AppHandle) {
 ///
 /// 检查 `proxy_config.enabled` 字段，如果有任一应用的状态为 `true`，
 /// 则自动启动代理服务并接管对应应用的 Live 配置。
-async fn restore_proxy_state_on_startup(state: &store::AppState) {
+pub(crate) async fn restore_proxy_state_on_startup(state: &store::AppState) {
     // 收集需要恢复接管的应用列表（从 proxy_config.enabled 读取）
     let mut apps_to_restore = Vec::new();
     for app_type in ["claude", "codex", "gemini"] {
@@ -168,6 +168,84 @@ pub struct ProviderMeta {
     /// 可用性监控开关（每个 Provider 独立，默认关闭）
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub availability_monitor_enabled: Option<bool>,
+    /// IPv4/IPv6 连接偏好（代理转发与健康检查共用，未设置为自动）
+    #[serde(rename = "ipPreference", skip_serializing_if = "Option::is_none")]
+    pub ip_preference: Option<crate::proxy::ip_preference::IpPreference>,
+    /// TLS 选项：自定义 CA、跳过证书校验、SNI 覆盖
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<crate::proxy::provider_tls::ProviderTlsOptions>,
+    /// 认证方案覆盖（Bearer / x-api-key / HMAC 签名，未设置时由适配器决定）
+    #[serde(rename = "authScheme", skip_serializing_if = "Option::is_none")]
+    pub auth_scheme: Option<crate::proxy::auth_scheme::AuthScheme>,
+    /// 云厂商托管的 Claude（Bedrock / Vertex AI），设置后由代理负责签名与格式改写
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cloud: Option<crate::proxy::providers::CloudProviderConfig>,
+    /// Azure OpenAI 部署路由（部署映射与 api-version）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub azure: Option<crate::proxy::providers::AzureOpenAiConfig>,
+    /// 本地模型服务（Ollama / llama.cpp）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local: Option<crate::proxy::providers::LocalModelConfig>,
+    /// gRPC 上游（自建推理服务），请求与响应由代理转换为 protobuf
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grpc: Option<crate::proxy::providers::GrpcUpstreamConfig>,
+    /// 健康检查时额外探测的模型（默认测试模型之外）
+    #[serde(
+        rename = "streamCheckModels",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub stream_check_models: Vec<String>,
+    /// 健康检查探测方式（轻量探测与完整流式检查的间隔）
+    #[serde(rename = "healthProbe", skip_serializing_if = "Option::is_none")]
+    pub health_probe: Option<crate::services::health_probe::HealthProbeConfig>,
+    /// 每月健康检查 token 预算（覆盖全局设置，0 表示不限制）
+    #[serde(rename = "probeTokenBudget", skip_serializing_if = "Option::is_none")]
+    pub probe_token_budget: Option<u64>,
+    /// 单次请求的输出 token 上限（代理转发时把 max_tokens 等字段截断到此值）
+    #[serde(rename = "maxOutputTokens", skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u64>,
+    /// SSE 数据块合并窗口（毫秒），上游逐 token 发包时攒一段再转发
+    #[serde(rename = "sseCoalesceMs", skip_serializing_if = "Option::is_none")]
+    pub sse_coalesce_ms: Option<u64>,
+    /// 能力标记（工具调用、图片输入、提示缓存、上下文窗口），切换前据此提示
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<crate::services::provider::ProviderCapabilities>,
+    /// 端点路径模板（按标准端点覆盖请求路径，代理与健康检查共用）
+    #[serde(rename = "endpointTemplates", skip_serializing_if = "Option::is_none")]
+    pub endpoint_templates: Option<crate::proxy::endpoint_template::EndpointTemplates>,
+    /// 维护窗口（期间健康检查失败不告警，请求失败不计入熔断）
+    #[serde(
+        rename = "maintenanceWindows",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub maintenance_windows: Vec<crate::services::maintenance::MaintenanceWindow>,
+    /// 客户端身份（发往上游的 User-Agent 与身份请求头，代理与健康检查共用）
+    #[serde(rename = "clientIdentity", skip_serializing_if = "Option::is_none")]
+    pub client_identity: Option<crate::proxy::client_identity::ClientIdentity>,
+    /// 自适应并发上限（按 429/503 与延迟调整，可设置硬上限）
+    #[serde(
+        rename = "adaptiveConcurrency",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub adaptive_concurrency: Option<crate::proxy::concurrency::AdaptiveConcurrencyConfig>,
+    /// 请求参数策略（与设置中的全局策略合并，同名参数以供应商为准）
+    #[serde(
+        rename = "parameterPolicy",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub parameter_policy: Option<crate::proxy::param_policy::ParameterPolicy>,
+    /// 标签（用于分组与筛选）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// 置顶（托盘图标按置顶供应商的整体健康状态着色）
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    /// 延迟 SLO（按请求日志计算错误预算与燃烧率，燃烧过快时告警）
+    #[serde(rename = "latencySlo", skip_serializing_if = "Option::is_none")]
+    pub latency_slo: Option<crate::services::latency_slo::LatencySlo>,
 }
 
 impl ProviderManager {
@@ -0,0 +1,275 @@
+//! 管理 API 的令牌与权限
+//!
+//! 每个令牌带有一组权限，管理 API 的每个路由声明所需权限，请求时逐个校验：
+//! - `read-status`：读取代理状态、当前供应商与健康状态
+//! - `switch`：切换当前供应商
+//! - `edit-providers`：修改供应商配置（包括更换 API Key）
+//! - `read-usage`：读取用量统计
+//! - `admin`：全部权限，并可管理令牌
+//!
+//! 数据库只保存令牌的 SHA-256 摘要，明文只在创建时返回一次。
+//! 管理员引导令牌（bootstrap）重新生成时会吊销旧的引导令牌。
+
+use std::str::FromStr;
+
+use axum::http::HeaderMap;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::database::Database;
+use crate::error::AppError;
+
+/// 令牌前缀（便于在配置文件、日志中识别）
+const TOKEN_PREFIX: &str = "ccs_";
+/// 除 `Authorization: Bearer` 外也接受的请求头
+pub const TOKEN_HEADER: &str = "x-cc-switch-token";
+
+/// 权限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ApiScope {
+    ReadStatus,
+    Switch,
+    EditProviders,
+    ReadUsage,
+    Admin,
+}
+
+impl ApiScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ReadStatus => "read-status",
+            Self::Switch => "switch",
+            Self::EditProviders => "edit-providers",
+            Self::ReadUsage => "read-usage",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+impl FromStr for ApiScope {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "read-status" => Ok(Self::ReadStatus),
+            "switch" => Ok(Self::Switch),
+            "edit-providers" => Ok(Self::EditProviders),
+            "read-usage" => Ok(Self::ReadUsage),
+            "admin" => Ok(Self::Admin),
+            other => Err(AppError::InvalidInput(format!("未知的权限: {other}"))),
+        }
+    }
+}
+
+/// 令牌信息（不含明文）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    /// 管理员引导令牌
+    #[serde(default)]
+    pub bootstrap: bool,
+    pub created_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<i64>,
+}
+
+impl ApiToken {
+    pub fn allows(&self, scope: ApiScope) -> bool {
+        self.scopes.contains(&ApiScope::Admin) || self.scopes.contains(&scope)
+    }
+}
+
+/// 新建的令牌（明文只返回这一次）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedApiToken {
+    pub token: ApiToken,
+    pub secret: String,
+}
+
+/// 校验失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthError {
+    /// 缺少令牌或令牌无效（401）
+    Unauthorized,
+    /// 令牌没有所需权限（403）
+    Forbidden(ApiScope),
+}
+
+pub fn hash_token(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.trim().as_bytes()))
+}
+
+fn generate_secret() -> Result<String, AppError> {
+    let mut bytes = [0u8; 24];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| AppError::Message("生成随机令牌失败".to_string()))?;
+    Ok(format!("{TOKEN_PREFIX}{}", hex::encode(bytes)))
+}
+
+/// 创建令牌
+pub fn create_token(
+    db: &Database,
+    name: &str,
+    scopes: &[ApiScope],
+    bootstrap: bool,
+) -> Result<CreatedApiToken, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::InvalidInput("令牌名称不能为空".to_string()));
+    }
+    let mut scopes = scopes.to_vec();
+    scopes.sort_by_key(|s| s.as_str());
+    scopes.dedup();
+    if scopes.is_empty() {
+        return Err(AppError::InvalidInput("至少需要一项权限".to_string()));
+    }
+
+    let secret = generate_secret()?;
+    let token = ApiToken {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        scopes,
+        bootstrap,
+        created_at: chrono::Utc::now().timestamp(),
+        last_used_at: None,
+    };
+    db.insert_api_token(&token, &hash_token(&secret))?;
+    Ok(CreatedApiToken { token, secret })
+}
+
+/// 重新生成管理员引导令牌（吊销旧的引导令牌），明文只返回这一次
+pub fn bootstrap_admin_token(db: &Database) -> Result<CreatedApiToken, AppError> {
+    let revoked = db.delete_bootstrap_api_tokens()?;
+    if revoked > 0 {
+        log::info!("[ManagementApi] 已吊销 {revoked} 个旧的管理员引导令牌");
+    }
+    create_token(db, "bootstrap", &[ApiScope::Admin], true)
+}
+
+/// 从请求头读取令牌
+pub fn token_from_headers(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| {
+            v.strip_prefix("Bearer ")
+                .or_else(|| v.strip_prefix("bearer "))
+        })
+        .or_else(|| headers.get(TOKEN_HEADER).and_then(|v| v.to_str().ok()))
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+}
+
+/// 校验请求是否带有具备 `scope` 权限的令牌
+pub fn authorize(
+    db: &Database,
+    headers: &HeaderMap,
+    scope: ApiScope,
+) -> Result<ApiToken, AuthError> {
+    let secret = token_from_headers(headers).ok_or(AuthError::Unauthorized)?;
+    let token = match db.find_api_token_by_hash(&hash_token(secret)) {
+        Ok(Some(token)) => token,
+        Ok(None) => return Err(AuthError::Unauthorized),
+        Err(e) => {
+            log::warn!("[ManagementApi] 读取令牌失败: {e}");
+            return Err(AuthError::Unauthorized);
+        }
+    };
+    if !token.allows(scope) {
+        return Err(AuthError::Forbidden(scope));
+    }
+    if let Err(e) = db.touch_api_token(&token.id, chrono::Utc::now().timestamp()) {
+        log::debug!("[ManagementApi] 更新令牌使用时间失败: {e}");
+    }
+    Ok(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn admin_allows_every_scope() {
+        let token = ApiToken {
+            id: "t".to_string(),
+            name: "bar".to_string(),
+            scopes: vec![ApiScope::ReadStatus],
+            bootstrap: false,
+            created_at: 0,
+            last_used_at: None,
+        };
+        assert!(token.allows(ApiScope::ReadStatus));
+        assert!(!token.allows(ApiScope::EditProviders));
+
+        let admin = ApiToken {
+            scopes: vec![ApiScope::Admin],
+            ..token
+        };
+        assert!(admin.allows(ApiScope::EditProviders));
+    }
+
+    #[test]
+    fn reads_token_from_either_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(token_from_headers(&headers), None);
+        headers.insert(TOKEN_HEADER, HeaderValue::from_static("ccs_abc"));
+        assert_eq!(token_from_headers(&headers), Some("ccs_abc"));
+        headers.insert(
+            axum::http::header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer ccs_def"),
+        );
+        assert_eq!(token_from_headers(&headers), Some("ccs_def"));
+    }
+
+    #[test]
+    fn enforces_scopes_against_stored_tokens() {
+        let db = Database::memory().expect("memory db");
+        let status = create_token(&db, "status-bar", &[ApiScope::ReadStatus], false).unwrap();
+        assert!(status.secret.starts_with(TOKEN_PREFIX));
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            authorize(&db, &headers, ApiScope::ReadStatus),
+            Err(AuthError::Unauthorized)
+        );
+        headers.insert(TOKEN_HEADER, HeaderValue::from_str(&status.secret).unwrap());
+        assert_eq!(
+            authorize(&db, &headers, ApiScope::ReadStatus).map(|t| t.id),
+            Ok(status.token.id.clone())
+        );
+        assert_eq!(
+            authorize(&db, &headers, ApiScope::EditProviders),
+            Err(AuthError::Forbidden(ApiScope::EditProviders))
+        );
+
+        // 重新生成引导令牌后旧的立即失效
+        let first = bootstrap_admin_token(&db).unwrap();
+        let second = bootstrap_admin_token(&db).unwrap();
+        headers.insert(TOKEN_HEADER, HeaderValue::from_str(&first.secret).unwrap());
+        assert_eq!(
+            authorize(&db, &headers, ApiScope::Admin),
+            Err(AuthError::Unauthorized)
+        );
+        headers.insert(TOKEN_HEADER, HeaderValue::from_str(&second.secret).unwrap());
+        assert!(authorize(&db, &headers, ApiScope::Switch).is_ok());
+    }
+
+    #[test]
+    fn rejects_empty_scopes_and_unknown_names() {
+        let db = Database::memory().expect("memory db");
+        assert!(create_token(&db, "x", &[], false).is_err());
+        assert!("rotate-keys".parse::<ApiScope>().is_err());
+        assert_eq!(
+            "read-usage".parse::<ApiScope>().unwrap(),
+            ApiScope::ReadUsage
+        );
+    }
+}
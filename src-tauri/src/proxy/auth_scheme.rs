@@ -0,0 +1,319 @@
+//! 供应商认证方案
+//!
+//! 默认由各应用的适配器决定认证头（x-api-key / Bearer / x-goog-api-key）。
+//! 部分企业网关要求固定的认证头或 HMAC 签名（时间戳 + 路径 + 请求体摘要），
+//! 可在 ProviderMeta.authScheme 中为单个供应商覆盖。代理转发与流式检查共用这里的签名逻辑。
+//!
+//! HMAC 模板支持的占位符：
+//! - `{method}` 请求方法（大写）
+//! - `{path}` 路径（含查询串）
+//! - `{host}` 主机名
+//! - `{timestamp}` / `{timestampMs}` Unix 时间戳（秒 / 毫秒）
+//! - `{nonce}` 随机串
+//! - `{bodySha256}` 请求体 SHA-256（小写 hex）
+//! - `{apiKey}` / `{keyId}`
+//! - `{signature}` 签名结果（仅可用于请求头模板）
+
+use std::collections::BTreeMap;
+
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::proxy::providers::AuthInfo;
+
+/// 适配器可能写入的认证头，切换认证方案时统一移除
+const ADAPTER_AUTH_HEADERS: &[&str] = &["authorization", "x-api-key", "x-goog-api-key"];
+
+/// 认证方案
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AuthScheme {
+    /// `Authorization: Bearer <apiKey>`
+    Bearer,
+    /// `x-api-key: <apiKey>`
+    XApiKey,
+    /// 按模板计算 HMAC 签名并写入请求头
+    Hmac(HmacAuthConfig),
+}
+
+/// 签名结果编码
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+/// HMAC-SHA256 签名配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HmacAuthConfig {
+    /// 访问密钥 ID（可在模板中以 {keyId} 引用）
+    #[serde(default)]
+    pub key_id: String,
+    /// 签名密钥（为空时使用供应商的 API Key）
+    #[serde(default)]
+    pub secret: String,
+    /// 待签名字符串模板
+    #[serde(default = "default_string_to_sign")]
+    pub string_to_sign: String,
+    /// 请求头模板（请求头名 -> 值模板）
+    #[serde(default = "default_signed_headers")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub encoding: SignatureEncoding,
+}
+
+fn default_string_to_sign() -> String {
+    "{method}\n{path}\n{timestamp}\n{bodySha256}".to_string()
+}
+
+fn default_signed_headers() -> BTreeMap<String, String> {
+    BTreeMap::from([
+        ("X-Timestamp".to_string(), "{timestamp}".to_string()),
+        ("X-Content-SHA256".to_string(), "{bodySha256}".to_string()),
+        ("X-Signature".to_string(), "{signature}".to_string()),
+    ])
+}
+
+impl Default for HmacAuthConfig {
+    fn default() -> Self {
+        Self {
+            key_id: String::new(),
+            secret: String::new(),
+            string_to_sign: default_string_to_sign(),
+            headers: default_signed_headers(),
+            encoding: SignatureEncoding::default(),
+        }
+    }
+}
+
+impl AuthScheme {
+    /// 读取供应商配置的认证方案（未配置时返回 None，沿用适配器默认行为）
+    pub fn of(provider: &Provider) -> Option<&Self> {
+        provider.meta.as_ref().and_then(|m| m.auth_scheme.as_ref())
+    }
+}
+
+/// 签名所需的请求信息
+struct SigningContext<'a> {
+    method: &'a str,
+    path: String,
+    host: &'a str,
+    timestamp: i64,
+    nonce: String,
+    body_sha256: String,
+    api_key: &'a str,
+    key_id: &'a str,
+}
+
+impl SigningContext<'_> {
+    fn render(&self, template: &str, signature: Option<&str>) -> String {
+        let rendered = template
+            .replace("{method}", self.method)
+            .replace("{path}", &self.path)
+            .replace("{host}", self.host)
+            .replace("{timestampMs}", &(self.timestamp * 1000).to_string())
+            .replace("{timestamp}", &self.timestamp.to_string())
+            .replace("{nonce}", &self.nonce)
+            .replace("{bodySha256}", &self.body_sha256)
+            .replace("{apiKey}", self.api_key)
+            .replace("{keyId}", self.key_id);
+        match signature {
+            Some(sig) => rendered.replace("{signature}", sig),
+            None => rendered,
+        }
+    }
+}
+
+/// 计算 HMAC-SHA256 签名
+fn sign(secret: &[u8], message: &str, encoding: SignatureEncoding) -> Result<String, AppError> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret)
+        .map_err(|e| AppError::Message(format!("HMAC 密钥无效: {e}")))?;
+    mac.update(message.as_bytes());
+    let bytes = mac.finalize().into_bytes();
+    Ok(match encoding {
+        SignatureEncoding::Hex => hex::encode(bytes),
+        SignatureEncoding::Base64 => base64::engine::general_purpose::STANDARD.encode(bytes),
+    })
+}
+
+fn insert_header(request: &mut reqwest::Request, name: &str, value: &str) -> Result<(), AppError> {
+    let name = HeaderName::from_bytes(name.trim().as_bytes())
+        .map_err(|e| AppError::Message(format!("无效的请求头名 {name}: {e}")))?;
+    let value = HeaderValue::from_str(value)
+        .map_err(|e| AppError::Message(format!("无效的请求头值 ({name}): {e}")))?;
+    request.headers_mut().insert(name, value);
+    Ok(())
+}
+
+/// 按供应商的认证方案改写已构建的请求
+///
+/// 未配置时不做任何修改并返回 false。需在请求体确定之后调用（签名覆盖请求体摘要）。
+pub fn apply_auth_scheme(
+    provider: &Provider,
+    auth: Option<&AuthInfo>,
+    request: &mut reqwest::Request,
+) -> Result<bool, AppError> {
+    apply_auth_scheme_at(provider, auth, request, chrono::Utc::now().timestamp())
+}
+
+fn apply_auth_scheme_at(
+    provider: &Provider,
+    auth: Option<&AuthInfo>,
+    request: &mut reqwest::Request,
+    timestamp: i64,
+) -> Result<bool, AppError> {
+    let Some(scheme) = AuthScheme::of(provider) else {
+        return Ok(false);
+    };
+    let api_key = auth.map(|a| a.api_key.as_str()).unwrap_or_default();
+
+    for name in ADAPTER_AUTH_HEADERS {
+        request.headers_mut().remove(*name);
+    }
+
+    match scheme {
+        AuthScheme::Bearer => {
+            insert_header(
+                request,
+                AUTHORIZATION.as_str(),
+                &format!("Bearer {api_key}"),
+            )?;
+        }
+        AuthScheme::XApiKey => {
+            insert_header(request, "x-api-key", api_key)?;
+        }
+        AuthScheme::Hmac(config) => {
+            let body = request
+                .body()
+                .and_then(|b| b.as_bytes())
+                .unwrap_or_default();
+            let url = request.url();
+            let path = match url.query() {
+                Some(q) => format!("{}?{q}", url.path()),
+                None => url.path().to_string(),
+            };
+            let ctx = SigningContext {
+                method: request.method().as_str(),
+                path,
+                host: url.host_str().unwrap_or_default(),
+                timestamp,
+                nonce: uuid::Uuid::new_v4().simple().to_string(),
+                body_sha256: hex::encode(Sha256::digest(body)),
+                api_key,
+                key_id: &config.key_id,
+            };
+
+            let secret = if config.secret.is_empty() {
+                api_key
+            } else {
+                config.secret.as_str()
+            };
+            if secret.is_empty() {
+                return Err(AppError::Message("HMAC 签名密钥为空".to_string()));
+            }
+
+            let signature = sign(
+                secret.as_bytes(),
+                &ctx.render(&config.string_to_sign, None),
+                config.encoding,
+            )?;
+            let rendered: Vec<(String, String)> = config
+                .headers
+                .iter()
+                .map(|(name, template)| (name.clone(), ctx.render(template, Some(&signature))))
+                .collect();
+            for (name, value) in rendered {
+                insert_header(request, &name, &value)?;
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderMeta;
+    use crate::proxy::providers::AuthStrategy;
+    use serde_json::json;
+
+    fn provider_with_scheme(scheme: AuthScheme) -> Provider {
+        let mut provider = Provider::with_id("p".into(), "P".into(), json!({}), None);
+        provider.meta = Some(ProviderMeta {
+            auth_scheme: Some(scheme),
+            ..ProviderMeta::default()
+        });
+        provider
+    }
+
+    fn build_request() -> reqwest::Request {
+        reqwest::Client::new()
+            .post("https://gw.example.com/v1/messages?beta=true")
+            .header("x-api-key", "sk-old")
+            .body(r#"{"a":1}"#)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn bearer_replaces_adapter_headers() {
+        let provider = provider_with_scheme(AuthScheme::Bearer);
+        let auth = AuthInfo::new("sk-test".to_string(), AuthStrategy::Anthropic);
+        let mut request = build_request();
+
+        assert!(apply_auth_scheme(&provider, Some(&auth), &mut request).unwrap());
+        assert!(request.headers().get("x-api-key").is_none());
+        assert_eq!(
+            request.headers().get("authorization").unwrap(),
+            "Bearer sk-test"
+        );
+    }
+
+    #[test]
+    fn hmac_signs_method_path_timestamp_and_body() {
+        let provider = provider_with_scheme(AuthScheme::Hmac(HmacAuthConfig {
+            secret: "secret".to_string(),
+            ..HmacAuthConfig::default()
+        }));
+        let mut request = build_request();
+
+        apply_auth_scheme_at(&provider, None, &mut request, 1_700_000_000).unwrap();
+
+        let body_sha = hex::encode(Sha256::digest(br#"{"a":1}"#));
+        let expected = sign(
+            b"secret",
+            &format!("POST\n/v1/messages?beta=true\n1700000000\n{body_sha}"),
+            SignatureEncoding::Hex,
+        )
+        .unwrap();
+        let headers = request.headers();
+        assert_eq!(headers.get("x-timestamp").unwrap(), "1700000000");
+        assert_eq!(headers.get("x-content-sha256").unwrap(), body_sha.as_str());
+        assert_eq!(headers.get("x-signature").unwrap(), expected.as_str());
+        assert!(headers.get("x-api-key").is_none());
+    }
+
+    #[test]
+    fn hmac_requires_secret() {
+        let provider = provider_with_scheme(AuthScheme::Hmac(HmacAuthConfig::default()));
+        let mut request = build_request();
+        assert!(apply_auth_scheme(&provider, None, &mut request).is_err());
+    }
+
+    #[test]
+    fn unconfigured_provider_is_untouched() {
+        let provider = Provider::with_id("p".into(), "P".into(), json!({}), None);
+        let mut request = build_request();
+        assert!(!apply_auth_scheme(&provider, None, &mut request).unwrap());
+        assert_eq!(request.headers().get("x-api-key").unwrap(), "sk-old");
+    }
+}
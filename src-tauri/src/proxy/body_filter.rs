@@ -63,18 +63,14 @@ pub fn filter_private_params(body: Value) -> Value {
 /// let output = filter_private_params_with_whitelist(input, &["_metadata"]);
 /// // output 包含 _metadata，不包含 _internal_id
 /// ```
+#[allow(dead_code)]
 pub fn filter_private_params_with_whitelist(body: Value, whitelist: &[String]) -> Value {
     let whitelist_set: HashSet<&str> = whitelist.iter().map(|s| s.as_str()).collect();
     filter_recursive_with_whitelist(body, &mut Vec::new(), &whitelist_set)
 }
 
-/// 递归过滤实现
-#[cfg(test)]
-fn filter_recursive(value: Value, removed_keys: &mut Vec<String>) -> Value {
-    filter_recursive_with_whitelist(value, removed_keys, &HashSet::new())
-}
-
 /// 递归过滤实现（支持白名单）
+#[allow(dead_code)]
 fn filter_recursive_with_whitelist(
     value: Value,
     removed_keys: &mut Vec<String>,
@@ -345,6 +345,13 @@ impl CircuitBreaker {
         }
     }
 
+    /// 请求结果不计入统计时（如维护窗口内）仅释放 HalfOpen 名额
+    pub fn release(&self, used_half_open_permit: bool) {
+        if used_half_open_permit {
+            self.release_half_open_permit();
+        }
+    }
+
     fn release_half_open_permit(&self) {
         let mut current = self.half_open_requests.load(Ordering::SeqCst);
         loop {
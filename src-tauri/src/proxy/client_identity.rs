@@ -0,0 +1,193 @@
+//! 供应商级客户端身份
+//!
+//! 部分中转站只放行特定客户端的 User-Agent 或请求头指纹。
+//! 这里为单个供应商配置发往上游的 User-Agent 与少量身份请求头，
+//! 代理转发、健康检查、测速与保活共用（经由 [`custom_headers`](super::custom_headers) 应用），
+//! 实际发送的取值记录在请求日志中。
+
+use std::collections::BTreeMap;
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT};
+use serde::{Deserialize, Serialize};
+
+use crate::provider::Provider;
+
+/// 身份请求头数量上限
+pub const MAX_IDENTITY_HEADERS: usize = 8;
+
+/// 不能作为身份请求头的名称（认证与协议头）
+const RESERVED_HEADERS: &[&str] = &[
+    "authorization",
+    "x-api-key",
+    "x-goog-api-key",
+    "proxy-authorization",
+    "cookie",
+    "host",
+    "content-length",
+    "content-type",
+    "connection",
+    "transfer-encoding",
+];
+
+/// 客户端身份（存储在 ProviderMeta.clientIdentity）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientIdentity {
+    /// 发往上游的 User-Agent，为空时保持客户端原值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// 身份请求头（如 `x-app`、`x-stainless-lang`），最多 [`MAX_IDENTITY_HEADERS`] 个
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl ClientIdentity {
+    /// 读取供应商配置的客户端身份
+    pub fn of(provider: &Provider) -> Option<&Self> {
+        provider
+            .meta
+            .as_ref()
+            .and_then(|m| m.client_identity.as_ref())
+            .filter(|i| **i != Self::default())
+    }
+
+    /// 有效的请求头（跳过保留头与非法取值，超出上限的部分忽略）
+    fn entries(&self) -> Vec<(HeaderName, HeaderValue)> {
+        let user_agent = self
+            .user_agent
+            .as_deref()
+            .map(str::trim)
+            .filter(|ua| !ua.is_empty())
+            .and_then(|ua| HeaderValue::from_str(ua).ok())
+            .map(|ua| (USER_AGENT, ua));
+
+        let headers = self
+            .headers
+            .iter()
+            .filter_map(|(name, value)| {
+                let name = name.trim().to_ascii_lowercase();
+                if RESERVED_HEADERS.contains(&name.as_str()) || name == USER_AGENT.as_str() {
+                    return None;
+                }
+                Some((
+                    HeaderName::from_bytes(name.as_bytes()).ok()?,
+                    HeaderValue::from_str(value.trim()).ok()?,
+                ))
+            })
+            .take(MAX_IDENTITY_HEADERS);
+
+        user_agent.into_iter().chain(headers).collect()
+    }
+
+    fn header_names(&self) -> Vec<HeaderName> {
+        self.entries().into_iter().map(|(name, _)| name).collect()
+    }
+}
+
+/// 应用供应商的客户端身份，返回应用的请求头数量
+pub fn apply(provider: &Provider, headers: &mut HeaderMap) -> usize {
+    let Some(identity) = ClientIdentity::of(provider) else {
+        return 0;
+    };
+    let entries = identity.entries();
+    let applied = entries.len();
+    for (name, value) in entries {
+        headers.insert(name, value);
+    }
+    applied
+}
+
+/// 请求日志中记录的身份取值（JSON 对象）
+///
+/// 与实际发送一致：先应用身份，再应用 custom_headers 中的同名覆盖。
+pub fn recorded(provider: &Provider) -> Option<String> {
+    let identity = ClientIdentity::of(provider)?;
+    let mut headers = HeaderMap::new();
+    apply(provider, &mut headers);
+    super::custom_headers::apply_custom_headers_from_provider(provider, &mut headers);
+
+    let secret_headers = super::custom_headers::secret_header_names(provider);
+    let values: BTreeMap<String, String> = identity
+        .header_names()
+        .iter()
+        .filter_map(|name| {
+            let value = headers.get(name)?.to_str().ok()?;
+            // 被 secret 自定义请求头覆盖的取值不写入日志
+            let value = if secret_headers.iter().any(|s| s == name.as_str()) {
+                "<secret>"
+            } else {
+                value
+            };
+            Some((name.as_str().to_string(), value.to_string()))
+        })
+        .collect();
+    if values.is_empty() {
+        return None;
+    }
+    serde_json::to_string(&values).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderMeta;
+    use serde_json::json;
+
+    fn provider(identity: ClientIdentity, settings_config: serde_json::Value) -> Provider {
+        let mut provider =
+            Provider::with_id("p1".to_string(), "P1".to_string(), settings_config, None);
+        provider.meta = Some(ProviderMeta {
+            client_identity: Some(identity),
+            ..Default::default()
+        });
+        provider
+    }
+
+    #[test]
+    fn applies_user_agent_and_skips_reserved_headers() {
+        let identity = ClientIdentity {
+            user_agent: Some("claude-cli/1.0.0 (external, cli)".to_string()),
+            headers: BTreeMap::from([
+                ("X-App".to_string(), "cli".to_string()),
+                ("Authorization".to_string(), "Bearer nope".to_string()),
+            ]),
+        };
+        let provider = provider(identity, json!({}));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(USER_AGENT, HeaderValue::from_static("client/0.1"));
+        assert_eq!(apply(&provider, &mut headers), 2);
+        assert_eq!(
+            headers.get(USER_AGENT).unwrap(),
+            "claude-cli/1.0.0 (external, cli)"
+        );
+        assert_eq!(headers.get("x-app").unwrap(), "cli");
+        assert!(headers.get("authorization").is_none());
+    }
+
+    #[test]
+    fn records_values_after_custom_header_overrides() {
+        let identity = ClientIdentity {
+            user_agent: Some("relay-approved/2.0".to_string()),
+            headers: BTreeMap::from([("x-app".to_string(), "cli".to_string())]),
+        };
+        let provider = provider(
+            identity,
+            json!({ "custom_headers": { "X-App": "desktop" } }),
+        );
+
+        let recorded: serde_json::Value =
+            serde_json::from_str(&recorded(&provider).unwrap()).unwrap();
+        assert_eq!(
+            recorded,
+            json!({ "user-agent": "relay-approved/2.0", "x-app": "desktop" })
+        );
+    }
+
+    #[test]
+    fn empty_identity_is_ignored() {
+        let provider = provider(ClientIdentity::default(), json!({}));
+        assert!(ClientIdentity::of(&provider).is_none());
+        assert!(recorded(&provider).is_none());
+    }
+}
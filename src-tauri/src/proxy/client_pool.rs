@@ -0,0 +1,188 @@
+//! 共享 HTTP 客户端
+//!
+//! 代理转发、健康检查与保活请求按连接配置（IP 偏好、TLS 选项）共用 reqwest 客户端，
+//! 从而共享连接池与 rustls 的 TLS 会话缓存：到同一中转站的新请求优先复用空闲连接，
+//! 需要新建连接时以会话恢复完成简短握手，减少远距离中转站的首字延迟。
+//!
+//! - 超时由调用方按请求设置，同一客户端可服务不同超时的场景
+//! - 不启用 0-RTT：早期数据可被重放，对非幂等的 POST 请求不安全
+//! - 网络环境变化（如切换 VPN）或 CA 证书包内容更新后可调用 `recycle` 丢弃所有连接
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use reqwest::Client;
+use serde::Serialize;
+
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::proxy::ip_preference::IpPreference;
+use crate::proxy::provider_tls::{self, ProviderTlsOptions};
+use crate::proxy::providers::GrpcUpstreamConfig;
+
+/// 空闲连接保留时间
+const POOL_IDLE_TIMEOUT_SECS: u64 = 90;
+
+/// TCP keepalive 间隔，避免中间设备回收长时间空闲的连接
+const TCP_KEEPALIVE_SECS: u64 = 60;
+
+/// 缓存的客户端上限（超过时整体回收，防止 TLS 设置反复修改后无限增长）
+const MAX_CLIENTS: usize = 64;
+
+const DEFAULT_KEY: &str = "default";
+
+static CLIENTS: Lazy<RwLock<HashMap<String, Client>>> = Lazy::new(|| RwLock::new(HashMap::new()));
+static BUILT: AtomicU64 = AtomicU64::new(0);
+static REUSED: AtomicU64 = AtomicU64::new(0);
+
+/// 共享客户端统计
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientPoolStats {
+    /// 当前缓存的客户端数
+    pub clients: usize,
+    /// 累计新建的客户端数
+    pub built: u64,
+    /// 累计复用已有客户端的次数
+    pub reused: u64,
+}
+
+/// 连接配置相同的供应商共用一个客户端
+fn client_key(provider: &Provider, base_url: &str) -> String {
+    if !provider_tls::needs_custom_client(provider) {
+        return DEFAULT_KEY.to_string();
+    }
+    let tls = ProviderTlsOptions::of(provider);
+    // SNI 覆盖按原主机解析，不同主机不能共用
+    let host = tls
+        .filter(|tls| tls.sni_override.is_some())
+        .and_then(|_| url::Url::parse(base_url).ok())
+        .and_then(|u| u.host_str().map(str::to_string))
+        .unwrap_or_default();
+    let tls = tls
+        .and_then(|tls| serde_json::to_string(tls).ok())
+        .unwrap_or_default();
+    format!("{:?}|{tls}|{host}", IpPreference::of(provider))
+}
+
+fn base_builder() -> reqwest::ClientBuilder {
+    Client::builder()
+        .user_agent("cc-switch/1.0")
+        .pool_idle_timeout(Duration::from_secs(POOL_IDLE_TIMEOUT_SECS))
+        .tcp_keepalive(Duration::from_secs(TCP_KEEPALIVE_SECS))
+}
+
+fn get_or_build(
+    key: String,
+    build: impl FnOnce() -> Result<Client, AppError>,
+) -> Result<Client, AppError> {
+    if let Some(client) = CLIENTS.read().unwrap_or_else(|e| e.into_inner()).get(&key) {
+        REUSED.fetch_add(1, Ordering::Relaxed);
+        return Ok(client.clone());
+    }
+
+    let client = build()?;
+    BUILT.fetch_add(1, Ordering::Relaxed);
+
+    let mut clients = CLIENTS.write().unwrap_or_else(|e| e.into_inner());
+    if clients.len() >= MAX_CLIENTS {
+        clients.clear();
+    }
+    // 并发构建时以先写入的为准，保证同一配置只有一个连接池
+    Ok(clients.entry(key).or_insert(client).clone())
+}
+
+/// 获取供应商对应的共享客户端（不带超时，调用方按请求设置）
+pub fn shared(provider: &Provider, base_url: &str) -> Result<Client, AppError> {
+    let grpc = GrpcUpstreamConfig::of(provider).is_some();
+    let mut key = client_key(provider, base_url);
+    if grpc {
+        key.push_str("|h2");
+    }
+    get_or_build(key, || {
+        let mut builder = provider_tls::configure(base_builder(), provider, base_url)?;
+        // gRPC 只走 HTTP/2，明文端点没有 ALPN 可协商
+        if grpc {
+            builder = builder.http2_prior_knowledge();
+        }
+        builder
+            .build()
+            .map_err(|e| AppError::Message(format!("创建客户端失败: {e}")))
+    })
+}
+
+/// 不带供应商专属配置的共享客户端
+pub fn default_client() -> Client {
+    get_or_build(DEFAULT_KEY.to_string(), || {
+        base_builder()
+            .build()
+            .map_err(|e| AppError::Message(format!("创建客户端失败: {e}")))
+    })
+    .expect("Failed to create HTTP client")
+}
+
+/// 丢弃所有共享客户端（已建立的连接在进行中的请求结束后关闭），返回回收的数量
+pub fn recycle() -> usize {
+    let mut clients = CLIENTS.write().unwrap_or_else(|e| e.into_inner());
+    let count = clients.len();
+    clients.clear();
+    if count > 0 {
+        log::info!("[ClientPool] 已回收 {count} 个共享 HTTP 客户端");
+    }
+    count
+}
+
+pub fn stats() -> ClientPoolStats {
+    ClientPoolStats {
+        clients: CLIENTS.read().unwrap_or_else(|e| e.into_inner()).len(),
+        built: BUILT.load(Ordering::Relaxed),
+        reused: REUSED.load(Ordering::Relaxed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderMeta;
+    use serde_json::json;
+
+    fn provider(meta: Option<ProviderMeta>) -> Provider {
+        let mut provider = Provider::with_id("p".into(), "P".into(), json!({}), None);
+        provider.meta = meta;
+        provider
+    }
+
+    #[test]
+    fn providers_share_clients_by_connection_settings() {
+        let plain = provider(None);
+        let ipv4 = provider(Some(ProviderMeta {
+            ip_preference: Some(IpPreference::Ipv4Only),
+            ..ProviderMeta::default()
+        }));
+        let sni = provider(Some(ProviderMeta {
+            tls: Some(ProviderTlsOptions {
+                sni_override: Some("relay.internal".into()),
+                ..ProviderTlsOptions::default()
+            }),
+            ..ProviderMeta::default()
+        }));
+
+        assert_eq!(client_key(&plain, "https://a.example"), DEFAULT_KEY);
+        assert_eq!(
+            client_key(&plain, "https://a.example"),
+            client_key(&plain, "https://b.example")
+        );
+        assert_eq!(
+            client_key(&ipv4, "https://a.example"),
+            client_key(&ipv4, "https://b.example")
+        );
+        assert_ne!(client_key(&ipv4, "https://a.example"), DEFAULT_KEY);
+        assert_ne!(
+            client_key(&sni, "https://a.example"),
+            client_key(&sni, "https://b.example")
+        );
+    }
+}
@@ -0,0 +1,442 @@
+//! 供应商并发统计与自适应并发上限
+//!
+//! 按供应商记录当前在途的上游请求数与峰值并发，用于判断变慢是本地排队还是上游本身。
+//! 请求从发往上游开始计入，直到响应体读完或被丢弃（流式响应持续到流结束）。
+//!
+//! 供应商配置了 `meta.adaptiveConcurrency` 时按 AIMD 调整并发上限：
+//! - 上游返回 429/503 或首字节延迟超过目标值时，上限减半（每秒最多一次）
+//! - 其余成功响应在并发接近上限时，上限每次增加 1/上限（约每轮满载增加 1）
+//! - 上限始终在 `minLimit` 与硬上限 `maxLimit` 之间；名额已满的请求排队等待，超时后换下一个供应商
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use once_cell::sync::Lazy;
+use reqwest::Response;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use crate::provider::Provider;
+
+/// 减小上限时的乘数
+const BACKOFF_FACTOR: f64 = 0.5;
+/// 两次减小上限的最小间隔（同一波 429 只减一次）
+const DECREASE_COOLDOWN: Duration = Duration::from_secs(1);
+/// 排队时重新检查名额的最长间隔
+const RECHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// 供应商的自适应并发设置（`meta.adaptiveConcurrency`）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdaptiveConcurrencyConfig {
+    /// 并发上限的硬上限
+    #[serde(default = "default_max_limit")]
+    pub max_limit: u32,
+    #[serde(default = "default_min_limit")]
+    pub min_limit: u32,
+    #[serde(default = "default_initial_limit")]
+    pub initial_limit: u32,
+    /// 首字节延迟目标（毫秒），超过视为过载；为空时只根据 429/503 调整
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_target_ms: Option<u64>,
+    /// 名额已满时的最长排队时间（毫秒）
+    #[serde(default = "default_queue_timeout_ms")]
+    pub queue_timeout_ms: u64,
+}
+
+fn default_max_limit() -> u32 {
+    32
+}
+
+fn default_min_limit() -> u32 {
+    1
+}
+
+fn default_initial_limit() -> u32 {
+    4
+}
+
+fn default_queue_timeout_ms() -> u64 {
+    30_000
+}
+
+impl AdaptiveConcurrencyConfig {
+    pub fn of(provider: &Provider) -> Option<&Self> {
+        provider.meta.as_ref()?.adaptive_concurrency.as_ref()
+    }
+
+    fn bounds(&self) -> (f64, f64) {
+        let min = self.min_limit.max(1) as f64;
+        (min, (self.max_limit as f64).max(min))
+    }
+
+    fn is_overloaded(&self, status: u16, latency: Duration) -> bool {
+        matches!(status, 429 | 503)
+            || self
+                .latency_target_ms
+                .is_some_and(|target| latency.as_millis() as u64 > target)
+    }
+}
+
+#[derive(Default)]
+struct Entry {
+    provider_name: String,
+    in_flight: u32,
+    peak: u32,
+    total: u64,
+    /// 正在排队等待名额的请求数
+    queued: u32,
+    /// 当前自适应上限（未开启时为空）
+    limit: Option<f64>,
+    max_limit: Option<u32>,
+    last_decrease: Option<Instant>,
+    released: Arc<Notify>,
+}
+
+impl Entry {
+    /// 按当前配置同步上限（开启、关闭或修改边界后生效）
+    fn sync_limit(&mut self, config: Option<&AdaptiveConcurrencyConfig>) {
+        match config {
+            Some(config) => {
+                let (min, max) = config.bounds();
+                let limit = self.limit.unwrap_or(config.initial_limit as f64);
+                self.limit = Some(limit.clamp(min, max));
+                self.max_limit = Some(max as u32);
+            }
+            None => {
+                self.limit = None;
+                self.max_limit = None;
+            }
+        }
+    }
+
+    fn has_slot(&self) -> bool {
+        self.limit
+            .is_none_or(|limit| (self.in_flight as f64) < limit.floor())
+    }
+}
+
+/// (app_type, provider_id) -> 统计
+static ENTRIES: Lazy<Mutex<HashMap<(String, String), Entry>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 单个供应商的并发统计
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderConcurrency {
+    pub app_type: String,
+    pub provider_id: String,
+    pub provider_name: String,
+    /// 当前在途请求数
+    pub in_flight: u32,
+    /// 自应用启动以来的峰值并发
+    pub peak: u32,
+    /// 累计发往该供应商的请求数
+    pub total: u64,
+    /// 正在排队等待并发名额的请求数
+    #[serde(default)]
+    pub queued: u32,
+    /// 当前自适应并发上限（未开启时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+    /// 自适应并发的硬上限
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_limit: Option<u32>,
+}
+
+/// 学习到的自适应并发上限（重启后恢复，避免从初始值重新探测）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LearnedLimit {
+    pub app_type: String,
+    pub provider_id: String,
+    pub provider_name: String,
+    pub limit: f64,
+}
+
+/// 在途请求的计数凭据，丢弃时计数减一
+pub struct InFlight {
+    key: (String, String),
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let mut entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = entries.get_mut(&self.key) {
+            entry.in_flight = entry.in_flight.saturating_sub(1);
+            entry.released.notify_one();
+        }
+    }
+}
+
+fn key_of(app_type: &str, provider: &Provider) -> (String, String) {
+    (app_type.to_string(), provider.id.clone())
+}
+
+fn admit(entry: &mut Entry, key: (String, String)) -> InFlight {
+    entry.in_flight += 1;
+    entry.peak = entry.peak.max(entry.in_flight);
+    entry.total += 1;
+    InFlight { key }
+}
+
+/// 开始一次发往 `provider` 的上游请求
+///
+/// 开启自适应并发且名额已满时排队等待；等待超过 `queueTimeoutMs` 返回 None。
+pub async fn acquire(app_type: &str, provider: &Provider) -> Option<InFlight> {
+    let key = key_of(app_type, provider);
+    let config = AdaptiveConcurrencyConfig::of(provider);
+    let deadline = config.map(|c| Instant::now() + Duration::from_millis(c.queue_timeout_ms));
+    let mut queued = false;
+
+    let result = loop {
+        let (released, wait) = {
+            let mut entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+            let entry = entries.entry(key.clone()).or_default();
+            entry.provider_name = provider.name.clone();
+            entry.sync_limit(config);
+            if entry.has_slot() {
+                if queued {
+                    entry.queued = entry.queued.saturating_sub(1);
+                }
+                break Some(admit(entry, key.clone()));
+            }
+            let remaining = deadline
+                .map(|d| d.saturating_duration_since(Instant::now()))
+                .unwrap_or_default();
+            if remaining.is_zero() {
+                if queued {
+                    entry.queued = entry.queued.saturating_sub(1);
+                }
+                break None;
+            }
+            if !queued {
+                entry.queued += 1;
+                queued = true;
+            }
+            (entry.released.clone(), remaining.min(RECHECK_INTERVAL))
+        };
+        let _ = tokio::time::timeout(wait, released.notified()).await;
+    };
+
+    if result.is_none() {
+        log::warn!(
+            "[{app_type}] {} 自适应并发名额已满，排队超时",
+            provider.name
+        );
+    }
+    result
+}
+
+/// 根据上游响应调整自适应并发上限（`status` 为空表示没有收到响应，不作调整）
+pub fn record_outcome(app_type: &str, provider: &Provider, status: Option<u16>, latency: Duration) {
+    let (Some(config), Some(status)) = (AdaptiveConcurrencyConfig::of(provider), status) else {
+        return;
+    };
+    let mut entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(entry) = entries.get_mut(&key_of(app_type, provider)) else {
+        return;
+    };
+    entry.sync_limit(Some(config));
+    let Some(limit) = entry.limit else {
+        return;
+    };
+    let (min, max) = config.bounds();
+
+    if config.is_overloaded(status, latency) {
+        let now = Instant::now();
+        if entry
+            .last_decrease
+            .is_some_and(|at| now.duration_since(at) < DECREASE_COOLDOWN)
+        {
+            return;
+        }
+        let next = (limit * BACKOFF_FACTOR).max(min);
+        entry.limit = Some(next);
+        entry.last_decrease = Some(now);
+        log::info!(
+            "[{app_type}] {} 过载（状态码 {status}，{}ms），并发上限 {} -> {}",
+            provider.name,
+            latency.as_millis(),
+            limit.floor(),
+            next.floor()
+        );
+    } else if (200..300).contains(&status) && (entry.in_flight as f64) + 1.0 >= limit.floor() {
+        // 只在上限确实成为瓶颈时增加，避免空闲期间一路涨到硬上限
+        entry.limit = Some((limit + 1.0 / limit).min(max));
+        if entry.limit.map(f64::floor) != Some(limit.floor()) {
+            entry.released.notify_one();
+        }
+    }
+}
+
+/// 让计数持续到响应体读完或被丢弃
+pub fn track_response(response: Response, in_flight: InFlight) -> Response {
+    let status = response.status();
+    let version = response.version();
+    let headers = response.headers().clone();
+    let stream = response.bytes_stream().map(move |chunk| {
+        let _ = &in_flight;
+        chunk
+    });
+
+    let mut tracked = axum::http::Response::new(reqwest::Body::wrap_stream(stream));
+    *tracked.status_mut() = status;
+    *tracked.version_mut() = version;
+    *tracked.headers_mut() = headers;
+    Response::from(tracked)
+}
+
+/// 所有供应商的并发统计（按应用、峰值倒序）
+pub fn snapshot() -> Vec<ProviderConcurrency> {
+    let entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+    let mut stats: Vec<_> = entries
+        .iter()
+        .map(|((app_type, provider_id), entry)| ProviderConcurrency {
+            app_type: app_type.clone(),
+            provider_id: provider_id.clone(),
+            provider_name: entry.provider_name.clone(),
+            in_flight: entry.in_flight,
+            peak: entry.peak,
+            total: entry.total,
+            queued: entry.queued,
+            limit: entry.limit.map(|l| l.floor() as u32),
+            max_limit: entry.max_limit,
+        })
+        .collect();
+    stats.sort_by(|a, b| {
+        a.app_type
+            .cmp(&b.app_type)
+            .then(b.peak.cmp(&a.peak))
+            .then(a.provider_id.cmp(&b.provider_id))
+    });
+    stats
+}
+
+/// 当前学习到的自适应上限
+pub fn learned_limits() -> Vec<LearnedLimit> {
+    let entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+    let mut limits: Vec<_> = entries
+        .iter()
+        .filter_map(|((app_type, provider_id), entry)| {
+            Some(LearnedLimit {
+                app_type: app_type.clone(),
+                provider_id: provider_id.clone(),
+                provider_name: entry.provider_name.clone(),
+                limit: entry.limit?,
+            })
+        })
+        .collect();
+    limits.sort_by(|a, b| {
+        (a.app_type.as_str(), a.provider_id.as_str())
+            .cmp(&(b.app_type.as_str(), b.provider_id.as_str()))
+    });
+    limits
+}
+
+/// 恢复上次运行学习到的上限（首次请求时按当前配置重新校正边界，已关闭则丢弃）
+pub fn restore_limits(limits: &[LearnedLimit]) {
+    let mut entries = ENTRIES.lock().unwrap_or_else(|e| e.into_inner());
+    for saved in limits {
+        let entry = entries
+            .entry((saved.app_type.clone(), saved.provider_id.clone()))
+            .or_default();
+        if entry.limit.is_none() {
+            entry.provider_name = saved.provider_name.clone();
+            entry.limit = Some(saved.limit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderMeta;
+    use serde_json::json;
+
+    fn provider(id: &str, adaptive: Option<AdaptiveConcurrencyConfig>) -> Provider {
+        let mut provider = Provider::with_id(id.to_string(), "Test".to_string(), json!({}), None);
+        provider.meta = Some(ProviderMeta {
+            adaptive_concurrency: adaptive,
+            ..Default::default()
+        });
+        provider
+    }
+
+    fn adaptive(initial_limit: u32, max_limit: u32) -> AdaptiveConcurrencyConfig {
+        AdaptiveConcurrencyConfig {
+            max_limit,
+            min_limit: 1,
+            initial_limit,
+            latency_target_ms: Some(1000),
+            queue_timeout_ms: 50,
+        }
+    }
+
+    fn stats_of(provider_id: &str) -> ProviderConcurrency {
+        snapshot()
+            .into_iter()
+            .find(|s| s.provider_id == provider_id)
+            .expect("provider stats")
+    }
+
+    #[tokio::test]
+    async fn counts_until_response_body_is_consumed() {
+        let provider = provider("concurrency-test", None);
+        let first = acquire("claude", &provider).await.unwrap();
+        let second = acquire("claude", &provider).await.unwrap();
+        assert_eq!(stats_of("concurrency-test").in_flight, 2);
+        drop(first);
+
+        let response = Response::from(axum::http::Response::new(reqwest::Body::from("ok")));
+        let response = track_response(response, second);
+        let stats = stats_of("concurrency-test");
+        assert_eq!((stats.in_flight, stats.peak, stats.total), (1, 2, 2));
+        assert_eq!(stats.limit, None);
+
+        assert_eq!(response.text().await.unwrap(), "ok");
+        assert_eq!(stats_of("concurrency-test").in_flight, 0);
+    }
+
+    #[tokio::test]
+    async fn queues_when_limit_reached_and_times_out() {
+        let provider = provider("aimd-queue", Some(adaptive(1, 4)));
+        let held = acquire("claude", &provider).await.unwrap();
+        assert!(acquire("claude", &provider).await.is_none());
+
+        let waiter = {
+            let provider = provider.clone();
+            tokio::spawn(async move { acquire("claude", &provider).await.is_some() })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(stats_of("aimd-queue").queued, 1);
+        drop(held);
+        assert!(waiter.await.unwrap());
+        assert_eq!(stats_of("aimd-queue").queued, 0);
+    }
+
+    #[tokio::test]
+    async fn limit_grows_additively_and_halves_on_overload() {
+        let provider = provider("aimd-adjust", Some(adaptive(2, 3)));
+        let _a = acquire("claude", &provider).await.unwrap();
+        let _b = acquire("claude", &provider).await.unwrap();
+
+        // 满载成功：2 -> 2.5 -> 2.9 -> 3（受硬上限约束）
+        for _ in 0..4 {
+            record_outcome("claude", &provider, Some(200), Duration::from_millis(100));
+        }
+        assert_eq!(stats_of("aimd-adjust").limit, Some(3));
+        assert_eq!(stats_of("aimd-adjust").max_limit, Some(3));
+
+        record_outcome("claude", &provider, Some(429), Duration::from_millis(100));
+        assert_eq!(stats_of("aimd-adjust").limit, Some(1));
+        // 冷却期内的过载信号不再减小
+        record_outcome("claude", &provider, Some(503), Duration::from_millis(100));
+        assert_eq!(stats_of("aimd-adjust").limit, Some(1));
+        // 没有收到响应不作调整
+        record_outcome("claude", &provider, None, Duration::from_secs(5));
+        assert_eq!(stats_of("aimd-adjust").limit, Some(1));
+    }
+}
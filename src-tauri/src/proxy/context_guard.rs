@@ -0,0 +1,157 @@
+//! 上下文窗口检查
+//!
+//! 请求超过模型上下文窗口时，部分上游会静默截断对话而不是报错。
+//! 开启后在转发前估算请求的 token 数（UTF-8 字节数 / 4，图片等二进制内容不计入）：
+//! - warn：只记录日志，照常转发
+//! - reject：直接返回 400，提示用户压缩对话
+//!
+//! 上下文窗口优先取设置中按模型配置的值，其次取供应商能力声明中的 `maxContextTokens`；
+//! 两者都没有时不做检查。
+
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::provider::Provider;
+use crate::services::provider::ProviderCapabilities;
+use crate::settings::{self, ContextGuardMode};
+
+use super::ProxyError;
+
+/// 不计入估算的字段（base64 图片、思考签名等）
+const SKIPPED_KEYS: &[&str] = &["data", "signature", "inline_data", "inlineData"];
+
+/// 估算请求体的 token 数
+pub fn estimate_request_tokens(body: &Value) -> u64 {
+    let mut bytes = 0u64;
+    collect_text_bytes(body, &mut bytes);
+    bytes.div_ceil(4)
+}
+
+fn collect_text_bytes(value: &Value, bytes: &mut u64) {
+    match value {
+        // data URL 形式的图片
+        Value::String(s) if s.starts_with("data:") => {}
+        Value::String(s) => *bytes += s.len() as u64,
+        Value::Array(items) => items.iter().for_each(|v| collect_text_bytes(v, bytes)),
+        Value::Object(map) => {
+            for (key, v) in map {
+                if !SKIPPED_KEYS.contains(&key.as_str()) {
+                    collect_text_bytes(v, bytes);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 查找模型的上下文窗口
+///
+/// 设置中的键为模型名；以 `*` 结尾时按前缀匹配，多个前缀命中时取最长的。
+pub fn resolve_context_window(
+    model: &str,
+    provider: &Provider,
+    windows: &BTreeMap<String, u64>,
+) -> Option<u64> {
+    if let Some(limit) = windows.get(model) {
+        return Some(*limit);
+    }
+    let by_prefix = windows
+        .iter()
+        .filter_map(|(key, limit)| Some((key.strip_suffix('*')?, *limit)))
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, limit)| limit);
+    by_prefix.or_else(|| ProviderCapabilities::of(provider).and_then(|c| c.max_context_tokens))
+}
+
+/// 转发前检查请求是否超过上下文窗口
+pub fn check(tag: &str, model: &str, provider: &Provider, body: &Value) -> Result<(), ProxyError> {
+    let settings = settings::get_settings();
+    if settings.context_guard_mode == ContextGuardMode::Off {
+        return Ok(());
+    }
+    let Some(limit) = resolve_context_window(model, provider, &settings.model_context_windows)
+    else {
+        return Ok(());
+    };
+    let estimated = estimate_request_tokens(body);
+    if estimated <= limit {
+        return Ok(());
+    }
+
+    match settings.context_guard_mode {
+        ContextGuardMode::Reject => {
+            log::warn!(
+                "[{tag}] 请求估算约 {estimated} tokens，超过 {model} 的上下文窗口 {limit}，已拒绝"
+            );
+            Err(ProxyError::ContextWindowExceeded {
+                model: model.to_string(),
+                estimated,
+                limit,
+            })
+        }
+        _ => {
+            log::warn!(
+                "[{tag}] 请求估算约 {estimated} tokens，超过 {model} 的上下文窗口 {limit}，上游可能截断内容"
+            );
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderMeta;
+    use serde_json::json;
+
+    #[test]
+    fn estimate_ignores_base64_images() {
+        let body = json!({
+            "model": "m",
+            "messages": [{
+                "role": "user",
+                "content": [
+                    { "type": "text", "text": "a".repeat(400) },
+                    { "type": "image", "source": { "type": "base64", "data": "x".repeat(10_000) } },
+                    { "type": "image_url", "image_url": { "url": format!("data:image/png;base64,{}", "y".repeat(10_000)) } }
+                ]
+            }]
+        });
+        let estimated = estimate_request_tokens(&body);
+        assert!((100..120).contains(&estimated), "estimated = {estimated}");
+    }
+
+    #[test]
+    fn resolve_prefers_exact_then_longest_prefix_then_provider() {
+        let mut provider = Provider::with_id("p".into(), "P".into(), json!({}), None);
+        provider.meta = Some(ProviderMeta {
+            capabilities: Some(ProviderCapabilities {
+                max_context_tokens: Some(64_000),
+                ..ProviderCapabilities::default()
+            }),
+            ..ProviderMeta::default()
+        });
+        let windows = BTreeMap::from([
+            ("deepseek-*".to_string(), 64_000),
+            ("deepseek-chat-*".to_string(), 128_000),
+            ("glm-4.6".to_string(), 200_000),
+        ]);
+
+        assert_eq!(
+            resolve_context_window("glm-4.6", &provider, &windows),
+            Some(200_000)
+        );
+        assert_eq!(
+            resolve_context_window("deepseek-chat-v3", &provider, &windows),
+            Some(128_000)
+        );
+        assert_eq!(
+            resolve_context_window("kimi-k2", &provider, &windows),
+            Some(64_000)
+        );
+        provider.meta = None;
+        assert_eq!(resolve_context_window("kimi-k2", &provider, &windows), None);
+    }
+}
@@ -5,9 +5,15 @@
 //! - 注入到上游请求 headers 中
 //! - 自定义请求头优先级最高（覆盖同名头）
 //! - 对少量 HTTP 协议级保留头强制忽略，避免协议层异常
+//! - 应用到请求时先应用供应商的客户端身份（见 [`client_identity`](super::client_identity)），
+//!   custom_headers 中的同名头仍可覆盖
+//!
+//! 取值可以是字符串，也可以是 `{ "value": "...", "secret": true }`：标记为 secret 的值
+//! （如推荐码、渠道令牌）在导出（不含 Key 时）与日志中隐藏。
 
 use crate::provider::Provider;
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde_json::Value;
 
 const PROTOCOL_RESERVED_HEADERS: &[&str] = &[
     "connection",
@@ -21,12 +27,51 @@ const PROTOCOL_RESERVED_HEADERS: &[&str] = &[
     "host",
 ];
 
-pub fn apply_custom_headers_from_provider(provider: &Provider, headers: &mut HeaderMap) -> usize {
-    let Some(obj) = provider
-        .settings_config
+/// 解析单个取值：(值, 是否为 secret)
+fn header_value(value: &Value) -> Option<(&str, bool)> {
+    match value {
+        Value::String(s) => Some((s.as_str(), false)),
+        Value::Object(obj) => Some((
+            obj.get("value")?.as_str()?,
+            obj.get("secret").and_then(Value::as_bool).unwrap_or(false),
+        )),
+        _ => None,
+    }
+}
+
+fn custom_headers(settings_config: &Value) -> Option<&serde_json::Map<String, Value>> {
+    settings_config
         .get("custom_headers")
         .and_then(|v| v.as_object())
+}
+
+/// 标记为 secret 的自定义请求头名称（小写）
+pub fn secret_header_names(provider: &Provider) -> Vec<String> {
+    custom_headers(&provider.settings_config)
+        .into_iter()
+        .flatten()
+        .filter(|(_, value)| header_value(value).is_some_and(|(_, secret)| secret))
+        .map(|(key, _)| key.trim().to_ascii_lowercase())
+        .collect()
+}
+
+/// 把 secret 自定义请求头的值替换为 `replacement`（导出、展示前调用）
+pub fn mask_secret_values(settings_config: &mut Value, replacement: &str) {
+    let Some(obj) = settings_config
+        .get_mut("custom_headers")
+        .and_then(|v| v.as_object_mut())
     else {
+        return;
+    };
+    for value in obj.values_mut() {
+        if header_value(value).is_some_and(|(_, secret)| secret) {
+            value["value"] = Value::String(replacement.to_string());
+        }
+    }
+}
+
+pub fn apply_custom_headers_from_provider(provider: &Provider, headers: &mut HeaderMap) -> usize {
+    let Some(obj) = custom_headers(&provider.settings_config) else {
         return 0;
     };
 
@@ -43,7 +88,7 @@ pub fn apply_custom_headers_from_provider(provider: &Provider, headers: &mut Hea
             continue;
         }
 
-        let Some(value_str) = value.as_str() else {
+        let Some((value_str, _)) = header_value(value) else {
             continue;
         };
 
@@ -63,7 +108,8 @@ pub fn apply_custom_headers_from_provider(provider: &Provider, headers: &mut Hea
 }
 
 pub fn apply_custom_headers_to_request(provider: &Provider, request: &mut reqwest::Request) -> usize {
-    apply_custom_headers_from_provider(provider, request.headers_mut())
+    super::client_identity::apply(provider, request.headers_mut())
+        + apply_custom_headers_from_provider(provider, request.headers_mut())
 }
 
 #[cfg(test)]
@@ -101,5 +147,31 @@ mod tests {
         assert_eq!(headers.get("x-tenant-id").unwrap().to_str().unwrap(), "abc");
         assert!(headers.get("content-length").is_none());
     }
+
+    #[test]
+    fn secret_values_are_applied_but_masked_on_export() {
+        let mut provider = make_provider(json!({
+            "custom_headers": {
+                "X-Channel": "relay-a",
+                "X-Referral": { "value": "ref-secret", "secret": true },
+                "X-Team": { "value": "core" }
+            }
+        }));
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(
+            apply_custom_headers_from_provider(&provider, &mut headers),
+            3
+        );
+        assert_eq!(headers.get("x-referral").unwrap(), "ref-secret");
+        assert_eq!(headers.get("x-team").unwrap(), "core");
+        assert_eq!(secret_header_names(&provider), vec!["x-referral"]);
+
+        mask_secret_values(&mut provider.settings_config, "");
+        let masked = &provider.settings_config["custom_headers"];
+        assert_eq!(masked["X-Referral"]["value"], "");
+        assert_eq!(masked["X-Referral"]["secret"], true);
+        assert_eq!(masked["X-Channel"], "relay-a");
+    }
 }
 
@@ -0,0 +1,219 @@
+//! 端点路径模板与 Base URL 规范化
+//!
+//! 不同中转站的接口路径不一致（`/v1`、`/v1/messages`、`/api/{version}/chat` 等）。
+//! 供应商可在 `endpointTemplates` 中按标准端点配置路径模板，代理转发和健康检查
+//! 都通过这里解析最终 URL；未配置时沿用适配器的默认拼接规则。
+//!
+//! 模板变量：
+//! - `{model}`：请求（或检查）使用的模型名
+//! - `{version}`：`endpointTemplates.version`，默认 `v1`
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::provider::Provider;
+
+const DEFAULT_VERSION: &str = "v1";
+const VARIABLES: &[&str] = &["model", "version"];
+
+/// 供应商端点路径模板
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointTemplates {
+    /// 标准端点（如 `/v1/messages`）到路径模板的映射
+    #[serde(default)]
+    pub paths: HashMap<String, String>,
+    /// `{version}` 的取值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+impl EndpointTemplates {
+    pub fn of(provider: &Provider) -> Option<&Self> {
+        provider
+            .meta
+            .as_ref()
+            .and_then(|m| m.endpoint_templates.as_ref())
+    }
+
+    /// 渲染标准端点对应的路径；未配置模板时返回 None
+    pub fn render(&self, endpoint: &str, model: Option<&str>) -> Option<String> {
+        let template = self.paths.get(endpoint)?.trim();
+        if template.is_empty() {
+            return None;
+        }
+        let version = self
+            .version
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .unwrap_or(DEFAULT_VERSION);
+        Some(
+            template
+                .replace("{version}", version)
+                .replace("{model}", model.unwrap_or_default()),
+        )
+    }
+
+    /// 校验模板，返回 (字段, 错误信息)
+    pub fn validate(&self) -> Vec<(String, String)> {
+        let mut errors = Vec::new();
+        for (endpoint, template) in &self.paths {
+            let field = format!("meta.endpointTemplates.paths.{endpoint}");
+            if !template.trim().starts_with('/') {
+                errors.push((field.clone(), "路径模板必须以 / 开头".to_string()));
+            }
+            let mut rest = template.as_str();
+            while let Some(start) = rest.find('{') {
+                let Some(len) = rest[start..].find('}') else {
+                    errors.push((field.clone(), "模板变量缺少右括号".to_string()));
+                    break;
+                };
+                let name = &rest[start + 1..start + len];
+                if !VARIABLES.contains(&name) {
+                    errors.push((field.clone(), format!("未知的模板变量: {{{name}}}")));
+                }
+                rest = &rest[start + len + 1..];
+            }
+        }
+        errors
+    }
+}
+
+/// 按供应商路径模板构建完整 URL（保留端点原有的查询参数）；未配置模板时返回 None
+pub fn resolve_url(
+    provider: &Provider,
+    base_url: &str,
+    endpoint: &str,
+    model: Option<&str>,
+) -> Option<String> {
+    let (path, query) = match endpoint.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (endpoint, None),
+    };
+    let rendered = EndpointTemplates::of(provider)?.render(path, model)?;
+    let mut url = format!(
+        "{}/{}",
+        base_url.trim_end_matches('/'),
+        rendered.trim_start_matches('/')
+    );
+    if let Some(query) = query {
+        url.push(if url.contains('?') { '&' } else { '?' });
+        url.push_str(query);
+    }
+    Some(url)
+}
+
+/// 规范化 Base URL：去除首尾空白和末尾斜杠，并检查协议、主机等常见错误
+pub fn normalize_base_url(raw: &str) -> Result<String, String> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Ok(String::new());
+    }
+    if trimmed.chars().any(char::is_whitespace) {
+        return Err(format!("地址中包含空白字符: {trimmed}"));
+    }
+
+    let Some((scheme, rest)) = trimmed.split_once("://") else {
+        return Err(format!(
+            "缺少协议，是否应为 https://{}？",
+            trimmed.trim_start_matches(['/', ':'])
+        ));
+    };
+    if !matches!(scheme.to_ascii_lowercase().as_str(), "http" | "https") {
+        return Err(format!(
+            "不支持的协议 {scheme}://，请使用 http:// 或 https://"
+        ));
+    }
+    if rest.contains("://") {
+        return Err(format!("协议重复: {trimmed}"));
+    }
+
+    let parsed = url::Url::parse(trimmed).map_err(|e| format!("不是有效的 URL（{e}）"))?;
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err(format!("缺少主机名: {trimmed}"));
+    }
+    if parsed.query().is_some() || parsed.fragment().is_some() {
+        return Err("Base URL 不应包含查询参数或锚点".to_string());
+    }
+
+    let (scheme_part, rest) = trimmed.split_at(scheme.len());
+    Ok(format!(
+        "{}{}",
+        scheme_part.to_ascii_lowercase(),
+        rest.trim_end_matches('/')
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderMeta;
+    use serde_json::json;
+
+    fn provider_with(paths: &[(&str, &str)], version: Option<&str>) -> Provider {
+        let mut provider = Provider::with_id("p".into(), "P".into(), json!({}), None);
+        provider.meta = Some(ProviderMeta {
+            endpoint_templates: Some(EndpointTemplates {
+                paths: paths
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+                version: version.map(str::to_string),
+            }),
+            ..Default::default()
+        });
+        provider
+    }
+
+    #[test]
+    fn resolves_templates_with_variables() {
+        let provider = provider_with(
+            &[
+                ("/v1/messages", "/api/{version}/messages"),
+                ("/v1/chat/completions", "/deployments/{model}/chat"),
+            ],
+            Some("v2"),
+        );
+        assert_eq!(
+            resolve_url(&provider, "https://relay.example/", "/v1/messages", None).as_deref(),
+            Some("https://relay.example/api/v2/messages")
+        );
+        assert_eq!(
+            resolve_url(
+                &provider,
+                "https://relay.example",
+                "/v1/chat/completions?stream=true",
+                Some("gpt-4o")
+            )
+            .as_deref(),
+            Some("https://relay.example/deployments/gpt-4o/chat?stream=true")
+        );
+        assert!(resolve_url(&provider, "https://relay.example", "/v1/responses", None).is_none());
+    }
+
+    #[test]
+    fn validate_reports_unknown_variables() {
+        let templates = EndpointTemplates {
+            paths: [("/v1/messages".to_string(), "v1/{modle}".to_string())].into(),
+            version: None,
+        };
+        assert_eq!(templates.validate().len(), 2);
+    }
+
+    #[test]
+    fn normalize_base_url_fixes_and_rejects() {
+        assert_eq!(
+            normalize_base_url(" HTTPS://relay.example/v1/ ").unwrap(),
+            "https://relay.example/v1"
+        );
+        assert!(normalize_base_url("relay.example")
+            .unwrap_err()
+            .contains("https://relay.example"));
+        assert!(normalize_base_url("htps://relay.example").is_err());
+        assert!(normalize_base_url("https://https://relay.example").is_err());
+        assert!(normalize_base_url("https://relay.example/v1?key=1").is_err());
+        assert_eq!(normalize_base_url("").unwrap(), "");
+    }
+}
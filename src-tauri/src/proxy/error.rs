@@ -1,11 +1,14 @@
 use axum::{
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
 use thiserror::Error;
 
+use super::upstream_hint::{describe, hint_for};
+use crate::i18n::Locale;
+
 #[derive(Debug, Error)]
 pub enum ProxyError {
     #[error("服务器已在运行")]
@@ -26,6 +29,9 @@ pub enum ProxyError {
     #[error("所有供应商已熔断，无可用渠道")]
     AllProvidersCircuitOpen,
 
+    #[error("网络不可用（离线），且未配置本地供应商")]
+    Offline,
+
     #[error("未配置供应商")]
     NoProvidersConfigured,
 
@@ -34,7 +40,12 @@ pub enum ProxyError {
     ProviderUnhealthy(String),
 
     #[error("上游错误 (状态码 {status}): {body:?}")]
-    UpstreamError { status: u16, body: Option<String> },
+    UpstreamError {
+        status: u16,
+        body: Option<String>,
+        /// 按透传策略过滤后的上游响应头
+        headers: HeaderMap,
+    },
 
     #[error("超过最大重试次数")]
     MaxRetriesExceeded,
@@ -53,6 +64,15 @@ pub enum ProxyError {
     #[error("无效的请求: {0}")]
     InvalidRequest(String),
 
+    #[error(
+        "请求估算约 {estimated} tokens，超过模型 {model} 的上下文窗口（{limit} tokens），请压缩对话（如 /compact）后重试"
+    )]
+    ContextWindowExceeded {
+        model: String,
+        estimated: u64,
+        limit: u64,
+    },
+
     #[error("超时: {0}")]
     Timeout(String),
 
@@ -77,23 +97,30 @@ impl IntoResponse for ProxyError {
             ProxyError::UpstreamError {
                 status: upstream_status,
                 body: upstream_body,
+                ..
             } => {
                 let http_status =
                     StatusCode::from_u16(*upstream_status).unwrap_or(StatusCode::BAD_GATEWAY);
 
                 // 尝试解析上游响应体为 JSON，如果失败则包装为字符串
                 let error_body = if let Some(body_str) = upstream_body {
-                    if let Ok(json_body) = serde_json::from_str::<serde_json::Value>(body_str) {
-                        // 上游返回的是 JSON，直接透传
+                    let hint = hint_for(*upstream_status, body_str, Locale::current());
+                    if let Ok(mut json_body) = serde_json::from_str::<serde_json::Value>(body_str) {
+                        // 上游返回的是 JSON，透传并附上归类结果
+                        if let (Some(obj), Some(hint)) = (json_body.as_object_mut(), hint) {
+                            obj.insert("hint".to_string(), json!(hint));
+                        }
                         json_body
                     } else {
-                        // 上游返回的不是 JSON，包装为错误消息
-                        json!({
-                            "error": {
-                                "message": body_str,
-                                "type": "upstream_error",
-                            }
-                        })
+                        // 上游返回的不是 JSON（HTML 错误页等），包装为摘要与建议
+                        let mut error = json!({
+                            "message": describe(*upstream_status, body_str, Locale::current()),
+                            "type": "upstream_error",
+                        });
+                        if let Some(hint) = hint {
+                            error["code"] = json!(hint.code);
+                        }
+                        json!({ "error": error })
                     }
                 } else {
                     json!({
@@ -120,6 +147,7 @@ impl IntoResponse for ProxyError {
                     ProxyError::AllProvidersCircuitOpen => {
                         (StatusCode::SERVICE_UNAVAILABLE, self.to_string())
                     }
+                    ProxyError::Offline => (StatusCode::SERVICE_UNAVAILABLE, self.to_string()),
                     ProxyError::NoProvidersConfigured => {
                         (StatusCode::SERVICE_UNAVAILABLE, self.to_string())
                     }
@@ -137,6 +165,9 @@ impl IntoResponse for ProxyError {
                         (StatusCode::UNPROCESSABLE_ENTITY, self.to_string())
                     }
                     ProxyError::InvalidRequest(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+                    ProxyError::ContextWindowExceeded { .. } => {
+                        (StatusCode::BAD_REQUEST, self.to_string())
+                    }
                     ProxyError::Timeout(_) => (StatusCode::GATEWAY_TIMEOUT, self.to_string()),
                     ProxyError::StreamIdleTimeout(_) => {
                         (StatusCode::GATEWAY_TIMEOUT, self.to_string())
@@ -159,7 +190,13 @@ impl IntoResponse for ProxyError {
             }
         };
 
-        (status, Json(body)).into_response()
+        let mut response = (status, Json(body)).into_response();
+        if let ProxyError::UpstreamError { headers, .. } = self {
+            for (name, value) in headers.iter() {
+                response.headers_mut().append(name.clone(), value.clone());
+            }
+        }
+        response
     }
 }
 
@@ -2,7 +2,9 @@
 //!
 //! 将 ProxyError 映射到合适的 HTTP 状态码，用于日志记录
 
+use super::upstream_hint::describe;
 use super::ProxyError;
+use crate::i18n::Locale;
 
 /// 将 ProxyError 映射到 HTTP 状态码
 ///
@@ -39,6 +41,9 @@ pub fn map_proxy_error_to_status(error: &ProxyError) -> u16 {
         // Provider 不健康：503 Service Unavailable
         ProxyError::ProviderUnhealthy(_) => 503,
 
+        // 超过上下文窗口（本地拒绝）：400 Bad Request
+        ProxyError::ContextWindowExceeded { .. } => 400,
+
         // 数据库错误：500 Internal Server Error
         ProxyError::DatabaseError(_) => 500,
 
@@ -53,13 +58,14 @@ pub fn map_proxy_error_to_status(error: &ProxyError) -> u16 {
 /// 将 ProxyError 转换为用户友好的错误消息
 pub fn get_error_message(error: &ProxyError) -> String {
     match error {
-        ProxyError::UpstreamError { status, body } => {
-            if let Some(body) = body {
-                format!("上游错误 ({status}): {body}")
-            } else {
-                format!("上游错误 ({status})")
-            }
-        }
+        ProxyError::UpstreamError { status, body, .. } => match body {
+            Some(body) => format!(
+                "上游错误 ({status}): {}",
+                describe(*status, body, Locale::current())
+                    .trim_start_matches(&format!("HTTP {status}: "))
+            ),
+            None => format!("上游错误 ({status})"),
+        },
         ProxyError::Timeout(msg) => format!("请求超时: {msg}"),
         ProxyError::ForwardFailed(msg) => format!("转发失败: {msg}"),
         ProxyError::NoAvailableProvider => "无可用 Provider".to_string(),
@@ -82,6 +88,7 @@ mod tests {
         let error = ProxyError::UpstreamError {
             status: 401,
             body: Some("Unauthorized".to_string()),
+            headers: Default::default(),
         };
         assert_eq!(map_proxy_error_to_status(&error), 401);
     }
@@ -109,6 +116,7 @@ mod tests {
         let error = ProxyError::UpstreamError {
             status: 500,
             body: Some("Internal Server Error".to_string()),
+            headers: Default::default(),
         };
         let msg = get_error_message(&error);
         assert!(msg.contains("上游错误"));
@@ -9,10 +9,12 @@
 
 use crate::database::Database;
 use crate::error::AppError;
+use crate::events::{self, AppEvent, ProviderSwitchedPayload};
+use crate::services::provider::switch_queue::{self, SwitchOutcome};
 use std::collections::HashSet;
 use std::str::FromStr;
 use std::sync::Arc;
-use tauri::{Emitter, Manager};
+use tauri::Manager;
 use tokio::sync::RwLock;
 
 /// 故障转移切换管理器
@@ -98,13 +100,38 @@ impl FailoverSwitchManager {
 
         log::info!("[Failover] 开始切换供应商: {app_type} -> {provider_name} ({provider_id})");
 
-        // 1. 更新数据库 is_current
-        self.db.set_current_provider(app_type, provider_id)?;
-
-        // 2. 更新本地 settings（设备级）
+        // 1-2. 经切换队列更新数据库 is_current 与本地 settings（设备级），避免与手动切换交错
         let app_type_enum = crate::app_config::AppType::from_str(app_type)
             .map_err(|_| AppError::Message(format!("无效的应用类型: {app_type}")))?;
-        crate::settings::set_current_provider(&app_type_enum, Some(provider_id))?;
+        let mut previous = None;
+        let outcome = switch_queue::run_exclusive(
+            app_type,
+            provider_id,
+            "failover",
+            || {
+                previous =
+                    crate::settings::get_effective_current_provider(&self.db, &app_type_enum)?;
+                Ok(previous.as_deref() == Some(provider_id))
+            },
+            || {
+                self.db.set_current_provider(app_type, provider_id)?;
+                crate::settings::set_current_provider(&app_type_enum, Some(provider_id))
+            },
+        )
+        .await?;
+        if outcome == SwitchOutcome::AlreadyActive {
+            log::debug!("[Failover] {app_type} 当前供应商已是 {provider_id}，跳过切换");
+            return Ok(false);
+        }
+        if let Err(e) = self.db.record_provider_switch(
+            app_type,
+            provider_id,
+            previous.as_deref(),
+            "failover",
+            chrono::Utc::now().timestamp(),
+        ) {
+            log::warn!("[Failover] 记录切换历史失败: {e}");
+        }
 
         // 3. 更新托盘菜单和发射事件
         if let Some(app) = app_handle {
@@ -131,15 +158,12 @@ impl FailoverSwitchManager {
                 }
             }
 
-            // 发射事件到前端
-            let event_data = serde_json::json!({
-                "appType": app_type,
-                "providerId": provider_id,
-                "source": "failover"  // 标识来源是故障转移
-            });
-            if let Err(e) = app.emit("provider-switched", event_data) {
-                log::error!("[Failover] 发射供应商切换事件失败: {e}");
-            }
+            // 通知前端（source 标识来源是故障转移）
+            events::publish(AppEvent::ProviderSwitched(ProviderSwitchedPayload {
+                app_type: app_type.to_string(),
+                provider_id: provider_id.to_string(),
+                source: Some("failover".to_string()),
+            }));
         }
 
         log::info!("[Failover] 供应商切换完成: {app_type} -> {provider_name} ({provider_id})");
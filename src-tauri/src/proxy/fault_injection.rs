@@ -0,0 +1,236 @@
+//! 故障注入（演练故障转移）
+//!
+//! 临时让某个供应商的代理请求失败、返回指定错误或增加延迟，
+//! 用于在真正断线之前验证故障转移规则、告警与预算是否按预期工作。
+//!
+//! 注入只保存在内存中并带有到期时间，重启应用或到期后自动失效。
+//! 注入在发往上游之前生效，因此熔断器、故障转移与请求日志都按真实失败处理。
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use axum::http::HeaderMap;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::ProxyError;
+
+/// 单次注入最长持续时间（秒）
+pub const MAX_DURATION_SECS: u64 = 3600;
+/// 注入延迟上限（毫秒）
+pub const MAX_LATENCY_MS: u64 = 120_000;
+
+static INJECTIONS: Lazy<RwLock<HashMap<(String, String), FaultInjection>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// 注入方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FaultMode {
+    /// 连接失败（可重试，触发故障转移）
+    Fail,
+    /// 返回指定的上游 HTTP 错误
+    Error,
+    /// 只增加延迟，请求照常转发
+    Latency,
+}
+
+/// 故障注入设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FaultInjection {
+    pub app_type: String,
+    pub provider_id: String,
+    pub mode: FaultMode,
+    /// 转发前等待的毫秒数（任何方式都可叠加延迟）
+    #[serde(default)]
+    pub latency_ms: u64,
+    /// `error` 方式返回的状态码
+    #[serde(default = "default_status")]
+    pub status: u16,
+    /// 命中比例（0~100），用于模拟间歇性故障
+    #[serde(default = "default_percent")]
+    pub percent: u8,
+    /// 到期时间（Unix 秒）
+    #[serde(default)]
+    pub expires_at: i64,
+}
+
+fn default_status() -> u16 {
+    503
+}
+
+fn default_percent() -> u8 {
+    100
+}
+
+impl FaultInjection {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.percent == 0 || self.percent > 100 {
+            return Err("命中比例必须在 1 到 100 之间".to_string());
+        }
+        if self.latency_ms > MAX_LATENCY_MS {
+            return Err(format!("延迟不能超过 {MAX_LATENCY_MS} 毫秒"));
+        }
+        if self.mode == FaultMode::Latency && self.latency_ms == 0 {
+            return Err("延迟方式需要设置 latencyMs".to_string());
+        }
+        if self.mode == FaultMode::Error && !(400..=599).contains(&self.status) {
+            return Err("错误状态码必须在 400 到 599 之间".to_string());
+        }
+        Ok(())
+    }
+
+    fn hit(&self) -> bool {
+        self.percent >= 100 || (uuid::Uuid::new_v4().as_u128() % 100) < self.percent as u128
+    }
+}
+
+fn key(app_type: &str, provider_id: &str) -> (String, String) {
+    (app_type.to_string(), provider_id.to_string())
+}
+
+/// 设置注入（同一供应商覆盖旧设置），持续 `duration_secs` 秒
+pub fn set(
+    mut injection: FaultInjection,
+    duration_secs: u64,
+    now: i64,
+) -> Result<FaultInjection, String> {
+    injection.validate()?;
+    injection.expires_at = now + duration_secs.clamp(1, MAX_DURATION_SECS) as i64;
+    log::warn!(
+        "[FaultInjection] {}/{} 注入 {:?}（延迟 {}ms，比例 {}%），到期 {}",
+        injection.app_type,
+        injection.provider_id,
+        injection.mode,
+        injection.latency_ms,
+        injection.percent,
+        injection.expires_at
+    );
+    INJECTIONS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(
+            key(&injection.app_type, &injection.provider_id),
+            injection.clone(),
+        );
+    Ok(injection)
+}
+
+/// 移除注入，返回是否存在
+pub fn clear(app_type: &str, provider_id: &str) -> bool {
+    INJECTIONS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&key(app_type, provider_id))
+        .is_some()
+}
+
+pub fn clear_all() -> usize {
+    let mut injections = INJECTIONS.write().unwrap_or_else(|e| e.into_inner());
+    let count = injections.len();
+    injections.clear();
+    count
+}
+
+/// 当前生效的注入（顺带清理已到期的）
+pub fn list(now: i64) -> Vec<FaultInjection> {
+    let mut injections = INJECTIONS.write().unwrap_or_else(|e| e.into_inner());
+    injections.retain(|_, i| i.expires_at > now);
+    let mut list: Vec<_> = injections.values().cloned().collect();
+    list.sort_by(|a, b| (&a.app_type, &a.provider_id).cmp(&(&b.app_type, &b.provider_id)));
+    list
+}
+
+fn active(app_type: &str, provider_id: &str, now: i64) -> Option<FaultInjection> {
+    let injections = INJECTIONS.read().unwrap_or_else(|e| e.into_inner());
+    injections
+        .get(&key(app_type, provider_id))
+        .filter(|i| i.expires_at > now)
+        .cloned()
+}
+
+/// 转发前调用：按注入设置等待，需要模拟失败时返回对应的错误
+pub async fn inject(app_type: &str, provider_id: &str) -> Option<ProxyError> {
+    let injection = active(app_type, provider_id, chrono::Utc::now().timestamp())?;
+    if !injection.hit() {
+        return None;
+    }
+    if injection.latency_ms > 0 {
+        tokio::time::sleep(Duration::from_millis(injection.latency_ms)).await;
+    }
+    log::info!(
+        "[FaultInjection] {app_type}/{provider_id} 命中注入 {:?}",
+        injection.mode
+    );
+    match injection.mode {
+        FaultMode::Latency => None,
+        FaultMode::Fail => Some(ProxyError::ForwardFailed(
+            "模拟故障：连接失败（故障注入）".to_string(),
+        )),
+        FaultMode::Error => Some(ProxyError::UpstreamError {
+            status: injection.status,
+            body: Some(
+                json!({
+                    "error": {
+                        "type": "fault_injection",
+                        "message": "Simulated upstream error injected by CC Switch"
+                    }
+                })
+                .to_string(),
+            ),
+            headers: HeaderMap::new(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn injection(provider_id: &str, mode: FaultMode) -> FaultInjection {
+        FaultInjection {
+            app_type: "claude".to_string(),
+            provider_id: provider_id.to_string(),
+            mode,
+            latency_ms: 0,
+            status: 529,
+            percent: 100,
+            expires_at: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn injects_until_cleared() {
+        let now = chrono::Utc::now().timestamp();
+        set(injection("fi-error", FaultMode::Error), 60, now).unwrap();
+        match inject("claude", "fi-error").await {
+            Some(ProxyError::UpstreamError { status, .. }) => assert_eq!(status, 529),
+            other => panic!("unexpected: {other:?}"),
+        }
+        assert!(inject("codex", "fi-error").await.is_none());
+
+        assert!(clear("claude", "fi-error"));
+        assert!(inject("claude", "fi-error").await.is_none());
+    }
+
+    #[test]
+    fn expired_injections_are_dropped() {
+        set(injection("fi-expired", FaultMode::Fail), 10, 1_000).unwrap();
+        assert!(list(1_005).iter().any(|i| i.provider_id == "fi-expired"));
+        assert!(!list(1_011).iter().any(|i| i.provider_id == "fi-expired"));
+    }
+
+    #[test]
+    fn validates_settings() {
+        assert!(injection("p", FaultMode::Latency).validate().is_err());
+        let mut bad_status = injection("p", FaultMode::Error);
+        bad_status.status = 200;
+        assert!(bad_status.validate().is_err());
+        let mut partial = injection("p", FaultMode::Fail);
+        partial.percent = 0;
+        assert!(partial.validate().is_err());
+    }
+}
@@ -3,17 +3,30 @@
 //! 负责将请求转发到上游Provider，支持故障转移
 
 use super::{
-    custom_headers::apply_custom_headers_to_request,
+    auth_scheme::apply_auth_scheme,
+    client_pool, concurrency,
+    custom_headers::{apply_custom_headers_to_request, secret_header_names},
+    endpoint_template,
     error::*,
     failover_switch::FailoverSwitchManager,
+    fault_injection, log_scrubber, offline, outbound_redaction, output_validation,
     provider_router::ProviderRouter,
-    providers::{get_adapter, ProviderAdapter},
+    provider_tls,
+    providers::{
+        adapt_cloud_response, adapt_grpc_response, get_adapter, prepare_azure_request,
+        prepare_cloud_request, prepare_grpc_request, AzureOpenAiConfig, CloudProviderConfig,
+        GrpcUpstreamConfig, ProviderAdapter,
+    },
+    response_headers,
+    trace::RequestTrace,
+    traffic::TrafficMeter,
     types::ProxyStatus,
     ProxyError,
 };
-use crate::{app_config::AppType, provider::Provider};
+use crate::{app_config::AppType, database::Database, provider::Provider};
 use reqwest::{Client, Response};
 use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
@@ -33,6 +46,8 @@ const HEADER_BLACKLIST: &[&str] = &[
     // 认证类（会被覆盖）
     "authorization",
     "x-api-key",
+    // 回放令牌只在代理内部使用
+    crate::services::replay::REPLAY_HEADER,
     // 连接类
     "host",
     "content-length",
@@ -81,6 +96,8 @@ const HEADER_BLACKLIST: &[&str] = &[
     // 客户端 IP 单独处理（默认透传）
     "x-forwarded-for",
     "x-real-ip",
+    // 请求标签仅供本地日志使用
+    "x-ccswitch-tag",
 ];
 
 const SENSITIVE_HEADERS_FOR_LOG: &[&str] = &["authorization", "x-api-key", "x-goog-api-key"];
@@ -110,7 +127,8 @@ pub struct ForwardError {
 }
 
 pub struct RequestForwarder {
-    client: Client,
+    /// 请求超时（共享客户端不带超时，按请求设置）
+    client_timeout: Duration,
     /// 共享的 ProviderRouter（持有熔断器状态）
     router: Arc<ProviderRouter>,
     status: Arc<RwLock<ProxyStatus>>,
@@ -121,6 +139,12 @@ pub struct RequestForwarder {
     app_handle: Option<tauri::AppHandle>,
     /// 请求开始时的"当前供应商 ID"（用于判断是否需要同步 UI/托盘）
     current_provider_id_at_start: String,
+    /// 所属请求的链路追踪
+    trace: RequestTrace,
+    /// 累计等待上游响应头的时间（微秒），用于区分代理自身开销
+    upstream_micros: AtomicU64,
+    /// 所属请求的流量计数
+    traffic: TrafficMeter,
 }
 
 impl RequestForwarder {
@@ -140,30 +164,60 @@ impl RequestForwarder {
         // 参考 Claude Code Hub 的 undici 全局超时设计
         const GLOBAL_TIMEOUT_SECS: u64 = 1800;
 
-        let mut client_builder = Client::builder();
-        if non_streaming_timeout > 0 {
+        let client_timeout = if non_streaming_timeout > 0 {
             // 使用配置的非流式超时
-            client_builder = client_builder.timeout(Duration::from_secs(non_streaming_timeout));
+            Duration::from_secs(non_streaming_timeout)
         } else {
             // 禁用超时时使用全局超时作为保底
-            client_builder = client_builder.timeout(Duration::from_secs(GLOBAL_TIMEOUT_SECS));
-        }
-
-        let client = client_builder
-            .build()
-            .expect("Failed to create HTTP client");
+            Duration::from_secs(GLOBAL_TIMEOUT_SECS)
+        };
 
         Self {
-            client,
+            client_timeout,
             router,
             status,
             current_providers,
             failover_manager,
             app_handle,
             current_provider_id_at_start,
+            trace: RequestTrace::default(),
+            upstream_micros: AtomicU64::new(0),
+            traffic: TrafficMeter::default(),
         }
     }
 
+    /// 不依赖运行中代理的转发器（只配合 `forward_once` 发送一次性请求）
+    pub fn standalone(db: Arc<Database>, timeout_secs: u64) -> Self {
+        Self::new(
+            Arc::new(ProviderRouter::new(db.clone())),
+            timeout_secs,
+            Arc::new(RwLock::new(ProxyStatus::default())),
+            Arc::new(RwLock::new(std::collections::HashMap::new())),
+            Arc::new(FailoverSwitchManager::new(db)),
+            None,
+            String::new(),
+            0,
+            0,
+        )
+    }
+
+    /// 本次转发累计等待上游响应头的时间（含故障转移的每次尝试）
+    pub fn upstream_time(&self) -> Duration {
+        Duration::from_micros(self.upstream_micros.load(Ordering::Relaxed))
+    }
+
+    /// 关联请求的链路追踪（为每次上游调用记录 span）
+    pub fn with_trace(mut self, trace: RequestTrace) -> Self {
+        self.trace = trace;
+        self
+    }
+
+    /// 关联请求的流量计数（记录每次发往上游的请求体大小）
+    pub fn with_traffic(mut self, traffic: TrafficMeter) -> Self {
+        self.traffic = traffic;
+        self
+    }
+
     /// 转发请求（带故障转移）
     ///
     /// # Arguments
@@ -191,6 +245,25 @@ impl RequestForwarder {
             });
         }
 
+        // 离线时只尝试本地供应商，避免每个远程供应商都等到超时
+        let providers = if offline::is_offline() {
+            let local = offline::local_providers(&providers);
+            if local.is_empty() {
+                log::warn!("[{app_type_str}] 当前离线且无本地供应商，直接拒绝请求");
+                return Err(ForwardError {
+                    error: ProxyError::Offline,
+                    provider: None,
+                });
+            }
+            log::info!(
+                "[{app_type_str}] 当前离线，改用 {} 个本地供应商",
+                local.len()
+            );
+            local
+        } else {
+            providers
+        };
+
         log::info!(
             "[{}] 故障转移链: {} 个可用供应商",
             app_type_str,
@@ -201,11 +274,25 @@ impl RequestForwarder {
         let mut last_provider = None;
         let mut attempted_providers = 0usize;
 
+        // 客户端要求结构化输出时校验响应（未开启时为 None）
+        let output_check = output_validation::OutputCheck::for_request(endpoint, &body);
+
         // 单 Provider 场景下跳过熔断器检查（故障转移关闭时）
         let bypass_circuit_breaker = providers.len() == 1;
 
         // 依次尝试每个供应商
         for provider in providers.iter() {
+            // 计入该供应商的在途请求（成功时持续到响应体读完）；自适应并发名额已满时排队，
+            // 排队超时换下一个供应商
+            let Some(in_flight) = concurrency::acquire(app_type_str, provider).await else {
+                last_error = Some(ProxyError::ProviderUnhealthy(format!(
+                    "{} 并发名额已满，排队超时",
+                    provider.name
+                )));
+                last_provider = Some(provider.clone());
+                continue;
+            };
+
             // 发起请求前先获取熔断器放行许可（HalfOpen 会占用探测名额）
             // 单 Provider 场景下跳过此检查，避免熔断器阻塞所有请求
             let (allowed, used_half_open_permit) = if bypass_circuit_breaker {
@@ -247,13 +334,48 @@ impl RequestForwarder {
                 status.last_request_at = Some(chrono::Utc::now().to_rfc3339());
             }
 
+            // 出站内容过滤（未开启、供应商在白名单中或没有命中时沿用原请求体）
+            let redacted = outbound_redaction::redact_request(app_type_str, &provider.id, &body);
+            let provider_body = redacted.as_ref().unwrap_or(&body);
+
             let start = Instant::now();
 
             // 转发请求（每个 Provider 只尝试一次，重试由客户端控制）
-            match self
-                .forward(provider, endpoint, &body, &headers, adapter.as_ref())
-                .await
-            {
+            // 故障注入在发往上游前生效，按真实失败走熔断与故障转移
+            let result = match fault_injection::inject(app_type_str, &provider.id).await {
+                Some(injected) => Err(injected),
+                None => match &output_check {
+                    Some(check) => {
+                        output_validation::send_checked(check, app_type_str, &provider.id, || {
+                            self.forward(
+                                provider,
+                                endpoint,
+                                provider_body,
+                                &headers,
+                                adapter.as_ref(),
+                            )
+                        })
+                        .await
+                    }
+                    None => {
+                        self.forward(
+                            provider,
+                            endpoint,
+                            provider_body,
+                            &headers,
+                            adapter.as_ref(),
+                        )
+                        .await
+                    }
+                },
+            };
+            let upstream_status = match &result {
+                Ok(response) => Some(response.status().as_u16()),
+                Err(ProxyError::UpstreamError { status, .. }) => Some(*status),
+                Err(_) => None,
+            };
+            concurrency::record_outcome(app_type_str, provider, upstream_status, start.elapsed());
+            match result {
                 Ok(response) => {
                     let latency = start.elapsed().as_millis() as u64;
 
@@ -327,7 +449,7 @@ impl RequestForwarder {
                     );
 
                     return Ok(ForwardResult {
-                        response,
+                        response: concurrency::track_response(response, in_flight),
                         provider: provider.clone(),
                     });
                 }
@@ -402,7 +524,7 @@ impl RequestForwarder {
             }
         }
 
-        if attempted_providers == 0 {
+        if attempted_providers == 0 && last_error.is_none() {
             // providers 列表非空，但全部被熔断器拒绝（典型：HalfOpen 探测名额被占用）
             {
                 let mut status = self.status.write().await;
@@ -436,12 +558,47 @@ impl RequestForwarder {
             providers.len()
         );
 
+        // 全部因网络原因失败时立即复查连通性，不必等下一轮定时探测
+        if matches!(
+            last_error,
+            Some(ProxyError::ForwardFailed(_) | ProxyError::Timeout(_))
+        ) {
+            tokio::spawn(offline::refresh());
+        }
+
         Err(ForwardError {
             error: last_error.unwrap_or(ProxyError::MaxRetriesExceeded),
             provider: last_provider,
         })
     }
 
+    /// 向单个供应商发送请求（不经过熔断器与故障转移，也不更新代理状态）
+    ///
+    /// 用于影子镜像与多供应商对比，请求改写与实际代理流量一致。
+    pub async fn forward_once(
+        &self,
+        app_type: &AppType,
+        endpoint: &str,
+        body: &Value,
+        headers: &axum::http::HeaderMap,
+        provider: &Provider,
+    ) -> Result<Response, ProxyError> {
+        let adapter = get_adapter(app_type);
+        self.forward(provider, endpoint, body, headers, adapter.as_ref())
+            .await
+    }
+
+    /// 获取供应商对应的共享 HTTP 客户端（配置了 IP 偏好或 TLS 选项时按配置区分）
+    fn client_for(&self, provider: &Provider, base_url: &str) -> Client {
+        client_pool::shared(provider, base_url).unwrap_or_else(|e| {
+            log::warn!(
+                "为 {} 创建专用客户端失败，使用默认客户端: {e}",
+                provider.name
+            );
+            client_pool::default_client()
+        })
+    }
+
     /// 转发单个请求（使用适配器）
     async fn forward(
         &self,
@@ -465,37 +622,52 @@ impl RequestForwarder {
                 endpoint
             };
 
-        // 使用适配器构建 URL
-        let url = adapter.build_url(&base_url, effective_endpoint);
-
         // 记录原始请求 JSON
         log::info!(
             "[{}] ====== 请求开始 ======\n>>> 原始请求 JSON:\n{}",
             adapter.name(),
-            serde_json::to_string_pretty(body).unwrap_or_else(|_| body.to_string())
+            log_scrubber::body_for_log(body)
         );
 
         // 应用模型映射（独立于格式转换）
-        let (mapped_body, _original_model, mapped_model) =
+        let (mut mapped_body, _original_model, mapped_model) =
             super::model_mapper::apply_model_mapping(body.clone(), provider);
 
         if let Some(ref mapped) = mapped_model {
             log::info!(
                 "[{}] >>> 模型映射后的请求 JSON:\n{}",
                 adapter.name(),
-                serde_json::to_string_pretty(&mapped_body).unwrap_or_default()
+                log_scrubber::body_for_log(&mapped_body)
             );
             log::info!("[{}] 模型已映射到: {}", adapter.name(), mapped);
         }
 
+        // 按供应商输出上限截断 max_tokens
+        for clamped in super::output_limit::clamp_output_tokens(&mut mapped_body, provider) {
+            log::info!(
+                "[{}] {} 从 {} 截断为 {}（供应商输出上限）",
+                adapter.name(),
+                clamped.field,
+                clamped.requested,
+                clamped.limit
+            );
+        }
+
+        // 优先使用供应商的端点路径模板，否则由适配器构建 URL
+        let model = mapped_body.get("model").and_then(|m| m.as_str());
+        let url = endpoint_template::resolve_url(provider, &base_url, effective_endpoint, model)
+            .unwrap_or_else(|| adapter.build_url(&base_url, effective_endpoint));
+        let url = provider_tls::rewrite_url(&url, provider);
+
         // 转换请求体（如果需要）
         let request_body = if needs_transform {
             log::info!("[{}] 转换请求格式 (Anthropic → OpenAI)", adapter.name());
+            let _span = self.trace.span("translation.request");
             let transformed = adapter.transform_request(mapped_body, provider)?;
             log::info!(
                 "[{}] >>> 转换后的请求 JSON:\n{}",
                 adapter.name(),
-                serde_json::to_string_pretty(&transformed).unwrap_or_default()
+                log_scrubber::body_for_log(&transformed)
             );
             transformed
         } else {
@@ -510,7 +682,8 @@ impl RequestForwarder {
         );
 
         // 构建请求
-        let mut request = self.client.post(&url);
+        let client = self.client_for(provider, &base_url);
+        let mut request = client.post(&url);
 
         // ========== 详细 Headers 日志 ==========
         log::info!("[{}] ====== 客户端原始 Headers ======", adapter.name());
@@ -574,14 +747,15 @@ impl RequestForwarder {
         request = request.header("accept-encoding", "identity");
 
         // 使用适配器添加认证头
-        if let Some(auth) = adapter.extract_auth(provider) {
+        let auth = adapter.extract_auth(provider);
+        if let Some(auth) = auth.as_ref() {
             log::debug!(
                 "[{}] 使用认证: {:?} (key: {})",
                 adapter.name(),
                 auth.strategy,
                 auth.masked_key()
             );
-            request = adapter.add_auth_headers(request, &auth);
+            request = adapter.add_auth_headers(request, auth);
         } else {
             log::error!(
                 "[{}] 未找到 API Key！Provider: {}",
@@ -610,6 +784,33 @@ impl RequestForwarder {
             ProxyError::ForwardFailed(e.to_string())
         })?;
 
+        // 供应商自定义认证方案（需在请求体确定后签名）
+        if apply_auth_scheme(provider, auth.as_ref(), &mut built)
+            .map_err(|e| ProxyError::ForwardFailed(e.to_string()))?
+        {
+            log::info!("[{}] 已应用 Provider 认证方案", adapter.name());
+        }
+
+        // 云厂商托管的 Claude：改写为 Bedrock / Vertex 请求并完成签名
+        let cloud = CloudProviderConfig::of(provider);
+        if let Some(cloud) = cloud {
+            prepare_cloud_request(cloud, &client, &mut built, &request_body).await?;
+            log::info!("[{}] 已改写为云厂商请求: {}", adapter.name(), built.url());
+        }
+
+        // gRPC 上游：Chat Completions 请求编码为 protobuf
+        let grpc = GrpcUpstreamConfig::of(provider);
+        if let Some(grpc) = grpc {
+            prepare_grpc_request(grpc, &mut built, &request_body)?;
+            log::info!("[{}] 已改写为 gRPC 请求: {}", adapter.name(), built.url());
+        }
+
+        // Azure OpenAI：按部署改写路径与认证头
+        if let Some(azure) = AzureOpenAiConfig::of(provider) {
+            prepare_azure_request(azure, &mut built, &request_body)?;
+            log::info!("[{}] 已路由到 Azure 部署: {}", adapter.name(), built.url());
+        }
+
         let applied_custom_headers = apply_custom_headers_to_request(provider, &mut built);
         if applied_custom_headers > 0 {
             log::info!(
@@ -624,21 +825,35 @@ impl RequestForwarder {
             "[{}] ====== 最终发送的 Headers ======",
             adapter.name()
         );
+        let secret_headers = secret_header_names(provider);
         for (k, v) in built.headers().iter() {
             let key_lower = k.as_str().to_ascii_lowercase();
             let value_str = v.to_str().unwrap_or("<binary>");
-            log::info!(
-                "[{}]   {}: {}",
-                adapter.name(),
-                k.as_str(),
+            let shown = if secret_headers.contains(&key_lower) {
+                "<secret>".to_string()
+            } else {
                 mask_header_value_for_log(&key_lower, value_str)
-            );
+            };
+            log::info!("[{}]   {}: {}", adapter.name(), k.as_str(), shown);
         }
 
         // 发送请求
         log::info!("[{}] 发送请求到: {}", adapter.name(), url);
-        let response = self.client.execute(built).await.map_err(|e| {
+        let mut span = self.trace.client_span("upstream.call");
+        span.set_attribute("provider.id", provider.id.clone());
+        span.set_attribute("provider.name", provider.name.clone());
+        span.set_attribute("url.full", built.url().to_string());
+        if let Some(body) = built.body().and_then(|b| b.as_bytes()) {
+            self.traffic.add_sent(body.len());
+        }
+        *built.timeout_mut() = Some(self.client_timeout);
+        let sent_at = Instant::now();
+        let response = client.execute(built).await;
+        self.upstream_micros
+            .fetch_add(sent_at.elapsed().as_micros() as u64, Ordering::Relaxed);
+        let response = response.map_err(|e| {
             log::error!("[{}] 请求失败: {}", adapter.name(), e);
+            span.set_error(&e);
             if e.is_timeout() {
                 ProxyError::Timeout(format!("请求超时: {e}"))
             } else if e.is_connect() {
@@ -651,11 +866,25 @@ impl RequestForwarder {
         // 检查响应状态
         let status = response.status();
         log::info!("[{}] 响应状态: {}", adapter.name(), status);
+        span.set_attribute("http.response.status_code", status.as_u16());
 
-        if status.is_success() {
-            Ok(response)
+        if let (true, Some(grpc)) = (status.is_success(), grpc) {
+            let adapted = adapt_grpc_response(grpc, response, &request_body).await;
+            match &adapted {
+                Ok(_) => span.set_ok(),
+                Err(e) => span.set_error(e),
+            }
+            adapted
+        } else if status.is_success() {
+            span.set_ok();
+            Ok(match cloud {
+                Some(cloud) => adapt_cloud_response(cloud, response, &request_body),
+                None => response,
+            })
         } else {
             let status_code = status.as_u16();
+            span.set_error(format!("HTTP {status_code}"));
+            let headers = response_headers::for_error_response(response.headers());
             let body_text = response.text().await.ok();
             log::error!(
                 "[{}] 上游错误 ({}): {:?}",
@@ -667,6 +896,7 @@ impl RequestForwarder {
             Err(ProxyError::UpstreamError {
                 status: status_code,
                 body: body_text,
+                headers,
             })
         }
     }
@@ -5,8 +5,10 @@
 use crate::app_config::AppType;
 use crate::provider::Provider;
 use crate::proxy::{
-    extract_session_id, forwarder::RequestForwarder, server::ProxyState, types::AppProxyConfig,
-    ProxyError,
+    context_guard, extract_session_id, forwarder::RequestForwarder,
+    handler_config::UsageParserConfig, model_audit, overhead, param_policy, request_tag,
+    server::ProxyState, session_override, shadow::ShadowCapture, trace::RequestTrace,
+    traffic::TrafficMeter, transcript::TranscriptCapture, types::AppProxyConfig, ProxyError,
 };
 use axum::http::HeaderMap;
 use std::time::Instant;
@@ -54,6 +56,18 @@ pub struct RequestContext {
     pub app_type: AppType,
     /// Session ID（从客户端请求提取或新生成）
     pub session_id: String,
+    /// 链路追踪（未启用时为空操作）
+    pub trace: RequestTrace,
+    /// 会话记录（未开启时为 None）
+    pub transcript: Option<TranscriptCapture>,
+    /// 影子镜像（本次请求未被采样时为 None）
+    pub shadow: Option<ShadowCapture>,
+    /// 与上游之间的流量计数
+    pub traffic: TrafficMeter,
+    /// 客户端通过 `X-CCSwitch-Tag` 请求头指定的标签
+    pub request_tag: Option<String>,
+    /// 是否为请求回放发出的请求
+    pub is_replay: bool,
 }
 
 impl RequestContext {
@@ -78,6 +92,7 @@ impl RequestContext {
         app_type_str: &'static str,
     ) -> Result<Self, ProxyError> {
         let start_time = Instant::now();
+        let trace = RequestTrace::start(app_type_str);
 
         // 从数据库读取应用级代理配置（per-app）
         let app_config = state
@@ -96,6 +111,8 @@ impl RequestContext {
             .unwrap_or("unknown")
             .to_string();
 
+        trace.set_attribute("llm.request.model", request_model.clone());
+
         // 提取 Session ID
         let session_result = extract_session_id(headers, body, app_type_str);
         let session_id = session_result.session_id.clone();
@@ -108,24 +125,55 @@ impl RequestContext {
             session_result.client_provided
         );
 
+        // 临时覆盖：命中时只发往指定供应商，不参与故障转移
+        let override_provider = match session_override::take(
+            app_type_str,
+            &session_id,
+            headers,
+            chrono::Utc::now().timestamp(),
+        ) {
+            Some(id) => {
+                let found = state
+                    .db
+                    .get_provider_by_id(&id, app_type_str)
+                    .map_err(|e| ProxyError::DatabaseError(e.to_string()))?;
+                if found.is_none() {
+                    log::warn!("[{tag}] 临时覆盖的供应商 {id} 不存在，按正常路由处理");
+                }
+                found
+            }
+            None => None,
+        };
+
         // 使用共享的 ProviderRouter 选择 Provider（熔断器状态跨请求保持）
         // 注意：只在这里调用一次，结果传递给 forwarder，避免重复消耗 HalfOpen 名额
-        let providers = state
-            .provider_router
-            .select_providers(app_type_str)
-            .await
-            .map_err(|e| match e {
-                crate::error::AppError::AllProvidersCircuitOpen => {
-                    ProxyError::AllProvidersCircuitOpen
-                }
-                crate::error::AppError::NoProvidersConfigured => ProxyError::NoProvidersConfigured,
-                _ => ProxyError::DatabaseError(e.to_string()),
-            })?;
+        let providers = match override_provider {
+            Some(provider) => {
+                trace.set_attribute("routing.override", provider.id.clone());
+                vec![provider]
+            }
+            None => state
+                .provider_router
+                .select_providers(app_type_str)
+                .await
+                .map_err(|e| match e {
+                    crate::error::AppError::AllProvidersCircuitOpen => {
+                        ProxyError::AllProvidersCircuitOpen
+                    }
+                    crate::error::AppError::NoProvidersConfigured => {
+                        ProxyError::NoProvidersConfigured
+                    }
+                    _ => ProxyError::DatabaseError(e.to_string()),
+                })?,
+        };
 
         let provider = providers
             .first()
             .cloned()
             .ok_or(ProxyError::NoAvailableProvider)?;
+        trace.set_attribute("session.id", session_id.clone());
+        let transcript = TranscriptCapture::start(&session_id, app_type_str, body);
+        trace.set_attribute("provider.id", provider.id.clone());
 
         log::info!(
             "[{}] Provider: {}, model: {}, failover chain: {} providers, session: {}",
@@ -147,6 +195,12 @@ impl RequestContext {
             app_type_str,
             app_type,
             session_id,
+            trace,
+            transcript,
+            shadow: None,
+            traffic: TrafficMeter::default(),
+            request_tag: request_tag::extract(headers),
+            is_replay: crate::services::replay::is_replay(headers),
         })
     }
 
@@ -169,9 +223,60 @@ impl RequestContext {
             .to_string();
 
         log::info!("[{}] 从 URI 提取模型: {}", self.tag, self.request_model);
+        self.trace
+            .set_attribute("llm.request.model", self.request_model.clone());
         self
     }
 
+    /// 按影子镜像设置复制本次请求（转发前调用）
+    pub fn start_shadow(
+        &mut self,
+        state: &ProxyState,
+        endpoint: &str,
+        body: &serde_json::Value,
+        headers: &HeaderMap,
+        parser: &'static UsageParserConfig,
+    ) {
+        self.shadow = ShadowCapture::start(
+            state,
+            &self.app_type,
+            self.app_type_str,
+            &self.provider.id,
+            &self.request_model,
+            endpoint,
+            body,
+            headers,
+            parser,
+        );
+    }
+
+    /// 模型替换检测的预期（按实际使用的供应商计算，未开启检测时为 None）
+    pub fn model_expectation(&self) -> Option<model_audit::Expectation> {
+        model_audit::Expectation::for_request(
+            &self.app_type,
+            self.app_type_str,
+            &self.provider,
+            &self.request_model,
+        )
+    }
+
+    /// 转发前检查请求是否超过模型上下文窗口（按设置警告或拒绝）
+    pub fn check_context_window(&self, body: &serde_json::Value) -> Result<(), ProxyError> {
+        context_guard::check(self.tag, &self.request_model, &self.provider, body)
+    }
+
+    /// 转发前按参数策略改写或拒绝请求
+    pub fn enforce_parameter_policy(&self, body: &mut serde_json::Value) -> Result<(), ProxyError> {
+        param_policy::enforce(self.tag, self.app_type_str, &self.provider, body)
+    }
+
+    /// 记录代理自身开销（收到上游响应头时调用，未开启测量时忽略）
+    pub fn record_overhead(&self, forwarder: &RequestForwarder) {
+        if overhead::enabled() {
+            overhead::record_request(self.start_time.elapsed(), forwarder.upstream_time());
+        }
+    }
+
     /// 创建 RequestForwarder
     ///
     /// 使用共享的 ProviderRouter，确保熔断器状态跨请求保持
@@ -208,6 +313,8 @@ impl RequestContext {
             first_byte_timeout,
             idle_timeout,
         )
+        .with_trace(self.trace.clone())
+        .with_traffic(self.traffic.clone())
     }
 
     /// 获取 Provider 列表（用于故障转移）
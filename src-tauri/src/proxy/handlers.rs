@@ -13,11 +13,14 @@ use super::{
         CLAUDE_PARSER_CONFIG, CODEX_PARSER_CONFIG, GEMINI_PARSER_CONFIG, OPENAI_PARSER_CONFIG,
     },
     handler_context::RequestContext,
+    log_scrubber,
     providers::{get_adapter, streaming::create_anthropic_sse_stream, transform},
+    response_headers,
     response_processor::{create_logged_passthrough_stream, process_response, SseUsageCollector},
     server::ProxyState,
+    sse_coalesce,
     types::*,
-    usage::parser::TokenUsage,
+    usage::{logger::RequestTraffic, parser::TokenUsage},
     ProxyError,
 };
 use crate::app_config::AppType;
@@ -45,6 +48,8 @@ pub async fn health_check() -> (StatusCode, Json<Value>) {
 pub async fn get_status(State(state): State<ProxyState>) -> Result<Json<ProxyStatus>, ProxyError> {
     let mut status = state.status.read().await.clone();
     status.tps = state.tps_monitor.lock().await.current_tps();
+    status.overhead = super::overhead::snapshot();
+    status.concurrency = super::concurrency::snapshot();
     Ok(Json(status))
 }
 
@@ -60,10 +65,19 @@ pub async fn get_status(State(state): State<ProxyState>) -> Result<Json<ProxySta
 pub async fn handle_messages(
     State(state): State<ProxyState>,
     headers: axum::http::HeaderMap,
-    Json(body): Json<Value>,
+    Json(mut body): Json<Value>,
 ) -> Result<axum::response::Response, ProxyError> {
     let mut ctx =
         RequestContext::new(&state, &body, &headers, AppType::Claude, "Claude", "claude").await?;
+    ctx.enforce_parameter_policy(&mut body)?;
+    ctx.check_context_window(&body)?;
+    ctx.start_shadow(
+        &state,
+        "/v1/messages",
+        &body,
+        &headers,
+        &CLAUDE_PARSER_CONFIG,
+    );
 
     let is_stream = body
         .get("stream")
@@ -93,6 +107,7 @@ pub async fn handle_messages(
     };
 
     ctx.provider = result.provider;
+    ctx.record_overhead(&forwarder);
     let response = result.response;
 
     // 检查是否需要格式转换（OpenRouter 等中转服务）
@@ -134,7 +149,7 @@ async fn handle_claude_transform(
         // 流式响应转换 (OpenAI SSE → Anthropic SSE)
         log::info!("[Claude] 开始流式响应转换 (OpenAI SSE → Anthropic SSE)");
 
-        let stream = response.bytes_stream();
+        let stream = ctx.traffic.count_received(response.bytes_stream());
         let sse_stream = create_anthropic_sse_stream(stream);
 
         // 创建使用量收集器
@@ -144,27 +159,26 @@ async fn handle_claude_transform(
             let model = ctx.request_model.clone();
             let status_code = status.as_u16();
             let start_time = ctx.start_time;
+            let traffic = ctx.traffic.clone();
+            let request_tag = ctx.request_tag.clone();
+            let is_replay = ctx.is_replay;
 
             SseUsageCollector::new(start_time, move |events, first_token_ms| {
                 if let Some(usage) = TokenUsage::from_claude_stream_events(&events) {
                     let latency_ms = start_time.elapsed().as_millis() as u64;
+                    let traffic = traffic.snapshot();
                     let state = state.clone();
                     let provider_id = provider_id.clone();
                     let model = model.clone();
+                    let request_tag = request_tag.clone();
                     let usage_tokens = usage.output_tokens as u64;
 
                     tokio::spawn(async move {
                         // TPS：仅统计 2xx 响应
                         if (200..300).contains(&status_code) && usage_tokens > 0 {
                             state
-                                .tps_monitor
-                                .lock()
-                                .await
-                                .record_completed_request(
-                                    usage_tokens,
-                                    start_time,
-                                    std::time::Instant::now(),
-                                );
+                                .record_throughput("claude", &provider_id, usage_tokens, start_time)
+                                .await;
                         }
 
                         log_usage(
@@ -177,6 +191,9 @@ async fn handle_claude_transform(
                             first_token_ms,
                             true,
                             status_code,
+                            traffic,
+                            request_tag,
+                            is_replay,
                         )
                         .await;
                     });
@@ -210,7 +227,7 @@ async fn handle_claude_transform(
             axum::http::HeaderValue::from_static("keep-alive"),
         );
 
-        let body = axum::body::Body::from_stream(logged_stream);
+        let body = sse_coalesce::body_for(logged_stream, &ctx.provider);
         log::info!("[Claude] ====== 请求结束 (流式转换) ======");
         return Ok((headers, body).into_response());
     }
@@ -218,12 +235,13 @@ async fn handle_claude_transform(
     // 非流式响应转换 (OpenAI → Anthropic)
     log::info!("[Claude] 开始转换响应 (OpenAI → Anthropic)");
 
-    let response_headers = response.headers().clone();
+    let upstream_headers = response_headers::filter(response.headers());
 
     let body_bytes = response.bytes().await.map_err(|e| {
         log::error!("[Claude] 读取响应体失败: {e}");
         ProxyError::ForwardFailed(format!("Failed to read response body: {e}"))
     })?;
+    ctx.traffic.add_received(body_bytes.len());
 
     let body_str = String::from_utf8_lossy(&body_bytes);
     log::info!("[Claude] OpenAI 响应长度: {} bytes", body_bytes.len());
@@ -237,18 +255,20 @@ async fn handle_claude_transform(
     log::info!("[Claude] 解析 OpenAI 响应成功");
     log::info!(
         "[Claude] <<< OpenAI 响应 JSON:\n{}",
-        serde_json::to_string_pretty(&openai_response).unwrap_or_default()
+        log_scrubber::body_for_log(&openai_response)
     );
 
+    let translation_span = ctx.trace.span("translation.response");
     let anthropic_response = transform::openai_to_anthropic(openai_response).map_err(|e| {
         log::error!("[Claude] 转换响应失败: {e}");
         e
     })?;
+    drop(translation_span);
 
     log::info!("[Claude] 转换响应成功");
     log::info!(
         "[Claude] <<< Anthropic 响应 JSON:\n{}",
-        serde_json::to_string_pretty(&anthropic_response).unwrap_or_default()
+        log_scrubber::body_for_log(&anthropic_response)
     );
 
     // TPS：仅使用 usage.output_tokens（不做估算），按“请求活跃时间”摊销到滑动窗口（仅统计 2xx）
@@ -258,14 +278,8 @@ async fn handle_claude_transform(
             .unwrap_or(0);
         if usage_tokens > 0 {
             state
-                .tps_monitor
-                .lock()
-                .await
-                .record_completed_request(
-                    usage_tokens,
-                    ctx.start_time,
-                    std::time::Instant::now(),
-                );
+                .record_throughput("claude", &ctx.provider.id, usage_tokens, ctx.start_time)
+                .await;
         }
     }
 
@@ -276,11 +290,14 @@ async fn handle_claude_transform(
             .and_then(|m| m.as_str())
             .unwrap_or("unknown");
         let latency_ms = ctx.latency_ms();
+        let traffic = ctx.traffic.snapshot();
 
         tokio::spawn({
             let state = state.clone();
             let provider_id = ctx.provider.id.clone();
             let model = model.to_string();
+            let request_tag = ctx.request_tag.clone();
+            let is_replay = ctx.is_replay;
             async move {
                 log_usage(
                     &state,
@@ -292,6 +309,9 @@ async fn handle_claude_transform(
                     None,
                     false,
                     status.as_u16(),
+                    traffic,
+                    request_tag,
+                    is_replay,
                 )
                 .await;
             }
@@ -303,7 +323,7 @@ async fn handle_claude_transform(
     // 构建响应
     let mut builder = axum::response::Response::builder().status(status);
 
-    for (key, value) in response_headers.iter() {
+    for (key, value) in upstream_headers.iter() {
         if key.as_str().to_lowercase() != "content-length"
             && key.as_str().to_lowercase() != "transfer-encoding"
         {
@@ -335,12 +355,21 @@ async fn handle_claude_transform(
 pub async fn handle_chat_completions(
     State(state): State<ProxyState>,
     headers: axum::http::HeaderMap,
-    Json(body): Json<Value>,
+    Json(mut body): Json<Value>,
 ) -> Result<axum::response::Response, ProxyError> {
     log::info!("[Codex] ====== /v1/chat/completions 请求开始 ======");
 
     let mut ctx =
         RequestContext::new(&state, &body, &headers, AppType::Codex, "Codex", "codex").await?;
+    ctx.enforce_parameter_policy(&mut body)?;
+    ctx.check_context_window(&body)?;
+    ctx.start_shadow(
+        &state,
+        "/v1/chat/completions",
+        &body,
+        &headers,
+        &OPENAI_PARSER_CONFIG,
+    );
 
     let is_stream = body
         .get("stream")
@@ -375,6 +404,7 @@ pub async fn handle_chat_completions(
     };
 
     ctx.provider = result.provider;
+    ctx.record_overhead(&forwarder);
     let response = result.response;
 
     log::info!("[Codex] 上游响应状态: {}", response.status());
@@ -386,10 +416,19 @@ pub async fn handle_chat_completions(
 pub async fn handle_responses(
     State(state): State<ProxyState>,
     headers: axum::http::HeaderMap,
-    Json(body): Json<Value>,
+    Json(mut body): Json<Value>,
 ) -> Result<axum::response::Response, ProxyError> {
     let mut ctx =
         RequestContext::new(&state, &body, &headers, AppType::Codex, "Codex", "codex").await?;
+    ctx.enforce_parameter_policy(&mut body)?;
+    ctx.check_context_window(&body)?;
+    ctx.start_shadow(
+        &state,
+        "/v1/responses",
+        &body,
+        &headers,
+        &CODEX_PARSER_CONFIG,
+    );
 
     let is_stream = body
         .get("stream")
@@ -418,6 +457,7 @@ pub async fn handle_responses(
     };
 
     ctx.provider = result.provider;
+    ctx.record_overhead(&forwarder);
     let response = result.response;
 
     log::info!("[Codex] 上游响应状态: {}", response.status());
@@ -434,12 +474,14 @@ pub async fn handle_gemini(
     State(state): State<ProxyState>,
     uri: axum::http::Uri,
     headers: axum::http::HeaderMap,
-    Json(body): Json<Value>,
+    Json(mut body): Json<Value>,
 ) -> Result<axum::response::Response, ProxyError> {
     // Gemini 的模型名称在 URI 中
     let mut ctx = RequestContext::new(&state, &body, &headers, AppType::Gemini, "Gemini", "gemini")
         .await?
         .with_model_from_uri(&uri);
+    ctx.enforce_parameter_policy(&mut body)?;
+    ctx.check_context_window(&body)?;
 
     // 提取完整的路径和查询参数
     let endpoint = uri
@@ -448,6 +490,7 @@ pub async fn handle_gemini(
         .unwrap_or(uri.path());
 
     log::info!("[Gemini] 请求端点: {endpoint}");
+    ctx.start_shadow(&state, endpoint, &body, &headers, &GEMINI_PARSER_CONFIG);
 
     let is_stream = body
         .get("stream")
@@ -476,6 +519,7 @@ pub async fn handle_gemini(
     };
 
     ctx.provider = result.provider;
+    ctx.record_overhead(&forwarder);
     let response = result.response;
 
     log::info!("[Gemini] 上游响应状态: {}", response.status());
@@ -495,6 +539,8 @@ fn log_forward_error(
 ) {
     use super::usage::logger::UsageLogger;
 
+    ctx.trace.set_error(&error.to_string());
+
     let logger = UsageLogger::new(&state.db);
     let status_code = map_proxy_error_to_status(error);
     let error_message = get_error_message(error);
@@ -511,6 +557,9 @@ fn log_forward_error(
         is_streaming,
         Some(ctx.session_id.clone()),
         None,
+        ctx.traffic.snapshot(),
+        ctx.request_tag.clone(),
+        ctx.is_replay,
     ) {
         log::warn!("记录失败请求日志失败: {e}");
     }
@@ -528,14 +577,23 @@ async fn log_usage(
     first_token_ms: Option<u64>,
     is_streaming: bool,
     status_code: u16,
+    traffic: RequestTraffic,
+    request_tag: Option<String>,
+    is_replay: bool,
 ) {
     use super::usage::logger::UsageLogger;
 
     let logger = UsageLogger::new(&state.db);
 
-    // 获取 provider 的 cost_multiplier
-    let multiplier = match state.db.get_provider_by_id(provider_id, app_type) {
-        Ok(Some(p)) => {
+    // 获取 provider 的 cost_multiplier 与实际发送的客户端身份
+    let provider = state
+        .db
+        .get_provider_by_id(provider_id, app_type)
+        .ok()
+        .flatten();
+    let client_identity = provider.as_ref().and_then(super::client_identity::recorded);
+    let multiplier = match provider {
+        Some(p) => {
             if let Some(meta) = p.meta {
                 if let Some(cm) = meta.cost_multiplier {
                     Decimal::from_str(&cm).unwrap_or(Decimal::from(1))
@@ -546,7 +604,7 @@ async fn log_usage(
                 Decimal::from(1)
             }
         }
-        _ => Decimal::from(1),
+        None => Decimal::from(1),
     };
 
     let request_id = uuid::Uuid::new_v4().to_string();
@@ -564,6 +622,10 @@ async fn log_usage(
         None,
         None, // provider_type
         is_streaming,
+        client_identity,
+        traffic,
+        request_tag,
+        is_replay,
     ) {
         log::warn!("记录使用量失败: {e}");
     }
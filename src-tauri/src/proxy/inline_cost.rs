@@ -0,0 +1,179 @@
+//! 响应内附带用量与费用
+//!
+//! 开启 `inlineUsageSummary` 后，代理在返回给客户端的响应中附带本次调用的用量与费用，
+//! 方便展示模型输出的工具直接显示每次调用花了多少：
+//! - 非流式 JSON 响应：顶层追加 `cc_switch_usage` 字段
+//! - 流式响应：在流末尾追加一个 `event: cc_switch_usage` 的 SSE 事件
+//!
+//! 费用按模型定价与供应商成本倍数计算，未找到定价时 `cost_usd` 为空。
+
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use futures::stream::{Stream, StreamExt};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::usage::{calculator::CostCalculator, logger::UsageLogger, parser::TokenUsage};
+use crate::database::Database;
+use crate::provider::Provider;
+
+/// 附加字段名 / SSE 事件名
+pub const FIELD: &str = "cc_switch_usage";
+
+/// 流式响应结束时由用量收集器写入（模型, 用量）
+pub type UsageSlot = Arc<Mutex<Option<(String, TokenUsage)>>>;
+
+/// 本次调用的用量与费用
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InlineUsage {
+    pub model: String,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub cache_read_tokens: u32,
+    pub cache_creation_tokens: u32,
+    /// 费用（美元，已乘供应商成本倍数）
+    pub cost_usd: Option<String>,
+}
+
+/// 是否开启
+pub fn enabled() -> bool {
+    crate::settings::get_settings().inline_usage_summary
+}
+
+/// 计算用量摘要
+pub fn summarize(
+    db: &Database,
+    provider: &Provider,
+    model: &str,
+    usage: &TokenUsage,
+) -> InlineUsage {
+    let multiplier = provider
+        .meta
+        .as_ref()
+        .and_then(|m| m.cost_multiplier.as_deref())
+        .and_then(|cm| Decimal::from_str(cm).ok())
+        .unwrap_or(Decimal::ONE);
+    let pricing = UsageLogger::new(db).get_model_pricing(model).ok().flatten();
+    let cost = CostCalculator::try_calculate(usage, pricing.as_ref(), multiplier);
+
+    InlineUsage {
+        model: model.to_string(),
+        input_tokens: usage.input_tokens,
+        output_tokens: usage.output_tokens,
+        cache_read_tokens: usage.cache_read_tokens,
+        cache_creation_tokens: usage.cache_creation_tokens,
+        cost_usd: cost.map(|c| c.total_cost.round_dp(6).normalize().to_string()),
+    }
+}
+
+/// 在非流式 JSON 响应中追加用量字段（响应不是 JSON 对象时返回 None）
+pub fn inject_into_json(response: &Value, summary: &InlineUsage) -> Option<Bytes> {
+    let mut response = response.clone();
+    response
+        .as_object_mut()?
+        .insert(FIELD.to_string(), serde_json::to_value(summary).ok()?);
+    serde_json::to_vec(&response).ok().map(Bytes::from)
+}
+
+/// 格式化为 SSE 事件
+fn sse_event(summary: &InlineUsage) -> Bytes {
+    let data = serde_json::json!({ "type": FIELD, FIELD: summary });
+    Bytes::from(format!("event: {FIELD}\ndata: {data}\n\n"))
+}
+
+/// 流正常结束后追加用量事件（收集器未解析出用量时不追加）
+pub fn append_to_stream<S>(
+    stream: S,
+    slot: UsageSlot,
+    db: Arc<Database>,
+    provider: Provider,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send
+where
+    S: Stream<Item = Result<Bytes, std::io::Error>> + Send + 'static,
+{
+    async_stream::stream! {
+        tokio::pin!(stream);
+        while let Some(chunk) = stream.next().await {
+            let failed = chunk.is_err();
+            yield chunk;
+            if failed {
+                return;
+            }
+        }
+
+        let recorded = slot.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some((model, usage)) = recorded {
+            yield Ok(sse_event(&summarize(&db, &provider, &model, &usage)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use serde_json::json;
+
+    fn summary() -> InlineUsage {
+        InlineUsage {
+            model: "m".to_string(),
+            input_tokens: 10,
+            output_tokens: 5,
+            cache_read_tokens: 0,
+            cache_creation_tokens: 0,
+            cost_usd: Some("0.0012".to_string()),
+        }
+    }
+
+    #[test]
+    fn injects_field_into_json_objects_only() {
+        let body = inject_into_json(&json!({"id": "msg_1"}), &summary()).unwrap();
+        let value: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(value["id"], "msg_1");
+        assert_eq!(value[FIELD]["output_tokens"], 5);
+        assert_eq!(value[FIELD]["cost_usd"], "0.0012");
+
+        assert!(inject_into_json(&json!([1, 2]), &summary()).is_none());
+    }
+
+    #[test]
+    fn formats_trailing_sse_event() {
+        let event = String::from_utf8(sse_event(&summary()).to_vec()).unwrap();
+        assert!(event.starts_with("event: cc_switch_usage\ndata: {"));
+        assert!(event.ends_with("}\n\n"));
+    }
+
+    #[test]
+    fn summarize_applies_pricing_and_multiplier() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        {
+            let conn = crate::database::lock_conn!(db.conn);
+            conn.execute(
+                "INSERT OR REPLACE INTO model_pricing (model_id, display_name, input_cost_per_million, output_cost_per_million)
+                 VALUES ('inline-test', 'Inline Test', '1.0', '2.0')",
+                [],
+            )
+            .unwrap();
+        }
+        let mut provider = Provider::with_id("p".into(), "P".into(), json!({}), None);
+        provider.meta = Some(crate::provider::ProviderMeta {
+            cost_multiplier: Some("2".to_string()),
+            ..crate::provider::ProviderMeta::default()
+        });
+        let usage = TokenUsage {
+            input_tokens: 1_000_000,
+            output_tokens: 500_000,
+            ..TokenUsage::default()
+        };
+
+        let summary = summarize(&db, &provider, "inline-test", &usage);
+        assert_eq!(summary.cost_usd.as_deref(), Some("4"));
+        assert!(summarize(&db, &provider, "unknown-model", &usage)
+            .cost_usd
+            .is_none());
+        Ok(())
+    }
+}
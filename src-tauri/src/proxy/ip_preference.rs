@@ -0,0 +1,130 @@
+//! 供应商级 IPv4/IPv6 连接偏好
+//!
+//! reqwest 底层连接器已实现 happy-eyeballs：先拨号解析结果中首个地址所属的协议族，
+//! 300ms 未连通再并行尝试另一协议族。这里通过自定义 DNS 解析器调整地址顺序或过滤协议族，
+//! 让某些中转站损坏的 AAAA 记录不再拖垮整个供应商。
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::ClientBuilder;
+use serde::{Deserialize, Serialize};
+
+use crate::provider::Provider;
+
+/// IP 协议族偏好
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum IpPreference {
+    /// 使用系统解析顺序
+    #[default]
+    Auto,
+    /// 优先 IPv4，失败时回退 IPv6
+    PreferIpv4,
+    /// 优先 IPv6，失败时回退 IPv4
+    PreferIpv6,
+    /// 仅使用 IPv4
+    Ipv4Only,
+    /// 仅使用 IPv6
+    Ipv6Only,
+}
+
+impl IpPreference {
+    /// 读取供应商配置的偏好（未配置为 Auto）
+    pub fn of(provider: &Provider) -> Self {
+        provider
+            .meta
+            .as_ref()
+            .and_then(|m| m.ip_preference)
+            .unwrap_or_default()
+    }
+}
+
+/// 按偏好排序 / 过滤解析结果（同协议族内保持原顺序）
+pub fn order_addrs(mut addrs: Vec<SocketAddr>, preference: IpPreference) -> Vec<SocketAddr> {
+    match preference {
+        IpPreference::Auto => {}
+        IpPreference::PreferIpv4 => addrs.sort_by_key(|a| a.is_ipv6()),
+        IpPreference::PreferIpv6 => addrs.sort_by_key(|a| a.is_ipv4()),
+        IpPreference::Ipv4Only => addrs.retain(|a| a.is_ipv4()),
+        IpPreference::Ipv6Only => addrs.retain(|a| a.is_ipv6()),
+    }
+    addrs
+}
+
+/// 按偏好调整地址顺序的 DNS 解析器
+struct PreferenceResolver {
+    preference: IpPreference,
+    /// 主机别名 (请求中使用的主机名, 实际解析的主机名)，用于 SNI 覆盖
+    alias: Option<(String, String)>,
+}
+
+impl Resolve for PreferenceResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let preference = self.preference;
+        let host = match &self.alias {
+            Some((from, to)) if from.eq_ignore_ascii_case(name.as_str()) => to.clone(),
+            _ => name.as_str().to_string(),
+        };
+        Box::pin(async move {
+            let resolved: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            let ordered = order_addrs(resolved, preference);
+            if ordered.is_empty() {
+                return Err(format!("{host} 没有符合 {preference:?} 的地址").into());
+            }
+            let addrs: Addrs = Box::new(ordered.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// 为 ClientBuilder 应用连接偏好（Auto 时不做修改）
+pub fn apply(builder: ClientBuilder, preference: IpPreference) -> ClientBuilder {
+    if preference == IpPreference::Auto {
+        builder
+    } else {
+        install_resolver(builder, preference, None)
+    }
+}
+
+/// 安装自定义解析器（可附带主机别名）
+pub(crate) fn install_resolver(
+    builder: ClientBuilder,
+    preference: IpPreference,
+    alias: Option<(String, String)>,
+) -> ClientBuilder {
+    builder.dns_resolver(Arc::new(PreferenceResolver { preference, alias }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addrs() -> Vec<SocketAddr> {
+        vec![
+            "[2001:db8::1]:0".parse().unwrap(),
+            "192.0.2.1:0".parse().unwrap(),
+            "[2001:db8::2]:0".parse().unwrap(),
+            "192.0.2.2:0".parse().unwrap(),
+        ]
+    }
+
+    #[test]
+    fn prefer_ipv4_moves_v4_first() {
+        let ordered = order_addrs(addrs(), IpPreference::PreferIpv4);
+        assert!(ordered[0].is_ipv4() && ordered[1].is_ipv4());
+        assert_eq!(ordered[0].to_string(), "192.0.2.1:0");
+        assert_eq!(ordered.len(), 4);
+    }
+
+    #[test]
+    fn only_filters_family() {
+        assert!(order_addrs(addrs(), IpPreference::Ipv6Only)
+            .iter()
+            .all(|a| a.is_ipv6()));
+        assert_eq!(order_addrs(addrs(), IpPreference::Ipv4Only).len(), 2);
+        assert_eq!(order_addrs(addrs(), IpPreference::Auto), addrs());
+    }
+}
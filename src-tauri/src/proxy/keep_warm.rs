@@ -0,0 +1,155 @@
+//! 空闲保活
+//!
+//! 代理空闲超过设定时长后，向各应用当前使用的供应商发送轻量请求，
+//! 减少长时间空闲（如隔夜）后首个请求的冷启动延迟。默认关闭。
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::proxy::custom_headers::apply_custom_headers_to_request;
+use crate::proxy::providers::get_adapter;
+use crate::proxy::{client_pool, provider_tls};
+use crate::services::stream_check::{StreamCheckConfig, StreamCheckService};
+use crate::settings::{AppSettings, KeepWarmMode};
+
+/// 空闲检测间隔（秒）
+pub const CHECK_INTERVAL_SECS: u64 = 60;
+
+/// HEAD 请求超时（秒）
+const HEAD_TIMEOUT_SECS: u64 = 15;
+
+/// 单次补全保活的 token 估算（约 8 个输入 token + 1 个输出 token）
+pub const COMPLETION_PING_TOKENS: u64 = 9;
+
+/// 保活开销估算
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeepWarmEstimate {
+    pub enabled: bool,
+    pub mode: KeepWarmMode,
+    pub idle_minutes: u32,
+    /// 参与保活的供应商数量
+    pub target_count: usize,
+    /// 完全空闲时每天最多发送的保活请求数
+    pub max_pings_per_day: u64,
+    /// 单次保活的 token 估算
+    pub tokens_per_ping: u64,
+    /// 完全空闲时每天最多消耗的 token
+    pub max_tokens_per_day: u64,
+}
+
+/// 根据设置估算保活开销（最坏情况：整天空闲）
+pub fn estimate(settings: &AppSettings, target_count: usize) -> KeepWarmEstimate {
+    let idle_minutes = settings.keep_warm_idle_minutes.max(1);
+    let max_pings_per_day = (24 * 60 / idle_minutes as u64) * target_count as u64;
+    let tokens_per_ping = match settings.keep_warm_mode {
+        KeepWarmMode::Head => 0,
+        KeepWarmMode::Completion => COMPLETION_PING_TOKENS,
+    };
+
+    KeepWarmEstimate {
+        enabled: settings.keep_warm_enabled,
+        mode: settings.keep_warm_mode,
+        idle_minutes,
+        target_count,
+        max_pings_per_day,
+        tokens_per_ping,
+        max_tokens_per_day: max_pings_per_day * tokens_per_ping,
+    }
+}
+
+/// 判断当前是否需要发送保活请求
+///
+/// 以最近一次真实请求与最近一次保活中较晚者为基准，超过空闲时长即触发。
+pub fn should_ping(
+    now: i64,
+    last_activity: Option<i64>,
+    last_ping: Option<i64>,
+    idle_secs: i64,
+) -> bool {
+    match last_activity.max(last_ping) {
+        Some(last) => now - last >= idle_secs,
+        None => false,
+    }
+}
+
+/// 向指定供应商发送一次保活请求，返回补全保活响应中带有的实际 token 消耗
+pub async fn ping(
+    app_type: &AppType,
+    provider: &Provider,
+    mode: KeepWarmMode,
+    check_config: &StreamCheckConfig,
+) -> Result<Option<u64>, AppError> {
+    match mode {
+        KeepWarmMode::Head => {
+            let base_url = get_adapter(app_type)
+                .extract_base_url(provider)
+                .map_err(|e| AppError::Message(format!("提取 base_url 失败: {e}")))?;
+
+            // 使用代理转发的共享客户端，保活的连接才能被后续请求复用
+            let client = client_pool::shared(provider, &base_url)?;
+            let base_url = provider_tls::rewrite_url(&base_url, provider);
+
+            // 与代理转发使用相同的客户端身份与自定义请求头
+            let mut request = client
+                .head(&base_url)
+                .build()
+                .map_err(|e| AppError::Message(format!("构建保活请求失败: {e}")))?;
+            apply_custom_headers_to_request(provider, &mut request);
+            *request.timeout_mut() = Some(Duration::from_secs(HEAD_TIMEOUT_SECS));
+
+            // 只需建立连接，任何 HTTP 状态码都视为成功
+            client
+                .execute(request)
+                .await
+                .map(|_| None)
+                .map_err(|e| AppError::Message(format!("保活请求失败: {e}")))
+        }
+        KeepWarmMode::Completion => {
+            let config = StreamCheckConfig {
+                max_retries: 0,
+                ..check_config.clone()
+            };
+            let result = StreamCheckService::check_with_retry(app_type, provider, &config).await?;
+            if result.success {
+                Ok(result.tokens_used)
+            } else {
+                Err(AppError::Message(result.message))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_ping_after_idle_period() {
+        assert!(!should_ping(1000, None, None, 600));
+        assert!(!should_ping(1000, Some(500), None, 600));
+        assert!(should_ping(1100, Some(500), None, 600));
+        // 上次保活之后重新计时
+        assert!(!should_ping(1100, Some(500), Some(1000), 600));
+        assert!(should_ping(1600, Some(500), Some(1000), 600));
+    }
+
+    #[test]
+    fn estimate_counts_tokens_only_for_completion() {
+        let mut settings = AppSettings {
+            keep_warm_idle_minutes: 30,
+            ..AppSettings::default()
+        };
+        let head = estimate(&settings, 2);
+        assert_eq!(head.max_pings_per_day, 96);
+        assert_eq!(head.max_tokens_per_day, 0);
+
+        settings.keep_warm_mode = KeepWarmMode::Completion;
+        let completion = estimate(&settings, 2);
+        assert_eq!(completion.max_tokens_per_day, 96 * COMPLETION_PING_TOKENS);
+    }
+}
@@ -0,0 +1,214 @@
+//! 本地套接字监听（Unix domain socket / Windows named pipe）
+//!
+//! 供支持本地套接字的客户端使用：只有本机（Unix 下仅当前用户）能够连接，不占用 TCP 端口。
+//! 套接字上的连接原样转发到代理的 TCP 监听地址，复用同一套路由与中间件。
+//!
+//! 开启 `socketOnly` 时 TCP 只在回环地址的随机端口上监听（仅用于内部转发），
+//! 避免端口冲突，局域网设备也无法访问代理。
+
+use std::net::{Ipv4Addr, SocketAddr};
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+
+use super::ProxyError;
+
+/// 本地套接字设置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalSocketConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 套接字路径（Unix）或管道名（Windows），为空时使用默认值
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// 只通过本地套接字提供服务
+    #[serde(default)]
+    pub socket_only: bool,
+}
+
+impl LocalSocketConfig {
+    /// 实际使用的套接字路径
+    pub fn resolved_path(&self) -> String {
+        self.path
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(default_path)
+    }
+}
+
+#[cfg(unix)]
+fn default_path() -> String {
+    crate::config::get_app_config_dir()
+        .join("proxy.sock")
+        .to_string_lossy()
+        .to_string()
+}
+
+#[cfg(windows)]
+fn default_path() -> String {
+    r"\\.\pipe\cc-switch-proxy".to_string()
+}
+
+/// 仅本地套接字模式下 TCP 的监听地址（回环地址 + 随机端口）
+pub fn socket_only_tcp_addr() -> SocketAddr {
+    SocketAddr::from((Ipv4Addr::LOCALHOST, 0))
+}
+
+/// 转发目标：监听在全部网卡时改用回环地址连接
+fn forward_target(addr: SocketAddr) -> SocketAddr {
+    if addr.ip().is_unspecified() {
+        let loopback = match addr {
+            SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+            SocketAddr::V6(_) => std::net::Ipv6Addr::LOCALHOST.into(),
+        };
+        SocketAddr::new(loopback, addr.port())
+    } else {
+        addr
+    }
+}
+
+async fn bridge<S: AsyncRead + AsyncWrite + Unpin>(mut client: S, upstream: SocketAddr) {
+    match TcpStream::connect(upstream).await {
+        Ok(mut tcp) => {
+            let _ = tokio::io::copy_bidirectional(&mut client, &mut tcp).await;
+        }
+        Err(e) => log::warn!("[LocalSocket] 转发到 {upstream} 失败: {e}"),
+    }
+}
+
+/// 开始在本地套接字上监听，连接转发到 `tcp_addr`
+#[cfg(unix)]
+pub async fn serve(path: &str, tcp_addr: SocketAddr) -> Result<JoinHandle<()>, ProxyError> {
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+    use tokio::net::{UnixListener, UnixStream};
+
+    let socket_path = Path::new(path);
+    if socket_path.exists() {
+        // 仍能连上说明有其他进程在使用；否则是上次异常退出残留的文件
+        if UnixStream::connect(socket_path).await.is_ok() {
+            return Err(ProxyError::BindFailed(format!(
+                "本地套接字已被占用: {path}"
+            )));
+        }
+        std::fs::remove_file(socket_path)
+            .map_err(|e| ProxyError::BindFailed(format!("清理残留套接字失败: {e}")))?;
+    }
+    if let Some(parent) = socket_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| ProxyError::BindFailed(e.to_string()))?;
+    }
+
+    let listener =
+        UnixListener::bind(socket_path).map_err(|e| ProxyError::BindFailed(e.to_string()))?;
+    // 仅当前用户可访问
+    std::fs::set_permissions(socket_path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| ProxyError::BindFailed(format!("设置套接字权限失败: {e}")))?;
+    log::info!("代理本地套接字监听于 {path}");
+
+    let upstream = forward_target(tcp_addr);
+    Ok(tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(bridge(stream, upstream));
+                }
+                Err(e) => {
+                    log::warn!("[LocalSocket] 接受连接失败: {e}");
+                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                }
+            }
+        }
+    }))
+}
+
+/// 开始在命名管道上监听，连接转发到 `tcp_addr`
+#[cfg(windows)]
+pub async fn serve(path: &str, tcp_addr: SocketAddr) -> Result<JoinHandle<()>, ProxyError> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let name = path.to_string();
+    // 默认拒绝远程客户端
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(&name)
+        .map_err(|e| ProxyError::BindFailed(format!("创建命名管道失败: {e}")))?;
+    log::info!("代理命名管道监听于 {name}");
+
+    let upstream = forward_target(tcp_addr);
+    Ok(tokio::spawn(async move {
+        loop {
+            if let Err(e) = server.connect().await {
+                log::warn!("[LocalSocket] 接受连接失败: {e}");
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                continue;
+            }
+            // 先创建下一个管道实例，再把已连接的实例交给转发任务
+            let next = match ServerOptions::new().create(&name) {
+                Ok(next) => next,
+                Err(e) => {
+                    log::error!("[LocalSocket] 创建命名管道失败，停止监听: {e}");
+                    return;
+                }
+            };
+            let connected = std::mem::replace(&mut server, next);
+            tokio::spawn(bridge(connected, upstream));
+        }
+    }))
+}
+
+/// 停止监听后清理套接字文件
+pub fn cleanup(path: &str) {
+    #[cfg(unix)]
+    {
+        let _ = std::fs::remove_file(path);
+    }
+    #[cfg(windows)]
+    let _ = path;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn forwards_unspecified_address_to_loopback() {
+        let addr: SocketAddr = "0.0.0.0:15721".parse().unwrap();
+        assert_eq!(forward_target(addr), "127.0.0.1:15721".parse().unwrap());
+        let addr: SocketAddr = "192.168.1.2:15721".parse().unwrap();
+        assert_eq!(forward_target(addr), addr);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn bridges_socket_connections_to_tcp() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let tcp = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let tcp_addr = tcp.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut conn, _) = tcp.accept().await.unwrap();
+            let mut buf = [0u8; 4];
+            conn.read_exact(&mut buf).await.unwrap();
+            conn.write_all(&buf).await.unwrap();
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("proxy.sock");
+        let path = path.to_str().unwrap();
+        let handle = serve(path, tcp_addr).await.unwrap();
+
+        let mut client = tokio::net::UnixStream::connect(path).await.unwrap();
+        client.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        client.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+
+        handle.abort();
+        cleanup(path);
+    }
+}
@@ -0,0 +1,203 @@
+//! 日志内容脱敏
+//!
+//! 代理会把请求 / 响应 JSON 写入日志，便于排查问题；写入前按设置处理敏感内容，
+//! 使日志可以直接附在问题反馈中：
+//! - redact（默认）：替换 API Key、邮箱以及自定义正则匹配的内容，凭据类字段整体隐藏
+//! - strict：只保留结构（字段名、类型、长度）以及 model / role 等少量元数据
+//! - off：原样记录
+
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::settings::{self, LogScrubMode};
+
+const REDACTED: &str = "[REDACTED]";
+
+/// 值整体隐藏的字段名（小写比较）
+const SECRET_KEYS: &[&str] = &[
+    "api_key",
+    "apikey",
+    "authorization",
+    "x-api-key",
+    "x-goog-api-key",
+    "password",
+    "secret",
+    "token",
+    "access_token",
+    "refresh_token",
+];
+
+/// strict 模式下保留原值的字段
+const METADATA_KEYS: &[&str] = &["model", "type", "role", "stop_reason", "finish_reason"];
+
+/// 内置规则：常见 API Key 格式与邮箱
+static BUILTIN_PATTERNS: Lazy<Vec<(Regex, &'static str)>> = Lazy::new(|| {
+    [
+        (r"sk-[A-Za-z0-9_\-]{16,}", REDACTED),
+        (r"AIza[0-9A-Za-z_\-]{30,}", REDACTED),
+        (r"gh[pousr]_[A-Za-z0-9]{30,}", REDACTED),
+        (r"AKIA[0-9A-Z]{16}", REDACTED),
+        (r"(?i)bearer\s+[A-Za-z0-9._\-]{16,}", "Bearer [REDACTED]"),
+        (
+            r"[A-Za-z0-9._%+\-]+@[A-Za-z0-9.\-]+\.[A-Za-z]{2,}",
+            "[EMAIL]",
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(pattern, replacement)| Some((Regex::new(pattern).ok()?, replacement)))
+    .collect()
+});
+
+/// 已编译的自定义规则（设置变化时重新编译）
+static CUSTOM_PATTERNS: Lazy<Mutex<(Vec<String>, Vec<Regex>)>> =
+    Lazy::new(|| Mutex::new((Vec::new(), Vec::new())));
+
+fn custom_patterns(patterns: &[String]) -> Vec<Regex> {
+    let mut cache = CUSTOM_PATTERNS.lock().unwrap_or_else(|e| e.into_inner());
+    if cache.0 != patterns {
+        let compiled = patterns
+            .iter()
+            .filter_map(|p| match Regex::new(p) {
+                Ok(re) => Some(re),
+                Err(e) => {
+                    log::warn!("忽略无效的日志脱敏规则 {p:?}: {e}");
+                    None
+                }
+            })
+            .collect();
+        *cache = (patterns.to_vec(), compiled);
+    }
+    cache.1.clone()
+}
+
+/// 按当前设置格式化 JSON 内容用于日志
+pub fn body_for_log<T: Serialize + ?Sized>(body: &T) -> String {
+    let value = serde_json::to_value(body).unwrap_or(Value::Null);
+    serde_json::to_string_pretty(&value_for_log(value)).unwrap_or_default()
+}
+
+/// 按当前设置脱敏 JSON 值（需要保留 JSON 结构时使用，如会话记录）
+pub fn value_for_log(value: Value) -> Value {
+    let settings = settings::get_settings();
+    scrub_value(
+        value,
+        settings.log_scrub_mode,
+        &custom_patterns(&settings.log_scrub_patterns),
+    )
+}
+
+/// 按当前设置处理无法解析为 JSON 的原始文本
+pub fn text_for_log(text: &str) -> String {
+    let settings = settings::get_settings();
+    match settings.log_scrub_mode {
+        LogScrubMode::Off => text.to_string(),
+        LogScrubMode::Redact => scrub_text(text, &custom_patterns(&settings.log_scrub_patterns)),
+        LogScrubMode::Strict => format!("<{} bytes>", text.len()),
+    }
+}
+
+/// 脱敏 JSON 值
+pub fn scrub_value(value: Value, mode: LogScrubMode, custom: &[Regex]) -> Value {
+    match mode {
+        LogScrubMode::Off => value,
+        LogScrubMode::Redact => redact(value, custom),
+        LogScrubMode::Strict => structure_only(value),
+    }
+}
+
+fn is_secret_key(key: &str) -> bool {
+    SECRET_KEYS.contains(&key.to_ascii_lowercase().as_str())
+}
+
+fn scrub_text(text: &str, custom: &[Regex]) -> String {
+    let mut out = text.to_string();
+    for (re, replacement) in BUILTIN_PATTERNS.iter() {
+        out = re.replace_all(&out, *replacement).into_owned();
+    }
+    for re in custom {
+        out = re.replace_all(&out, REDACTED).into_owned();
+    }
+    out
+}
+
+fn redact(value: Value, custom: &[Regex]) -> Value {
+    match value {
+        Value::String(s) => Value::String(scrub_text(&s, custom)),
+        Value::Array(items) => Value::Array(items.into_iter().map(|v| redact(v, custom)).collect()),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, v)| {
+                    let v = if is_secret_key(&key) && !v.is_null() {
+                        Value::String(REDACTED.to_string())
+                    } else {
+                        redact(v, custom)
+                    };
+                    (key, v)
+                })
+                .collect::<Map<_, _>>(),
+        ),
+        other => other,
+    }
+}
+
+fn structure_only(value: Value) -> Value {
+    match value {
+        Value::String(s) => Value::String(format!("<{} chars>", s.chars().count())),
+        Value::Array(items) => Value::Array(items.into_iter().map(structure_only).collect()),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, v)| {
+                    let keep =
+                        matches!(v, Value::String(_)) && METADATA_KEYS.contains(&key.as_str());
+                    let v = if keep { v } else { structure_only(v) };
+                    (key, v)
+                })
+                .collect::<Map<_, _>>(),
+        ),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> Value {
+        json!({
+            "model": "claude-sonnet-4",
+            "max_tokens": 1024,
+            "api_key": "whatever",
+            "messages": [{
+                "role": "user",
+                "content": "my key is sk-ant-REDACTED, mail me at dev@example.com, ticket ACME-4821"
+            }]
+        })
+    }
+
+    #[test]
+    fn redact_replaces_keys_emails_and_custom_patterns() {
+        let custom = vec![Regex::new(r"ACME-\d+").unwrap()];
+        let out = scrub_value(sample(), LogScrubMode::Redact, &custom);
+        assert_eq!(out["api_key"], REDACTED);
+        assert_eq!(out["model"], "claude-sonnet-4");
+        assert_eq!(
+            out["messages"][0]["content"],
+            "my key is [REDACTED], mail me at [EMAIL], ticket [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn strict_keeps_only_structure() {
+        let out = scrub_value(sample(), LogScrubMode::Strict, &[]);
+        assert_eq!(out["model"], "claude-sonnet-4");
+        assert_eq!(out["max_tokens"], 1024);
+        assert_eq!(out["messages"][0]["role"], "user");
+        assert_eq!(out["messages"][0]["content"], "<89 chars>");
+        assert_eq!(out["api_key"], "<8 chars>");
+    }
+}
@@ -0,0 +1,428 @@
+//! 本地管理 API
+//!
+//! 与代理共用监听地址，挂载在 `/api` 下，供状态栏脚本、外部面板等读取状态或切换供应商。
+//! 默认关闭；开启后每个请求都需要携带令牌（`Authorization: Bearer` 或 `x-cc-switch-token`），
+//! 并按路由校验权限（见 [`api_auth`](super::api_auth)）。
+//!
+//! `/api/events` 以 SSE 推送事件总线上的事件（健康变化、切换、TPS 采样、告警等），
+//! 外部面板无需轮询即可获得更新。
+
+use std::convert::Infallible;
+use std::str::FromStr;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{delete, get, post},
+    Json, Router,
+};
+use futures::Stream;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::Manager;
+use tokio::sync::{broadcast::error::RecvError, watch};
+
+use super::api_auth::{self, ApiScope, ApiToken, AuthError, CreatedApiToken};
+use super::concurrency;
+use super::server::ProxyState;
+use super::types::ProxyStatus;
+use crate::app_config::AppType;
+use crate::events::{self, EventFilter, EventKind};
+use crate::provider::Provider;
+use crate::services::status_export::{self, AppStatusEntry};
+use crate::services::usage_stats::{TagStats, UsageSummary};
+use crate::services::ProviderService;
+use crate::store::AppState;
+
+/// 每次代理停止时递增，通知打开中的事件流结束（否则优雅关闭会一直等待长连接）
+static STREAM_CLOSE: Lazy<watch::Sender<u64>> = Lazy::new(|| watch::channel(0).0);
+
+/// 结束所有打开中的事件流
+pub fn close_event_streams() {
+    STREAM_CLOSE.send_modify(|generation| *generation += 1);
+}
+
+/// 管理 API 错误（JSON `{"error": "..."}`）
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    fn internal(message: impl ToString) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, message.to_string())
+    }
+}
+
+impl From<AuthError> for ApiError {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::Unauthorized => Self::new(StatusCode::UNAUTHORIZED, "缺少或无效的令牌"),
+            AuthError::Forbidden(scope) => Self::new(
+                StatusCode::FORBIDDEN,
+                format!("令牌没有 {} 权限", scope.as_str()),
+            ),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(json!({ "error": self.message }))).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+/// 未开启时表现为不存在的路由；开启时校验令牌权限（查询数据库，放到阻塞线程执行）
+async fn guard(
+    state: &ProxyState,
+    headers: &HeaderMap,
+    scope: ApiScope,
+) -> Result<ApiToken, ApiError> {
+    if !crate::settings::get_settings().management_api_enabled {
+        return Err(ApiError::new(StatusCode::NOT_FOUND, "管理 API 未开启"));
+    }
+    let db = state.db.clone();
+    let headers = headers.clone();
+    Ok(
+        tokio::task::spawn_blocking(move || api_auth::authorize(&db, &headers, scope))
+            .await
+            .map_err(ApiError::internal)??,
+    )
+}
+
+fn parse_app(app: &str) -> Result<AppType, ApiError> {
+    AppType::from_str(app).map_err(|_| ApiError::bad_request(format!("无效的应用类型: {app}")))
+}
+
+pub fn router() -> Router<ProxyState> {
+    Router::new()
+        .route("/api/status", get(get_status))
+        .route("/api/providers/:app", get(list_providers))
+        .route(
+            "/api/providers/:app/:id",
+            axum::routing::put(update_provider),
+        )
+        .route("/api/providers/:app/:id/switch", post(switch_provider))
+        .route("/api/usage", get(get_usage))
+        .route("/api/usage/tags", get(get_tag_usage))
+        .route("/api/events", get(event_stream))
+        .route("/api/tokens", get(list_tokens).post(create_token))
+        .route("/api/tokens/:id", delete(revoke_token))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StatusResponse {
+    proxy: ProxyStatus,
+    apps: Vec<AppStatusEntry>,
+}
+
+/// GET /api/status（read-status）
+async fn get_status(
+    State(state): State<ProxyState>,
+    headers: HeaderMap,
+) -> ApiResult<StatusResponse> {
+    guard(&state, &headers, ApiScope::ReadStatus).await?;
+    let mut proxy = state.status.read().await.clone();
+    proxy.tps = state.tps_monitor.lock().await.current_tps();
+    proxy.concurrency = concurrency::snapshot();
+    let db = state.db.clone();
+    let apps = tokio::task::spawn_blocking(move || status_export::app_status_entries(&db))
+        .await
+        .map_err(ApiError::internal)?;
+    Ok(Json(StatusResponse { proxy, apps }))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProviderEntry {
+    id: String,
+    name: String,
+    current: bool,
+}
+
+/// GET /api/providers/:app（read-status，不含配置内容）
+async fn list_providers(
+    State(state): State<ProxyState>,
+    headers: HeaderMap,
+    Path(app): Path<String>,
+) -> ApiResult<Vec<ProviderEntry>> {
+    guard(&state, &headers, ApiScope::ReadStatus).await?;
+    let app_type = parse_app(&app)?;
+    let providers = state
+        .db
+        .get_all_providers(app_type.as_str())
+        .map_err(ApiError::internal)?;
+    let current = crate::settings::get_effective_current_provider(&state.db, &app_type)
+        .map_err(ApiError::internal)?;
+    Ok(Json(
+        providers
+            .into_values()
+            .map(|p| ProviderEntry {
+                current: current.as_deref() == Some(p.id.as_str()),
+                id: p.id,
+                name: p.name,
+            })
+            .collect(),
+    ))
+}
+
+/// POST /api/providers/:app/:id/switch（switch）
+async fn switch_provider(
+    State(state): State<ProxyState>,
+    headers: HeaderMap,
+    Path((app, id)): Path<(String, String)>,
+) -> ApiResult<Value> {
+    let token = guard(&state, &headers, ApiScope::Switch).await?;
+    let app_type = parse_app(&app)?;
+    let handle = state
+        .app_handle
+        .clone()
+        .ok_or_else(|| ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "应用未就绪"))?;
+    let app_str = app_type.as_str().to_string();
+    let provider_id = id.clone();
+    tokio::task::spawn_blocking(move || {
+        let Some(app_state) = handle.try_state::<AppState>() else {
+            return Err("应用未就绪".to_string());
+        };
+        crate::commands::switch_provider_from(
+            &handle,
+            app_state.inner(),
+            &app_str,
+            provider_id,
+            "api",
+        )?;
+        if let Ok(menu) = crate::tray::create_tray_menu(&handle, app_state.inner()) {
+            if let Some(tray) = handle.tray_by_id("main") {
+                let _ = tray.set_menu(Some(menu));
+            }
+        }
+        Ok(())
+    })
+    .await
+    .map_err(ApiError::internal)?
+    .map_err(ApiError::bad_request)?;
+
+    log::info!("[ManagementApi] 令牌 {} 切换 {app} -> {id}", token.name);
+    Ok(Json(json!({ "switched": true })))
+}
+
+/// PUT /api/providers/:app/:id（edit-providers，请求体为完整的供应商配置）
+async fn update_provider(
+    State(state): State<ProxyState>,
+    headers: HeaderMap,
+    Path((app, id)): Path<(String, String)>,
+    Json(provider): Json<Provider>,
+) -> ApiResult<Value> {
+    let token = guard(&state, &headers, ApiScope::EditProviders).await?;
+    let app_type = parse_app(&app)?;
+    if provider.id != id {
+        return Err(ApiError::bad_request("请求体中的供应商 ID 与路径不一致"));
+    }
+    let handle = state
+        .app_handle
+        .clone()
+        .ok_or_else(|| ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "应用未就绪"))?;
+    // 更新会写 Live 配置并同步 MCP，放到阻塞线程执行
+    tokio::task::spawn_blocking(move || {
+        let Some(app_state) = handle.try_state::<AppState>() else {
+            return Err("应用未就绪".to_string());
+        };
+        ProviderService::update(app_state.inner(), app_type, provider).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(ApiError::internal)?
+    .map_err(ApiError::bad_request)?;
+    log::info!("[ManagementApi] 令牌 {} 更新供应商 {app}/{id}", token.name);
+    Ok(Json(json!({ "updated": true })))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UsageQuery {
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+}
+
+/// GET /api/usage?startDate=&endDate=（read-usage）
+async fn get_usage(
+    State(state): State<ProxyState>,
+    headers: HeaderMap,
+    Query(query): Query<UsageQuery>,
+) -> ApiResult<UsageSummary> {
+    guard(&state, &headers, ApiScope::ReadUsage).await?;
+    let db = state.db.clone();
+    tokio::task::spawn_blocking(move || db.get_usage_summary(query.start_date, query.end_date))
+        .await
+        .map_err(ApiError::internal)?
+        .map(Json)
+        .map_err(ApiError::internal)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TagUsageQuery {
+    app: Option<String>,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+}
+
+/// GET /api/usage/tags?app=&startDate=&endDate=（read-usage），按 `X-CCSwitch-Tag` 汇总
+async fn get_tag_usage(
+    State(state): State<ProxyState>,
+    headers: HeaderMap,
+    Query(query): Query<TagUsageQuery>,
+) -> ApiResult<Vec<TagStats>> {
+    guard(&state, &headers, ApiScope::ReadUsage).await?;
+    let db = state.db.clone();
+    tokio::task::spawn_blocking(move || {
+        db.get_tag_stats(query.app.as_deref(), query.start_date, query.end_date)
+    })
+    .await
+    .map_err(ApiError::internal)?
+    .map(Json)
+    .map_err(ApiError::internal)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EventStreamQuery {
+    /// 逗号分隔的事件名（如 `health-changed,provider-switched`），为空时不限制
+    types: Option<String>,
+    app: Option<String>,
+    provider: Option<String>,
+    /// 只推送告警事件
+    #[serde(default)]
+    alerts_only: bool,
+}
+
+impl EventStreamQuery {
+    fn filter(&self) -> Result<EventFilter, ApiError> {
+        let kinds = self
+            .types
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                EventKind::from_name(name)
+                    .ok_or_else(|| ApiError::bad_request(format!("未知的事件类型: {name}")))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(EventFilter {
+            kinds,
+            app_type: self.app.clone(),
+            provider_id: self.provider.clone(),
+        })
+    }
+}
+
+/// GET /api/events?types=&app=&provider=&alertsOnly=（read-status）
+///
+/// SSE 事件名即事件类型，data 为 `{"type", "payload"}` JSON；处理过慢时发送 `lagged` 事件，data 为丢弃的条数。
+async fn event_stream(
+    State(state): State<ProxyState>,
+    headers: HeaderMap,
+    Query(query): Query<EventStreamQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let token = guard(&state, &headers, ApiScope::ReadStatus).await?;
+    let filter = query.filter()?;
+    let alerts_only = query.alerts_only;
+    let mut rx = events::subscribe();
+    let mut close = STREAM_CLOSE.subscribe();
+    log::info!("[ManagementApi] 令牌 {} 订阅事件流", token.name);
+
+    let stream = async_stream::stream! {
+        let mut seq: u64 = 0;
+        loop {
+            let received = tokio::select! {
+                _ = close.changed() => break,
+                received = rx.recv() => received,
+            };
+            match received {
+                Ok(event) => {
+                    if !filter.matches(&event) || (alerts_only && !event.is_alert()) {
+                        continue;
+                    }
+                    seq += 1;
+                    match Event::default()
+                        .event(event.kind().as_str())
+                        .id(seq.to_string())
+                        .json_data(&event)
+                    {
+                        Ok(sse_event) => yield Ok(sse_event),
+                        Err(e) => log::warn!("[ManagementApi] 序列化事件失败: {e}"),
+                    }
+                }
+                Err(RecvError::Lagged(n)) => {
+                    yield Ok(Event::default().event("lagged").data(n.to_string()));
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    };
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// GET /api/tokens（admin）
+async fn list_tokens(
+    State(state): State<ProxyState>,
+    headers: HeaderMap,
+) -> ApiResult<Vec<ApiToken>> {
+    guard(&state, &headers, ApiScope::Admin).await?;
+    state
+        .db
+        .list_api_tokens()
+        .map(Json)
+        .map_err(ApiError::internal)
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateTokenRequest {
+    name: String,
+    scopes: Vec<ApiScope>,
+}
+
+/// POST /api/tokens（admin），明文令牌只在响应中出现一次
+async fn create_token(
+    State(state): State<ProxyState>,
+    headers: HeaderMap,
+    Json(request): Json<CreateTokenRequest>,
+) -> ApiResult<CreatedApiToken> {
+    guard(&state, &headers, ApiScope::Admin).await?;
+    api_auth::create_token(&state.db, &request.name, &request.scopes, false)
+        .map(Json)
+        .map_err(|e| ApiError::bad_request(e.to_string()))
+}
+
+/// DELETE /api/tokens/:id（admin）
+async fn revoke_token(
+    State(state): State<ProxyState>,
+    headers: HeaderMap,
+    Path(id): Path<String>,
+) -> ApiResult<Value> {
+    guard(&state, &headers, ApiScope::Admin).await?;
+    let revoked = state.db.delete_api_token(&id).map_err(ApiError::internal)?;
+    Ok(Json(json!({ "revoked": revoked })))
+}
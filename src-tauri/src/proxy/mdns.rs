@@ -0,0 +1,271 @@
+//! 局域网发现（mDNS / DNS-SD）
+//!
+//! 开启广播且代理监听在非回环地址（局域网共享）时，以 `_cc-switch._tcp.local` 服务类型广播代理地址，
+//! 队友的 cc-switch 或脚本（`dns-sd -B _cc-switch._tcp`、`avahi-browse _cc-switch._tcp`）可以自动发现。
+//! 广播默认关闭，需在设置中显式开启。
+//!
+//! 广播与发现均基于 `mdns-sd`，可与系统的 Bonjour / Avahi 共用 5353 端口。
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::{Deserialize, Serialize};
+
+/// 广播的服务类型
+pub const SERVICE_TYPE: &str = "_cc-switch._tcp.local.";
+
+/// 局域网广播设置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanDiscoveryConfig {
+    /// 代理对局域网共享时是否广播（默认关闭）
+    #[serde(default)]
+    pub advertise: bool,
+    /// 服务名称，为空时使用主机名
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_name: Option<String>,
+}
+
+/// 局域网中发现的 cc-switch 代理
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscoveredProxy {
+    pub name: String,
+    pub host: String,
+    pub address: String,
+    pub port: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// 可直接填入客户端的代理地址
+    pub url: String,
+}
+
+/// 将任意字符串转换为合法的主机名标签
+fn dns_label(raw: &str) -> String {
+    let label: String = raw
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .take(63)
+        .collect();
+    let label = label.trim_matches('-');
+    if label.is_empty() {
+        "cc-switch".to_string()
+    } else {
+        label.to_string()
+    }
+}
+
+fn local_hostname() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|h| h.trim().trim_end_matches(".local").to_string())
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "cc-switch".to_string())
+}
+
+// ==================== 广播 ====================
+
+/// 构造广播的服务；监听在全部网卡时由 mdns-sd 自动跟随本机地址
+fn service_info(name: &str, listen: SocketAddr) -> Result<ServiceInfo, mdns_sd::Error> {
+    let host = format!("{}.local.", dns_label(&local_hostname()));
+    // 实例名中的点会被当作标签分隔符
+    let instance = name.replace('.', "-");
+    let properties = [("version", env!("CARGO_PKG_VERSION")), ("path", "/")];
+    if listen.ip().is_unspecified() {
+        ServiceInfo::new(
+            SERVICE_TYPE,
+            &instance,
+            &host,
+            (),
+            listen.port(),
+            &properties[..],
+        )
+        .map(ServiceInfo::enable_addr_auto)
+    } else {
+        ServiceInfo::new(
+            SERVICE_TYPE,
+            &instance,
+            &host,
+            listen.ip(),
+            listen.port(),
+            &properties[..],
+        )
+    }
+}
+
+/// 正在进行的广播，调用 `stop` 或被丢弃时停止
+pub struct Advertiser {
+    daemon: Option<ServiceDaemon>,
+    fullname: String,
+}
+
+impl Advertiser {
+    pub fn stop(&mut self) {
+        let Some(daemon) = self.daemon.take() else {
+            return;
+        };
+        // 注销会发送 TTL 为 0 的应答，让其他主机及时移除缓存
+        if let Ok(receiver) = daemon.unregister(&self.fullname) {
+            let _ = receiver.recv_timeout(Duration::from_secs(1));
+        }
+        let _ = daemon.shutdown();
+    }
+}
+
+impl Drop for Advertiser {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// 开启广播且代理对局域网共享时开始广播；监听回环地址、未开启广播或失败时返回 None
+pub fn start_advertising(config: &LanDiscoveryConfig, listen: SocketAddr) -> Option<Advertiser> {
+    if !config.advertise || listen.ip().is_loopback() {
+        return None;
+    }
+    let name = config
+        .service_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("cc-switch on {}", local_hostname()));
+
+    let result = service_info(&name, listen).and_then(|info| {
+        let daemon = ServiceDaemon::new()?;
+        let fullname = info.get_fullname().to_string();
+        if let Err(e) = daemon.register(info) {
+            let _ = daemon.shutdown();
+            return Err(e);
+        }
+        Ok(Advertiser {
+            daemon: Some(daemon),
+            fullname,
+        })
+    });
+    match result {
+        Ok(advertiser) => {
+            log::info!("[mDNS] 广播 {} -> {listen}", advertiser.fullname);
+            Some(advertiser)
+        }
+        Err(e) => {
+            log::warn!("[mDNS] 无法广播代理: {e}");
+            None
+        }
+    }
+}
+
+// ==================== 发现 ====================
+
+/// 在局域网中查询 cc-switch 代理，等待 `timeout` 收集应答
+pub fn discover(timeout: Duration) -> Result<Vec<DiscoveredProxy>, String> {
+    let daemon = ServiceDaemon::new().map_err(|e| format!("启动 mDNS 服务失败: {e}"))?;
+    let receiver = match daemon.browse(SERVICE_TYPE) {
+        Ok(receiver) => receiver,
+        Err(e) => {
+            let _ = daemon.shutdown();
+            return Err(format!("发送 mDNS 查询失败: {e}"));
+        }
+    };
+
+    let deadline = Instant::now() + timeout;
+    let mut resolved = HashMap::new();
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        match receiver.recv_timeout(remaining) {
+            Ok(ServiceEvent::ServiceResolved(info)) => {
+                if let Some(proxy) = to_discovered(&info) {
+                    resolved.insert(info.get_fullname().to_ascii_lowercase(), proxy);
+                }
+            }
+            Ok(ServiceEvent::ServiceRemoved(_, fullname)) => {
+                resolved.remove(&fullname.to_ascii_lowercase());
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+    let _ = daemon.stop_browse(SERVICE_TYPE);
+    let _ = daemon.shutdown();
+
+    let mut found: Vec<_> = resolved.into_values().collect();
+    found.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(found)
+}
+
+/// 将解析出的服务转换为代理信息（优先使用 IPv4 地址）
+fn to_discovered(info: &ServiceInfo) -> Option<DiscoveredProxy> {
+    let addresses = info.get_addresses();
+    let address = addresses
+        .iter()
+        .filter(|ip| ip.is_ipv4())
+        .chain(addresses.iter().filter(|ip| ip.is_ipv6()))
+        .min_by_key(|ip| ip.is_loopback())
+        .copied()?;
+    let port = info.get_port();
+    let url = match address {
+        IpAddr::V4(ip) => format!("http://{ip}:{port}"),
+        IpAddr::V6(ip) => format!("http://[{ip}]:{port}"),
+    };
+    let suffix = format!(".{SERVICE_TYPE}");
+    let fullname = info.get_fullname();
+    Some(DiscoveredProxy {
+        name: fullname
+            .strip_suffix(&suffix)
+            .unwrap_or(fullname)
+            .to_string(),
+        host: info.get_hostname().trim_end_matches('.').to_string(),
+        address: address.to_string(),
+        port,
+        version: info.get_property_val_str("version").map(str::to_string),
+        url,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advertising_is_opt_in() {
+        let config: LanDiscoveryConfig = serde_json::from_str("{}").unwrap();
+        assert!(!config.advertise);
+        assert_eq!(config, LanDiscoveryConfig::default());
+
+        let listen: SocketAddr = "0.0.0.0:15721".parse().unwrap();
+        assert!(start_advertising(&config, listen).is_none());
+
+        let enabled = LanDiscoveryConfig {
+            advertise: true,
+            service_name: None,
+        };
+        let loopback: SocketAddr = "127.0.0.1:15721".parse().unwrap();
+        assert!(start_advertising(&enabled, loopback).is_none());
+    }
+
+    #[test]
+    fn converts_resolved_service() {
+        let listen: SocketAddr = "192.168.1.20:15721".parse().unwrap();
+        let info = service_info("cc-switch on alice.example", listen).unwrap();
+
+        let proxy = to_discovered(&info).unwrap();
+        assert_eq!(proxy.name, "cc-switch on alice-example");
+        assert_eq!(proxy.address, "192.168.1.20");
+        assert_eq!(proxy.url, "http://192.168.1.20:15721");
+        assert_eq!(proxy.version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+        assert!(proxy.host.ends_with(".local"));
+    }
+
+    #[test]
+    fn sanitizes_host_labels() {
+        assert_eq!(dns_label("Alice's MacBook.local"), "Alice-s-MacBook-local");
+        assert_eq!(dns_label("..."), "cc-switch");
+    }
+}
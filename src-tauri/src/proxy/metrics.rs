@@ -0,0 +1,160 @@
+//! Prometheus 格式的代理指标（GET /metrics）
+
+use std::fmt::Write;
+
+use axum::{
+    extract::State,
+    http::header::CONTENT_TYPE,
+    response::{IntoResponse, Response},
+};
+
+use super::concurrency::{self, ProviderConcurrency};
+use super::server::ProxyState;
+use super::types::ProxyStatus;
+
+/// GET /metrics
+pub async fn metrics(State(state): State<ProxyState>) -> Response {
+    let mut status = state.status.read().await.clone();
+    status.tps = state.tps_monitor.lock().await.current_tps();
+    let body = render(&status, &concurrency::snapshot());
+    (
+        [(CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        body,
+    )
+        .into_response()
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// 按供应商输出的指标：名称、类型、说明、取值
+type ProviderMetric = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&ProviderConcurrency) -> f64,
+);
+
+/// 渲染指标文本
+fn render(status: &ProxyStatus, providers: &[ProviderConcurrency]) -> String {
+    let mut out = String::new();
+    for (name, kind, help, value) in [
+        (
+            "ccswitch_requests_total",
+            "counter",
+            "Requests forwarded by the proxy",
+            status.total_requests as f64,
+        ),
+        (
+            "ccswitch_requests_failed_total",
+            "counter",
+            "Requests that failed on every provider",
+            status.failed_requests as f64,
+        ),
+        (
+            "ccswitch_failovers_total",
+            "counter",
+            "Provider failovers",
+            status.failover_count as f64,
+        ),
+        (
+            "ccswitch_output_tokens_per_second",
+            "gauge",
+            "Output tokens per second over the sliding window",
+            status.tps,
+        ),
+    ] {
+        header(&mut out, name, kind, help);
+        let _ = writeln!(out, "{name} {value}");
+    }
+
+    const PER_PROVIDER: [ProviderMetric; 5] = [
+        (
+            "ccswitch_provider_in_flight",
+            "gauge",
+            "Upstream requests currently in flight",
+            |p| p.in_flight as f64,
+        ),
+        (
+            "ccswitch_provider_peak_concurrency",
+            "gauge",
+            "Peak concurrent upstream requests since start",
+            |p| p.peak as f64,
+        ),
+        (
+            "ccswitch_provider_requests_total",
+            "counter",
+            "Upstream requests sent to the provider",
+            |p| p.total as f64,
+        ),
+        (
+            "ccswitch_provider_queued",
+            "gauge",
+            "Requests waiting for an adaptive concurrency slot",
+            |p| p.queued as f64,
+        ),
+        (
+            "ccswitch_provider_concurrency_limit",
+            "gauge",
+            "Current adaptive concurrency limit (0 when disabled)",
+            |p| p.limit.unwrap_or(0) as f64,
+        ),
+    ];
+    for (name, kind, help, value) in PER_PROVIDER {
+        header(&mut out, name, kind, help);
+        for p in providers {
+            let _ = writeln!(
+                out,
+                "{name}{{app=\"{}\",provider_id=\"{}\",provider=\"{}\"}} {}",
+                escape_label(&p.app_type),
+                escape_label(&p.provider_id),
+                escape_label(&p.provider_name),
+                value(p)
+            );
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_provider_gauges_with_escaped_labels() {
+        let status = ProxyStatus {
+            total_requests: 3,
+            ..Default::default()
+        };
+        let providers = vec![ProviderConcurrency {
+            app_type: "claude".to_string(),
+            provider_id: "p1".to_string(),
+            provider_name: "My \"Relay\"".to_string(),
+            in_flight: 2,
+            peak: 5,
+            total: 9,
+            queued: 1,
+            limit: Some(4),
+            max_limit: Some(8),
+        }];
+
+        let text = render(&status, &providers);
+        assert!(text.contains("ccswitch_requests_total 3\n"));
+        assert!(text.contains("# TYPE ccswitch_provider_in_flight gauge\n"));
+        assert!(text.contains(
+            "ccswitch_provider_concurrency_limit{app=\"claude\",provider_id=\"p1\",provider=\"My \\\"Relay\\\"\"} 4\n"
+        ));
+        assert!(text.contains(
+            "ccswitch_provider_peak_concurrency{app=\"claude\",provider_id=\"p1\",provider=\"My \\\"Relay\\\"\"} 5\n"
+        ));
+    }
+}
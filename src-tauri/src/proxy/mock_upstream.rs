@@ -0,0 +1,616 @@
+//! 内置模拟上游
+//!
+//! 在代理监听地址的 `/mock` 下提供 Anthropic / OpenAI（Chat 与 Responses）/ Gemini 格式的模拟接口，
+//! 流式响应按设置的首字延迟与输出速度逐词返回，也可按比例返回错误。
+//! 可直接作为供应商的 base_url，无需消耗 token 即可体验路由、健康检查与 TPS 监控。
+//!
+//! 默认关闭，关闭时所有路由返回 404。
+
+use std::convert::Infallible;
+use std::time::Duration;
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::server::ProxyState;
+use crate::app_config::AppType;
+
+/// 模拟回复的词（按顺序循环输出）
+const WORDS: &[&str] = &[
+    "Hello",
+    " from",
+    " the",
+    " CC",
+    " Switch",
+    " mock",
+    " upstream",
+    ".",
+];
+/// 单次回复的输出上限
+const MAX_OUTPUT_TOKENS: u32 = 4096;
+
+/// 模拟上游设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MockUpstreamConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 首字节前的等待（毫秒）
+    #[serde(default = "default_latency_ms")]
+    pub latency_ms: u64,
+    /// 流式输出速度（每秒 token 数，0 表示一次性输出）
+    #[serde(default = "default_tokens_per_second")]
+    pub tokens_per_second: u32,
+    /// 每次回复的输出 token 数（不超过请求的 max_tokens）
+    #[serde(default = "default_output_tokens")]
+    pub output_tokens: u32,
+    /// 返回错误的比例（0~100）
+    #[serde(default)]
+    pub error_percent: u8,
+    /// 错误响应的状态码
+    #[serde(default = "default_error_status")]
+    pub error_status: u16,
+}
+
+fn default_latency_ms() -> u64 {
+    300
+}
+
+fn default_tokens_per_second() -> u32 {
+    40
+}
+
+fn default_output_tokens() -> u32 {
+    32
+}
+
+fn default_error_status() -> u16 {
+    529
+}
+
+impl Default for MockUpstreamConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            latency_ms: default_latency_ms(),
+            tokens_per_second: default_tokens_per_second(),
+            output_tokens: default_output_tokens(),
+            error_percent: 0,
+            error_status: default_error_status(),
+        }
+    }
+}
+
+/// 作为供应商使用的 base_url
+pub fn base_url(app_type: &AppType, listen_address: &str, listen_port: u16) -> String {
+    let host = match listen_address {
+        "0.0.0.0" | "::" => "127.0.0.1",
+        other => other,
+    };
+    match app_type {
+        AppType::Codex => format!("http://{host}:{listen_port}/mock/v1"),
+        AppType::Claude | AppType::Gemini => format!("http://{host}:{listen_port}/mock"),
+    }
+}
+
+/// 指向模拟上游的供应商配置
+pub fn provider_settings(app_type: &AppType, base_url: &str) -> Value {
+    match app_type {
+        AppType::Claude => json!({
+            "env": {
+                "ANTHROPIC_BASE_URL": base_url,
+                "ANTHROPIC_AUTH_TOKEN": "mock-key",
+                "ANTHROPIC_MODEL": "mock-claude",
+            }
+        }),
+        AppType::Codex => json!({
+            "auth": { "OPENAI_API_KEY": "mock-key" },
+            "config": format!(
+                r#"model_provider = "mock"
+model = "mock-gpt"
+
+[model_providers.mock]
+name = "Mock"
+base_url = "{base_url}"
+wire_api = "responses"
+requires_openai_auth = true"#
+            ),
+        }),
+        AppType::Gemini => json!({
+            "env": {
+                "GOOGLE_GEMINI_BASE_URL": base_url,
+                "GEMINI_API_KEY": "mock-key",
+                "GEMINI_MODEL": "mock-gemini",
+            }
+        }),
+    }
+}
+
+pub fn router() -> Router<ProxyState> {
+    Router::new()
+        .route("/mock/v1/models", get(list_models))
+        .route("/mock/v1/messages", post(anthropic_messages))
+        .route("/mock/v1/chat/completions", post(chat_completions))
+        .route("/mock/v1/responses", post(responses))
+        .route("/mock/v1beta/models/*path", post(gemini_generate))
+}
+
+/// 已确定的回复
+struct Reply {
+    model: String,
+    input_tokens: u32,
+    words: Vec<&'static str>,
+    /// 每个词之间的间隔
+    interval: Duration,
+}
+
+impl Reply {
+    fn text(&self) -> String {
+        self.words.concat()
+    }
+
+    fn output_tokens(&self) -> u32 {
+        self.words.len() as u32
+    }
+}
+
+enum Plan {
+    Disabled,
+    Error(u16),
+    Reply(Reply),
+}
+
+/// 按设置决定本次请求的结果，并等待首字延迟
+async fn plan(body: &Value, requested_max: Option<u64>, default_model: &str) -> Plan {
+    let config = crate::settings::get_settings().mock_upstream;
+    if !config.enabled {
+        return Plan::Disabled;
+    }
+    if config.latency_ms > 0 {
+        tokio::time::sleep(Duration::from_millis(config.latency_ms)).await;
+    }
+    if config.error_percent > 0
+        && (config.error_percent >= 100
+            || (uuid::Uuid::new_v4().as_u128() % 100) < config.error_percent as u128)
+    {
+        return Plan::Error(config.error_status);
+    }
+
+    let count = requested_max
+        .map(|max| max.min(config.output_tokens as u64) as u32)
+        .unwrap_or(config.output_tokens)
+        .clamp(1, MAX_OUTPUT_TOKENS);
+    Plan::Reply(Reply {
+        model: body
+            .get("model")
+            .and_then(Value::as_str)
+            .unwrap_or(default_model)
+            .to_string(),
+        // 粗略估算：每 4 个字节约 1 个 token
+        input_tokens: (body.to_string().len() / 4).max(1) as u32,
+        words: WORDS.iter().copied().cycle().take(count as usize).collect(),
+        interval: match config.tokens_per_second {
+            0 => Duration::ZERO,
+            tps => Duration::from_secs_f64(1.0 / tps as f64),
+        },
+    })
+}
+
+fn disabled() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": "模拟上游未开启" })),
+    )
+        .into_response()
+}
+
+fn error_response(status: u16, body: Value) -> Response {
+    let status = StatusCode::from_u16(status).unwrap_or(StatusCode::SERVICE_UNAVAILABLE);
+    (status, Json(body)).into_response()
+}
+
+/// SSE 响应：`head` 立即发送，`words` 中的帧按间隔发送，`tail` 在最后发送
+fn sse(head: Vec<String>, words: Vec<String>, tail: Vec<String>, interval: Duration) -> Response {
+    let frames = head
+        .into_iter()
+        .map(|f| (Duration::ZERO, f))
+        .chain(words.into_iter().map(move |f| (interval, f)))
+        .chain(tail.into_iter().map(|f| (Duration::ZERO, f)));
+    let stream = futures::stream::iter(frames).then(|(delay, frame)| async move {
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        Ok::<_, Infallible>(Bytes::from(frame))
+    });
+    (
+        [
+            (header::CONTENT_TYPE, "text/event-stream"),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        Body::from_stream(stream),
+    )
+        .into_response()
+}
+
+fn event(name: &str, data: &Value) -> String {
+    format!("event: {name}\ndata: {data}\n\n")
+}
+
+fn data(data: &Value) -> String {
+    format!("data: {data}\n\n")
+}
+
+fn is_stream(body: &Value) -> bool {
+    body.get("stream").and_then(Value::as_bool).unwrap_or(false)
+}
+
+fn max_tokens(body: &Value, key: &str) -> Option<u64> {
+    body.get(key).and_then(Value::as_u64)
+}
+
+fn mock_id(prefix: &str) -> String {
+    format!("{prefix}_mock_{}", uuid::Uuid::new_v4().simple())
+}
+
+/// GET /mock/v1/models
+async fn list_models() -> Response {
+    if !crate::settings::get_settings().mock_upstream.enabled {
+        return disabled();
+    }
+    let data: Vec<Value> = ["mock-claude", "mock-gpt", "mock-gemini"]
+        .iter()
+        .map(|id| json!({ "id": id, "object": "model", "owned_by": "cc-switch" }))
+        .collect();
+    Json(json!({ "object": "list", "data": data })).into_response()
+}
+
+/// POST /mock/v1/messages（Anthropic Messages）
+async fn anthropic_messages(Json(body): Json<Value>) -> Response {
+    let reply = match plan(&body, max_tokens(&body, "max_tokens"), "mock-claude").await {
+        Plan::Disabled => return disabled(),
+        Plan::Error(status) => {
+            return error_response(
+                status,
+                json!({
+                    "type": "error",
+                    "error": { "type": "overloaded_error", "message": "Mock upstream error" }
+                }),
+            )
+        }
+        Plan::Reply(reply) => reply,
+    };
+    let id = mock_id("msg");
+
+    if !is_stream(&body) {
+        return Json(json!({
+            "id": id,
+            "type": "message",
+            "role": "assistant",
+            "model": reply.model,
+            "content": [{ "type": "text", "text": reply.text() }],
+            "stop_reason": "end_turn",
+            "stop_sequence": null,
+            "usage": {
+                "input_tokens": reply.input_tokens,
+                "output_tokens": reply.output_tokens()
+            }
+        }))
+        .into_response();
+    }
+
+    let head = vec![
+        event(
+            "message_start",
+            &json!({
+                "type": "message_start",
+                "message": {
+                    "id": id,
+                    "type": "message",
+                    "role": "assistant",
+                    "model": reply.model,
+                    "content": [],
+                    "stop_reason": null,
+                    "stop_sequence": null,
+                    "usage": { "input_tokens": reply.input_tokens, "output_tokens": 0 }
+                }
+            }),
+        ),
+        event(
+            "content_block_start",
+            &json!({
+                "type": "content_block_start",
+                "index": 0,
+                "content_block": { "type": "text", "text": "" }
+            }),
+        ),
+    ];
+    let words = reply
+        .words
+        .iter()
+        .map(|word| {
+            event(
+                "content_block_delta",
+                &json!({
+                    "type": "content_block_delta",
+                    "index": 0,
+                    "delta": { "type": "text_delta", "text": word }
+                }),
+            )
+        })
+        .collect();
+    let tail = vec![
+        event(
+            "content_block_stop",
+            &json!({ "type": "content_block_stop", "index": 0 }),
+        ),
+        event(
+            "message_delta",
+            &json!({
+                "type": "message_delta",
+                "delta": { "stop_reason": "end_turn", "stop_sequence": null },
+                "usage": {
+                    "input_tokens": reply.input_tokens,
+                    "output_tokens": reply.output_tokens()
+                }
+            }),
+        ),
+        event("message_stop", &json!({ "type": "message_stop" })),
+    ];
+    sse(head, words, tail, reply.interval)
+}
+
+fn openai_error(status: u16) -> Response {
+    error_response(
+        status,
+        json!({ "error": { "message": "Mock upstream error", "type": "server_error" } }),
+    )
+}
+
+/// POST /mock/v1/chat/completions（OpenAI Chat Completions）
+async fn chat_completions(Json(body): Json<Value>) -> Response {
+    let requested = max_tokens(&body, "max_tokens").or(max_tokens(&body, "max_completion_tokens"));
+    let reply = match plan(&body, requested, "mock-gpt").await {
+        Plan::Disabled => return disabled(),
+        Plan::Error(status) => return openai_error(status),
+        Plan::Reply(reply) => reply,
+    };
+    let id = mock_id("chatcmpl");
+    let created = chrono::Utc::now().timestamp();
+    let usage = json!({
+        "prompt_tokens": reply.input_tokens,
+        "completion_tokens": reply.output_tokens(),
+        "total_tokens": reply.input_tokens + reply.output_tokens()
+    });
+
+    if !is_stream(&body) {
+        return Json(json!({
+            "id": id,
+            "object": "chat.completion",
+            "created": created,
+            "model": reply.model,
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": reply.text() },
+                "finish_reason": "stop"
+            }],
+            "usage": usage
+        }))
+        .into_response();
+    }
+
+    let chunk = |delta: Value, finish_reason: Value| {
+        json!({
+            "id": id,
+            "object": "chat.completion.chunk",
+            "created": created,
+            "model": reply.model,
+            "choices": [{ "index": 0, "delta": delta, "finish_reason": finish_reason }]
+        })
+    };
+    let head = vec![data(&chunk(
+        json!({ "role": "assistant", "content": "" }),
+        Value::Null,
+    ))];
+    let words = reply
+        .words
+        .iter()
+        .map(|word| data(&chunk(json!({ "content": word }), Value::Null)))
+        .collect();
+    let mut last = chunk(json!({}), json!("stop"));
+    last["usage"] = usage;
+    let tail = vec![data(&last), "data: [DONE]\n\n".to_string()];
+    sse(head, words, tail, reply.interval)
+}
+
+/// POST /mock/v1/responses（OpenAI Responses）
+async fn responses(Json(body): Json<Value>) -> Response {
+    let reply = match plan(&body, max_tokens(&body, "max_output_tokens"), "mock-gpt").await {
+        Plan::Disabled => return disabled(),
+        Plan::Error(status) => return openai_error(status),
+        Plan::Reply(reply) => reply,
+    };
+    let id = mock_id("resp");
+    let completed = json!({
+        "id": id,
+        "object": "response",
+        "created_at": chrono::Utc::now().timestamp(),
+        "model": reply.model,
+        "status": "completed",
+        "output": [{
+            "id": mock_id("msg"),
+            "type": "message",
+            "role": "assistant",
+            "status": "completed",
+            "content": [{ "type": "output_text", "text": reply.text(), "annotations": [] }]
+        }],
+        "usage": {
+            "input_tokens": reply.input_tokens,
+            "output_tokens": reply.output_tokens(),
+            "total_tokens": reply.input_tokens + reply.output_tokens()
+        }
+    });
+
+    if !is_stream(&body) {
+        return Json(completed).into_response();
+    }
+
+    let mut created = completed.clone();
+    created["status"] = json!("in_progress");
+    created["output"] = json!([]);
+    created["usage"] = Value::Null;
+    let head = vec![event(
+        "response.created",
+        &json!({ "type": "response.created", "response": created }),
+    )];
+    let words = reply
+        .words
+        .iter()
+        .map(|word| {
+            event(
+                "response.output_text.delta",
+                &json!({
+                    "type": "response.output_text.delta",
+                    "output_index": 0,
+                    "content_index": 0,
+                    "delta": word
+                }),
+            )
+        })
+        .collect();
+    let tail = vec![event(
+        "response.completed",
+        &json!({ "type": "response.completed", "response": completed }),
+    )];
+    sse(head, words, tail, reply.interval)
+}
+
+/// POST /mock/v1beta/models/{model}:generateContent | :streamGenerateContent（Gemini）
+async fn gemini_generate(
+    Path(path): Path<String>,
+    Query(query): Query<std::collections::HashMap<String, String>>,
+    Json(body): Json<Value>,
+) -> Response {
+    let (model, method) = path.split_once(':').unwrap_or((path.as_str(), ""));
+    let requested = body
+        .pointer("/generationConfig/maxOutputTokens")
+        .and_then(Value::as_u64);
+    let mut plan_body = body.clone();
+    plan_body["model"] = json!(model);
+    let reply = match plan(&plan_body, requested, "mock-gemini").await {
+        Plan::Disabled => return disabled(),
+        Plan::Error(status) => {
+            return error_response(
+                status,
+                json!({
+                    "error": {
+                        "code": status,
+                        "message": "Mock upstream error",
+                        "status": "UNAVAILABLE"
+                    }
+                }),
+            )
+        }
+        Plan::Reply(reply) => reply,
+    };
+    let usage = json!({
+        "promptTokenCount": reply.input_tokens,
+        "candidatesTokenCount": reply.output_tokens(),
+        "totalTokenCount": reply.input_tokens + reply.output_tokens()
+    });
+    let candidate = |text: &str, finish: Option<&str>| {
+        let mut candidate = json!({
+            "content": { "role": "model", "parts": [{ "text": text }] },
+            "index": 0
+        });
+        if let Some(finish) = finish {
+            candidate["finishReason"] = json!(finish);
+        }
+        candidate
+    };
+
+    if method != "streamGenerateContent" {
+        return Json(json!({
+            "candidates": [candidate(&reply.text(), Some("STOP"))],
+            "usageMetadata": usage,
+            "modelVersion": reply.model
+        }))
+        .into_response();
+    }
+    if query.get("alt").map(String::as_str) != Some("sse") {
+        // 非 SSE 的流式接口返回 JSON 数组
+        let chunks: Vec<Value> = reply
+            .words
+            .iter()
+            .map(|word| json!({ "candidates": [candidate(word, None)] }))
+            .chain(std::iter::once(json!({
+                "candidates": [candidate("", Some("STOP"))],
+                "usageMetadata": usage
+            })))
+            .collect();
+        return Json(Value::Array(chunks)).into_response();
+    }
+
+    let words = reply
+        .words
+        .iter()
+        .map(|word| data(&json!({ "candidates": [candidate(word, None)] })))
+        .collect();
+    let tail = vec![data(&json!({
+        "candidates": [candidate("", Some("STOP"))],
+        "usageMetadata": usage,
+        "modelVersion": reply.model
+    }))];
+    sse(Vec::new(), words, tail, reply.interval)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_url_matches_app_endpoints() {
+        assert_eq!(
+            base_url(&AppType::Codex, "0.0.0.0", 15721),
+            "http://127.0.0.1:15721/mock/v1"
+        );
+        assert_eq!(
+            base_url(&AppType::Claude, "127.0.0.1", 15721),
+            "http://127.0.0.1:15721/mock"
+        );
+    }
+
+    #[test]
+    fn provider_settings_point_at_mock() {
+        let url = base_url(&AppType::Codex, "127.0.0.1", 15721);
+        let settings = provider_settings(&AppType::Codex, &url);
+        assert!(settings["config"]
+            .as_str()
+            .unwrap()
+            .contains("base_url = \"http://127.0.0.1:15721/mock/v1\""));
+        let settings = provider_settings(&AppType::Gemini, "http://127.0.0.1:1/mock");
+        assert_eq!(settings["env"]["GEMINI_MODEL"], "mock-gemini");
+    }
+
+    #[tokio::test]
+    async fn sse_emits_frames_in_order() {
+        let response = sse(
+            vec!["a".to_string()],
+            vec!["b".to_string(), "c".to_string()],
+            vec!["d".to_string()],
+            Duration::from_millis(1),
+        );
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&bytes[..], b"abcd");
+    }
+}
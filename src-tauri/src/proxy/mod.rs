@@ -2,26 +2,64 @@
 //!
 //! 提供本地HTTP代理服务，支持多Provider故障转移和请求透传
 
+pub mod api_auth;
+pub mod auth_scheme;
 pub mod body_filter;
 pub mod circuit_breaker;
+pub mod client_identity;
+pub mod client_pool;
+pub mod concurrency;
+pub mod context_guard;
 pub mod custom_headers;
+pub mod endpoint_template;
 pub mod error;
 pub mod error_mapper;
 pub(crate) mod failover_switch;
-mod forwarder;
+pub mod fault_injection;
+pub(crate) mod forwarder;
 pub mod handler_config;
 pub mod handler_context;
 mod handlers;
 mod health;
+pub mod inline_cost;
+pub mod ip_preference;
+pub(crate) mod keep_warm;
+pub mod local_socket;
+pub mod log_scrubber;
+pub mod management_api;
+pub mod mdns;
+pub mod metrics;
+pub mod mock_upstream;
+pub mod model_audit;
 pub mod model_mapper;
+pub mod offline;
+pub mod outbound_redaction;
+pub mod output_limit;
+pub mod output_validation;
+pub mod overhead;
+pub mod param_policy;
 pub mod provider_router;
+pub mod provider_tls;
 pub mod providers;
+pub mod request_dedup;
+pub mod request_journal;
+pub mod request_tag;
 pub mod response_handler;
+pub mod response_headers;
 pub mod response_processor;
 pub(crate) mod server;
 pub mod session;
+pub mod session_override;
+pub mod shadow;
+pub mod sse_coalesce;
+pub mod sse_sanitize;
 pub(crate) mod tps_monitor;
+pub(crate) mod tps_sampler;
+pub mod trace;
+pub mod traffic;
+pub mod transcript;
 pub(crate) mod types;
+pub mod upstream_hint;
 pub mod usage;
 
 // 公开导出给外部使用（commands, services等模块需要）
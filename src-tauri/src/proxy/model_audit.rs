@@ -0,0 +1,519 @@
+//! 中转站模型替换检测
+//!
+//! 代理收到成功响应后，把响应元数据与本次请求应得的模型（请求模型经供应商模型映射后）对照：
+//! - `model_mismatch` / `tier_downgrade`：响应报告的模型与预期不符（后者为降到更低档位，如 opus → sonnet）
+//! - `foreign_stop_reason`：结束原因不属于该协议的取值（如 Claude 接口返回 OpenAI 的 `stop`），
+//!   说明上游实际由其他厂商的模型转接
+//! - `speed_outlier`：流式输出速度远高于其他供应商同一模型的历史平均，疑似换成了更小的模型
+//!
+//! 每条线索连同证据写入数据库，同一供应商同类线索有冷却时间；
+//! 近期线索达到阈值时把供应商标记为“疑似替换”并发送事件。
+//! 需要格式转换的供应商（本地模型、OpenRouter 兼容模式）本就使用其他模型，不做检测。
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+use serde_json::Value;
+
+use super::model_mapper::ModelMapping;
+use super::providers::get_adapter;
+use super::usage::logger::streaming_output_tps;
+use crate::app_config::AppType;
+use crate::database::{Database, SubstitutionEvidence};
+use crate::events::{self, AppEvent};
+use crate::provider::Provider;
+
+/// 同一供应商同类线索的记录间隔（秒）
+const EVIDENCE_COOLDOWN_SECS: i64 = 300;
+/// 统计“疑似替换”的时间窗口（秒）
+pub const SUSPECT_WINDOW_SECS: i64 = 7 * 24 * 3600;
+/// 窗口内达到该线索数即标记为疑似替换
+pub const SUSPECT_THRESHOLD: u64 = 3;
+/// 参与速度比较的最少输出 token 数（过短的回复速度不稳定）
+const MIN_SPEED_TOKENS: u64 = 200;
+/// 速度基线需要的最少样本数
+const MIN_BASELINE_SAMPLES: u64 = 20;
+/// 超过基线多少倍视为异常
+const SPEED_OUTLIER_RATIO: f64 = 2.5;
+
+static LAST_RECORDED: Lazy<Mutex<HashMap<(String, &'static str), i64>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+const CLAUDE_STOP_REASONS: &[&str] = &[
+    "end_turn",
+    "max_tokens",
+    "stop_sequence",
+    "tool_use",
+    "pause_turn",
+    "refusal",
+    "model_context_window_exceeded",
+];
+
+/// 是否开启检测
+pub fn enabled() -> bool {
+    crate::settings::get_settings().model_substitution_check
+}
+
+/// 从响应中提取的元数据
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResponseFacts {
+    pub reported_model: Option<String>,
+    pub stop_reason: Option<String>,
+    pub output_tokens: u64,
+    /// 流式输出速度（token/秒，仅流式响应）
+    pub output_tps: Option<f64>,
+}
+
+impl ResponseFacts {
+    /// 非流式响应
+    pub fn from_response(json: &Value, output_tokens: u64) -> Self {
+        Self {
+            reported_model: model_of(json),
+            stop_reason: stop_reason_of(json),
+            output_tokens,
+            output_tps: None,
+        }
+    }
+
+    /// 流式响应（模型取第一个出现的值，结束原因取最后一个）
+    pub fn from_events(
+        events: &[Value],
+        output_tokens: u64,
+        latency_ms: u64,
+        first_token_ms: Option<u64>,
+    ) -> Self {
+        let reported_model = events.iter().find_map(|e| {
+            e.pointer("/message/model")
+                .or_else(|| e.pointer("/response/model"))
+                .and_then(|m| m.as_str())
+                .map(str::to_string)
+                .or_else(|| model_of(e))
+        });
+        let stop_reason = events.iter().rev().find_map(|e| {
+            e.pointer("/delta/stop_reason")
+                .and_then(|s| s.as_str())
+                .map(str::to_string)
+                .or_else(|| stop_reason_of(e))
+        });
+        Self {
+            reported_model,
+            stop_reason,
+            output_tokens,
+            output_tps: streaming_output_tps(
+                output_tokens.min(u32::MAX as u64) as u32,
+                latency_ms,
+                first_token_ms,
+            ),
+        }
+    }
+}
+
+fn model_of(json: &Value) -> Option<String> {
+    ["model", "modelVersion"]
+        .iter()
+        .find_map(|key| json.get(*key).and_then(|m| m.as_str()))
+        .filter(|m| !m.is_empty())
+        .map(str::to_string)
+}
+
+fn stop_reason_of(json: &Value) -> Option<String> {
+    json.get("stop_reason")
+        .or_else(|| json.pointer("/choices/0/finish_reason"))
+        .or_else(|| json.pointer("/candidates/0/finishReason"))
+        .and_then(|s| s.as_str())
+        .map(str::to_string)
+}
+
+/// 本次请求的预期（请求开始处理响应时创建）
+#[derive(Debug, Clone)]
+pub struct Expectation {
+    app_type: AppType,
+    app_type_str: &'static str,
+    provider_id: String,
+    requested_model: String,
+    /// 可接受的模型（thinking 与非 thinking 请求可能映射到不同模型）
+    expected: Vec<String>,
+}
+
+impl Expectation {
+    /// 未开启检测、模型未知或供应商需要格式转换时返回 None
+    pub fn for_request(
+        app_type: &AppType,
+        app_type_str: &'static str,
+        provider: &Provider,
+        request_model: &str,
+    ) -> Option<Self> {
+        if request_model.is_empty() || request_model == "unknown" || !enabled() {
+            return None;
+        }
+        if get_adapter(app_type).needs_transform(provider) {
+            return None;
+        }
+        let mapping = ModelMapping::from_provider(provider);
+        let mut expected = vec![mapping.map_model(request_model, false)];
+        let thinking = mapping.map_model(request_model, true);
+        if !expected.contains(&thinking) {
+            expected.push(thinking);
+        }
+        Some(Self {
+            app_type: app_type.clone(),
+            app_type_str,
+            provider_id: provider.id.clone(),
+            requested_model: request_model.to_string(),
+            expected,
+        })
+    }
+
+    pub fn expected_model(&self) -> &str {
+        &self.expected[0]
+    }
+
+    /// 检查成功响应，发现线索时在后台写入证据
+    pub fn check(&self, db: Arc<Database>, facts: ResponseFacts) {
+        let quick = self.inspect(&facts);
+        let needs_speed = facts.output_tps.is_some() && facts.output_tokens >= MIN_SPEED_TOKENS;
+        if quick.is_empty() && !needs_speed {
+            return;
+        }
+        let expectation = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut findings = quick;
+            if let Some(tps) = facts.output_tps.filter(|_| needs_speed) {
+                findings.extend(expectation.check_speed(&db, tps));
+            }
+            for (signal, detail) in findings {
+                expectation.record(&db, signal, detail, facts.reported_model.clone());
+            }
+        });
+    }
+
+    /// 不依赖历史数据的检查
+    fn inspect(&self, facts: &ResponseFacts) -> Vec<(&'static str, String)> {
+        let mut findings = Vec::new();
+
+        if let Some(reported) = &facts.reported_model {
+            if !self.expected.iter().any(|m| same_model(m, reported)) {
+                let expected = self.expected_model();
+                let signal = match (tier(expected), tier(reported)) {
+                    (Some(want), Some(got))
+                        if family(expected) == family(reported) && got < want =>
+                    {
+                        "tier_downgrade"
+                    }
+                    _ => "model_mismatch",
+                };
+                findings.push((signal, format!("预期模型 {expected}，响应报告 {reported}")));
+            }
+        }
+
+        if let Some(stop) = &facts.stop_reason {
+            if is_foreign_stop_reason(&self.app_type, stop) {
+                findings.push((
+                    "foreign_stop_reason",
+                    format!("结束原因 {stop} 不属于 {} 协议", self.app_type_str),
+                ));
+            }
+        }
+
+        findings
+    }
+
+    fn check_speed(&self, db: &Database, tps: f64) -> Option<(&'static str, String)> {
+        let since = chrono::Utc::now().timestamp() - SUSPECT_WINDOW_SECS;
+        let (baseline, samples) = match db.get_model_tps_baseline(
+            self.app_type_str,
+            self.expected_model(),
+            &self.provider_id,
+            MIN_SPEED_TOKENS,
+            since,
+        ) {
+            Ok(Some(baseline)) => baseline,
+            Ok(None) => return None,
+            Err(e) => {
+                log::debug!("[ModelAudit] 读取速度基线失败: {e}");
+                return None;
+            }
+        };
+        if samples < MIN_BASELINE_SAMPLES || baseline <= 0.0 || tps < baseline * SPEED_OUTLIER_RATIO
+        {
+            return None;
+        }
+        Some((
+            "speed_outlier",
+            format!(
+                "输出速度 {tps:.0} token/s，其他供应商同模型平均 {baseline:.0} token/s（{samples} 个样本）"
+            ),
+        ))
+    }
+
+    fn record(
+        &self,
+        db: &Database,
+        signal: &'static str,
+        detail: String,
+        reported: Option<String>,
+    ) {
+        let now = chrono::Utc::now().timestamp();
+        {
+            let mut last = LAST_RECORDED.lock().unwrap_or_else(|e| e.into_inner());
+            let key = (
+                format!("{}:{}", self.app_type_str, self.provider_id),
+                signal,
+            );
+            if last
+                .get(&key)
+                .is_some_and(|t| now - t < EVIDENCE_COOLDOWN_SECS)
+            {
+                return;
+            }
+            last.insert(key, now);
+        }
+
+        log::warn!(
+            "[ModelAudit] 供应商 {} 疑似替换模型（{signal}）: {detail}",
+            self.provider_id
+        );
+        let since = now - SUSPECT_WINDOW_SECS;
+        let was_suspected = self.is_suspected(db, since);
+        let evidence = SubstitutionEvidence {
+            id: 0,
+            app_type: self.app_type_str.to_string(),
+            provider_id: self.provider_id.clone(),
+            signal: signal.to_string(),
+            requested_model: self.requested_model.clone(),
+            expected_model: self.expected_model().to_string(),
+            reported_model: reported,
+            detail,
+            created_at: now,
+        };
+        if let Err(e) = db.insert_substitution_evidence(&evidence) {
+            log::warn!("[ModelAudit] 保存检测证据失败: {e}");
+            return;
+        }
+        if was_suspected {
+            return;
+        }
+        if let Ok(Some(suspect)) =
+            db.get_substitution_suspect(self.app_type_str, &self.provider_id, since)
+        {
+            if suspect.suspected {
+                events::publish(AppEvent::ModelSubstitutionSuspected(suspect));
+            }
+        }
+    }
+
+    fn is_suspected(&self, db: &Database, since: i64) -> bool {
+        matches!(
+            db.get_substitution_suspect(self.app_type_str, &self.provider_id, since),
+            Ok(Some(s)) if s.suspected
+        )
+    }
+}
+
+/// 归一化模型名：忽略大小写、厂商前缀、`-latest`、日期后缀与 `[1m]` 之类的标注，`.` 视同 `-`
+fn normalize(model: &str) -> String {
+    let mut name = model.trim().to_lowercase();
+    if let Some(pos) = name.rfind('/') {
+        name = name[pos + 1..].to_string();
+    }
+    if let Some(pos) = name.find('[') {
+        name.truncate(pos);
+    }
+    name = name.replace(['.', '@'], "-");
+    if let Some(stripped) = name.strip_suffix("-latest") {
+        name = stripped.to_string();
+    }
+    // 日期后缀：-20250514 或 -2025-05-14
+    let parts: Vec<&str> = name.split('-').collect();
+    let keep = match parts.as_slice() {
+        [head @ .., y, m, d]
+            if y.len() == 4
+                && m.len() == 2
+                && d.len() == 2
+                && is_digits(y)
+                && is_digits(m)
+                && is_digits(d) =>
+        {
+            head.len()
+        }
+        [head @ .., date] if date.len() == 8 && is_digits(date) => head.len(),
+        _ => parts.len(),
+    };
+    parts[..keep.max(1)].join("-")
+}
+
+fn is_digits(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// 同一模型（允许一方是另一方的带版本后缀形式，如 gemini-2.5-pro 与 gemini-2.5-pro-preview，
+/// 但后缀不能改变档位，gpt-5 与 gpt-5-mini 不算同一模型）
+fn same_model(expected: &str, reported: &str) -> bool {
+    let (a, b) = (normalize(expected), normalize(reported));
+    a == b
+        || ((b.starts_with(&format!("{a}-")) || a.starts_with(&format!("{b}-")))
+            && tier(&a) == tier(&b))
+}
+
+/// 模型系列（claude / gpt / gemini 等，取第一段）
+fn family(model: &str) -> String {
+    let name = normalize(model);
+    let family = name.split('-').next().unwrap_or_default();
+    if family.starts_with('o') && family[1..].bytes().all(|b| b.is_ascii_digit()) {
+        // o3、o4-mini 与 gpt 归为同一系列
+        return "gpt".to_string();
+    }
+    family.to_string()
+}
+
+/// 系列内的档位（越大越强），无法判断时为 None
+fn tier(model: &str) -> Option<u8> {
+    let name = normalize(model);
+    let has = |word: &str| name.split('-').any(|part| part == word);
+    if name.contains("opus") {
+        Some(3)
+    } else if name.contains("sonnet") {
+        Some(2)
+    } else if name.contains("haiku") {
+        Some(1)
+    } else if has("nano") || has("lite") {
+        Some(0)
+    } else if has("mini") || has("flash") {
+        Some(1)
+    } else if has("pro") || name.starts_with("gpt") || family(model) == "gpt" {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+fn is_foreign_stop_reason(app_type: &AppType, stop: &str) -> bool {
+    match app_type {
+        AppType::Claude => !CLAUDE_STOP_REASONS.contains(&stop),
+        // Gemini 的 finishReason 均为大写枚举（STOP、MAX_TOKENS 等）
+        AppType::Gemini => stop.chars().any(|c| c.is_ascii_lowercase()),
+        // OpenAI 兼容中转的取值差异较大，不做判断
+        AppType::Codex => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn expectation(app_type: AppType, expected: &[&str]) -> Expectation {
+        let app_type_str = match app_type {
+            AppType::Claude => "claude",
+            AppType::Codex => "codex",
+            AppType::Gemini => "gemini",
+        };
+        Expectation {
+            app_type,
+            app_type_str,
+            provider_id: "p1".to_string(),
+            requested_model: expected[0].to_string(),
+            expected: expected.iter().map(|m| m.to_string()).collect(),
+        }
+    }
+
+    fn signals(exp: &Expectation, facts: ResponseFacts) -> Vec<&'static str> {
+        exp.inspect(&facts).into_iter().map(|(s, _)| s).collect()
+    }
+
+    #[test]
+    fn normalizes_model_aliases() {
+        assert!(same_model(
+            "claude-sonnet-4-5",
+            "claude-sonnet-4-5-20250929"
+        ));
+        assert!(same_model(
+            "claude-3-5-sonnet-latest",
+            "claude-3-5-sonnet-20241022"
+        ));
+        assert!(same_model(
+            "claude-sonnet-4.5",
+            "anthropic/claude-sonnet-4-5"
+        ));
+        assert!(same_model("claude-opus-4-1[1m]", "claude-opus-4-1"));
+        assert!(same_model("gemini-2.5-pro", "gemini-2.5-pro-preview-06-05"));
+        assert!(!same_model("claude-opus-4-1", "claude-sonnet-4-5"));
+        assert!(!same_model("gpt-5", "gpt-4o-mini"));
+        assert!(!same_model("gpt-5", "gpt-5-mini"));
+        assert!(!same_model("gemini-2.5-flash", "gemini-2.5-flash-lite"));
+    }
+
+    #[test]
+    fn classifies_model_mismatch() {
+        let exp = expectation(AppType::Claude, &["claude-opus-4-1"]);
+        let facts = |model: &str| ResponseFacts {
+            reported_model: Some(model.to_string()),
+            stop_reason: Some("end_turn".to_string()),
+            ..Default::default()
+        };
+        assert!(signals(&exp, facts("claude-opus-4-1-20250805")).is_empty());
+        assert_eq!(
+            signals(&exp, facts("claude-sonnet-4-5")),
+            vec!["tier_downgrade"]
+        );
+        assert_eq!(
+            signals(&exp, facts("deepseek-chat")),
+            vec!["model_mismatch"]
+        );
+
+        let exp = expectation(AppType::Codex, &["gpt-5"]);
+        let facts = ResponseFacts {
+            reported_model: Some("gpt-5-mini".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(signals(&exp, facts), vec!["tier_downgrade"]);
+    }
+
+    #[test]
+    fn accepts_any_mapped_model() {
+        let exp = expectation(AppType::Claude, &["glm-4.6", "glm-4.6-thinking"]);
+        let facts = ResponseFacts {
+            reported_model: Some("glm-4.6-thinking".to_string()),
+            ..Default::default()
+        };
+        assert!(signals(&exp, facts).is_empty());
+    }
+
+    #[test]
+    fn flags_foreign_stop_reason() {
+        let exp = expectation(AppType::Claude, &["claude-sonnet-4-5"]);
+        let json = json!({ "model": "claude-sonnet-4-5", "stop_reason": "stop" });
+        assert_eq!(
+            signals(&exp, ResponseFacts::from_response(&json, 10)),
+            vec!["foreign_stop_reason"]
+        );
+
+        let exp = expectation(AppType::Gemini, &["gemini-2.5-pro"]);
+        let json = json!({
+            "modelVersion": "gemini-2.5-pro",
+            "candidates": [{ "finishReason": "STOP" }]
+        });
+        assert!(signals(&exp, ResponseFacts::from_response(&json, 10)).is_empty());
+    }
+
+    #[test]
+    fn reads_facts_from_stream_events() {
+        let events = vec![
+            json!({ "type": "message_start", "message": { "model": "claude-haiku-4-5" } }),
+            json!({ "type": "message_delta", "delta": { "stop_reason": "end_turn" } }),
+        ];
+        let facts = ResponseFacts::from_events(&events, 500, 2_000, Some(1_000));
+        assert_eq!(facts.reported_model.as_deref(), Some("claude-haiku-4-5"));
+        assert_eq!(facts.stop_reason.as_deref(), Some("end_turn"));
+        assert_eq!(facts.output_tps, Some(500.0));
+
+        let events = vec![
+            json!({ "model": "gpt-5", "choices": [{ "delta": { "content": "hi" } }] }),
+            json!({ "model": "gpt-5", "choices": [{ "finish_reason": "stop" }] }),
+        ];
+        let facts = ResponseFacts::from_events(&events, 0, 100, None);
+        assert_eq!(facts.stop_reason.as_deref(), Some("stop"));
+        assert_eq!(facts.output_tps, None);
+    }
+}
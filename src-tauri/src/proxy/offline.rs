@@ -0,0 +1,177 @@
+//! 离线检测与降级
+//!
+//! 代理运行期间定时探测外网连通性。离线时：
+//! - 暂停空闲保活与健康检查，避免每次都等到超时
+//! - 代理请求只路由到本地供应商（Ollama / llama.cpp）；没有本地供应商时立即返回明确的离线错误
+//!
+//! 单个探测地址可能被防火墙拦截，任意一个地址可连通即视为在线。
+//! 探测与真实流量共用同一客户端（遵循系统代理设置）；只有全部地址都连接失败才判定为离线，
+//! 超时等其他失败无法说明网络状况，视为未知并保持原状态。
+
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::time::Duration;
+
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+
+use crate::provider::Provider;
+use crate::proxy::providers::LocalModelConfig;
+
+/// 在线时的探测间隔（秒）
+pub const CHECK_INTERVAL_SECS: u64 = 30;
+
+/// 离线时的探测间隔（秒），尽快发现网络恢复
+pub const OFFLINE_CHECK_INTERVAL_SECS: u64 = 10;
+
+/// 单次探测超时（秒）
+const PROBE_TIMEOUT_SECS: u64 = 3;
+
+/// 探测地址（任意 HTTP 响应即视为在线，不关心状态码）
+const PROBE_URLS: &[&str] = &[
+    "http://connectivitycheck.gstatic.com/generate_204",
+    "http://www.msftconnecttest.com/connecttest.txt",
+    "http://connect.rom.miui.com/generate_204",
+];
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+/// 最近一次状态变化的时间戳（秒，0 表示从未变化）
+static CHANGED_AT: AtomicI64 = AtomicI64::new(0);
+
+/// 网络状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkStatus {
+    pub offline: bool,
+    pub changed_at: Option<i64>,
+}
+
+/// 当前是否离线（未探测过时视为在线）
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+pub fn status() -> NetworkStatus {
+    let changed_at = CHANGED_AT.load(Ordering::Relaxed);
+    NetworkStatus {
+        offline: is_offline(),
+        changed_at: (changed_at > 0).then_some(changed_at),
+    }
+}
+
+/// 单次探测结论
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeOutcome {
+    Online,
+    Offline,
+    /// 探测失败但无法判断网络状况（超时、代理异常等）
+    Unknown,
+}
+
+/// 汇总各地址的探测结果：任意地址有响应即在线，全部连接失败才离线
+fn conclude(results: &[Result<(), bool>]) -> ProbeOutcome {
+    if results.iter().any(Result::is_ok) {
+        ProbeOutcome::Online
+    } else if !results.is_empty() && results.iter().all(|r| *r == Err(true)) {
+        ProbeOutcome::Offline
+    } else {
+        ProbeOutcome::Unknown
+    }
+}
+
+/// 探测外网是否可达（与代理转发共用客户端，遵循同样的系统代理设置）
+pub async fn probe() -> ProbeOutcome {
+    let client = super::client_pool::default_client();
+    let probes = PROBE_URLS.iter().map(|url| {
+        let request = client
+            .head(*url)
+            .timeout(Duration::from_secs(PROBE_TIMEOUT_SECS));
+        async move {
+            // Err(true) 表示连接失败（DNS 解析失败、网络不可达等）
+            request.send().await.map(|_| ()).map_err(|e| e.is_connect())
+        }
+    });
+    conclude(&join_all(probes).await)
+}
+
+/// 重新探测并更新状态，返回状态是否发生变化
+pub async fn refresh() -> bool {
+    let offline = match probe().await {
+        ProbeOutcome::Online => false,
+        ProbeOutcome::Offline => true,
+        ProbeOutcome::Unknown => {
+            log::debug!("[Offline] 探测结果未知，保持当前状态");
+            return false;
+        }
+    };
+    let changed = OFFLINE.swap(offline, Ordering::Relaxed) != offline;
+    if changed {
+        CHANGED_AT.store(chrono::Utc::now().timestamp(), Ordering::Relaxed);
+        if offline {
+            log::warn!("[Offline] 网络不可用，进入离线模式");
+        } else {
+            log::info!("[Offline] 网络已恢复");
+            // 断网前建立的连接多半已失效
+            super::client_pool::recycle();
+        }
+    }
+    changed
+}
+
+/// 当前状态下的下次探测间隔
+pub fn next_interval() -> Duration {
+    Duration::from_secs(if is_offline() {
+        OFFLINE_CHECK_INTERVAL_SECS
+    } else {
+        CHECK_INTERVAL_SECS
+    })
+}
+
+/// 离线时可用的供应商（仅本地供应商，保持原有顺序）
+pub fn local_providers(providers: &[Provider]) -> Vec<Provider> {
+    providers
+        .iter()
+        .filter(|p| LocalModelConfig::of(p).is_some())
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderMeta;
+    use serde_json::json;
+
+    fn provider(id: &str, local: bool) -> Provider {
+        let mut provider = Provider::with_id(id.to_string(), id.to_string(), json!({}), None);
+        if local {
+            provider.meta = Some(ProviderMeta {
+                local: Some(LocalModelConfig::default()),
+                ..ProviderMeta::default()
+            });
+        }
+        provider
+    }
+
+    #[test]
+    fn only_connect_failures_mean_offline() {
+        assert_eq!(conclude(&[Err(true), Ok(())]), ProbeOutcome::Online);
+        assert_eq!(conclude(&[Err(true), Err(true)]), ProbeOutcome::Offline);
+        // 超时或代理错误不能说明网络断开
+        assert_eq!(conclude(&[Err(true), Err(false)]), ProbeOutcome::Unknown);
+        assert_eq!(conclude(&[]), ProbeOutcome::Unknown);
+    }
+
+    #[test]
+    fn local_providers_keeps_only_local_in_order() {
+        let providers = vec![
+            provider("remote", false),
+            provider("ollama", true),
+            provider("llama", true),
+        ];
+        let ids: Vec<String> = local_providers(&providers)
+            .into_iter()
+            .map(|p| p.id)
+            .collect();
+        assert_eq!(ids, ["ollama", "llama"]);
+    }
+}
@@ -0,0 +1,405 @@
+//! 出站内容过滤
+//!
+//! 通过第三方中转站转发时，开启 `outboundRedaction` 后代理会在请求离开本机前
+//! 处理提示词中的敏感内容（内部主机名、客户邮箱、疑似密钥等）：
+//! - mask：替换为占位符（默认 `[REDACTED]`）
+//! - strip：直接删除
+//!
+//! 只处理消息 / 系统提示等正文字段，model、role、tool_use_id、思考签名等结构字段保持不变。
+//! `allowProviders` 中的供应商（如官方直连）不做过滤。替换次数按供应商与规则计数。
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, RwLock};
+
+use once_cell::sync::Lazy;
+use regex::{NoExpand, Regex};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// 内置密钥规则的名称
+const SECRET_RULE: &str = "secret";
+/// 内置邮箱规则的名称
+const EMAIL_RULE: &str = "email";
+
+/// 疑似密钥的内置规则（与日志脱敏的内置规则一致）
+const SECRET_PATTERNS: &[(&str, &str)] = &[
+    (r"sk-[A-Za-z0-9_\-]{16,}", REDACTED),
+    (r"AIza[0-9A-Za-z_\-]{30,}", REDACTED),
+    (r"gh[pousr]_[A-Za-z0-9]{30,}", REDACTED),
+    (r"AKIA[0-9A-Z]{16}", REDACTED),
+    (r"(?i)bearer\s+[A-Za-z0-9._\-]{16,}", "Bearer [REDACTED]"),
+];
+
+const EMAIL_PATTERN: &str = r"[A-Za-z0-9._%+\-]+@[A-Za-z0-9.\-]+\.[A-Za-z]{2,}";
+
+/// 包含正文的顶层字段（Anthropic / OpenAI / Responses / Gemini）
+const TEXT_ROOTS: &[&str] = &[
+    "messages",
+    "system",
+    "input",
+    "instructions",
+    "prompt",
+    "contents",
+    "systemInstruction",
+];
+
+/// 正文中保持原样的结构字段
+const SKIP_KEYS: &[&str] = &[
+    "type",
+    "role",
+    "id",
+    "name",
+    "tool_use_id",
+    "call_id",
+    "cache_control",
+    "signature",
+    "thinking",
+    "encrypted_content",
+    "data",
+    "media_type",
+    "mime_type",
+    "mimeType",
+    "image_url",
+];
+
+/// 命中规则后的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactionAction {
+    /// 替换为占位符
+    #[default]
+    Mask,
+    /// 直接删除
+    Strip,
+}
+
+/// 自定义过滤规则
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionRule {
+    /// 规则名，用于计数
+    pub name: String,
+    /// 正则表达式
+    pub pattern: String,
+    #[serde(default)]
+    pub action: RedactionAction,
+    /// mask 时的占位符，默认 `[REDACTED]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement: Option<String>,
+}
+
+/// 出站内容过滤设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutboundRedactionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 过滤疑似密钥（API Key、Bearer Token 等）
+    #[serde(default = "default_builtin_secrets")]
+    pub builtin_secrets: bool,
+    /// 过滤邮箱地址（替换为 `[EMAIL]`）
+    #[serde(default)]
+    pub emails: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<RedactionRule>,
+    /// 不做过滤的供应商 ID
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_providers: Vec<String>,
+}
+
+fn default_builtin_secrets() -> bool {
+    true
+}
+
+impl Default for OutboundRedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            builtin_secrets: default_builtin_secrets(),
+            emails: false,
+            rules: Vec::new(),
+            allow_providers: Vec::new(),
+        }
+    }
+}
+
+/// 单个供应商的过滤统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionStats {
+    pub app_type: String,
+    pub provider_id: String,
+    /// 发生过替换的请求数
+    pub requests: u64,
+    /// 替换总次数
+    pub redactions: u64,
+    /// 规则名 -> 替换次数
+    pub by_rule: BTreeMap<String, u64>,
+}
+
+static STATS: Lazy<RwLock<HashMap<(String, String), RedactionStats>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+#[derive(Clone)]
+struct CompiledRule {
+    name: String,
+    re: Regex,
+    replacement: String,
+}
+
+/// 编译规则时使用的设置及编译结果
+type CompiledCache = Option<(OutboundRedactionConfig, Vec<CompiledRule>)>;
+
+/// 已编译的规则（设置变化时重新编译）
+static COMPILED: Lazy<Mutex<CompiledCache>> = Lazy::new(|| Mutex::new(None));
+
+fn compile(config: &OutboundRedactionConfig) -> Vec<CompiledRule> {
+    let mut rules = Vec::new();
+    if config.builtin_secrets {
+        for (pattern, replacement) in SECRET_PATTERNS {
+            if let Ok(re) = Regex::new(pattern) {
+                rules.push(CompiledRule {
+                    name: SECRET_RULE.to_string(),
+                    re,
+                    replacement: replacement.to_string(),
+                });
+            }
+        }
+    }
+    if config.emails {
+        if let Ok(re) = Regex::new(EMAIL_PATTERN) {
+            rules.push(CompiledRule {
+                name: EMAIL_RULE.to_string(),
+                re,
+                replacement: "[EMAIL]".to_string(),
+            });
+        }
+    }
+    for rule in &config.rules {
+        let re = match Regex::new(&rule.pattern) {
+            Ok(re) => re,
+            Err(e) => {
+                log::warn!("忽略无效的出站过滤规则 {:?}: {e}", rule.name);
+                continue;
+            }
+        };
+        let replacement = match rule.action {
+            RedactionAction::Strip => String::new(),
+            RedactionAction::Mask => rule
+                .replacement
+                .clone()
+                .unwrap_or_else(|| REDACTED.to_string()),
+        };
+        rules.push(CompiledRule {
+            name: rule.name.clone(),
+            re,
+            replacement,
+        });
+    }
+    rules
+}
+
+fn compiled_rules(config: &OutboundRedactionConfig) -> Vec<CompiledRule> {
+    let mut cache = COMPILED.lock().unwrap_or_else(|e| e.into_inner());
+    match cache.as_ref() {
+        Some((cached, rules)) if cached == config => rules.clone(),
+        _ => {
+            let rules = compile(config);
+            *cache = Some((config.clone(), rules.clone()));
+            rules
+        }
+    }
+}
+
+/// 按当前设置过滤发往 `provider_id` 的请求体，没有任何替换时返回 None
+pub fn redact_request(app_type: &str, provider_id: &str, body: &Value) -> Option<Value> {
+    let config = crate::settings::get_settings().outbound_redaction;
+    if !config.enabled || config.allow_providers.iter().any(|id| id == provider_id) {
+        return None;
+    }
+    let rules = compiled_rules(&config);
+    let (redacted, counts) = redact_body(body, &rules);
+    if counts.is_empty() {
+        return None;
+    }
+
+    let total: u64 = counts.values().sum();
+    log::info!("[{app_type}] 出站过滤：发往 {provider_id} 的请求替换了 {total} 处内容");
+    let mut stats = STATS.write().unwrap_or_else(|e| e.into_inner());
+    let entry = stats
+        .entry((app_type.to_string(), provider_id.to_string()))
+        .or_insert_with(|| RedactionStats {
+            app_type: app_type.to_string(),
+            provider_id: provider_id.to_string(),
+            ..Default::default()
+        });
+    entry.requests += 1;
+    entry.redactions += total;
+    for (name, count) in counts {
+        *entry.by_rule.entry(name).or_default() += count;
+    }
+    Some(redacted)
+}
+
+/// 过滤请求体的正文字段，返回结果与各规则的替换次数
+fn redact_body(body: &Value, rules: &[CompiledRule]) -> (Value, BTreeMap<String, u64>) {
+    let mut out = body.clone();
+    let mut counts = BTreeMap::new();
+    if rules.is_empty() {
+        return (out, counts);
+    }
+    if let Some(map) = out.as_object_mut() {
+        for root in TEXT_ROOTS {
+            if let Some(value) = map.get_mut(*root) {
+                redact_value(value, rules, &mut counts);
+            }
+        }
+    }
+    (out, counts)
+}
+
+fn redact_value(value: &mut Value, rules: &[CompiledRule], counts: &mut BTreeMap<String, u64>) {
+    match value {
+        Value::String(text) => {
+            for rule in rules {
+                let hits = rule.re.find_iter(text).count() as u64;
+                if hits > 0 {
+                    *text = rule
+                        .re
+                        .replace_all(text, NoExpand(&rule.replacement))
+                        .into_owned();
+                    *counts.entry(rule.name.clone()).or_default() += hits;
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                redact_value(item, rules, counts);
+            }
+        }
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                if !SKIP_KEYS.contains(&key.as_str()) {
+                    redact_value(v, rules, counts);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 各供应商的过滤统计
+pub fn stats() -> Vec<RedactionStats> {
+    let mut stats: Vec<_> = STATS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .cloned()
+        .collect();
+    stats.sort_by(|a, b| {
+        (a.app_type.as_str(), a.provider_id.as_str())
+            .cmp(&(b.app_type.as_str(), b.provider_id.as_str()))
+    });
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config() -> OutboundRedactionConfig {
+        OutboundRedactionConfig {
+            enabled: true,
+            emails: true,
+            rules: vec![
+                RedactionRule {
+                    name: "internal-host".to_string(),
+                    pattern: r"[a-z0-9\-]+\.corp\.example\.net".to_string(),
+                    action: RedactionAction::Mask,
+                    replacement: Some("<host $1>".to_string()),
+                },
+                RedactionRule {
+                    name: "ticket".to_string(),
+                    pattern: r"\s*ACME-\d+".to_string(),
+                    action: RedactionAction::Strip,
+                    replacement: None,
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn masks_and_strips_text_fields_with_counts() {
+        let body = json!({
+            "model": "claude-sonnet-4",
+            "system": "deploy to db-1.corp.example.net",
+            "messages": [{
+                "role": "user",
+                "content": [{
+                    "type": "text",
+                    "text": "key sk-ant-REDACTED from ops@acme.io ACME-42 on api.corp.example.net"
+                }]
+            }]
+        });
+
+        let (out, counts) = redact_body(&body, &compile(&config()));
+        assert_eq!(out["model"], "claude-sonnet-4");
+        assert_eq!(out["system"], "deploy to <host $1>");
+        assert_eq!(
+            out["messages"][0]["content"][0]["text"],
+            "key [REDACTED] from [EMAIL] on <host $1>"
+        );
+        assert_eq!(out["messages"][0]["content"][0]["type"], "text");
+        assert_eq!(counts["secret"], 1);
+        assert_eq!(counts["email"], 1);
+        assert_eq!(counts["internal-host"], 2);
+        assert_eq!(counts["ticket"], 1);
+    }
+
+    #[test]
+    fn leaves_structural_fields_untouched() {
+        let body = json!({
+            "model": "sk-model-name-that-looks-secret",
+            "metadata": { "user_id": "ops@acme.io" },
+            "messages": [{
+                "role": "assistant",
+                "content": [
+                    { "type": "thinking", "thinking": "ops@acme.io", "signature": "sig" },
+                    { "type": "tool_use", "id": "toolu_01", "name": "lookup", "input": { "q": "ops@acme.io" } }
+                ]
+            }]
+        });
+
+        let (out, counts) = redact_body(&body, &compile(&config()));
+        assert_eq!(out["model"], body["model"]);
+        assert_eq!(out["metadata"], body["metadata"]);
+        assert_eq!(
+            out["messages"][0]["content"][0],
+            body["messages"][0]["content"][0]
+        );
+        assert_eq!(out["messages"][0]["content"][1]["input"]["q"], "[EMAIL]");
+        assert_eq!(counts.values().sum::<u64>(), 1);
+    }
+
+    #[test]
+    fn invalid_rules_are_skipped() {
+        let mut config = config();
+        config.builtin_secrets = false;
+        config.emails = false;
+        config.rules.push(RedactionRule {
+            name: "broken".to_string(),
+            pattern: "(".to_string(),
+            action: RedactionAction::Mask,
+            replacement: None,
+        });
+        let rules = compile(&config);
+        assert_eq!(
+            rules.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(),
+            vec!["internal-host", "ticket"]
+        );
+    }
+}
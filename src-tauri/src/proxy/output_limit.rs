@@ -0,0 +1,163 @@
+//! 输出 token 上限
+//!
+//! 部分中转站对 max_tokens 有上限，超过时直接报错而不是自动截断。
+//! 供应商配置了 `maxOutputTokens` 时，代理在转发前把请求中的输出上限字段截断到该值。
+//!
+//! Anthropic 要求 `thinking.budget_tokens` 小于 `max_tokens`：截断 `max_tokens` 后思考预算
+//! 随之下调；下调后低于最小预算时移除 `thinking`（关闭扩展思考），避免上游拒绝请求。
+
+use serde_json::Value;
+
+use crate::provider::Provider;
+
+/// 各协议的输出上限字段（Anthropic / OpenAI Chat / OpenAI Responses）
+const TOP_LEVEL_FIELDS: &[&str] = &["max_tokens", "max_completion_tokens", "max_output_tokens"];
+/// Anthropic 扩展思考的最小预算
+const MIN_THINKING_BUDGET: u64 = 1024;
+
+/// 一次截断（`limit` 为 0 表示该字段已被移除）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClampedField {
+    pub field: &'static str,
+    pub requested: u64,
+    pub limit: u64,
+}
+
+/// 按供应商的输出上限截断请求体，返回被截断的字段
+pub fn clamp_output_tokens(body: &mut Value, provider: &Provider) -> Vec<ClampedField> {
+    let Some(limit) = provider
+        .meta
+        .as_ref()
+        .and_then(|m| m.max_output_tokens)
+        .filter(|limit| *limit > 0)
+    else {
+        return Vec::new();
+    };
+
+    let mut clamped = Vec::new();
+    for field in TOP_LEVEL_FIELDS {
+        if let Some(c) = clamp_field(body, field, limit) {
+            clamped.push(c);
+        }
+    }
+    if clamped.iter().any(|c| c.field == "max_tokens") {
+        clamped.extend(clamp_thinking_budget(body, limit));
+    }
+    // Gemini: generationConfig.maxOutputTokens
+    if let Some(config) = body.get_mut("generationConfig") {
+        if let Some(mut c) = clamp_field(config, "maxOutputTokens", limit) {
+            c.field = "generationConfig.maxOutputTokens";
+            clamped.push(c);
+        }
+    }
+    clamped
+}
+
+/// 思考预算需小于截断后的 `max_tokens`，放不下最小预算时移除 `thinking`
+fn clamp_thinking_budget(body: &mut Value, max_tokens: u64) -> Option<ClampedField> {
+    let requested = body.get("thinking")?.get("budget_tokens")?.as_u64()?;
+    if requested < max_tokens {
+        return None;
+    }
+    let budget = max_tokens - 1;
+    if budget < MIN_THINKING_BUDGET {
+        body.as_object_mut()?.remove("thinking");
+        return Some(ClampedField {
+            field: "thinking",
+            requested,
+            limit: 0,
+        });
+    }
+    body["thinking"]["budget_tokens"] = Value::from(budget);
+    Some(ClampedField {
+        field: "thinking.budget_tokens",
+        requested,
+        limit: budget,
+    })
+}
+
+fn clamp_field(object: &mut Value, field: &'static str, limit: u64) -> Option<ClampedField> {
+    let value = object.get_mut(field)?;
+    let requested = value.as_u64()?;
+    if requested <= limit {
+        return None;
+    }
+    *value = Value::from(limit);
+    Some(ClampedField {
+        field,
+        requested,
+        limit,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderMeta;
+    use serde_json::json;
+
+    fn provider(limit: Option<u64>) -> Provider {
+        let mut provider = Provider::with_id("p".into(), "P".into(), json!({}), None);
+        provider.meta = Some(ProviderMeta {
+            max_output_tokens: limit,
+            ..ProviderMeta::default()
+        });
+        provider
+    }
+
+    #[test]
+    fn clamps_fields_above_limit() {
+        let mut body = json!({
+            "max_tokens": 32_000,
+            "max_completion_tokens": 4_000,
+            "generationConfig": { "maxOutputTokens": 65_536 }
+        });
+        let clamped = clamp_output_tokens(&mut body, &provider(Some(8_192)));
+
+        assert_eq!(body["max_tokens"], 8_192);
+        assert_eq!(body["max_completion_tokens"], 4_000);
+        assert_eq!(body["generationConfig"]["maxOutputTokens"], 8_192);
+        let fields: Vec<_> = clamped.iter().map(|c| c.field).collect();
+        assert_eq!(fields, ["max_tokens", "generationConfig.maxOutputTokens"]);
+        assert_eq!(clamped[0].requested, 32_000);
+    }
+
+    #[test]
+    fn thinking_budget_follows_clamped_max_tokens() {
+        let mut body = json!({
+            "max_tokens": 32_000,
+            "thinking": { "type": "enabled", "budget_tokens": 16_000 }
+        });
+        let clamped = clamp_output_tokens(&mut body, &provider(Some(8_192)));
+        assert_eq!(body["thinking"]["budget_tokens"], 8_191);
+        assert_eq!(clamped[1].field, "thinking.budget_tokens");
+
+        // 放不下最小预算时关闭扩展思考
+        let mut body = json!({
+            "max_tokens": 32_000,
+            "thinking": { "type": "enabled", "budget_tokens": 16_000 }
+        });
+        let clamped = clamp_output_tokens(&mut body, &provider(Some(1_000)));
+        assert!(body.get("thinking").is_none());
+        assert_eq!(clamped[1].limit, 0);
+
+        // 预算本就小于上限时保持不变
+        let mut body = json!({
+            "max_tokens": 32_000,
+            "thinking": { "type": "enabled", "budget_tokens": 4_000 }
+        });
+        assert_eq!(
+            clamp_output_tokens(&mut body, &provider(Some(8_192))).len(),
+            1
+        );
+        assert_eq!(body["thinking"]["budget_tokens"], 4_000);
+    }
+
+    #[test]
+    fn no_limit_leaves_body_untouched() {
+        let mut body = json!({ "max_tokens": 32_000 });
+        assert!(clamp_output_tokens(&mut body, &provider(None)).is_empty());
+        assert!(clamp_output_tokens(&mut body, &provider(Some(0))).is_empty());
+        assert_eq!(body["max_tokens"], 32_000);
+    }
+}
@@ -0,0 +1,601 @@
+//! 结构化输出校验
+//!
+//! 客户端要求结构化输出（JSON 模式、`json_schema`、工具调用）时，开启 `outputValidation`
+//! 后代理会在返回非流式响应前检查：
+//! - 回复文本能否解析为 JSON，请求带有 schema（或设置中配置了 schema）时还需符合 schema
+//! - 工具调用参数能否解析为 JSON，并符合请求中对应工具声明的参数 schema
+//!
+//! 校验失败按供应商计数，可选自动重试一次（同一供应商），重试仍失败时原样返回响应。
+//! schema 校验支持常用子集：type、enum、const、required、properties、
+//! additionalProperties、items、anyOf / oneOf / allOf。
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::RwLock;
+
+use bytes::Bytes;
+use once_cell::sync::Lazy;
+use reqwest::Response;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{transcript, ProxyError};
+
+/// 结构化输出校验设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputValidationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 校验失败时自动重试一次
+    #[serde(default = "default_retry_once")]
+    pub retry_once: bool,
+    /// 请求未携带 schema 时，JSON 模式回复使用的 schema
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Value>,
+}
+
+fn default_retry_once() -> bool {
+    true
+}
+
+impl Default for OutputValidationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retry_once: default_retry_once(),
+            schema: None,
+        }
+    }
+}
+
+/// 单个供应商的校验统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputValidationStats {
+    pub app_type: String,
+    pub provider_id: String,
+    /// 已校验的响应数（含重试）
+    pub checked: u64,
+    /// 校验失败数
+    pub failed: u64,
+    /// 因校验失败发起的重试数
+    pub retried: u64,
+    /// 重试后通过校验的次数
+    pub recovered: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+static STATS: Lazy<RwLock<HashMap<(String, String), OutputValidationStats>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// 按请求得出的校验要求
+#[derive(Debug, Clone, Default)]
+pub struct OutputCheck {
+    /// 回复文本需为 JSON
+    json_text: bool,
+    /// 回复文本需符合的 schema
+    text_schema: Option<Value>,
+    /// 工具名 -> 参数 schema
+    tool_schemas: HashMap<String, Value>,
+    /// 请求声明了工具（需校验工具调用参数）
+    has_tools: bool,
+    retry_once: bool,
+}
+
+impl OutputCheck {
+    /// 按当前设置与请求体构造校验要求（未开启、流式请求或请求不涉及结构化输出时为 None）
+    pub fn for_request(endpoint: &str, body: &Value) -> Option<Self> {
+        let config = crate::settings::get_settings().output_validation;
+        if !config.enabled {
+            return None;
+        }
+        Self::from_request(&config, endpoint, body)
+    }
+
+    fn from_request(config: &OutputValidationConfig, endpoint: &str, body: &Value) -> Option<Self> {
+        let streaming = body.get("stream").and_then(|s| s.as_bool()) == Some(true)
+            || endpoint.contains("streamGenerateContent");
+        if streaming {
+            return None;
+        }
+
+        let mut check = Self {
+            retry_once: config.retry_once,
+            ..Self::default()
+        };
+
+        // OpenAI Chat：response_format；Responses：text.format
+        for format in [body.get("response_format"), body.pointer("/text/format")]
+            .into_iter()
+            .flatten()
+        {
+            match format.get("type").and_then(|t| t.as_str()) {
+                Some("json_object") => check.json_text = true,
+                Some("json_schema") => {
+                    check.json_text = true;
+                    check.text_schema = format
+                        .pointer("/json_schema/schema")
+                        .or_else(|| format.get("schema"))
+                        .cloned();
+                }
+                _ => {}
+            }
+        }
+        // Gemini：generationConfig.responseMimeType
+        if let Some(generation) = body.get("generationConfig") {
+            if generation.get("responseMimeType").and_then(|m| m.as_str())
+                == Some("application/json")
+            {
+                check.json_text = true;
+                check.text_schema = generation
+                    .get("responseJsonSchema")
+                    .or_else(|| generation.get("responseSchema"))
+                    .cloned();
+            }
+        }
+        if check.json_text && check.text_schema.is_none() {
+            check.text_schema = config.schema.clone();
+        }
+
+        if let Some(tools) = body.get("tools").and_then(|t| t.as_array()) {
+            for tool in tools {
+                check.has_tools = true;
+                // Gemini 把函数声明放在 functionDeclarations 中
+                let declarations = match tool.get("functionDeclarations").and_then(|d| d.as_array())
+                {
+                    Some(declarations) => declarations.iter().collect(),
+                    None => vec![tool.get("function").unwrap_or(tool)],
+                };
+                for declaration in declarations {
+                    let name = declaration.get("name").and_then(|n| n.as_str());
+                    let schema = declaration
+                        .get("parameters")
+                        .or_else(|| declaration.get("input_schema"));
+                    if let (Some(name), Some(schema)) = (name, schema) {
+                        check.tool_schemas.insert(name.to_string(), schema.clone());
+                    }
+                }
+            }
+        }
+
+        (check.json_text || check.has_tools).then_some(check)
+    }
+
+    /// 校验非流式响应体，失败时返回原因
+    pub fn validate(&self, body: &[u8]) -> Result<(), String> {
+        let response: Value =
+            serde_json::from_slice(body).map_err(|e| format!("响应体不是合法 JSON: {e}"))?;
+        let calls = tool_calls(&response);
+
+        if self.json_text {
+            let text = transcript::completion_from_response(&response);
+            // 模型改为调用工具时没有回复文本，只校验工具参数
+            if !text.trim().is_empty() || calls.is_empty() {
+                let value: Value = serde_json::from_str(text.trim())
+                    .map_err(|e| format!("回复不是合法 JSON: {e}"))?;
+                if let Some(schema) = &self.text_schema {
+                    validate_schema(&value, schema, "$")
+                        .map_err(|e| format!("回复不符合 schema: {e}"))?;
+                }
+            }
+        }
+
+        if self.has_tools {
+            for (name, arguments) in calls {
+                let value = match arguments {
+                    Value::String(raw) => serde_json::from_str(&raw)
+                        .map_err(|e| format!("工具 {name} 的参数不是合法 JSON: {e}"))?,
+                    other => other,
+                };
+                if let Some(schema) = self.tool_schemas.get(&name) {
+                    validate_schema(&value, schema, "$")
+                        .map_err(|e| format!("工具 {name} 的参数不符合 schema: {e}"))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 响应中的工具调用：(工具名, 参数)，OpenAI 格式的参数为 JSON 字符串
+fn tool_calls(response: &Value) -> Vec<(String, Value)> {
+    let mut calls = Vec::new();
+    let mut push = |name: Option<&Value>, arguments: Option<&Value>| {
+        if let (Some(name), Some(arguments)) = (name.and_then(|n| n.as_str()), arguments) {
+            calls.push((name.to_string(), arguments.clone()));
+        }
+    };
+
+    // Claude：content[].tool_use
+    for block in response
+        .get("content")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+    {
+        if block.get("type").and_then(|t| t.as_str()) == Some("tool_use") {
+            push(block.get("name"), block.get("input"));
+        }
+    }
+    // OpenAI Chat：choices[].message.tool_calls[].function
+    for choice in response
+        .get("choices")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+    {
+        for call in choice
+            .pointer("/message/tool_calls")
+            .and_then(|c| c.as_array())
+            .into_iter()
+            .flatten()
+        {
+            push(
+                call.pointer("/function/name"),
+                call.pointer("/function/arguments"),
+            );
+        }
+    }
+    // OpenAI Responses：output[].function_call
+    for item in response
+        .get("output")
+        .and_then(|o| o.as_array())
+        .into_iter()
+        .flatten()
+    {
+        if item.get("type").and_then(|t| t.as_str()) == Some("function_call") {
+            push(item.get("name"), item.get("arguments"));
+        }
+    }
+    // Gemini：candidates[].content.parts[].functionCall
+    for candidate in response
+        .get("candidates")
+        .and_then(|c| c.as_array())
+        .into_iter()
+        .flatten()
+    {
+        for part in candidate
+            .pointer("/content/parts")
+            .and_then(|p| p.as_array())
+            .into_iter()
+            .flatten()
+        {
+            if let Some(call) = part.get("functionCall") {
+                push(call.get("name"), call.get("args"));
+            }
+        }
+    }
+    calls
+}
+
+fn type_matches(value: &Value, ty: &str) -> bool {
+    match ty.to_ascii_lowercase().as_str() {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|f| f.fract() == 0.0)
+        }
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// 按 JSON Schema 常用子集校验 `value`，失败时返回出错位置与原因
+fn validate_schema(value: &Value, schema: &Value, path: &str) -> Result<(), String> {
+    let Some(schema) = schema.as_object() else {
+        return Ok(());
+    };
+
+    if let Some(ty) = schema.get("type") {
+        let types: Vec<&str> = match ty {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(|t| t.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| type_matches(value, t)) {
+            return Err(format!("{path} 应为 {}", types.join(" | ")));
+        }
+    }
+    if let Some(options) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !options.contains(value) {
+            return Err(format!("{path} 不在允许的取值中"));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            return Err(format!("{path} 应为 {expected}"));
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        for field in schema
+            .get("required")
+            .and_then(|r| r.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|f| f.as_str())
+        {
+            if !object.contains_key(field) {
+                return Err(format!("{path} 缺少必填字段 {field}"));
+            }
+        }
+        let properties = schema.get("properties").and_then(|p| p.as_object());
+        for (key, item) in object {
+            let item_path = format!("{path}.{key}");
+            match properties.and_then(|p| p.get(key)) {
+                Some(property) => validate_schema(item, property, &item_path)?,
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        return Err(format!("{path} 不允许额外字段 {key}"));
+                    }
+                    Some(additional @ Value::Object(_)) => {
+                        validate_schema(item, additional, &item_path)?
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (i, item) in array.iter().enumerate() {
+            validate_schema(item, items, &format!("{path}[{i}]"))?;
+        }
+    }
+
+    if let Some(all) = schema.get("allOf").and_then(|a| a.as_array()) {
+        for sub in all {
+            validate_schema(value, sub, path)?;
+        }
+    }
+    for keyword in ["anyOf", "oneOf"] {
+        if let Some(options) = schema.get(keyword).and_then(|a| a.as_array()) {
+            if !options
+                .iter()
+                .any(|sub| validate_schema(value, sub, path).is_ok())
+            {
+                return Err(format!("{path} 不符合 {keyword} 中的任何一项"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 读出响应体并重新包装，返回可继续转发的响应与响应体
+async fn buffer(response: Response) -> Result<(Response, Bytes), ProxyError> {
+    let status = response.status();
+    let version = response.version();
+    let headers = response.headers().clone();
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| ProxyError::ForwardFailed(format!("Failed to read response body: {e}")))?;
+
+    let mut rebuilt = axum::http::Response::new(reqwest::Body::from(body.clone()));
+    *rebuilt.status_mut() = status;
+    *rebuilt.version_mut() = version;
+    *rebuilt.headers_mut() = headers;
+    Ok((Response::from(rebuilt), body))
+}
+
+fn record(app_type: &str, provider_id: &str, update: impl FnOnce(&mut OutputValidationStats)) {
+    let mut stats = STATS.write().unwrap_or_else(|e| e.into_inner());
+    let entry = stats
+        .entry((app_type.to_string(), provider_id.to_string()))
+        .or_insert_with(|| OutputValidationStats {
+            app_type: app_type.to_string(),
+            provider_id: provider_id.to_string(),
+            ..Default::default()
+        });
+    update(entry);
+}
+
+/// 发送请求并校验成功的非流式响应，失败时按设置重试一次
+pub async fn send_checked<F, Fut>(
+    check: &OutputCheck,
+    app_type: &str,
+    provider_id: &str,
+    mut send: F,
+) -> Result<Response, ProxyError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Response, ProxyError>>,
+{
+    let mut retried = false;
+    loop {
+        let response = send().await?;
+        let is_sse = response
+            .headers()
+            .get("content-type")
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.contains("text/event-stream"));
+        if !response.status().is_success() || is_sse {
+            return Ok(response);
+        }
+
+        let (response, body) = buffer(response).await?;
+        let verdict = check.validate(&body);
+        let retry = verdict.is_err() && check.retry_once && !retried;
+        record(app_type, provider_id, |stats| {
+            stats.checked += 1;
+            match &verdict {
+                Ok(()) if retried => stats.recovered += 1,
+                Ok(()) => {}
+                Err(reason) => {
+                    stats.failed += 1;
+                    stats.last_error = Some(reason.clone());
+                }
+            }
+            if retry {
+                stats.retried += 1;
+            }
+        });
+
+        match verdict {
+            Err(reason) if retry => {
+                log::warn!(
+                    "[{app_type}] 供应商 {provider_id} 结构化输出校验失败，重试一次: {reason}"
+                );
+                retried = true;
+            }
+            Err(reason) => {
+                log::warn!("[{app_type}] 供应商 {provider_id} 结构化输出校验失败: {reason}");
+                return Ok(response);
+            }
+            Ok(()) => return Ok(response),
+        }
+    }
+}
+
+/// 各供应商的校验统计
+pub fn stats() -> Vec<OutputValidationStats> {
+    let mut stats: Vec<_> = STATS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .cloned()
+        .collect();
+    stats.sort_by(|a, b| {
+        (a.app_type.as_str(), a.provider_id.as_str())
+            .cmp(&(b.app_type.as_str(), b.provider_id.as_str()))
+    });
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn enabled() -> OutputValidationConfig {
+        OutputValidationConfig {
+            enabled: true,
+            ..Default::default()
+        }
+    }
+
+    fn json_response(body: Value) -> Response {
+        let mut response = axum::http::Response::new(reqwest::Body::from(body.to_string()));
+        response
+            .headers_mut()
+            .insert("content-type", "application/json".parse().unwrap());
+        Response::from(response)
+    }
+
+    #[test]
+    fn only_structured_non_streaming_requests_are_checked() {
+        let config = enabled();
+        let plain = json!({"messages": []});
+        assert!(OutputCheck::from_request(&config, "/v1/chat/completions", &plain).is_none());
+
+        let streaming = json!({"stream": true, "response_format": {"type": "json_object"}});
+        assert!(OutputCheck::from_request(&config, "/v1/chat/completions", &streaming).is_none());
+
+        let gemini = json!({"generationConfig": {"responseMimeType": "application/json"}});
+        assert!(
+            OutputCheck::from_request(&config, "/v1beta/models/x:generateContent", &gemini)
+                .is_some()
+        );
+        assert!(OutputCheck::from_request(
+            &config,
+            "/v1beta/models/x:streamGenerateContent",
+            &gemini
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn validates_json_text_against_request_schema() {
+        let request = json!({
+            "response_format": {"type": "json_schema", "json_schema": {"schema": {
+                "type": "object",
+                "required": ["score"],
+                "properties": {"score": {"type": "integer"}},
+                "additionalProperties": false
+            }}}
+        });
+        let check =
+            OutputCheck::from_request(&enabled(), "/v1/chat/completions", &request).unwrap();
+        let reply = |content: &str| {
+            json!({"choices": [{"message": {"content": content}}]})
+                .to_string()
+                .into_bytes()
+        };
+
+        assert!(check.validate(&reply(r#"{"score": 3}"#)).is_ok());
+        assert!(check
+            .validate(&reply("Sure! {\"score\": 3}"))
+            .unwrap_err()
+            .contains("不是合法 JSON"));
+        assert!(check
+            .validate(&reply(r#"{"score": "3"}"#))
+            .unwrap_err()
+            .contains("$.score"));
+        assert!(check
+            .validate(&reply(r#"{"score": 3, "x": 1}"#))
+            .unwrap_err()
+            .contains("额外字段 x"));
+        assert!(check
+            .validate(&reply("{}"))
+            .unwrap_err()
+            .contains("必填字段 score"));
+    }
+
+    #[test]
+    fn validates_tool_arguments_against_declared_schema() {
+        let request = json!({"tools": [{"name": "lookup", "input_schema": {
+            "type": "object",
+            "required": ["id"],
+            "properties": {"id": {"type": "string"}}
+        }}]});
+        let check = OutputCheck::from_request(&enabled(), "/v1/messages", &request).unwrap();
+
+        let ok = json!({"content": [{"type": "tool_use", "name": "lookup", "input": {"id": "a"}}]});
+        assert!(check.validate(ok.to_string().as_bytes()).is_ok());
+        let bad = json!({"content": [{"type": "tool_use", "name": "lookup", "input": {}}]});
+        assert!(check.validate(bad.to_string().as_bytes()).is_err());
+
+        let openai = json!({"choices": [{"message": {"tool_calls": [
+            {"function": {"name": "lookup", "arguments": "{\"id\": "}}
+        ]}}]});
+        assert!(check
+            .validate(openai.to_string().as_bytes())
+            .unwrap_err()
+            .contains("不是合法 JSON"));
+    }
+
+    #[tokio::test]
+    async fn retries_once_and_counts_failures() {
+        let request = json!({"response_format": {"type": "json_object"}});
+        let check =
+            OutputCheck::from_request(&enabled(), "/v1/chat/completions", &request).unwrap();
+        let replies = std::sync::Mutex::new(vec![
+            json!({"choices": [{"message": {"content": "{\"ok\": true}"}}]}),
+            json!({"choices": [{"message": {"content": "not json"}}]}),
+        ]);
+
+        let response = send_checked(&check, "codex", "validation-test", || {
+            let reply = replies.lock().unwrap().pop().unwrap();
+            async move { Ok(json_response(reply)) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            response.text().await.unwrap(),
+            r#"{"choices":[{"message":{"content":"{\"ok\": true}"}}]}"#
+        );
+
+        let stats = stats()
+            .into_iter()
+            .find(|s| s.provider_id == "validation-test")
+            .unwrap();
+        assert_eq!(
+            (stats.checked, stats.failed, stats.retried, stats.recovered),
+            (2, 1, 1, 1)
+        );
+    }
+}
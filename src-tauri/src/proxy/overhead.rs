@@ -0,0 +1,121 @@
+//! 代理自身开销测量
+//!
+//! 开启 `measureProxyOverhead` 后，记录代理自身引入的延迟，与上游耗时分开统计：
+//! - 处理开销：请求到达至拿到上游响应头的总耗时，减去等待上游的时间（读配置、选供应商、改写请求体、日志等）
+//! - 转发开销：流式响应中每个数据块从收到到转发给客户端之间的处理时间
+//! - 上游耗时：发出请求到收到响应头（用于对比）
+//!
+//! 每类保留最近 [`WINDOW`] 个样本，通过代理状态中的 `overhead` 字段展示 p50 / p95。
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+/// 每类保留的样本数
+const WINDOW: usize = 1024;
+
+#[derive(Default)]
+struct Samples {
+    request: VecDeque<u64>,
+    chunk: VecDeque<u64>,
+    upstream: VecDeque<u64>,
+}
+
+static SAMPLES: Lazy<Mutex<Samples>> = Lazy::new(|| Mutex::new(Samples::default()));
+
+/// 某类开销的分位数（毫秒）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencySummary {
+    pub samples: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+/// 代理自身开销统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OverheadStats {
+    /// 每个请求的处理开销（不含等待上游）
+    pub request: LatencySummary,
+    /// 流式数据块的转发开销
+    pub chunk: LatencySummary,
+    /// 上游响应头耗时（对比用）
+    pub upstream: LatencySummary,
+}
+
+/// 是否开启测量
+pub fn enabled() -> bool {
+    crate::settings::get_settings().measure_proxy_overhead
+}
+
+fn push(window: &mut VecDeque<u64>, duration: Duration) {
+    if window.len() >= WINDOW {
+        window.pop_front();
+    }
+    window.push_back(duration.as_micros() as u64);
+}
+
+/// 记录一个请求的处理开销与上游耗时
+pub fn record_request(total: Duration, upstream: Duration) {
+    let mut samples = SAMPLES.lock().unwrap_or_else(|e| e.into_inner());
+    push(&mut samples.request, total.saturating_sub(upstream));
+    push(&mut samples.upstream, upstream);
+}
+
+/// 记录一个数据块的转发开销
+pub fn record_chunk(elapsed: Duration) {
+    let mut samples = SAMPLES.lock().unwrap_or_else(|e| e.into_inner());
+    push(&mut samples.chunk, elapsed);
+}
+
+fn summarize(window: &VecDeque<u64>) -> LatencySummary {
+    if window.is_empty() {
+        return LatencySummary::default();
+    }
+    let mut sorted: Vec<u64> = window.iter().copied().collect();
+    sorted.sort_unstable();
+    let percentile = |p: f64| {
+        let idx = ((sorted.len() as f64 * p).ceil() as usize).clamp(1, sorted.len()) - 1;
+        sorted[idx] as f64 / 1000.0
+    };
+    LatencySummary {
+        samples: sorted.len(),
+        p50_ms: percentile(0.50),
+        p95_ms: percentile(0.95),
+        max_ms: *sorted.last().unwrap_or(&0) as f64 / 1000.0,
+    }
+}
+
+/// 当前统计（未开启测量时返回 None）
+pub fn snapshot() -> Option<OverheadStats> {
+    if !enabled() {
+        return None;
+    }
+    let samples = SAMPLES.lock().unwrap_or_else(|e| e.into_inner());
+    Some(OverheadStats {
+        request: summarize(&samples.request),
+        chunk: summarize(&samples.chunk),
+        upstream: summarize(&samples.upstream),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarize_reports_percentiles_in_ms() {
+        let window: VecDeque<u64> = (1..=100).map(|i| i * 1000).collect();
+        let summary = summarize(&window);
+        assert_eq!(summary.samples, 100);
+        assert_eq!(summary.p50_ms, 50.0);
+        assert_eq!(summary.p95_ms, 95.0);
+        assert_eq!(summary.max_ms, 100.0);
+        assert_eq!(summarize(&VecDeque::new()), LatencySummary::default());
+    }
+}
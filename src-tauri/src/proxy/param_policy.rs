@@ -0,0 +1,329 @@
+//! 请求参数策略
+//!
+//! 与同事共用代理时，对方工具的参数配置不一定合适。设置中的全局策略与供应商的
+//! `meta.parameterPolicy` 合并后，在转发前检查请求参数：
+//! - `max` / `min`：数值上下限，如 `{"temperature": 1.0}`
+//! - `forbid`：禁止出现的参数，如 `["logprobs"]`
+//! - `force`：强制取值，如 `{"stream": true}`
+//!
+//! 参数名支持以 `.` 分隔的嵌套路径（如 `generationConfig.temperature`）。
+//! 违反策略时按 `action` 改写后转发（rewrite，默认）或直接返回 400（reject），并记录日志与统计。
+//! 按请求的当前供应商检查，故障转移到其他供应商时不再重新检查。
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::ProxyError;
+use crate::provider::Provider;
+
+/// 违反策略时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyAction {
+    /// 改写为符合策略的值后转发
+    #[default]
+    Rewrite,
+    /// 拒绝请求
+    Reject,
+}
+
+/// 参数策略
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParameterPolicy {
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub max: BTreeMap<String, f64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub min: BTreeMap<String, f64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub forbid: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub force: BTreeMap<String, Value>,
+    /// 为空时沿用全局策略的处理方式（都未设置时为 rewrite）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<PolicyAction>,
+}
+
+impl ParameterPolicy {
+    pub fn of(provider: &Provider) -> Option<&Self> {
+        provider.meta.as_ref()?.parameter_policy.as_ref()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.max.is_empty()
+            && self.min.is_empty()
+            && self.forbid.is_empty()
+            && self.force.is_empty()
+    }
+
+    /// 供应商策略覆盖全局策略中的同名参数
+    fn merged(global: &Self, provider: Option<&Self>) -> Self {
+        let Some(provider) = provider else {
+            return global.clone();
+        };
+        let mut merged = global.clone();
+        merged.max.extend(provider.max.clone());
+        merged.min.extend(provider.min.clone());
+        merged.force.extend(provider.force.clone());
+        for field in &provider.forbid {
+            if !merged.forbid.contains(field) {
+                merged.forbid.push(field.clone());
+            }
+        }
+        merged.action = provider.action.or(global.action);
+        merged
+    }
+}
+
+/// 一次违反策略
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub field: String,
+    pub message: String,
+}
+
+/// 单个供应商的策略统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PolicyStats {
+    pub app_type: String,
+    pub provider_id: String,
+    /// 被改写的请求数
+    pub rewritten: u64,
+    /// 被拒绝的请求数
+    pub rejected: u64,
+    /// 参数名 -> 违反次数
+    pub by_field: BTreeMap<String, u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_violation: Option<String>,
+}
+
+static STATS: Lazy<RwLock<HashMap<(String, String), PolicyStats>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+fn lookup<'a>(body: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+    path.split('.')
+        .try_fold(body, |value, key| value.get_mut(key))
+}
+
+fn remove(body: &mut Value, path: &str) -> Option<Value> {
+    let (parent, key) = match path.rsplit_once('.') {
+        Some((parent, key)) => (lookup(body, parent)?, key),
+        None => (body, path),
+    };
+    parent.as_object_mut()?.remove(key)
+}
+
+fn insert(body: &mut Value, path: &str, value: Value) {
+    let mut current = body;
+    let mut keys = path.split('.').peekable();
+    while let Some(key) = keys.next() {
+        let Some(object) = current.as_object_mut() else {
+            return;
+        };
+        if keys.peek().is_none() {
+            object.insert(key.to_string(), value);
+            return;
+        }
+        current = object
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Object(Default::default()));
+    }
+}
+
+fn clamp(body: &mut Value, path: &str, bound: f64, upper: bool) -> Option<Violation> {
+    let value = lookup(body, path)?;
+    let requested = value.as_f64()?;
+    let violated = if upper {
+        requested > bound
+    } else {
+        requested < bound
+    };
+    if !violated {
+        return None;
+    }
+    *value = Value::from(bound);
+    let relation = if upper { "上限" } else { "下限" };
+    Some(Violation {
+        field: path.to_string(),
+        message: format!("{path} = {requested} 超出{relation} {bound}"),
+    })
+}
+
+/// 按策略改写请求体，返回违反的项目（请求体已改写为符合策略的值）
+pub fn apply(policy: &ParameterPolicy, body: &mut Value) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for (path, bound) in &policy.max {
+        violations.extend(clamp(body, path, *bound, true));
+    }
+    for (path, bound) in &policy.min {
+        violations.extend(clamp(body, path, *bound, false));
+    }
+    for path in &policy.forbid {
+        if remove(body, path).is_some() {
+            violations.push(Violation {
+                field: path.clone(),
+                message: format!("不允许使用 {path}"),
+            });
+        }
+    }
+    for (path, forced) in &policy.force {
+        let current = lookup(body, path).map(|v| v.clone());
+        if current.as_ref() != Some(forced) {
+            let shown = current.map_or_else(|| "未设置".to_string(), |v| v.to_string());
+            insert(body, path, forced.clone());
+            violations.push(Violation {
+                field: path.clone(),
+                message: format!("{path} 必须为 {forced}（请求为 {shown}）"),
+            });
+        }
+    }
+    violations
+}
+
+/// 预判转发时的请求体：按全局与供应商策略改写（不记录统计）
+///
+/// 用于在选定供应商前判断请求特征（如是否流式）；拒绝类策略同样按改写处理。
+pub fn preview(provider: Option<&Provider>, body: &mut Value) {
+    let global = crate::settings::get_settings().parameter_policy;
+    let policy = ParameterPolicy::merged(&global, provider.and_then(ParameterPolicy::of));
+    if !policy.is_empty() {
+        apply(&policy, body);
+    }
+}
+
+fn record(app_type: &str, provider_id: &str, violations: &[Violation], rejected: bool) {
+    let mut stats = STATS.write().unwrap_or_else(|e| e.into_inner());
+    let entry = stats
+        .entry((app_type.to_string(), provider_id.to_string()))
+        .or_insert_with(|| PolicyStats {
+            app_type: app_type.to_string(),
+            provider_id: provider_id.to_string(),
+            ..PolicyStats::default()
+        });
+    if rejected {
+        entry.rejected += 1;
+    } else {
+        entry.rewritten += 1;
+    }
+    for violation in violations {
+        *entry.by_field.entry(violation.field.clone()).or_default() += 1;
+    }
+    entry.last_violation = violations.last().map(|v| v.message.clone());
+}
+
+/// 转发前按全局与供应商策略检查请求（改写时直接修改请求体）
+pub fn enforce(
+    tag: &str,
+    app_type: &str,
+    provider: &Provider,
+    body: &mut Value,
+) -> Result<(), ProxyError> {
+    let global = crate::settings::get_settings().parameter_policy;
+    let policy = ParameterPolicy::merged(&global, ParameterPolicy::of(provider));
+    if policy.is_empty() {
+        return Ok(());
+    }
+
+    let mut checked = body.clone();
+    let violations = apply(&policy, &mut checked);
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let summary = violations
+        .iter()
+        .map(|v| v.message.as_str())
+        .collect::<Vec<_>>()
+        .join("；");
+
+    match policy.action.unwrap_or_default() {
+        PolicyAction::Reject => {
+            log::warn!("[{tag}] 请求违反参数策略，已拒绝: {summary}");
+            record(app_type, &provider.id, &violations, true);
+            Err(ProxyError::InvalidRequest(format!(
+                "请求违反参数策略: {summary}"
+            )))
+        }
+        PolicyAction::Rewrite => {
+            log::warn!("[{tag}] 请求违反参数策略，已改写: {summary}");
+            record(app_type, &provider.id, &violations, false);
+            *body = checked;
+            Ok(())
+        }
+    }
+}
+
+/// 各供应商的策略统计
+pub fn stats() -> Vec<PolicyStats> {
+    let mut stats: Vec<_> = STATS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .cloned()
+        .collect();
+    stats.sort_by(|a, b| {
+        (a.app_type.as_str(), a.provider_id.as_str())
+            .cmp(&(b.app_type.as_str(), b.provider_id.as_str()))
+    });
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn rewrites_bounds_forbidden_and_forced_fields() {
+        let policy: ParameterPolicy = serde_json::from_value(json!({
+            "max": { "temperature": 1.0, "generationConfig.temperature": 1.0 },
+            "min": { "top_p": 0.1 },
+            "forbid": ["logprobs"],
+            "force": { "stream": true }
+        }))
+        .unwrap();
+        let mut body = json!({
+            "temperature": 1.8,
+            "top_p": 0.5,
+            "logprobs": true,
+            "generationConfig": { "temperature": 0.3 }
+        });
+
+        let violations = apply(&policy, &mut body);
+        assert_eq!(body["temperature"], 1.0);
+        assert_eq!(body["top_p"], 0.5);
+        assert!(body.get("logprobs").is_none());
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["generationConfig"]["temperature"], 0.3);
+        let fields: Vec<_> = violations.iter().map(|v| v.field.as_str()).collect();
+        assert_eq!(fields, ["temperature", "logprobs", "stream"]);
+
+        // 已符合策略的请求不再报告
+        assert!(apply(&policy, &mut body).is_empty());
+    }
+
+    #[test]
+    fn provider_policy_overrides_global() {
+        let global = ParameterPolicy {
+            max: BTreeMap::from([("temperature".to_string(), 1.0)]),
+            forbid: vec!["logprobs".to_string()],
+            ..ParameterPolicy::default()
+        };
+        let provider = ParameterPolicy {
+            max: BTreeMap::from([("temperature".to_string(), 0.7)]),
+            forbid: vec!["logprobs".to_string(), "n".to_string()],
+            action: Some(PolicyAction::Reject),
+            ..ParameterPolicy::default()
+        };
+
+        let merged = ParameterPolicy::merged(&global, Some(&provider));
+        assert_eq!(merged.max["temperature"], 0.7);
+        assert_eq!(merged.forbid, ["logprobs", "n"]);
+        assert_eq!(merged.action, Some(PolicyAction::Reject));
+        assert_eq!(ParameterPolicy::merged(&global, None), global);
+    }
+}
@@ -6,6 +6,7 @@ use crate::database::Database;
 use crate::error::AppError;
 use crate::provider::Provider;
 use crate::proxy::circuit_breaker::{AllowResult, CircuitBreaker, CircuitBreakerConfig};
+use crate::services::maintenance;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -176,6 +177,24 @@ impl ProviderRouter {
         let circuit_key = format!("{app_type}:{provider_id}");
         let breaker = self.get_or_create_circuit_breaker(&circuit_key).await;
 
+        // 维护窗口内的失败只记录日志，不计入熔断器与健康状态，避免计划内停机触发故障转移
+        if !success
+            && self
+                .db
+                .get_provider_by_id(provider_id, app_type)
+                .ok()
+                .flatten()
+                .is_some_and(|p| maintenance::is_in_maintenance(&p))
+        {
+            breaker.release(used_half_open_permit);
+            log::info!(
+                "Provider {} request failed during maintenance window (not counted): {}",
+                provider_id,
+                error_msg.as_deref().unwrap_or("Unknown error")
+            );
+            return Ok(());
+        }
+
         if success {
             breaker.record_success(used_half_open_permit).await;
             log::debug!("Provider {provider_id} request succeeded");
@@ -0,0 +1,159 @@
+//! 供应商级 TLS 选项
+//!
+//! 支持为单个供应商追加受信任的 CA 证书包、（明确标注危险并记录日志的）跳过证书校验，
+//! 以及 SNI 覆盖，便于接入使用内部 CA 的自建中转站。
+//! 代理转发、健康检查与保活请求共用这里构建的客户端配置。
+
+use reqwest::ClientBuilder;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::proxy::ip_preference::{self, IpPreference};
+
+/// 供应商 TLS 选项（存储在 ProviderMeta.tls）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderTlsOptions {
+    /// 额外信任的 CA 证书包（PEM 文件路径，可包含多个证书）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ca_bundle_path: Option<String>,
+    /// 跳过证书校验（危险：仅用于排障，启用时每次建连都会记录警告）
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub insecure_skip_verify: bool,
+    /// SNI 覆盖：TLS 握手与证书校验使用该主机名，实际仍连接 base_url 的主机
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sni_override: Option<String>,
+}
+
+impl ProviderTlsOptions {
+    /// 读取供应商配置的 TLS 选项
+    pub fn of(provider: &Provider) -> Option<&Self> {
+        provider
+            .meta
+            .as_ref()
+            .and_then(|m| m.tls.as_ref())
+            .filter(|t| **t != Self::default())
+    }
+
+    fn sni_override(&self) -> Option<&str> {
+        self.sni_override
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+    }
+}
+
+/// 该供应商是否需要单独构建 HTTP 客户端（配置了 IP 偏好或 TLS 选项）
+pub fn needs_custom_client(provider: &Provider) -> bool {
+    IpPreference::of(provider) != IpPreference::Auto || ProviderTlsOptions::of(provider).is_some()
+}
+
+/// 为 ClientBuilder 应用供应商的 IP 偏好与 TLS 选项
+pub fn configure(
+    builder: ClientBuilder,
+    provider: &Provider,
+    base_url: &str,
+) -> Result<ClientBuilder, AppError> {
+    let preference = IpPreference::of(provider);
+    let Some(tls) = ProviderTlsOptions::of(provider) else {
+        return Ok(ip_preference::apply(builder, preference));
+    };
+
+    let mut builder = builder;
+
+    if let Some(path) = tls
+        .ca_bundle_path
+        .as_deref()
+        .filter(|p| !p.trim().is_empty())
+    {
+        let pem = std::fs::read(path).map_err(|e| AppError::io(path, e))?;
+        let certs = reqwest::Certificate::from_pem_bundle(&pem)
+            .map_err(|e| AppError::Message(format!("解析 CA 证书包失败 ({path}): {e}")))?;
+        if certs.is_empty() {
+            return Err(AppError::Message(format!("CA 证书包中没有证书: {path}")));
+        }
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+
+    if tls.insecure_skip_verify {
+        log::warn!(
+            "[TLS] 供应商 {} 已关闭证书校验，连接可能被中间人攻击",
+            provider.name
+        );
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    // SNI 覆盖：请求 URL 改用 SNI 主机名，解析时映射回原主机
+    let alias = tls.sni_override().and_then(|sni| {
+        let original = url::Url::parse(base_url).ok()?.host_str()?.to_string();
+        Some((sni.to_string(), original))
+    });
+
+    Ok(match alias {
+        Some(alias) => ip_preference::install_resolver(builder, preference, Some(alias)),
+        None => ip_preference::apply(builder, preference),
+    })
+}
+
+/// 配置了 SNI 覆盖时，将 URL 的主机名替换为 SNI 主机名（端口与路径不变）
+pub fn rewrite_url(url: &str, provider: &Provider) -> String {
+    let Some(sni) = ProviderTlsOptions::of(provider).and_then(|t| t.sni_override()) else {
+        return url.to_string();
+    };
+    let Ok(mut parsed) = url::Url::parse(url) else {
+        return url.to_string();
+    };
+    if parsed.set_host(Some(sni)).is_err() {
+        return url.to_string();
+    }
+    let rewritten = parsed.to_string();
+    // Url 会为空路径补 "/"，保持与原始 base_url 一致
+    if !url.ends_with('/') && parsed.path() == "/" {
+        rewritten.trim_end_matches('/').to_string()
+    } else {
+        rewritten
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderMeta;
+    use serde_json::json;
+
+    fn provider_with_tls(tls: ProviderTlsOptions) -> Provider {
+        let mut provider = Provider::with_id("p".into(), "P".into(), json!({}), None);
+        provider.meta = Some(ProviderMeta {
+            tls: Some(tls),
+            ..ProviderMeta::default()
+        });
+        provider
+    }
+
+    #[test]
+    fn rewrite_url_replaces_host_only() {
+        let provider = provider_with_tls(ProviderTlsOptions {
+            sni_override: Some("relay.internal".to_string()),
+            ..ProviderTlsOptions::default()
+        });
+        assert_eq!(
+            rewrite_url("https://10.0.0.5:8443/v1/messages", &provider),
+            "https://relay.internal:8443/v1/messages"
+        );
+        assert_eq!(
+            rewrite_url("https://10.0.0.5", &provider),
+            "https://relay.internal"
+        );
+    }
+
+    #[test]
+    fn default_options_do_not_require_custom_client() {
+        let provider = provider_with_tls(ProviderTlsOptions::default());
+        assert!(ProviderTlsOptions::of(&provider).is_none());
+        assert!(!needs_custom_client(&provider));
+        assert_eq!(rewrite_url("https://a.com/x", &provider), "https://a.com/x");
+    }
+}
@@ -96,6 +96,11 @@ pub enum AuthStrategy {
     ///
     /// 用于 Gemini CLI 等需要 OAuth 的场景
     GoogleOAuth,
+
+    /// AWS SigV4 签名
+    ///
+    /// 签名覆盖请求体，需在请求构建完成后由 Bedrock 改写逻辑添加
+    AwsSigV4,
 }
 
 #[cfg(test)]
@@ -195,6 +200,7 @@ mod tests {
             AuthStrategy::Bearer,
             AuthStrategy::Google,
             AuthStrategy::GoogleOAuth,
+            AuthStrategy::AwsSigV4,
         ];
 
         for (i, s1) in strategies.iter().enumerate() {
@@ -0,0 +1,191 @@
+//! Azure OpenAI 部署路由
+//!
+//! Azure OpenAI 的模型由“部署”承载，部署名位于 URL 路径中：
+//! - Chat Completions：`{resource}/openai/deployments/{deployment}/chat/completions?api-version=...`
+//! - Responses：`{resource}/openai/responses?api-version=...`（部署名放在请求体 model 字段）
+//!
+//! 认证使用 `api-key` 请求头而非 Bearer。配置存储在 ProviderMeta.azure，
+//! 代理转发与流式检查在请求构建完成后调用 [`prepare_request`] 改写。
+
+use std::collections::BTreeMap;
+
+use reqwest::header::{HeaderValue, AUTHORIZATION};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::provider::Provider;
+use crate::proxy::error::ProxyError;
+
+/// 默认 API 版本（同时支持 Chat Completions 与 Responses）
+pub const DEFAULT_AZURE_API_VERSION: &str = "2025-04-01-preview";
+
+fn default_api_version() -> String {
+    DEFAULT_AZURE_API_VERSION.to_string()
+}
+
+/// Azure OpenAI 配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AzureOpenAiConfig {
+    #[serde(default = "default_api_version")]
+    pub api_version: String,
+    /// 部署映射（逻辑模型名 -> 部署名）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub deployments: BTreeMap<String, String>,
+    /// 未命中映射时使用的部署（未设置时直接使用模型名作为部署名）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_deployment: Option<String>,
+}
+
+impl Default for AzureOpenAiConfig {
+    fn default() -> Self {
+        Self {
+            api_version: default_api_version(),
+            deployments: BTreeMap::new(),
+            default_deployment: None,
+        }
+    }
+}
+
+impl AzureOpenAiConfig {
+    /// 读取供应商的 Azure 配置（未配置时为 None）
+    pub fn of(provider: &Provider) -> Option<&Self> {
+        provider.meta.as_ref().and_then(|m| m.azure.as_ref())
+    }
+
+    /// 解析模型对应的部署名
+    pub fn deployment_for(&self, model: &str) -> String {
+        self.deployments
+            .get(model)
+            .or(self.default_deployment.as_ref())
+            .map(|d| d.trim().to_string())
+            .filter(|d| !d.is_empty())
+            .unwrap_or_else(|| model.to_string())
+    }
+}
+
+/// 把 OpenAI 格式的请求改写为 Azure 部署请求（路径、api-version、api-key）
+pub fn prepare_request(
+    config: &AzureOpenAiConfig,
+    request: &mut reqwest::Request,
+    body: &Value,
+) -> Result<(), ProxyError> {
+    let model = body.get("model").and_then(|v| v.as_str()).unwrap_or("");
+    let deployment = config.deployment_for(model);
+
+    // 取出 OpenAI 操作路径（去掉 base_url 中可能带的 /openai 与 /v1 前缀）
+    let url = request.url().clone();
+    let path = url.path();
+    let operation = [
+        "/chat/completions",
+        "/completions",
+        "/embeddings",
+        "/responses",
+    ]
+    .into_iter()
+    .find(|op| path.ends_with(op))
+    .ok_or_else(|| ProxyError::ConfigError(format!("Azure OpenAI 不支持的端点: {path}")))?;
+
+    let new_path = if operation == "/responses" {
+        // Responses API 不区分部署路径，部署名放在请求体
+        let mut payload = body.clone();
+        if let Some(obj) = payload.as_object_mut() {
+            obj.insert("model".into(), Value::String(deployment.clone()));
+        }
+        let bytes = serde_json::to_vec(&payload)
+            .map_err(|e| ProxyError::TransformError(format!("序列化 Azure 请求失败: {e}")))?;
+        *request.body_mut() = Some(bytes.into());
+        "/openai/responses".to_string()
+    } else {
+        if deployment.is_empty() {
+            return Err(ProxyError::ConfigError(
+                "Azure OpenAI 无法确定部署名（请求缺少 model 且未配置默认部署）".to_string(),
+            ));
+        }
+        format!("/openai/deployments/{deployment}{operation}")
+    };
+
+    let mut rewritten = url;
+    rewritten.set_path(&new_path);
+    let query: Vec<(String, String)> = rewritten
+        .query_pairs()
+        .filter(|(k, _)| k != "api-version")
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    rewritten
+        .query_pairs_mut()
+        .clear()
+        .extend_pairs(query)
+        .append_pair("api-version", config.api_version.trim());
+    *request.url_mut() = rewritten;
+
+    // Bearer -> api-key
+    let headers = request.headers_mut();
+    if let Some(auth) = headers.remove(AUTHORIZATION) {
+        let key = auth
+            .to_str()
+            .ok()
+            .map(|v| v.trim_start_matches("Bearer ").trim().to_string())
+            .unwrap_or_default();
+        if !key.is_empty() {
+            let value = HeaderValue::from_str(&key)
+                .map_err(|e| ProxyError::AuthError(format!("无效的 API Key: {e}")))?;
+            headers.insert("api-key", value);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn build(url: &str) -> reqwest::Request {
+        reqwest::Client::new()
+            .post(url)
+            .header("Authorization", "Bearer az-key")
+            .build()
+            .unwrap()
+    }
+
+    fn config() -> AzureOpenAiConfig {
+        AzureOpenAiConfig {
+            deployments: BTreeMap::from([("gpt-4o".to_string(), "prod-4o".to_string())]),
+            ..AzureOpenAiConfig::default()
+        }
+    }
+
+    #[test]
+    fn chat_completions_route_to_deployment() {
+        let mut request = build("https://res.openai.azure.com/openai/v1/chat/completions");
+        prepare_request(&config(), &mut request, &json!({ "model": "gpt-4o" })).unwrap();
+
+        assert_eq!(
+            request.url().as_str(),
+            format!(
+                "https://res.openai.azure.com/openai/deployments/prod-4o/chat/completions?api-version={DEFAULT_AZURE_API_VERSION}"
+            )
+        );
+        assert_eq!(request.headers().get("api-key").unwrap(), "az-key");
+        assert!(request.headers().get("authorization").is_none());
+    }
+
+    #[test]
+    fn responses_put_deployment_in_body() {
+        let mut config = config();
+        config.default_deployment = Some("fallback".to_string());
+        let mut request = build("https://res.openai.azure.com/v1/responses?api-version=old");
+        prepare_request(&config, &mut request, &json!({ "model": "gpt-5" })).unwrap();
+
+        assert_eq!(request.url().path(), "/openai/responses");
+        assert_eq!(
+            request.url().query(),
+            Some(format!("api-version={DEFAULT_AZURE_API_VERSION}").as_str())
+        );
+        let body: Value =
+            serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert_eq!(body["model"], "fallback");
+    }
+}
@@ -0,0 +1,493 @@
+//! AWS Bedrock 请求改写
+//!
+//! - 端点：`/model/{modelId}/invoke`（非流式）与 `/model/{modelId}/invoke-with-response-stream`（流式）
+//! - 认证：AWS SigV4（服务名 `bedrock`）
+//! - 流式响应为 `application/vnd.amazon.eventstream` 二进制帧，每帧 payload 为
+//!   `{"bytes": "<base64 编码的 Anthropic 事件 JSON>"}`，这里还原为 Anthropic SSE
+
+use std::collections::HashMap;
+
+use base64::Engine;
+use bytes::Bytes;
+use futures::StreamExt;
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::Response;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use super::cloud::BedrockConfig;
+use crate::proxy::error::ProxyError;
+
+/// Bedrock Messages API 版本
+const BEDROCK_ANTHROPIC_VERSION: &str = "bedrock-2023-05-31";
+
+/// SigV4 服务名
+const SERVICE: &str = "bedrock";
+
+pub(super) fn base_url(region: &str) -> String {
+    format!("https://bedrock-runtime.{region}.amazonaws.com")
+}
+
+/// 按 Bedrock 命名规则推断模型 ID（claude-xxx-YYYYMMDD -> anthropic.claude-xxx-YYYYMMDD-v1:0）
+pub(super) fn default_model_id(model: &str) -> String {
+    if model.starts_with("claude-") {
+        format!("anthropic.{model}-v1:0")
+    } else {
+        model.to_string()
+    }
+}
+
+/// AWS 规范的 URI 编码（仅保留非保留字符）
+fn uri_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    // HMAC 接受任意长度的密钥，new_from_slice 不会失败
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC 接受任意长度密钥");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// SigV4 凭证
+pub(super) struct SigV4Credentials<'a> {
+    pub access_key_id: &'a str,
+    pub secret_access_key: &'a str,
+    pub session_token: Option<&'a str>,
+}
+
+/// 为请求添加 SigV4 签名（签名 host、x-amz-date 与可选的 x-amz-security-token）
+pub(super) fn sign_request(
+    request: &mut reqwest::Request,
+    credentials: &SigV4Credentials<'_>,
+    region: &str,
+    service: &str,
+    amz_date: &str,
+) -> Result<(), ProxyError> {
+    let date = &amz_date[..8];
+    let payload = request
+        .body()
+        .and_then(|b| b.as_bytes())
+        .unwrap_or_default();
+    let payload_hash = hex::encode(Sha256::digest(payload));
+
+    let url = request.url();
+    let host = match url.port() {
+        Some(port) => format!("{}:{port}", url.host_str().unwrap_or_default()),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+
+    // 非 S3 服务：路径段需在已编码的基础上再编码一次
+    let canonical_uri = url
+        .path()
+        .split('/')
+        .map(uri_encode)
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let mut query: Vec<(String, String)> = url
+        .query_pairs()
+        .map(|(k, v)| (uri_encode(&k), uri_encode(&v)))
+        .collect();
+    query.sort();
+    let canonical_query = query
+        .iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    let mut signed: Vec<(&str, String)> =
+        vec![("host", host), ("x-amz-date", amz_date.to_string())];
+    if let Some(token) = credentials.session_token {
+        signed.push(("x-amz-security-token", token.to_string()));
+    }
+    let canonical_headers: String = signed
+        .iter()
+        .map(|(k, v)| format!("{k}:{}\n", v.trim()))
+        .collect();
+    let signed_headers = signed.iter().map(|(k, _)| *k).collect::<Vec<_>>().join(";");
+
+    let canonical_request = format!(
+        "{}\n{canonical_uri}\n{canonical_query}\n{canonical_headers}\n{signed_headers}\n{payload_hash}",
+        request.method().as_str()
+    );
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let k_date = hmac_sha256(
+        format!("AWS4{}", credentials.secret_access_key).as_bytes(),
+        date,
+    );
+    let k_region = hmac_sha256(&k_date, region);
+    let k_service = hmac_sha256(&k_region, service);
+    let k_signing = hmac_sha256(&k_service, "aws4_request");
+    let signature = hex::encode(hmac_sha256(&k_signing, &string_to_sign));
+
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+        credentials.access_key_id
+    );
+
+    let header = |v: &str| {
+        HeaderValue::from_str(v).map_err(|e| ProxyError::AuthError(format!("SigV4 签名失败: {e}")))
+    };
+    let headers = request.headers_mut();
+    headers.insert("x-amz-date", header(amz_date)?);
+    if let Some(token) = credentials.session_token {
+        headers.insert("x-amz-security-token", header(token)?);
+    }
+    headers.insert(AUTHORIZATION, header(&authorization)?);
+    Ok(())
+}
+
+/// 改写为 Bedrock InvokeModel 请求并签名
+pub(super) fn prepare(
+    config: &BedrockConfig,
+    request: &mut reqwest::Request,
+    mut payload: Value,
+    model_id: &str,
+    stream: bool,
+) -> Result<(), ProxyError> {
+    if config.region.trim().is_empty()
+        || config.access_key_id.trim().is_empty()
+        || config.secret_access_key.trim().is_empty()
+    {
+        return Err(ProxyError::ConfigError(
+            "Bedrock 供应商缺少 region / accessKeyId / secretAccessKey".to_string(),
+        ));
+    }
+
+    // Bedrock 通过请求体而非请求头接收 beta 特性
+    let betas: Vec<String> = request
+        .headers_mut()
+        .remove("anthropic-beta")
+        .and_then(|v| v.to_str().ok().map(str::to_string))
+        .map(|v| {
+            v.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if let Some(obj) = payload.as_object_mut() {
+        obj.remove("stream");
+        obj.insert("anthropic_version".into(), json!(BEDROCK_ANTHROPIC_VERSION));
+        if !betas.is_empty() {
+            obj.insert("anthropic_beta".into(), json!(betas));
+        }
+    }
+
+    let action = if stream {
+        "invoke-with-response-stream"
+    } else {
+        "invoke"
+    };
+    let url = format!(
+        "{}/model/{}/{action}",
+        base_url(config.region.trim()),
+        uri_encode(model_id)
+    );
+    *request.url_mut() = url::Url::parse(&url)
+        .map_err(|e| ProxyError::ConfigError(format!("无效的 Bedrock 端点 {url}: {e}")))?;
+
+    let bytes = serde_json::to_vec(&payload)
+        .map_err(|e| ProxyError::TransformError(format!("序列化 Bedrock 请求失败: {e}")))?;
+    *request.body_mut() = Some(bytes.into());
+
+    let headers = request.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    headers.insert(
+        ACCEPT,
+        HeaderValue::from_static(if stream {
+            "application/vnd.amazon.eventstream"
+        } else {
+            "application/json"
+        }),
+    );
+
+    let credentials = SigV4Credentials {
+        access_key_id: config.access_key_id.trim(),
+        secret_access_key: config.secret_access_key.trim(),
+        session_token: config
+            .session_token
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty()),
+    };
+    let amz_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    sign_request(
+        request,
+        &credentials,
+        config.region.trim(),
+        SERVICE,
+        &amz_date,
+    )
+}
+
+/// AWS event stream 消息（仅保留字符串类型的头）
+#[derive(Debug)]
+pub(super) struct EventMessage {
+    pub headers: HashMap<String, String>,
+    pub payload: Vec<u8>,
+}
+
+/// AWS event stream 增量解码器
+///
+/// 帧格式：total_len(u32) | headers_len(u32) | prelude_crc(u32) | headers | payload | message_crc(u32)。
+/// 传输层已有 TLS 完整性保护，这里不校验 CRC。
+#[derive(Debug, Default)]
+pub(super) struct EventStreamDecoder {
+    buf: Vec<u8>,
+}
+
+impl EventStreamDecoder {
+    pub fn push(&mut self, data: &[u8]) -> Result<Vec<EventMessage>, String> {
+        self.buf.extend_from_slice(data);
+        let mut messages = Vec::new();
+
+        while self.buf.len() >= 12 {
+            let total = u32::from_be_bytes(self.buf[0..4].try_into().unwrap()) as usize;
+            let headers_len = u32::from_be_bytes(self.buf[4..8].try_into().unwrap()) as usize;
+            if total < 16 || 12 + headers_len + 4 > total {
+                return Err(format!("无效的 event stream 帧长度: {total}"));
+            }
+            if self.buf.len() < total {
+                break;
+            }
+
+            let frame: Vec<u8> = self.buf.drain(..total).collect();
+            let headers = parse_headers(&frame[12..12 + headers_len])?;
+            let payload = frame[12 + headers_len..total - 4].to_vec();
+            messages.push(EventMessage { headers, payload });
+        }
+
+        Ok(messages)
+    }
+}
+
+fn parse_headers(mut data: &[u8]) -> Result<HashMap<String, String>, String> {
+    let mut headers = HashMap::new();
+    let truncated = || "event stream 头部被截断".to_string();
+
+    while !data.is_empty() {
+        let name_len = data[0] as usize;
+        let name = data.get(1..1 + name_len).ok_or_else(truncated)?;
+        let name = String::from_utf8_lossy(name).to_string();
+        data = &data[1 + name_len..];
+
+        let value_type = *data.first().ok_or_else(truncated)?;
+        data = &data[1..];
+        let value_len = match value_type {
+            0 | 1 => 0,
+            2 => 1,
+            3 => 2,
+            4 => 4,
+            5 | 8 => 8,
+            9 => 16,
+            6 | 7 => {
+                let len = data.get(0..2).ok_or_else(truncated)?;
+                data = &data[2..];
+                u16::from_be_bytes([len[0], len[1]]) as usize
+            }
+            other => return Err(format!("未知的 event stream 头类型: {other}")),
+        };
+        let value = data.get(..value_len).ok_or_else(truncated)?;
+        if value_type == 7 {
+            headers.insert(name, String::from_utf8_lossy(value).to_string());
+        }
+        data = &data[value_len..];
+    }
+
+    Ok(headers)
+}
+
+/// 将单条 event stream 消息转换为 Anthropic SSE 事件
+pub(super) fn message_to_sse(message: &EventMessage) -> Option<String> {
+    let header = |name: &str| message.headers.get(name).map(String::as_str);
+    let payload: Value = serde_json::from_slice(&message.payload).ok()?;
+
+    match header(":message-type") {
+        Some("event") if header(":event-type") == Some("chunk") => {
+            let encoded = payload.get("bytes")?.as_str()?;
+            let decoded = base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .ok()?;
+            let event: Value = serde_json::from_slice(&decoded).ok()?;
+            let event_type = event.get("type")?.as_str()?.to_string();
+            Some(format!("event: {event_type}\ndata: {event}\n\n"))
+        }
+        Some("exception") => {
+            let error = json!({
+                "type": "error",
+                "error": {
+                    "type": header(":exception-type").unwrap_or("api_error"),
+                    "message": payload.get("message").and_then(|v| v.as_str()).unwrap_or_default(),
+                }
+            });
+            Some(format!("event: error\ndata: {error}\n\n"))
+        }
+        _ => None,
+    }
+}
+
+/// 把 Bedrock 流式响应包装为 Anthropic SSE 响应
+pub(super) fn event_stream_to_sse(response: Response) -> Response {
+    let status = response.status();
+    let mut upstream = response.bytes_stream();
+
+    let stream = async_stream::stream! {
+        let mut decoder = EventStreamDecoder::default();
+        while let Some(chunk) = upstream.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    yield Err(std::io::Error::other(e));
+                    break;
+                }
+            };
+            match decoder.push(&chunk) {
+                Ok(messages) => {
+                    for message in messages {
+                        if let Some(sse) = message_to_sse(&message) {
+                            yield Ok(Bytes::from(sse));
+                        }
+                    }
+                }
+                Err(e) => {
+                    log::error!("[Bedrock] 解析 event stream 失败: {e}");
+                    yield Err(std::io::Error::other(e));
+                    break;
+                }
+            }
+        }
+    };
+
+    let mut translated = axum::http::Response::new(reqwest::Body::wrap_stream(stream));
+    *translated.status_mut() = status;
+    translated
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("text/event-stream"));
+    Response::from(translated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode_frame(headers: &[(&str, &str)], payload: &[u8]) -> Vec<u8> {
+        let mut header_bytes = Vec::new();
+        for (name, value) in headers {
+            header_bytes.push(name.len() as u8);
+            header_bytes.extend_from_slice(name.as_bytes());
+            header_bytes.push(7);
+            header_bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            header_bytes.extend_from_slice(value.as_bytes());
+        }
+        let total = 12 + header_bytes.len() + payload.len() + 4;
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&(total as u32).to_be_bytes());
+        frame.extend_from_slice(&(header_bytes.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&[0; 4]);
+        frame.extend_from_slice(&header_bytes);
+        frame.extend_from_slice(payload);
+        frame.extend_from_slice(&[0; 4]);
+        frame
+    }
+
+    #[test]
+    fn sigv4_matches_aws_get_vanilla_vector() {
+        let mut request = reqwest::Client::new()
+            .get("https://example.amazonaws.com/")
+            .build()
+            .unwrap();
+        let credentials = SigV4Credentials {
+            access_key_id: "AKIDEXAMPLE",
+            secret_access_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            session_token: None,
+        };
+        sign_request(
+            &mut request,
+            &credentials,
+            "us-east-1",
+            "service",
+            "20150830T123600Z",
+        )
+        .unwrap();
+
+        assert_eq!(
+            request.headers().get("authorization").unwrap(),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+             SignedHeaders=host;x-amz-date, \
+             Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn prepare_moves_model_into_path_and_sets_version() {
+        let config = BedrockConfig {
+            region: "us-west-2".to_string(),
+            access_key_id: "AKID".to_string(),
+            secret_access_key: "secret".to_string(),
+            ..BedrockConfig::default()
+        };
+        let mut request = reqwest::Client::new()
+            .post("http://127.0.0.1/v1/messages")
+            .header("anthropic-beta", "a, b")
+            .build()
+            .unwrap();
+        prepare(
+            &config,
+            &mut request,
+            json!({ "max_tokens": 1, "stream": true }),
+            "anthropic.claude-3-5-haiku-20241022-v1:0",
+            true,
+        )
+        .unwrap();
+
+        assert_eq!(
+            request.url().as_str(),
+            "https://bedrock-runtime.us-west-2.amazonaws.com/model/anthropic.claude-3-5-haiku-20241022-v1%3A0/invoke-with-response-stream"
+        );
+        let body: Value =
+            serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert_eq!(body["anthropic_version"], BEDROCK_ANTHROPIC_VERSION);
+        assert_eq!(body["anthropic_beta"], json!(["a", "b"]));
+        assert!(body.get("stream").is_none());
+        assert!(request.headers().contains_key("authorization"));
+    }
+
+    #[test]
+    fn decoder_handles_split_frames_and_converts_chunks() {
+        let event = json!({ "type": "message_stop" }).to_string();
+        let payload = json!({
+            "bytes": base64::engine::general_purpose::STANDARD.encode(event.as_bytes())
+        })
+        .to_string();
+        let frame = encode_frame(
+            &[(":message-type", "event"), (":event-type", "chunk")],
+            payload.as_bytes(),
+        );
+
+        let mut decoder = EventStreamDecoder::default();
+        assert!(decoder.push(&frame[..10]).unwrap().is_empty());
+        let messages = decoder.push(&frame[10..]).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            message_to_sse(&messages[0]).unwrap(),
+            "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n"
+        );
+    }
+}
@@ -6,8 +6,14 @@
 //! - **Claude**: Anthropic 官方 API (x-api-key + anthropic-version)
 //! - **ClaudeAuth**: 中转服务 (仅 Bearer 认证，无 x-api-key)
 //! - **OpenRouter**: 已支持 Claude Code 兼容接口，默认透传（保留旧转换逻辑备用）
-
-use super::{AuthInfo, AuthStrategy, ProviderAdapter, ProviderType};
+//! - **Bedrock / Vertex**: 云厂商托管，请求由 `cloud` 模块改写并签名
+//! - **Local**: 本地 Ollama / llama.cpp，经 Anthropic ↔ OpenAI 转换后发送
+//! - **Grpc**: 自建 gRPC 推理服务，经 Anthropic ↔ OpenAI 转换后由 `grpc` 模块编码为 protobuf
+
+use super::{
+    AuthInfo, AuthStrategy, CloudProviderConfig, GrpcUpstreamConfig, LocalModelConfig,
+    ProviderAdapter, ProviderType,
+};
 use crate::provider::Provider;
 use crate::proxy::error::ProxyError;
 use reqwest::RequestBuilder;
@@ -23,10 +29,27 @@ impl ClaudeAdapter {
     /// 获取供应商类型
     ///
     /// 根据 base_url 和 auth_mode 检测具体的供应商类型：
+    /// - Bedrock / Vertex: ProviderMeta 中配置了云厂商
+    /// - Local: ProviderMeta 中配置了本地模型服务
+    /// - Grpc: ProviderMeta 中配置了 gRPC 上游
     /// - OpenRouter: base_url 包含 openrouter.ai
     /// - ClaudeAuth: auth_mode 为 bearer_only
     /// - Claude: 默认 Anthropic 官方
     pub fn provider_type(&self, provider: &Provider) -> ProviderType {
+        match CloudProviderConfig::of(provider) {
+            Some(CloudProviderConfig::Bedrock(_)) => return ProviderType::Bedrock,
+            Some(CloudProviderConfig::Vertex(_)) => return ProviderType::Vertex,
+            None => {}
+        }
+
+        if LocalModelConfig::of(provider).is_some() {
+            return ProviderType::Local;
+        }
+
+        if GrpcUpstreamConfig::of(provider).is_some() {
+            return ProviderType::Grpc;
+        }
+
         // 检测 OpenRouter
         if self.is_openrouter(provider) {
             return ProviderType::OpenRouter;
@@ -160,6 +183,17 @@ impl ProviderAdapter for ClaudeAdapter {
     }
 
     fn extract_base_url(&self, provider: &Provider) -> Result<String, ProxyError> {
+        // 0. 云厂商托管：使用平台端点
+        if let Some(cloud) = CloudProviderConfig::of(provider) {
+            return Ok(cloud.base_url());
+        }
+        if let Some(local) = LocalModelConfig::of(provider) {
+            return Ok(local.endpoint());
+        }
+        if let Some(grpc) = GrpcUpstreamConfig::of(provider) {
+            return Ok(grpc.endpoint());
+        }
+
         // 1. 从 env 中获取
         if let Some(env) = provider.settings_config.get("env") {
             if let Some(url) = env.get("ANTHROPIC_BASE_URL").and_then(|v| v.as_str()) {
@@ -202,6 +236,26 @@ impl ProviderAdapter for ClaudeAdapter {
         let strategy = match provider_type {
             ProviderType::OpenRouter => AuthStrategy::Bearer,
             ProviderType::ClaudeAuth => AuthStrategy::ClaudeAuth,
+            // 云厂商凭证不在 env 中，用身份标识占位（仅用于日志），真正的认证在请求改写时完成
+            ProviderType::Bedrock => {
+                let cloud = CloudProviderConfig::of(provider)?;
+                return Some(AuthInfo::new(cloud.identity(), AuthStrategy::AwsSigV4));
+            }
+            ProviderType::Vertex => {
+                let cloud = CloudProviderConfig::of(provider)?;
+                return Some(AuthInfo::new(cloud.identity(), AuthStrategy::GoogleOAuth));
+            }
+            // 本地服务无需认证，使用占位 key 以沿用 Bearer 流程
+            ProviderType::Local => {
+                return Some(AuthInfo::new("local".to_string(), AuthStrategy::Bearer));
+            }
+            // 自建服务的 token 可选，作为 authorization metadata 发送
+            ProviderType::Grpc => {
+                let key = self
+                    .extract_key(provider)
+                    .unwrap_or_else(|| "grpc".to_string());
+                return Some(AuthInfo::new(key, AuthStrategy::Bearer));
+            }
             _ => AuthStrategy::Anthropic,
         };
 
@@ -249,7 +303,11 @@ impl ProviderAdapter for ClaudeAdapter {
         // Anthropic ↔ OpenAI 的格式转换。
         //
         // 如果未来需要回退到旧的 OpenAI Chat Completions 方案，可恢复下面这行：
-        self.is_openrouter_compat_enabled(_provider)
+        //
+        // 本地模型服务只提供 OpenAI 兼容接口，gRPC 上游以 OpenAI 格式为中间格式，始终需要转换。
+        LocalModelConfig::of(_provider).is_some()
+            || GrpcUpstreamConfig::of(_provider).is_some()
+            || self.is_openrouter_compat_enabled(_provider)
     }
 
     fn transform_request(
@@ -257,7 +315,12 @@ impl ProviderAdapter for ClaudeAdapter {
         body: serde_json::Value,
         provider: &Provider,
     ) -> Result<serde_json::Value, ProxyError> {
-        super::transform::anthropic_to_openai(body, provider)
+        let mut result = super::transform::anthropic_to_openai(body, provider)?;
+        // 本地服务使用配置的模型
+        if let Some(model) = LocalModelConfig::of(provider).and_then(|l| l.request_model()) {
+            result["model"] = serde_json::json!(model);
+        }
+        Ok(result)
     }
 
     fn transform_response(&self, body: serde_json::Value) -> Result<serde_json::Value, ProxyError> {
@@ -0,0 +1,321 @@
+//! 云厂商托管的 Claude（AWS Bedrock / GCP Vertex AI）
+//!
+//! 这类供应商不提供 Anthropic 兼容的 `/v1/messages` 端点：
+//! - 模型 ID 位于 URL 路径中，且命名规则与 Anthropic 官方不同
+//! - 请求体需去掉 `model` 并携带平台特定的 `anthropic_version`
+//! - 认证分别使用 SigV4 签名与服务账号换取的 OAuth access_token
+//!
+//! Claude Code 仍按 Anthropic 格式请求代理，这里负责把已构建的请求改写为平台请求，
+//! 并在需要时把响应还原为 Anthropic SSE。配置存储在 ProviderMeta.cloud，
+//! 其中的密钥保存时移入保险库（系统钥匙串），meta 中只保留引用 `credentialRef`。
+
+use std::collections::BTreeMap;
+
+use reqwest::{Client, Response};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{bedrock, vertex};
+use crate::provider::Provider;
+use crate::proxy::error::ProxyError;
+use crate::services::vault;
+
+/// 云厂商供应商配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CloudProviderConfig {
+    Bedrock(BedrockConfig),
+    Vertex(VertexConfig),
+}
+
+/// AWS Bedrock 配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BedrockConfig {
+    pub region: String,
+    pub access_key_id: String,
+    /// 仅在填写时出现，保存后移入保险库
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub secret_access_key: String,
+    /// 临时凭证的会话令牌（STS），保存后移入保险库
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_token: Option<String>,
+    /// 模型映射（Claude 模型名 -> Bedrock 模型 ID / 推理配置文件 ID）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub model_map: BTreeMap<String, String>,
+    /// 保险库中凭据的引用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_ref: Option<String>,
+}
+
+/// GCP Vertex AI 配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VertexConfig {
+    pub project_id: String,
+    /// 区域（如 us-east5；`global` 使用全局端点）
+    pub region: String,
+    /// 服务账号密钥 JSON 内容（与 serviceAccountPath 二选一），保存后移入保险库
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_account_key: Option<String>,
+    /// 服务账号密钥文件路径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_account_path: Option<String>,
+    /// 模型映射（Claude 模型名 -> Vertex 模型 ID）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub model_map: BTreeMap<String, String>,
+    /// 保险库中凭据的引用
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_ref: Option<String>,
+}
+
+/// 云厂商配置中的密钥部分（存入保险库）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloudSecrets {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_access_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_token: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_account_key: Option<String>,
+}
+
+impl CloudSecrets {
+    /// 用 `other` 中填写了的字段覆盖当前值（只改其中一项密钥时保留其余项）
+    pub fn merge(&mut self, other: CloudSecrets) {
+        if other.secret_access_key.is_some() {
+            self.secret_access_key = other.secret_access_key;
+        }
+        if other.session_token.is_some() {
+            self.session_token = other.session_token;
+        }
+        if other.service_account_key.is_some() {
+            self.service_account_key = other.service_account_key;
+        }
+    }
+}
+
+fn take_non_empty(value: &mut Option<String>) -> Option<String> {
+    value.take().filter(|v| !v.trim().is_empty())
+}
+
+impl CloudProviderConfig {
+    /// 读取供应商配置的云厂商类型（未配置时为 None）
+    pub fn of(provider: &Provider) -> Option<&Self> {
+        provider.meta.as_ref().and_then(|m| m.cloud.as_ref())
+    }
+
+    /// 平台端点（用于连接复用、保活与日志）
+    pub fn base_url(&self) -> String {
+        match self {
+            Self::Bedrock(c) => bedrock::base_url(&c.region),
+            Self::Vertex(c) => vertex::base_url(&c.region),
+        }
+    }
+
+    /// 用于日志的身份标识（不含密钥）
+    pub fn identity(&self) -> String {
+        match self {
+            Self::Bedrock(c) => c.access_key_id.clone(),
+            Self::Vertex(c) => format!("{}@{}", c.project_id, c.region),
+        }
+    }
+
+    pub fn credential_ref(&self) -> Option<&str> {
+        match self {
+            Self::Bedrock(c) => c.credential_ref.as_deref(),
+            Self::Vertex(c) => c.credential_ref.as_deref(),
+        }
+    }
+
+    pub fn set_credential_ref(&mut self, reference: String) {
+        match self {
+            Self::Bedrock(c) => c.credential_ref = Some(reference),
+            Self::Vertex(c) => c.credential_ref = Some(reference),
+        }
+    }
+
+    /// 取出配置中明文填写的密钥（原字段清空），没有填写时返回 None
+    pub fn take_secrets(&mut self) -> Option<CloudSecrets> {
+        let secrets = match self {
+            Self::Bedrock(c) => CloudSecrets {
+                secret_access_key: Some(std::mem::take(&mut c.secret_access_key))
+                    .filter(|v| !v.trim().is_empty()),
+                session_token: take_non_empty(&mut c.session_token),
+                service_account_key: None,
+            },
+            Self::Vertex(c) => CloudSecrets {
+                service_account_key: take_non_empty(&mut c.service_account_key),
+                ..CloudSecrets::default()
+            },
+        };
+        (secrets != CloudSecrets::default()).then_some(secrets)
+    }
+
+    /// 填入保险库中的密钥（配置中仍有明文时以明文为准）
+    pub fn with_secrets(&self, secrets: &CloudSecrets) -> Self {
+        let mut resolved = self.clone();
+        match &mut resolved {
+            Self::Bedrock(c) => {
+                if c.secret_access_key.trim().is_empty() {
+                    c.secret_access_key = secrets.secret_access_key.clone().unwrap_or_default();
+                }
+                if c.session_token.is_none() {
+                    c.session_token = secrets.session_token.clone();
+                }
+            }
+            Self::Vertex(c) => {
+                if c.service_account_key.is_none() {
+                    c.service_account_key = secrets.service_account_key.clone();
+                }
+            }
+        }
+        resolved
+    }
+
+    /// 将 Claude 模型名映射为平台模型 ID（显式映射优先，其次按平台命名规则推断）
+    pub fn map_model(&self, model: &str) -> String {
+        match self {
+            Self::Bedrock(c) => c
+                .model_map
+                .get(model)
+                .cloned()
+                .unwrap_or_else(|| bedrock::default_model_id(model)),
+            Self::Vertex(c) => c
+                .model_map
+                .get(model)
+                .cloned()
+                .unwrap_or_else(|| vertex::default_model_id(model)),
+        }
+    }
+}
+
+/// 请求体是否为流式
+fn is_stream(body: &Value) -> bool {
+    body.get("stream")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// 把已构建的 Anthropic Messages 请求改写为平台请求（URL、请求体、认证）
+///
+/// 需在其它请求头处理完成后调用：Bedrock 的 SigV4 签名覆盖请求体。
+pub async fn prepare_request(
+    cloud: &CloudProviderConfig,
+    client: &Client,
+    request: &mut reqwest::Request,
+    body: &Value,
+) -> Result<(), ProxyError> {
+    let model = body
+        .get("model")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ProxyError::InvalidRequest("请求缺少 model 字段".to_string()))?;
+    let model_id = cloud.map_model(model);
+    let stream = is_stream(body);
+
+    let resolved;
+    let cloud = match cloud.credential_ref() {
+        Some(reference) => {
+            // 未命中缓存时会访问钥匙串（Linux 上为子进程），不能阻塞运行时线程
+            let reference = reference.to_string();
+            let secrets = tokio::task::spawn_blocking(move || vault::cloud_secrets(&reference))
+                .await
+                .map_err(|e| ProxyError::Internal(e.to_string()))?
+                .map_err(|e| ProxyError::ConfigError(e.to_string()))?;
+            resolved = cloud.with_secrets(&secrets);
+            &resolved
+        }
+        None => cloud,
+    };
+
+    let mut payload = body.clone();
+    if let Some(obj) = payload.as_object_mut() {
+        obj.remove("model");
+    }
+
+    // 平台不识别 Anthropic 的认证与版本头
+    for name in ["authorization", "x-api-key", "anthropic-version"] {
+        request.headers_mut().remove(name);
+    }
+
+    match cloud {
+        CloudProviderConfig::Bedrock(config) => {
+            bedrock::prepare(config, request, payload, &model_id, stream)
+        }
+        CloudProviderConfig::Vertex(config) => {
+            vertex::prepare(config, client, request, payload, &model_id, stream).await
+        }
+    }
+}
+
+/// 将平台响应还原为 Anthropic 格式（目前仅 Bedrock 流式需要转换）
+pub fn adapt_response(cloud: &CloudProviderConfig, response: Response, body: &Value) -> Response {
+    match cloud {
+        CloudProviderConfig::Bedrock(_) if is_stream(body) => {
+            bedrock::event_stream_to_sse(response)
+        }
+        _ => response,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explicit_model_map_takes_precedence() {
+        let mut config = BedrockConfig::default();
+        config.model_map.insert(
+            "claude-sonnet-4-5-20250929".to_string(),
+            "us.anthropic.claude-sonnet-4-5-20250929-v1:0".to_string(),
+        );
+        let cloud = CloudProviderConfig::Bedrock(config);
+        assert_eq!(
+            cloud.map_model("claude-sonnet-4-5-20250929"),
+            "us.anthropic.claude-sonnet-4-5-20250929-v1:0"
+        );
+        assert_eq!(
+            cloud.map_model("claude-3-5-haiku-20241022"),
+            "anthropic.claude-3-5-haiku-20241022-v1:0"
+        );
+    }
+
+    #[test]
+    fn secrets_move_out_of_config() {
+        let mut cloud = CloudProviderConfig::Bedrock(BedrockConfig {
+            region: "us-west-2".to_string(),
+            access_key_id: "AKID".to_string(),
+            secret_access_key: "secret".to_string(),
+            ..BedrockConfig::default()
+        });
+        let secrets = cloud.take_secrets().unwrap();
+        assert_eq!(secrets.secret_access_key.as_deref(), Some("secret"));
+        assert!(cloud.take_secrets().is_none());
+
+        let serialized = serde_json::to_value(&cloud).unwrap();
+        assert!(serialized.get("secretAccessKey").is_none());
+
+        let resolved = cloud.with_secrets(&secrets);
+        assert!(
+            matches!(resolved, CloudProviderConfig::Bedrock(ref c) if c.secret_access_key == "secret")
+        );
+    }
+
+    #[test]
+    fn config_deserializes_with_kind_tag() {
+        let cloud: CloudProviderConfig = serde_json::from_value(serde_json::json!({
+            "kind": "vertex",
+            "projectId": "my-project",
+            "region": "us-east5"
+        }))
+        .unwrap();
+        assert!(
+            matches!(cloud, CloudProviderConfig::Vertex(ref c) if c.project_id == "my-project")
+        );
+        assert_eq!(
+            cloud.base_url(),
+            "https://us-east5-aiplatform.googleapis.com"
+        );
+    }
+}
@@ -1,11 +1,11 @@
 //! Codex (OpenAI) Provider Adapter
 //!
-//! 仅透传模式，支持直连 OpenAI API
+//! 仅透传模式，支持直连 OpenAI API；配置了 gRPC 上游时 Chat Completions 请求由 `grpc` 模块转换
 //!
 //! ## 客户端检测
 //! 支持检测官方 Codex 客户端 (codex_vscode, codex_cli_rs)
 
-use super::{AuthInfo, AuthStrategy, ProviderAdapter};
+use super::{AuthInfo, AuthStrategy, GrpcUpstreamConfig, ProviderAdapter};
 use crate::provider::Provider;
 use crate::proxy::error::ProxyError;
 use regex::Regex;
@@ -86,6 +86,10 @@ impl ProviderAdapter for CodexAdapter {
     }
 
     fn extract_base_url(&self, provider: &Provider) -> Result<String, ProxyError> {
+        if let Some(grpc) = GrpcUpstreamConfig::of(provider) {
+            return Ok(grpc.endpoint());
+        }
+
         // 1. 尝试直接获取 base_url 字段
         if let Some(url) = provider
             .settings_config
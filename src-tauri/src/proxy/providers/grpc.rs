@@ -0,0 +1,799 @@
+//! gRPC 上游（自建推理服务）
+//!
+//! 部分自建推理服务只提供 gRPC 流式接口。请求先统一为 OpenAI Chat Completions 格式
+//! （Claude 请求沿用 Anthropic → OpenAI 转换），这里编码为 protobuf 发往配置的 gRPC 方法，
+//! 再把响应消息还原为 OpenAI 格式（流式为 SSE chunk），之后沿用原有的响应转换链路。
+//! 配置存储在 ProviderMeta.grpc。
+//!
+//! 默认消息定义如下，字段编号与服务不一致时可在 `fields` 中覆盖（编号为 0 表示不发送该字段）：
+//!
+//! ```text
+//! service TextGeneration {
+//!   rpc Generate(GenerateRequest) returns (stream GenerateResponse);
+//! }
+//! message ChatMessage { string role = 1; string content = 2; }
+//! message GenerateRequest {
+//!   string model = 1;
+//!   repeated ChatMessage messages = 2;
+//!   uint32 max_tokens = 3;
+//!   float temperature = 4;
+//!   float top_p = 5;
+//!   repeated string stop = 6;
+//! }
+//! message GenerateResponse {
+//!   string text = 1;           // 本条消息新增的文本
+//!   string finish_reason = 2;  // 最后一条消息非空
+//!   uint32 prompt_tokens = 3;
+//!   uint32 completion_tokens = 4;
+//! }
+//! ```
+//!
+//! 明文端点（`http://`）通过 HTTP/2 prior knowledge（h2c）连接。不支持压缩消息、工具调用与图片。
+//! 响应体按 HTTP/2 帧读取，`grpc-status` 同时检查响应头（Trailers-Only）与结束时的 trailers；
+//! 非零状态或没有 `finish_reason` 就结束的响应按上游错误处理。
+
+use bytes::Bytes;
+use http_body_util::BodyExt;
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use reqwest::Response;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::provider::Provider;
+use crate::proxy::error::ProxyError;
+
+const DEFAULT_METHOD: &str = "/cc_switch.v1.TextGeneration/Generate";
+/// 单条 gRPC 消息的长度上限（与 gRPC 默认的接收上限一致）
+const MAX_MESSAGE_BYTES: usize = 4 * 1024 * 1024;
+
+/// gRPC 上游配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrpcUpstreamConfig {
+    /// 服务地址（如 `http://127.0.0.1:50051`）
+    pub endpoint: String,
+    /// 方法路径（`/包名.服务名/方法名`，未设置时使用默认定义）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    /// 请求中使用的模型名（未设置时沿用请求中的模型）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub fields: GrpcFieldNumbers,
+}
+
+/// protobuf 字段编号
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GrpcFieldNumbers {
+    pub model: u32,
+    pub messages: u32,
+    pub max_tokens: u32,
+    pub temperature: u32,
+    pub top_p: u32,
+    pub stop: u32,
+    /// ChatMessage.role
+    pub role: u32,
+    /// ChatMessage.content
+    pub content: u32,
+    /// GenerateResponse.text
+    pub text: u32,
+    pub finish_reason: u32,
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+}
+
+impl Default for GrpcFieldNumbers {
+    fn default() -> Self {
+        Self {
+            model: 1,
+            messages: 2,
+            max_tokens: 3,
+            temperature: 4,
+            top_p: 5,
+            stop: 6,
+            role: 1,
+            content: 2,
+            text: 1,
+            finish_reason: 2,
+            prompt_tokens: 3,
+            completion_tokens: 4,
+        }
+    }
+}
+
+impl GrpcUpstreamConfig {
+    /// 读取供应商的 gRPC 配置（未配置时为 None）
+    pub fn of(provider: &Provider) -> Option<&Self> {
+        provider.meta.as_ref().and_then(|m| m.grpc.as_ref())
+    }
+
+    /// 服务地址（去掉末尾的 /）
+    pub fn endpoint(&self) -> String {
+        self.endpoint.trim().trim_end_matches('/').to_string()
+    }
+
+    fn method(&self) -> &str {
+        self.method
+            .as_deref()
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+            .unwrap_or(DEFAULT_METHOD)
+    }
+}
+
+// ========== protobuf 编码 ==========
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn put_key(buf: &mut Vec<u8>, field: u32, wire_type: u8) {
+    put_varint(buf, (u64::from(field) << 3) | u64::from(wire_type));
+}
+
+fn put_bytes(buf: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    if field == 0 {
+        return;
+    }
+    put_key(buf, field, 2);
+    put_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+fn put_uint(buf: &mut Vec<u8>, field: u32, value: u64) {
+    if field == 0 {
+        return;
+    }
+    put_key(buf, field, 0);
+    put_varint(buf, value);
+}
+
+fn put_float(buf: &mut Vec<u8>, field: u32, value: f32) {
+    if field == 0 {
+        return;
+    }
+    put_key(buf, field, 5);
+    buf.extend_from_slice(&value.to_le_bytes());
+}
+
+/// 取出消息的纯文本内容（数组形式只保留 text 部分）
+fn message_text(content: Option<&Value>) -> String {
+    match content {
+        Some(Value::String(text)) => text.clone(),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|part| part.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join(""),
+        _ => String::new(),
+    }
+}
+
+/// 把 OpenAI Chat Completions 请求编码为 GenerateRequest
+fn encode_request(config: &GrpcUpstreamConfig, body: &Value) -> Result<Vec<u8>, ProxyError> {
+    let fields = &config.fields;
+    let messages = body
+        .get("messages")
+        .and_then(Value::as_array)
+        .ok_or_else(|| {
+            ProxyError::InvalidRequest("gRPC 上游仅支持 Chat Completions 格式的请求".to_string())
+        })?;
+
+    let mut buf = Vec::new();
+    let model = config
+        .model
+        .as_deref()
+        .or_else(|| body.get("model").and_then(Value::as_str))
+        .unwrap_or_default();
+    if !model.is_empty() {
+        put_bytes(&mut buf, fields.model, model.as_bytes());
+    }
+    for message in messages {
+        let mut encoded = Vec::new();
+        let role = message
+            .get("role")
+            .and_then(Value::as_str)
+            .unwrap_or("user");
+        put_bytes(&mut encoded, fields.role, role.as_bytes());
+        put_bytes(
+            &mut encoded,
+            fields.content,
+            message_text(message.get("content")).as_bytes(),
+        );
+        put_bytes(&mut buf, fields.messages, &encoded);
+    }
+    let max_tokens = body
+        .get("max_tokens")
+        .or_else(|| body.get("max_completion_tokens"))
+        .and_then(Value::as_u64);
+    if let Some(max_tokens) = max_tokens {
+        put_uint(&mut buf, fields.max_tokens, max_tokens);
+    }
+    if let Some(temperature) = body.get("temperature").and_then(Value::as_f64) {
+        put_float(&mut buf, fields.temperature, temperature as f32);
+    }
+    if let Some(top_p) = body.get("top_p").and_then(Value::as_f64) {
+        put_float(&mut buf, fields.top_p, top_p as f32);
+    }
+    match body.get("stop") {
+        Some(Value::String(stop)) => put_bytes(&mut buf, fields.stop, stop.as_bytes()),
+        Some(Value::Array(stops)) => {
+            for stop in stops.iter().filter_map(Value::as_str) {
+                put_bytes(&mut buf, fields.stop, stop.as_bytes());
+            }
+        }
+        _ => {}
+    }
+
+    // gRPC 长度前缀帧：压缩标记(u8) | 长度(u32 BE) | 消息
+    let mut frame = Vec::with_capacity(buf.len() + 5);
+    frame.push(0);
+    frame.extend_from_slice(&(buf.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&buf);
+    Ok(frame)
+}
+
+// ========== protobuf 解码 ==========
+
+/// 一条 GenerateResponse
+#[derive(Debug, Default, PartialEq)]
+struct GenerateChunk {
+    text: String,
+    finish_reason: Option<String>,
+    prompt_tokens: Option<u64>,
+    completion_tokens: Option<u64>,
+}
+
+fn read_varint(data: &mut &[u8]) -> Result<u64, String> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = data.split_first().ok_or("protobuf varint 被截断")?;
+        *data = rest;
+        value |= u64::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("protobuf varint 过长".to_string())
+}
+
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8], String> {
+    if data.len() < len {
+        return Err("protobuf 字段被截断".to_string());
+    }
+    let (head, rest) = data.split_at(len);
+    *data = rest;
+    Ok(head)
+}
+
+fn decode_chunk(fields: &GrpcFieldNumbers, mut data: &[u8]) -> Result<GenerateChunk, String> {
+    let mut chunk = GenerateChunk::default();
+    while !data.is_empty() {
+        let key = read_varint(&mut data)?;
+        let (field, wire_type) = ((key >> 3) as u32, (key & 0x7) as u8);
+        match wire_type {
+            0 => {
+                let value = read_varint(&mut data)?;
+                if field == fields.prompt_tokens {
+                    chunk.prompt_tokens = Some(value);
+                } else if field == fields.completion_tokens {
+                    chunk.completion_tokens = Some(value);
+                }
+            }
+            1 => {
+                take(&mut data, 8)?;
+            }
+            2 => {
+                let len = read_varint(&mut data)? as usize;
+                let value = String::from_utf8_lossy(take(&mut data, len)?).to_string();
+                if field == fields.text {
+                    chunk.text.push_str(&value);
+                } else if field == fields.finish_reason && !value.is_empty() {
+                    chunk.finish_reason = Some(value);
+                }
+            }
+            5 => {
+                take(&mut data, 4)?;
+            }
+            other => return Err(format!("不支持的 protobuf 字段类型: {other}")),
+        }
+    }
+    Ok(chunk)
+}
+
+/// gRPC 长度前缀帧的增量解码器
+#[derive(Debug, Default)]
+struct FrameDecoder {
+    buf: Vec<u8>,
+}
+
+impl FrameDecoder {
+    fn push(&mut self, data: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+        self.buf.extend_from_slice(data);
+        let mut messages = Vec::new();
+        while self.buf.len() >= 5 {
+            if self.buf[0] != 0 {
+                return Err("上游返回了压缩的 gRPC 消息".to_string());
+            }
+            let len = u32::from_be_bytes(self.buf[1..5].try_into().unwrap()) as usize;
+            if len > MAX_MESSAGE_BYTES {
+                return Err(format!("gRPC 消息长度 {len} 超过上限 {MAX_MESSAGE_BYTES}"));
+            }
+            if self.buf.len() < 5 + len {
+                break;
+            }
+            let frame: Vec<u8> = self.buf.drain(..5 + len).collect();
+            messages.push(frame[5..].to_vec());
+        }
+        Ok(messages)
+    }
+}
+
+// ========== 请求与响应 ==========
+
+/// 请求体是否为流式
+fn is_stream(body: &Value) -> bool {
+    body.get("stream")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// 把已构建的 Chat Completions 请求改写为 gRPC 请求（URL、请求体、请求头）
+pub fn prepare_request(
+    config: &GrpcUpstreamConfig,
+    request: &mut reqwest::Request,
+    body: &Value,
+) -> Result<(), ProxyError> {
+    let endpoint = config.endpoint();
+    if endpoint.is_empty() {
+        return Err(ProxyError::ConfigError(
+            "gRPC 供应商缺少 endpoint".to_string(),
+        ));
+    }
+    let url = format!("{endpoint}{}", config.method());
+    *request.url_mut() = url::Url::parse(&url)
+        .map_err(|e| ProxyError::ConfigError(format!("无效的 gRPC 地址 {url}: {e}")))?;
+    *request.body_mut() = Some(encode_request(config, body)?.into());
+
+    let headers = request.headers_mut();
+    // 认证头作为 gRPC metadata 保留，其余 HTTP API 专用的头去掉
+    for name in ["x-api-key", "anthropic-version", "anthropic-beta", "accept"] {
+        headers.remove(name);
+    }
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/grpc"));
+    headers.insert("te", HeaderValue::from_static("trailers"));
+    headers.insert("grpc-accept-encoding", HeaderValue::from_static("identity"));
+    Ok(())
+}
+
+/// gRPC 状态码对应的 HTTP 状态码
+fn http_status_for(code: u32) -> u16 {
+    match code {
+        3 | 9 | 11 => 400,
+        16 => 401,
+        7 => 403,
+        5 => 404,
+        8 => 429,
+        12 => 501,
+        14 => 503,
+        4 => 504,
+        _ => 502,
+    }
+}
+
+/// grpc-message 按百分号编码传输
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+/// 响应头（Trailers-Only）或 trailers 中的错误状态
+fn status_error(headers: &HeaderMap) -> Option<ProxyError> {
+    let code: u32 = headers
+        .get("grpc-status")?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    if code == 0 {
+        return None;
+    }
+    let message = headers
+        .get("grpc-message")
+        .and_then(|v| v.to_str().ok())
+        .map(percent_decode)
+        .unwrap_or_default();
+    Some(ProxyError::UpstreamError {
+        status: http_status_for(code),
+        body: Some(
+            json!({ "error": { "message": format!("gRPC {code}: {message}") } }).to_string(),
+        ),
+        headers: Default::default(),
+    })
+}
+
+fn normalize_finish_reason(reason: &str) -> &'static str {
+    match reason.to_ascii_lowercase().as_str() {
+        "length" | "max_tokens" | "max_length" => "length",
+        _ => "stop",
+    }
+}
+
+fn usage_json(chunk: &GenerateChunk) -> Option<Value> {
+    if chunk.prompt_tokens.is_none() && chunk.completion_tokens.is_none() {
+        return None;
+    }
+    let prompt = chunk.prompt_tokens.unwrap_or_default();
+    let completion = chunk.completion_tokens.unwrap_or_default();
+    Some(json!({
+        "prompt_tokens": prompt,
+        "completion_tokens": completion,
+        "total_tokens": prompt + completion,
+    }))
+}
+
+/// 单条 GenerateResponse 对应的 OpenAI 流式 chunk
+fn chunk_to_sse(id: &str, model: &str, chunk: &GenerateChunk, first: bool) -> String {
+    let mut delta = json!({ "content": chunk.text });
+    if first {
+        delta["role"] = json!("assistant");
+    }
+    let mut event = json!({
+        "id": id,
+        "object": "chat.completion.chunk",
+        "created": chrono::Utc::now().timestamp(),
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "delta": delta,
+            "finish_reason": chunk.finish_reason.as_deref().map(normalize_finish_reason),
+        }],
+    });
+    if let Some(usage) = usage_json(chunk) {
+        event["usage"] = usage;
+    }
+    format!("data: {event}\n\n")
+}
+
+/// 合并全部 GenerateResponse 为 OpenAI 非流式响应
+fn completion_json(id: &str, model: &str, chunks: &[GenerateChunk]) -> Value {
+    let text: String = chunks.iter().map(|c| c.text.as_str()).collect();
+    let finish_reason = chunks
+        .iter()
+        .rev()
+        .find_map(|c| c.finish_reason.as_deref())
+        .map_or("stop", normalize_finish_reason);
+    let mut last = GenerateChunk::default();
+    for chunk in chunks {
+        last.prompt_tokens = chunk.prompt_tokens.or(last.prompt_tokens);
+        last.completion_tokens = chunk.completion_tokens.or(last.completion_tokens);
+    }
+    let mut completion = json!({
+        "id": id,
+        "object": "chat.completion",
+        "created": chrono::Utc::now().timestamp(),
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": text },
+            "finish_reason": finish_reason,
+        }],
+    });
+    if let Some(usage) = usage_json(&last) {
+        completion["usage"] = usage;
+    }
+    completion
+}
+
+fn rebuild(
+    status: reqwest::StatusCode,
+    body: reqwest::Body,
+    content_type: &'static str,
+) -> Response {
+    let mut translated = axum::http::Response::new(body);
+    *translated.status_mut() = status;
+    translated
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    Response::from(translated)
+}
+
+/// 逐帧读取的 gRPC 响应消息
+struct MessageReader {
+    body: reqwest::Body,
+    decoder: FrameDecoder,
+    fields: GrpcFieldNumbers,
+    finished: bool,
+}
+
+impl MessageReader {
+    fn new(response: Response, fields: GrpcFieldNumbers) -> Self {
+        Self {
+            body: response.into(),
+            decoder: FrameDecoder::default(),
+            fields,
+            finished: false,
+        }
+    }
+
+    /// 读取下一批消息；响应结束时返回 None，trailers 带错误状态或缺少结束原因时返回错误
+    async fn next(&mut self) -> Option<Result<Vec<GenerateChunk>, ProxyError>> {
+        let frame = match self.body.frame().await {
+            Some(Ok(frame)) => frame,
+            Some(Err(e)) => {
+                return Some(Err(ProxyError::ForwardFailed(format!(
+                    "读取 gRPC 响应失败: {e}"
+                ))))
+            }
+            None if self.finished => return None,
+            None => {
+                return Some(Err(ProxyError::ForwardFailed(
+                    "gRPC 响应在生成结束前中断（没有 finish_reason）".to_string(),
+                )))
+            }
+        };
+        let data = match frame.into_data() {
+            Ok(data) => data,
+            Err(frame) => {
+                let error = frame.trailers_ref().and_then(status_error);
+                return Some(error.map_or_else(|| Ok(Vec::new()), Err));
+            }
+        };
+        let chunks = self.decoder.push(&data).and_then(|frames| {
+            frames
+                .iter()
+                .map(|frame| decode_chunk(&self.fields, frame))
+                .collect::<Result<Vec<_>, _>>()
+        });
+        Some(match chunks {
+            Ok(chunks) => {
+                self.finished |= chunks.iter().any(|c| c.finish_reason.is_some());
+                Ok(chunks)
+            }
+            Err(e) => Err(ProxyError::TransformError(e)),
+        })
+    }
+}
+
+/// 将 gRPC 响应还原为 OpenAI Chat Completions 响应（流式为 SSE）
+pub async fn adapt_response(
+    config: &GrpcUpstreamConfig,
+    response: Response,
+    body: &Value,
+) -> Result<Response, ProxyError> {
+    if let Some(error) = status_error(response.headers()) {
+        return Err(error);
+    }
+    let status = response.status();
+    let id = format!("chatcmpl-grpc-{}", uuid::Uuid::new_v4().simple());
+    let model = config
+        .model
+        .clone()
+        .or_else(|| {
+            body.get("model")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        })
+        .unwrap_or_default();
+    let mut reader = MessageReader::new(response, config.fields);
+
+    if !is_stream(body) {
+        let mut chunks = Vec::new();
+        while let Some(batch) = reader.next().await {
+            chunks.extend(batch?);
+        }
+        let completion = completion_json(&id, &model, &chunks);
+        return Ok(rebuild(
+            status,
+            completion.to_string().into(),
+            "application/json",
+        ));
+    }
+
+    let stream = async_stream::stream! {
+        let mut first = true;
+        while let Some(batch) = reader.next().await {
+            let chunks = match batch {
+                Ok(chunks) => chunks,
+                Err(e) => {
+                    log::error!("[gRPC] 流式响应失败: {e}");
+                    yield Err(std::io::Error::other(e.to_string()));
+                    return;
+                }
+            };
+            for message in chunks {
+                yield Ok(Bytes::from(chunk_to_sse(&id, &model, &message, first)));
+                first = false;
+            }
+        }
+        yield Ok(Bytes::from_static(b"data: [DONE]\n\n"));
+    };
+    Ok(rebuild(
+        status,
+        reqwest::Body::wrap_stream(stream),
+        "text/event-stream",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode_chunk(text: &str, finish_reason: &str, completion_tokens: u64) -> Vec<u8> {
+        let mut message = Vec::new();
+        put_bytes(&mut message, 1, text.as_bytes());
+        if !finish_reason.is_empty() {
+            put_bytes(&mut message, 2, finish_reason.as_bytes());
+            put_uint(&mut message, 3, 12);
+            put_uint(&mut message, 4, completion_tokens);
+        }
+        let mut frame = vec![0];
+        frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&message);
+        frame
+    }
+
+    #[test]
+    fn encodes_chat_request_with_configured_fields() {
+        let config = GrpcUpstreamConfig {
+            endpoint: "http://127.0.0.1:50051".into(),
+            model: Some("qwen".into()),
+            ..Default::default()
+        };
+        let body = json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [
+                { "role": "system", "content": "be brief" },
+                { "role": "user", "content": [{ "type": "text", "text": "hi" }] }
+            ],
+            "max_tokens": 300,
+            "temperature": 0.5,
+            "stop": "END"
+        });
+        let frame = encode_request(&config, &body).unwrap();
+        assert_eq!(frame[0], 0);
+        assert_eq!(
+            u32::from_be_bytes(frame[1..5].try_into().unwrap()) as usize,
+            frame.len() - 5
+        );
+
+        let message = &frame[5..];
+        // model = "qwen"
+        assert_eq!(&message[..6], b"\x0a\x04qwen");
+        // messages[1] = { role: "user", content: "hi" }
+        assert!(message
+            .windows(10)
+            .any(|w| w == b"\x12\x0a\x0a\x04user\x12\x02"));
+        // max_tokens = 300（varint 0xAC 0x02）
+        assert!(message.windows(3).any(|w| w == [0x18, 0xAC, 0x02]));
+        let temperature = [&[0x25u8][..], &0.5f32.to_le_bytes()[..]].concat();
+        assert!(message.windows(5).any(|w| w == temperature.as_slice()));
+        assert!(message.ends_with(b"\x32\x03END"));
+
+        assert_eq!(
+            percent_decode("model%20not%20found%E2%9C%93"),
+            "model not found✓"
+        );
+
+        let missing = encode_request(&config, &json!({ "input": "hi" }));
+        assert!(matches!(missing, Err(ProxyError::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn decodes_split_frames_into_openai_chunks() {
+        let fields = GrpcFieldNumbers::default();
+        let mut stream = encode_chunk("Hel", "", 0);
+        stream.extend(encode_chunk("lo", "max_tokens", 2));
+
+        let mut decoder = FrameDecoder::default();
+        let (head, tail) = stream.split_at(7);
+        let mut frames = decoder.push(head).unwrap();
+        assert!(frames.is_empty());
+        frames.extend(decoder.push(tail).unwrap());
+        let chunks: Vec<_> = frames
+            .iter()
+            .map(|f| decode_chunk(&fields, f).unwrap())
+            .collect();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[1].finish_reason.as_deref(), Some("max_tokens"));
+
+        let first: Value = serde_json::from_str(
+            chunk_to_sse("id", "qwen", &chunks[0], true)
+                .trim_start_matches("data: ")
+                .trim(),
+        )
+        .unwrap();
+        assert_eq!(first["choices"][0]["delta"]["role"], "assistant");
+        assert!(first["choices"][0]["finish_reason"].is_null());
+
+        let completion = completion_json("id", "qwen", &chunks);
+        assert_eq!(completion["choices"][0]["message"]["content"], "Hello");
+        assert_eq!(completion["choices"][0]["finish_reason"], "length");
+        assert_eq!(completion["usage"]["total_tokens"], 14);
+    }
+
+    #[test]
+    fn rejects_oversized_frames() {
+        let mut frame = vec![0];
+        frame.extend_from_slice(&(MAX_MESSAGE_BYTES as u32 + 1).to_be_bytes());
+        assert!(FrameDecoder::default().push(&frame).is_err());
+    }
+
+    /// 由 HTTP/2 帧（数据与 trailers）组成的 gRPC 响应
+    fn grpc_response(frames: Vec<hyper::body::Frame<Bytes>>) -> Response {
+        let body = http_body_util::StreamBody::new(futures::stream::iter(
+            frames.into_iter().map(Ok::<_, std::io::Error>),
+        ));
+        Response::from(axum::http::Response::new(reqwest::Body::wrap(body)))
+    }
+
+    fn trailers(status: &str, message: &str) -> hyper::body::Frame<Bytes> {
+        let mut trailers = HeaderMap::new();
+        trailers.insert("grpc-status", HeaderValue::from_str(status).unwrap());
+        trailers.insert("grpc-message", HeaderValue::from_str(message).unwrap());
+        hyper::body::Frame::trailers(trailers)
+    }
+
+    async fn read_all(response: Response) -> Result<Vec<GenerateChunk>, ProxyError> {
+        let mut reader = MessageReader::new(response, GrpcFieldNumbers::default());
+        let mut chunks = Vec::new();
+        while let Some(batch) = reader.next().await {
+            chunks.extend(batch?);
+        }
+        Ok(chunks)
+    }
+
+    #[tokio::test]
+    async fn trailers_and_missing_finish_reason_are_errors() {
+        let ok = grpc_response(vec![
+            hyper::body::Frame::data(Bytes::from(encode_chunk("hi", "stop", 1))),
+            trailers("0", ""),
+        ]);
+        assert_eq!(read_all(ok).await.unwrap().len(), 1);
+
+        let failed = grpc_response(vec![
+            hyper::body::Frame::data(Bytes::from(encode_chunk("hi", "", 0))),
+            trailers("8", "quota%20exhausted"),
+        ]);
+        match read_all(failed).await {
+            Err(ProxyError::UpstreamError { status, body, .. }) => {
+                assert_eq!(status, 429);
+                assert!(body.unwrap().contains("quota exhausted"));
+            }
+            other => panic!("expected upstream error, got {other:?}"),
+        }
+
+        let truncated = grpc_response(vec![
+            hyper::body::Frame::data(Bytes::from(encode_chunk("hi", "", 0))),
+            trailers("0", ""),
+        ]);
+        assert!(matches!(
+            read_all(truncated).await,
+            Err(ProxyError::ForwardFailed(_))
+        ));
+    }
+}
@@ -0,0 +1,116 @@
+//! 本地模型供应商（Ollama / llama.cpp）
+//!
+//! 两者都提供 OpenAI 兼容的 `/v1/chat/completions`，Claude 请求沿用
+//! Anthropic ↔ OpenAI 转换链路发送到本地服务，便于离线时回退到本地模型。
+//! 配置存储在 ProviderMeta.local。
+
+use serde::{Deserialize, Serialize};
+
+use crate::provider::Provider;
+
+/// 本地推理运行时
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LocalRuntime {
+    #[default]
+    Ollama,
+    LlamaCpp,
+}
+
+impl LocalRuntime {
+    /// 默认监听地址
+    pub fn default_endpoint(&self) -> &'static str {
+        match self {
+            Self::Ollama => "http://127.0.0.1:11434",
+            Self::LlamaCpp => "http://127.0.0.1:8080",
+        }
+    }
+
+    /// 默认可执行文件名
+    pub fn default_command(&self) -> &'static str {
+        match self {
+            Self::Ollama => "ollama",
+            Self::LlamaCpp => "llama-server",
+        }
+    }
+}
+
+/// 本地模型配置
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalModelConfig {
+    #[serde(default)]
+    pub runtime: LocalRuntime,
+    /// 服务地址（未设置时使用运行时默认地址）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    /// 使用的模型（Ollama 模型名；llama.cpp 为 GGUF 文件路径）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// 是否由 cc-switch 启动 / 停止服务进程
+    #[serde(default)]
+    pub managed: bool,
+    /// 自定义可执行文件路径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// 附加启动参数
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+}
+
+impl LocalModelConfig {
+    /// 读取供应商的本地模型配置（未配置时为 None）
+    pub fn of(provider: &Provider) -> Option<&Self> {
+        provider.meta.as_ref().and_then(|m| m.local.as_ref())
+    }
+
+    /// 服务地址（去掉末尾的 / 与 /v1）
+    pub fn endpoint(&self) -> String {
+        let endpoint = self
+            .endpoint
+            .as_deref()
+            .map(str::trim)
+            .filter(|e| !e.is_empty())
+            .unwrap_or(self.runtime.default_endpoint())
+            .trim_end_matches('/');
+        endpoint.strip_suffix("/v1").unwrap_or(endpoint).to_string()
+    }
+
+    /// 请求中使用的模型名
+    ///
+    /// llama.cpp 只加载一个模型，model 字段会被忽略，这里返回文件名便于日志辨认。
+    pub fn request_model(&self) -> Option<String> {
+        let model = self
+            .model
+            .as_deref()
+            .map(str::trim)
+            .filter(|m| !m.is_empty())?;
+        Some(match self.runtime {
+            LocalRuntime::Ollama => model.to_string(),
+            LocalRuntime::LlamaCpp => std::path::Path::new(model)
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| model.to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoint_defaults_and_strips_v1() {
+        let config = LocalModelConfig::default();
+        assert_eq!(config.endpoint(), "http://127.0.0.1:11434");
+
+        let config = LocalModelConfig {
+            runtime: LocalRuntime::LlamaCpp,
+            endpoint: Some("http://localhost:9000/v1/".to_string()),
+            model: Some("/models/qwen2.5-coder-7b.gguf".to_string()),
+            ..LocalModelConfig::default()
+        };
+        assert_eq!(config.endpoint(), "http://localhost:9000");
+        assert_eq!(config.request_model().as_deref(), Some("qwen2.5-coder-7b"));
+    }
+}
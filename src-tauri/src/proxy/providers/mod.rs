@@ -5,20 +5,31 @@
 //! ## 模块结构
 //! - `adapter`: 定义 `ProviderAdapter` trait
 //! - `auth`: 认证类型和策略
+//! - `azure`: Azure OpenAI 部署路由
+//! - `bedrock` / `vertex` / `cloud`: 云厂商托管的 Claude（AWS Bedrock / GCP Vertex AI）
 //! - `claude`: Claude (Anthropic) 适配器
 //! - `codex`: Codex (OpenAI) 适配器
 //! - `gemini`: Gemini (Google) 适配器
+//! - `grpc`: gRPC 上游（自建推理服务，protobuf ↔ OpenAI 格式）
+//! - `local`: 本地模型服务（Ollama / llama.cpp）
 //! - `models`: API 数据模型
 //! - `transform`: 格式转换
 
 mod adapter;
 mod auth;
+mod azure;
+mod bedrock;
 mod claude;
+mod cloud;
 mod codex;
 mod gemini;
+mod grpc;
+mod local;
+#[allow(dead_code)]
 pub mod models;
 pub mod streaming;
 pub mod transform;
+mod vertex;
 
 use crate::app_config::AppType;
 use crate::provider::Provider;
@@ -27,9 +38,19 @@ use serde::{Deserialize, Serialize};
 // 公开导出
 pub use adapter::ProviderAdapter;
 pub use auth::{AuthInfo, AuthStrategy};
+pub use azure::{prepare_request as prepare_azure_request, AzureOpenAiConfig};
 pub use claude::ClaudeAdapter;
+pub use cloud::{
+    adapt_response as adapt_cloud_response, prepare_request as prepare_cloud_request,
+    CloudProviderConfig, CloudSecrets,
+};
 pub use codex::CodexAdapter;
 pub use gemini::GeminiAdapter;
+pub use grpc::{
+    adapt_response as adapt_grpc_response, prepare_request as prepare_grpc_request,
+    GrpcUpstreamConfig,
+};
+pub use local::{LocalModelConfig, LocalRuntime};
 
 /// 供应商类型枚举
 ///
@@ -50,6 +71,14 @@ pub enum ProviderType {
     GeminiCli,
     /// OpenRouter（已支持 Claude Code 兼容接口，默认透传；保留旧转换逻辑备用）
     OpenRouter,
+    /// AWS Bedrock 托管的 Claude（SigV4 签名）
+    Bedrock,
+    /// GCP Vertex AI 托管的 Claude（服务账号 OAuth）
+    Vertex,
+    /// 本地模型服务（Ollama / llama.cpp，OpenAI 兼容接口）
+    Local,
+    /// gRPC 上游（自建推理服务）
+    Grpc,
 }
 
 impl ProviderType {
@@ -75,6 +104,10 @@ impl ProviderType {
                 "https://generativelanguage.googleapis.com"
             }
             ProviderType::OpenRouter => "https://openrouter.ai/api",
+            ProviderType::Bedrock => "https://bedrock-runtime.us-east-1.amazonaws.com",
+            ProviderType::Vertex => "https://aiplatform.googleapis.com",
+            ProviderType::Local => "http://127.0.0.1:11434",
+            ProviderType::Grpc => "http://127.0.0.1:50051",
         }
     }
 
@@ -85,6 +118,18 @@ impl ProviderType {
     pub fn from_app_type_and_config(app_type: &AppType, provider: &Provider) -> Self {
         match app_type {
             AppType::Claude => {
+                // 检测是否为云厂商托管
+                match CloudProviderConfig::of(provider) {
+                    Some(CloudProviderConfig::Bedrock(_)) => return ProviderType::Bedrock,
+                    Some(CloudProviderConfig::Vertex(_)) => return ProviderType::Vertex,
+                    None => {}
+                }
+                if LocalModelConfig::of(provider).is_some() {
+                    return ProviderType::Local;
+                }
+                if GrpcUpstreamConfig::of(provider).is_some() {
+                    return ProviderType::Grpc;
+                }
                 // 检测是否为 OpenRouter
                 let adapter = ClaudeAdapter::new();
                 if let Ok(base_url) = adapter.extract_base_url(provider) {
@@ -144,6 +189,10 @@ impl ProviderType {
             ProviderType::Gemini => "gemini",
             ProviderType::GeminiCli => "gemini_cli",
             ProviderType::OpenRouter => "openrouter",
+            ProviderType::Bedrock => "bedrock",
+            ProviderType::Vertex => "vertex",
+            ProviderType::Local => "local",
+            ProviderType::Grpc => "grpc",
         }
     }
 }
@@ -165,6 +214,10 @@ impl std::str::FromStr for ProviderType {
             "gemini" => Ok(ProviderType::Gemini),
             "gemini_cli" | "gemini-cli" => Ok(ProviderType::GeminiCli),
             "openrouter" => Ok(ProviderType::OpenRouter),
+            "bedrock" => Ok(ProviderType::Bedrock),
+            "vertex" => Ok(ProviderType::Vertex),
+            "local" => Ok(ProviderType::Local),
+            "grpc" => Ok(ProviderType::Grpc),
             _ => Err(format!("Invalid provider type: {s}")),
         }
     }
@@ -183,9 +236,13 @@ pub fn get_adapter(app_type: &AppType) -> Box<dyn ProviderAdapter> {
 #[allow(dead_code)]
 pub fn get_adapter_for_provider_type(provider_type: &ProviderType) -> Box<dyn ProviderAdapter> {
     match provider_type {
-        ProviderType::Claude | ProviderType::ClaudeAuth | ProviderType::OpenRouter => {
-            Box::new(ClaudeAdapter::new())
-        }
+        ProviderType::Claude
+        | ProviderType::ClaudeAuth
+        | ProviderType::OpenRouter
+        | ProviderType::Bedrock
+        | ProviderType::Vertex
+        | ProviderType::Local
+        | ProviderType::Grpc => Box::new(ClaudeAdapter::new()),
         ProviderType::Codex => Box::new(CodexAdapter::new()),
         ProviderType::Gemini | ProviderType::GeminiCli => Box::new(GeminiAdapter::new()),
     }
@@ -7,6 +7,8 @@ use futures::stream::{Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
+use crate::proxy::log_scrubber;
+
 /// OpenAI 流式响应数据结构
 #[derive(Debug, Deserialize)]
 struct OpenAIStreamChunk {
@@ -110,10 +112,10 @@ pub fn create_anthropic_sse_stream(
                                     if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(data) {
                                         log::info!(
                                             "[Claude/OpenRouter] <<< OpenAI SSE 事件:\n{}",
-                                            serde_json::to_string_pretty(&json_value).unwrap_or_else(|_| data.to_string())
+                                            log_scrubber::body_for_log(&json_value)
                                         );
                                     } else {
-                                        log::info!("[Claude/OpenRouter] <<< OpenAI SSE 数据: {data}");
+                                        log::info!("[Claude/OpenRouter] <<< OpenAI SSE 数据: {}", log_scrubber::text_for_log(data));
                                     }
 
                                     if message_id.is_none() {
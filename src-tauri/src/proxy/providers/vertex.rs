@@ -0,0 +1,246 @@
+//! GCP Vertex AI 请求改写
+//!
+//! - 端点：`.../publishers/anthropic/models/{model}:streamRawPredict`（流式）与 `:rawPredict`
+//! - 认证：服务账号私钥签发 JWT，换取 OAuth access_token（按服务账号缓存，过期前自动刷新）
+//! - 响应本身就是 Anthropic 格式，无需转换
+
+use std::collections::HashMap;
+
+use base64::Engine;
+use once_cell::sync::Lazy;
+use reqwest::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::Mutex;
+
+use super::cloud::VertexConfig;
+use crate::proxy::error::ProxyError;
+
+/// Vertex Messages API 版本
+const VERTEX_ANTHROPIC_VERSION: &str = "vertex-2023-10-16";
+
+const GOOGLE_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+const CLOUD_PLATFORM_SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+const JWT_BEARER_GRANT: &str = "urn:ietf:params:oauth:grant-type:jwt-bearer";
+
+/// access_token 提前刷新的余量（秒）
+const TOKEN_REFRESH_MARGIN_SECS: i64 = 60;
+
+/// access_token 缓存：client_email -> (token, 过期时间戳)
+static TOKEN_CACHE: Lazy<Mutex<HashMap<String, (String, i64)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub(super) fn base_url(region: &str) -> String {
+    match region.trim() {
+        "" | "global" => "https://aiplatform.googleapis.com".to_string(),
+        region => format!("https://{region}-aiplatform.googleapis.com"),
+    }
+}
+
+/// 按 Vertex 命名规则推断模型 ID（claude-xxx-YYYYMMDD -> claude-xxx@YYYYMMDD）
+pub(super) fn default_model_id(model: &str) -> String {
+    if model.contains('@') {
+        return model.to_string();
+    }
+    match model.rsplit_once('-') {
+        Some((name, date)) if date.len() == 8 && date.chars().all(|c| c.is_ascii_digit()) => {
+            format!("{name}@{date}")
+        }
+        _ => model.to_string(),
+    }
+}
+
+/// 服务账号密钥（只读取签发 JWT 所需字段）
+#[derive(Debug, Deserialize)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    #[serde(default)]
+    token_uri: Option<String>,
+}
+
+fn load_service_account(config: &VertexConfig) -> Result<ServiceAccountKey, ProxyError> {
+    let raw = match (
+        config.service_account_key.as_deref().map(str::trim),
+        config.service_account_path.as_deref().map(str::trim),
+    ) {
+        (Some(key), _) if !key.is_empty() => key.to_string(),
+        (_, Some(path)) if !path.is_empty() => std::fs::read_to_string(path)
+            .map_err(|e| ProxyError::ConfigError(format!("读取服务账号文件失败 ({path}): {e}")))?,
+        _ => {
+            return Err(ProxyError::ConfigError(
+                "Vertex 供应商缺少服务账号密钥".to_string(),
+            ))
+        }
+    };
+    serde_json::from_str(&raw)
+        .map_err(|e| ProxyError::ConfigError(format!("解析服务账号密钥失败: {e}")))
+}
+
+/// 用服务账号私钥签发 RS256 JWT
+fn build_jwt(key: &ServiceAccountKey, token_uri: &str, now: i64) -> Result<String, ProxyError> {
+    let b64 = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    let header = b64.encode(json!({ "alg": "RS256", "typ": "JWT" }).to_string());
+    let claims = b64.encode(
+        json!({
+            "iss": key.client_email,
+            "scope": CLOUD_PLATFORM_SCOPE,
+            "aud": token_uri,
+            "iat": now,
+            "exp": now + 3600,
+        })
+        .to_string(),
+    );
+    let signing_input = format!("{header}.{claims}");
+
+    let der: String = key
+        .private_key
+        .lines()
+        .filter(|l| !l.starts_with("-----"))
+        .collect();
+    let der = base64::engine::general_purpose::STANDARD
+        .decode(der.trim())
+        .map_err(|e| ProxyError::ConfigError(format!("服务账号私钥不是有效的 PEM: {e}")))?;
+    let key_pair = ring::signature::RsaKeyPair::from_pkcs8(&der)
+        .map_err(|e| ProxyError::ConfigError(format!("服务账号私钥无效: {e}")))?;
+
+    let mut signature = vec![0u8; key_pair.public().modulus_len()];
+    key_pair
+        .sign(
+            &ring::signature::RSA_PKCS1_SHA256,
+            &ring::rand::SystemRandom::new(),
+            signing_input.as_bytes(),
+            &mut signature,
+        )
+        .map_err(|e| ProxyError::AuthError(format!("签发 JWT 失败: {e}")))?;
+
+    Ok(format!("{signing_input}.{}", b64.encode(signature)))
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<i64>,
+}
+
+/// 获取 access_token（命中缓存时不发起网络请求）
+async fn access_token(config: &VertexConfig, client: &Client) -> Result<String, ProxyError> {
+    let key = load_service_account(config)?;
+    let now = chrono::Utc::now().timestamp();
+
+    // 持锁刷新，避免并发请求重复换取 token
+    let mut cache = TOKEN_CACHE.lock().await;
+    if let Some((token, expires_at)) = cache.get(&key.client_email) {
+        if *expires_at - TOKEN_REFRESH_MARGIN_SECS > now {
+            return Ok(token.clone());
+        }
+    }
+
+    let token_uri = key.token_uri.as_deref().unwrap_or(GOOGLE_TOKEN_URI);
+    let assertion = build_jwt(&key, token_uri, now)?;
+    let response = client
+        .post(token_uri)
+        .form(&[("grant_type", JWT_BEARER_GRANT), ("assertion", &assertion)])
+        .send()
+        .await
+        .map_err(|e| ProxyError::AuthError(format!("换取 Vertex access_token 失败: {e}")))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(ProxyError::AuthError(format!(
+            "换取 Vertex access_token 失败 (HTTP {}): {body}",
+            status.as_u16()
+        )));
+    }
+    let token: TokenResponse = response
+        .json()
+        .await
+        .map_err(|e| ProxyError::AuthError(format!("解析 access_token 响应失败: {e}")))?;
+
+    let expires_at = now + token.expires_in.unwrap_or(3600);
+    cache.insert(key.client_email, (token.access_token.clone(), expires_at));
+    Ok(token.access_token)
+}
+
+/// 改写为 Vertex rawPredict 请求并附加 access_token
+pub(super) async fn prepare(
+    config: &VertexConfig,
+    client: &Client,
+    request: &mut reqwest::Request,
+    mut payload: Value,
+    model_id: &str,
+    stream: bool,
+) -> Result<(), ProxyError> {
+    if config.project_id.trim().is_empty() {
+        return Err(ProxyError::ConfigError(
+            "Vertex 供应商缺少 projectId".to_string(),
+        ));
+    }
+
+    if let Some(obj) = payload.as_object_mut() {
+        obj.insert("anthropic_version".into(), json!(VERTEX_ANTHROPIC_VERSION));
+    }
+
+    let region = match config.region.trim() {
+        "" => "global",
+        region => region,
+    };
+    let action = if stream {
+        "streamRawPredict"
+    } else {
+        "rawPredict"
+    };
+    let url = format!(
+        "{}/v1/projects/{}/locations/{region}/publishers/anthropic/models/{model_id}:{action}",
+        base_url(region),
+        config.project_id.trim()
+    );
+    *request.url_mut() = url::Url::parse(&url)
+        .map_err(|e| ProxyError::ConfigError(format!("无效的 Vertex 端点 {url}: {e}")))?;
+
+    let bytes = serde_json::to_vec(&payload)
+        .map_err(|e| ProxyError::TransformError(format!("序列化 Vertex 请求失败: {e}")))?;
+    *request.body_mut() = Some(bytes.into());
+
+    let token = access_token(config, client).await?;
+    let bearer = HeaderValue::from_str(&format!("Bearer {token}"))
+        .map_err(|e| ProxyError::AuthError(format!("无效的 access_token: {e}")))?;
+    let headers = request.headers_mut();
+    headers.insert(AUTHORIZATION, bearer);
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_model_id_uses_at_separator() {
+        assert_eq!(
+            default_model_id("claude-sonnet-4-5-20250929"),
+            "claude-sonnet-4-5@20250929"
+        );
+        assert_eq!(
+            default_model_id("claude-opus-4-1@20250805"),
+            "claude-opus-4-1@20250805"
+        );
+        assert_eq!(default_model_id("claude-sonnet-4-5"), "claude-sonnet-4-5");
+    }
+
+    #[test]
+    fn missing_service_account_is_config_error() {
+        let config = VertexConfig {
+            project_id: "p".to_string(),
+            region: "us-east5".to_string(),
+            ..VertexConfig::default()
+        };
+        assert!(matches!(
+            load_service_account(&config),
+            Err(ProxyError::ConfigError(_))
+        ));
+    }
+}
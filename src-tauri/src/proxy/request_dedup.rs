@@ -0,0 +1,444 @@
+//! 重复请求合并
+//!
+//! 客户端在原请求仍在返回时（常见于流式响应）重试完全相同的请求，开启后重试的请求不再发往上游，
+//! 而是接到原请求的响应上：先补发已收到的数据，再跟随后续数据，同一次生成只付一次费用。
+//!
+//! - 按路径、请求体与客户端凭据计算指纹，不同客户端之间不会共享响应
+//! - 只在原请求开始后的 `windowSecs` 内合并；原请求结束后的重试按新请求处理
+//! - 上游请求在独立任务中执行，不绑定发起它的连接：原客户端断开（重试的典型原因）后
+//!   仍继续返回给跟随的请求，所有客户端都离开后才取消
+//! - 合并的响应带 `x-cc-switch-deduplicated: 1`
+//!
+//! 注意：合并只看请求内容，无法区分"重试"与"有意重复"。窗口内有意并发发送完全相同的请求
+//! （例如对同一提示词多次采样）也会拿到同一份回复；有这类用法时请关闭此功能。
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::{Body, Bytes},
+    extract::Request,
+    http::{HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::{Stream, StreamExt};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tokio::sync::Notify;
+
+/// 标记响应来自合并的原请求
+pub const DEDUPLICATED_HEADER: &str = "x-cc-switch-deduplicated";
+/// 参与指纹计算的最大请求体
+const MAX_BODY_BYTES: usize = 32 * 1024 * 1024;
+/// 区分客户端的凭据请求头
+const CREDENTIAL_HEADERS: &[&str] = &["authorization", "x-api-key", "x-goog-api-key"];
+
+/// 重复请求合并设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestDedupConfig {
+    /// 开启后窗口内完全相同的请求共享同一份响应（包括有意重复发送的请求）
+    #[serde(default)]
+    pub enabled: bool,
+    /// 原请求开始后多久内的相同请求会被合并（秒）
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+}
+
+fn default_window_secs() -> u64 {
+    30
+}
+
+impl Default for RequestDedupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_secs: default_window_secs(),
+        }
+    }
+}
+
+/// 原请求的响应进度
+#[derive(Default)]
+struct Progress {
+    head: Option<(StatusCode, HeaderMap)>,
+    chunks: Vec<Bytes>,
+    done: bool,
+    aborted: bool,
+}
+
+struct InFlight {
+    fingerprint: String,
+    started_at: Instant,
+    progress: Mutex<Progress>,
+    changed: Notify,
+    /// 正在接收响应的客户端数（原请求与跟随的请求）
+    subscribers: AtomicUsize,
+    left: Notify,
+}
+
+impl InFlight {
+    fn update(&self, f: impl FnOnce(&mut Progress)) {
+        f(&mut self.progress.lock().unwrap_or_else(|e| e.into_inner()));
+        self.changed.notify_waiters();
+    }
+}
+
+/// 指纹 -> 在途的原请求
+static IN_FLIGHT: Lazy<Mutex<HashMap<String, Arc<InFlight>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 移除登记（只移除同一个原请求，避免误删窗口过期后新登记的请求）
+fn unregister(map: &mut HashMap<String, Arc<InFlight>>, entry: &Arc<InFlight>) {
+    if map
+        .get(&entry.fingerprint)
+        .is_some_and(|existing| Arc::ptr_eq(existing, entry))
+    {
+        map.remove(&entry.fingerprint);
+    }
+}
+
+/// 客户端对响应的订阅：释放（客户端断开或响应读完）时减少订阅数
+struct Subscription(Arc<InFlight>);
+
+impl Subscription {
+    /// 调用方须持有 `IN_FLIGHT` 锁，与 [`all_left`] 的判断互斥
+    fn new(entry: Arc<InFlight>) -> Self {
+        entry.subscribers.fetch_add(1, Ordering::SeqCst);
+        Self(entry)
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.0.subscribers.fetch_sub(1, Ordering::SeqCst);
+        self.0.left.notify_one();
+    }
+}
+
+enum Slot {
+    Leader(Subscription),
+    Follower(Subscription),
+}
+
+fn fingerprint(path: &str, headers: &HeaderMap, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(path.as_bytes());
+    for name in CREDENTIAL_HEADERS {
+        hasher.update([0]);
+        if let Some(value) = headers.get(*name) {
+            hasher.update(value.as_bytes());
+        }
+    }
+    hasher.update([0]);
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+/// 查找窗口内可合并的原请求，没有时登记为新的原请求
+fn claim(fingerprint: String, window: Duration) -> Slot {
+    let mut map = IN_FLIGHT.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(existing) = map.get(&fingerprint) {
+        if existing.started_at.elapsed() < window {
+            return Slot::Follower(Subscription::new(existing.clone()));
+        }
+    }
+    let entry = Arc::new(InFlight {
+        fingerprint: fingerprint.clone(),
+        started_at: Instant::now(),
+        progress: Mutex::new(Progress::default()),
+        changed: Notify::new(),
+        subscribers: AtomicUsize::new(0),
+        left: Notify::new(),
+    });
+    map.insert(fingerprint, entry.clone());
+    Slot::Leader(Subscription::new(entry))
+}
+
+/// 等待所有客户端离开；离开时同时移除登记，之后的相同请求按新请求处理
+async fn all_left(entry: &Arc<InFlight>) {
+    loop {
+        entry.left.notified().await;
+        let mut map = IN_FLIGHT.lock().unwrap_or_else(|e| e.into_inner());
+        if entry.subscribers.load(Ordering::SeqCst) == 0 {
+            unregister(&mut map, entry);
+            return;
+        }
+    }
+}
+
+/// 读取上游响应并记录进度，返回是否完整结束
+async fn pump(entry: &Arc<InFlight>, upstream: impl Future<Output = Response>) -> bool {
+    let response = tokio::select! {
+        response = upstream => response,
+        _ = all_left(entry) => return false,
+    };
+    let (head, body) = response.into_parts();
+    entry.update(|p| p.head = Some((head.status, head.headers)));
+
+    let mut data = body.into_data_stream();
+    loop {
+        tokio::select! {
+            chunk = data.next() => match chunk {
+                Some(Ok(bytes)) => entry.update(|p| p.chunks.push(bytes)),
+                Some(Err(e)) => {
+                    log::warn!("[RequestDedup] 上游响应中断: {e}");
+                    return false;
+                }
+                None => return true,
+            },
+            _ = all_left(entry) => {
+                log::debug!("[RequestDedup] 所有客户端已断开，取消上游请求");
+                return false;
+            }
+        }
+    }
+}
+
+/// 在独立任务中执行上游请求，不随发起它的连接断开而取消
+fn drive(entry: Arc<InFlight>, upstream: impl Future<Output = Response> + Send + 'static) {
+    tokio::spawn(async move {
+        let finished = pump(&entry, upstream).await;
+        unregister(
+            &mut IN_FLIGHT.lock().unwrap_or_else(|e| e.into_inner()),
+            &entry,
+        );
+        entry.update(|p| {
+            p.done = finished;
+            p.aborted = !finished;
+        });
+    });
+}
+
+/// 等待原请求的响应头（原请求在此之前中断时返回 None）
+async fn wait_head(entry: &InFlight) -> Option<(StatusCode, HeaderMap)> {
+    loop {
+        let changed = entry.changed.notified();
+        {
+            let progress = entry.progress.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(head) = &progress.head {
+                return Some(head.clone());
+            }
+            if progress.aborted {
+                return None;
+            }
+        }
+        changed.await;
+    }
+}
+
+/// 补发已收到的数据并跟随后续数据（响应体被丢弃时释放订阅）
+fn follow(subscription: Subscription) -> impl Stream<Item = Result<Bytes, axum::Error>> {
+    async_stream::stream! {
+        let entry = subscription.0.clone();
+        let _subscription = subscription;
+        let mut next = 0;
+        loop {
+            let changed = entry.changed.notified();
+            let (chunks, done, aborted) = {
+                let progress = entry.progress.lock().unwrap_or_else(|e| e.into_inner());
+                (progress.chunks[next..].to_vec(), progress.done, progress.aborted)
+            };
+            next += chunks.len();
+            for chunk in chunks {
+                yield Ok(chunk);
+            }
+            if done {
+                break;
+            }
+            if aborted {
+                yield Err(axum::Error::new(std::io::Error::other("原请求已中断")));
+                break;
+            }
+            changed.await;
+        }
+    }
+}
+
+async fn attach(subscription: Subscription, deduplicated: bool) -> Option<Response> {
+    let (status, headers) = wait_head(&subscription.0).await?;
+    let mut response = Response::new(Body::from_stream(follow(subscription)));
+    *response.status_mut() = status;
+    *response.headers_mut() = headers;
+    if deduplicated {
+        response
+            .headers_mut()
+            .insert(DEDUPLICATED_HEADER, HeaderValue::from_static("1"));
+    }
+    Some(response)
+}
+
+/// 代理路由中间件：相同请求仍在返回时合并到原请求的响应上
+pub async fn layer(request: Request, next: Next) -> Response {
+    let config = crate::settings::get_settings().request_dedup;
+    if !config.enabled || request.method() != Method::POST {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = request.into_parts();
+    let body = match axum::body::to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(e) => {
+            return (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                Json(json!({ "error": format!("读取请求体失败: {e}") })),
+            )
+                .into_response()
+        }
+    };
+    let path = parts
+        .uri
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or_else(|| parts.uri.path())
+        .to_string();
+    let key = fingerprint(&path, &parts.headers, &body);
+
+    match claim(key, Duration::from_secs(config.window_secs)) {
+        Slot::Follower(subscription) => {
+            if let Some(response) = attach(subscription, true).await {
+                log::info!("[RequestDedup] 相同请求仍在返回，已合并到原请求: {path}");
+                return response;
+            }
+            // 原请求在返回响应头前中断：按新请求转发
+            next.run(Request::from_parts(parts, Body::from(body))).await
+        }
+        Slot::Leader(subscription) => {
+            drive(
+                subscription.0.clone(),
+                next.run(Request::from_parts(parts, Body::from(body))),
+            );
+            attach(subscription, false).await.unwrap_or_else(|| {
+                (
+                    StatusCode::BAD_GATEWAY,
+                    Json(json!({ "error": "上游请求已中断" })),
+                )
+                    .into_response()
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn fingerprint_separates_clients() {
+        let mut alice = HeaderMap::new();
+        alice.insert("x-api-key", HeaderValue::from_static("sk-alice"));
+        let mut bob = HeaderMap::new();
+        bob.insert("x-api-key", HeaderValue::from_static("sk-bob"));
+        let body = br#"{"stream":true}"#;
+
+        assert_eq!(
+            fingerprint("/v1/messages", &alice, body),
+            fingerprint("/v1/messages", &alice.clone(), body)
+        );
+        assert_ne!(
+            fingerprint("/v1/messages", &alice, body),
+            fingerprint("/v1/messages", &bob, body)
+        );
+        assert_ne!(
+            fingerprint("/v1/messages", &alice, body),
+            fingerprint("/v1/messages", &alice, br#"{"stream":false}"#)
+        );
+    }
+
+    /// 由测试逐块推送数据的上游响应
+    fn upstream() -> (
+        mpsc::UnboundedSender<Result<Bytes, std::io::Error>>,
+        impl Future<Output = Response> + Send + 'static,
+    ) {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let stream = async_stream::stream! {
+            while let Some(chunk) = rx.recv().await {
+                yield chunk;
+            }
+        };
+        (tx, async move { Response::new(Body::from_stream(stream)) })
+    }
+
+    async fn read(response: Response) -> Result<Bytes, axum::Error> {
+        axum::body::to_bytes(response.into_body(), usize::MAX).await
+    }
+
+    #[tokio::test]
+    async fn retry_attaches_to_running_stream() {
+        let window = Duration::from_secs(30);
+        let Slot::Leader(leader) = claim("dedup-test".into(), window) else {
+            panic!("first request should lead");
+        };
+        let (tx, upstream) = upstream();
+        drive(leader.0.clone(), upstream);
+        let leader = attach(leader, false).await.unwrap();
+        assert!(leader.headers().get(DEDUPLICATED_HEADER).is_none());
+
+        tx.send(Ok(Bytes::from("data: a\n\n"))).unwrap();
+        let Slot::Follower(follower) = claim("dedup-test".into(), window) else {
+            panic!("retry should follow");
+        };
+        let follower = attach(follower, true).await.unwrap();
+        assert_eq!(follower.headers()[DEDUPLICATED_HEADER], "1");
+        tx.send(Ok(Bytes::from("data: b\n\n"))).unwrap();
+        drop(tx);
+
+        let (leader, follower) = tokio::join!(read(leader), read(follower));
+        assert_eq!(leader.unwrap(), "data: a\n\ndata: b\n\n");
+        assert_eq!(follower.unwrap(), "data: a\n\ndata: b\n\n");
+        // 原请求结束后不再合并
+        assert!(matches!(
+            claim("dedup-test".into(), window),
+            Slot::Leader(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn leader_disconnect_keeps_upstream_for_followers() {
+        let window = Duration::from_secs(30);
+        let Slot::Leader(leader) = claim("dedup-leader-gone".into(), window) else {
+            panic!("first request should lead");
+        };
+        let (tx, upstream) = upstream();
+        drive(leader.0.clone(), upstream);
+        let leader = attach(leader, false).await.unwrap();
+        let Slot::Follower(follower) = claim("dedup-leader-gone".into(), window) else {
+            panic!("retry should follow");
+        };
+        let follower = attach(follower, true).await.unwrap();
+
+        // 原客户端断开
+        drop(leader);
+        tx.send(Ok(Bytes::from("data: a\n\n"))).unwrap();
+        drop(tx);
+        assert_eq!(read(follower).await.unwrap(), "data: a\n\n");
+    }
+
+    #[tokio::test]
+    async fn upstream_is_cancelled_when_all_clients_leave() {
+        let window = Duration::from_secs(30);
+        let Slot::Leader(leader) = claim("dedup-all-gone".into(), window) else {
+            panic!("first request should lead");
+        };
+        let (tx, upstream) = upstream();
+        drive(leader.0.clone(), upstream);
+        let leader = attach(leader, false).await.unwrap();
+
+        drop(leader);
+        tokio::time::timeout(Duration::from_secs(1), tx.closed())
+            .await
+            .expect("upstream should be dropped");
+        assert!(matches!(
+            claim("dedup-all-gone".into(), window),
+            Slot::Leader(_)
+        ));
+    }
+}
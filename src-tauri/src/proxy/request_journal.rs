@@ -0,0 +1,456 @@
+//! 非流式请求持久化
+//!
+//! 开启后，代理在转发带 `Idempotency-Key` 的非流式请求前先把请求写入数据库，得到响应后删除。
+//! 没有幂等键的请求无法安全地重复发送，也无法取回结果，不做持久化。
+//! 代理异常退出（崩溃、强制结束）时残留的记录会在下次启动时重新发送：
+//! - 超过最长保留时间的请求标记为过期，不再发送
+//! - 重新发送的结果保存下来，客户端带同一个 `Idempotency-Key` 重试时直接取回，
+//!   不会再次请求上游；重新发送尚未完成时返回 409
+//! - 客户端的认证头随请求保存，重新发送时同样经过虚拟 Key / 局域网令牌校验与会话覆盖匹配
+//!
+//! 是否流式按参数策略改写后的请求体判断（策略可能强制 `stream`）。
+//! 客户端可通过 `x-cc-switch-persist: off` 为单个请求关闭持久化。
+//! 客户端主动断开的请求不会被重新发送。
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::param_policy;
+use super::server::ProxyState;
+use crate::app_config::AppType;
+use crate::database::{Database, QueuedRequest, QueuedRequestStatus};
+use crate::provider::Provider;
+
+/// 关闭单个请求持久化的请求头（取值 `off` / `false` / `0`）
+pub const OPT_OUT_HEADER: &str = "x-cc-switch-persist";
+/// 客户端提供的幂等键，用于取回重启后重新发送的结果
+pub const IDEMPOTENCY_HEADER: &str = "idempotency-key";
+/// 标记响应来自重启后重新发送的结果
+pub const RESUMED_HEADER: &str = "x-cc-switch-resumed";
+/// 可持久化的最大请求体
+const MAX_BODY_BYTES: usize = 32 * 1024 * 1024;
+/// 重新发送单个请求的超时
+const RESUME_TIMEOUT_SECS: u64 = 600;
+/// 不保存的请求头
+///
+/// 客户端认证头（`authorization` / `x-api-key` / `x-goog-api-key`）需要保留：
+/// 代理据此校验虚拟 Key 与局域网令牌、匹配会话覆盖，上游凭据仍由代理按供应商注入。
+const SKIPPED_HEADERS: &[&str] = &[
+    "host",
+    "content-length",
+    "connection",
+    "transfer-encoding",
+    "proxy-authorization",
+    "cookie",
+    IDEMPOTENCY_HEADER,
+    OPT_OUT_HEADER,
+    // 回放令牌每次运行重新生成，不写入磁盘
+    crate::services::replay::REPLAY_HEADER,
+];
+
+/// 代理正在停止：此时被中断的请求保留记录，下次启动时重新发送
+static STOPPING: AtomicBool = AtomicBool::new(false);
+
+/// 请求持久化设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestPersistenceConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 最长保留时间（秒），超过后不再重新发送；结果也只保留这么久
+    #[serde(default = "default_max_age_secs")]
+    pub max_age_secs: u64,
+}
+
+fn default_max_age_secs() -> u64 {
+    3600
+}
+
+impl Default for RequestPersistenceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_age_secs: default_max_age_secs(),
+        }
+    }
+}
+
+pub fn set_stopping(stopping: bool) {
+    STOPPING.store(stopping, Ordering::SeqCst);
+}
+
+fn opted_out(headers: &HeaderMap) -> bool {
+    headers
+        .get(OPT_OUT_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| {
+            matches!(
+                v.trim().to_ascii_lowercase().as_str(),
+                "off" | "false" | "0"
+            )
+        })
+        .unwrap_or(false)
+}
+
+fn idempotency_key(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(IDEMPOTENCY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|k| !k.is_empty() && k.len() <= 200)
+        .map(str::to_string)
+}
+
+/// 按请求路径判断所属应用（与代理路由一致）
+fn app_for_path(path: &str) -> Option<AppType> {
+    let path = path.split('?').next().unwrap_or(path);
+    if path.contains("/v1beta/") {
+        Some(AppType::Gemini)
+    } else if path.ends_with("/messages") {
+        Some(AppType::Claude)
+    } else if path.ends_with("/responses") || path.ends_with("/chat/completions") {
+        Some(AppType::Codex)
+    } else {
+        None
+    }
+}
+
+/// 请求将转发到的供应商（按当前供应商预判，用于应用供应商参数策略）
+fn current_provider(db: &Database, path: &str) -> Option<Provider> {
+    let app = app_for_path(path)?;
+    let id = db.get_current_provider(app.as_str()).ok()??;
+    db.get_provider_by_id(&id, app.as_str()).ok()?
+}
+
+/// 只持久化非流式请求（按参数策略改写后的请求体判断）
+fn is_streaming(path: &str, body: &[u8], provider: Option<&Provider>) -> bool {
+    if path.contains("streamGenerateContent") {
+        return true;
+    }
+    let Ok(mut body) = serde_json::from_slice::<Value>(body) else {
+        return false;
+    };
+    param_policy::preview(provider, &mut body);
+    body.get("stream").and_then(Value::as_bool).unwrap_or(false)
+}
+
+fn kept_headers(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter(|(name, _)| !SKIPPED_HEADERS.contains(&name.as_str()))
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|v| (name.as_str().to_string(), v.to_string()))
+        })
+        .collect()
+}
+
+/// 未得到响应就被丢弃时删除记录（客户端断开），代理停止时保留
+struct PendingGuard {
+    db: Arc<Database>,
+    id: Option<String>,
+}
+
+impl PendingGuard {
+    fn finish(mut self) {
+        if let Some(id) = self.id.take() {
+            if let Err(e) = self.db.delete_queued_request(&id) {
+                log::warn!("[RequestJournal] 删除请求记录 {id} 失败: {e}");
+            }
+        }
+    }
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        if let Some(id) = self.id.take() {
+            if STOPPING.load(Ordering::SeqCst) {
+                log::info!("[RequestJournal] 代理停止，保留未完成的请求 {id}");
+            } else if let Err(e) = self.db.delete_queued_request(&id) {
+                log::warn!("[RequestJournal] 删除请求记录 {id} 失败: {e}");
+            }
+        }
+    }
+}
+
+fn stored_response(record: &QueuedRequest) -> Response {
+    let status = record
+        .response_status
+        .and_then(|s| StatusCode::from_u16(s).ok())
+        .unwrap_or(StatusCode::BAD_GATEWAY);
+    let mut response = Response::new(Body::from(record.response_body.clone().unwrap_or_default()));
+    *response.status_mut() = status;
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    headers.insert(RESUMED_HEADER, HeaderValue::from_static("1"));
+    response
+}
+
+/// 代理路由中间件：持久化带幂等键的非流式请求，并为重试返回已保存的结果
+pub async fn layer(State(state): State<ProxyState>, request: Request, next: Next) -> Response {
+    let config = crate::settings::get_settings().request_persistence;
+    if !config.enabled || request.method() != Method::POST || opted_out(request.headers()) {
+        return next.run(request).await;
+    }
+    let Some(key) = idempotency_key(request.headers()) else {
+        return next.run(request).await;
+    };
+
+    let (parts, body) = request.into_parts();
+    let body = match axum::body::to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(e) => {
+            return (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                Json(json!({ "error": format!("读取请求体失败: {e}") })),
+            )
+                .into_response()
+        }
+    };
+    let path = parts
+        .uri
+        .path_and_query()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| parts.uri.path().to_string());
+    let provider = current_provider(&state.db, &path);
+    if is_streaming(&path, &body, provider.as_ref()) {
+        return next.run(Request::from_parts(parts, Body::from(body))).await;
+    }
+
+    match state.db.get_queued_request(&key) {
+        Ok(Some(record)) => match record.status {
+            QueuedRequestStatus::Completed => {
+                log::info!("[RequestJournal] 返回重启后重新发送的结果 {key}");
+                return stored_response(&record);
+            }
+            QueuedRequestStatus::Pending | QueuedRequestStatus::Resuming => {
+                return (
+                    StatusCode::CONFLICT,
+                    [(header::RETRY_AFTER, "5")],
+                    Json(json!({ "error": "相同幂等键的请求仍在处理中" })),
+                )
+                    .into_response();
+            }
+            // 失败或过期的记录不再有用，按新请求处理
+            QueuedRequestStatus::Failed | QueuedRequestStatus::Expired => {
+                let _ = state.db.delete_queued_request(&key);
+            }
+        },
+        Ok(None) => {}
+        Err(e) => log::warn!("[RequestJournal] 读取请求记录失败: {e}"),
+    }
+
+    let record = QueuedRequest {
+        id: key,
+        endpoint: path,
+        headers: kept_headers(&parts.headers),
+        body: String::from_utf8_lossy(&body).to_string(),
+        status: QueuedRequestStatus::Pending,
+        response_status: None,
+        response_body: None,
+        created_at: chrono::Utc::now().timestamp(),
+        updated_at: 0,
+    };
+    let id = match state.db.insert_queued_request(&record) {
+        Ok(true) => Some(record.id),
+        Ok(false) => None,
+        Err(e) => {
+            log::warn!("[RequestJournal] 保存请求失败，按普通请求转发: {e}");
+            None
+        }
+    };
+    let guard = PendingGuard {
+        db: state.db.clone(),
+        id,
+    };
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    guard.finish();
+    response
+}
+
+/// 重新发送上次运行残留的请求（代理启动后调用）
+///
+/// `started_at` 为本次启动时间，之后接收的请求由本次运行正常处理。
+pub async fn resume_pending(db: Arc<Database>, addr: SocketAddr, started_at: i64) {
+    let config = crate::settings::get_settings().request_persistence;
+    let now = chrono::Utc::now().timestamp();
+    let cutoff = now - config.max_age_secs as i64;
+    match db.expire_queued_requests(cutoff, now) {
+        Ok(0) => {}
+        Ok(n) => log::info!("[RequestJournal] {n} 个请求超过最长保留时间，不再重新发送"),
+        Err(e) => log::warn!("[RequestJournal] 标记过期请求失败: {e}"),
+    }
+    if let Err(e) = db.prune_queued_requests(cutoff) {
+        log::warn!("[RequestJournal] 清理请求记录失败: {e}");
+    }
+
+    let requests = match db.get_unanswered_queued_requests(started_at) {
+        Ok(requests) if !requests.is_empty() => requests,
+        Ok(_) => return,
+        Err(e) => {
+            log::warn!("[RequestJournal] 读取残留请求失败: {e}");
+            return;
+        }
+    };
+    if !config.enabled {
+        log::info!(
+            "[RequestJournal] 请求持久化已关闭，{} 个残留请求不再重新发送",
+            requests.len()
+        );
+        let _ = db.expire_queued_requests(started_at, now);
+        return;
+    }
+
+    let client = match reqwest::Client::builder()
+        .no_proxy()
+        .timeout(Duration::from_secs(RESUME_TIMEOUT_SECS))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            log::warn!("[RequestJournal] 创建 HTTP 客户端失败: {e}");
+            return;
+        }
+    };
+    let mut addr = addr;
+    if addr.ip().is_unspecified() {
+        addr.set_ip(std::net::Ipv4Addr::LOCALHOST.into());
+    }
+
+    log::info!("[RequestJournal] 重新发送 {} 个残留请求", requests.len());
+    for request in requests {
+        let now = chrono::Utc::now().timestamp();
+        let _ = db.set_queued_request_status(&request.id, QueuedRequestStatus::Resuming, now);
+        let result = resend(&client, addr, &request).await;
+        let now = chrono::Utc::now().timestamp();
+        let saved = match result {
+            Ok((status, body)) => db.finish_queued_request(
+                &request.id,
+                QueuedRequestStatus::Completed,
+                Some(status),
+                Some(&body),
+                now,
+            ),
+            Err(e) => {
+                log::warn!("[RequestJournal] 重新发送请求 {} 失败: {e}", request.id);
+                db.finish_queued_request(
+                    &request.id,
+                    QueuedRequestStatus::Failed,
+                    None,
+                    Some(&e),
+                    now,
+                )
+            }
+        };
+        if let Err(e) = saved {
+            log::warn!("[RequestJournal] 保存重新发送结果失败: {e}");
+        }
+    }
+}
+
+/// 通过本机代理重新发送（走完整的路由、故障转移与用量记录）
+async fn resend(
+    client: &reqwest::Client,
+    addr: SocketAddr,
+    request: &QueuedRequest,
+) -> Result<(u16, String), String> {
+    let mut builder = client
+        .post(format!("http://{addr}{}", request.endpoint))
+        .header(OPT_OUT_HEADER, "off");
+    for (name, value) in &request.headers {
+        builder = builder.header(name, value);
+    }
+    let response = builder
+        .body(Bytes::from(request.body.clone()))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = response.status().as_u16();
+    let body = response.text().await.map_err(|e| e.to_string())?;
+    Ok((status, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_streaming_requests() {
+        assert!(is_streaming("/v1/messages", br#"{"stream":true}"#, None));
+        assert!(!is_streaming("/v1/messages", br#"{"stream":false}"#, None));
+        assert!(!is_streaming("/v1/messages", b"{}", None));
+        assert!(is_streaming(
+            "/v1beta/models/gemini-pro:streamGenerateContent?alt=sse",
+            b"{}",
+            None
+        ));
+    }
+
+    #[test]
+    fn streaming_follows_provider_policy() {
+        let mut provider = Provider::with_id("p".into(), "P".into(), json!({}), None);
+        provider.meta = Some(crate::provider::ProviderMeta {
+            parameter_policy: Some(
+                serde_json::from_value(json!({ "force": { "stream": true } })).unwrap(),
+            ),
+            ..Default::default()
+        });
+        assert!(is_streaming(
+            "/v1/messages",
+            br#"{"stream":false}"#,
+            Some(&provider)
+        ));
+    }
+
+    #[test]
+    fn maps_paths_to_apps() {
+        assert_eq!(app_for_path("/claude/v1/messages"), Some(AppType::Claude));
+        assert_eq!(app_for_path("/v1/chat/completions"), Some(AppType::Codex));
+        assert_eq!(
+            app_for_path("/v1beta/models/gemini-pro:generateContent?key=x"),
+            Some(AppType::Gemini)
+        );
+        assert_eq!(app_for_path("/health"), None);
+    }
+
+    #[test]
+    fn keeps_client_auth_and_drops_control_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_static("Bearer vk-x"));
+        headers.insert("cookie", HeaderValue::from_static("sid=1"));
+        headers.insert("anthropic-version", HeaderValue::from_static("2023-06-01"));
+        headers.insert(IDEMPOTENCY_HEADER, HeaderValue::from_static("job-1"));
+        let mut kept = kept_headers(&headers);
+        kept.sort();
+        assert_eq!(
+            kept,
+            vec![
+                ("anthropic-version".to_string(), "2023-06-01".to_string()),
+                ("authorization".to_string(), "Bearer vk-x".to_string()),
+            ]
+        );
+        assert_eq!(idempotency_key(&headers).as_deref(), Some("job-1"));
+        assert!(!opted_out(&headers));
+        headers.insert(OPT_OUT_HEADER, HeaderValue::from_static("OFF"));
+        assert!(opted_out(&headers));
+    }
+}
@@ -0,0 +1,50 @@
+//! 请求标签
+//!
+//! 下游客户端可通过 `X-CCSwitch-Tag` 请求头为请求打上标签（如项目名），
+//! 标签写入请求日志，可按标签汇总用量，无需为每个项目单独配置密钥。
+//! 该请求头只在代理内部使用，不会转发给上游。
+
+use axum::http::HeaderMap;
+
+/// 标签请求头
+pub const TAG_HEADER: &str = "x-ccswitch-tag";
+/// 标签最长字符数，超出部分截断
+pub const MAX_TAG_CHARS: usize = 64;
+
+/// 从请求头提取标签：去掉首尾空白，空值或含控制字符时忽略
+pub fn extract(headers: &HeaderMap) -> Option<String> {
+    let value = headers.get(TAG_HEADER)?.to_str().ok()?.trim();
+    if value.is_empty() || value.chars().any(char::is_control) {
+        return None;
+    }
+    Some(value.chars().take(MAX_TAG_CHARS).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(TAG_HEADER, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn extracts_trimmed_tag() {
+        assert_eq!(
+            extract(&headers("  billing-api ")),
+            Some("billing-api".into())
+        );
+        assert_eq!(extract(&headers("   ")), None);
+        assert_eq!(extract(&headers("a\tb")), None);
+        assert_eq!(extract(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn truncates_long_tags() {
+        let tag = extract(&headers(&"x".repeat(MAX_TAG_CHARS + 5))).unwrap();
+        assert_eq!(tag.chars().count(), MAX_TAG_CHARS);
+    }
+}
@@ -0,0 +1,152 @@
+//! 上游响应头透传策略
+//!
+//! 决定哪些上游响应头返回给客户端：
+//! - all（默认）：透传全部响应头，剥离内置与自定义剥离列表中的条目
+//! - allowlist：只透传基础响应头、调试相关响应头（request id / 限流 / 模型）和自定义列表
+//!
+//! 上游错误响应同样按此策略附带响应头，使 Claude Code 等客户端的报错里能看到上游 request id。
+//! 名称匹配不区分大小写，以 `*` 结尾时按前缀匹配。
+
+use axum::http::HeaderMap;
+
+use crate::settings::{self, ResponseHeaderMode};
+
+/// 始终剥离的响应头
+const BUILTIN_STRIP: &[&str] = &["set-cookie"];
+
+/// allowlist 模式下也保留的基础响应头
+const BASE_HEADERS: &[&str] = &[
+    "content-type",
+    "content-length",
+    "content-encoding",
+    "cache-control",
+];
+
+/// 便于排查问题的调试响应头（allowlist 模式下默认保留）
+const DEBUG_HEADERS: &[&str] = &[
+    "request-id",
+    "x-request-id",
+    "x-amzn-requestid",
+    "cf-ray",
+    "retry-after",
+    "anthropic-ratelimit-*",
+    "x-ratelimit-*",
+    "openai-model",
+    "openai-processing-ms",
+    "x-model*",
+];
+
+fn matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.trim().to_ascii_lowercase();
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == pattern,
+    }
+}
+
+fn matches_any<S: AsRef<str>>(patterns: &[S], name: &str) -> bool {
+    patterns.iter().any(|p| matches(p.as_ref(), name))
+}
+
+/// 按指定策略过滤响应头
+pub fn filter_with(
+    headers: &HeaderMap,
+    mode: ResponseHeaderMode,
+    allow: &[String],
+    strip: &[String],
+) -> HeaderMap {
+    let mut filtered = HeaderMap::with_capacity(headers.len());
+    for (name, value) in headers {
+        let lower = name.as_str();
+        if matches_any(BUILTIN_STRIP, lower) || matches_any(strip, lower) {
+            continue;
+        }
+        let keep = match mode {
+            ResponseHeaderMode::All => true,
+            ResponseHeaderMode::Allowlist => {
+                matches_any(BASE_HEADERS, lower)
+                    || matches_any(DEBUG_HEADERS, lower)
+                    || matches_any(allow, lower)
+            }
+        };
+        if keep {
+            filtered.append(name.clone(), value.clone());
+        }
+    }
+    filtered
+}
+
+/// 按当前设置过滤响应头
+pub fn filter(headers: &HeaderMap) -> HeaderMap {
+    let settings = settings::get_settings();
+    filter_with(
+        headers,
+        settings.response_header_mode,
+        &settings.response_header_allow,
+        &settings.response_header_strip,
+    )
+}
+
+/// 上游错误响应中需要附带的响应头（响应体会被重新生成，去掉描述原响应体的头）
+pub fn for_error_response(headers: &HeaderMap) -> HeaderMap {
+    let mut filtered = filter(headers);
+    for name in [
+        "content-type",
+        "content-length",
+        "content-encoding",
+        "transfer-encoding",
+    ] {
+        filtered.remove(name);
+    }
+    filtered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upstream() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in [
+            ("content-type", "application/json"),
+            ("request-id", "req_123"),
+            ("anthropic-ratelimit-tokens-remaining", "1000"),
+            ("set-cookie", "session=1"),
+            ("server", "relay"),
+            ("x-relay-user", "42"),
+        ] {
+            headers.insert(name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn all_mode_strips_cookies_and_custom_entries() {
+        let filtered = filter_with(
+            &upstream(),
+            ResponseHeaderMode::All,
+            &[],
+            &["x-relay-*".to_string()],
+        );
+        assert!(filtered.contains_key("request-id"));
+        assert!(filtered.contains_key("server"));
+        assert!(!filtered.contains_key("set-cookie"));
+        assert!(!filtered.contains_key("x-relay-user"));
+    }
+
+    #[test]
+    fn allowlist_mode_keeps_debug_headers() {
+        let filtered = filter_with(
+            &upstream(),
+            ResponseHeaderMode::Allowlist,
+            &["Server".to_string()],
+            &[],
+        );
+        assert!(filtered.contains_key("content-type"));
+        assert!(filtered.contains_key("request-id"));
+        assert!(filtered.contains_key("anthropic-ratelimit-tokens-remaining"));
+        assert!(filtered.contains_key("server"));
+        assert!(!filtered.contains_key("x-relay-user"));
+        assert!(!filtered.contains_key("set-cookie"));
+    }
+}
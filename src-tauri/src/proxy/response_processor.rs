@@ -5,8 +5,10 @@
 use super::{
     handler_config::UsageParserConfig,
     handler_context::{RequestContext, StreamingTimeoutConfig},
+    inline_cost, log_scrubber, model_audit, overhead, response_headers,
     server::ProxyState,
-    usage::parser::TokenUsage,
+    sse_coalesce, sse_sanitize, transcript,
+    usage::{logger::RequestTraffic, parser::TokenUsage},
     ProxyError,
 };
 use axum::response::Response;
@@ -51,18 +53,30 @@ pub async fn handle_streaming(
     let status = response.status();
     let mut builder = axum::response::Response::builder().status(status);
 
-    // 复制响应头
-    for (key, value) in response.headers() {
+    // 按透传策略复制响应头
+    for (key, value) in response_headers::filter(response.headers()).iter() {
         builder = builder.header(key, value);
     }
 
-    // 创建字节流
-    let stream = response
-        .bytes_stream()
+    // 创建字节流（按数据块累计接收字节数）
+    let stream = ctx
+        .traffic
+        .count_received(response.bytes_stream())
         .map(|chunk| chunk.map_err(|e| std::io::Error::other(e.to_string())));
+    // 先修复编码与换行，后续的用量解析与客户端看到的是同一份数据
+    let stream = sse_sanitize::sanitize(stream, ctx.app_type_str, &ctx.provider);
+
+    // 开启响应内附带用量时，收集器在流结束时写入用量，供末尾追加事件
+    let usage_slot = inline_cost::enabled().then(inline_cost::UsageSlot::default);
 
     // 创建使用量收集器
-    let usage_collector = create_usage_collector(ctx, state, status.as_u16(), parser_config);
+    let usage_collector = create_usage_collector(
+        ctx,
+        state,
+        status.as_u16(),
+        parser_config,
+        usage_slot.clone(),
+    );
 
     // 获取流式超时配置
     let timeout_config = ctx.streaming_timeout_config();
@@ -71,7 +85,18 @@ pub async fn handle_streaming(
     let logged_stream =
         create_logged_passthrough_stream(stream, ctx.tag, Some(usage_collector), timeout_config);
 
-    let body = axum::body::Body::from_stream(logged_stream);
+    let body = match usage_slot {
+        Some(slot) => sse_coalesce::body_for(
+            inline_cost::append_to_stream(
+                logged_stream,
+                slot,
+                state.db.clone(),
+                ctx.provider.clone(),
+            ),
+            &ctx.provider,
+        ),
+        None => sse_coalesce::body_for(logged_stream, &ctx.provider),
+    };
     builder.body(body).unwrap()
 }
 
@@ -82,7 +107,7 @@ pub async fn handle_non_streaming(
     state: &ProxyState,
     parser_config: &UsageParserConfig,
 ) -> Result<Response, ProxyError> {
-    let response_headers = response.headers().clone();
+    let upstream_headers = response_headers::filter(response.headers());
     let status = response.status();
 
     // 读取响应体
@@ -90,18 +115,58 @@ pub async fn handle_non_streaming(
         log::error!("[{}] 读取响应失败: {e}", ctx.tag);
         ProxyError::ForwardFailed(format!("Failed to read response body: {e}"))
     })?;
+    ctx.traffic.add_received(body_bytes.len());
+
+    // 开启响应内附带用量时替换后的响应体
+    let mut injected_body: Option<Bytes> = None;
 
     // 解析并记录使用量
     if let Ok(json_value) = serde_json::from_slice::<Value>(&body_bytes) {
         log::info!(
             "[{}] <<< 响应 JSON:\n{}",
             ctx.tag,
-            serde_json::to_string_pretty(&json_value).unwrap_or_default()
+            log_scrubber::body_for_log(&json_value)
         );
 
+        if let Some(capture) = &ctx.transcript {
+            capture.finish(
+                state.db.clone(),
+                &ctx.provider.id,
+                &ctx.request_model,
+                transcript::completion_from_response(&json_value),
+            );
+        }
+
         // 解析使用量
         let parsed_usage = (parser_config.response_parser)(&json_value);
 
+        if let Some(shadow) = &ctx.shadow {
+            shadow.finish_primary(
+                state.db.clone(),
+                &ctx.provider.id,
+                status.as_u16(),
+                ctx.latency_ms(),
+                parsed_usage
+                    .as_ref()
+                    .map(|u| u.output_tokens as u64)
+                    .unwrap_or(0),
+                transcript::completion_from_response(&json_value),
+            );
+        }
+
+        if status.is_success() {
+            if let Some(expectation) = ctx.model_expectation() {
+                let output_tokens = parsed_usage.as_ref().map(|u| u.output_tokens as u64);
+                expectation.check(
+                    state.db.clone(),
+                    model_audit::ResponseFacts::from_response(
+                        &json_value,
+                        output_tokens.unwrap_or(0),
+                    ),
+                );
+            }
+        }
+
         // TPS：仅使用 usage.output_tokens（不做估算），按“请求活跃时间”摊销到滑动窗口（仅统计 2xx）
         if (200..300).contains(&status.as_u16()) {
             let usage_tokens = parsed_usage
@@ -111,14 +176,13 @@ pub async fn handle_non_streaming(
 
             if usage_tokens > 0 {
                 state
-                    .tps_monitor
-                    .lock()
-                    .await
-                    .record_completed_request(
+                    .record_throughput(
+                        ctx.app_type_str,
+                        &ctx.provider.id,
                         usage_tokens,
                         ctx.start_time,
-                        std::time::Instant::now(),
-                    );
+                    )
+                    .await;
             }
         }
 
@@ -132,6 +196,11 @@ pub async fn handle_non_streaming(
                 ctx.request_model.clone()
             };
 
+            if status.is_success() && inline_cost::enabled() {
+                let summary = inline_cost::summarize(&state.db, &ctx.provider, &model, &usage);
+                injected_body = inline_cost::inject_into_json(&json_value, &summary);
+            }
+
             spawn_log_usage(state, ctx, usage, &model, status.as_u16(), false);
         } else {
             let model = json_value
@@ -172,11 +241,15 @@ pub async fn handle_non_streaming(
 
     // 构建响应
     let mut builder = axum::response::Response::builder().status(status);
-    for (key, value) in response_headers.iter() {
+    for (key, value) in upstream_headers.iter() {
+        // 响应体被改写后长度变化，交由 axum 重新计算
+        if injected_body.is_some() && key == axum::http::header::CONTENT_LENGTH {
+            continue;
+        }
         builder = builder.header(key, value);
     }
 
-    let body = axum::body::Body::from(body_bytes);
+    let body = axum::body::Body::from(injected_body.unwrap_or(body_bytes));
     Ok(builder.body(body).unwrap())
 }
 
@@ -277,6 +350,7 @@ fn create_usage_collector(
     state: &ProxyState,
     status_code: u16,
     parser_config: &UsageParserConfig,
+    usage_slot: Option<inline_cost::UsageSlot>,
 ) -> SseUsageCollector {
     let state = state.clone();
     let provider_id = ctx.provider.id.clone();
@@ -287,16 +361,68 @@ fn create_usage_collector(
     let stream_parser = parser_config.stream_parser;
     let model_extractor = parser_config.model_extractor;
     let session_id = ctx.session_id.clone();
+    let trace = ctx.trace.clone();
+    let capture = ctx.transcript.clone();
+    let shadow = ctx.shadow.clone();
+    let traffic = ctx.traffic.clone();
+    let request_tag = ctx.request_tag.clone();
+    let is_replay = ctx.is_replay;
+    let expectation = (200..300)
+        .contains(&status_code)
+        .then(|| ctx.model_expectation())
+        .flatten();
 
     SseUsageCollector::new(start_time, move |events, first_token_ms| {
+        if let Some(capture) = &capture {
+            capture.finish(
+                state.db.clone(),
+                &provider_id,
+                &model_extractor(&events, &request_model),
+                transcript::completion_from_events(&events),
+            );
+        }
+
+        if let Some(shadow) = &shadow {
+            shadow.finish_primary(
+                state.db.clone(),
+                &provider_id,
+                status_code,
+                start_time.elapsed().as_millis() as u64,
+                stream_parser(&events)
+                    .map(|u| u.output_tokens as u64)
+                    .unwrap_or(0),
+                transcript::completion_from_events(&events),
+            );
+        }
+
+        if let Some(expectation) = &expectation {
+            let output_tokens = stream_parser(&events).map(|u| u.output_tokens as u64);
+            expectation.check(
+                state.db.clone(),
+                model_audit::ResponseFacts::from_events(
+                    &events,
+                    output_tokens.unwrap_or(0),
+                    start_time.elapsed().as_millis() as u64,
+                    first_token_ms,
+                ),
+            );
+        }
+
         if let Some(usage) = stream_parser(&events) {
             let model = model_extractor(&events, &request_model);
+            if let Some(slot) = &usage_slot {
+                *slot.lock().unwrap_or_else(|e| e.into_inner()) =
+                    Some((model.clone(), usage.clone()));
+            }
             let latency_ms = start_time.elapsed().as_millis() as u64;
+            let traffic = traffic.snapshot();
 
             let state = state.clone();
             let provider_id = provider_id.clone();
             let session_id = session_id.clone();
             let _events_for_tps = events;
+            let trace = trace.clone();
+            let request_tag = request_tag.clone();
 
             tokio::spawn(async move {
                 // TPS：仅使用 usage.output_tokens（不做估算），按“请求活跃时间”摊销到滑动窗口（仅统计 2xx）
@@ -304,17 +430,12 @@ fn create_usage_collector(
                     let usage_tokens = usage.output_tokens as u64;
                     if usage_tokens > 0 {
                         state
-                            .tps_monitor
-                            .lock()
-                            .await
-                            .record_completed_request(
-                                usage_tokens,
-                                start_time,
-                                std::time::Instant::now(),
-                            );
+                            .record_throughput(app_type_str, &provider_id, usage_tokens, start_time)
+                            .await;
                     }
                 }
 
+                let _span = trace.span("db.write_usage");
                 log_usage_internal(
                     &state,
                     &provider_id,
@@ -326,17 +447,24 @@ fn create_usage_collector(
                     true, // is_streaming
                     status_code,
                     Some(session_id),
+                    traffic,
+                    request_tag,
+                    is_replay,
                 )
                 .await;
             });
         } else {
             let model = model_extractor(&events, &request_model);
             let latency_ms = start_time.elapsed().as_millis() as u64;
+            let traffic = traffic.snapshot();
             let state = state.clone();
             let provider_id = provider_id.clone();
             let session_id = session_id.clone();
+            let trace = trace.clone();
+            let request_tag = request_tag.clone();
 
             tokio::spawn(async move {
+                let _span = trace.span("db.write_usage");
                 log_usage_internal(
                     &state,
                     &provider_id,
@@ -348,6 +476,9 @@ fn create_usage_collector(
                     true, // is_streaming
                     status_code,
                     Some(session_id),
+                    traffic,
+                    request_tag,
+                    is_replay,
                 )
                 .await;
             });
@@ -371,8 +502,13 @@ fn spawn_log_usage(
     let model = model.to_string();
     let latency_ms = ctx.latency_ms();
     let session_id = ctx.session_id.clone();
+    let trace = ctx.trace.clone();
+    let traffic = ctx.traffic.snapshot();
+    let request_tag = ctx.request_tag.clone();
+    let is_replay = ctx.is_replay;
 
     tokio::spawn(async move {
+        let _span = trace.span("db.write_usage");
         log_usage_internal(
             &state,
             &provider_id,
@@ -384,6 +520,9 @@ fn spawn_log_usage(
             is_streaming,
             status_code,
             Some(session_id),
+            traffic,
+            request_tag,
+            is_replay,
         )
         .await;
     });
@@ -402,14 +541,23 @@ async fn log_usage_internal(
     is_streaming: bool,
     status_code: u16,
     session_id: Option<String>,
+    traffic: RequestTraffic,
+    request_tag: Option<String>,
+    is_replay: bool,
 ) {
     use super::usage::logger::UsageLogger;
 
     let logger = UsageLogger::new(&state.db);
 
-    // 获取 provider 的 cost_multiplier
-    let multiplier = match state.db.get_provider_by_id(provider_id, app_type) {
-        Ok(Some(p)) => {
+    // 获取 provider 的 cost_multiplier 与实际发送的客户端身份
+    let provider = state
+        .db
+        .get_provider_by_id(provider_id, app_type)
+        .ok()
+        .flatten();
+    let client_identity = provider.as_ref().and_then(super::client_identity::recorded);
+    let multiplier = match provider {
+        Some(p) => {
             if let Some(meta) = p.meta {
                 if let Some(cm) = meta.cost_multiplier {
                     Decimal::from_str(&cm).unwrap_or(Decimal::from(1))
@@ -420,7 +568,7 @@ async fn log_usage_internal(
                 Decimal::from(1)
             }
         }
-        _ => Decimal::from(1),
+        None => Decimal::from(1),
     };
 
     let request_id = uuid::Uuid::new_v4().to_string();
@@ -447,6 +595,10 @@ async fn log_usage_internal(
         session_id,
         None, // provider_type
         is_streaming,
+        client_identity,
+        traffic,
+        request_tag,
+        is_replay,
     ) {
         log::warn!("记录使用量失败: {e}");
     }
@@ -463,6 +615,7 @@ pub fn create_logged_passthrough_stream(
         let mut buffer = String::new();
         let mut collector = usage_collector;
         let mut is_first_chunk = true;
+        let measure_overhead = overhead::enabled();
 
         // 超时配置
         let first_byte_timeout = if timeout_config.first_byte_timeout > 0 {
@@ -505,6 +658,7 @@ pub fn create_logged_passthrough_stream(
 
             match chunk_result {
                 Some(Ok(bytes)) => {
+                    let received_at = std::time::Instant::now();
                     is_first_chunk = false;
                     let text = String::from_utf8_lossy(&bytes);
                     buffer.push_str(&text);
@@ -526,10 +680,10 @@ pub fn create_logged_passthrough_stream(
                                             log::info!(
                                                 "[{}] <<< SSE 事件:\n{}",
                                                 tag,
-                                                serde_json::to_string_pretty(&json_value).unwrap_or_else(|_| data.to_string())
+                                                log_scrubber::body_for_log(&json_value)
                                             );
                                         } else {
-                                            log::info!("[{tag}] <<< SSE 数据: {data}");
+                                            log::info!("[{tag}] <<< SSE 数据: {}", log_scrubber::text_for_log(data));
                                         }
                                     } else {
                                         log::info!("[{tag}] <<< SSE: [DONE]");
@@ -539,6 +693,9 @@ pub fn create_logged_passthrough_stream(
                         }
                     }
 
+                    if measure_overhead {
+                        overhead::record_chunk(received_at.elapsed());
+                    }
                     yield Ok(bytes);
                 }
                 Some(Err(e)) => {
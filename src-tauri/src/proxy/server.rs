@@ -7,6 +7,7 @@ use super::{
     ProxyError,
 };
 use crate::database::Database;
+use crate::events::{self, AppEvent};
 use axum::{
     routing::{get, post},
     Router,
@@ -17,7 +18,16 @@ use tokio::sync::{oneshot, RwLock};
 use tokio::task::JoinHandle;
 use tower_http::cors::{Any, CorsLayer};
 
+use super::keep_warm;
+use super::local_socket;
+use super::mdns;
+use super::offline;
+use super::request_dedup;
+use super::request_journal;
 use super::tps_monitor::{TpsMonitor, DEFAULT_WINDOW_SECS};
+use super::tps_sampler::{TpsSampler, DEFAULT_FLUSH_INTERVAL_SECS};
+use crate::services::probe_budget::{BudgetState, ProbeBudgetService};
+use crate::settings::KeepWarmMode;
 
 /// 代理服务器状态（共享）
 #[derive(Clone)]
@@ -36,6 +46,144 @@ pub struct ProxyState {
     pub failover_manager: Arc<FailoverSwitchManager>,
     /// TPS 监控（真实请求滑动窗口聚合）
     pub tps_monitor: Arc<tokio::sync::Mutex<TpsMonitor>>,
+    /// 按应用类型拆分的 TPS 滑动窗口（总览面板使用）
+    pub app_tps_monitors: Arc<tokio::sync::Mutex<std::collections::HashMap<String, TpsMonitor>>>,
+    /// 吞吐量采样（待落盘的分钟桶）
+    pub tps_sampler: Arc<tokio::sync::Mutex<TpsSampler>>,
+}
+
+impl ProxyState {
+    /// 记录一次完成请求的输出 token（同时更新 TPS 滑动窗口与持久化采样）
+    pub async fn record_throughput(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        output_tokens: u64,
+        start: std::time::Instant,
+    ) {
+        let end = std::time::Instant::now();
+        self.tps_monitor
+            .lock()
+            .await
+            .record_completed_request(output_tokens, start, end);
+        self.app_tps_monitors
+            .lock()
+            .await
+            .entry(app_type.to_string())
+            .or_insert_with(|| TpsMonitor::new(DEFAULT_WINDOW_SECS))
+            .record_completed_request(output_tokens, start, end);
+        self.tps_sampler.lock().await.record(
+            app_type,
+            provider_id,
+            output_tokens,
+            (end - start).as_millis() as u64,
+            chrono::Utc::now().timestamp(),
+        );
+    }
+
+    /// 将待落盘的吞吐量样本写入数据库（未开启持久化时直接丢弃），并按保留期清理旧样本
+    pub async fn flush_tps_samples(&self) {
+        let samples = self.tps_sampler.lock().await.drain();
+        for sample in &samples {
+            events::publish(AppEvent::TpsSample(sample.clone()));
+        }
+        let settings = crate::settings::get_settings();
+        if !settings.persist_tps_samples {
+            return;
+        }
+
+        if let Err(e) = self.db.save_tps_samples(&samples) {
+            log::warn!("保存吞吐量样本失败: {e}");
+        }
+
+        let retention_secs = settings.tps_sample_retention_days.max(1) as i64 * 86_400;
+        let cutoff = chrono::Utc::now().timestamp() - retention_secs;
+        match self.db.prune_tps_samples(cutoff) {
+            Ok(n) if n > 0 => log::debug!("已清理 {n} 条过期吞吐量样本"),
+            Ok(_) => {}
+            Err(e) => log::warn!("清理吞吐量样本失败: {e}"),
+        }
+    }
+
+    /// 通知前端网络状态变化
+    fn emit_network_status(&self) {
+        events::publish(AppEvent::NetworkStatusChanged(offline::status()));
+    }
+
+    /// 空闲保活检测：空闲超过设定时长时，向各应用当前供应商发送一次保活请求
+    async fn keep_warm_tick(&self, last_ping: &mut Option<i64>) {
+        let settings = crate::settings::get_settings();
+        if !settings.keep_warm_enabled || offline::is_offline() {
+            return;
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let last_activity = self
+            .status
+            .read()
+            .await
+            .last_request_at
+            .as_deref()
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+            .map(|t| t.timestamp());
+        let idle_secs = settings.keep_warm_idle_minutes.max(1) as i64 * 60;
+        if !keep_warm::should_ping(now, last_activity, *last_ping, idle_secs) {
+            return;
+        }
+        *last_ping = Some(now);
+
+        let targets: Vec<(String, String)> = self
+            .current_providers
+            .read()
+            .await
+            .iter()
+            .map(|(app_type, (provider_id, _))| (app_type.clone(), provider_id.clone()))
+            .collect();
+        let check_config = self.db.get_stream_check_config().unwrap_or_default();
+
+        for (app_type_str, provider_id) in targets {
+            let Ok(app_type) = app_type_str.parse::<crate::app_config::AppType>() else {
+                continue;
+            };
+            let provider = match self.db.get_provider_by_id(&provider_id, &app_type_str) {
+                Ok(Some(p)) => p,
+                Ok(None) => continue,
+                Err(e) => {
+                    log::warn!("[KeepWarm] 读取供应商 {provider_id} 失败: {e}");
+                    continue;
+                }
+            };
+
+            // 接近或用尽探测预算时，补全保活降级为不消耗 token 的 HEAD 请求
+            let budget = ProbeBudgetService::state_or_normal(
+                &self.db,
+                &app_type_str,
+                &provider,
+                &check_config,
+            );
+            let mode = if budget == BudgetState::Normal {
+                settings.keep_warm_mode
+            } else {
+                KeepWarmMode::Head
+            };
+
+            match keep_warm::ping(&app_type, &provider, mode, &check_config).await {
+                Ok(tokens_used) => {
+                    if mode == KeepWarmMode::Completion {
+                        ProbeBudgetService::record_tokens(
+                            &self.db,
+                            &app_type_str,
+                            &provider,
+                            &check_config,
+                            tokens_used.unwrap_or(keep_warm::COMPLETION_PING_TOKENS),
+                        );
+                    }
+                    log::debug!("[KeepWarm] {app_type_str}/{} 保活成功", provider.name);
+                }
+                Err(e) => log::warn!("[KeepWarm] {app_type_str}/{} 保活失败: {e}", provider.name),
+            }
+        }
+    }
 }
 
 /// 代理HTTP服务器
@@ -45,6 +193,16 @@ pub struct ProxyServer {
     shutdown_tx: Arc<RwLock<Option<oneshot::Sender<()>>>>,
     /// 服务器任务句柄，用于等待服务器实际关闭
     server_handle: Arc<RwLock<Option<JoinHandle<()>>>>,
+    /// 吞吐量样本定时落盘任务句柄
+    sampler_handle: Arc<RwLock<Option<JoinHandle<()>>>>,
+    /// 空闲保活任务句柄
+    keep_warm_handle: Arc<RwLock<Option<JoinHandle<()>>>>,
+    /// 离线检测任务句柄
+    offline_handle: Arc<RwLock<Option<JoinHandle<()>>>>,
+    /// 本地套接字监听任务句柄（路径记录在状态中）
+    local_socket_handle: Arc<RwLock<Option<JoinHandle<()>>>>,
+    /// 局域网 mDNS 广播
+    mdns_advertiser: Arc<RwLock<Option<mdns::Advertiser>>>,
 }
 
 impl ProxyServer {
@@ -70,6 +228,8 @@ impl ProxyServer {
             tps_monitor: Arc::new(tokio::sync::Mutex::new(TpsMonitor::new(
                 DEFAULT_WINDOW_SECS,
             ))),
+            app_tps_monitors: Arc::new(tokio::sync::Mutex::new(std::collections::HashMap::new())),
+            tps_sampler: Arc::new(tokio::sync::Mutex::new(TpsSampler::new())),
         };
 
         Self {
@@ -77,6 +237,11 @@ impl ProxyServer {
             state,
             shutdown_tx: Arc::new(RwLock::new(None)),
             server_handle: Arc::new(RwLock::new(None)),
+            sampler_handle: Arc::new(RwLock::new(None)),
+            keep_warm_handle: Arc::new(RwLock::new(None)),
+            offline_handle: Arc::new(RwLock::new(None)),
+            local_socket_handle: Arc::new(RwLock::new(None)),
+            mdns_advertiser: Arc::new(RwLock::new(None)),
         }
     }
 
@@ -86,10 +251,15 @@ impl ProxyServer {
             return Err(ProxyError::AlreadyRunning);
         }
 
-        let addr: SocketAddr =
+        let settings = crate::settings::get_settings();
+        let socket_config = settings.proxy_local_socket;
+        let addr: SocketAddr = if socket_config.enabled && socket_config.socket_only {
+            local_socket::socket_only_tcp_addr()
+        } else {
             format!("{}:{}", self.config.listen_address, self.config.listen_port)
                 .parse()
-                .map_err(|e| ProxyError::BindFailed(format!("无效的地址: {e}")))?;
+                .map_err(|e| ProxyError::BindFailed(format!("无效的地址: {e}")))?
+        };
 
         // 创建关闭通道
         let (shutdown_tx, shutdown_rx) = oneshot::channel();
@@ -101,22 +271,46 @@ impl ProxyServer {
         let listener = tokio::net::TcpListener::bind(&addr)
             .await
             .map_err(|e| ProxyError::BindFailed(e.to_string()))?;
+        // 仅本地套接字模式下端口随机分配，以实际监听地址为准
+        let addr = listener.local_addr().unwrap_or(addr);
 
         log::info!("代理服务器启动于 {addr}");
 
+        let socket_path = if socket_config.enabled {
+            let path = socket_config.resolved_path();
+            let handle = local_socket::serve(&path, addr).await?;
+            *self.local_socket_handle.write().await = Some(handle);
+            Some(path)
+        } else {
+            None
+        };
+
+        // 局域网共享时广播代理地址（失败不影响代理启动）
+        *self.mdns_advertiser.write().await =
+            mdns::start_advertising(&settings.lan_discovery, addr);
+
         // 保存关闭句柄
         *self.shutdown_tx.write().await = Some(shutdown_tx);
 
         // 更新状态
         let mut status = self.state.status.write().await;
         status.running = true;
-        status.address = self.config.listen_address.clone();
-        status.port = self.config.listen_port;
+        status.address = addr.ip().to_string();
+        status.port = addr.port();
+        status.local_socket = socket_path;
         drop(status);
 
         // 记录启动时间
         *self.state.start_time.write().await = Some(std::time::Instant::now());
 
+        // 重新发送上次运行残留的请求（监听器已绑定，连接会在服务器运行后被接受）
+        request_journal::set_stopping(false);
+        tokio::spawn(request_journal::resume_pending(
+            self.state.db.clone(),
+            addr,
+            chrono::Utc::now().timestamp(),
+        ));
+
         // 启动服务器
         let state = self.state.clone();
         let handle = tokio::spawn(async move {
@@ -135,9 +329,48 @@ impl ProxyServer {
         // 保存服务器任务句柄
         *self.server_handle.write().await = Some(handle);
 
+        // 启动吞吐量样本定时落盘任务
+        let state = self.state.clone();
+        let sampler_handle = tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(std::time::Duration::from_secs(DEFAULT_FLUSH_INTERVAL_SECS));
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                state.flush_tps_samples().await;
+            }
+        });
+        *self.sampler_handle.write().await = Some(sampler_handle);
+
+        // 启动空闲保活任务（是否发送由设置决定，每轮重新读取）
+        let state = self.state.clone();
+        let keep_warm_handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(
+                keep_warm::CHECK_INTERVAL_SECS,
+            ));
+            let mut last_ping = None;
+            loop {
+                interval.tick().await;
+                state.keep_warm_tick(&mut last_ping).await;
+            }
+        });
+        *self.keep_warm_handle.write().await = Some(keep_warm_handle);
+
+        // 启动离线检测任务（状态变化时通知前端）
+        let state = self.state.clone();
+        let offline_handle = tokio::spawn(async move {
+            loop {
+                if offline::refresh().await {
+                    state.emit_network_status();
+                }
+                tokio::time::sleep(offline::next_interval()).await;
+            }
+        });
+        *self.offline_handle.write().await = Some(offline_handle);
+
         Ok(ProxyServerInfo {
-            address: self.config.listen_address.clone(),
-            port: self.config.listen_port,
+            address: addr.ip().to_string(),
+            port: addr.port(),
             started_at: chrono::Utc::now().to_rfc3339(),
         })
     }
@@ -145,6 +378,8 @@ impl ProxyServer {
     pub async fn stop(&self) -> Result<(), ProxyError> {
         // 1. 发送关闭信号
         if let Some(tx) = self.shutdown_tx.write().await.take() {
+            request_journal::set_stopping(true);
+            super::management_api::close_event_streams();
             let _ = tx.send(());
         } else {
             return Err(ProxyError::NotRunning);
@@ -159,8 +394,31 @@ impl ProxyServer {
             }
         }
 
+        // 停止定时落盘任务，并写入最后一批样本
+        if let Some(handle) = self.sampler_handle.write().await.take() {
+            handle.abort();
+        }
+        self.state.flush_tps_samples().await;
+
+        if let Some(handle) = self.keep_warm_handle.write().await.take() {
+            handle.abort();
+        }
+        if let Some(handle) = self.offline_handle.write().await.take() {
+            handle.abort();
+        }
+        if let Some(handle) = self.local_socket_handle.write().await.take() {
+            handle.abort();
+        }
+        if let Some(path) = self.state.status.write().await.local_socket.take() {
+            local_socket::cleanup(&path);
+        }
+        if let Some(mut advertiser) = self.mdns_advertiser.write().await.take() {
+            advertiser.stop();
+        }
+
         // 清理 TPS 监控窗口，避免停止后短时间仍显示旧值
         self.state.tps_monitor.lock().await.reset();
+        self.state.app_tps_monitors.lock().await.clear();
 
         Ok(())
     }
@@ -186,10 +444,34 @@ impl ProxyServer {
 
         // TPS：滑动窗口聚合
         status.tps = self.state.tps_monitor.lock().await.current_tps();
+        status.offline = offline::is_offline();
+        status.overhead = super::overhead::snapshot();
+        status.concurrency = super::concurrency::snapshot();
 
         status
     }
 
+    /// 当前参与保活的供应商数量
+    pub async fn keep_warm_target_count(&self) -> usize {
+        self.state.current_providers.read().await.len()
+    }
+
+    /// 各应用类型当前的 TPS（无请求的应用不出现）
+    pub async fn get_app_tps(&self) -> std::collections::HashMap<String, f64> {
+        self.state
+            .app_tps_monitors
+            .lock()
+            .await
+            .iter_mut()
+            .map(|(app_type, monitor)| (app_type.clone(), monitor.current_tps()))
+            .collect()
+    }
+
+    /// 获取 TPS 分桶历史快照
+    pub async fn get_tps_history(&self) -> Vec<TpsHistoryPoint> {
+        self.state.tps_monitor.lock().await.history_snapshot()
+    }
+
     fn build_router(&self) -> Router {
         let cors = CorsLayer::new()
             .allow_origin(Any)
@@ -200,6 +482,7 @@ impl ProxyServer {
             // 健康检查
             .route("/health", get(handlers::health_check))
             .route("/status", get(handlers::get_status))
+            .route("/metrics", get(super::metrics::metrics))
             // Claude API (支持带前缀和不带前缀两种格式)
             .route("/v1/messages", post(handlers::handle_messages))
             .route("/claude/v1/messages", post(handlers::handle_messages))
@@ -225,6 +508,17 @@ impl ProxyServer {
             // Gemini API (支持带前缀和不带前缀)
             .route("/v1beta/*path", post(handlers::handle_gemini))
             .route("/gemini/v1beta/*path", post(handlers::handle_gemini))
+            // 持久化非流式请求（仅作用于以上代理路由）
+            .layer(axum::middleware::from_fn_with_state(
+                self.state.clone(),
+                request_journal::layer,
+            ))
+            // 合并仍在返回中的重复请求（位于持久化之外，合并的请求不再记录）
+            .layer(axum::middleware::from_fn(request_dedup::layer))
+            // 管理 API（未开启时返回 404）
+            .merge(super::management_api::router())
+            // 模拟上游（未开启时返回 404）
+            .merge(super::mock_upstream::router())
             .layer(cors)
             .with_state(self.state.clone())
     }
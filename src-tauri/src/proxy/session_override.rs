@@ -0,0 +1,242 @@
+//! 临时供应商覆盖
+//!
+//! 将某个会话、客户端密钥或客户端的请求临时路由到指定供应商，
+//! 在 N 分钟或 N 次请求后自动恢复，不修改全局的当前供应商。
+//! 适合“只用备用中转站试一下这个提示词”的场景。
+//!
+//! 覆盖保存在内存中，退出时随代理运行状态一起保存，重启后恢复仍未到期的部分。
+//! 命中覆盖的请求只发往指定供应商，不参与故障转移。
+
+use std::sync::RwLock;
+
+use axum::http::HeaderMap;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+/// 客户端标识请求头
+pub const CLIENT_HEADER: &str = "x-cc-switch-client";
+/// 单条覆盖最长持续时间（分钟）
+pub const MAX_MINUTES: u32 = 24 * 60;
+
+static OVERRIDES: Lazy<RwLock<Vec<SessionOverride>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+/// 覆盖的匹配对象
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "camelCase")]
+pub enum OverrideTarget {
+    /// 会话 ID（与请求日志中的 session_id 一致）
+    Session(String),
+    /// 客户端发给代理的密钥（`x-api-key` 或 `Authorization: Bearer`）
+    ClientKey(String),
+    /// 客户端名称（`x-cc-switch-client` 请求头，或 User-Agent 前缀）
+    Client(String),
+}
+
+impl OverrideTarget {
+    fn matches(&self, session_id: &str, headers: &HeaderMap) -> bool {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        match self {
+            Self::Session(id) => id == session_id,
+            Self::ClientKey(key) => {
+                let bearer = header("authorization")
+                    .and_then(|v| v.strip_prefix("Bearer "))
+                    .map(str::trim);
+                header("x-api-key").map(str::trim) == Some(key.as_str())
+                    || bearer == Some(key.as_str())
+            }
+            Self::Client(name) => {
+                header(CLIENT_HEADER) == Some(name.as_str())
+                    || header("user-agent").is_some_and(|ua| ua.starts_with(name.as_str()))
+            }
+        }
+    }
+
+    fn value(&self) -> &str {
+        match self {
+            Self::Session(v) | Self::ClientKey(v) | Self::Client(v) => v,
+        }
+    }
+}
+
+/// 临时覆盖
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionOverride {
+    pub id: String,
+    pub app_type: String,
+    pub target: OverrideTarget,
+    pub provider_id: String,
+    /// 到期时间（Unix 秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    /// 剩余请求次数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remaining_requests: Option<u32>,
+    pub created_at: i64,
+}
+
+impl SessionOverride {
+    fn is_live(&self, now: i64) -> bool {
+        self.expires_at.is_none_or(|at| at > now) && self.remaining_requests != Some(0)
+    }
+}
+
+/// 添加覆盖（同一应用、同一对象的旧覆盖会被替换）
+///
+/// `minutes` 与 `requests` 至少设置一个，同时设置时先到者为准。
+pub fn set(
+    app_type: &str,
+    target: OverrideTarget,
+    provider_id: &str,
+    minutes: Option<u32>,
+    requests: Option<u32>,
+    now: i64,
+) -> Result<SessionOverride, String> {
+    if target.value().trim().is_empty() {
+        return Err("匹配对象不能为空".to_string());
+    }
+    if minutes.is_none() && requests.is_none() {
+        return Err("需要设置持续分钟数或请求次数".to_string());
+    }
+    if minutes == Some(0) || requests == Some(0) {
+        return Err("持续分钟数与请求次数必须大于 0".to_string());
+    }
+
+    let entry = SessionOverride {
+        id: uuid::Uuid::new_v4().to_string(),
+        app_type: app_type.to_string(),
+        target,
+        provider_id: provider_id.to_string(),
+        expires_at: minutes.map(|m| now + m.min(MAX_MINUTES) as i64 * 60),
+        remaining_requests: requests,
+        created_at: now,
+    };
+    let mut overrides = OVERRIDES.write().unwrap_or_else(|e| e.into_inner());
+    overrides.retain(|o| !(o.app_type == entry.app_type && o.target == entry.target));
+    overrides.push(entry.clone());
+    log::info!(
+        "[SessionOverride] {app_type} {:?} -> {provider_id}（{minutes:?} 分钟 / {requests:?} 次）",
+        entry.target
+    );
+    Ok(entry)
+}
+
+/// 移除覆盖，返回是否存在
+pub fn clear(id: &str) -> bool {
+    let mut overrides = OVERRIDES.write().unwrap_or_else(|e| e.into_inner());
+    let before = overrides.len();
+    overrides.retain(|o| o.id != id);
+    overrides.len() != before
+}
+
+/// 仍然生效的覆盖（顺带清理已失效的）
+pub fn list(now: i64) -> Vec<SessionOverride> {
+    let mut overrides = OVERRIDES.write().unwrap_or_else(|e| e.into_inner());
+    overrides.retain(|o| o.is_live(now));
+    overrides.clone()
+}
+
+/// 恢复上次运行保存的覆盖（跳过已失效或已存在的），返回恢复的数量
+pub fn restore(saved: Vec<SessionOverride>, now: i64) -> usize {
+    let mut overrides = OVERRIDES.write().unwrap_or_else(|e| e.into_inner());
+    let before = overrides.len();
+    for entry in saved {
+        if entry.is_live(now) && !overrides.iter().any(|o| o.id == entry.id) {
+            overrides.push(entry);
+        }
+    }
+    overrides.len() - before
+}
+
+/// 为请求查找覆盖并计入一次使用，返回目标供应商 ID
+///
+/// 多条覆盖同时匹配时，会话优先于客户端密钥，客户端密钥优先于客户端名称。
+pub fn take(app_type: &str, session_id: &str, headers: &HeaderMap, now: i64) -> Option<String> {
+    let mut overrides = OVERRIDES.write().unwrap_or_else(|e| e.into_inner());
+    overrides.retain(|o| o.is_live(now));
+    let rank = |target: &OverrideTarget| match target {
+        OverrideTarget::Session(_) => 0,
+        OverrideTarget::ClientKey(_) => 1,
+        OverrideTarget::Client(_) => 2,
+    };
+    let entry = overrides
+        .iter_mut()
+        .filter(|o| o.app_type == app_type && o.target.matches(session_id, headers))
+        .min_by_key(|o| rank(&o.target))?;
+    if let Some(remaining) = entry.remaining_requests.as_mut() {
+        *remaining -= 1;
+    }
+    Some(entry.provider_id.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn request_count_override_reverts() {
+        set(
+            "claude",
+            OverrideTarget::Session("so-session".to_string()),
+            "backup",
+            None,
+            Some(2),
+            1_000,
+        )
+        .unwrap();
+        let headers = HeaderMap::new();
+        assert_eq!(
+            take("claude", "so-session", &headers, 1_000).as_deref(),
+            Some("backup")
+        );
+        assert!(take("codex", "so-session", &headers, 1_000).is_none());
+        assert!(take("claude", "so-session", &headers, 1_000).is_some());
+        assert!(take("claude", "so-session", &headers, 1_000).is_none());
+    }
+
+    #[test]
+    fn time_override_matches_client_key_and_expires() {
+        let entry = set(
+            "codex",
+            OverrideTarget::ClientKey("sk-so-virtual".to_string()),
+            "relay-b",
+            Some(5),
+            None,
+            1_000,
+        )
+        .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "authorization",
+            HeaderValue::from_static("Bearer sk-so-virtual"),
+        );
+        assert_eq!(
+            take("codex", "any", &headers, 1_200).as_deref(),
+            Some("relay-b")
+        );
+        assert!(take("codex", "any", &headers, 1_300).is_none());
+        assert!(!clear(&entry.id));
+    }
+
+    #[test]
+    fn client_header_and_validation() {
+        let entry = set(
+            "gemini",
+            OverrideTarget::Client("so-script".to_string()),
+            "p2",
+            Some(10),
+            Some(10),
+            0,
+        )
+        .unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(CLIENT_HEADER, HeaderValue::from_static("so-script"));
+        assert!(take("gemini", "s", &headers, 1).is_some());
+        assert!(clear(&entry.id));
+
+        let target = OverrideTarget::Client("x".to_string());
+        assert!(set("gemini", target.clone(), "p2", None, None, 0).is_err());
+        assert!(set("gemini", target, "p2", Some(0), None, 0).is_err());
+    }
+}
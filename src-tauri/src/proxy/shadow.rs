@@ -0,0 +1,295 @@
+//! 影子镜像（A/B 响应对比）
+//!
+//! 按设置的采样比例把请求复制一份发往另一个供应商（影子），影子的响应不会返回给客户端，
+//! 只与实际响应比较延迟、输出 token 与回复文本相似度，写入数据库，便于用真实流量评估新中转站。
+//!
+//! 影子请求不经过熔断器和故障转移，也不计入代理统计与用量记录。
+//! 实际响应出错或客户端中断时不产生对比记录。
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::oneshot;
+
+use super::{
+    forwarder::RequestForwarder, handler_config::UsageParserConfig,
+    response_processor::is_sse_response, server::ProxyState, transcript, ProxyError,
+};
+use crate::app_config::AppType;
+use crate::database::{Database, ShadowComparison};
+
+/// 影子请求超时（秒）
+const SHADOW_TIMEOUT_SECS: u64 = 600;
+
+/// 影子镜像规则（每个应用一条）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShadowRule {
+    pub app_type: String,
+    /// 影子供应商 ID
+    pub provider_id: String,
+    /// 采样比例（0~100）
+    #[serde(default)]
+    pub sample_percent: u8,
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// 一侧响应的对比指标
+#[derive(Debug, Clone, Default)]
+struct SideResult {
+    status: u16,
+    latency_ms: u64,
+    output_tokens: u64,
+    text: String,
+    error: Option<String>,
+}
+
+/// 进行中的影子请求（请求开始时发出，实际响应完成后写入对比结果）
+#[derive(Clone)]
+pub struct ShadowCapture {
+    app_type: &'static str,
+    shadow_provider_id: String,
+    model: String,
+    result: Arc<Mutex<Option<oneshot::Receiver<SideResult>>>>,
+}
+
+fn sampled(percent: u8) -> bool {
+    percent >= 100 || (uuid::Uuid::new_v4().as_u128() % 100) < percent as u128
+}
+
+impl ShadowCapture {
+    /// 按设置决定是否镜像本次请求；命中时立即在后台发出影子请求
+    #[allow(clippy::too_many_arguments)]
+    pub fn start(
+        state: &ProxyState,
+        app_type: &AppType,
+        app_type_str: &'static str,
+        primary_provider_id: &str,
+        model: &str,
+        endpoint: &str,
+        body: &Value,
+        headers: &HeaderMap,
+        parser: &'static UsageParserConfig,
+    ) -> Option<Self> {
+        let rule = crate::settings::get_settings()
+            .shadow_rules
+            .into_iter()
+            .find(|r| r.enabled && r.app_type == app_type_str)?;
+        if rule.provider_id == primary_provider_id || !sampled(rule.sample_percent) {
+            return None;
+        }
+        let provider = match state.db.get_provider_by_id(&rule.provider_id, app_type_str) {
+            Ok(Some(provider)) => provider,
+            Ok(None) => {
+                log::warn!("[Shadow] 影子供应商 {} 不存在，跳过镜像", rule.provider_id);
+                return None;
+            }
+            Err(e) => {
+                log::warn!("[Shadow] 读取影子供应商失败: {e}");
+                return None;
+            }
+        };
+
+        log::info!(
+            "[Shadow] 镜像 {app_type_str} 请求到影子供应商 {}",
+            provider.name
+        );
+        let forwarder = RequestForwarder::new(
+            state.provider_router.clone(),
+            SHADOW_TIMEOUT_SECS,
+            state.status.clone(),
+            state.current_providers.clone(),
+            state.failover_manager.clone(),
+            None,
+            String::new(),
+            0,
+            0,
+        );
+        let (tx, rx) = oneshot::channel();
+        let app_type = app_type.clone();
+        let endpoint = endpoint.to_string();
+        let body = body.clone();
+        let headers = headers.clone();
+        tokio::spawn(async move {
+            let start = Instant::now();
+            let side = match forwarder
+                .forward_once(&app_type, &endpoint, &body, &headers, &provider)
+                .await
+            {
+                Ok(response) => read_response(response, parser, start).await,
+                Err(e) => SideResult {
+                    status: match &e {
+                        ProxyError::UpstreamError { status, .. } => *status,
+                        _ => 0,
+                    },
+                    latency_ms: start.elapsed().as_millis() as u64,
+                    error: Some(e.to_string()),
+                    ..Default::default()
+                },
+            };
+            let _ = tx.send(side);
+        });
+
+        Some(Self {
+            app_type: app_type_str,
+            shadow_provider_id: rule.provider_id,
+            model: model.to_string(),
+            result: Arc::new(Mutex::new(Some(rx))),
+        })
+    }
+
+    /// 实际响应完成后调用：等待影子响应并写入对比结果（后台执行，只记录一次）
+    pub fn finish_primary(
+        &self,
+        db: Arc<Database>,
+        primary_provider_id: &str,
+        status: u16,
+        latency_ms: u64,
+        output_tokens: u64,
+        text: String,
+    ) {
+        let Some(rx) = self.result.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+            return;
+        };
+        let primary = SideResult {
+            status,
+            latency_ms,
+            output_tokens,
+            text,
+            error: None,
+        };
+        let capture = self.clone();
+        let primary_provider_id = primary_provider_id.to_string();
+        tokio::spawn(async move {
+            let wait = Duration::from_secs(SHADOW_TIMEOUT_SECS + 5);
+            let Ok(Ok(shadow)) = tokio::time::timeout(wait, rx).await else {
+                log::debug!("[Shadow] 未等到影子响应，跳过对比");
+                return;
+            };
+            let record = ShadowComparison {
+                id: 0,
+                app_type: capture.app_type.to_string(),
+                primary_provider_id,
+                shadow_provider_id: capture.shadow_provider_id.clone(),
+                model: capture.model.clone(),
+                primary_status: primary.status,
+                shadow_status: shadow.status,
+                primary_latency_ms: primary.latency_ms,
+                shadow_latency_ms: shadow.latency_ms,
+                primary_output_tokens: primary.output_tokens,
+                shadow_output_tokens: shadow.output_tokens,
+                similarity: similarity(&primary.text, &shadow.text),
+                shadow_error: shadow.error,
+                created_at: chrono::Utc::now().timestamp(),
+            };
+            let _ = tokio::task::spawn_blocking(move || {
+                if let Err(e) = db.insert_shadow_comparison(&record) {
+                    log::warn!("[Shadow] 保存对比结果失败: {e}");
+                }
+            })
+            .await;
+        });
+    }
+}
+
+/// 读取影子响应的完整内容（流式响应拼接所有事件）
+async fn read_response(
+    response: reqwest::Response,
+    parser: &UsageParserConfig,
+    start: Instant,
+) -> SideResult {
+    let status = response.status().as_u16();
+    let is_sse = is_sse_response(&response);
+    let bytes = match response.bytes().await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return SideResult {
+                status,
+                latency_ms: start.elapsed().as_millis() as u64,
+                error: Some(format!("读取影子响应失败: {e}")),
+                ..Default::default()
+            }
+        }
+    };
+    let latency_ms = start.elapsed().as_millis() as u64;
+    let raw = String::from_utf8_lossy(&bytes);
+
+    let (text, usage) = if is_sse {
+        let events = sse_events(&raw);
+        (
+            transcript::completion_from_events(&events),
+            (parser.stream_parser)(&events),
+        )
+    } else {
+        match serde_json::from_str::<Value>(&raw) {
+            Ok(json) => (
+                transcript::completion_from_response(&json),
+                (parser.response_parser)(&json),
+            ),
+            Err(_) => (String::new(), None),
+        }
+    };
+    SideResult {
+        status,
+        latency_ms,
+        output_tokens: usage.map(|u| u.output_tokens as u64).unwrap_or(0),
+        text,
+        error: None,
+    }
+}
+
+/// 解析完整 SSE 响应中的 JSON 事件
+pub(crate) fn sse_events(raw: &str) -> Vec<Value> {
+    raw.lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(str::trim)
+        .filter(|data| *data != "[DONE]")
+        .filter_map(|data| serde_json::from_str(data).ok())
+        .collect()
+}
+
+/// 回复文本相似度：按词（忽略大小写）计算 Jaccard 系数，双方都为空时视为完全一致
+pub(crate) fn similarity(a: &str, b: &str) -> f64 {
+    let words =
+        |s: &str| -> HashSet<String> { s.split_whitespace().map(str::to_lowercase).collect() };
+    let (a, b) = (words(a), words(b));
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let common = a.intersection(&b).count();
+    common as f64 / (a.len() + b.len() - common) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn similarity_compares_words() {
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("Hello world", "hello   WORLD"), 1.0);
+        assert_eq!(similarity("a b", "c d"), 0.0);
+        let partial = similarity("a b c", "a b d");
+        assert!((partial - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn parses_sse_events() {
+        let raw = "event: message_start\ndata: {\"type\":\"message_start\"}\n\n\
+                   data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\ndata: [DONE]\n\n";
+        let events = sse_events(raw);
+        assert_eq!(events.len(), 2);
+        assert_eq!(transcript::completion_from_events(&events), "Hi");
+    }
+
+    #[test]
+    fn sampling_bounds() {
+        assert!(!sampled(0));
+        assert!(sampled(100));
+    }
+}
@@ -0,0 +1,124 @@
+//! SSE 数据块合并
+//!
+//! 部分中转站每个 token 单独发送一个 TCP 包，终端会因此频繁重绘。
+//! 供应商配置了 `sseCoalesceMs` 时，代理把一段时间内收到的数据攒在一起再转发；
+//! 只在完整事件（`\n\n`）边界处切分，不会把一个事件拆到两次写入中。
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures::stream::{Stream, StreamExt};
+
+use crate::provider::Provider;
+
+/// 合并窗口上限（毫秒），避免配置过大导致明显卡顿
+const MAX_WINDOW_MS: u64 = 500;
+
+/// 供应商配置的合并窗口（未配置或为 0 时不合并）
+pub fn coalesce_window(provider: &Provider) -> Option<Duration> {
+    provider
+        .meta
+        .as_ref()
+        .and_then(|m| m.sse_coalesce_ms)
+        .filter(|ms| *ms > 0)
+        .map(|ms| Duration::from_millis(ms.min(MAX_WINDOW_MS)))
+}
+
+/// 按供应商配置生成响应体（需要时先合并数据块）
+pub fn body_for<S>(stream: S, provider: &Provider) -> axum::body::Body
+where
+    S: Stream<Item = Result<Bytes, std::io::Error>> + Send + 'static,
+{
+    match coalesce_window(provider) {
+        Some(window) => axum::body::Body::from_stream(coalesce(stream, window)),
+        None => axum::body::Body::from_stream(stream),
+    }
+}
+
+/// 取出缓冲区中最后一个事件边界之前的内容
+fn take_complete_events(pending: &mut Vec<u8>) -> Option<Bytes> {
+    let end = pending.windows(2).rposition(|w| w == b"\n\n")? + 2;
+    let rest = pending.split_off(end);
+    Some(Bytes::from(std::mem::replace(pending, rest)))
+}
+
+/// 按时间窗口合并 SSE 数据块
+pub fn coalesce<S>(
+    stream: S,
+    window: Duration,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send
+where
+    S: Stream<Item = Result<Bytes, std::io::Error>> + Send + 'static,
+{
+    async_stream::stream! {
+        tokio::pin!(stream);
+        let mut pending: Vec<u8> = Vec::new();
+        let mut deadline: Option<tokio::time::Instant> = None;
+
+        loop {
+            let next = match deadline {
+                Some(at) => match tokio::time::timeout_at(at, stream.next()).await {
+                    Ok(next) => next,
+                    Err(_) => {
+                        // 窗口结束：转发已完整的事件，半个事件留待下一块数据
+                        deadline = None;
+                        if let Some(events) = take_complete_events(&mut pending) {
+                            yield Ok(events);
+                        }
+                        continue;
+                    }
+                },
+                None => stream.next().await,
+            };
+
+            match next {
+                Some(Ok(bytes)) => {
+                    pending.extend_from_slice(&bytes);
+                    if deadline.is_none() {
+                        deadline = Some(tokio::time::Instant::now() + window);
+                    }
+                }
+                Some(Err(e)) => {
+                    if !pending.is_empty() {
+                        yield Ok(Bytes::from(std::mem::take(&mut pending)));
+                    }
+                    yield Err(e);
+                    break;
+                }
+                None => {
+                    if !pending.is_empty() {
+                        yield Ok(Bytes::from(std::mem::take(&mut pending)));
+                    }
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_only_complete_events() {
+        let mut pending = b"data: a\n\ndata: b\n\ndata: c".to_vec();
+        let events = take_complete_events(&mut pending).unwrap();
+        assert_eq!(&events[..], b"data: a\n\ndata: b\n\n");
+        assert_eq!(pending, b"data: c");
+        assert!(take_complete_events(&mut pending).is_none());
+    }
+
+    #[tokio::test]
+    async fn merges_chunks_within_window() {
+        let chunks: Vec<Result<Bytes, std::io::Error>> = ["data: 1\n\n", "data: 2\n\n", "data: 3"]
+            .into_iter()
+            .map(|s| Ok(Bytes::from(s)))
+            .collect();
+        let out: Vec<Bytes> = coalesce(futures::stream::iter(chunks), Duration::from_millis(50))
+            .map(|r| r.unwrap())
+            .collect()
+            .await;
+        assert_eq!(out, vec![Bytes::from("data: 1\n\ndata: 2\n\ndata: 3")]);
+    }
+}
@@ -0,0 +1,297 @@
+//! SSE 响应编码修复
+//!
+//! 部分中转站返回的流式响应带有 BOM、CR/CRLF 换行或非法 UTF-8 字节，会让 Claude Code 的
+//! SSE 解析器出错。代理在转发前逐块修复：
+//! - 非法 UTF-8 字节替换为 U+FFFD（跨数据块的多字节字符会等下一块拼完整再判断）
+//! - 去掉流开头与每行开头的 BOM
+//! - CRLF 与单独的 CR 统一为 LF
+//!
+//! 无需修复的数据块原样转发。按供应商统计修复次数，作为中转站质量的参考。
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use bytes::Bytes;
+use futures::stream::{Stream, StreamExt};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::provider::Provider;
+
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// 单个供应商的修复统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SanitizeStats {
+    pub app_type: String,
+    pub provider_id: String,
+    pub provider_name: String,
+    /// 需要修复的流式响应数
+    pub streams: u64,
+    /// 替换的非法 UTF-8 序列数
+    pub invalid_utf8: u64,
+    /// 去掉的 BOM 数
+    pub boms: u64,
+    /// 改写的 CR / CRLF 换行数
+    pub line_endings: u64,
+}
+
+static STATS: Lazy<RwLock<HashMap<(String, String), SanitizeStats>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Fixes {
+    invalid_utf8: u64,
+    boms: u64,
+    line_endings: u64,
+}
+
+impl Fixes {
+    fn any(&self) -> bool {
+        self.invalid_utf8 + self.boms + self.line_endings > 0
+    }
+}
+
+/// 逐块修复的状态
+struct Sanitizer {
+    /// 上一块末尾不完整的 UTF-8 序列
+    carry: Vec<u8>,
+    /// 上一块以 CR 结尾，本块开头的 LF 属于同一个换行
+    skip_lf: bool,
+    at_line_start: bool,
+    fixes: Fixes,
+}
+
+impl Default for Sanitizer {
+    fn default() -> Self {
+        Self {
+            carry: Vec::new(),
+            skip_lf: false,
+            at_line_start: true,
+            fixes: Fixes::default(),
+        }
+    }
+}
+
+impl Sanitizer {
+    fn is_clean(&self, chunk: &[u8]) -> bool {
+        self.carry.is_empty()
+            && !self.skip_lf
+            && !chunk.contains(&b'\r')
+            && !chunk.windows(BOM.len()).any(|w| w == BOM)
+            && std::str::from_utf8(chunk).is_ok()
+    }
+
+    fn push_text(&mut self, text: &str, out: &mut String) {
+        for c in text.chars() {
+            if std::mem::take(&mut self.skip_lf) && c == '\n' {
+                continue;
+            }
+            match c {
+                '\u{FEFF}' if self.at_line_start => self.fixes.boms += 1,
+                '\r' => {
+                    self.fixes.line_endings += 1;
+                    self.skip_lf = true;
+                    self.at_line_start = true;
+                    out.push('\n');
+                }
+                '\n' => {
+                    self.at_line_start = true;
+                    out.push('\n');
+                }
+                _ => {
+                    self.at_line_start = false;
+                    out.push(c);
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, chunk: Bytes) -> Bytes {
+        if self.is_clean(&chunk) {
+            if let Some(last) = chunk.last() {
+                self.at_line_start = *last == b'\n';
+            }
+            return chunk;
+        }
+
+        let mut bytes = std::mem::take(&mut self.carry);
+        bytes.extend_from_slice(&chunk);
+        let mut out = String::with_capacity(bytes.len());
+        let mut rest = &bytes[..];
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(text) => {
+                    self.push_text(text, &mut out);
+                    break;
+                }
+                Err(e) => {
+                    let (valid, tail) = rest.split_at(e.valid_up_to());
+                    if let Ok(text) = std::str::from_utf8(valid) {
+                        self.push_text(text, &mut out);
+                    }
+                    match e.error_len() {
+                        Some(len) => {
+                            self.fixes.invalid_utf8 += 1;
+                            self.push_text("\u{FFFD}", &mut out);
+                            rest = &tail[len..];
+                        }
+                        None => {
+                            self.carry = tail.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        Bytes::from(out)
+    }
+
+    /// 流结束时仍不完整的序列按非法字节处理
+    fn finish(&mut self) -> Option<Bytes> {
+        if self.carry.is_empty() {
+            return None;
+        }
+        self.carry.clear();
+        self.fixes.invalid_utf8 += 1;
+        Some(Bytes::from_static("\u{FFFD}".as_bytes()))
+    }
+}
+
+/// 流结束或被丢弃时记入统计
+struct Tracked {
+    key: (String, String),
+    provider_name: String,
+    sanitizer: Sanitizer,
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        let fixes = self.sanitizer.fixes;
+        if !fixes.any() {
+            return;
+        }
+        log::debug!(
+            "[{}] {} 的流式响应已修复（非法 UTF-8 {}，BOM {}，换行 {}）",
+            self.key.0,
+            self.provider_name,
+            fixes.invalid_utf8,
+            fixes.boms,
+            fixes.line_endings
+        );
+        let mut stats = STATS.write().unwrap_or_else(|e| e.into_inner());
+        let entry = stats
+            .entry(self.key.clone())
+            .or_insert_with(|| SanitizeStats {
+                app_type: self.key.0.clone(),
+                provider_id: self.key.1.clone(),
+                ..SanitizeStats::default()
+            });
+        entry.provider_name = self.provider_name.clone();
+        entry.streams += 1;
+        entry.invalid_utf8 += fixes.invalid_utf8;
+        entry.boms += fixes.boms;
+        entry.line_endings += fixes.line_endings;
+    }
+}
+
+/// 修复流式响应（设置中关闭时原样转发）
+pub fn sanitize<S>(
+    stream: S,
+    app_type: &str,
+    provider: &Provider,
+) -> impl Stream<Item = Result<Bytes, std::io::Error>> + Send
+where
+    S: Stream<Item = Result<Bytes, std::io::Error>> + Send + 'static,
+{
+    let enabled = crate::settings::get_settings().sse_sanitize;
+    let mut tracked = Tracked {
+        key: (app_type.to_string(), provider.id.clone()),
+        provider_name: provider.name.clone(),
+        sanitizer: Sanitizer::default(),
+    };
+    async_stream::stream! {
+        tokio::pin!(stream);
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(bytes) if enabled => {
+                    let fixed = tracked.sanitizer.push(bytes);
+                    if !fixed.is_empty() {
+                        yield Ok(fixed);
+                    }
+                }
+                other => yield other,
+            }
+        }
+        if let Some(tail) = tracked.sanitizer.finish() {
+            yield Ok(tail);
+        }
+    }
+}
+
+/// 各供应商的修复统计
+pub fn stats() -> Vec<SanitizeStats> {
+    let mut stats: Vec<_> = STATS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .cloned()
+        .collect();
+    stats.sort_by(|a, b| {
+        (a.app_type.as_str(), a.provider_id.as_str())
+            .cmp(&(b.app_type.as_str(), b.provider_id.as_str()))
+    });
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(chunks: &[&[u8]]) -> (String, Fixes) {
+        let mut sanitizer = Sanitizer::default();
+        let mut out = Vec::new();
+        for chunk in chunks {
+            out.extend_from_slice(&sanitizer.push(Bytes::copy_from_slice(chunk)));
+        }
+        if let Some(tail) = sanitizer.finish() {
+            out.extend_from_slice(&tail);
+        }
+        (String::from_utf8(out).unwrap(), sanitizer.fixes)
+    }
+
+    #[test]
+    fn clean_streams_pass_through_untouched() {
+        let (out, fixes) = run(&[
+            b"data: {\"text\":\"\xE4\xBD\xA0\"}\n\n",
+            b"data: [DONE]\n\n",
+        ]);
+        assert_eq!(out, "data: {\"text\":\"你\"}\n\ndata: [DONE]\n\n");
+        assert!(!fixes.any());
+    }
+
+    #[test]
+    fn fixes_boms_line_endings_and_bad_bytes_across_chunks() {
+        // BOM 在流开头，CRLF 被拆到两块，多字节字符被拆到两块，另有一个非法字节
+        let (out, fixes) = run(&[
+            b"\xEF\xBB\xBFevent: a\r",
+            b"\ndata: \xE4\xBD",
+            b"\xA0\xFF\r\r\xEF\xBB\xBFdata: b\n\n",
+        ]);
+        assert_eq!(out, "event: a\ndata: 你\u{FFFD}\n\ndata: b\n\n");
+        assert_eq!(
+            fixes,
+            Fixes {
+                invalid_utf8: 1,
+                boms: 2,
+                line_endings: 3,
+            }
+        );
+
+        // 流在多字节字符中间结束
+        let (out, fixes) = run(&[b"data: \xE4\xBD"]);
+        assert_eq!(out, "data: \u{FFFD}");
+        assert_eq!(fixes.invalid_utf8, 1);
+    }
+}
@@ -1,12 +1,24 @@
 //! 代理 TPS 监控（滑动窗口）
 //!
 //! 目标：在代理模式下对真实用户请求的输出 token 进行滑动窗口聚合，并以 TPS（token/秒）形式暴露。
+//! 同时维护最近 1 小时的分桶环形历史，供前端绘制吞吐量走势图（仅内存，不持久化）。
 
-use std::{collections::VecDeque, time::Duration};
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use super::types::TpsHistoryPoint;
 
 /// 默认统计窗口（秒）
 pub const DEFAULT_WINDOW_SECS: u64 = 5;
 
+/// 历史分桶粒度（秒）
+pub const HISTORY_BUCKET_SECS: u64 = 5;
+
+/// 历史覆盖时长（秒）
+pub const HISTORY_SPAN_SECS: u64 = 3600;
+
 #[derive(Debug, Clone)]
 struct RequestSegment {
     start: std::time::Instant,
@@ -19,6 +31,7 @@ struct RequestSegment {
 pub struct TpsMonitor {
     window: Duration,
     segments: VecDeque<RequestSegment>,
+    history: TpsHistory,
 }
 
 impl TpsMonitor {
@@ -26,6 +39,7 @@ impl TpsMonitor {
         Self {
             window: Duration::from_secs(window_secs.max(1)),
             segments: VecDeque::new(),
+            history: TpsHistory::new(HISTORY_BUCKET_SECS, HISTORY_SPAN_SECS),
         }
     }
 
@@ -53,6 +67,19 @@ impl TpsMonitor {
             output_tokens,
         });
         self.trim_expired_at(end);
+
+        // Instant 无法直接换算为墙钟时间：以“当前时刻”为锚点回推请求区间
+        let now_instant = std::time::Instant::now();
+        let now_secs = unix_secs_f64(SystemTime::now());
+        let end_secs = now_secs - now_instant.saturating_duration_since(end).as_secs_f64();
+        let start_secs = end_secs - (end - start).as_secs_f64();
+        self.history.record(output_tokens, start_secs, end_secs);
+    }
+
+    /// 获取最近 1 小时的分桶历史快照（按时间升序，缺失的桶补 0）
+    pub fn history_snapshot(&self) -> Vec<TpsHistoryPoint> {
+        self.history
+            .snapshot_at(unix_secs_f64(SystemTime::now()) as u64)
     }
 
     /// 获取当前 TPS（滑动窗口聚合 / 固定窗口秒数）
@@ -138,6 +165,90 @@ impl Default for TpsMonitor {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default)]
+struct HistoryBucket {
+    /// 桶序号（unix 秒 / 桶粒度），用于判断环形槽位是否已过期
+    index: u64,
+    tokens: f64,
+}
+
+/// 固定大小的分桶环形历史
+///
+/// 每个桶累计落在该时间段内的输出 token；请求跨越多个桶时按重叠时长均摊。
+/// 槽位通过桶序号懒惰失效，无需后台清理。
+#[derive(Debug)]
+struct TpsHistory {
+    bucket_secs: u64,
+    buckets: Vec<HistoryBucket>,
+}
+
+impl TpsHistory {
+    fn new(bucket_secs: u64, span_secs: u64) -> Self {
+        let bucket_secs = bucket_secs.max(1);
+        let len = (span_secs / bucket_secs).max(1) as usize;
+        Self {
+            bucket_secs,
+            buckets: vec![HistoryBucket::default(); len],
+        }
+    }
+
+    fn record(&mut self, output_tokens: u64, start_secs: f64, end_secs: f64) {
+        if output_tokens == 0 || end_secs <= start_secs || start_secs < 0.0 {
+            return;
+        }
+
+        let bucket = self.bucket_secs as f64;
+        let len = self.buckets.len() as u64;
+        let total_secs = end_secs - start_secs;
+        let first = (start_secs / bucket).floor() as u64;
+        let last = (end_secs / bucket).floor() as u64;
+        // 超出历史覆盖范围的部分直接丢弃
+        let oldest = last.saturating_sub(len - 1);
+
+        for index in first.max(oldest)..=last {
+            let bucket_start = index as f64 * bucket;
+            let overlap = end_secs.min(bucket_start + bucket) - start_secs.max(bucket_start);
+            if overlap <= 0.0 {
+                continue;
+            }
+
+            let slot = &mut self.buckets[(index % len) as usize];
+            if slot.index != index {
+                *slot = HistoryBucket { index, tokens: 0.0 };
+            }
+            slot.tokens += output_tokens as f64 * (overlap / total_secs);
+        }
+    }
+
+    fn snapshot_at(&self, now_secs: u64) -> Vec<TpsHistoryPoint> {
+        let len = self.buckets.len() as u64;
+        let newest = now_secs / self.bucket_secs;
+        let oldest = newest.saturating_sub(len - 1);
+
+        (oldest..=newest)
+            .map(|index| {
+                let slot = &self.buckets[(index % len) as usize];
+                let tokens = if slot.index == index {
+                    slot.tokens
+                } else {
+                    0.0
+                };
+                TpsHistoryPoint {
+                    timestamp: (index * self.bucket_secs) as i64,
+                    tokens,
+                    tps: tokens / self.bucket_secs as f64,
+                }
+            })
+            .collect()
+    }
+}
+
+fn unix_secs_f64(time: SystemTime) -> f64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -180,4 +291,33 @@ mod tests {
         let later = end + Duration::from_secs(6);
         assert_eq!(m.current_tps_at(later), 0.0);
     }
+
+    #[test]
+    fn history_spreads_tokens_across_buckets() {
+        let mut h = TpsHistory::new(5, 60);
+        // 区间 [1000, 1010) 跨越两个 5 秒桶，各分得一半
+        h.record(100, 1000.0, 1010.0);
+
+        let points = h.snapshot_at(1012);
+        assert_eq!(points.len(), 12);
+        let last = &points[points.len() - 1];
+        assert_eq!(last.timestamp, 1010);
+        assert_eq!(last.tokens, 0.0);
+
+        let b1 = &points[points.len() - 3];
+        let b2 = &points[points.len() - 2];
+        assert_eq!(b1.timestamp, 1000);
+        assert!((b1.tokens - 50.0).abs() < 1e-9);
+        assert!((b2.tps - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn history_slots_expire_after_span() {
+        let mut h = TpsHistory::new(5, 60);
+        h.record(100, 1000.0, 1005.0);
+
+        // 环形槽位被复用前，旧桶不再出现在快照中
+        let points = h.snapshot_at(1000 + 60);
+        assert!(points.iter().all(|p| p.tokens == 0.0));
+    }
 }
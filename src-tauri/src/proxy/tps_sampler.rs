@@ -0,0 +1,95 @@
+//! 代理吞吐量采样器
+//!
+//! 按 (app_type, provider_id, 分钟) 聚合真实请求的输出 token，定期落盘到 `tps_samples` 表，
+//! 使吞吐量历史在重启后仍可查询。内存中只保留尚未落盘的分钟桶。
+
+use std::collections::HashMap;
+
+use super::types::TpsSample;
+
+/// 采样粒度（秒）
+pub const SAMPLE_BUCKET_SECS: i64 = 60;
+
+/// 默认落盘间隔（秒）
+pub const DEFAULT_FLUSH_INTERVAL_SECS: u64 = 60;
+
+#[derive(Debug, Default, Clone, Copy)]
+struct SampleAccum {
+    output_tokens: u64,
+    request_count: u64,
+    active_ms: u64,
+}
+
+/// 吞吐量采样器（待落盘的分钟桶）
+#[derive(Debug, Default)]
+pub struct TpsSampler {
+    pending: HashMap<(String, String, i64), SampleAccum>,
+}
+
+impl TpsSampler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次完成的请求（按请求结束时间归入分钟桶）
+    pub fn record(
+        &mut self,
+        app_type: &str,
+        provider_id: &str,
+        output_tokens: u64,
+        active_ms: u64,
+        ended_at: i64,
+    ) {
+        if output_tokens == 0 {
+            return;
+        }
+
+        let bucket_start = ended_at - ended_at.rem_euclid(SAMPLE_BUCKET_SECS);
+        let entry = self
+            .pending
+            .entry((app_type.to_string(), provider_id.to_string(), bucket_start))
+            .or_default();
+        entry.output_tokens += output_tokens;
+        entry.request_count += 1;
+        entry.active_ms += active_ms;
+    }
+
+    /// 取出全部待落盘样本并清空
+    pub fn drain(&mut self) -> Vec<TpsSample> {
+        self.pending
+            .drain()
+            .map(|((app_type, provider_id, bucket_start), acc)| TpsSample {
+                app_type,
+                provider_id,
+                bucket_start,
+                output_tokens: acc.output_tokens,
+                request_count: acc.request_count,
+                active_ms: acc.active_ms,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregates_by_provider_and_minute() {
+        let mut s = TpsSampler::new();
+        s.record("claude", "p1", 100, 2000, 120);
+        s.record("claude", "p1", 50, 1000, 179);
+        s.record("claude", "p1", 10, 500, 180);
+        s.record("claude", "p2", 0, 500, 130);
+
+        let mut samples = s.drain();
+        samples.sort_by_key(|x| x.bucket_start);
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].bucket_start, 120);
+        assert_eq!(samples[0].output_tokens, 150);
+        assert_eq!(samples[0].request_count, 2);
+        assert_eq!(samples[0].active_ms, 3000);
+        assert_eq!(samples[1].bucket_start, 180);
+        assert!(s.drain().is_empty());
+    }
+}
@@ -0,0 +1,399 @@
+//! 请求链路追踪（OpenTelemetry）
+//!
+//! 为每个代理请求记录一条 trace，用于定位 cc-switch 自身引入的延迟：
+//! - `proxy.request`：根 span，覆盖请求处理及其后续异步工作（流式转发、写库）
+//! - `upstream.call`：每次向上游发送请求（故障转移时每个供应商一个）
+//! - `translation.request` / `translation.response`：格式转换
+//! - `db.write_usage`：写入请求日志
+//!
+//! 根 span 在最后一个引用释放时结束，随后放入导出缓冲区，由 [`run`] 按 OTLP/HTTP JSON
+//! 批量发送到 `<endpoint>/v1/traces`（Jaeger、OpenTelemetry Collector 均可接收）。
+//! 未配置导出地址时不产生任何开销。
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::services::log_shipper::{otlp_resource, otlp_signal_url, otlp_value, post_json};
+
+/// 导出缓冲区最多保留的 span 数
+const MAX_BUFFERED_SPANS: usize = 20_000;
+
+/// 导出间隔（秒）
+const EXPORT_INTERVAL_SECS: u64 = 5;
+
+/// 单次导出的 span 数上限
+const EXPORT_BATCH_SIZE: usize = 512;
+
+static EXPORT_BUFFER: Lazy<Mutex<Vec<SpanRecord>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// 链路追踪导出配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TracingConfig {
+    /// OTLP/HTTP 接收地址，如 `http://localhost:4318`
+    pub otlp_endpoint: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// 采样比例（0~1）
+    #[serde(default = "default_sample_ratio")]
+    pub sample_ratio: f64,
+}
+
+fn default_sample_ratio() -> f64 {
+    1.0
+}
+
+/// span 状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SpanStatus {
+    Unset,
+    Ok,
+    Error,
+}
+
+/// 已结束的 span
+#[derive(Debug, Clone)]
+struct SpanRecord {
+    trace_id: String,
+    span_id: String,
+    parent_span_id: Option<String>,
+    name: &'static str,
+    kind: u8,
+    start_ns: u128,
+    end_ns: u128,
+    attributes: Vec<(String, Value)>,
+    status: SpanStatus,
+    status_message: Option<String>,
+}
+
+impl SpanRecord {
+    fn to_otlp(&self) -> Value {
+        let attributes: Vec<Value> = self
+            .attributes
+            .iter()
+            .map(|(key, value)| json!({ "key": key, "value": otlp_value(value) }))
+            .collect();
+        let mut status = json!({
+            "code": match self.status {
+                SpanStatus::Unset => 0,
+                SpanStatus::Ok => 1,
+                SpanStatus::Error => 2,
+            }
+        });
+        if let Some(message) = &self.status_message {
+            status["message"] = json!(message);
+        }
+        let mut span = json!({
+            "traceId": self.trace_id,
+            "spanId": self.span_id,
+            "name": self.name,
+            "kind": self.kind,
+            "startTimeUnixNano": self.start_ns.to_string(),
+            "endTimeUnixNano": self.end_ns.to_string(),
+            "attributes": attributes,
+            "status": status,
+        });
+        if let Some(parent) = &self.parent_span_id {
+            span["parentSpanId"] = json!(parent);
+        }
+        span
+    }
+}
+
+/// OTLP span kind
+const SPAN_KIND_INTERNAL: u8 = 1;
+const SPAN_KIND_SERVER: u8 = 2;
+const SPAN_KIND_CLIENT: u8 = 3;
+
+fn now_ns() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default()
+}
+
+fn random_hex(bytes: usize) -> String {
+    let id = uuid::Uuid::new_v4();
+    id.as_bytes()[..bytes]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+struct TraceInner {
+    root: Mutex<SpanRecord>,
+    /// 已结束的子 span
+    finished: Mutex<Vec<SpanRecord>>,
+}
+
+impl Drop for TraceInner {
+    fn drop(&mut self) {
+        let mut root = self.root.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        root.end_ns = now_ns();
+        let mut spans =
+            std::mem::take(&mut *self.finished.lock().unwrap_or_else(|e| e.into_inner()));
+        spans.push(root);
+
+        let mut buffer = EXPORT_BUFFER.lock().unwrap_or_else(|e| e.into_inner());
+        if buffer.len() + spans.len() > MAX_BUFFERED_SPANS {
+            log::debug!("[Trace] 导出缓冲区已满，丢弃 {} 个 span", spans.len());
+            return;
+        }
+        buffer.extend(spans);
+    }
+}
+
+/// 一次请求的 trace（未启用或未被采样时为空操作）
+#[derive(Clone, Default)]
+pub struct RequestTrace {
+    inner: Option<Arc<TraceInner>>,
+}
+
+impl RequestTrace {
+    /// 按设置开始一条 trace
+    pub fn start(app_type: &str) -> Self {
+        let Some(config) = crate::settings::get_settings().tracing else {
+            return Self::default();
+        };
+        let sampled = config.sample_ratio >= 1.0
+            || (uuid::Uuid::new_v4().as_u128() as f64 / u128::MAX as f64) < config.sample_ratio;
+        if !sampled {
+            return Self::default();
+        }
+
+        let root = SpanRecord {
+            trace_id: random_hex(16),
+            span_id: random_hex(8),
+            parent_span_id: None,
+            name: "proxy.request",
+            kind: SPAN_KIND_SERVER,
+            start_ns: now_ns(),
+            end_ns: 0,
+            attributes: vec![("app.type".to_string(), json!(app_type))],
+            status: SpanStatus::Unset,
+            status_message: None,
+        };
+        Self {
+            inner: Some(Arc::new(TraceInner {
+                root: Mutex::new(root),
+                finished: Mutex::new(Vec::new()),
+            })),
+        }
+    }
+
+    /// 为根 span 设置属性
+    pub fn set_attribute(&self, key: &str, value: impl Into<Value>) {
+        if let Some(inner) = &self.inner {
+            let mut root = inner.root.lock().unwrap_or_else(|e| e.into_inner());
+            set_attr(&mut root.attributes, key, value.into());
+        }
+    }
+
+    /// 标记请求失败
+    pub fn set_error(&self, message: &str) {
+        if let Some(inner) = &self.inner {
+            let mut root = inner.root.lock().unwrap_or_else(|e| e.into_inner());
+            root.status = SpanStatus::Error;
+            root.status_message = Some(message.to_string());
+        }
+    }
+
+    /// 开始一个内部子 span
+    pub fn span(&self, name: &'static str) -> Span {
+        self.child(name, SPAN_KIND_INTERNAL)
+    }
+
+    /// 开始一个出站调用子 span
+    pub fn client_span(&self, name: &'static str) -> Span {
+        self.child(name, SPAN_KIND_CLIENT)
+    }
+
+    fn child(&self, name: &'static str, kind: u8) -> Span {
+        let record = self.inner.as_ref().map(|inner| {
+            let root = inner.root.lock().unwrap_or_else(|e| e.into_inner());
+            SpanRecord {
+                trace_id: root.trace_id.clone(),
+                span_id: random_hex(8),
+                parent_span_id: Some(root.span_id.clone()),
+                name,
+                kind,
+                start_ns: now_ns(),
+                end_ns: 0,
+                attributes: Vec::new(),
+                status: SpanStatus::Unset,
+                status_message: None,
+            }
+        });
+        Span {
+            trace: self.clone(),
+            record,
+        }
+    }
+}
+
+fn set_attr(attributes: &mut Vec<(String, Value)>, key: &str, value: Value) {
+    match attributes.iter_mut().find(|(k, _)| k == key) {
+        Some((_, v)) => *v = value,
+        None => attributes.push((key.to_string(), value)),
+    }
+}
+
+/// 进行中的子 span，释放时结束
+pub struct Span {
+    trace: RequestTrace,
+    record: Option<SpanRecord>,
+}
+
+impl Span {
+    pub fn set_attribute(&mut self, key: &str, value: impl Into<Value>) {
+        if let Some(record) = &mut self.record {
+            set_attr(&mut record.attributes, key, value.into());
+        }
+    }
+
+    pub fn set_ok(&mut self) {
+        if let Some(record) = &mut self.record {
+            record.status = SpanStatus::Ok;
+        }
+    }
+
+    pub fn set_error(&mut self, message: impl ToString) {
+        if let Some(record) = &mut self.record {
+            record.status = SpanStatus::Error;
+            record.status_message = Some(message.to_string());
+        }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let (Some(mut record), Some(inner)) = (self.record.take(), self.trace.inner.as_ref())
+        else {
+            return;
+        };
+        record.end_ns = now_ns();
+        inner
+            .finished
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(record);
+    }
+}
+
+fn otlp_payload(spans: &[SpanRecord]) -> Value {
+    json!({
+        "resourceSpans": [{
+            "resource": otlp_resource(),
+            "scopeSpans": [{
+                "scope": { "name": "cc-switch.proxy" },
+                "spans": spans.iter().map(SpanRecord::to_otlp).collect::<Vec<_>>(),
+            }]
+        }]
+    })
+}
+
+/// 后台导出循环
+pub async fn run() {
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            log::error!("[Trace] 创建 HTTP 客户端失败，链路追踪不会导出: {e}");
+            return;
+        }
+    };
+
+    loop {
+        tokio::time::sleep(Duration::from_secs(EXPORT_INTERVAL_SECS)).await;
+        let Some(config) = crate::settings::get_settings().tracing else {
+            EXPORT_BUFFER
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clear();
+            continue;
+        };
+        if super::offline::is_offline() {
+            continue;
+        }
+
+        let url = otlp_signal_url(&config.otlp_endpoint, "traces");
+        loop {
+            let batch: Vec<SpanRecord> = {
+                let mut buffer = EXPORT_BUFFER.lock().unwrap_or_else(|e| e.into_inner());
+                let n = EXPORT_BATCH_SIZE.min(buffer.len());
+                buffer.drain(..n).collect()
+            };
+            if batch.is_empty() {
+                break;
+            }
+            // 追踪数据仅用于诊断，导出失败时直接丢弃
+            if let Err(e) = post_json(&client, &url, &config.headers, &otlp_payload(&batch)).await {
+                log::warn!("[Trace] 导出 {} 个 span 失败: {e}", batch.len());
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_trace_is_noop() {
+        let trace = RequestTrace::default();
+        let mut span = trace.span("db.write_usage");
+        span.set_attribute("k", 1);
+        drop(span);
+        assert!(trace.inner.is_none());
+    }
+
+    #[test]
+    fn child_spans_share_trace_and_export_as_otlp() {
+        let root = SpanRecord {
+            trace_id: random_hex(16),
+            span_id: random_hex(8),
+            parent_span_id: None,
+            name: "proxy.request",
+            kind: SPAN_KIND_SERVER,
+            start_ns: 1,
+            end_ns: 0,
+            attributes: Vec::new(),
+            status: SpanStatus::Unset,
+            status_message: None,
+        };
+        let trace = RequestTrace {
+            inner: Some(Arc::new(TraceInner {
+                root: Mutex::new(root),
+                finished: Mutex::new(Vec::new()),
+            })),
+        };
+        {
+            let mut span = trace.client_span("upstream.call");
+            span.set_attribute("http.response.status_code", 200);
+            span.set_ok();
+        }
+
+        let inner = trace.inner.as_ref().unwrap();
+        let finished = inner.finished.lock().unwrap().clone();
+        let root = inner.root.lock().unwrap().clone();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].trace_id.len(), 32);
+        assert_eq!(finished[0].parent_span_id.as_ref(), Some(&root.span_id));
+
+        let otlp = finished[0].to_otlp();
+        assert_eq!(otlp["kind"], SPAN_KIND_CLIENT);
+        assert_eq!(otlp["status"]["code"], 1);
+        assert_eq!(
+            otlp["attributes"][0],
+            json!({ "key": "http.response.status_code", "value": { "intValue": "200" } })
+        );
+    }
+}
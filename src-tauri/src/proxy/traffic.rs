@@ -0,0 +1,86 @@
+//! 请求流量计量
+//!
+//! 记录每个请求与上游之间实际传输的字节数（请求体与响应体，不含头部），
+//! 故障转移的每次尝试都计入发送量，流式响应按收到的数据块累计。
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+
+use super::usage::logger::RequestTraffic;
+
+/// 单个请求的流量计数（克隆后共享同一计数）
+#[derive(Debug, Clone, Default)]
+pub struct TrafficMeter {
+    inner: Arc<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    sent: AtomicU64,
+    received: AtomicU64,
+}
+
+impl TrafficMeter {
+    pub fn add_sent(&self, bytes: usize) {
+        self.inner.sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub fn add_received(&self, bytes: usize) {
+        self.inner
+            .received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    /// 当前累计值
+    pub fn snapshot(&self) -> RequestTraffic {
+        RequestTraffic {
+            request_bytes: self.inner.sent.load(Ordering::Relaxed),
+            response_bytes: self.inner.received.load(Ordering::Relaxed),
+        }
+    }
+
+    /// 包装上游响应流，按数据块累计接收字节数
+    pub fn count_received<S, E>(&self, stream: S) -> impl Stream<Item = Result<Bytes, E>>
+    where
+        S: Stream<Item = Result<Bytes, E>>,
+    {
+        let meter = self.clone();
+        stream.map(move |chunk| {
+            if let Ok(bytes) = &chunk {
+                meter.add_received(bytes.len());
+            }
+            chunk
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn counts_streamed_chunks_and_retries() {
+        let meter = TrafficMeter::default();
+        meter.add_sent(100);
+        // 故障转移重试再次发送
+        meter.clone().add_sent(100);
+
+        let chunks = futures::stream::iter(vec![
+            Ok::<_, std::io::Error>(Bytes::from_static(b"data: {}\n\n")),
+            Ok(Bytes::from_static(b"data: [DONE]\n\n")),
+        ]);
+        let collected: Vec<_> = meter.count_received(chunks).collect().await;
+        assert_eq!(collected.len(), 2);
+
+        assert_eq!(
+            meter.snapshot(),
+            RequestTraffic {
+                request_bytes: 200,
+                response_bytes: 24,
+            }
+        );
+    }
+}
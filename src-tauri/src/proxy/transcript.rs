@@ -0,0 +1,194 @@
+//! 会话记录
+//!
+//! 开启 `captureTranscripts` 后，把经代理转发的请求体与完整回复（流式响应拼接所有增量）
+//! 按会话写入数据库，便于独立留存发给第三方中转站的内容。
+//!
+//! 写入前按日志脱敏设置（`logScrubMode`）处理请求与回复，与请求日志保持一致；
+//! 超过保留期的记录由后台任务定期清理。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::Value;
+
+use super::log_scrubber;
+use crate::database::Database;
+
+/// 过期记录的清理间隔
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// 待完成的一次记录（请求开始时创建，拿到完整回复后写入）
+#[derive(Clone)]
+pub struct TranscriptCapture {
+    session_id: String,
+    app_type: &'static str,
+    prompt: Arc<Value>,
+}
+
+impl TranscriptCapture {
+    /// 按当前设置开始记录（未开启时返回 None）
+    pub fn start(session_id: &str, app_type: &'static str, body: &Value) -> Option<Self> {
+        if !crate::settings::get_settings().capture_transcripts {
+            return None;
+        }
+        Some(Self {
+            session_id: session_id.to_string(),
+            app_type,
+            prompt: Arc::new(body.clone()),
+        })
+    }
+
+    /// 写入记录（后台执行，失败只记日志）
+    pub fn finish(&self, db: Arc<Database>, provider_id: &str, model: &str, completion: String) {
+        let capture = self.clone();
+        let provider_id = provider_id.to_string();
+        let model = model.to_string();
+        tokio::task::spawn_blocking(move || {
+            let prompt = log_scrubber::value_for_log(capture.prompt.as_ref().clone());
+            let prompt = serde_json::to_string(&prompt).unwrap_or_default();
+            let completion = log_scrubber::text_for_log(&completion);
+            if let Err(e) = db.insert_transcript(
+                &capture.session_id,
+                capture.app_type,
+                &provider_id,
+                &model,
+                &prompt,
+                &completion,
+                chrono::Utc::now().timestamp(),
+            ) {
+                log::warn!("保存会话记录失败: {e}");
+            }
+        });
+    }
+}
+
+/// 清理超过保留期的会话记录
+fn prune_expired(db: &Database) -> Result<usize, crate::error::AppError> {
+    let retention_days = crate::settings::get_settings()
+        .transcript_retention_days
+        .max(1) as i64;
+    db.prune_transcripts(chrono::Utc::now().timestamp() - retention_days * 86_400)
+}
+
+/// 后台定期清理过期的会话记录
+pub async fn run(db: Arc<Database>) {
+    loop {
+        let db_for_task = db.clone();
+        match tokio::task::spawn_blocking(move || prune_expired(&db_for_task)).await {
+            Ok(Err(e)) => log::warn!("清理过期会话记录失败: {e}"),
+            Err(e) => log::warn!("会话记录清理任务异常: {e}"),
+            Ok(Ok(_)) => {}
+        }
+        tokio::time::sleep(PRUNE_INTERVAL).await;
+    }
+}
+
+/// 从单个响应 JSON 或流式事件中提取文本
+///
+/// 兼容 Claude Messages、OpenAI Chat Completions、OpenAI Responses 与 Gemini 格式。
+fn push_text(value: &Value, out: &mut String) {
+    // Claude：content_block_delta / 非流式 content 数组
+    if let Some(text) = value.pointer("/delta/text").and_then(|t| t.as_str()) {
+        out.push_str(text);
+    }
+    if let Some(blocks) = value.get("content").and_then(|c| c.as_array()) {
+        for block in blocks {
+            if let Some(text) = block.get("text").and_then(|t| t.as_str()) {
+                out.push_str(text);
+            }
+        }
+    }
+
+    // OpenAI Chat：choices[].delta.content / choices[].message.content
+    if let Some(choices) = value.get("choices").and_then(|c| c.as_array()) {
+        for choice in choices {
+            for pointer in ["/delta/content", "/message/content"] {
+                if let Some(text) = choice.pointer(pointer).and_then(|t| t.as_str()) {
+                    out.push_str(text);
+                }
+            }
+        }
+    }
+
+    // OpenAI Responses：response.output_text.delta / 非流式 output 数组
+    if value.get("type").and_then(|t| t.as_str()) == Some("response.output_text.delta") {
+        if let Some(text) = value.get("delta").and_then(|t| t.as_str()) {
+            out.push_str(text);
+        }
+    }
+    if let Some(items) = value.get("output").and_then(|o| o.as_array()) {
+        for item in items {
+            push_text(item, out);
+        }
+    }
+
+    // Gemini：candidates[].content.parts[].text
+    if let Some(candidates) = value.get("candidates").and_then(|c| c.as_array()) {
+        for candidate in candidates {
+            if let Some(parts) = candidate
+                .pointer("/content/parts")
+                .and_then(|p| p.as_array())
+            {
+                for part in parts {
+                    if let Some(text) = part.get("text").and_then(|t| t.as_str()) {
+                        out.push_str(text);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 非流式响应的回复文本
+pub fn completion_from_response(response: &Value) -> String {
+    let mut out = String::new();
+    push_text(response, &mut out);
+    out
+}
+
+/// 流式响应的回复文本（按顺序拼接所有增量）
+pub fn completion_from_events(events: &[Value]) -> String {
+    let mut out = String::new();
+    for event in events {
+        push_text(event, &mut out);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn assembles_streamed_deltas_across_formats() {
+        let claude = [
+            json!({"type": "content_block_delta", "delta": {"type": "text_delta", "text": "Hel"}}),
+            json!({"type": "content_block_delta", "delta": {"type": "text_delta", "text": "lo"}}),
+        ];
+        assert_eq!(completion_from_events(&claude), "Hello");
+
+        let openai = [
+            json!({"choices": [{"delta": {"role": "assistant"}}]}),
+            json!({"choices": [{"delta": {"content": "Hi"}}]}),
+        ];
+        assert_eq!(completion_from_events(&openai), "Hi");
+
+        let responses = [json!({"type": "response.output_text.delta", "delta": "ok"})];
+        assert_eq!(completion_from_events(&responses), "ok");
+
+        let gemini = [json!({"candidates": [{"content": {"parts": [{"text": "yo"}]}}]})];
+        assert_eq!(completion_from_events(&gemini), "yo");
+    }
+
+    #[test]
+    fn extracts_non_streaming_text() {
+        let claude = json!({"content": [{"type": "text", "text": "a"}, {"type": "tool_use"}]});
+        assert_eq!(completion_from_response(&claude), "a");
+
+        let responses = json!({
+            "output": [{"type": "message", "content": [{"type": "output_text", "text": "b"}]}]
+        });
+        assert_eq!(completion_from_response(&responses), "b");
+    }
+}
@@ -89,9 +89,49 @@ pub struct ProxyStatus {
     /// 最近 5 秒滑动窗口 TPS（输出 token/秒，空闲为 0）
     #[serde(default)]
     pub tps: f64,
+    /// 是否处于离线模式
+    #[serde(default)]
+    pub offline: bool,
     /// 当前活跃的代理目标列表
     #[serde(default)]
     pub active_targets: Vec<ActiveTarget>,
+    /// 代理自身开销（开启测量时提供）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overhead: Option<super::overhead::OverheadStats>,
+    /// 各供应商的在途请求数与峰值并发
+    #[serde(default)]
+    pub concurrency: Vec<super::concurrency::ProviderConcurrency>,
+    /// 本地套接字路径（开启时提供）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_socket: Option<String>,
+}
+
+/// TPS 历史分桶数据点（用于吞吐量走势图）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TpsHistoryPoint {
+    /// 桶起始时间（unix 秒）
+    pub timestamp: i64,
+    /// 该桶内累计输出 token
+    pub tokens: f64,
+    /// 该桶平均 TPS
+    pub tps: f64,
+}
+
+/// 持久化的吞吐量样本（按供应商、按分钟聚合）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TpsSample {
+    pub app_type: String,
+    pub provider_id: String,
+    /// 分钟桶起始时间（unix 秒）
+    pub bucket_start: i64,
+    /// 该分钟内完成请求的输出 token 总数
+    pub output_tokens: u64,
+    /// 该分钟内完成的请求数
+    pub request_count: u64,
+    /// 请求活跃时长累计（毫秒）
+    pub active_ms: u64,
 }
 
 /// 活跃的代理目标信息
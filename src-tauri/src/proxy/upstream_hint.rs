@@ -0,0 +1,296 @@
+//! 上游错误翻译
+//!
+//! 将常见的上游错误响应（额度用尽、Key 无效、地区限制、模型下线等）归类为稳定的错误码，
+//! 并附带可操作的建议，用于代理返回给客户端的错误与健康检查结果。
+//! 中转站常返回 HTML 错误页或中文提示，这里只保留页面标题 / 正文摘要，不再原样透出整页内容。
+
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{self, Locale};
+
+/// 摘要最长字符数
+const SUMMARY_MAX_CHARS: usize = 200;
+
+/// 可识别的上游错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum UpstreamErrorCode {
+    QuotaExceeded,
+    InvalidKey,
+    RegionBlocked,
+    ModelDeprecated,
+    RateLimited,
+}
+
+impl UpstreamErrorCode {
+    const ALL: [Self; 5] = [
+        Self::QuotaExceeded,
+        Self::InvalidKey,
+        Self::RegionBlocked,
+        Self::ModelDeprecated,
+        Self::RateLimited,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::QuotaExceeded => "QUOTA_EXCEEDED",
+            Self::InvalidKey => "INVALID_KEY",
+            Self::RegionBlocked => "REGION_BLOCKED",
+            Self::ModelDeprecated => "MODEL_DEPRECATED",
+            Self::RateLimited => "RATE_LIMITED",
+        }
+    }
+
+    /// 响应体中出现即判定为该类错误的片段（小写比较）
+    fn patterns(&self) -> &'static [&'static str] {
+        match self {
+            Self::QuotaExceeded => &[
+                "insufficient_quota",
+                "exceeded your current quota",
+                "credit balance is too low",
+                "insufficient balance",
+                "billing_hard_limit",
+                "quota exceeded",
+                "余额不足",
+                "额度不足",
+                "额度已用尽",
+                "令牌额度",
+                "用户额度",
+                "欠费",
+            ],
+            Self::InvalidKey => &[
+                "invalid_api_key",
+                "invalid api key",
+                "incorrect api key",
+                "invalid x-api-key",
+                "authentication_error",
+                "api key not valid",
+                "无效的令牌",
+                "令牌无效",
+                "令牌已过期",
+                "未提供令牌",
+                "密钥无效",
+            ],
+            Self::RegionBlocked => &[
+                "unsupported_country_region_territory",
+                "country, region, or territory not supported",
+                "not available in your region",
+                "user location is not supported",
+                "地区不支持",
+                "所在地区",
+            ],
+            Self::ModelDeprecated => &[
+                "model_not_found",
+                "has been deprecated",
+                "is deprecated",
+                "does not exist or you do not have access",
+                "模型不存在",
+                "无可用渠道",
+                "不支持该模型",
+            ],
+            Self::RateLimited => &[
+                "rate_limit",
+                "rate limit",
+                "too many requests",
+                "请求过于频繁",
+                "并发",
+            ],
+        }
+    }
+
+    /// 从格式化后的消息（`[CODE] ...`）中解析错误码，用于读取已保存的检查结果
+    pub fn from_message(message: &str) -> Option<Self> {
+        let start = message.find('[')? + 1;
+        let end = start + message[start..].find(']')?;
+        let tag = &message[start..end];
+        Self::ALL.into_iter().find(|code| code.as_str() == tag)
+    }
+}
+
+/// 归类结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpstreamHint {
+    pub code: UpstreamErrorCode,
+    /// 建议的处理方式（按设置语言渲染）
+    pub suggestion: String,
+}
+
+/// 按响应体关键字归类，关键字无法判断时退回到状态码
+pub fn classify(status: u16, body: &str) -> Option<UpstreamErrorCode> {
+    let lower = body.to_lowercase();
+    if let Some(code) = UpstreamErrorCode::ALL
+        .into_iter()
+        .find(|code| code.patterns().iter().any(|p| lower.contains(p)))
+    {
+        return Some(code);
+    }
+    match status {
+        401 => Some(UpstreamErrorCode::InvalidKey),
+        402 => Some(UpstreamErrorCode::QuotaExceeded),
+        429 => Some(UpstreamErrorCode::RateLimited),
+        _ => None,
+    }
+}
+
+pub fn hint_for(status: u16, body: &str, locale: Locale) -> Option<UpstreamHint> {
+    classify(status, body).map(|code| UpstreamHint {
+        code,
+        suggestion: i18n::tr(locale, &format!("upstreamHint.{}", code.as_str())),
+    })
+}
+
+fn is_html(body: &str) -> bool {
+    let head = body
+        .trim_start()
+        .get(..64)
+        .unwrap_or(body)
+        .to_ascii_lowercase();
+    head.starts_with("<!doctype html") || head.starts_with("<html") || head.contains("<head")
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for ch in html.chars() {
+        match ch {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(ch),
+            _ => {}
+        }
+    }
+    text
+}
+
+fn html_title(html: &str) -> Option<String> {
+    // ASCII 小写保持字节偏移不变，可直接用于切片原文
+    let lower = html.to_ascii_lowercase();
+    let start = lower.find("<title")?;
+    let start = start + lower[start..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = html.get(start..end)?.trim();
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+/// 响应体摘要：HTML 取标题（无标题时取去标签后的正文），压缩空白并截断
+pub fn summarize_body(body: &str) -> String {
+    let text = if is_html(body) {
+        // 去掉 <style> / <script> 内容，避免摘要全是 CSS
+        let without_blocks = ["style", "script"]
+            .iter()
+            .fold(body.to_string(), |acc, tag| {
+                let lower = acc.to_ascii_lowercase();
+                match (
+                    lower.find(&format!("<{tag}")),
+                    lower.find(&format!("</{tag}>")),
+                ) {
+                    (Some(start), Some(end)) if end > start => {
+                        format!("{}{}", &acc[..start], &acc[end + tag.len() + 3..])
+                    }
+                    _ => acc,
+                }
+            });
+        html_title(&without_blocks).unwrap_or_else(|| strip_tags(&without_blocks))
+    } else {
+        body.to_string()
+    };
+
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if collapsed.chars().count() > SUMMARY_MAX_CHARS {
+        let truncated: String = collapsed.chars().take(SUMMARY_MAX_CHARS).collect();
+        format!("{truncated}…")
+    } else {
+        collapsed
+    }
+}
+
+/// 渲染上游错误：`HTTP <状态码>: [<错误码>] <建议> — <摘要>`，无法归类时只给出摘要
+pub fn describe(status: u16, body: &str, locale: Locale) -> String {
+    let summary = summarize_body(body);
+    match hint_for(status, body, locale) {
+        Some(hint) if summary.is_empty() => {
+            format!(
+                "HTTP {status}: [{}] {}",
+                hint.code.as_str(),
+                hint.suggestion
+            )
+        }
+        Some(hint) => format!(
+            "HTTP {status}: [{}] {} — {summary}",
+            hint.code.as_str(),
+            hint.suggestion
+        ),
+        None => format!("HTTP {status}: {summary}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_common_upstream_bodies() {
+        let cases = [
+            (
+                429,
+                r#"{"error":{"type":"insufficient_quota","message":"You exceeded your current quota"}}"#,
+                Some(UpstreamErrorCode::QuotaExceeded),
+            ),
+            (
+                403,
+                r#"{"error":{"message":"该令牌额度已用尽","type":"new_api_error"}}"#,
+                Some(UpstreamErrorCode::QuotaExceeded),
+            ),
+            (
+                403,
+                r#"{"error":{"code":"unsupported_country_region_territory"}}"#,
+                Some(UpstreamErrorCode::RegionBlocked),
+            ),
+            (
+                503,
+                "当前分组 default 下对于模型 claude-3-opus 无可用渠道",
+                Some(UpstreamErrorCode::ModelDeprecated),
+            ),
+            (401, "Unauthorized", Some(UpstreamErrorCode::InvalidKey)),
+            (502, "Bad Gateway", None),
+        ];
+        for (status, body, expected) in cases {
+            assert_eq!(classify(status, body), expected, "{body}");
+        }
+    }
+
+    #[test]
+    fn summarizes_html_error_pages() {
+        let page = "<!DOCTYPE html><html><head><style>body{color:red}</style>\
+                    <title> 502 Bad Gateway </title></head><body><h1>502</h1></body></html>";
+        assert_eq!(summarize_body(page), "502 Bad Gateway");
+
+        let untitled = "<html><body><h1>服务暂不可用</h1><p>请稍后重试</p></body></html>";
+        assert_eq!(summarize_body(untitled), "服务暂不可用 请稍后重试");
+
+        let long = "x".repeat(SUMMARY_MAX_CHARS + 10);
+        assert!(summarize_body(&long).ends_with('…'));
+    }
+
+    #[test]
+    fn describe_embeds_parseable_code() {
+        let message = describe(401, r#"{"error":"invalid api key"}"#, Locale::En);
+        assert!(message.starts_with("HTTP 401: [INVALID_KEY] "));
+        assert_eq!(
+            UpstreamErrorCode::from_message(&message),
+            Some(UpstreamErrorCode::InvalidKey)
+        );
+        assert_eq!(
+            UpstreamErrorCode::from_message("HTTP 502: Bad Gateway"),
+            None
+        );
+        assert_eq!(
+            describe(502, "Bad   Gateway\n", Locale::En),
+            "HTTP 502: Bad Gateway"
+        );
+    }
+}
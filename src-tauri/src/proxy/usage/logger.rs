@@ -8,6 +8,13 @@ use crate::services::usage_stats::find_model_pricing_row;
 use rust_decimal::Decimal;
 use std::time::SystemTime;
 
+/// 单个请求与上游之间传输的字节数（请求体 / 响应体）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestTraffic {
+    pub request_bytes: u64,
+    pub response_bytes: u64,
+}
+
 /// 请求日志
 #[derive(Debug, Clone)]
 pub struct RequestLog {
@@ -28,6 +35,39 @@ pub struct RequestLog {
     pub is_streaming: bool,
     /// 成本倍数
     pub cost_multiplier: String,
+    /// 流式输出速度（token/秒，仅流式成功请求）
+    pub output_tps: Option<f64>,
+    /// 实际发送的客户端身份（User-Agent 等，JSON 对象；供应商未配置时为空）
+    pub client_identity: Option<String>,
+    /// 与上游之间传输的字节数
+    pub traffic: RequestTraffic,
+    /// 客户端通过 `X-CCSwitch-Tag` 请求头指定的标签
+    pub tag: Option<String>,
+    /// 是否为请求回放发出的请求（不计入用量统计与健康评估）
+    pub is_replay: bool,
+}
+
+/// 计算单个流式响应的输出速度
+///
+/// 口径：output_tokens / 流持续时间；有首字时间时从首字开始计时，以排除排队与首字等待。
+pub fn streaming_output_tps(
+    output_tokens: u32,
+    latency_ms: u64,
+    first_token_ms: Option<u64>,
+) -> Option<f64> {
+    if output_tokens == 0 {
+        return None;
+    }
+
+    let duration_ms = match first_token_ms {
+        Some(first) if first < latency_ms => latency_ms - first,
+        _ => latency_ms,
+    };
+    if duration_ms == 0 {
+        return None;
+    }
+
+    Some(output_tokens as f64 / (duration_ms as f64 / 1000.0))
 }
 
 /// 使用量记录器
@@ -74,8 +114,9 @@ impl<'a> UsageLogger<'a> {
                 input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens,
                 input_cost_usd, output_cost_usd, cache_read_cost_usd, cache_creation_cost_usd, total_cost_usd,
                 latency_ms, first_token_ms, status_code, error_message, session_id,
-                provider_type, is_streaming, cost_multiplier, created_at
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)",
+                provider_type, is_streaming, cost_multiplier, created_at, output_tps, client_identity,
+                request_bytes, response_bytes, tag, is_replay
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28)",
             rusqlite::params![
                 log.request_id,
                 log.provider_id,
@@ -99,10 +140,28 @@ impl<'a> UsageLogger<'a> {
                 log.is_streaming as i64,
                 log.cost_multiplier,
                 created_at,
+                log.output_tps,
+                log.client_identity,
+                log.traffic.request_bytes as i64,
+                log.traffic.response_bytes as i64,
+                log.tag,
+                log.is_replay,
             ],
         )
         .map_err(|e| AppError::Database(format!("记录请求日志失败: {e}")))?;
+        drop(conn);
 
+        if (200..300).contains(&log.status_code) && !log.is_replay {
+            self.db.record_provider_usage(
+                &log.app_type,
+                &log.provider_id,
+                (log.usage.input_tokens + log.usage.output_tokens) as u64,
+                created_at,
+            )?;
+        }
+
+        crate::services::log_shipper::ship(log);
+        crate::services::access_log::append(log);
         Ok(())
     }
 
@@ -135,6 +194,11 @@ impl<'a> UsageLogger<'a> {
             provider_type: None,
             is_streaming: false,
             cost_multiplier: "1.0".to_string(),
+            output_tps: None,
+            client_identity: None,
+            traffic: RequestTraffic::default(),
+            tag: None,
+            is_replay: false,
         };
 
         self.log_request(&log)
@@ -156,6 +220,9 @@ impl<'a> UsageLogger<'a> {
         is_streaming: bool,
         session_id: Option<String>,
         provider_type: Option<String>,
+        traffic: RequestTraffic,
+        tag: Option<String>,
+        is_replay: bool,
     ) -> Result<(), AppError> {
         let log = RequestLog {
             request_id,
@@ -172,6 +239,11 @@ impl<'a> UsageLogger<'a> {
             provider_type,
             is_streaming,
             cost_multiplier: "1.0".to_string(),
+            output_tps: None,
+            client_identity: None,
+            traffic,
+            tag,
+            is_replay,
         };
 
         self.log_request(&log)
@@ -207,6 +279,10 @@ impl<'a> UsageLogger<'a> {
         session_id: Option<String>,
         provider_type: Option<String>,
         is_streaming: bool,
+        client_identity: Option<String>,
+        traffic: RequestTraffic,
+        tag: Option<String>,
+        is_replay: bool,
     ) -> Result<(), AppError> {
         let pricing = self.get_model_pricing(&model)?;
 
@@ -215,6 +291,11 @@ impl<'a> UsageLogger<'a> {
         }
 
         let cost = CostCalculator::try_calculate(&usage, pricing.as_ref(), cost_multiplier);
+        let output_tps = if is_streaming && (200..300).contains(&status_code) {
+            streaming_output_tps(usage.output_tokens, latency_ms, first_token_ms)
+        } else {
+            None
+        };
 
         let log = RequestLog {
             request_id,
@@ -231,6 +312,11 @@ impl<'a> UsageLogger<'a> {
             provider_type,
             is_streaming,
             cost_multiplier: cost_multiplier.to_string(),
+            output_tps,
+            client_identity,
+            traffic,
+            tag,
+            is_replay,
         };
 
         self.log_request(&log)
@@ -279,13 +365,20 @@ mod tests {
             None,
             Some("claude".to_string()),
             false,
+            None,
+            RequestTraffic {
+                request_bytes: 2048,
+                response_bytes: 512,
+            },
+            Some("billing".to_string()),
+            false,
         )?;
 
         // 验证记录已插入
         let conn = crate::database::lock_conn!(db.conn);
         let count: i64 = conn
             .query_row(
-                "SELECT COUNT(*) FROM proxy_request_logs WHERE request_id = 'req-123'",
+                "SELECT COUNT(*) FROM proxy_request_logs WHERE request_id = 'req-123' AND tag = 'billing'",
                 [],
                 |row| row.get(0),
             )
@@ -294,6 +387,16 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_streaming_output_tps() {
+        // 首字后 2 秒输出 100 token
+        assert_eq!(streaming_output_tps(100, 2500, Some(500)), Some(50.0));
+        // 无首字时间时按总耗时计算
+        assert_eq!(streaming_output_tps(100, 4000, None), Some(25.0));
+        assert_eq!(streaming_output_tps(0, 1000, None), None);
+        assert_eq!(streaming_output_tps(10, 0, None), None);
+    }
+
     #[test]
     fn test_log_error() -> Result<(), AppError> {
         let db = Database::memory()?;
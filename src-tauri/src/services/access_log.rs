@@ -0,0 +1,345 @@
+//! 代理访问日志文件
+//!
+//! 开启后每完成一次代理请求向 `access-logs/access.log` 追加一行 JSON（字段与日志外发一致）。
+//! 当前文件超过大小上限或跨天时轮转：重命名为 `access-<时间>.log`，
+//! 在后台线程中用 zstd 压缩为 `.log.zst`，并只保留最新的若干个历史文件。
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::{Local, NaiveDate};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::proxy::usage::logger::RequestLog;
+use crate::services::log_shipper::AccessLogRecord;
+
+const ACTIVE_FILE: &str = "access.log";
+const ROTATED_PREFIX: &str = "access-";
+const ZSTD_LEVEL: i32 = 3;
+const MB: u64 = 1024 * 1024;
+
+static WRITER: Lazy<Mutex<Option<ActiveLog>>> = Lazy::new(|| Mutex::new(None));
+
+/// 访问日志文件设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessLogConfig {
+    /// 单个文件大小上限（MB），超过后轮转；0 表示不按大小轮转
+    #[serde(default = "default_max_file_mb")]
+    pub max_file_mb: u64,
+    /// 是否每天轮转
+    #[serde(default = "default_true")]
+    pub rotate_daily: bool,
+    /// 是否用 zstd 压缩轮转后的文件
+    #[serde(default = "default_true")]
+    pub compress: bool,
+    /// 保留的历史文件数（不含当前文件）
+    #[serde(default = "default_retention")]
+    pub retention: u32,
+}
+
+fn default_max_file_mb() -> u64 {
+    20
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_retention() -> u32 {
+    14
+}
+
+impl Default for AccessLogConfig {
+    fn default() -> Self {
+        Self {
+            max_file_mb: default_max_file_mb(),
+            rotate_daily: default_true(),
+            compress: default_true(),
+            retention: default_retention(),
+        }
+    }
+}
+
+/// 日志目录中的文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessLogFile {
+    pub name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    /// 最后修改时间（秒级时间戳）
+    pub modified_at: Option<i64>,
+    /// 是否为正在写入的文件
+    pub active: bool,
+    pub compressed: bool,
+}
+
+struct ActiveLog {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+    opened_on: NaiveDate,
+}
+
+/// 访问日志目录
+pub fn log_dir() -> PathBuf {
+    crate::config::get_app_config_dir().join("access-logs")
+}
+
+fn is_rotated(name: &str) -> bool {
+    name.starts_with(ROTATED_PREFIX) && (name.ends_with(".log") || name.ends_with(".log.zst"))
+}
+
+fn open_active(dir: &Path) -> Result<ActiveLog, AppError> {
+    fs::create_dir_all(dir).map_err(|e| AppError::io(dir, e))?;
+    let path = dir.join(ACTIVE_FILE);
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| AppError::io(&path, e))?;
+    let metadata = file.metadata().map_err(|e| AppError::io(&path, e))?;
+    // 沿用已有文件时按其修改日期判断是否跨天
+    let opened_on = metadata
+        .modified()
+        .ok()
+        .map(|t| chrono::DateTime::<Local>::from(t).date_naive())
+        .unwrap_or_else(|| Local::now().date_naive());
+    Ok(ActiveLog {
+        dir: dir.to_path_buf(),
+        file,
+        size: metadata.len(),
+        opened_on,
+    })
+}
+
+fn needs_rotation(active: &ActiveLog, config: &AccessLogConfig, today: NaiveDate) -> bool {
+    if active.size == 0 {
+        return false;
+    }
+    let over_size = config.max_file_mb > 0 && active.size >= config.max_file_mb.saturating_mul(MB);
+    over_size || (config.rotate_daily && active.opened_on != today)
+}
+
+/// 把当前文件重命名为历史文件，返回新路径
+fn rotate_active(dir: &Path, opened_on: NaiveDate) -> Result<PathBuf, AppError> {
+    let source = dir.join(ACTIVE_FILE);
+    let stamp = format!(
+        "{}-{}",
+        opened_on.format("%Y%m%d"),
+        Local::now().format("%H%M%S")
+    );
+    let mut target = dir.join(format!("{ROTATED_PREFIX}{stamp}.log"));
+    let mut n = 1;
+    while target.exists() || target.with_extension("log.zst").exists() {
+        target = dir.join(format!("{ROTATED_PREFIX}{stamp}-{n}.log"));
+        n += 1;
+    }
+    fs::rename(&source, &target).map_err(|e| AppError::io(&source, e))?;
+    Ok(target)
+}
+
+/// 压缩历史文件，成功后删除原文件
+fn compress_file(path: &Path) -> Result<PathBuf, AppError> {
+    let target = path.with_extension("log.zst");
+    let mut input = File::open(path).map_err(|e| AppError::io(path, e))?;
+    let output = File::create(&target).map_err(|e| AppError::io(&target, e))?;
+    let result = zstd::stream::Encoder::new(output, ZSTD_LEVEL)
+        .and_then(|mut encoder| {
+            std::io::copy(&mut input, &mut encoder)?;
+            encoder.finish()
+        })
+        .and_then(|file| file.sync_all());
+    if let Err(e) = result {
+        let _ = fs::remove_file(&target);
+        return Err(AppError::io(&target, e));
+    }
+    fs::remove_file(path).map_err(|e| AppError::io(path, e))?;
+    Ok(target)
+}
+
+/// 只保留最新的 `retention` 个历史文件，返回删除的文件数
+fn prune(dir: &Path, retention: usize) -> usize {
+    let mut rotated: Vec<_> = list_files(dir).into_iter().filter(|f| !f.active).collect();
+    if rotated.len() <= retention {
+        return 0;
+    }
+    // 文件名带时间，按名称倒序即从新到旧
+    rotated.sort_by(|a, b| b.name.cmp(&a.name));
+    let mut removed = 0;
+    for file in rotated.into_iter().skip(retention) {
+        match fs::remove_file(&file.path) {
+            Ok(()) => removed += 1,
+            Err(e) => log::warn!("[AccessLog] 删除 {} 失败: {e}", file.path),
+        }
+    }
+    removed
+}
+
+/// 轮转后的收尾（压缩与清理），在后台线程执行以免阻塞请求
+fn finish_rotation(dir: PathBuf, rotated: PathBuf, config: AccessLogConfig) {
+    std::thread::spawn(move || {
+        if config.compress {
+            if let Err(e) = compress_file(&rotated) {
+                log::warn!("[AccessLog] 压缩 {} 失败: {e}", rotated.display());
+            }
+        }
+        prune(&dir, config.retention as usize);
+    });
+}
+
+fn write_line(
+    slot: &mut Option<ActiveLog>,
+    dir: &Path,
+    config: &AccessLogConfig,
+    line: &str,
+) -> Result<(), AppError> {
+    // 数据目录变更后重新打开
+    if slot.as_ref().is_some_and(|active| active.dir != dir) {
+        *slot = None;
+    }
+    if slot.is_none() {
+        *slot = Some(open_active(dir)?);
+    }
+
+    let today = Local::now().date_naive();
+    if let Some(active) = slot.as_ref() {
+        if needs_rotation(active, config, today) {
+            let opened_on = active.opened_on;
+            *slot = None;
+            let rotated = rotate_active(dir, opened_on)?;
+            finish_rotation(dir.to_path_buf(), rotated, config.clone());
+            *slot = Some(open_active(dir)?);
+        }
+    }
+
+    let active = slot.as_mut().expect("access log opened above");
+    let path = dir.join(ACTIVE_FILE);
+    active
+        .file
+        .write_all(line.as_bytes())
+        .and_then(|_| active.file.write_all(b"\n"))
+        .map_err(|e| AppError::io(&path, e))?;
+    active.size += line.len() as u64 + 1;
+    Ok(())
+}
+
+/// 追加一条访问记录（未开启时关闭已打开的文件并忽略）
+pub fn append(request: &RequestLog) {
+    let mut slot = WRITER.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(config) = crate::settings::get_settings().access_log else {
+        *slot = None;
+        return;
+    };
+    let line = match serde_json::to_string(&AccessLogRecord::from_request_log(request)) {
+        Ok(line) => line,
+        Err(e) => {
+            log::warn!("[AccessLog] 序列化访问记录失败: {e}");
+            return;
+        }
+    };
+    if let Err(e) = write_line(&mut slot, &log_dir(), &config, &line) {
+        log::warn!("[AccessLog] 写入访问日志失败: {e}");
+        *slot = None;
+    }
+}
+
+fn list_files(dir: &Path) -> Vec<AccessLogFile> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let active = name == ACTIVE_FILE;
+            if !active && !is_rotated(&name) {
+                return None;
+            }
+            let metadata = entry.metadata().ok()?;
+            Some(AccessLogFile {
+                compressed: name.ends_with(".zst"),
+                path: entry.path().display().to_string(),
+                size_bytes: metadata.len(),
+                modified_at: metadata
+                    .modified()
+                    .ok()
+                    .map(|t| chrono::DateTime::<Local>::from(t).timestamp()),
+                active,
+                name,
+            })
+        })
+        .collect()
+}
+
+/// 列出日志目录中的文件：当前文件在前，其余从新到旧
+pub fn list() -> Vec<AccessLogFile> {
+    let mut files = list_files(&log_dir());
+    files.sort_by(|a, b| b.active.cmp(&a.active).then_with(|| b.name.cmp(&a.name)));
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_file_mb: u64, retention: u32) -> AccessLogConfig {
+        AccessLogConfig {
+            max_file_mb,
+            retention,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn rotates_on_day_change_and_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut active = open_active(dir.path()).unwrap();
+        let today = Local::now().date_naive();
+        assert!(!needs_rotation(&active, &config(1, 3), today));
+
+        active.size = 10;
+        assert!(!needs_rotation(&active, &config(1, 3), today));
+        assert!(needs_rotation(
+            &active,
+            &config(1, 3),
+            today.succ_opt().unwrap()
+        ));
+
+        active.size = MB;
+        assert!(needs_rotation(&active, &config(1, 3), today));
+        assert!(!needs_rotation(&active, &config(0, 3), today));
+    }
+
+    #[test]
+    fn compresses_and_prunes_rotated_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let today = Local::now().date_naive();
+        let mut slot = None;
+        write_line(&mut slot, dir.path(), &config(1, 2), "{\"n\":1}").unwrap();
+        drop(slot);
+
+        let rotated = rotate_active(dir.path(), today).unwrap();
+        let compressed = compress_file(&rotated).unwrap();
+        assert!(!rotated.exists());
+        let restored = zstd::decode_all(File::open(&compressed).unwrap()).unwrap();
+        assert_eq!(restored, b"{\"n\":1}\n");
+
+        for name in [
+            "access-20240101-000000.log.zst",
+            "access-20240102-000000.log",
+        ] {
+            fs::write(dir.path().join(name), b"x").unwrap();
+        }
+        fs::write(dir.path().join(ACTIVE_FILE), b"").unwrap();
+        assert_eq!(prune(dir.path(), 2), 1);
+        assert!(!dir.path().join("access-20240101-000000.log.zst").exists());
+        assert!(dir.path().join(ACTIVE_FILE).exists());
+        assert_eq!(list_files(dir.path()).len(), 3);
+    }
+}
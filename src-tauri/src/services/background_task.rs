@@ -0,0 +1,243 @@
+//! 后台任务
+//!
+//! 批量健康检查、端点测速等耗时命令立即返回任务，在后台执行：
+//! - 进度与部分结果通过 `task-progress` 事件推送，同时写入 background_tasks
+//! - 可按任务 ID 取消：进行中的一步随之中止（见 [`TaskHandle::token`]），已产生的结果会保留
+//! - 界面重新加载后可按任务 ID 重新获取状态与已产生的结果
+//!
+//! 应用退出时仍在运行的任务在下次启动时标记为 `interrupted`。
+
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::database::{BackgroundTask, Database, TaskStatus};
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::services::cancellation::{self, CancelToken};
+
+/// 已结束任务的保留时长（秒）
+const FINISHED_RETENTION_SECS: i64 = 7 * 86_400;
+
+/// 任务进度事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskProgress {
+    pub task_id: String,
+    pub kind: String,
+    pub status: TaskStatus,
+    pub done: u32,
+    pub total: u32,
+    /// 本次新产生的结果
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 任务执行过程中使用的句柄
+pub struct TaskHandle {
+    db: Arc<Database>,
+    cancel: CancelToken,
+    task: Mutex<BackgroundTask>,
+}
+
+impl TaskHandle {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// 任务的取消令牌，传给各步骤以便取消时中止进行中的请求
+    pub fn token(&self) -> &CancelToken {
+        &self.cancel
+    }
+
+    /// 设置总步数
+    pub fn set_total(&self, total: u32) {
+        let snapshot = {
+            let mut task = self.lock();
+            task.total = total;
+            task.clone()
+        };
+        self.save(&snapshot);
+        publish(&snapshot, None);
+    }
+
+    /// 追加一个结果并推进一步
+    pub fn push_result(&self, result: &impl Serialize) -> Result<(), AppError> {
+        let value =
+            serde_json::to_value(result).map_err(|e| AppError::JsonSerialize { source: e })?;
+        let snapshot = {
+            let mut task = self.lock();
+            task.done += 1;
+            task.total = task.total.max(task.done);
+            task.results.push(value.clone());
+            task.updated_at = chrono::Utc::now().timestamp();
+            task.clone()
+        };
+        self.save(&snapshot);
+        publish(&snapshot, Some(value));
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BackgroundTask> {
+        self.task.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn save(&self, task: &BackgroundTask) {
+        if let Err(e) = self.db.update_background_task(task) {
+            log::warn!("[Task] 保存任务 {} 进度失败: {e}", task.id);
+        }
+    }
+
+    fn finish(&self, result: Result<(), AppError>) {
+        let snapshot = {
+            let mut task = self.lock();
+            match result {
+                Err(e) => {
+                    task.status = TaskStatus::Failed;
+                    task.error = Some(e.to_string());
+                }
+                Ok(()) if self.is_cancelled() => task.status = TaskStatus::Cancelled,
+                Ok(()) => task.status = TaskStatus::Completed,
+            }
+            task.updated_at = chrono::Utc::now().timestamp();
+            task.clone()
+        };
+        self.save(&snapshot);
+        cancellation::unregister(&snapshot.id);
+        log::info!(
+            "[Task] 任务 {}（{}）结束: {}，{}/{}",
+            snapshot.id,
+            snapshot.kind,
+            snapshot.status.as_str(),
+            snapshot.done,
+            snapshot.total
+        );
+        publish(&snapshot, None);
+    }
+}
+
+fn publish(task: &BackgroundTask, result: Option<Value>) {
+    events::publish(AppEvent::TaskProgress(TaskProgress {
+        task_id: task.id.clone(),
+        kind: task.kind.clone(),
+        status: task.status,
+        done: task.done,
+        total: task.total,
+        result,
+        error: task.error.clone(),
+    }));
+}
+
+/// 创建任务并在后台执行 `run`，立即返回任务记录
+pub fn spawn<F, Fut>(db: Arc<Database>, kind: &str, run: F) -> Result<BackgroundTask, AppError>
+where
+    F: FnOnce(Arc<TaskHandle>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(), AppError>> + Send + 'static,
+{
+    let now = chrono::Utc::now().timestamp();
+    let task = BackgroundTask {
+        id: uuid::Uuid::new_v4().to_string(),
+        kind: kind.to_string(),
+        status: TaskStatus::Running,
+        done: 0,
+        total: 0,
+        results: Vec::new(),
+        error: None,
+        created_at: now,
+        updated_at: now,
+    };
+    db.insert_background_task(&task)?;
+
+    let cancel = cancellation::register(&task.id);
+    let handle = Arc::new(TaskHandle {
+        db,
+        cancel,
+        task: Mutex::new(task.clone()),
+    });
+    tauri::async_runtime::spawn(async move {
+        let result = run(handle.clone()).await;
+        handle.finish(result);
+    });
+    Ok(task)
+}
+
+/// 请求取消运行中的任务，返回任务是否在运行
+pub fn cancel(id: &str) -> bool {
+    cancellation::cancel(id)
+}
+
+/// 启动时调用：标记上次退出时中断的任务，清理过期记录
+pub fn recover(db: &Database) -> Result<(), AppError> {
+    let now = chrono::Utc::now().timestamp();
+    let interrupted = db.mark_interrupted_background_tasks(now)?;
+    if interrupted > 0 {
+        log::info!("[Task] {interrupted} 个后台任务在上次退出时中断");
+    }
+    db.prune_background_tasks(now - FINISHED_RETENTION_SECS)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn wait_finished(db: &Database, id: &str) -> BackgroundTask {
+        for _ in 0..200 {
+            let task = db.get_background_task(id).unwrap().unwrap();
+            if task.status.is_finished() {
+                return task;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("task {id} did not finish");
+    }
+
+    #[tokio::test]
+    async fn records_results_until_cancelled() {
+        let db = Arc::new(Database::memory().unwrap());
+        let task = spawn(db.clone(), "test", |task| async move {
+            task.set_total(3);
+            task.push_result(&1)?;
+            while !task.is_cancelled() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+            Ok(())
+        })
+        .unwrap();
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(cancel(&task.id));
+        let finished = wait_finished(&db, &task.id).await;
+        assert_eq!(finished.status, TaskStatus::Cancelled);
+        assert_eq!((finished.done, finished.total), (1, 3));
+        assert_eq!(finished.results, vec![serde_json::json!(1)]);
+        assert!(!cancel(&task.id));
+    }
+
+    #[tokio::test]
+    async fn failed_tasks_keep_error_and_interrupted_on_restart() {
+        let db = Arc::new(Database::memory().unwrap());
+        let task = spawn(db.clone(), "test", |_| async move {
+            Err(AppError::Message("boom".to_string()))
+        })
+        .unwrap();
+        let finished = wait_finished(&db, &task.id).await;
+        assert_eq!(finished.status, TaskStatus::Failed);
+        assert_eq!(finished.error.as_deref(), Some("boom"));
+
+        let mut stale = finished.clone();
+        stale.id = "stale".to_string();
+        stale.status = TaskStatus::Running;
+        db.insert_background_task(&stale).unwrap();
+        recover(&db).unwrap();
+        assert_eq!(
+            db.get_background_task("stale").unwrap().unwrap().status,
+            TaskStatus::Interrupted
+        );
+    }
+}
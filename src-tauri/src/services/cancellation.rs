@@ -0,0 +1,131 @@
+//! 取消令牌
+//!
+//! 健康检查、TPS 测试等耗时操作接收 [`CancelToken`]，取消时丢弃正在执行的 future，
+//! 进行中的 HTTP 请求随之中止，而不是在后台一直运行到超时。
+//!
+//! 需要由界面取消的操作以调用方提供的 ID 注册令牌，[`cancel`] 按 ID 触发。
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+use tokio::sync::Notify;
+
+/// 按 ID 注册的令牌
+static REGISTRY: Lazy<RwLock<HashMap<String, CancelToken>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+#[derive(Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+/// 可克隆的取消令牌，所有克隆共享同一取消状态
+#[derive(Clone, Default)]
+pub struct CancelToken {
+    inner: Arc<Inner>,
+}
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 等待令牌被取消
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// 执行 `fut`，令牌被取消时立即丢弃它并返回 None
+    pub async fn run<F: Future>(&self, fut: F) -> Option<F::Output> {
+        tokio::select! {
+            biased;
+            _ = self.cancelled() => None,
+            output = fut => Some(output),
+        }
+    }
+}
+
+/// 以 `id` 注册一个新令牌（同 ID 的旧令牌会被替换）
+pub fn register(id: &str) -> CancelToken {
+    let token = CancelToken::new();
+    REGISTRY
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(id.to_string(), token.clone());
+    token
+}
+
+/// 操作结束后移除令牌
+pub fn unregister(id: &str) {
+    REGISTRY
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(id);
+}
+
+/// 取消 `id` 对应的操作，返回该操作是否仍在进行
+pub fn cancel(id: &str) -> bool {
+    match REGISTRY.read().unwrap_or_else(|e| e.into_inner()).get(id) {
+        Some(token) => {
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn run_drops_future_when_cancelled() {
+        let token = register("cancel-test");
+        let waiting = token.clone();
+        let handle = tokio::spawn(async move {
+            waiting
+                .run(tokio::time::sleep(Duration::from_secs(30)))
+                .await
+        });
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(cancel("cancel-test"));
+        assert!(tokio::time::timeout(Duration::from_secs(1), handle)
+            .await
+            .unwrap()
+            .unwrap()
+            .is_none());
+
+        unregister("cancel-test");
+        assert!(!cancel("cancel-test"));
+    }
+
+    #[tokio::test]
+    async fn run_returns_output_when_not_cancelled() {
+        let token = CancelToken::new();
+        assert_eq!(token.run(async { 42 }).await, Some(42));
+
+        token.cancel();
+        assert_eq!(token.run(async { 42 }).await, None);
+    }
+}
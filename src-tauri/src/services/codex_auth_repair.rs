@@ -0,0 +1,149 @@
+//! Codex auth.json 自动修复
+//!
+//! Codex 刷新登录状态时偶尔会改写 auth.json，丢掉 cc-switch 写入的中转站 Key。
+//! 后台定期检查（auth.json 修改时间变化时才读取）：当前 Codex 供应商配置了 Key、
+//! 而 auth.json 中的 Key 缺失或为空时，只补回该字段（保留 Codex 写入的其他内容），
+//! 并写入审计日志。Key 被改成其他值属于用户修改，交给 Live 配置一致性检查处理。
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use serde_json::{json, Value};
+
+use crate::app_config::AppType;
+use crate::codex_config::get_codex_auth_path;
+use crate::config::{read_json_file, write_json_file};
+use crate::database::Database;
+use crate::error::AppError;
+use crate::services::startup_profile::{self, Subsystem};
+use crate::services::ProxyService;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const AUTH_KEY: &str = "OPENAI_API_KEY";
+
+/// 审计日志类别
+pub const AUDIT_CATEGORY: &str = "codexAuthRepair";
+
+fn non_empty_key(auth: &Value) -> Option<&str> {
+    auth.get(AUTH_KEY)
+        .and_then(|v| v.as_str())
+        .filter(|key| !key.trim().is_empty())
+}
+
+/// Live auth.json 丢失了供应商的 Key 时返回补回后的内容
+fn repaired(live: &Value, expected: &Value) -> Option<Value> {
+    let key = non_empty_key(expected)?;
+    if non_empty_key(live).is_some() {
+        return None;
+    }
+    let mut fixed = if live.is_object() {
+        live.clone()
+    } else {
+        json!({})
+    };
+    fixed[AUTH_KEY] = Value::String(key.to_string());
+    Some(fixed)
+}
+
+/// 检查并修复一次，返回是否做了修复
+pub fn check_once(db: &Database, proxy_service: &ProxyService) -> Result<bool, AppError> {
+    let app_type = AppType::Codex;
+    // 代理接管中 auth.json 指向本地代理，由接管流程负责恢复
+    if crate::instance_guard::is_attached()
+        || proxy_service.detect_takeover_in_live_config_for_app(&app_type)
+    {
+        return Ok(false);
+    }
+    let path = get_codex_auth_path();
+    if !path.exists() {
+        return Ok(false);
+    }
+    let Some(current_id) = crate::settings::get_effective_current_provider(db, &app_type)? else {
+        return Ok(false);
+    };
+    let Some(provider) = db.get_provider_by_id(&current_id, app_type.as_str())? else {
+        return Ok(false);
+    };
+    let Some(expected) = provider.settings_config.get("auth") else {
+        return Ok(false);
+    };
+
+    let live: Value = read_json_file(&path)?;
+    let Some(fixed) = repaired(&live, expected) else {
+        return Ok(false);
+    };
+    write_json_file(&path, &fixed)?;
+    log::warn!(
+        "[CodexAuthRepair] auth.json 中的 {AUTH_KEY} 已丢失，已为供应商 {} 补回",
+        provider.name
+    );
+
+    let fields: Vec<&String> = live
+        .as_object()
+        .map(|obj| obj.keys().collect())
+        .unwrap_or_default();
+    if let Err(e) = db.record_audit(
+        AUDIT_CATEGORY,
+        Some(app_type.as_str()),
+        Some(&provider.id),
+        &format!(
+            "auth.json 缺少 {AUTH_KEY}，已恢复供应商 {} 的 Key",
+            provider.name
+        ),
+        Some(&json!({ "path": path.to_string_lossy(), "liveFields": fields })),
+    ) {
+        log::warn!("[CodexAuthRepair] 写入审计日志失败: {e}");
+    }
+    Ok(true)
+}
+
+/// 后台检查循环（auth.json 未变化时跳过）
+pub async fn run(db: Arc<Database>, proxy_service: ProxyService) {
+    let mut last_modified: Option<SystemTime> = None;
+    loop {
+        tokio::time::sleep(CHECK_INTERVAL).await;
+        if !crate::settings::get_settings().codex_auth_repair
+            || !startup_profile::is_enabled(Subsystem::Sync)
+        {
+            continue;
+        }
+        let modified = std::fs::metadata(get_codex_auth_path())
+            .and_then(|m| m.modified())
+            .ok();
+        if modified.is_none() || modified == last_modified {
+            continue;
+        }
+        match check_once(&db, &proxy_service) {
+            // 修复后文件再次变化，下一轮重新读取修改时间
+            Ok(true) => last_modified = None,
+            Ok(false) => last_modified = modified,
+            Err(e) => log::warn!("[CodexAuthRepair] 检查 auth.json 失败: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restores_only_a_dropped_key() {
+        let expected = json!({ "OPENAI_API_KEY": "sk-relay" });
+
+        let rewritten = json!({
+            "OPENAI_API_KEY": null,
+            "tokens": { "id_token": "t" },
+            "last_refresh": "2026-01-01T00:00:00Z"
+        });
+        let fixed = repaired(&rewritten, &expected).unwrap();
+        assert_eq!(fixed["OPENAI_API_KEY"], "sk-relay");
+        assert_eq!(fixed["tokens"]["id_token"], "t");
+        assert_eq!(fixed["last_refresh"], "2026-01-01T00:00:00Z");
+
+        assert!(repaired(&json!({}), &expected).is_some());
+        // 用户改成了其他 Key：不覆盖
+        assert!(repaired(&json!({ "OPENAI_API_KEY": "sk-other" }), &expected).is_none());
+        // 官方登录的供应商没有 Key：不修复
+        assert!(repaired(&json!({}), &json!({ "OPENAI_API_KEY": "" })).is_none());
+    }
+}
@@ -0,0 +1,181 @@
+//! 配置锁（只读模式）
+//!
+//! 锁定后拒绝新增、编辑、删除与切换供应商，避免结对编程等共用电脑的场景下误操作。
+//! 可选设置解锁口令（PBKDF2-HMAC-SHA256 哈希后保存）。
+//!
+//! 锁状态保存在数据库 settings 表中，不放在 `AppSettings` 里，避免保存设置时被整体覆盖解除。
+//! 故障转移属于自动行为，不受锁影响。只读附加实例（见 `instance_guard`）同样视为已锁定。
+
+use std::num::NonZeroU32;
+
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::error::AppError;
+
+const SETTING_KEY: &str = "config_lock";
+const PBKDF2_ITERATIONS: u32 = 100_000;
+const SALT_LEN: usize = 16;
+const HASH_LEN: usize = 32;
+
+/// 持久化的锁记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LockRecord {
+    locked_at: i64,
+    /// 口令盐（hex），未设置口令时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    salt: Option<String>,
+    /// 口令哈希（hex）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+}
+
+/// 配置锁状态
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigLockStatus {
+    pub locked: bool,
+    /// 解锁是否需要口令
+    pub has_passphrase: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locked_at: Option<i64>,
+}
+
+fn iterations() -> NonZeroU32 {
+    NonZeroU32::new(PBKDF2_ITERATIONS).expect("iterations > 0")
+}
+
+fn load(db: &Database) -> Result<Option<LockRecord>, AppError> {
+    let Some(raw) = db.get_setting(SETTING_KEY)? else {
+        return Ok(None);
+    };
+    if raw.is_empty() {
+        return Ok(None);
+    }
+    serde_json::from_str(&raw)
+        .map(Some)
+        .map_err(|e| AppError::Message(format!("解析配置锁失败: {e}")))
+}
+
+/// 当前锁状态
+pub fn status(db: &Database) -> Result<ConfigLockStatus, AppError> {
+    Ok(match load(db)? {
+        Some(record) => ConfigLockStatus {
+            locked: true,
+            has_passphrase: record.hash.is_some(),
+            locked_at: Some(record.locked_at),
+        },
+        None => ConfigLockStatus::default(),
+    })
+}
+
+/// 锁定配置；`passphrase` 为空时任何人都可直接解锁
+pub fn lock(db: &Database, passphrase: Option<&str>) -> Result<ConfigLockStatus, AppError> {
+    if load(db)?.is_some() {
+        return Err(AppError::Message("配置已处于锁定状态".to_string()));
+    }
+    let (salt, hash) = match passphrase.filter(|p| !p.is_empty()) {
+        Some(passphrase) => {
+            let mut salt = [0u8; SALT_LEN];
+            SystemRandom::new()
+                .fill(&mut salt)
+                .map_err(|_| AppError::Message("生成随机数失败".to_string()))?;
+            let mut hash = [0u8; HASH_LEN];
+            pbkdf2::derive(
+                pbkdf2::PBKDF2_HMAC_SHA256,
+                iterations(),
+                &salt,
+                passphrase.as_bytes(),
+                &mut hash,
+            );
+            (Some(hex::encode(salt)), Some(hex::encode(hash)))
+        }
+        None => (None, None),
+    };
+    let record = LockRecord {
+        locked_at: chrono::Utc::now().timestamp(),
+        salt,
+        hash,
+    };
+    let json = serde_json::to_string(&record).map_err(|e| AppError::JsonSerialize { source: e })?;
+    db.set_setting(SETTING_KEY, &json)?;
+    status(db)
+}
+
+/// 解锁配置，设置了口令时需要提供正确的口令
+pub fn unlock(db: &Database, passphrase: Option<&str>) -> Result<ConfigLockStatus, AppError> {
+    let Some(record) = load(db)? else {
+        return status(db);
+    };
+    if let (Some(salt), Some(hash)) = (&record.salt, &record.hash) {
+        let wrong = || {
+            AppError::localized(
+                "config.lock.wrongPassphrase",
+                "解锁口令错误",
+                "Incorrect unlock passphrase",
+            )
+        };
+        let salt = hex::decode(salt).map_err(|_| wrong())?;
+        let hash = hex::decode(hash).map_err(|_| wrong())?;
+        pbkdf2::verify(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            iterations(),
+            &salt,
+            passphrase.unwrap_or_default().as_bytes(),
+            &hash,
+        )
+        .map_err(|_| wrong())?;
+    }
+    db.set_setting(SETTING_KEY, "")?;
+    status(db)
+}
+
+/// 配置被锁定时返回错误（供修改供应商的操作调用）
+pub fn ensure_unlocked(db: &Database) -> Result<(), AppError> {
+    if crate::instance_guard::is_attached() {
+        return Err(AppError::localized(
+            "instance.attached",
+            "另一个 cc-switch 实例正在运行，当前窗口为只读模式",
+            "Another cc-switch instance is running; this window is read-only",
+        ));
+    }
+    if load(db)?.is_some() {
+        return Err(AppError::localized(
+            "config.locked",
+            "配置已锁定，请先解锁后再修改供应商",
+            "Configuration is locked; unlock it before changing providers",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_blocks_until_unlocked_with_passphrase() {
+        let db = Database::memory().expect("create memory db");
+        assert!(ensure_unlocked(&db).is_ok());
+
+        let status = lock(&db, Some("pair")).unwrap();
+        assert!(status.locked && status.has_passphrase);
+        assert!(ensure_unlocked(&db).is_err());
+        assert!(lock(&db, None).is_err());
+
+        assert!(unlock(&db, Some("wrong")).is_err());
+        assert!(unlock(&db, None).is_err());
+        assert!(!unlock(&db, Some("pair")).unwrap().locked);
+        assert!(ensure_unlocked(&db).is_ok());
+    }
+
+    #[test]
+    fn lock_without_passphrase_unlocks_directly() {
+        let db = Database::memory().expect("create memory db");
+        assert!(!lock(&db, Some("")).unwrap().has_passphrase);
+        assert!(!unlock(&db, None).unwrap().locked);
+    }
+}
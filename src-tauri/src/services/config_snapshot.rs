@@ -0,0 +1,297 @@
+//! 配置快照与变更对比
+//!
+//! 定期为供应商表和应用设置拍摄快照，内容有变化时保存快照及与上一份的差异。
+//! 同步、导入、手动编辑数据库等不经过单项更新命令的修改也能被发现，
+//! 可用于回答“从昨天到现在改了什么”。
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::app_config::AppType;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::services::startup_profile::{self, Subsystem};
+
+/// 快照保留天数（始终保留最新一份作为对比基线）
+const RETENTION_DAYS: i64 = 30;
+const REDACTED: &str = "[REDACTED]";
+/// 路径末段包含这些词时隐藏取值
+const SENSITIVE_WORDS: &[&str] = &["key", "token", "secret", "password", "credential"];
+
+/// 变更类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// 单个字段的变更（路径形如 `providers/claude/<id>/settingsConfig/env/ANTHROPIC_BASE_URL`）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Value>,
+}
+
+/// 已保存的快照
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSnapshot {
+    pub id: i64,
+    pub taken_at: i64,
+    pub content: Value,
+    /// 与上一份快照的差异
+    pub changes: Vec<ConfigChange>,
+}
+
+/// 快照列表项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigSnapshotEntry {
+    pub id: i64,
+    pub taken_at: i64,
+    pub change_count: usize,
+}
+
+/// 某个时间点以来的变更
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigChangeReport {
+    pub since: i64,
+    /// 作为基线的快照时间（早于 `since` 时没有快照则为之后最早的一份，完全没有快照时为 None）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub baseline_taken_at: Option<i64>,
+    /// 基线与当前配置的差异
+    pub changes: Vec<ConfigChange>,
+    /// 期间检测到变化的快照
+    pub snapshots: Vec<ConfigSnapshotEntry>,
+}
+
+/// 读取当前的供应商表与设置
+pub fn capture(db: &Database) -> Result<Value, AppError> {
+    let mut providers = Map::new();
+    for app_type in [AppType::Claude, AppType::Codex, AppType::Gemini] {
+        let app = app_type.as_str();
+        let all = db.get_all_providers(app)?;
+        let entries = all
+            .into_iter()
+            .map(|(id, provider)| {
+                serde_json::to_value(provider)
+                    .map(|v| (id, v))
+                    .map_err(|e| AppError::JsonSerialize { source: e })
+            })
+            .collect::<Result<Map<_, _>, _>>()?;
+        providers.insert(app.to_string(), Value::Object(entries));
+    }
+    let settings = serde_json::to_value(crate::settings::get_settings())
+        .map_err(|e| AppError::JsonSerialize { source: e })?;
+    Ok(json!({ "providers": providers, "settings": settings }))
+}
+
+/// 拍摄快照；与上一份相同时不保存，返回 None
+pub fn take_snapshot(db: &Database) -> Result<Option<ConfigSnapshotEntry>, AppError> {
+    let content = capture(db)?;
+    let previous = db.get_latest_config_snapshot()?;
+    let changes = match &previous {
+        Some(previous) if previous.content == content => return Ok(None),
+        Some(previous) => diff(&previous.content, &content),
+        None => Vec::new(),
+    };
+    let taken_at = chrono::Utc::now().timestamp();
+    let id = db.insert_config_snapshot(taken_at, &content, &changes)?;
+    db.prune_config_snapshots(taken_at - RETENTION_DAYS * 24 * 3600)?;
+    if !changes.is_empty() {
+        log::info!("[ConfigSnapshot] 检测到 {} 处配置变化", changes.len());
+    }
+    Ok(Some(ConfigSnapshotEntry {
+        id,
+        taken_at,
+        change_count: changes.len(),
+    }))
+}
+
+/// `since` 以来的变更：以当时的快照为基线，与当前配置对比
+pub fn changes_since(db: &Database, since: i64) -> Result<ConfigChangeReport, AppError> {
+    let baseline = match db.get_config_snapshot_at_or_before(since)? {
+        Some(snapshot) => Some(snapshot),
+        None => db.get_first_config_snapshot_after(since)?,
+    };
+    let current = capture(db)?;
+    let changes = baseline
+        .as_ref()
+        .map(|b| diff(&b.content, &current))
+        .unwrap_or_default();
+    let snapshots = db
+        .list_config_snapshots(Some(since), 1000)?
+        .into_iter()
+        .filter(|s| s.change_count > 0)
+        .collect();
+    Ok(ConfigChangeReport {
+        since,
+        baseline_taken_at: baseline.map(|b| b.taken_at),
+        changes,
+        snapshots,
+    })
+}
+
+/// 对比两份快照（敏感字段只标记变化，不输出取值）
+pub fn diff(before: &Value, after: &Value) -> Vec<ConfigChange> {
+    let (mut old, mut new) = (BTreeMap::new(), BTreeMap::new());
+    flatten("", before, &mut old);
+    flatten("", after, &mut new);
+
+    let mut changes = Vec::new();
+    for (path, value) in &old {
+        match new.remove(path) {
+            None => changes.push(change(path, ChangeKind::Removed, Some(value), None)),
+            Some(next) if next != *value => {
+                changes.push(change(path, ChangeKind::Modified, Some(value), Some(&next)))
+            }
+            Some(_) => {}
+        }
+    }
+    // 剩下的都是旧快照中没有的路径
+    for (path, value) in &new {
+        changes.push(change(path, ChangeKind::Added, None, Some(value)));
+    }
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    changes
+}
+
+fn change(
+    path: &str,
+    kind: ChangeKind,
+    before: Option<&Value>,
+    after: Option<&Value>,
+) -> ConfigChange {
+    let sensitive = is_sensitive(path);
+    let shown = |v: Option<&Value>| {
+        v.map(|v| {
+            if sensitive {
+                Value::String(REDACTED.to_string())
+            } else {
+                v.clone()
+            }
+        })
+    };
+    ConfigChange {
+        path: path.to_string(),
+        kind,
+        before: shown(before),
+        after: shown(after),
+    }
+}
+
+fn is_sensitive(path: &str) -> bool {
+    let last = path.rsplit('/').next().unwrap_or_default().to_lowercase();
+    SENSITIVE_WORDS.iter().any(|w| last.contains(w))
+}
+
+/// 展开为 路径 → 叶子值（数组整体视为一个值）
+pub(crate) fn flatten(prefix: &str, value: &Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, v) in map {
+                let key = key.replace('~', "~0").replace('/', "~1");
+                let path = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{prefix}/{key}")
+                };
+                flatten(&path, v, out);
+            }
+        }
+        other => {
+            out.insert(prefix.to_string(), other.clone());
+        }
+    }
+}
+
+/// 后台快照循环：启动时先拍一次，之后按设置的间隔执行（间隔为 0 时暂停）
+pub async fn run(db: Arc<Database>) {
+    loop {
+        let interval = crate::settings::get_settings().config_snapshot_interval_minutes;
+        if interval > 0 && startup_profile::is_enabled(Subsystem::Sync) {
+            let db = db.clone();
+            match tokio::task::spawn_blocking(move || take_snapshot(&db)).await {
+                Ok(Err(e)) => log::warn!("[ConfigSnapshot] 拍摄配置快照失败: {e}"),
+                Err(e) => log::warn!("[ConfigSnapshot] 快照任务异常: {e}"),
+                Ok(Ok(_)) => {}
+            }
+        }
+        let minutes = if interval > 0 { interval } else { 1 };
+        tokio::time::sleep(Duration::from_secs(minutes as u64 * 60)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_nested_fields() {
+        let before = json!({
+            "providers": { "claude": {
+                "a": { "name": "A", "settingsConfig": { "env": {
+                    "ANTHROPIC_BASE_URL": "https://a.example",
+                    "ANTHROPIC_AUTH_TOKEN": "sk-old"
+                } } },
+                "b": { "name": "B" }
+            } },
+            "settings": { "language": "zh" }
+        });
+        let after = json!({
+            "providers": { "claude": {
+                "a": { "name": "A", "settingsConfig": { "env": {
+                    "ANTHROPIC_BASE_URL": "https://b.example",
+                    "ANTHROPIC_AUTH_TOKEN": "sk-new"
+                } } },
+                "c": { "name": "C" }
+            } },
+            "settings": { "language": "zh" }
+        });
+
+        let changes = diff(&before, &after);
+        let summary: Vec<_> = changes.iter().map(|c| (c.path.as_str(), c.kind)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "providers/claude/a/settingsConfig/env/ANTHROPIC_AUTH_TOKEN",
+                    ChangeKind::Modified
+                ),
+                (
+                    "providers/claude/a/settingsConfig/env/ANTHROPIC_BASE_URL",
+                    ChangeKind::Modified
+                ),
+                ("providers/claude/b/name", ChangeKind::Removed),
+                ("providers/claude/c/name", ChangeKind::Added),
+            ]
+        );
+        assert_eq!(changes[0].after, Some(json!(REDACTED)));
+        assert_eq!(changes[1].after, Some(json!("https://b.example")));
+        assert!(diff(&after, &after).is_empty());
+    }
+
+    #[test]
+    fn escapes_path_segments() {
+        let mut out = BTreeMap::new();
+        flatten(
+            "",
+            &json!({ "a/b": { "c": [1, 2] }, "empty": {} }),
+            &mut out,
+        );
+        assert_eq!(out.get("a~1b/c"), Some(&json!([1, 2])));
+        assert_eq!(out.get("empty"), Some(&json!({})));
+    }
+}
@@ -0,0 +1,210 @@
+//! 数据目录迁移
+//!
+//! 将数据库、日志、备份等数据从当前数据目录复制到用户选择的新目录（同步盘、U 盘等）：
+//! 1. 校验目标目录（不能与当前目录相同或互相嵌套，必须为空或不存在）
+//! 2. 复制除数据库外的所有文件，数据库通过 SQLite Backup 生成一致性副本
+//! 3. 校验新数据库完整性，失败时清理已复制的内容
+//!
+//! 原目录保持不变作为回退，调用方在迁移成功后更新数据目录配置并重启应用。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::error::AppError;
+
+const DB_FILE: &str = "cc-switch.db";
+/// 数据库及其日志文件（通过 Backup 复制，不直接拷贝）
+const DB_FILES: &[&str] = &[
+    "cc-switch.db",
+    "cc-switch.db-wal",
+    "cc-switch.db-shm",
+    "cc-switch.db-journal",
+];
+
+/// 迁移结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataMigrationReport {
+    pub source: String,
+    pub target: String,
+    /// 复制的文件数（含数据库）
+    pub files_copied: usize,
+    pub bytes_copied: u64,
+}
+
+/// 将当前数据目录迁移到 `target`
+pub fn migrate(db: &Database, target: &Path) -> Result<DataMigrationReport, AppError> {
+    let source = crate::config::get_app_config_dir();
+    migrate_between(db, &source, target)
+}
+
+fn migrate_between(
+    db: &Database,
+    source: &Path,
+    target: &Path,
+) -> Result<DataMigrationReport, AppError> {
+    validate_target(source, target)?;
+    let created = !target.exists();
+    fs::create_dir_all(target).map_err(|e| AppError::io(target, e))?;
+
+    let mut report = DataMigrationReport {
+        source: source.display().to_string(),
+        target: target.display().to_string(),
+        files_copied: 0,
+        bytes_copied: 0,
+    };
+    let result = copy_tree(source, target, true, &mut report).and_then(|_| {
+        let db_path = target.join(DB_FILE);
+        db.backup_to_file(&db_path)?;
+        verify_database(&db_path)?;
+        report.files_copied += 1;
+        report.bytes_copied += fs::metadata(&db_path).map(|m| m.len()).unwrap_or(0);
+        Ok(())
+    });
+
+    if let Err(e) = result {
+        // 目标目录原本为空，出错时整体清理，避免留下不完整的数据
+        let _ = fs::remove_dir_all(target);
+        if !created {
+            let _ = fs::create_dir_all(target);
+        }
+        return Err(e);
+    }
+    log::info!(
+        "数据目录已复制 {} -> {}（{} 个文件）",
+        report.source,
+        report.target,
+        report.files_copied
+    );
+    Ok(report)
+}
+
+/// 规范化路径；目标目录尚不存在时规范化其父目录
+fn canonical(path: &Path) -> Result<PathBuf, AppError> {
+    if path.exists() {
+        return path.canonicalize().map_err(|e| AppError::io(path, e));
+    }
+    let parent = path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .ok_or_else(|| AppError::InvalidInput(format!("无效的目标目录: {}", path.display())))?;
+    let name = path
+        .file_name()
+        .ok_or_else(|| AppError::InvalidInput(format!("无效的目标目录: {}", path.display())))?;
+    Ok(canonical(parent)?.join(name))
+}
+
+fn validate_target(source: &Path, target: &Path) -> Result<(), AppError> {
+    if !target.is_absolute() {
+        return Err(AppError::InvalidInput("目标目录必须是绝对路径".to_string()));
+    }
+    let source = canonical(source)?;
+    let target_canonical = canonical(target)?;
+    if source == target_canonical {
+        return Err(AppError::InvalidInput(
+            "目标目录与当前数据目录相同".to_string(),
+        ));
+    }
+    if target_canonical.starts_with(&source) || source.starts_with(&target_canonical) {
+        return Err(AppError::InvalidInput(
+            "目标目录不能位于当前数据目录内，也不能包含当前数据目录".to_string(),
+        ));
+    }
+    if target.exists() {
+        if !target.is_dir() {
+            return Err(AppError::InvalidInput(format!(
+                "目标路径不是目录: {}",
+                target.display()
+            )));
+        }
+        let mut entries = fs::read_dir(target).map_err(|e| AppError::io(target, e))?;
+        if entries.next().is_some() {
+            return Err(AppError::InvalidInput(format!(
+                "目标目录不为空: {}",
+                target.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn copy_tree(
+    from: &Path,
+    to: &Path,
+    top_level: bool,
+    report: &mut DataMigrationReport,
+) -> Result<(), AppError> {
+    for entry in fs::read_dir(from).map_err(|e| AppError::io(from, e))? {
+        let entry = entry.map_err(|e| AppError::io(from, e))?;
+        let path = entry.path();
+        let name = entry.file_name();
+        if top_level && DB_FILES.iter().any(|f| name == *f) {
+            continue;
+        }
+        let dest = to.join(&name);
+        if path.is_dir() {
+            fs::create_dir_all(&dest).map_err(|e| AppError::io(&dest, e))?;
+            copy_tree(&path, &dest, false, report)?;
+        } else {
+            report.bytes_copied += fs::copy(&path, &dest).map_err(|e| AppError::io(&dest, e))?;
+            report.files_copied += 1;
+        }
+    }
+    Ok(())
+}
+
+fn verify_database(path: &Path) -> Result<(), AppError> {
+    let conn = rusqlite::Connection::open(path).map_err(AppError::from)?;
+    crate::database::encryption::key_connection(&conn)?;
+    let result: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .map_err(AppError::from)?;
+    if result != "ok" {
+        return Err(AppError::Database(format!(
+            "迁移后的数据库校验失败: {result}"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copies_files_and_database() {
+        let source = tempfile::tempdir().unwrap();
+        let target_root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(source.path().join("backups")).unwrap();
+        fs::write(source.path().join("backups/a.db"), b"backup").unwrap();
+        fs::write(source.path().join("vault.key"), b"key").unwrap();
+        fs::write(source.path().join(DB_FILE), b"stale").unwrap();
+
+        let db = Database::memory().unwrap();
+        let target = target_root.path().join("cc-switch-data");
+        let report = migrate_between(&db, source.path(), &target).unwrap();
+
+        assert_eq!(report.files_copied, 3);
+        assert_eq!(fs::read(target.join("vault.key")).unwrap(), b"key");
+        assert!(target.join("backups/a.db").exists());
+        verify_database(&target.join(DB_FILE)).unwrap();
+        // 原目录保持不变
+        assert_eq!(fs::read(source.path().join(DB_FILE)).unwrap(), b"stale");
+    }
+
+    #[test]
+    fn rejects_nested_or_non_empty_targets() {
+        let source = tempfile::tempdir().unwrap();
+        let db = Database::memory().unwrap();
+        assert!(migrate_between(&db, source.path(), &source.path().join("sub")).is_err());
+        assert!(migrate_between(&db, source.path(), source.path()).is_err());
+
+        let other = tempfile::tempdir().unwrap();
+        fs::write(other.path().join("file"), b"x").unwrap();
+        assert!(migrate_between(&db, source.path(), other.path()).is_err());
+        assert!(migrate_between(&db, source.path(), Path::new("relative/dir")).is_err());
+    }
+}
@@ -0,0 +1,266 @@
+//! 启动自检与诊断
+//!
+//! 汇总数据库完整性、配置目录写权限、代理端口、上游连通性等检查项，
+//! 生成结构化报告供前端展示。
+
+use std::path::Path;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app_config::AppType;
+use crate::database::Database;
+use crate::proxy::keep_warm;
+use crate::services::stream_check::StreamCheckConfig;
+use crate::services::vault;
+use crate::settings::KeepWarmMode;
+
+/// 检查项状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticStatus {
+    Pass,
+    Skipped,
+    Warn,
+    Fail,
+}
+
+/// 单个检查项结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticCheck {
+    /// 检查项标识（如 `database.integrity`、`config.claude`）
+    pub id: String,
+    pub status: DiagnosticStatus,
+    pub message: String,
+    pub duration_ms: u64,
+}
+
+/// 诊断报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsReport {
+    /// 所有检查项中最严重的状态
+    pub overall: DiagnosticStatus,
+    pub checks: Vec<DiagnosticCheck>,
+    pub generated_at: i64,
+}
+
+const APP_TYPES: [AppType; 3] = [AppType::Claude, AppType::Codex, AppType::Gemini];
+
+/// 钥匙串探测使用的账户，与正式密钥分开，检查结束后删除
+const KEYCHAIN_PROBE_ACCOUNT: &str = "diagnostics-probe";
+
+pub struct DiagnosticsService;
+
+impl DiagnosticsService {
+    /// 执行全部检查
+    pub async fn run(db: &Database, proxy_running: bool) -> DiagnosticsReport {
+        let mut checks = Vec::new();
+
+        checks.push(timed("database.integrity", || Self::check_database(db)));
+
+        for app_type in APP_TYPES {
+            let dir = config_dir(&app_type);
+            checks.push(timed(&format!("config.{}", app_type.as_str()), || {
+                Self::check_dir_writable(&dir)
+            }));
+        }
+
+        let start = Instant::now();
+        let (status, message) = Self::check_proxy_port(db, proxy_running).await;
+        checks.push(finish("proxy.port", start, status, message));
+
+        for app_type in APP_TYPES {
+            let start = Instant::now();
+            let (status, message) = Self::check_connectivity(db, &app_type).await;
+            let id = format!("connectivity.{}", app_type.as_str());
+            checks.push(finish(&id, start, status, message));
+        }
+
+        // 钥匙串操作可能等待系统授权或调用外部命令，放到阻塞线程执行
+        let start = Instant::now();
+        let (status, message) = tokio::task::spawn_blocking(Self::check_keychain)
+            .await
+            .unwrap_or_else(|e| (DiagnosticStatus::Fail, format!("钥匙串检查异常: {e}")));
+        checks.push(finish("keychain", start, status, message));
+
+        DiagnosticsReport {
+            overall: checks
+                .iter()
+                .map(|c| c.status)
+                .max()
+                .unwrap_or(DiagnosticStatus::Pass),
+            checks,
+            generated_at: chrono::Utc::now().timestamp(),
+        }
+    }
+
+    fn check_database(db: &Database) -> (DiagnosticStatus, String) {
+        match db.integrity_check() {
+            Ok(problems) if problems.is_empty() => {
+                (DiagnosticStatus::Pass, "数据库完整性检查通过".to_string())
+            }
+            Ok(problems) => (
+                DiagnosticStatus::Fail,
+                format!("数据库完整性检查发现问题: {}", problems.join("; ")),
+            ),
+            Err(e) => (DiagnosticStatus::Fail, format!("无法执行完整性检查: {e}")),
+        }
+    }
+
+    /// 在目录中创建临时文件以验证写权限
+    fn check_dir_writable(dir: &Path) -> (DiagnosticStatus, String) {
+        if !dir.exists() {
+            return (
+                DiagnosticStatus::Warn,
+                format!("配置目录不存在（应用可能未安装）: {}", dir.display()),
+            );
+        }
+
+        match tempfile::NamedTempFile::new_in(dir) {
+            Ok(_) => (
+                DiagnosticStatus::Pass,
+                format!("配置目录可写: {}", dir.display()),
+            ),
+            Err(e) => (
+                DiagnosticStatus::Fail,
+                format!("配置目录不可写: {} ({e})", dir.display()),
+            ),
+        }
+    }
+
+    async fn check_proxy_port(db: &Database, proxy_running: bool) -> (DiagnosticStatus, String) {
+        let config = match db.get_proxy_config().await {
+            Ok(c) => c,
+            Err(e) => return (DiagnosticStatus::Fail, format!("读取代理配置失败: {e}")),
+        };
+        let addr = format!("{}:{}", config.listen_address, config.listen_port);
+
+        if proxy_running {
+            return (DiagnosticStatus::Pass, format!("代理正在运行，监听 {addr}"));
+        }
+
+        match std::net::TcpListener::bind(&addr) {
+            Ok(_) => (DiagnosticStatus::Pass, format!("端口可用: {addr}")),
+            Err(e) => (DiagnosticStatus::Fail, format!("端口不可用: {addr} ({e})")),
+        }
+    }
+
+    /// 写入、读回并删除一个探测条目，验证系统钥匙串可用
+    ///
+    /// 附件主密钥与数据库加密密钥都保存在钥匙串中，不可用时仅提示而不判定失败
+    fn check_keychain() -> (DiagnosticStatus, String) {
+        const PROBE_VALUE: &str = "ok";
+        let written = vault::keychain_set(
+            KEYCHAIN_PROBE_ACCOUNT,
+            "CC Switch diagnostics probe",
+            PROBE_VALUE,
+        );
+        let read_back =
+            written && vault::keychain_get(KEYCHAIN_PROBE_ACCOUNT).as_deref() == Some(PROBE_VALUE);
+        if written {
+            vault::keychain_delete(KEYCHAIN_PROBE_ACCOUNT);
+        }
+
+        match (written, read_back) {
+            (true, true) => (DiagnosticStatus::Pass, "系统钥匙串可用".to_string()),
+            (true, false) => (
+                DiagnosticStatus::Warn,
+                "系统钥匙串可写入但无法读回，附件与数据库加密可能无法使用".to_string(),
+            ),
+            (false, _) => (
+                DiagnosticStatus::Warn,
+                "系统钥匙串不可用，附件与数据库加密（钥匙串模式）无法使用".to_string(),
+            ),
+        }
+    }
+
+    /// 对当前供应商的 base_url 发送 HEAD 请求（不消耗 token）
+    async fn check_connectivity(db: &Database, app_type: &AppType) -> (DiagnosticStatus, String) {
+        let provider = match db
+            .get_current_provider(app_type.as_str())
+            .and_then(|id| match id {
+                Some(id) => db.get_provider_by_id(&id, app_type.as_str()),
+                None => Ok(None),
+            }) {
+            Ok(Some(p)) => p,
+            Ok(None) => return (DiagnosticStatus::Skipped, "未设置当前供应商".to_string()),
+            Err(e) => return (DiagnosticStatus::Fail, format!("读取当前供应商失败: {e}")),
+        };
+
+        match keep_warm::ping(
+            app_type,
+            &provider,
+            KeepWarmMode::Head,
+            &StreamCheckConfig::default(),
+        )
+        .await
+        {
+            Ok(_) => (
+                DiagnosticStatus::Pass,
+                format!("可连接到供应商 {}", provider.name),
+            ),
+            Err(e) => (
+                DiagnosticStatus::Fail,
+                format!("无法连接到供应商 {}: {e}", provider.name),
+            ),
+        }
+    }
+}
+
+fn config_dir(app_type: &AppType) -> std::path::PathBuf {
+    match app_type {
+        AppType::Claude => crate::config::get_claude_config_dir(),
+        AppType::Codex => crate::codex_config::get_codex_config_dir(),
+        AppType::Gemini => crate::gemini_config::get_gemini_dir(),
+    }
+}
+
+fn timed(id: &str, f: impl FnOnce() -> (DiagnosticStatus, String)) -> DiagnosticCheck {
+    let start = Instant::now();
+    let (status, message) = f();
+    finish(id, start, status, message)
+}
+
+fn finish(id: &str, start: Instant, status: DiagnosticStatus, message: String) -> DiagnosticCheck {
+    DiagnosticCheck {
+        id: id.to_string(),
+        status,
+        message,
+        duration_ms: start.elapsed().as_millis() as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_ordering_picks_most_severe() {
+        let statuses = [
+            DiagnosticStatus::Pass,
+            DiagnosticStatus::Fail,
+            DiagnosticStatus::Skipped,
+            DiagnosticStatus::Warn,
+        ];
+        assert_eq!(statuses.iter().max(), Some(&DiagnosticStatus::Fail));
+    }
+
+    #[test]
+    fn dir_writable_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let (status, _) = DiagnosticsService::check_dir_writable(dir.path());
+        assert_eq!(status, DiagnosticStatus::Pass);
+
+        let (status, _) = DiagnosticsService::check_dir_writable(&dir.path().join("missing"));
+        assert_eq!(status, DiagnosticStatus::Warn);
+    }
+
+    #[test]
+    fn database_integrity_passes_on_fresh_db() {
+        let db = Database::memory().unwrap();
+        let (status, _) = DiagnosticsService::check_database(&db);
+        assert_eq!(status, DiagnosticStatus::Pass);
+    }
+}
@@ -0,0 +1,377 @@
+//! 磁盘占用保护
+//!
+//! 定期检查数据库、日志目录与备份目录的占用，超过设置的上限时按策略清理最旧的数据：
+//! - 数据库：按时间删除最早的请求日志，清理后执行 VACUUM 收缩文件
+//! - 日志 / 备份目录：删除最早修改的文件（备份始终保留最新一份）
+//!
+//! 每次超限都会发送 `disk-usage-warning` 事件，而不是悄悄占满磁盘。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::config::get_app_config_dir;
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+
+/// 检查间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(30 * 60);
+/// 数据库每轮最多删除的请求日志比例（百分比）与最少条数
+const PRUNE_PERCENT: usize = 10;
+const PRUNE_MIN_ROWS: usize = 100;
+/// 数据库单次检查最多清理的轮数
+const MAX_PRUNE_ROUNDS: usize = 20;
+const MB: u64 = 1024 * 1024;
+
+static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 磁盘占用上限设置（单位 MB，0 表示不限制）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskGuardConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_max_database_mb")]
+    pub max_database_mb: u64,
+    #[serde(default = "default_max_logs_mb")]
+    pub max_logs_mb: u64,
+    #[serde(default = "default_max_backups_mb")]
+    pub max_backups_mb: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_max_database_mb() -> u64 {
+    1024
+}
+
+fn default_max_logs_mb() -> u64 {
+    200
+}
+
+fn default_max_backups_mb() -> u64 {
+    1024
+}
+
+impl Default for DiskGuardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            max_database_mb: default_max_database_mb(),
+            max_logs_mb: default_max_logs_mb(),
+            max_backups_mb: default_max_backups_mb(),
+        }
+    }
+}
+
+/// 检查对象
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiskTarget {
+    Database,
+    Logs,
+    Backups,
+}
+
+/// 单个对象的占用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskTargetUsage {
+    pub target: DiskTarget,
+    pub path: String,
+    pub usage_bytes: u64,
+    /// 上限（0 表示不限制）
+    pub limit_bytes: u64,
+}
+
+/// 超限告警（清理后发送）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskUsageWarning {
+    pub target: DiskTarget,
+    pub path: String,
+    /// 清理前的占用
+    pub usage_bytes: u64,
+    pub limit_bytes: u64,
+    pub freed_bytes: u64,
+    /// 删除的请求日志条数或文件数
+    pub removed: usize,
+    /// 清理后仍超过上限
+    pub still_over_limit: bool,
+}
+
+/// 设置日志目录（应用启动时调用）
+pub fn set_log_dir(dir: PathBuf) {
+    let _ = LOG_DIR.set(dir);
+}
+
+fn backup_dir() -> PathBuf {
+    get_app_config_dir().join("backups")
+}
+
+fn limit_bytes(mb: u64) -> u64 {
+    mb.saturating_mul(MB)
+}
+
+/// 当前占用
+pub fn usage(db: &Database) -> Result<Vec<DiskTargetUsage>, AppError> {
+    let config = crate::settings::get_settings().disk_guard;
+    let mut targets = vec![DiskTargetUsage {
+        target: DiskTarget::Database,
+        path: get_app_config_dir()
+            .join("cc-switch.db")
+            .display()
+            .to_string(),
+        usage_bytes: db.database_used_bytes()?,
+        limit_bytes: limit_bytes(config.max_database_mb),
+    }];
+    if let Some(dir) = LOG_DIR.get() {
+        targets.push(DiskTargetUsage {
+            target: DiskTarget::Logs,
+            path: dir.display().to_string(),
+            usage_bytes: dir_size(dir),
+            limit_bytes: limit_bytes(config.max_logs_mb),
+        });
+    }
+    let dir = backup_dir();
+    targets.push(DiskTargetUsage {
+        target: DiskTarget::Backups,
+        path: dir.display().to_string(),
+        usage_bytes: dir_size(&dir),
+        limit_bytes: limit_bytes(config.max_backups_mb),
+    });
+    Ok(targets)
+}
+
+/// 检查全部对象，超限时清理并发送告警，返回告警列表
+pub fn enforce(db: &Database) -> Result<Vec<DiskUsageWarning>, AppError> {
+    let config = crate::settings::get_settings().disk_guard;
+    let mut warnings = Vec::new();
+
+    if let Some(warning) = enforce_database(db, limit_bytes(config.max_database_mb))? {
+        warnings.push(warning);
+    }
+    if let Some(dir) = LOG_DIR.get() {
+        warnings.extend(enforce_dir(
+            DiskTarget::Logs,
+            dir,
+            limit_bytes(config.max_logs_mb),
+            0,
+        ));
+    }
+    warnings.extend(enforce_dir(
+        DiskTarget::Backups,
+        &backup_dir(),
+        limit_bytes(config.max_backups_mb),
+        1,
+    ));
+
+    for warning in &warnings {
+        log::warn!(
+            "[DiskGuard] {} 占用 {} 字节，超过上限 {} 字节，已清理 {} 项（释放 {} 字节）",
+            warning.path,
+            warning.usage_bytes,
+            warning.limit_bytes,
+            warning.removed,
+            warning.freed_bytes
+        );
+        events::publish(AppEvent::DiskUsageWarning(warning.clone()));
+    }
+    Ok(warnings)
+}
+
+fn enforce_database(db: &Database, limit: u64) -> Result<Option<DiskUsageWarning>, AppError> {
+    let before = db.database_used_bytes()?;
+    if limit == 0 || before <= limit {
+        return Ok(None);
+    }
+
+    let mut removed = 0;
+    let mut used = before;
+    for _ in 0..MAX_PRUNE_ROUNDS {
+        if used <= limit {
+            break;
+        }
+        let total = db.count_request_logs()?;
+        if total == 0 {
+            break;
+        }
+        let batch = (total * PRUNE_PERCENT / 100).max(PRUNE_MIN_ROWS);
+        removed += db.prune_oldest_request_logs(batch)?;
+        used = db.database_used_bytes()?;
+    }
+    if removed > 0 {
+        db.vacuum()?;
+        used = db.database_used_bytes()?;
+    }
+
+    Ok(Some(DiskUsageWarning {
+        target: DiskTarget::Database,
+        path: get_app_config_dir()
+            .join("cc-switch.db")
+            .display()
+            .to_string(),
+        usage_bytes: before,
+        limit_bytes: limit,
+        freed_bytes: before.saturating_sub(used),
+        removed,
+        still_over_limit: used > limit,
+    }))
+}
+
+/// 目录超限时按修改时间删除最旧的文件（保留最新的 `keep` 个）
+fn enforce_dir(
+    target: DiskTarget,
+    dir: &Path,
+    limit: u64,
+    keep: usize,
+) -> Option<DiskUsageWarning> {
+    let mut files = list_files(dir);
+    let before: u64 = files.iter().map(|(_, size, _)| size).sum();
+    if limit == 0 || before <= limit {
+        return None;
+    }
+
+    files.sort_by_key(|(_, _, modified)| *modified);
+    let removable = files.len().saturating_sub(keep);
+    let mut used = before;
+    let mut removed = 0;
+    for (path, size, _) in files.into_iter().take(removable) {
+        if used <= limit {
+            break;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => {
+                used -= size;
+                removed += 1;
+            }
+            Err(e) => log::warn!("[DiskGuard] 删除 {} 失败: {e}", path.display()),
+        }
+    }
+
+    Some(DiskUsageWarning {
+        target,
+        path: dir.display().to_string(),
+        usage_bytes: before,
+        limit_bytes: limit,
+        freed_bytes: before - used,
+        removed,
+        still_over_limit: used > limit,
+    })
+}
+
+/// 目录下的文件（不递归）：路径、大小、修改时间
+fn list_files(dir: &Path) -> Vec<(PathBuf, u64, Option<std::time::SystemTime>)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            metadata
+                .is_file()
+                .then(|| (entry.path(), metadata.len(), metadata.modified().ok()))
+        })
+        .collect()
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    list_files(dir).iter().map(|(_, size, _)| size).sum()
+}
+
+impl Database {
+    /// 数据库实际使用的字节数（不含空闲页）
+    pub fn database_used_bytes(&self) -> Result<u64, AppError> {
+        let conn = lock_conn!(self.conn);
+        let pragma = |name: &str| -> Result<i64, AppError> {
+            conn.query_row(&format!("PRAGMA {name}"), [], |row| row.get(0))
+                .map_err(AppError::from)
+        };
+        let pages = pragma("page_count")? - pragma("freelist_count")?;
+        Ok((pages.max(0) * pragma("page_size")?) as u64)
+    }
+
+    pub fn count_request_logs(&self) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row("SELECT COUNT(*) FROM proxy_request_logs", [], |row| {
+            row.get::<_, i64>(0)
+        })
+        .map(|count| count as usize)
+        .map_err(AppError::from)
+    }
+
+    /// 删除最早的 `limit` 条请求日志，返回删除条数
+    pub fn prune_oldest_request_logs(&self, limit: usize) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM proxy_request_logs WHERE request_id IN (
+                SELECT request_id FROM proxy_request_logs ORDER BY created_at, request_id LIMIT ?1
+            )",
+            params![limit as i64],
+        )
+        .map_err(AppError::from)
+    }
+
+    /// 回收空闲页，收缩数据库文件
+    pub fn vacuum(&self) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute_batch("VACUUM").map_err(AppError::from)
+    }
+}
+
+/// 后台检查循环
+pub async fn run(db: Arc<Database>) {
+    loop {
+        if crate::settings::get_settings().disk_guard.enabled {
+            let db = db.clone();
+            match tokio::task::spawn_blocking(move || enforce(&db)).await {
+                Ok(Err(e)) => log::warn!("[DiskGuard] 检查磁盘占用失败: {e}"),
+                Err(e) => log::warn!("[DiskGuard] 检查任务异常: {e}"),
+                Ok(Ok(_)) => {}
+            }
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prunes_oldest_files_and_keeps_newest() {
+        let dir = tempfile::tempdir().unwrap();
+        for (i, name) in ["a.db", "b.db", "c.db"].iter().enumerate() {
+            let path = dir.path().join(name);
+            fs::write(&path, vec![0u8; 1000]).unwrap();
+            let modified = std::time::SystemTime::UNIX_EPOCH + Duration::from_secs(1000 + i as u64);
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        }
+
+        let warning = enforce_dir(DiskTarget::Backups, dir.path(), 1500, 1).unwrap();
+        assert_eq!(warning.removed, 2);
+        assert_eq!(warning.freed_bytes, 2000);
+        assert!(!warning.still_over_limit);
+        assert!(dir.path().join("c.db").exists());
+        assert!(!dir.path().join("a.db").exists());
+
+        // 只剩保留的最新一份时不再删除
+        let warning = enforce_dir(DiskTarget::Backups, dir.path(), 500, 1).unwrap();
+        assert_eq!(warning.removed, 0);
+        assert!(warning.still_over_limit);
+        assert!(enforce_dir(DiskTarget::Backups, dir.path(), 0, 1).is_none());
+    }
+}
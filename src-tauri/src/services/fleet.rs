@@ -0,0 +1,133 @@
+//! 多应用总览
+//!
+//! 一次返回各应用类型的当前供应商、健康状态、今日用量与实时 TPS，
+//! 供仪表盘替代多次轮询。结果在服务端缓存 1 秒，突发的并发请求只查询一次。
+
+use std::time::{Duration, Instant};
+
+use chrono::{Local, TimeZone};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::database::Database;
+use crate::services::proxy::ProxyService;
+use crate::services::status_export::{app_status_entries, AppStatusEntry};
+use crate::services::usage_stats::AppUsageTotals;
+
+/// 缓存有效期
+const CACHE_TTL: Duration = Duration::from_secs(1);
+
+static CACHE: Lazy<Mutex<Option<(Instant, FleetSummary)>>> = Lazy::new(|| Mutex::new(None));
+
+/// 单个应用类型的汇总
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetAppSummary {
+    #[serde(flatten)]
+    pub status: AppStatusEntry,
+    /// 今日（本地时间 0 点起）经代理的用量
+    pub today: AppUsageTotals,
+    /// 当前 TPS（代理未运行或近期无请求时为 0）
+    pub tps: f64,
+}
+
+/// 总览
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetSummary {
+    pub generated_at: i64,
+    pub proxy_running: bool,
+    pub apps: Vec<FleetAppSummary>,
+}
+
+/// 本地时间今日 0 点的时间戳
+fn local_midnight() -> i64 {
+    let now = Local::now();
+    now.date_naive()
+        .and_hms_opt(0, 0, 0)
+        .and_then(|t| Local.from_local_datetime(&t).earliest())
+        .map(|t| t.timestamp())
+        .unwrap_or_else(|| now.timestamp() - 24 * 60 * 60)
+}
+
+fn build(
+    db: &Database,
+    proxy_running: bool,
+    app_tps: &std::collections::HashMap<String, f64>,
+) -> FleetSummary {
+    let mut usage = db
+        .get_usage_by_app_since(local_midnight())
+        .unwrap_or_else(|e| {
+            log::warn!("读取今日用量失败: {e}");
+            Default::default()
+        });
+
+    let apps = app_status_entries(db)
+        .into_iter()
+        .map(|status| FleetAppSummary {
+            today: usage.remove(&status.app_type).unwrap_or_default(),
+            tps: app_tps.get(&status.app_type).copied().unwrap_or(0.0),
+            status,
+        })
+        .collect();
+
+    FleetSummary {
+        generated_at: chrono::Utc::now().timestamp(),
+        proxy_running,
+        apps,
+    }
+}
+
+pub struct FleetService;
+
+impl FleetService {
+    /// 获取总览；缓存未过期时直接返回，持锁期间的并发调用会等待同一次查询结果
+    pub async fn summary(db: &Database, proxy_service: &ProxyService) -> FleetSummary {
+        let mut cache = CACHE.lock().await;
+        if let Some((at, summary)) = cache.as_ref() {
+            if at.elapsed() < CACHE_TTL {
+                return summary.clone();
+            }
+        }
+
+        let proxy_running = proxy_service.is_running().await;
+        let app_tps = proxy_service.get_app_tps().await;
+        let summary = build(db, proxy_running, &app_tps);
+        *cache = Some((Instant::now(), summary.clone()));
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+
+    #[test]
+    fn build_covers_every_app_type() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let app_tps = [("codex".to_string(), 12.5)].into_iter().collect();
+
+        let summary = build(&db, true, &app_tps);
+        let app_types: Vec<_> = summary
+            .apps
+            .iter()
+            .map(|a| a.status.app_type.as_str())
+            .collect();
+        assert_eq!(app_types, ["claude", "codex", "gemini"]);
+
+        let codex = &summary.apps[1];
+        assert_eq!(codex.tps, 12.5);
+        assert_eq!(codex.today.request_count, 0);
+        assert_eq!(summary.apps[0].tps, 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn midnight_is_not_in_the_future() {
+        let midnight = local_midnight();
+        let now = Local::now().timestamp();
+        assert!(midnight <= now && now - midnight <= 25 * 60 * 60);
+    }
+}
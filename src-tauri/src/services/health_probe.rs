@@ -0,0 +1,406 @@
+//! 可插拔的健康检查探测方式
+//!
+//! 健康检查服务通过 [`HealthProbe`] 执行单次探测，供应商可在 `meta.healthProbe` 中选择：
+//! - `sseCompletion`：发送 1 token 的流式补全（默认，消耗 token）
+//! - `modelsList`：请求模型列表接口，同时验证网络与密钥
+//! - `tcpConnect`：只建立 TCP 连接
+//! - `customHttp`：请求自定义地址并校验状态码
+//!
+//! 选择轻量探测时，定时检查平时只运行轻量探测，
+//! 每隔 `fullCheckIntervalMinutes`（默认 60 分钟）仍执行一次完整的流式检查。
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use once_cell::sync::Lazy;
+use reqwest::{Client, Method, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::proxy::auth_scheme::apply_auth_scheme;
+use crate::proxy::custom_headers::apply_custom_headers_to_request;
+use crate::proxy::providers::{get_adapter, AuthInfo};
+use crate::services::onboarding;
+use crate::services::stream_check::StreamCheckService;
+
+/// 最近一次完整流式检查的时间（按应用与供应商），重启后首次定时检查为完整检查
+static LAST_FULL_CHECK: Lazy<RwLock<HashMap<(String, String), i64>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+
+/// 探测方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProbeKind {
+    #[default]
+    SseCompletion,
+    ModelsList,
+    TcpConnect,
+    CustomHttp,
+}
+
+impl ProbeKind {
+    /// 是否消耗 token（计入探测预算）
+    pub fn consumes_tokens(self) -> bool {
+        self == Self::SseCompletion
+    }
+}
+
+/// 自定义 HTTP 探测
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomHttpProbeConfig {
+    /// 完整地址，或相对供应商 base_url 的路径（如 `/health`）
+    pub url: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// 视为健康的状态码（为空时接受任意 2xx）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expected_statuses: Vec<u16>,
+    /// 是否附带供应商的认证信息
+    #[serde(default)]
+    pub with_auth: bool,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+/// 供应商的健康检查探测设置（`meta.healthProbe`）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthProbeConfig {
+    #[serde(default)]
+    pub kind: ProbeKind,
+    /// 使用轻量探测时，完整流式检查的间隔（分钟，0 表示定时检查不再执行完整检查）
+    #[serde(default = "default_full_check_interval_minutes")]
+    pub full_check_interval_minutes: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_http: Option<CustomHttpProbeConfig>,
+}
+
+fn default_full_check_interval_minutes() -> u32 {
+    60
+}
+
+impl HealthProbeConfig {
+    pub fn of(provider: &Provider) -> Option<&Self> {
+        provider.meta.as_ref()?.health_probe.as_ref()
+    }
+}
+
+/// 单次探测的上下文
+pub struct ProbeContext<'a> {
+    pub app_type: &'a AppType,
+    pub provider: &'a Provider,
+    pub client: &'a Client,
+    /// 已按 TLS 设置改写的 base_url
+    pub base_url: &'a str,
+    pub auth: &'a AuthInfo,
+    pub model: &'a str,
+    pub timeout: Duration,
+}
+
+/// 单次探测结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeOutcome {
+    pub http_status: Option<u16>,
+    /// 实际检查的模型（不涉及模型的探测为空）
+    pub model: String,
+    /// 响应中带有的实际 token 消耗
+    pub tokens_used: Option<u64>,
+}
+
+/// 健康检查探测方式
+pub trait HealthProbe: Send + Sync {
+    fn kind(&self) -> ProbeKind;
+
+    /// 执行一次探测，失败时返回错误（由检查服务统一处理重试与结果）
+    fn probe<'a>(
+        &'a self,
+        ctx: &'a ProbeContext<'a>,
+    ) -> BoxFuture<'a, Result<ProbeOutcome, AppError>>;
+}
+
+/// 流式补全探测：只需收到首个 chunk
+pub struct SseCompletionProbe;
+
+impl HealthProbe for SseCompletionProbe {
+    fn kind(&self) -> ProbeKind {
+        ProbeKind::SseCompletion
+    }
+
+    fn probe<'a>(
+        &'a self,
+        ctx: &'a ProbeContext<'a>,
+    ) -> BoxFuture<'a, Result<ProbeOutcome, AppError>> {
+        Box::pin(StreamCheckService::check_completion_stream(
+            ctx.app_type,
+            ctx.provider,
+            ctx.client,
+            ctx.base_url,
+            ctx.auth,
+            ctx.model,
+        ))
+    }
+}
+
+/// 模型列表探测：请求 `/v1/models`，401/403 视为失败
+pub struct ModelsListProbe;
+
+impl HealthProbe for ModelsListProbe {
+    fn kind(&self) -> ProbeKind {
+        ProbeKind::ModelsList
+    }
+
+    fn probe<'a>(
+        &'a self,
+        ctx: &'a ProbeContext<'a>,
+    ) -> BoxFuture<'a, Result<ProbeOutcome, AppError>> {
+        Box::pin(async move {
+            let adapter = get_adapter(ctx.app_type);
+            let url = onboarding::models_url(ctx.base_url);
+            let mut request = adapter.add_auth_headers(ctx.client.get(&url), ctx.auth);
+            if *ctx.app_type == AppType::Claude {
+                request = request.header("anthropic-version", "2023-06-01");
+            }
+            let mut built = request
+                .build()
+                .map_err(|e| AppError::Message(e.to_string()))?;
+            apply_auth_scheme(ctx.provider, Some(ctx.auth), &mut built)?;
+            apply_custom_headers_to_request(ctx.provider, &mut built);
+
+            let response = ctx.client.execute(built).await.map_err(AppError::from)?;
+            check_status(response, &[]).await
+        })
+    }
+}
+
+/// TCP 连接探测：只验证网络可达，不发送请求
+pub struct TcpConnectProbe;
+
+impl HealthProbe for TcpConnectProbe {
+    fn kind(&self) -> ProbeKind {
+        ProbeKind::TcpConnect
+    }
+
+    fn probe<'a>(
+        &'a self,
+        ctx: &'a ProbeContext<'a>,
+    ) -> BoxFuture<'a, Result<ProbeOutcome, AppError>> {
+        Box::pin(async move {
+            let addr = tcp_address(ctx.base_url)?;
+            tokio::time::timeout(ctx.timeout, tokio::net::TcpStream::connect(&addr))
+                .await
+                .map_err(|_| AppError::Message(format!("连接 {addr} 超时")))?
+                .map_err(|e| AppError::Message(format!("连接 {addr} 失败: {e}")))?;
+            Ok(ProbeOutcome {
+                http_status: None,
+                model: String::new(),
+                tokens_used: None,
+            })
+        })
+    }
+}
+
+/// 自定义 HTTP 探测
+pub struct CustomHttpProbe {
+    pub config: CustomHttpProbeConfig,
+}
+
+impl HealthProbe for CustomHttpProbe {
+    fn kind(&self) -> ProbeKind {
+        ProbeKind::CustomHttp
+    }
+
+    fn probe<'a>(
+        &'a self,
+        ctx: &'a ProbeContext<'a>,
+    ) -> BoxFuture<'a, Result<ProbeOutcome, AppError>> {
+        Box::pin(async move {
+            let method = Method::from_bytes(self.config.method.trim().to_uppercase().as_bytes())
+                .map_err(|_| {
+                    AppError::Message(format!("无效的请求方法: {}", self.config.method))
+                })?;
+            let url = custom_url(ctx.base_url, &self.config.url);
+            let mut request = ctx.client.request(method, &url);
+            if self.config.with_auth {
+                request = get_adapter(ctx.app_type).add_auth_headers(request, ctx.auth);
+            }
+            let mut built = request
+                .build()
+                .map_err(|e| AppError::Message(e.to_string()))?;
+            if self.config.with_auth {
+                apply_auth_scheme(ctx.provider, Some(ctx.auth), &mut built)?;
+            }
+            apply_custom_headers_to_request(ctx.provider, &mut built);
+
+            let response = ctx.client.execute(built).await.map_err(AppError::from)?;
+            check_status(response, &self.config.expected_statuses).await
+        })
+    }
+}
+
+async fn check_status(
+    response: reqwest::Response,
+    expected: &[u16],
+) -> Result<ProbeOutcome, AppError> {
+    let status = response.status();
+    let healthy = if expected.is_empty() {
+        status.is_success()
+    } else {
+        expected.contains(&status.as_u16())
+    };
+    if !healthy || matches!(status, StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) {
+        return Err(AppError::UpstreamStatus {
+            status: status.as_u16(),
+            body: response.text().await.unwrap_or_default(),
+        });
+    }
+    Ok(ProbeOutcome {
+        http_status: Some(status.as_u16()),
+        model: String::new(),
+        tokens_used: None,
+    })
+}
+
+fn tcp_address(base_url: &str) -> Result<String, AppError> {
+    let url = reqwest::Url::parse(base_url)
+        .map_err(|e| AppError::Message(format!("无效的地址 {base_url}: {e}")))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| AppError::Message(format!("地址缺少主机名: {base_url}")))?;
+    let port = url.port_or_known_default().unwrap_or(443);
+    Ok(format!("{host}:{port}"))
+}
+
+fn custom_url(base_url: &str, url: &str) -> String {
+    let url = url.trim();
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return url.to_string();
+    }
+    format!(
+        "{}/{}",
+        base_url.trim_end_matches('/'),
+        url.trim_start_matches('/')
+    )
+}
+
+/// 按探测方式构造探测器（自定义探测未配置地址时退回模型列表探测）
+pub fn probe_for(kind: ProbeKind, provider: &Provider) -> Box<dyn HealthProbe> {
+    match kind {
+        ProbeKind::SseCompletion => Box::new(SseCompletionProbe),
+        ProbeKind::ModelsList => Box::new(ModelsListProbe),
+        ProbeKind::TcpConnect => Box::new(TcpConnectProbe),
+        ProbeKind::CustomHttp => match HealthProbeConfig::of(provider)
+            .and_then(|c| c.custom_http.clone())
+            .filter(|c| !c.url.trim().is_empty())
+        {
+            Some(config) => Box::new(CustomHttpProbe { config }),
+            None => Box::new(ModelsListProbe),
+        },
+    }
+}
+
+fn needs_full_check(config: &HealthProbeConfig, last_full: Option<i64>, now: i64) -> bool {
+    if config.kind.consumes_tokens() {
+        return true;
+    }
+    if config.full_check_interval_minutes == 0 {
+        return false;
+    }
+    last_full.is_none_or(|at| now - at >= config.full_check_interval_minutes as i64 * 60)
+}
+
+/// 定时检查应使用的探测方式：未设置时为完整流式检查，
+/// 选择轻量探测时仅在完整检查到期后返回流式检查
+pub fn scheduled_kind(app_type: &str, provider: &Provider, now: i64) -> ProbeKind {
+    let Some(config) = HealthProbeConfig::of(provider) else {
+        return ProbeKind::SseCompletion;
+    };
+    let last_full = LAST_FULL_CHECK
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&(app_type.to_string(), provider.id.clone()))
+        .copied();
+    if needs_full_check(config, last_full, now) {
+        ProbeKind::SseCompletion
+    } else {
+        config.kind
+    }
+}
+
+/// 记录完成了一次完整流式检查
+pub fn record_full_check(app_type: &str, provider_id: &str, now: i64) {
+    LAST_FULL_CHECK
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert((app_type.to_string(), provider_id.to_string()), now);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(kind: ProbeKind, interval: u32) -> HealthProbeConfig {
+        HealthProbeConfig {
+            kind,
+            full_check_interval_minutes: interval,
+            custom_http: None,
+        }
+    }
+
+    #[test]
+    fn full_check_runs_on_interval() {
+        let cheap = config(ProbeKind::TcpConnect, 60);
+        assert!(needs_full_check(&cheap, None, 1_000));
+        assert!(!needs_full_check(&cheap, Some(1_000), 1_000 + 59 * 60));
+        assert!(needs_full_check(&cheap, Some(1_000), 1_000 + 60 * 60));
+        assert!(!needs_full_check(
+            &config(ProbeKind::ModelsList, 0),
+            None,
+            0
+        ));
+        assert!(needs_full_check(
+            &config(ProbeKind::SseCompletion, 0),
+            Some(0),
+            0
+        ));
+    }
+
+    #[test]
+    fn builds_probe_addresses() {
+        assert_eq!(
+            tcp_address("https://relay.example/v1").unwrap(),
+            "relay.example:443"
+        );
+        assert_eq!(
+            tcp_address("http://127.0.0.1:8080").unwrap(),
+            "127.0.0.1:8080"
+        );
+        assert_eq!(
+            custom_url("https://relay.example/", "/health"),
+            "https://relay.example/health"
+        );
+        assert_eq!(
+            custom_url("https://relay.example", "https://status.example/ping"),
+            "https://status.example/ping"
+        );
+    }
+
+    #[test]
+    fn parses_provider_config() {
+        let config: HealthProbeConfig = serde_json::from_value(serde_json::json!({
+            "kind": "customHttp",
+            "customHttp": { "url": "/health", "expectedStatuses": [200, 204] }
+        }))
+        .unwrap();
+        assert_eq!(config.full_check_interval_minutes, 60);
+        let custom = config.custom_http.unwrap();
+        assert_eq!(custom.method, "GET");
+        assert!(!custom.with_auth);
+    }
+}
@@ -0,0 +1,303 @@
+//! 外发任务队列（至少投递一次）
+//!
+//! 告警等外发请求先写入 pending_jobs，再由后台任务投递，断网期间产生的告警在恢复后补发：
+//! - 投递失败按指数退避重试（30 秒起，最长 1 小时）
+//! - 连续失败 [`MAX_ATTEMPTS`] 次后标记为死信，可通过命令手动重试
+//! - 离线时暂停投递
+//!
+//! 目前支持的任务类型为 webhook（预算告警、健康检查失败）。
+//! 健康检查只在供应商由正常转为失败时告警，同一告警在冷却期内不重复写入。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Notify;
+
+use crate::database::{Database, PendingJob};
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::proxy::offline;
+use crate::services::startup_profile::{self, Subsystem};
+
+/// webhook 任务类型
+pub const JOB_KIND_WEBHOOK: &str = "webhook";
+
+/// 最大投递次数，超过后标记为死信
+pub const MAX_ATTEMPTS: u32 = 8;
+
+/// 首次重试的退避时间（秒）
+const BACKOFF_BASE_SECS: i64 = 30;
+
+/// 退避时间上限（秒）
+const BACKOFF_MAX_SECS: i64 = 3600;
+
+/// 轮询间隔（秒）
+const POLL_INTERVAL_SECS: u64 = 15;
+
+/// 单轮最多投递的任务数
+const BATCH_SIZE: u32 = 20;
+
+/// 同一告警（同类事件、同一供应商）两次写入的最小间隔（秒）
+const ALERT_COOLDOWN_SECS: i64 = 30 * 60;
+
+/// 单次投递超时（秒）
+const DELIVERY_TIMEOUT_SECS: u64 = 10;
+
+/// 已完成任务保留时长（秒）
+const DONE_RETENTION_SECS: i64 = 7 * 86_400;
+
+/// 入队后唤醒投递任务，避免等待下一轮轮询
+static WAKE: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// webhook 任务内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookJob {
+    pub url: String,
+    pub body: Value,
+}
+
+/// 第 attempts 次失败后的退避时间（秒）
+pub fn backoff_secs(attempts: u32) -> i64 {
+    let exp = attempts.saturating_sub(1).min(16);
+    (BACKOFF_BASE_SECS << exp).min(BACKOFF_MAX_SECS)
+}
+
+/// 写入一个 webhook 任务
+pub fn enqueue_webhook(db: &Database, url: &str, body: Value) -> Result<i64, AppError> {
+    let job = WebhookJob {
+        url: url.to_string(),
+        body,
+    };
+    let payload = serde_json::to_string(&job).map_err(|e| AppError::JsonSerialize { source: e })?;
+    let id = db.enqueue_job(JOB_KIND_WEBHOOK, &payload, chrono::Utc::now().timestamp())?;
+    WAKE.notify_one();
+    Ok(id)
+}
+
+/// 手动重试死信任务，返回任务是否存在
+pub fn retry_dead(db: &Database, id: i64) -> Result<bool, AppError> {
+    let found = db.retry_job(id, chrono::Utc::now().timestamp())?;
+    if found {
+        WAKE.notify_one();
+    }
+    Ok(found)
+}
+
+async fn deliver(client: &reqwest::Client, job: &PendingJob) -> Result<(), AppError> {
+    match job.kind.as_str() {
+        JOB_KIND_WEBHOOK => {
+            let webhook: WebhookJob = serde_json::from_str(&job.payload)
+                .map_err(|e| AppError::Message(format!("解析任务内容失败: {e}")))?;
+            let response = client.post(&webhook.url).json(&webhook.body).send().await?;
+            let status = response.status();
+            if !status.is_success() {
+                let body = response.text().await.unwrap_or_default();
+                return Err(AppError::UpstreamStatus {
+                    status: status.as_u16(),
+                    body,
+                });
+            }
+            Ok(())
+        }
+        other => Err(AppError::Message(format!("未知的任务类型: {other}"))),
+    }
+}
+
+/// 投递一轮已到期的任务
+async fn process_due(db: &Database, client: &reqwest::Client) {
+    let now = chrono::Utc::now().timestamp();
+    let jobs = match db.get_due_jobs(now, BATCH_SIZE) {
+        Ok(jobs) => jobs,
+        Err(e) => {
+            log::warn!("[Jobs] 读取待投递任务失败: {e}");
+            return;
+        }
+    };
+
+    for job in jobs {
+        let result = deliver(client, &job).await;
+        let now = chrono::Utc::now().timestamp();
+        let update = match result {
+            Ok(()) => db.mark_job_done(job.id, now),
+            Err(e) => {
+                let attempts = job.attempts + 1;
+                let next_attempt_at =
+                    (attempts < MAX_ATTEMPTS).then(|| now + backoff_secs(attempts));
+                if next_attempt_at.is_none() {
+                    log::warn!(
+                        "[Jobs] 任务 {} 投递失败 {attempts} 次，标记为死信: {e}",
+                        job.id
+                    );
+                } else {
+                    log::debug!("[Jobs] 任务 {} 投递失败（第 {attempts} 次）: {e}", job.id);
+                }
+                db.mark_job_failed(job.id, &e.to_string(), next_attempt_at, now)
+            }
+        };
+        if let Err(e) = update {
+            log::warn!("[Jobs] 更新任务 {} 状态失败: {e}", job.id);
+        }
+    }
+}
+
+/// 后台投递循环
+pub async fn run(db: Arc<Database>) {
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(DELIVERY_TIMEOUT_SECS))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            log::error!("[Jobs] 创建 HTTP 客户端失败，外发任务不会被投递: {e}");
+            return;
+        }
+    };
+
+    loop {
+        if !offline::is_offline() {
+            process_due(&db, &client).await;
+        }
+        let cutoff = chrono::Utc::now().timestamp() - DONE_RETENTION_SECS;
+        if let Err(e) = db.prune_done_jobs(cutoff) {
+            log::warn!("[Jobs] 清理已完成任务失败: {e}");
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(POLL_INTERVAL_SECS)) => {}
+            _ = WAKE.notified() => {}
+        }
+    }
+}
+
+/// 告警去重：健康检查只在转为失败时告警，同一告警在冷却期内只发送一次
+#[derive(Default)]
+struct AlertGate {
+    /// 最近一次检查失败的（应用类型, 供应商 ID）
+    failing: HashSet<(String, String)>,
+    /// 告警键 -> 上次写入时间
+    last_sent: HashMap<String, i64>,
+}
+
+impl AlertGate {
+    fn should_send(&mut self, event: &AppEvent, now: i64) -> bool {
+        if let AppEvent::HealthChanged(p) = event {
+            // 跳过（如离线）或取消的检查不代表供应商状态
+            if !p.result.is_conclusive() {
+                return false;
+            }
+            let key = (p.app_type.clone(), p.provider_id.clone());
+            if p.result.success {
+                self.failing.remove(&key);
+                return false;
+            }
+            // 维护窗口内的失败不告警，也不改变状态；已处于失败状态时不重复告警
+            if p.in_maintenance || !self.failing.insert(key) {
+                return false;
+            }
+        } else if !event.is_alert() {
+            return false;
+        }
+
+        let key = match event.scope() {
+            Some((app_type, provider_id)) => {
+                format!("{}:{app_type}:{provider_id}", event.kind().as_str())
+            }
+            None => event.kind().as_str().to_string(),
+        };
+        match self.last_sent.get(&key) {
+            Some(&sent_at) if now - sent_at < ALERT_COOLDOWN_SECS => false,
+            _ => {
+                self.last_sent.insert(key, now);
+                true
+            }
+        }
+    }
+}
+
+/// 监听事件总线，为告警事件写入 webhook 任务（未配置 webhook 地址时忽略）
+pub async fn run_alert_producer(db: Arc<Database>) {
+    let mut rx = events::subscribe();
+    let mut gate = AlertGate::default();
+    loop {
+        let event = match rx.recv().await {
+            Ok(event) => event,
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                log::warn!("[Jobs] 告警监听处理过慢，丢弃 {n} 条事件");
+                continue;
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+        };
+        let now = chrono::Utc::now().timestamp();
+        if !gate.should_send(&event, now) || !startup_profile::is_enabled(Subsystem::Alerting) {
+            continue;
+        }
+        let Some(url) = crate::settings::get_settings().alert_webhook_url else {
+            continue;
+        };
+        let body = serde_json::json!({
+            "event": event,
+            "firedAt": now,
+        });
+        if let Err(e) = enqueue_webhook(&db, &url, body) {
+            log::warn!("[Jobs] 写入告警任务失败: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::HealthChangedPayload;
+    use crate::services::stream_check::StreamCheckResult;
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        assert_eq!(backoff_secs(1), 30);
+        assert_eq!(backoff_secs(2), 60);
+        assert_eq!(backoff_secs(4), 240);
+        assert_eq!(backoff_secs(MAX_ATTEMPTS), BACKOFF_MAX_SECS);
+    }
+
+    fn health(success: bool) -> AppEvent {
+        let failed = StreamCheckResult::failed("down");
+        AppEvent::HealthChanged(HealthChangedPayload {
+            app_type: "claude".to_string(),
+            provider_id: "p1".to_string(),
+            result: StreamCheckResult { success, ..failed },
+            in_maintenance: false,
+        })
+    }
+
+    #[test]
+    fn alerts_only_on_transition_to_failed() {
+        let mut gate = AlertGate::default();
+        assert!(gate.should_send(&health(false), 0));
+        // 持续失败不重复告警
+        assert!(!gate.should_send(&health(false), 60));
+        assert!(!gate.should_send(&health(true), 120));
+        // 恢复后再次失败，但仍在冷却期内
+        assert!(!gate.should_send(&health(false), 180));
+        assert!(!gate.should_send(&health(true), 240));
+        assert!(gate.should_send(&health(false), ALERT_COOLDOWN_SECS + 1));
+    }
+
+    #[test]
+    fn ignores_skipped_checks() {
+        let skipped = AppEvent::HealthChanged(HealthChangedPayload {
+            app_type: "claude".to_string(),
+            provider_id: "p1".to_string(),
+            result: StreamCheckResult::skipped("offline"),
+            in_maintenance: false,
+        });
+        assert!(!skipped.is_alert());
+        let mut gate = AlertGate::default();
+        assert!(!gate.should_send(&skipped, 0));
+        // 跳过的检查不改变失败状态，之后的真实失败仍然告警
+        assert!(gate.should_send(&health(false), 60));
+    }
+}
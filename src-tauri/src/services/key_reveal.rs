@@ -0,0 +1,381 @@
+//! 显示与复制供应商 Key 前的系统认证
+//!
+//! 供应商列表返回给前端时 Key 已替换为 [`REDACTED_KEY`]，保存时由后端按原值还原。
+//! 前端"显示 Key"与"复制 Key"经此处取回明文，取回前要求用户验证本人身份：
+//! macOS 为 LocalAuthentication（Touch ID 或登录密码），Windows 为 Windows Hello
+//! （未配置时改为输入登录密码），
+//! Linux 为 polkit 的 `auth_self` 动作（输入本人密码，不需要管理员）。
+//! 认证通过后一分钟内的再次取回不再重复询问，每次取回都写入审计日志。
+
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::app_config::AppType;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::proxy::providers::get_adapter;
+
+/// 返回给前端的供应商配置中替代 Key 的占位符
+pub const REDACTED_KEY: &str = "[REDACTED]";
+
+const AUDIT_CATEGORY: &str = "keyReveal";
+
+/// 认证通过后免于再次认证的时长
+const REAUTH_WINDOW: Duration = Duration::from_secs(60);
+
+static LAST_VERIFIED: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// 取回 Key 的用途
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RevealAction {
+    Reveal,
+    Copy,
+}
+
+impl RevealAction {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Reveal => "显示",
+            Self::Copy => "复制",
+        }
+    }
+}
+
+/// 按字段名判断是否为凭据（`*_KEY` / `*_TOKEN` / `apiKey` 等）
+fn is_secret_field(name: &str) -> bool {
+    let name = name.to_ascii_lowercase().replace(['_', '-'], "");
+    ["key", "token", "secret", "password"]
+        .iter()
+        .any(|suffix| name.ends_with(suffix))
+}
+
+fn redact_value(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (name, child) in map.iter_mut() {
+                match child {
+                    Value::String(text) if is_secret_field(name) && !text.is_empty() => {
+                        *text = REDACTED_KEY.to_string();
+                    }
+                    _ => redact_value(child),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        _ => {}
+    }
+}
+
+/// 隐藏供应商配置中的 Key（用于返回给前端的列表）
+pub fn redact_provider(provider: &mut Provider) {
+    redact_value(&mut provider.settings_config);
+}
+
+/// 将占位符还原为 `stored` 中同一位置的原值，返回是否仍有无法还原的占位符
+fn restore_value(value: &mut Value, stored: Option<&Value>) -> bool {
+    match value {
+        Value::String(text) if text == REDACTED_KEY => match stored {
+            Some(Value::String(original)) => {
+                *text = original.clone();
+                false
+            }
+            _ => true,
+        },
+        Value::Object(map) => {
+            let mut unresolved = false;
+            for (name, child) in map.iter_mut() {
+                unresolved |= restore_value(child, stored.and_then(|s| s.get(name)));
+            }
+            unresolved
+        }
+        Value::Array(items) => {
+            let mut unresolved = false;
+            for (i, child) in items.iter_mut().enumerate() {
+                unresolved |= restore_value(child, stored.and_then(|s| s.get(i)));
+            }
+            unresolved
+        }
+        _ => false,
+    }
+}
+
+/// 保存前把前端传回的占位符还原为 `source`（原供应商）中的 Key
+pub fn restore_redacted(
+    provider: &mut Provider,
+    source: Option<&Provider>,
+) -> Result<(), AppError> {
+    let stored = source.map(|p| &p.settings_config);
+    if restore_value(&mut provider.settings_config, stored) {
+        return Err(AppError::localized(
+            "keyReveal.unresolved",
+            "Key 已隐藏且无法还原，请重新填写 Key",
+            "The key is hidden and cannot be restored; please enter the key again",
+        ));
+    }
+    Ok(())
+}
+
+fn within_window(last: Option<Instant>, now: Instant) -> bool {
+    last.is_some_and(|at| now.saturating_duration_since(at) < REAUTH_WINDOW)
+}
+
+/// PowerShell 脚本退出码：Windows Hello 未配置或不可用
+#[cfg(target_os = "windows")]
+const HELLO_UNAVAILABLE: i32 = 3;
+
+#[cfg(target_os = "windows")]
+fn run_powershell(script: &str, interactive: bool) -> Result<Option<i32>, AppError> {
+    use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+    let mut command = Command::new("powershell");
+    command.arg("-NoProfile");
+    // NonInteractive 模式下 PromptForCredential 不会弹出对话框
+    if !interactive {
+        command.arg("-NonInteractive");
+    }
+    // 以 UTF-16LE + Base64 传递脚本，避免命令行对引号的转义问题
+    let encoded: Vec<u8> = script.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    let status = command
+        .args(["-EncodedCommand", &BASE64.encode(encoded)])
+        .creation_flags(CREATE_NO_WINDOW)
+        .status()
+        .map_err(|e| AppError::Message(format!("无法调用系统认证: {e}")))?;
+    Ok(status.code())
+}
+
+#[cfg(target_os = "windows")]
+fn verify_user(reason: &str) -> Result<bool, AppError> {
+    let reason = reason.replace('\'', "''");
+    // 经 PowerShell 调用 WinRT 的 UserConsentVerifier（Windows Hello）
+    let hello = format!(
+        "Add-Type -AssemblyName System.Runtime.WindowsRuntime; \
+         $asTask = ([System.WindowsRuntimeSystemExtensions].GetMethods() | Where-Object {{ \
+           $_.Name -eq 'AsTask' -and $_.GetParameters().Count -eq 1 -and \
+           $_.GetParameters()[0].ParameterType.Name -eq 'IAsyncOperation`1' }})[0]; \
+         function Await($op, $type) {{ \
+           $task = $asTask.MakeGenericMethod($type).Invoke($null, @($op)); \
+           $task.Wait(); $task.Result }}; \
+         $verifier = [Windows.Security.Credentials.UI.UserConsentVerifier, \
+           Windows.Security.Credentials.UI, ContentType = WindowsRuntime]; \
+         $availability = [Windows.Security.Credentials.UI.UserConsentVerifierAvailability, \
+           Windows.Security.Credentials.UI, ContentType = WindowsRuntime]; \
+         $result = [Windows.Security.Credentials.UI.UserConsentVerificationResult, \
+           Windows.Security.Credentials.UI, ContentType = WindowsRuntime]; \
+         if ((Await ($verifier::CheckAvailabilityAsync()) $availability) -ne 'Available') {{ \
+           exit {HELLO_UNAVAILABLE} }}; \
+         if ((Await ($verifier::RequestVerificationAsync('{reason}')) $result) -eq 'Verified') {{ \
+           exit 0 }} else {{ exit 1 }}"
+    );
+    match run_powershell(&hello, false)? {
+        Some(0) => return Ok(true),
+        Some(HELLO_UNAVAILABLE) => {
+            log::info!("[KeyReveal] Windows Hello 不可用，改为验证登录密码");
+        }
+        _ => return Ok(false),
+    }
+
+    // 未配置 Windows Hello：弹出凭据对话框，用 LogonUser 校验密码，
+    // 并确认登录的是当前用户（比较 SID）；Microsoft 账户以邮箱登录时域名须为空
+    let credential = format!(
+        "$cred = $Host.UI.PromptForCredential('CC Switch', '{reason}', \
+           \"$env:USERDOMAIN\\$env:USERNAME\", ''); \
+         if ($null -eq $cred) {{ exit 1 }}; \
+         Add-Type -Namespace CcSwitch -Name Logon -MemberDefinition '\
+           [DllImport(\"advapi32.dll\", SetLastError = true, CharSet = CharSet.Unicode)] \
+           public static extern bool LogonUser(string user, string domain, string password, \
+             int logonType, int logonProvider, out IntPtr token); \
+           [DllImport(\"kernel32.dll\")] public static extern bool CloseHandle(IntPtr handle);'; \
+         $net = $cred.GetNetworkCredential(); \
+         $domain = if ($net.Domain) {{ $net.Domain }} \
+           elseif ($net.UserName -like '*@*') {{ [NullString]::Value }} else {{ '.' }}; \
+         $token = [IntPtr]::Zero; \
+         if (-not [CcSwitch.Logon]::LogonUser($net.UserName, $domain, $net.Password, \
+           2, 0, [ref]$token)) {{ exit 1 }}; \
+         try {{ \
+           $user = (New-Object System.Security.Principal.WindowsIdentity($token)).User; \
+           $current = [System.Security.Principal.WindowsIdentity]::GetCurrent().User; \
+           if ($user -eq $current) {{ exit 0 }} else {{ exit 1 }} \
+         }} finally {{ [CcSwitch.Logon]::CloseHandle($token) | Out-Null }}"
+    );
+    Ok(run_powershell(&credential, true)? == Some(0))
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "LocalAuthentication", kind = "framework")]
+extern "C" {}
+
+#[cfg(target_os = "macos")]
+fn verify_user(reason: &str) -> Result<bool, AppError> {
+    use block2::RcBlock;
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyObject, Bool};
+    use objc2::{class, msg_send, msg_send_id};
+    use objc2_foundation::NSString;
+
+    /// LAPolicyDeviceOwnerAuthentication：Touch ID，或回退到登录密码
+    const POLICY_DEVICE_OWNER_AUTHENTICATION: isize = 2;
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let reply = RcBlock::new(move |success: Bool, _error: *mut AnyObject| {
+        let _ = tx.send(success.as_bool());
+    });
+    let reason = NSString::from_str(reason);
+    // SAFETY: LAContext 由 LocalAuthentication 框架提供，方法签名与
+    // `-evaluatePolicy:localizedReason:reply:` 一致；回调在私有队列中执行
+    let context: Retained<AnyObject> = unsafe { msg_send_id![class!(LAContext), new] };
+    unsafe {
+        let _: () = msg_send![
+            &*context,
+            evaluatePolicy: POLICY_DEVICE_OWNER_AUTHENTICATION,
+            localizedReason: &*reason,
+            reply: &*reply
+        ];
+    }
+    // 等待回调期间保持 context 存活
+    let verified = rx
+        .recv()
+        .map_err(|_| AppError::Message("系统认证被中断".to_string()))?;
+    drop(context);
+    Ok(verified)
+}
+
+/// 安装包提供的 polkit 动作（`auth_self`：验证本人密码）
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const POLKIT_ACTION: &str = "com.ccswitch.desktop.reveal-key";
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn verify_user(_reason: &str) -> Result<bool, AppError> {
+    // pkcheck 由 polkit 代理弹出认证对话框：0 为通过，1 为未授权，2 为用户取消，
+    // 127 为出错（如动作未注册）
+    let output = Command::new("pkcheck")
+        .args(["--action-id", POLKIT_ACTION, "--allow-user-interaction"])
+        .args(["--process", &std::process::id().to_string()])
+        .output()
+        .map_err(|e| AppError::Message(format!("无法调用 polkit 认证（pkcheck）: {e}")))?;
+    match output.status.code() {
+        Some(0) => Ok(true),
+        Some(1 | 2) => Ok(false),
+        _ => {
+            // 未通过安装包安装（如 AppImage）时动作未注册，回退到 pkexec 的管理员认证
+            log::warn!(
+                "[KeyReveal] polkit 动作 {POLKIT_ACTION} 不可用，回退到 pkexec: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            let status = Command::new("pkexec")
+                .arg("true")
+                .status()
+                .map_err(|e| AppError::Message(format!("无法调用 polkit 认证（pkexec）: {e}")))?;
+            Ok(status.success())
+        }
+    }
+}
+
+fn authenticate(reason: &str) -> Result<(), AppError> {
+    // 认证对话框期间不持有锁，其他取回请求不会被阻塞在锁上
+    let last = *LAST_VERIFIED.lock().unwrap_or_else(|e| e.into_inner());
+    if within_window(last, Instant::now()) {
+        return Ok(());
+    }
+    if !verify_user(reason)? {
+        return Err(AppError::localized(
+            "keyReveal.denied",
+            "系统认证未通过，已取消",
+            "System authentication was not completed",
+        ));
+    }
+    *LAST_VERIFIED.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+    Ok(())
+}
+
+/// 通过系统认证后返回供应商的 Key（阻塞直到认证完成）
+pub fn reveal(
+    db: &Database,
+    app_type: AppType,
+    provider_id: &str,
+    action: RevealAction,
+) -> Result<String, AppError> {
+    let provider = db
+        .get_provider_by_id(provider_id, app_type.as_str())?
+        .ok_or_else(|| AppError::Message(format!("供应商 {provider_id} 不存在")))?;
+    let key = get_adapter(&app_type)
+        .extract_auth(&provider)
+        .map(|auth| auth.api_key)
+        .filter(|key| !key.is_empty())
+        .ok_or_else(|| AppError::Message(format!("供应商 {} 未配置 Key", provider.name)))?;
+
+    authenticate(&format!(
+        "cc-switch 需要验证身份以{}「{}」的 Key",
+        action.label(),
+        provider.name
+    ))?;
+
+    if let Err(e) = db.record_audit(
+        AUDIT_CATEGORY,
+        Some(app_type.as_str()),
+        Some(&provider.id),
+        &format!("{}了供应商 {} 的 Key", action.label(), provider.name),
+        Some(&json!({ "action": action })),
+    ) {
+        log::warn!("[KeyReveal] 写入审计日志失败: {e}");
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_and_restores_keys() {
+        let stored = Provider::with_id(
+            "p".into(),
+            "P".into(),
+            json!({
+                "env": {
+                    "ANTHROPIC_AUTH_TOKEN": "sk-secret",
+                    "ANTHROPIC_BASE_URL": "https://relay.example"
+                },
+                "auth": { "OPENAI_API_KEY": "" }
+            }),
+            None,
+        );
+        let mut listed = stored.clone();
+        redact_provider(&mut listed);
+        assert_eq!(
+            listed.settings_config["env"]["ANTHROPIC_AUTH_TOKEN"],
+            REDACTED_KEY
+        );
+        assert_eq!(
+            listed.settings_config["env"]["ANTHROPIC_BASE_URL"],
+            "https://relay.example"
+        );
+        assert_eq!(listed.settings_config["auth"]["OPENAI_API_KEY"], "");
+
+        let mut edited = listed.clone();
+        edited.settings_config["env"]["ANTHROPIC_BASE_URL"] = json!("https://new.example");
+        restore_redacted(&mut edited, Some(&stored)).unwrap();
+        assert_eq!(
+            edited.settings_config["env"]["ANTHROPIC_AUTH_TOKEN"],
+            "sk-secret"
+        );
+
+        // 没有原值可还原时拒绝保存占位符
+        let mut copied = listed.clone();
+        assert!(restore_redacted(&mut copied, None).is_err());
+    }
+
+    #[test]
+    fn reauth_window_expires() {
+        let now = Instant::now();
+        assert!(!within_window(None, now));
+        assert!(within_window(Some(now), now + Duration::from_secs(30)));
+        assert!(!within_window(Some(now), now + REAUTH_WINDOW));
+    }
+}
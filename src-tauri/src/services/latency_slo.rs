@@ -0,0 +1,354 @@
+//! 供应商延迟 SLO
+//!
+//! 供应商可在 `ProviderMeta.latencySlo` 中声明延迟目标，例如"7 天内 95% 的请求在 3 秒内完成"。
+//! 根据代理请求日志计算达标率与错误预算：
+//! - 达标请求：2xx 且延迟低于阈值（流式请求按首 token 时间计算）
+//! - 除 429 外的 4xx 多为请求本身的问题，不计入统计，也不消耗错误预算
+//! - 错误预算：`1 - 目标百分比`，剩余预算 = 1 - 窗口内不达标占比 / 错误预算
+//! - 燃烧率：最近 1 小时 / 6 小时的不达标占比 / 错误预算（1 表示恰好在窗口结束时用完）
+//!
+//! 1 小时与 6 小时燃烧率同时达到告警倍数，或预算已用尽时，
+//! 后台检查发送 `slo-burn-rate-warning` 事件（恢复前每个供应商只告警一次）。
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::services::startup_profile::{self, Subsystem};
+
+/// 后台检查间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// 短窗口内请求少于该数时不计算燃烧率
+const MIN_BURN_REQUESTS: u64 = 10;
+const HOUR_SECS: i64 = 60 * 60;
+const DAY_SECS: i64 = 24 * HOUR_SECS;
+
+/// 正在告警的（应用, 供应商），恢复后移除
+static ALERTING: Lazy<Mutex<HashSet<(String, String)>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// 延迟 SLO 定义
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencySlo {
+    /// 延迟阈值（毫秒）
+    #[serde(default = "default_threshold_ms")]
+    pub threshold_ms: u64,
+    /// 达标请求的目标百分比
+    #[serde(default = "default_target_percent")]
+    pub target_percent: f64,
+    /// 统计窗口（天）
+    #[serde(default = "default_window_days")]
+    pub window_days: u32,
+    /// 燃烧率达到该倍数时告警
+    #[serde(default = "default_alert_burn_rate")]
+    pub alert_burn_rate: f64,
+}
+
+fn default_threshold_ms() -> u64 {
+    3000
+}
+
+fn default_target_percent() -> f64 {
+    95.0
+}
+
+fn default_window_days() -> u32 {
+    7
+}
+
+fn default_alert_burn_rate() -> f64 {
+    2.0
+}
+
+impl Default for LatencySlo {
+    fn default() -> Self {
+        Self {
+            threshold_ms: default_threshold_ms(),
+            target_percent: default_target_percent(),
+            window_days: default_window_days(),
+            alert_burn_rate: default_alert_burn_rate(),
+        }
+    }
+}
+
+impl LatencySlo {
+    /// 校验 SLO 定义
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=600_000).contains(&self.threshold_ms) {
+            return Err("延迟阈值必须在 1 到 600000 毫秒之间".to_string());
+        }
+        if !(self.target_percent > 0.0 && self.target_percent < 100.0) {
+            return Err("目标百分比必须大于 0 且小于 100".to_string());
+        }
+        if !(1..=90).contains(&self.window_days) {
+            return Err("统计窗口必须在 1 到 90 天之间".to_string());
+        }
+        if !(self.alert_burn_rate > 0.0 && self.alert_burn_rate.is_finite()) {
+            return Err("告警燃烧率必须大于 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// 错误预算（允许不达标的请求占比）
+    fn error_budget(&self) -> f64 {
+        1.0 - self.target_percent / 100.0
+    }
+}
+
+/// 一段时间内的请求数与达标请求数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SloCounts {
+    pub total: u64,
+    pub good: u64,
+}
+
+impl SloCounts {
+    fn bad_fraction(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.total.saturating_sub(self.good) as f64 / self.total as f64
+        }
+    }
+}
+
+/// 计算结果（纯计算部分）
+#[derive(Debug, Clone, PartialEq)]
+pub struct SloEvaluation {
+    pub compliance_percent: Option<f64>,
+    pub budget_remaining_percent: f64,
+    pub burn_rate_1h: Option<f64>,
+    pub burn_rate_6h: Option<f64>,
+    pub projected_exhaustion_at: Option<i64>,
+    pub alerting: bool,
+}
+
+/// 供应商的 SLO 状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SloStatus {
+    pub app_type: String,
+    pub provider_id: String,
+    pub provider_name: String,
+    pub slo: LatencySlo,
+    /// 窗口内的请求数
+    pub total: u64,
+    /// 窗口内的达标请求数
+    pub good: u64,
+    /// 达标率（窗口内没有请求时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compliance_percent: Option<f64>,
+    /// 剩余错误预算百分比（用尽后为负数）
+    pub budget_remaining_percent: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burn_rate_1h: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burn_rate_6h: Option<f64>,
+    /// 按当前燃烧率预计预算用尽的时间（Unix 秒，不会用尽时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub projected_exhaustion_at: Option<i64>,
+    pub alerting: bool,
+}
+
+fn burn_rate(slo: &LatencySlo, counts: SloCounts) -> Option<f64> {
+    (counts.total >= MIN_BURN_REQUESTS).then(|| counts.bad_fraction() / slo.error_budget())
+}
+
+/// 根据窗口、最近 1 小时与 6 小时的统计计算预算与燃烧率
+pub fn evaluate(
+    slo: &LatencySlo,
+    window: SloCounts,
+    last_1h: SloCounts,
+    last_6h: SloCounts,
+    now: i64,
+) -> SloEvaluation {
+    let remaining = 1.0 - window.bad_fraction() / slo.error_budget();
+    let burn_rate_1h = burn_rate(slo, last_1h);
+    let burn_rate_6h = burn_rate(slo, last_6h);
+
+    let exhausted = remaining <= 0.0;
+    // 燃烧率不超过 1 时，窗口内的不达标占比不会超出预算
+    let projected_exhaustion_at = if exhausted {
+        Some(now)
+    } else {
+        burn_rate_6h.filter(|rate| *rate > 1.0).map(|rate| {
+            let window_secs = slo.window_days as f64 * DAY_SECS as f64;
+            now + (remaining * window_secs / rate) as i64
+        })
+    };
+    let burning = matches!(
+        (burn_rate_1h, burn_rate_6h),
+        (Some(short), Some(long)) if short >= slo.alert_burn_rate && long >= slo.alert_burn_rate
+    );
+
+    SloEvaluation {
+        compliance_percent: (window.total > 0)
+            .then(|| window.good as f64 / window.total as f64 * 100.0),
+        budget_remaining_percent: remaining * 100.0,
+        burn_rate_1h,
+        burn_rate_6h,
+        projected_exhaustion_at,
+        alerting: exhausted || burning,
+    }
+}
+
+/// 各供应商的 SLO 状态（只包含声明了 SLO 的供应商）
+pub fn get_statuses(db: &Database, app_type: Option<&str>) -> Result<Vec<SloStatus>, AppError> {
+    let now = chrono::Utc::now().timestamp();
+    let mut statuses = Vec::new();
+    for app in ["claude", "codex", "gemini"] {
+        if app_type.is_some_and(|a| a != app) {
+            continue;
+        }
+        for (provider_id, provider) in db.get_all_providers(app)? {
+            let Some(slo) = provider.meta.as_ref().and_then(|m| m.latency_slo.clone()) else {
+                continue;
+            };
+            let counts = |since: i64| db.get_slo_counts(&provider_id, app, since, slo.threshold_ms);
+            let window = counts(now - slo.window_days as i64 * DAY_SECS)?;
+            let evaluation = evaluate(
+                &slo,
+                window,
+                counts(now - HOUR_SECS)?,
+                counts(now - 6 * HOUR_SECS)?,
+                now,
+            );
+            statuses.push(SloStatus {
+                app_type: app.to_string(),
+                provider_id,
+                provider_name: provider.name,
+                slo,
+                total: window.total,
+                good: window.good,
+                compliance_percent: evaluation.compliance_percent,
+                budget_remaining_percent: evaluation.budget_remaining_percent,
+                burn_rate_1h: evaluation.burn_rate_1h,
+                burn_rate_6h: evaluation.burn_rate_6h,
+                projected_exhaustion_at: evaluation.projected_exhaustion_at,
+                alerting: evaluation.alerting,
+            });
+        }
+    }
+    Ok(statuses)
+}
+
+/// 检查一次 SLO，对燃烧过快的供应商发送告警（恢复前只告警一次）
+pub fn check_alerts(db: &Database) -> Result<usize, AppError> {
+    let statuses = get_statuses(db, None)?;
+    let mut alerting = ALERTING.lock().unwrap_or_else(|e| e.into_inner());
+    let mut sent = 0;
+    for status in statuses {
+        let key = (status.app_type.clone(), status.provider_id.clone());
+        if !status.alerting {
+            alerting.remove(&key);
+            continue;
+        }
+        if !alerting.insert(key) {
+            continue;
+        }
+        log::warn!(
+            "[SLO] {}/{} 错误预算剩余 {:.1}%（1h 燃烧率 {:?}，6h 燃烧率 {:?}）",
+            status.app_type,
+            status.provider_id,
+            status.budget_remaining_percent,
+            status.burn_rate_1h,
+            status.burn_rate_6h
+        );
+        events::publish(AppEvent::SloBurnRateWarning(status));
+        sent += 1;
+    }
+    Ok(sent)
+}
+
+/// 后台定期检查 SLO 告警（关闭告警子系统时跳过）
+pub async fn run(db: Arc<Database>) {
+    loop {
+        if startup_profile::is_enabled(Subsystem::Alerting) {
+            let db = db.clone();
+            match tokio::task::spawn_blocking(move || check_alerts(&db)).await {
+                Ok(Err(e)) => log::warn!("[SLO] 检查延迟 SLO 失败: {e}"),
+                Err(e) => log::warn!("[SLO] 检查任务异常: {e}"),
+                Ok(Ok(_)) => {}
+            }
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(total: u64, good: u64) -> SloCounts {
+        SloCounts { total, good }
+    }
+
+    #[test]
+    fn validates_slo_definition() {
+        assert!(LatencySlo::default().validate().is_ok());
+        for invalid in [
+            LatencySlo {
+                threshold_ms: 0,
+                ..Default::default()
+            },
+            LatencySlo {
+                target_percent: 100.0,
+                ..Default::default()
+            },
+            LatencySlo {
+                window_days: 0,
+                ..Default::default()
+            },
+            LatencySlo {
+                alert_burn_rate: 0.0,
+                ..Default::default()
+            },
+        ] {
+            assert!(invalid.validate().is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn fast_burn_projects_early_exhaustion() {
+        let slo = LatencySlo::default();
+        // 窗口内不达标 2%（预算 5%），最近 6 小时不达标 20%，燃烧率 4
+        let eval = evaluate(&slo, counts(1000, 980), counts(50, 40), counts(100, 80), 0);
+        assert!((eval.budget_remaining_percent - 60.0).abs() < 1e-6);
+        assert!((eval.burn_rate_6h.unwrap() - 4.0).abs() < 1e-6);
+        assert!(eval.alerting);
+        // 剩余 60% 的 7 天预算，按 4 倍速消耗约 1.05 天用尽
+        let expected = (0.6 * 7.0 * DAY_SECS as f64 / 4.0) as i64;
+        assert!((eval.projected_exhaustion_at.unwrap() - expected).abs() <= 1);
+    }
+
+    #[test]
+    fn slow_burn_and_sparse_traffic_do_not_alert() {
+        let slo = LatencySlo::default();
+        let eval = evaluate(
+            &slo,
+            counts(1000, 990),
+            counts(100, 98),
+            counts(200, 196),
+            0,
+        );
+        assert_eq!(eval.projected_exhaustion_at, None);
+        assert!(!eval.alerting);
+
+        // 短窗口请求过少时不计算燃烧率
+        let eval = evaluate(&slo, counts(5, 0), counts(5, 0), counts(5, 0), 100);
+        assert_eq!(eval.burn_rate_1h, None);
+        assert!(eval.alerting, "窗口内预算已用尽");
+        assert_eq!(eval.projected_exhaustion_at, Some(100));
+
+        let empty = evaluate(&slo, counts(0, 0), counts(0, 0), counts(0, 0), 0);
+        assert_eq!(empty.compliance_percent, None);
+        assert!(!empty.alerting);
+    }
+}
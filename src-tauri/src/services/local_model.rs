@@ -0,0 +1,271 @@
+//! 本地模型服务进程管理与原生健康检查
+//!
+//! 对 `managed` 的本地供应商，由 cc-switch 启动 / 停止 Ollama 或 llama.cpp 服务进程；
+//! 应用退出时统一停止。健康检查使用各运行时的原生接口而非补全请求：
+//! - Ollama：`GET /api/tags`，并确认配置的模型已拉取
+//! - llama.cpp：`GET /health`
+
+use std::collections::HashMap;
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+use crate::proxy::providers::{LocalModelConfig, LocalRuntime};
+
+/// 本地服务进程：provider_id -> 子进程
+static PROCESSES: Lazy<Mutex<HashMap<String, Child>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 原生健康检查超时（秒）
+const NATIVE_HEALTH_TIMEOUT_SECS: u64 = 5;
+
+/// 本地服务状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalModelStatus {
+    pub provider_id: String,
+    pub runtime: LocalRuntime,
+    pub endpoint: String,
+    /// 是否由 cc-switch 管理进程
+    pub managed: bool,
+    /// 由 cc-switch 启动的进程是否仍在运行
+    pub process_running: bool,
+    pub pid: Option<u32>,
+    /// 原生接口是否可达
+    pub reachable: bool,
+    pub message: String,
+}
+
+pub struct LocalModelService;
+
+impl LocalModelService {
+    /// 构建启动命令
+    fn build_command(config: &LocalModelConfig) -> Result<Command, AppError> {
+        let program = config
+            .command
+            .as_deref()
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .unwrap_or(config.runtime.default_command());
+        let url = url::Url::parse(&config.endpoint())
+            .map_err(|e| AppError::Message(format!("无效的本地服务地址: {e}")))?;
+        let host = url.host_str().unwrap_or("127.0.0.1").to_string();
+        let port = url.port_or_known_default().unwrap_or(80);
+
+        let mut command = Command::new(program);
+        match config.runtime {
+            LocalRuntime::Ollama => {
+                command
+                    .arg("serve")
+                    .env("OLLAMA_HOST", format!("{host}:{port}"));
+            }
+            LocalRuntime::LlamaCpp => {
+                let model = config
+                    .model
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|m| !m.is_empty())
+                    .ok_or_else(|| {
+                        AppError::Message("llama.cpp 需要配置模型文件路径".to_string())
+                    })?;
+                command
+                    .args(["--host", &host, "--port", &port.to_string()])
+                    .args(["-m", model]);
+            }
+        }
+        command
+            .args(&config.args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        Ok(command)
+    }
+
+    /// 启动本地服务进程（已在运行时直接返回）
+    pub fn start(provider_id: &str, config: &LocalModelConfig) -> Result<u32, AppError> {
+        if !config.managed {
+            return Err(AppError::Message(
+                "该本地供应商未启用进程管理，请手动启动服务".to_string(),
+            ));
+        }
+
+        let mut processes = PROCESSES.lock().map_err(AppError::from)?;
+        if let Some(child) = processes.get_mut(provider_id) {
+            if matches!(child.try_wait(), Ok(None)) {
+                return Ok(child.id());
+            }
+            processes.remove(provider_id);
+        }
+
+        let child = Self::build_command(config)?
+            .spawn()
+            .map_err(|e| AppError::Message(format!("启动本地模型服务失败: {e}")))?;
+        let pid = child.id();
+        log::info!(
+            "已启动本地模型服务 {:?} (provider={provider_id}, pid={pid})",
+            config.runtime
+        );
+        processes.insert(provider_id.to_string(), child);
+        Ok(pid)
+    }
+
+    /// 停止由 cc-switch 启动的服务进程，返回是否确实停止了进程
+    pub fn stop(provider_id: &str) -> Result<bool, AppError> {
+        let child = PROCESSES
+            .lock()
+            .map_err(AppError::from)?
+            .remove(provider_id);
+        let Some(mut child) = child else {
+            return Ok(false);
+        };
+        if let Err(e) = child.kill() {
+            log::warn!("停止本地模型服务失败 (provider={provider_id}): {e}");
+        }
+        let _ = child.wait();
+        log::info!("已停止本地模型服务 (provider={provider_id})");
+        Ok(true)
+    }
+
+    /// 停止全部服务进程（应用退出时调用）
+    pub fn stop_all() {
+        let children: Vec<(String, Child)> = match PROCESSES.lock() {
+            Ok(mut processes) => processes.drain().collect(),
+            Err(e) => {
+                log::error!("获取本地模型进程列表失败: {e}");
+                return;
+            }
+        };
+        for (provider_id, mut child) in children {
+            let _ = child.kill();
+            let _ = child.wait();
+            log::info!("退出时已停止本地模型服务 (provider={provider_id})");
+        }
+    }
+
+    fn process_info(provider_id: &str) -> (bool, Option<u32>) {
+        let Ok(mut processes) = PROCESSES.lock() else {
+            return (false, None);
+        };
+        let Some(child) = processes.get_mut(provider_id) else {
+            return (false, None);
+        };
+        if matches!(child.try_wait(), Ok(None)) {
+            return (true, Some(child.id()));
+        }
+        // 进程已自行退出
+        processes.remove(provider_id);
+        (false, None)
+    }
+
+    /// 通过原生接口检查服务，返回（HTTP 状态码, 实际可用的模型）
+    pub async fn native_health(config: &LocalModelConfig) -> Result<(u16, String), AppError> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(NATIVE_HEALTH_TIMEOUT_SECS))
+            .build()
+            .map_err(|e| AppError::Message(format!("创建客户端失败: {e}")))?;
+        let endpoint = config.endpoint();
+        let path = match config.runtime {
+            LocalRuntime::Ollama => "/api/tags",
+            LocalRuntime::LlamaCpp => "/health",
+        };
+
+        let response = client
+            .get(format!("{endpoint}{path}"))
+            .send()
+            .await
+            .map_err(|e| AppError::Message(format!("本地服务不可达 ({endpoint}): {e}")))?;
+        let status = response.status().as_u16();
+        if !response.status().is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::Message(format!("HTTP {status}: {body}")));
+        }
+
+        let model = config.request_model().unwrap_or_default();
+        if config.runtime == LocalRuntime::Ollama && !model.is_empty() {
+            let tags: serde_json::Value = response
+                .json()
+                .await
+                .map_err(|e| AppError::Message(format!("解析 Ollama 模型列表失败: {e}")))?;
+            let installed = tags
+                .get("models")
+                .and_then(|m| m.as_array())
+                .map(|models| {
+                    models.iter().any(|m| {
+                        let name = m.get("name").and_then(|n| n.as_str()).unwrap_or("");
+                        name == model || name.strip_suffix(":latest") == Some(model.as_str())
+                    })
+                })
+                .unwrap_or(false);
+            if !installed {
+                return Err(AppError::Message(format!(
+                    "Ollama 未安装模型 {model}，请先执行 ollama pull {model}"
+                )));
+            }
+        }
+
+        Ok((status, model))
+    }
+
+    /// 汇总本地服务状态
+    pub async fn status(provider_id: &str, config: &LocalModelConfig) -> LocalModelStatus {
+        let (process_running, pid) = Self::process_info(provider_id);
+        let (reachable, message) = match Self::native_health(config).await {
+            Ok(_) => (true, "服务正常".to_string()),
+            Err(e) => (false, e.to_string()),
+        };
+        LocalModelStatus {
+            provider_id: provider_id.to_string(),
+            runtime: config.runtime,
+            endpoint: config.endpoint(),
+            managed: config.managed,
+            process_running,
+            pid,
+            reachable,
+            message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_requires_managed_flag() {
+        let config = LocalModelConfig::default();
+        assert!(LocalModelService::start("p", &config).is_err());
+        assert!(!LocalModelService::stop("p").unwrap());
+    }
+
+    #[test]
+    fn llama_cpp_command_requires_model_and_uses_endpoint_port() {
+        let mut config = LocalModelConfig {
+            runtime: LocalRuntime::LlamaCpp,
+            endpoint: Some("http://127.0.0.1:9001".to_string()),
+            managed: true,
+            ..LocalModelConfig::default()
+        };
+        assert!(LocalModelService::build_command(&config).is_err());
+
+        config.model = Some("/models/a.gguf".to_string());
+        let command = LocalModelService::build_command(&config).unwrap();
+        let args: Vec<String> = command
+            .get_args()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(
+            args,
+            [
+                "--host",
+                "127.0.0.1",
+                "--port",
+                "9001",
+                "-m",
+                "/models/a.gguf"
+            ]
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! 运行时日志级别
+//!
+//! 日志插件以 Trace 级别安装，是否输出由这里按模块决定：设置中的 `logLevels` 给出默认级别
+//! 与各模块（proxy、streamCheck、database、sync）的覆盖级别，修改后立即生效并随设置持久化，
+//! 便于排查问题时只为代理打开 trace 日志。
+
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use log::{LevelFilter, Metadata};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+/// 日志级别
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => LevelFilter::Off,
+            LogLevel::Error => LevelFilter::Error,
+            LogLevel::Warn => LevelFilter::Warn,
+            LogLevel::Info => LevelFilter::Info,
+            LogLevel::Debug => LevelFilter::Debug,
+            LogLevel::Trace => LevelFilter::Trace,
+        }
+    }
+}
+
+/// 可单独设置级别的模块
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LogModule {
+    /// 本地代理（转发、故障转移、代理管理命令）
+    Proxy,
+    /// 健康检查与探测
+    StreamCheck,
+    /// 数据库
+    Database,
+    /// 配置同步（live 配置写入、一致性检查、快照、MCP 同步）
+    Sync,
+}
+
+impl LogModule {
+    /// 模块包含的日志 target 前缀（相对本 crate）
+    fn targets(self) -> &'static [&'static str] {
+        match self {
+            Self::Proxy => &["proxy", "services::proxy", "commands::proxy"],
+            Self::StreamCheck => &[
+                "services::stream_check",
+                "services::health_probe",
+                "commands::stream_check",
+            ],
+            Self::Database => &["database"],
+            Self::Sync => &[
+                "services::provider::live",
+                "services::provider::consistency",
+                "services::config_snapshot",
+                "services::mcp",
+                "mcp",
+            ],
+        }
+    }
+}
+
+/// 日志级别设置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLevelConfig {
+    /// 未单独设置的模块（含第三方库）使用的级别
+    #[serde(default)]
+    pub default: LogLevel,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub modules: BTreeMap<LogModule, LogLevel>,
+}
+
+/// 生效中的规则：默认级别 + (target 前缀, 级别)，前缀越长越优先
+struct Filter {
+    default: LevelFilter,
+    rules: Vec<(String, LevelFilter)>,
+}
+
+static FILTER: Lazy<RwLock<Filter>> = Lazy::new(|| {
+    RwLock::new(Filter {
+        default: LevelFilter::Info,
+        rules: Vec::new(),
+    })
+});
+
+fn compile(config: &LogLevelConfig) -> Filter {
+    let crate_name = env!("CARGO_CRATE_NAME");
+    let mut rules: Vec<(String, LevelFilter)> = config
+        .modules
+        .iter()
+        .flat_map(|(module, level)| {
+            module
+                .targets()
+                .iter()
+                .map(move |target| (format!("{crate_name}::{target}"), (*level).into()))
+        })
+        .collect();
+    rules.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+    Filter {
+        default: config.default.into(),
+        rules,
+    }
+}
+
+fn matches(target: &str, prefix: &str) -> bool {
+    target
+        .strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+}
+
+impl Filter {
+    fn level_for(&self, target: &str) -> LevelFilter {
+        self.rules
+            .iter()
+            .find(|(prefix, _)| matches(target, prefix))
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.rules
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+/// 应用新的级别设置（立即生效）
+pub fn apply(config: &LogLevelConfig) {
+    let filter = compile(config);
+    log::set_max_level(filter.max_level());
+    *FILTER.write().unwrap_or_else(|e| e.into_inner()) = filter;
+}
+
+/// 供日志插件调用：该条日志是否输出
+pub fn enabled(metadata: &Metadata) -> bool {
+    metadata.level()
+        <= FILTER
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .level_for(metadata.target())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn module_overrides_take_precedence_over_default() {
+        let config = LogLevelConfig {
+            default: LogLevel::Warn,
+            modules: BTreeMap::from([
+                (LogModule::Proxy, LogLevel::Trace),
+                (LogModule::Database, LogLevel::Off),
+            ]),
+        };
+        let filter = compile(&config);
+        assert_eq!(
+            filter.level_for("cc_switch_lib::proxy::forwarder"),
+            LevelFilter::Trace
+        );
+        assert_eq!(
+            filter.level_for("cc_switch_lib::commands::proxy"),
+            LevelFilter::Trace
+        );
+        assert_eq!(
+            filter.level_for("cc_switch_lib::database::dao::providers"),
+            LevelFilter::Off
+        );
+        // 只按完整的模块路径匹配
+        assert_eq!(
+            filter.level_for("cc_switch_lib::proxy_helpers"),
+            LevelFilter::Warn
+        );
+        assert_eq!(filter.level_for("hyper::client"), LevelFilter::Warn);
+        assert_eq!(filter.max_level(), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn config_round_trips_with_camel_case_modules() {
+        let config: LogLevelConfig =
+            serde_json::from_str(r#"{"default":"info","modules":{"streamCheck":"debug"}}"#)
+                .unwrap();
+        assert_eq!(config.modules[&LogModule::StreamCheck], LogLevel::Debug);
+        assert_eq!(
+            serde_json::to_value(&config).unwrap()["modules"]["streamCheck"],
+            "debug"
+        );
+    }
+}
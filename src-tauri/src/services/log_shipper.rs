@@ -0,0 +1,417 @@
+//! 访问日志外发
+//!
+//! 代理每完成一次请求写入 proxy_request_logs 的同时，把访问记录放入内存缓冲，
+//! 由后台任务按批发送到外部日志系统：
+//! - syslog：RFC 5424 格式，通过 UDP 逐条发送
+//! - otlp：OTLP/HTTP JSON（`/v1/logs`）
+//! - http：POST JSON 数组到任意地址
+//!
+//! 缓冲区满时丢弃最旧的记录；发送失败时保留记录并按指数退避重试，离线时暂停发送。
+//! 错误信息可能带有请求内容，外发前按日志脱敏设置处理。
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::Notify;
+
+use crate::error::AppError;
+use crate::proxy::log_scrubber;
+use crate::proxy::offline;
+use crate::proxy::usage::logger::RequestLog;
+use crate::services::job_queue::backoff_secs;
+
+/// 内存缓冲区最多保留的记录数
+const MAX_BUFFERED: usize = 10_000;
+
+/// 单次发送超时（秒）
+const SEND_TIMEOUT_SECS: u64 = 10;
+
+/// 退避时间上限（秒），日志外发比告警更看重时效
+const MAX_BACKOFF_SECS: i64 = 300;
+
+static BUFFER: Lazy<Mutex<VecDeque<AccessLogRecord>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+static WAKE: Lazy<Notify> = Lazy::new(Notify::new);
+
+/// 外发目标
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum LogShipTarget {
+    /// UDP syslog，如 `127.0.0.1:514`
+    Syslog { address: String },
+    /// OTLP/HTTP 日志接收地址，如 `http://localhost:4318`（未带路径时补全 `/v1/logs`）
+    Otlp {
+        endpoint: String,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        headers: BTreeMap<String, String>,
+    },
+    /// 接收 JSON 数组的 HTTP 地址
+    Http {
+        url: String,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        headers: BTreeMap<String, String>,
+    },
+}
+
+/// 访问日志外发配置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogShippingConfig {
+    pub target: LogShipTarget,
+    /// 每批最多发送的记录数
+    #[serde(default = "default_batch_size")]
+    pub batch_size: u32,
+    /// 发送间隔（秒）
+    #[serde(default = "default_flush_interval_secs")]
+    pub flush_interval_secs: u32,
+}
+
+fn default_batch_size() -> u32 {
+    100
+}
+
+fn default_flush_interval_secs() -> u32 {
+    5
+}
+
+/// 外发的访问记录
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessLogRecord {
+    /// 毫秒时间戳
+    pub timestamp: i64,
+    pub request_id: String,
+    pub app_type: String,
+    pub provider_id: String,
+    pub model: String,
+    pub status_code: u16,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub first_token_ms: Option<u64>,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub total_cost_usd: String,
+    pub is_streaming: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+
+impl AccessLogRecord {
+    pub fn from_request_log(log: &RequestLog) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            request_id: log.request_id.clone(),
+            app_type: log.app_type.clone(),
+            provider_id: log.provider_id.clone(),
+            model: log.model.clone(),
+            status_code: log.status_code,
+            latency_ms: log.latency_ms,
+            first_token_ms: log.first_token_ms,
+            input_tokens: log.usage.input_tokens,
+            output_tokens: log.usage.output_tokens,
+            total_cost_usd: log
+                .cost
+                .as_ref()
+                .map(|c| c.total_cost.to_string())
+                .unwrap_or_else(|| "0".to_string()),
+            is_streaming: log.is_streaming,
+            session_id: log.session_id.clone(),
+            error_message: log.error_message.as_deref().map(log_scrubber::text_for_log),
+            tag: log.tag.clone(),
+        }
+    }
+
+    fn is_error(&self) -> bool {
+        self.status_code >= 400
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "{} {} {} -> {} ({} ms)",
+            self.app_type, self.provider_id, self.model, self.status_code, self.latency_ms
+        )
+    }
+}
+
+/// 放入一条访问记录（未配置外发时直接忽略）
+pub fn ship(log: &RequestLog) {
+    let Some(config) = crate::settings::get_settings().log_shipping else {
+        return;
+    };
+    let mut buffer = BUFFER.lock().unwrap_or_else(|e| e.into_inner());
+    if buffer.len() >= MAX_BUFFERED {
+        buffer.pop_front();
+    }
+    buffer.push_back(AccessLogRecord::from_request_log(log));
+    if buffer.len() >= config.batch_size as usize {
+        WAKE.notify_one();
+    }
+}
+
+fn take_batch(size: usize) -> Vec<AccessLogRecord> {
+    let mut buffer = BUFFER.lock().unwrap_or_else(|e| e.into_inner());
+    let n = size.min(buffer.len());
+    buffer.drain(..n).collect()
+}
+
+/// 发送失败的记录放回队首，保持顺序
+fn requeue(batch: Vec<AccessLogRecord>) {
+    let mut buffer = BUFFER.lock().unwrap_or_else(|e| e.into_inner());
+    for record in batch.into_iter().rev() {
+        if buffer.len() >= MAX_BUFFERED {
+            break;
+        }
+        buffer.push_front(record);
+    }
+}
+
+/// RFC 5424 syslog 行（facility local0）
+fn syslog_line(record: &AccessLogRecord) -> String {
+    let severity = if record.is_error() { 4 } else { 6 };
+    let timestamp = chrono::DateTime::from_timestamp_millis(record.timestamp)
+        .unwrap_or_default()
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    let body = serde_json::to_string(record).unwrap_or_default();
+    format!(
+        "<{}>1 {timestamp} - cc-switch - access - {body}",
+        16 * 8 + severity
+    )
+}
+
+/// 转换为 OTLP JSON 的 AnyValue
+pub(crate) fn otlp_value(value: &Value) -> Value {
+    match value {
+        Value::Bool(b) => json!({ "boolValue": b }),
+        Value::Number(n) if n.is_f64() => json!({ "doubleValue": n }),
+        // OTLP JSON 中 int64 按字符串编码
+        Value::Number(n) => json!({ "intValue": n.to_string() }),
+        Value::String(s) => json!({ "stringValue": s }),
+        other => json!({ "stringValue": other.to_string() }),
+    }
+}
+
+/// OTLP 资源描述（服务名与版本）
+pub(crate) fn otlp_resource() -> Value {
+    json!({
+        "attributes": [
+            { "key": "service.name", "value": { "stringValue": "cc-switch" } },
+            { "key": "service.version", "value": { "stringValue": env!("CARGO_PKG_VERSION") } },
+        ]
+    })
+}
+
+/// OTLP/HTTP JSON 日志请求体
+fn otlp_payload(records: &[AccessLogRecord]) -> Value {
+    let log_records: Vec<Value> = records
+        .iter()
+        .map(|record| {
+            let attributes: Vec<Value> = serde_json::to_value(record)
+                .ok()
+                .and_then(|v| v.as_object().cloned())
+                .unwrap_or_default()
+                .iter()
+                .filter(|(key, _)| key.as_str() != "timestamp")
+                .map(|(key, value)| json!({ "key": key, "value": otlp_value(value) }))
+                .collect();
+            let (severity_number, severity_text) = if record.is_error() {
+                (13, "WARN")
+            } else {
+                (9, "INFO")
+            };
+            json!({
+                "timeUnixNano": (record.timestamp as i128 * 1_000_000).to_string(),
+                "severityNumber": severity_number,
+                "severityText": severity_text,
+                "body": { "stringValue": record.summary() },
+                "attributes": attributes,
+            })
+        })
+        .collect();
+
+    json!({
+        "resourceLogs": [{
+            "resource": otlp_resource(),
+            "scopeLogs": [{
+                "scope": { "name": "cc-switch.proxy" },
+                "logRecords": log_records,
+            }]
+        }]
+    })
+}
+
+/// OTLP/HTTP 信号地址（endpoint 未带 `/v1/<signal>` 路径时补全）
+pub(crate) fn otlp_signal_url(endpoint: &str, signal: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    let suffix = format!("/v1/{signal}");
+    if endpoint.ends_with(&suffix) {
+        endpoint.to_string()
+    } else {
+        format!("{endpoint}{suffix}")
+    }
+}
+
+pub(crate) async fn post_json(
+    client: &reqwest::Client,
+    url: &str,
+    headers: &BTreeMap<String, String>,
+    body: &Value,
+) -> Result<(), AppError> {
+    let mut request = client.post(url).json(body);
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(AppError::UpstreamStatus {
+            status: status.as_u16(),
+            body,
+        });
+    }
+    Ok(())
+}
+
+async fn send_batch(
+    client: &reqwest::Client,
+    target: &LogShipTarget,
+    batch: &[AccessLogRecord],
+) -> Result<(), AppError> {
+    match target {
+        LogShipTarget::Syslog { address } => {
+            let socket = tokio::net::UdpSocket::bind("0.0.0.0:0")
+                .await
+                .map_err(|e| AppError::Message(format!("创建 syslog 套接字失败: {e}")))?;
+            for record in batch {
+                socket
+                    .send_to(syslog_line(record).as_bytes(), address.as_str())
+                    .await
+                    .map_err(|e| AppError::Message(format!("发送 syslog 失败: {e}")))?;
+            }
+            Ok(())
+        }
+        LogShipTarget::Otlp { endpoint, headers } => {
+            let url = otlp_signal_url(endpoint, "logs");
+            post_json(client, &url, headers, &otlp_payload(batch)).await
+        }
+        LogShipTarget::Http { url, headers } => {
+            let body =
+                serde_json::to_value(batch).map_err(|e| AppError::JsonSerialize { source: e })?;
+            post_json(client, url, headers, &body).await
+        }
+    }
+}
+
+/// 后台发送循环
+pub async fn run() {
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(SEND_TIMEOUT_SECS))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            log::error!("[LogShip] 创建 HTTP 客户端失败，访问日志不会外发: {e}");
+            return;
+        }
+    };
+    let mut failures: u32 = 0;
+
+    loop {
+        let config = crate::settings::get_settings().log_shipping;
+        let interval = config
+            .as_ref()
+            .map(|c| c.flush_interval_secs.max(1) as u64)
+            .unwrap_or(30);
+        let wait = if failures > 0 {
+            backoff_secs(failures).min(MAX_BACKOFF_SECS) as u64
+        } else {
+            interval
+        };
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(wait)) => {}
+            _ = WAKE.notified(), if failures == 0 => {}
+        }
+
+        let Some(config) = config else {
+            // 关闭外发后丢弃残留记录
+            BUFFER.lock().unwrap_or_else(|e| e.into_inner()).clear();
+            continue;
+        };
+        if offline::is_offline() {
+            continue;
+        }
+
+        loop {
+            let batch = take_batch(config.batch_size.max(1) as usize);
+            if batch.is_empty() {
+                break;
+            }
+            match send_batch(&client, &config.target, &batch).await {
+                Ok(()) => failures = 0,
+                Err(e) => {
+                    failures += 1;
+                    log::warn!(
+                        "[LogShip] 发送 {} 条访问日志失败（第 {failures} 次）: {e}",
+                        batch.len()
+                    );
+                    requeue(batch);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(status_code: u16) -> AccessLogRecord {
+        AccessLogRecord {
+            timestamp: 1_700_000_000_123,
+            request_id: "r1".to_string(),
+            app_type: "claude".to_string(),
+            provider_id: "p1".to_string(),
+            model: "claude-sonnet-4".to_string(),
+            status_code,
+            latency_ms: 850,
+            first_token_ms: Some(120),
+            input_tokens: 10,
+            output_tokens: 20,
+            total_cost_usd: "0.0012".to_string(),
+            is_streaming: true,
+            session_id: None,
+            error_message: None,
+            tag: None,
+        }
+    }
+
+    #[test]
+    fn syslog_line_uses_rfc5424_header() {
+        let line = syslog_line(&record(200));
+        assert!(line.starts_with("<134>1 2023-11-14T22:13:20.123Z - cc-switch - access - {"));
+        assert!(syslog_line(&record(502)).starts_with("<132>1 "));
+    }
+
+    #[test]
+    fn otlp_payload_maps_fields_to_attributes() {
+        let payload = otlp_payload(&[record(200)]);
+        let log = &payload["resourceLogs"][0]["scopeLogs"][0]["logRecords"][0];
+        assert_eq!(log["timeUnixNano"], "1700000000123000000");
+        assert_eq!(log["severityText"], "INFO");
+        let attrs = log["attributes"].as_array().unwrap();
+        assert!(attrs.contains(&json!({ "key": "statusCode", "value": { "intValue": "200" } })));
+        assert!(attrs.contains(&json!({ "key": "isStreaming", "value": { "boolValue": true } })));
+        assert_eq!(
+            otlp_signal_url("http://localhost:4318/", "logs"),
+            "http://localhost:4318/v1/logs"
+        );
+    }
+}
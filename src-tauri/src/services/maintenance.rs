@@ -0,0 +1,235 @@
+//! 供应商维护窗口
+//!
+//! 供应商可在 `ProviderMeta.maintenanceWindows` 中声明维护时间段：
+//! - 一次性窗口：`startAt` ~ `endAt`（Unix 秒）
+//! - 周期窗口：`cron`（分 时 日 月 周，本地时间）+ `durationMinutes`
+//!
+//! 维护期间健康检查失败照常记录，但不发送告警，代理请求失败也不计入熔断器和健康状态，
+//! 避免计划内的停机触发故障转移。
+
+use chrono::{Datelike, Duration, Local, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+
+use crate::provider::Provider;
+
+/// 周期窗口的最长持续时间（分钟）
+pub const MAX_DURATION_MINUTES: u32 = 24 * 60;
+
+fn default_duration_minutes() -> u32 {
+    60
+}
+
+/// 维护窗口
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceWindow {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// 一次性窗口开始时间（Unix 秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_at: Option<i64>,
+    /// 一次性窗口结束时间（Unix 秒）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_at: Option<i64>,
+    /// 周期窗口的 cron 表达式（如 `0 3 * * 0` 表示每周日 03:00）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cron: Option<String>,
+    /// 周期窗口每次持续的分钟数
+    #[serde(default = "default_duration_minutes")]
+    pub duration_minutes: u32,
+}
+
+impl MaintenanceWindow {
+    /// 校验窗口定义
+    pub fn validate(&self) -> Result<(), String> {
+        match (&self.cron, self.start_at, self.end_at) {
+            (Some(cron), None, None) => {
+                CronSpec::parse(cron)?;
+                if self.duration_minutes == 0 || self.duration_minutes > MAX_DURATION_MINUTES {
+                    return Err(format!(
+                        "持续时间必须在 1 到 {MAX_DURATION_MINUTES} 分钟之间"
+                    ));
+                }
+                Ok(())
+            }
+            (None, Some(start), Some(end)) if end > start => Ok(()),
+            (None, Some(_), Some(_)) => Err("结束时间必须晚于开始时间".to_string()),
+            _ => Err("需要设置 cron，或同时设置 startAt 与 endAt".to_string()),
+        }
+    }
+
+    /// 指定时刻是否处于该窗口内
+    pub fn is_active_at(&self, timestamp: i64, local: NaiveDateTime) -> bool {
+        if let Some(cron) = &self.cron {
+            let Ok(spec) = CronSpec::parse(cron) else {
+                return false;
+            };
+            let duration = self.duration_minutes.min(MAX_DURATION_MINUTES);
+            // 向前回溯，查找仍在持续时间内的最近一次触发
+            let now = local.with_second(0).unwrap_or(local);
+            return (0..duration).any(|m| spec.matches(&(now - Duration::minutes(m as i64))));
+        }
+        match (self.start_at, self.end_at) {
+            (Some(start), Some(end)) => start <= timestamp && timestamp < end,
+            _ => false,
+        }
+    }
+}
+
+/// 当前处于的维护窗口
+pub fn active_window(provider: &Provider) -> Option<&MaintenanceWindow> {
+    let windows = &provider.meta.as_ref()?.maintenance_windows;
+    if windows.is_empty() {
+        return None;
+    }
+    let now = Local::now();
+    windows
+        .iter()
+        .find(|w| w.is_active_at(now.timestamp(), now.naive_local()))
+}
+
+/// 供应商当前是否处于维护中
+pub fn is_in_maintenance(provider: &Provider) -> bool {
+    active_window(provider).is_some()
+}
+
+/// 解析后的 cron 表达式（每个字段为允许取值的位图）
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CronSpec {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// 日 / 周字段是否为 `*`（两者都被限定时按标准 cron 语义取并集）
+    dom_any: bool,
+    dow_any: bool,
+}
+
+impl CronSpec {
+    fn parse(expr: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, dom, month, dow] = fields[..] else {
+            return Err(format!("cron 表达式需要 5 个字段: {expr}"));
+        };
+        let mut days_of_week = parse_field(dow, 0, 7)?;
+        // 0 与 7 都表示周日
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week |= 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days_of_month: parse_field(dom, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            days_of_week,
+            dom_any: dom == "*",
+            dow_any: dow == "*",
+        })
+    }
+
+    fn matches(&self, time: &NaiveDateTime) -> bool {
+        let bit = |set: u64, value: u32| set & (1 << value) != 0;
+        let dom = bit(self.days_of_month, time.day());
+        let dow = bit(self.days_of_week, time.weekday().num_days_from_sunday());
+        let day = match (self.dom_any, self.dow_any) {
+            (false, false) => dom || dow,
+            _ => dom && dow,
+        };
+        bit(self.minutes, time.minute())
+            && bit(self.hours, time.hour())
+            && bit(self.months, time.month())
+            && day
+    }
+}
+
+/// 解析单个字段（支持 `*`、`a`、`a-b`、`*/n`、`a-b/n` 以及逗号列表）
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let invalid = || format!("无效的 cron 字段: {field}");
+    let mut set = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (
+                a.parse().map_err(|_| invalid())?,
+                b.parse().map_err(|_| invalid())?,
+            )
+        } else {
+            let value = range.parse().map_err(|_| invalid())?;
+            // `5/15` 表示从 5 开始每 15
+            (value, if part.contains('/') { max } else { value })
+        };
+        if start < min || end > max || start > end {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, mo, d)
+            .unwrap()
+            .and_hms_opt(h, mi, 0)
+            .unwrap()
+    }
+
+    fn recurring(cron: &str, duration_minutes: u32) -> MaintenanceWindow {
+        MaintenanceWindow {
+            label: None,
+            start_at: None,
+            end_at: None,
+            cron: Some(cron.to_string()),
+            duration_minutes,
+        }
+    }
+
+    #[test]
+    fn recurring_window_covers_duration() {
+        // 每周日 03:00 起 90 分钟（2026-10-18 为周日）
+        let window = recurring("0 3 * * 0", 90);
+        assert!(window.validate().is_ok());
+        assert!(window.is_active_at(0, at(2026, 10, 18, 3, 0)));
+        assert!(window.is_active_at(0, at(2026, 10, 18, 4, 29)));
+        assert!(!window.is_active_at(0, at(2026, 10, 18, 4, 30)));
+        assert!(!window.is_active_at(0, at(2026, 10, 19, 3, 10)));
+    }
+
+    #[test]
+    fn one_off_window_uses_timestamps() {
+        let window = MaintenanceWindow {
+            label: Some("migration".to_string()),
+            start_at: Some(100),
+            end_at: Some(200),
+            cron: None,
+            duration_minutes: 60,
+        };
+        let local = at(2026, 1, 1, 0, 0);
+        assert!(window.is_active_at(150, local));
+        assert!(!window.is_active_at(200, local));
+    }
+
+    #[test]
+    fn parse_field_supports_ranges_steps_and_lists() {
+        assert_eq!(parse_field("1-3,5", 0, 59).unwrap(), 0b10_1110);
+        assert_eq!(parse_field("*/20", 0, 59).unwrap(), 1 | 1 << 20 | 1 << 40);
+        assert!(parse_field("60", 0, 59).is_err());
+        assert!(recurring("0 3 * *", 60).validate().is_err());
+        assert!(recurring("0 3 * * 0", 0).validate().is_err());
+    }
+}
@@ -1,15 +1,45 @@
+pub mod access_log;
+pub mod background_task;
+pub mod cancellation;
+pub mod codex_auth_repair;
 pub mod config;
+pub mod config_lock;
+pub mod config_snapshot;
+pub mod data_dir;
+pub mod diagnostics;
+pub mod disk_guard;
 pub mod env_checker;
 pub mod env_manager;
+pub mod fleet;
+pub mod health_probe;
+pub mod job_queue;
+pub mod key_reveal;
+pub mod latency_slo;
+pub mod local_model;
+pub mod log_level;
+pub mod log_shipper;
+pub mod maintenance;
 pub mod mcp;
+pub mod onboarding;
+pub mod probe_budget;
 pub mod prompt;
+pub mod prompt_compare;
 pub mod provider;
 pub mod proxy;
+pub mod proxy_runtime;
+pub mod replay;
+pub mod reset;
 pub mod skill;
 pub mod speedtest;
+pub mod startup_profile;
+pub mod status_export;
 pub mod stream_check;
+pub mod timeline;
 pub mod tps_test;
+pub mod traffic_health;
+pub mod usage_forecast;
 pub mod usage_stats;
+pub mod vault;
 
 pub use config::ConfigService;
 pub use mcp::McpService;
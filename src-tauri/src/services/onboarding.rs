@@ -0,0 +1,456 @@
+//! 供应商接入校验
+//!
+//! 对尚未保存的草稿供应商依次执行分阶段检查，供前端向导指出配置错在哪一步：
+//! 1. 地址可达：base_url 能建立连接并返回任意 HTTP 响应
+//! 2. 认证：携带密钥请求模型列表，401 / 403 视为密钥无效
+//! 3. 模型列表：解析 `/v1/models` 返回的模型（中转站不支持时只给出警告）
+//! 4. 流式补全：发送 1 token 的流式请求（与健康检查相同）
+//!
+//! 某一阶段失败后，后续阶段标记为跳过。
+
+use std::time::{Duration, Instant};
+
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::i18n::Locale;
+use crate::provider::Provider;
+use crate::proxy::auth_scheme::apply_auth_scheme;
+use crate::proxy::custom_headers::apply_custom_headers_to_request;
+use crate::proxy::provider_tls;
+use crate::proxy::providers::get_adapter;
+use crate::services::cancellation::CancelToken;
+use crate::services::stream_check::{StreamCheckConfig, StreamCheckService};
+
+/// 可达与认证阶段的超时
+const PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+/// 结果中最多返回的模型数量
+const MAX_MODELS: usize = 200;
+
+/// 校验阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OnboardingStage {
+    Reachability,
+    Auth,
+    Models,
+    Completion,
+}
+
+/// 阶段结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StageStatus {
+    Passed,
+    /// 通过但有提示（如中转站不提供模型列表）
+    Warning,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageResult {
+    pub stage: OnboardingStage,
+    pub status: StageStatus,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+}
+
+impl StageResult {
+    fn new(stage: OnboardingStage, status: StageStatus, message: impl Into<String>) -> Self {
+        Self {
+            stage,
+            status,
+            message: message.into(),
+            http_status: None,
+            duration_ms: None,
+        }
+    }
+
+    fn skipped(stage: OnboardingStage) -> Self {
+        Self::new(stage, StageStatus::Skipped, "前一阶段未通过，已跳过")
+    }
+
+    fn timed(mut self, start: Instant, http_status: Option<u16>) -> Self {
+        self.duration_ms = Some(start.elapsed().as_millis() as u64);
+        self.http_status = http_status;
+        self
+    }
+}
+
+/// 校验报告
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnboardingReport {
+    /// 所有阶段均未失败
+    pub success: bool,
+    pub endpoint: String,
+    pub stages: Vec<StageResult>,
+    /// 模型列表阶段获取到的模型 ID
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub models: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completion_model: Option<String>,
+}
+
+/// 按顺序执行全部阶段
+pub async fn validate_draft(
+    app_type: &AppType,
+    provider: &Provider,
+    config: &StreamCheckConfig,
+    model: Option<&str>,
+) -> Result<OnboardingReport, AppError> {
+    let adapter = get_adapter(app_type);
+    let base_url = adapter
+        .extract_base_url(provider)
+        .map_err(|e| AppError::Message(format!("提取 base_url 失败: {e}")))?;
+    let builder = Client::builder()
+        .timeout(PROBE_TIMEOUT)
+        .user_agent("cc-switch/1.0");
+    let client = provider_tls::configure(builder, provider, &base_url)?
+        .build()
+        .map_err(|e| AppError::Message(format!("创建客户端失败: {e}")))?;
+    let endpoint = provider_tls::rewrite_url(&base_url, provider);
+
+    let mut report = OnboardingReport {
+        success: false,
+        endpoint: endpoint.clone(),
+        stages: Vec::with_capacity(4),
+        models: Vec::new(),
+        completion_model: None,
+    };
+
+    let reachability = check_reachability(&client, &endpoint).await;
+    let reachable = reachability.status != StageStatus::Failed;
+    report.stages.push(reachability);
+    if !reachable {
+        return Ok(finish(report));
+    }
+
+    let (auth, models) = check_auth_and_models(app_type, provider, &client, &endpoint).await;
+    let authorized = auth.status != StageStatus::Failed;
+    report.stages.push(auth);
+    if !authorized {
+        return Ok(finish(report));
+    }
+    report.stages.push(models.result);
+    report.models = models.ids;
+
+    let completion_model = model
+        .map(str::to_string)
+        .unwrap_or_else(|| StreamCheckService::resolve_test_model(app_type, provider, config));
+    report.stages.push(
+        check_completion(
+            app_type,
+            provider,
+            config,
+            &completion_model,
+            &report.models,
+        )
+        .await,
+    );
+    report.completion_model = Some(completion_model);
+    Ok(finish(report))
+}
+
+/// 补齐未执行的阶段并汇总结果
+fn finish(mut report: OnboardingReport) -> OnboardingReport {
+    for stage in [
+        OnboardingStage::Reachability,
+        OnboardingStage::Auth,
+        OnboardingStage::Models,
+        OnboardingStage::Completion,
+    ] {
+        if !report.stages.iter().any(|s| s.stage == stage) {
+            report.stages.push(StageResult::skipped(stage));
+        }
+    }
+    report.success = report
+        .stages
+        .iter()
+        .all(|s| matches!(s.status, StageStatus::Passed | StageStatus::Warning));
+    report
+}
+
+/// 任意 HTTP 响应都说明地址可达（根路径常见 404）
+async fn check_reachability(client: &Client, endpoint: &str) -> StageResult {
+    let start = Instant::now();
+    match client.get(endpoint).send().await {
+        Ok(response) => StageResult::new(
+            OnboardingStage::Reachability,
+            StageStatus::Passed,
+            "地址可达",
+        )
+        .timed(start, Some(response.status().as_u16())),
+        Err(e) => {
+            let message = if e.is_timeout() {
+                format!("连接超时: {e}")
+            } else if e.is_connect() {
+                format!("无法连接: {e}")
+            } else {
+                format!("请求失败: {e}")
+            };
+            StageResult::new(OnboardingStage::Reachability, StageStatus::Failed, message)
+                .timed(start, None)
+        }
+    }
+}
+
+struct ModelsOutcome {
+    result: StageResult,
+    ids: Vec<String>,
+}
+
+/// 认证与模型列表共用一次 `/v1/models` 请求
+async fn check_auth_and_models(
+    app_type: &AppType,
+    provider: &Provider,
+    client: &Client,
+    endpoint: &str,
+) -> (StageResult, ModelsOutcome) {
+    let start = Instant::now();
+    let skipped_models = || ModelsOutcome {
+        result: StageResult::skipped(OnboardingStage::Models),
+        ids: Vec::new(),
+    };
+
+    let adapter = get_adapter(app_type);
+    let Some(auth) = adapter.extract_auth(provider) else {
+        return (
+            StageResult::new(OnboardingStage::Auth, StageStatus::Failed, "未找到 API Key"),
+            skipped_models(),
+        );
+    };
+
+    let url = models_url(endpoint);
+    let mut request = adapter.add_auth_headers(client.get(&url), &auth);
+    if *app_type == AppType::Claude {
+        request = request.header("anthropic-version", "2023-06-01");
+    }
+    let mut built = match request.build() {
+        Ok(built) => built,
+        Err(e) => {
+            return (
+                StageResult::new(OnboardingStage::Auth, StageStatus::Failed, e.to_string()),
+                skipped_models(),
+            )
+        }
+    };
+    if let Err(e) = apply_auth_scheme(provider, Some(&auth), &mut built) {
+        return (
+            StageResult::new(
+                OnboardingStage::Auth,
+                StageStatus::Failed,
+                e.localized_message(Locale::current()),
+            ),
+            skipped_models(),
+        );
+    }
+    apply_custom_headers_to_request(provider, &mut built);
+
+    let response = match client.execute(built).await {
+        Ok(response) => response,
+        Err(e) => {
+            return (
+                StageResult::new(
+                    OnboardingStage::Auth,
+                    StageStatus::Failed,
+                    format!("请求失败: {e}"),
+                )
+                .timed(start, None),
+                skipped_models(),
+            )
+        }
+    };
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+
+    let auth_result = match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => StageResult::new(
+            OnboardingStage::Auth,
+            StageStatus::Failed,
+            format!("API Key 无效或没有权限: {}", excerpt(&body)),
+        ),
+        s if s.is_success() => {
+            StageResult::new(OnboardingStage::Auth, StageStatus::Passed, "API Key 有效")
+        }
+        // 模型列表不可用时无法确认密钥，交给流式补全阶段验证
+        _ => StageResult::new(
+            OnboardingStage::Auth,
+            StageStatus::Warning,
+            "模型列表接口不可用，将在流式补全阶段验证密钥",
+        ),
+    }
+    .timed(start, Some(status.as_u16()));
+    if auth_result.status == StageStatus::Failed {
+        return (auth_result, skipped_models());
+    }
+
+    let models = if status.is_success() {
+        let ids = parse_model_ids(&body);
+        let result = if ids.is_empty() {
+            StageResult::new(
+                OnboardingStage::Models,
+                StageStatus::Warning,
+                "模型列表为空或格式无法识别",
+            )
+        } else {
+            StageResult::new(
+                OnboardingStage::Models,
+                StageStatus::Passed,
+                format!("获取到 {} 个模型", ids.len()),
+            )
+        };
+        ModelsOutcome {
+            result: result.timed(start, Some(status.as_u16())),
+            ids,
+        }
+    } else {
+        ModelsOutcome {
+            result: StageResult::new(
+                OnboardingStage::Models,
+                StageStatus::Warning,
+                format!("该供应商不提供模型列表（HTTP {}）", status.as_u16()),
+            )
+            .timed(start, Some(status.as_u16())),
+            ids: Vec::new(),
+        }
+    };
+    (auth_result, models)
+}
+
+async fn check_completion(
+    app_type: &AppType,
+    provider: &Provider,
+    config: &StreamCheckConfig,
+    model: &str,
+    models: &[String],
+) -> StageResult {
+    let start = Instant::now();
+    // 向导中需要立即给出结果，不重试
+    let config = StreamCheckConfig {
+        max_retries: 0,
+        ..config.clone()
+    };
+    let result = match StreamCheckService::check_model_with_retry(
+        app_type,
+        provider,
+        &config,
+        Some(model),
+        &CancelToken::new(),
+    )
+    .await
+    {
+        Ok(result) => result,
+        Err(e) => {
+            return StageResult::new(
+                OnboardingStage::Completion,
+                StageStatus::Failed,
+                e.to_string(),
+            )
+            .timed(start, None)
+        }
+    };
+
+    let mut stage = if result.success {
+        StageResult::new(
+            OnboardingStage::Completion,
+            StageStatus::Passed,
+            format!("模型 {model} 流式响应正常"),
+        )
+    } else {
+        let mut message = result.message.clone();
+        if !models.is_empty() && !models.iter().any(|m| m == model) {
+            message.push_str(&format!("（模型 {model} 不在模型列表中）"));
+        }
+        StageResult::new(OnboardingStage::Completion, StageStatus::Failed, message)
+    };
+    stage.http_status = result.http_status;
+    stage.duration_ms = result
+        .response_time_ms
+        .or(Some(start.elapsed().as_millis() as u64));
+    stage
+}
+
+pub(crate) fn models_url(endpoint: &str) -> String {
+    let base = endpoint.trim_end_matches('/');
+    if base.ends_with("/v1") {
+        format!("{base}/models")
+    } else {
+        format!("{base}/v1/models")
+    }
+}
+
+/// 兼容 OpenAI / Anthropic（`data[].id`）与 Gemini（`models[].name`）格式
+fn parse_model_ids(body: &str) -> Vec<String> {
+    let Ok(value) = serde_json::from_str::<Value>(body) else {
+        return Vec::new();
+    };
+    let (list, key) = match (value.get("data"), value.get("models")) {
+        (Some(Value::Array(list)), _) => (list, "id"),
+        (_, Some(Value::Array(list))) => (list, "name"),
+        _ => return Vec::new(),
+    };
+    list.iter()
+        .filter_map(|item| item.get(key).and_then(Value::as_str))
+        .map(|id| id.trim_start_matches("models/").to_string())
+        .take(MAX_MODELS)
+        .collect()
+}
+
+fn excerpt(body: &str) -> String {
+    body.chars().take(200).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_openai_and_gemini_model_lists() {
+        let openai = r#"{"object":"list","data":[{"id":"gpt-4o"},{"id":"gpt-4o-mini"}]}"#;
+        assert_eq!(parse_model_ids(openai), vec!["gpt-4o", "gpt-4o-mini"]);
+        let gemini = r#"{"models":[{"name":"models/gemini-2.5-pro"}]}"#;
+        assert_eq!(parse_model_ids(gemini), vec!["gemini-2.5-pro"]);
+        assert!(parse_model_ids("<html>").is_empty());
+    }
+
+    #[test]
+    fn models_url_avoids_duplicate_version() {
+        assert_eq!(
+            models_url("https://relay.example/v1/"),
+            "https://relay.example/v1/models"
+        );
+        assert_eq!(
+            models_url("https://relay.example"),
+            "https://relay.example/v1/models"
+        );
+    }
+
+    #[test]
+    fn finish_marks_unrun_stages_skipped() {
+        let report = finish(OnboardingReport {
+            success: false,
+            endpoint: String::new(),
+            stages: vec![StageResult::new(
+                OnboardingStage::Reachability,
+                StageStatus::Failed,
+                "无法连接",
+            )],
+            models: Vec::new(),
+            completion_model: None,
+        });
+        assert!(!report.success);
+        assert_eq!(report.stages.len(), 4);
+        assert!(report.stages[1..]
+            .iter()
+            .all(|s| s.status == StageStatus::Skipped));
+    }
+}
@@ -0,0 +1,189 @@
+//! 健康检查 token 预算
+//!
+//! 自动探测（批量检查、切换后快照、补全保活）会消耗付费供应商的真实 token。
+//! 这里按供应商、按自然月（UTC）单独累计探测消耗，与真实请求的用量统计分开：
+//! - 超过预算的 80%：降低自动探测频率（批量检查复用近期结果，保活降级为 HEAD）
+//! - 预算用尽：本月暂停自动探测
+//!
+//! 手动检查不受预算限制，但同样计入消耗。本地供应商不消耗 token。
+//! 消耗按响应中的实际用量计算，供应商未返回用量时按估算值计入。
+
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::provider::Provider;
+use crate::proxy::keep_warm::COMPLETION_PING_TOKENS;
+use crate::proxy::providers::LocalModelConfig;
+use crate::services::stream_check::{StreamCheckConfig, StreamCheckResult};
+
+/// 响应未带用量时，单次成功探测的 token 估算（约 8 个输入 token + 1 个输出 token）
+pub const PROBE_TOKENS_ESTIMATE: u64 = COMPLETION_PING_TOKENS;
+
+/// 进入限流的预算使用比例（百分比）
+const SOFT_LIMIT_PERCENT: u64 = 80;
+
+/// 限流时自动探测间隔的放大倍数
+pub const THROTTLE_FACTOR: u32 = 4;
+
+/// 限流时同一供应商两次自动批量检查的最小间隔（秒）
+pub const THROTTLED_MIN_INTERVAL_SECS: i64 = 30 * 60;
+
+/// 预算状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BudgetState {
+    Normal,
+    /// 接近预算，降低探测频率
+    Throttled,
+    /// 预算用尽，暂停自动探测
+    Exhausted,
+}
+
+/// 单个供应商本月的探测预算状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeBudgetStatus {
+    pub app_type: String,
+    pub provider_id: String,
+    /// 统计月份（YYYY-MM，UTC）
+    pub month: String,
+    pub tokens_used: u64,
+    pub probe_count: u64,
+    /// 月度预算（None 表示不限制）
+    pub budget: Option<u64>,
+    pub state: BudgetState,
+}
+
+/// 当前统计月份
+pub fn current_month() -> String {
+    chrono::Utc::now().format("%Y-%m").to_string()
+}
+
+/// 供应商的月度预算：meta.probeTokenBudget 优先，其次全局配置；0 表示不限制
+pub fn effective_budget(provider: &Provider, config: &StreamCheckConfig) -> Option<u64> {
+    let budget = provider
+        .meta
+        .as_ref()
+        .and_then(|m| m.probe_token_budget)
+        .unwrap_or(config.probe_token_budget);
+    (budget > 0).then_some(budget)
+}
+
+/// 根据已用量判断预算状态
+pub fn budget_state(used: u64, budget: Option<u64>) -> BudgetState {
+    match budget {
+        None => BudgetState::Normal,
+        Some(budget) if used >= budget => BudgetState::Exhausted,
+        Some(budget) if used * 100 >= budget * SOFT_LIMIT_PERCENT => BudgetState::Throttled,
+        Some(_) => BudgetState::Normal,
+    }
+}
+
+/// 一次成功探测计入的 token：优先使用响应中的实际用量
+pub fn charged_tokens(tokens_used: Option<u64>) -> u64 {
+    tokens_used.unwrap_or(PROBE_TOKENS_ESTIMATE)
+}
+
+pub struct ProbeBudgetService;
+
+impl ProbeBudgetService {
+    /// 查询供应商本月的探测预算状态
+    pub fn status(
+        db: &Database,
+        app_type: &str,
+        provider: &Provider,
+        config: &StreamCheckConfig,
+    ) -> Result<ProbeBudgetStatus, AppError> {
+        let month = current_month();
+        let (tokens_used, probe_count) = db.get_probe_usage(app_type, &provider.id, &month)?;
+        let budget = effective_budget(provider, config);
+        Ok(ProbeBudgetStatus {
+            app_type: app_type.to_string(),
+            provider_id: provider.id.clone(),
+            month,
+            tokens_used,
+            probe_count,
+            budget,
+            state: budget_state(tokens_used, budget),
+        })
+    }
+
+    /// 查询预算状态，失败时按不限制处理（预算统计不应阻断健康检查）
+    pub fn state_or_normal(
+        db: &Database,
+        app_type: &str,
+        provider: &Provider,
+        config: &StreamCheckConfig,
+    ) -> BudgetState {
+        match Self::status(db, app_type, provider, config) {
+            Ok(status) => status.state,
+            Err(e) => {
+                log::warn!("读取探测预算失败 ({app_type}/{}): {e}", provider.id);
+                BudgetState::Normal
+            }
+        }
+    }
+
+    /// 记录一次探测消耗的 token（本地供应商不计入），预算状态变差时发布 `budget-warning` 事件
+    pub fn record_tokens(
+        db: &Database,
+        app_type: &str,
+        provider: &Provider,
+        config: &StreamCheckConfig,
+        tokens: u64,
+    ) {
+        if tokens == 0 || LocalModelConfig::of(provider).is_some() {
+            return;
+        }
+        let before = Self::state_or_normal(db, app_type, provider, config);
+        if let Err(e) = db.record_probe_usage(app_type, &provider.id, &current_month(), tokens) {
+            log::warn!("记录探测 token 消耗失败 ({app_type}/{}): {e}", provider.id);
+            return;
+        }
+        if let Ok(status) = Self::status(db, app_type, provider, config) {
+            if status.state != before && status.state != BudgetState::Normal {
+                events::publish(AppEvent::BudgetWarning(status));
+            }
+        }
+    }
+
+    /// 按检查结果记录消耗：只有拿到成功响应的探测才会被计费
+    pub fn record_result(
+        db: &Database,
+        app_type: &str,
+        provider: &Provider,
+        config: &StreamCheckConfig,
+        result: &StreamCheckResult,
+    ) {
+        if result.success {
+            Self::record_tokens(
+                db,
+                app_type,
+                provider,
+                config,
+                charged_tokens(result.tokens_used),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_state_thresholds() {
+        assert_eq!(budget_state(1_000_000, None), BudgetState::Normal);
+        assert_eq!(budget_state(79, Some(100)), BudgetState::Normal);
+        assert_eq!(budget_state(80, Some(100)), BudgetState::Throttled);
+        assert_eq!(budget_state(100, Some(100)), BudgetState::Exhausted);
+    }
+
+    #[test]
+    fn charges_reported_usage_before_estimate() {
+        assert_eq!(charged_tokens(Some(1_234)), 1_234);
+        assert_eq!(charged_tokens(None), PROBE_TOKENS_ESTIMATE);
+    }
+}
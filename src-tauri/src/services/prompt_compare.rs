@@ -0,0 +1,336 @@
+//! 多供应商提示词对比
+//!
+//! 把同一条提示词发给多个供应商（顺序或并行），返回每个供应商的回复文本、上游报告的模型、
+//! 耗时与 token 用量，并保存到数据库供界面并排展示差异。
+//! 请求经过与代理相同的改写流程（模型映射、认证方案、云厂商签名、自定义请求头），
+//! 便于核实中转站实际提供的是否为其声称的模型。
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::app_config::AppType;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::proxy::forwarder::RequestForwarder;
+use crate::proxy::handler_config::{
+    UsageParserConfig, CLAUDE_PARSER_CONFIG, CODEX_PARSER_CONFIG, GEMINI_PARSER_CONFIG,
+};
+use crate::proxy::usage::parser::TokenUsage;
+use crate::proxy::{shadow, transcript, ProxyError};
+use crate::services::stream_check::StreamCheckService;
+
+const DEFAULT_MAX_TOKENS: u32 = 1024;
+const MAX_PROVIDERS: usize = 8;
+
+/// 对比请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptCompareRequest {
+    pub app_type: AppType,
+    pub provider_ids: Vec<String>,
+    pub prompt: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    /// 为空时使用各供应商的默认测试模型
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    /// 并行发送（默认顺序发送，避免互相影响耗时）
+    #[serde(default)]
+    pub parallel: bool,
+}
+
+/// 单个供应商的输出
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptCompareOutput {
+    pub provider_id: String,
+    pub provider_name: String,
+    pub model_requested: String,
+    /// 上游响应中报告的模型
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_reported: Option<String>,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    pub latency_ms: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub text: String,
+    /// 与第一个成功输出的文本相似度（0~1）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub similarity: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 一次对比及其全部输出
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptComparison {
+    pub id: String,
+    pub app_type: String,
+    pub prompt: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    pub parallel: bool,
+    pub created_at: i64,
+    pub outputs: Vec<PromptCompareOutput>,
+}
+
+pub struct PromptCompareService;
+
+impl PromptCompareService {
+    /// 执行对比并保存结果
+    pub async fn run(
+        db: Arc<Database>,
+        request: PromptCompareRequest,
+    ) -> Result<PromptComparison, AppError> {
+        if request.prompt.trim().is_empty() {
+            return Err(AppError::InvalidInput("提示词不能为空".to_string()));
+        }
+        if request.provider_ids.is_empty() || request.provider_ids.len() > MAX_PROVIDERS {
+            return Err(AppError::InvalidInput(format!(
+                "请选择 1~{MAX_PROVIDERS} 个供应商"
+            )));
+        }
+
+        let app_type_str = request.app_type.as_str();
+        let all = db.get_all_providers(app_type_str)?;
+        let providers = request
+            .provider_ids
+            .iter()
+            .map(|id| {
+                all.get(id)
+                    .cloned()
+                    .ok_or_else(|| AppError::Message(format!("供应商 {id} 不存在")))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let config = db.get_stream_check_config().unwrap_or_default();
+        let forwarder = RequestForwarder::standalone(db.clone(), config.timeout_secs.max(60));
+
+        let mut outputs = if request.parallel {
+            join_all(
+                providers
+                    .iter()
+                    .map(|p| Self::run_one(&forwarder, &request, p, &config)),
+            )
+            .await
+        } else {
+            let mut outputs = Vec::with_capacity(providers.len());
+            for provider in &providers {
+                outputs.push(Self::run_one(&forwarder, &request, provider, &config).await);
+            }
+            outputs
+        };
+        annotate_similarity(&mut outputs);
+
+        let comparison = PromptComparison {
+            id: uuid::Uuid::new_v4().to_string(),
+            app_type: app_type_str.to_string(),
+            prompt: request.prompt,
+            system: request.system,
+            parallel: request.parallel,
+            created_at: chrono::Utc::now().timestamp(),
+            outputs,
+        };
+        db.save_prompt_comparison(&comparison)?;
+        Ok(comparison)
+    }
+
+    async fn run_one(
+        forwarder: &RequestForwarder,
+        request: &PromptCompareRequest,
+        provider: &Provider,
+        config: &crate::services::stream_check::StreamCheckConfig,
+    ) -> PromptCompareOutput {
+        let app_type = &request.app_type;
+        let model = request
+            .model
+            .as_deref()
+            .map(str::trim)
+            .filter(|m| !m.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| StreamCheckService::resolve_test_model(app_type, provider, config));
+        let max_tokens = request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+        let (endpoint, body) = build_request(
+            app_type,
+            &model,
+            &request.prompt,
+            request.system.as_deref(),
+            max_tokens,
+        );
+
+        let mut output = PromptCompareOutput {
+            provider_id: provider.id.clone(),
+            provider_name: provider.name.clone(),
+            model_requested: model,
+            model_reported: None,
+            success: false,
+            http_status: None,
+            latency_ms: 0,
+            input_tokens: 0,
+            output_tokens: 0,
+            text: String::new(),
+            similarity: None,
+            error: None,
+        };
+
+        let start = Instant::now();
+        let result = forwarder
+            .forward_once(
+                app_type,
+                &endpoint,
+                &body,
+                &axum::http::HeaderMap::new(),
+                provider,
+            )
+            .await;
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                output.latency_ms = start.elapsed().as_millis() as u64;
+                if let ProxyError::UpstreamError { status, .. } = &e {
+                    output.http_status = Some(*status);
+                }
+                output.error = Some(e.to_string());
+                return output;
+            }
+        };
+        output.http_status = Some(response.status().as_u16());
+        let json = response.json::<Value>().await;
+        output.latency_ms = start.elapsed().as_millis() as u64;
+        match json {
+            Ok(json) => {
+                output.success = true;
+                output.text = transcript::completion_from_response(&json);
+                output.model_reported = reported_model(&json);
+                if let Some(usage) = parse_usage(app_type, &json) {
+                    output.input_tokens = usage.input_tokens as u64;
+                    output.output_tokens = usage.output_tokens as u64;
+                }
+            }
+            Err(e) => output.error = Some(format!("解析响应失败: {e}")),
+        }
+        output
+    }
+}
+
+/// 按应用构建非流式请求（端点, 请求体）
+fn build_request(
+    app_type: &AppType,
+    model: &str,
+    prompt: &str,
+    system: Option<&str>,
+    max_tokens: u32,
+) -> (String, Value) {
+    let system = system.map(str::trim).filter(|s| !s.is_empty());
+    match app_type {
+        AppType::Claude => {
+            let mut body = json!({
+                "model": model,
+                "max_tokens": max_tokens,
+                "messages": [{ "role": "user", "content": prompt }],
+            });
+            if let Some(system) = system {
+                body["system"] = json!(system);
+            }
+            ("/v1/messages".to_string(), body)
+        }
+        AppType::Codex => {
+            let mut body = json!({
+                "model": model,
+                "input": [{ "role": "user", "content": prompt }],
+                "max_output_tokens": max_tokens,
+                "stream": false,
+            });
+            if let Some(system) = system {
+                body["instructions"] = json!(system);
+            }
+            ("/v1/responses".to_string(), body)
+        }
+        AppType::Gemini => {
+            let mut body = json!({
+                "contents": [{ "role": "user", "parts": [{ "text": prompt }] }],
+                "generationConfig": { "maxOutputTokens": max_tokens },
+            });
+            if let Some(system) = system {
+                body["systemInstruction"] = json!({ "parts": [{ "text": system }] });
+            }
+            (format!("/v1beta/models/{model}:generateContent"), body)
+        }
+    }
+}
+
+fn parser_for(app_type: &AppType) -> &'static UsageParserConfig {
+    match app_type {
+        AppType::Claude => &CLAUDE_PARSER_CONFIG,
+        AppType::Codex => &CODEX_PARSER_CONFIG,
+        AppType::Gemini => &GEMINI_PARSER_CONFIG,
+    }
+}
+
+/// 解析用量；Claude 供应商开启格式转换时响应为 OpenAI 格式
+fn parse_usage(app_type: &AppType, json: &Value) -> Option<TokenUsage> {
+    (parser_for(app_type).response_parser)(json).or_else(|| TokenUsage::from_openai_response(json))
+}
+
+fn reported_model(json: &Value) -> Option<String> {
+    ["model", "modelVersion"]
+        .iter()
+        .find_map(|key| json.get(*key).and_then(|m| m.as_str()))
+        .map(str::to_string)
+}
+
+/// 以第一个成功的输出为基准计算相似度
+fn annotate_similarity(outputs: &mut [PromptCompareOutput]) {
+    let Some(base) = outputs.iter().find(|o| o.success).map(|o| o.text.clone()) else {
+        return;
+    };
+    for output in outputs.iter_mut().filter(|o| o.success) {
+        output.similarity = Some(shadow::similarity(&base, &output.text));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_native_request_per_app() {
+        let (endpoint, body) =
+            build_request(&AppType::Claude, "claude-x", "hi", Some("be brief"), 64);
+        assert_eq!(endpoint, "/v1/messages");
+        assert_eq!(body["system"], "be brief");
+        assert_eq!(body["max_tokens"], 64);
+
+        let (endpoint, body) = build_request(&AppType::Codex, "gpt-x", "hi", None, 64);
+        assert_eq!(endpoint, "/v1/responses");
+        assert!(body.get("instructions").is_none());
+
+        let (endpoint, _) = build_request(&AppType::Gemini, "gemini-x", "hi", Some(" "), 64);
+        assert_eq!(endpoint, "/v1beta/models/gemini-x:generateContent");
+    }
+
+    #[test]
+    fn reads_reported_model_and_usage() {
+        let claude = json!({
+            "model": "claude-sonnet-4",
+            "content": [{ "type": "text", "text": "ok" }],
+            "usage": { "input_tokens": 5, "output_tokens": 2 }
+        });
+        assert_eq!(reported_model(&claude).as_deref(), Some("claude-sonnet-4"));
+        let usage = parse_usage(&AppType::Claude, &claude).unwrap();
+        assert_eq!((usage.input_tokens, usage.output_tokens), (5, 2));
+
+        let gemini = json!({ "modelVersion": "gemini-2.5-pro" });
+        assert_eq!(reported_model(&gemini).as_deref(), Some("gemini-2.5-pro"));
+    }
+}
@@ -0,0 +1,427 @@
+//! 批量导入供应商（CSV / JSON）
+//!
+//! 每行描述一个供应商：name、base_url、key、tags、apps（一行可同时导入到多个应用）。
+//! 逐行校验并与已有供应商（以及同一文件中前面的行）查重，结果汇总为
+//! created / skipped / failed 报告；通过校验的供应商在同一事务中写入。
+//!
+//! CSV 第一行为表头，列名不区分大小写；tags、apps 单元格内用 `;` 或 `|` 分隔。
+//! JSON 为对象数组，或 `{ "providers": [...] }`。
+
+use std::path::Path;
+
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+use crate::app_config::AppType;
+use crate::deeplink::{build_provider_from_request, DeepLinkImportRequest};
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::proxy::endpoint_template::normalize_base_url;
+
+use super::DuplicateMatch;
+
+/// 导入文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Csv,
+    Json,
+}
+
+impl ImportFormat {
+    /// 按扩展名判断，无法判断时按内容（以 `[` / `{` 开头视为 JSON）
+    pub fn detect(path: Option<&Path>, text: &str) -> Self {
+        let ext = path
+            .and_then(|p| p.extension())
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("csv") | Some("tsv") => Self::Csv,
+            Some("json") => Self::Json,
+            _ if text.trim_start().starts_with(['[', '{']) => Self::Json,
+            _ => Self::Csv,
+        }
+    }
+}
+
+/// 导入文件中的一行
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRow {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, alias = "base_url", alias = "endpoint", alias = "url")]
+    pub base_url: String,
+    #[serde(default, alias = "key", alias = "api_key")]
+    pub api_key: String,
+    #[serde(default, deserialize_with = "list_or_string")]
+    pub tags: Vec<String>,
+    #[serde(
+        default,
+        alias = "app",
+        alias = "appTypes",
+        alias = "app_types",
+        deserialize_with = "list_or_string"
+    )]
+    pub apps: Vec<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default, alias = "website_url", alias = "homepage")]
+    pub website_url: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+/// 拆分 `a;b|c` 形式的列表
+fn split_list(raw: &str) -> Vec<String> {
+    raw.split([';', '|', ','])
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 同时接受字符串数组与分隔字符串
+fn list_or_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Null => Ok(Vec::new()),
+        Value::String(s) => Ok(split_list(&s)),
+        Value::Array(items) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::String(s) => Ok(s.trim().to_string()),
+                other => Err(de::Error::custom(format!("应为字符串: {other}"))),
+            })
+            .filter(|item| !matches!(item, Ok(s) if s.is_empty()))
+            .collect(),
+        other => Err(de::Error::custom(format!("应为字符串或数组: {other}"))),
+    }
+}
+
+/// 解析 CSV 记录（支持双引号包裹、`""` 转义与单元格内换行）
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let delimiter = match text.lines().next() {
+        Some(header) if header.contains('\t') && !header.contains(',') => '\t',
+        _ => ',',
+    };
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            c if c == delimiter => record.push(std::mem::take(&mut field)),
+            '\r' => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records.retain(|r| r.iter().any(|f| !f.trim().is_empty()));
+    records
+}
+
+fn csv_rows(text: &str) -> Result<Vec<ImportRow>, AppError> {
+    let mut records = parse_csv(text).into_iter();
+    let header: Vec<String> = records
+        .next()
+        .ok_or_else(|| AppError::InvalidInput("CSV 文件为空".to_string()))?
+        .iter()
+        .map(|h| h.trim().to_ascii_lowercase().replace([' ', '-'], "_"))
+        .collect();
+    if !header.iter().any(|h| h == "name") {
+        return Err(AppError::InvalidInput("CSV 表头缺少 name 列".to_string()));
+    }
+
+    Ok(records
+        .map(|record| {
+            let mut row = ImportRow::default();
+            for (column, value) in header.iter().zip(record) {
+                let value = value.trim().to_string();
+                match column.as_str() {
+                    "name" => row.name = value,
+                    "base_url" | "baseurl" | "endpoint" | "url" => row.base_url = value,
+                    "key" | "api_key" | "apikey" => row.api_key = value,
+                    "tags" => row.tags = split_list(&value),
+                    "apps" | "app" | "app_types" => row.apps = split_list(&value),
+                    "model" => row.model = Some(value).filter(|v| !v.is_empty()),
+                    "website_url" | "homepage" => {
+                        row.website_url = Some(value).filter(|v| !v.is_empty())
+                    }
+                    "notes" => row.notes = Some(value).filter(|v| !v.is_empty()),
+                    _ => {}
+                }
+            }
+            row
+        })
+        .collect())
+}
+
+fn json_rows(text: &str) -> Result<Vec<ImportRow>, AppError> {
+    let value: Value = serde_json::from_str(text)
+        .map_err(|e| AppError::InvalidInput(format!("JSON 解析失败: {e}")))?;
+    let items = match value {
+        Value::Array(items) => items,
+        Value::Object(mut map) => match map.remove("providers") {
+            Some(Value::Array(items)) => items,
+            _ => {
+                return Err(AppError::InvalidInput(
+                    "JSON 缺少 providers 数组".to_string(),
+                ))
+            }
+        },
+        _ => return Err(AppError::InvalidInput("JSON 应为供应商数组".to_string())),
+    };
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| {
+            serde_json::from_value(item)
+                .map_err(|e| AppError::InvalidInput(format!("第 {} 行格式错误: {e}", index + 1)))
+        })
+        .collect()
+}
+
+/// 解析导入文件
+pub fn parse_rows(text: &str, format: ImportFormat) -> Result<Vec<ImportRow>, AppError> {
+    match format {
+        ImportFormat::Csv => csv_rows(text),
+        ImportFormat::Json => json_rows(text),
+    }
+}
+
+/// 按行构造各应用的候选供应商（尚未做保存前的规范化与查重）
+pub(super) fn build_candidates(row: &ImportRow) -> Result<Vec<(AppType, Provider)>, String> {
+    let name = row.name.trim();
+    if name.is_empty() {
+        return Err("缺少名称".to_string());
+    }
+    let base_url = normalize_base_url(&row.base_url)?;
+    if base_url.is_empty() {
+        return Err("缺少 Base URL".to_string());
+    }
+    let api_key = row.api_key.trim();
+    if api_key.is_empty() {
+        return Err("缺少 API Key".to_string());
+    }
+    if row.apps.is_empty() {
+        return Err("缺少应用类型（apps）".to_string());
+    }
+    let mut apps = Vec::new();
+    for app in &row.apps {
+        let app_type = app
+            .parse::<AppType>()
+            .map_err(|_| format!("未知的应用类型: {app}"))?;
+        if !apps.contains(&app_type) {
+            apps.push(app_type);
+        }
+    }
+
+    let request = DeepLinkImportRequest {
+        name: Some(name.to_string()),
+        endpoint: Some(base_url),
+        api_key: Some(api_key.to_string()),
+        model: row.model.clone().filter(|m| !m.trim().is_empty()),
+        homepage: row.website_url.clone(),
+        notes: row.notes.clone(),
+        ..Default::default()
+    };
+    apps.into_iter()
+        .map(|app_type| {
+            let mut provider =
+                build_provider_from_request(&app_type, &request).map_err(|e| e.to_string())?;
+            provider.id = uuid::Uuid::new_v4().to_string();
+            provider.created_at = Some(chrono::Utc::now().timestamp_millis());
+            if !row.tags.is_empty() {
+                provider.meta.get_or_insert_with(Default::default).tags = row.tags.clone();
+            }
+            Ok((app_type, provider))
+        })
+        .collect()
+}
+
+/// 单行（单个应用）的导入结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportRowStatus {
+    Created,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRowResult {
+    /// 数据行号（从 1 开始，不含表头）
+    pub row: usize,
+    /// 行级错误（如缺少字段）时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    pub name: String,
+    pub status: ImportRowStatus,
+    /// created：新供应商 ID；skipped：与之重复的已有供应商 ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// 批量导入报告
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkImportReport {
+    /// 仅校验，未写入
+    pub dry_run: bool,
+    pub created: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    pub rows: Vec<ImportRowResult>,
+}
+
+impl BulkImportReport {
+    pub(super) fn new(dry_run: bool) -> Self {
+        Self {
+            dry_run,
+            ..Default::default()
+        }
+    }
+
+    pub(super) fn created(&mut self, row: usize, app_type: &AppType, provider: &Provider) {
+        self.created += 1;
+        self.rows.push(ImportRowResult {
+            row,
+            app: Some(app_type.as_str().to_string()),
+            name: provider.name.clone(),
+            status: ImportRowStatus::Created,
+            provider_id: Some(provider.id.clone()),
+            message: None,
+        });
+    }
+
+    pub(super) fn skipped(
+        &mut self,
+        row: usize,
+        app_type: &AppType,
+        name: &str,
+        dup: DuplicateMatch,
+    ) {
+        self.skipped += 1;
+        self.rows.push(ImportRowResult {
+            row,
+            app: Some(app_type.as_str().to_string()),
+            name: name.to_string(),
+            status: ImportRowStatus::Skipped,
+            message: Some(format!("与已有供应商「{}」重复", dup.name)),
+            provider_id: Some(dup.provider_id),
+        });
+    }
+
+    pub(super) fn failed(
+        &mut self,
+        row: usize,
+        app_type: Option<&AppType>,
+        name: &str,
+        message: String,
+    ) {
+        self.failed += 1;
+        self.rows.push(ImportRowResult {
+            row,
+            app: app_type.map(|a| a.as_str().to_string()),
+            name: name.to_string(),
+            status: ImportRowStatus::Failed,
+            provider_id: None,
+            message: Some(message),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_csv_with_quotes_and_lists() {
+        let text = "\u{feff}Name,Base URL,Key,Tags,Apps\n\
+            \"Relay, Inc\",https://relay.example/,sk-1,team;cheap,claude|codex\n\
+            \n\
+            Other,https://other.example,sk-2,,gemini\n";
+        let rows = parse_rows(text, ImportFormat::detect(None, text)).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].name, "Relay, Inc");
+        assert_eq!(rows[0].base_url, "https://relay.example/");
+        assert_eq!(rows[0].tags, vec!["team", "cheap"]);
+        assert_eq!(rows[0].apps, vec!["claude", "codex"]);
+        assert!(rows[1].tags.is_empty());
+    }
+
+    #[test]
+    fn parses_json_with_aliases() {
+        let text = r#"{"providers": [
+            {"name": "A", "base_url": "https://a.example", "key": "sk-a", "tags": ["x"], "apps": "claude,gemini"},
+            {"name": "B", "baseUrl": "https://b.example", "apiKey": "sk-b", "app": ["codex"]}
+        ]}"#;
+        assert_eq!(ImportFormat::detect(None, text), ImportFormat::Json);
+        let rows = parse_rows(text, ImportFormat::Json).unwrap();
+        assert_eq!(rows[0].api_key, "sk-a");
+        assert_eq!(rows[0].apps, vec!["claude", "gemini"]);
+        assert_eq!(rows[1].base_url, "https://b.example");
+        assert_eq!(rows[1].apps, vec!["codex"]);
+    }
+
+    #[test]
+    fn builds_one_provider_per_app_and_reports_row_errors() {
+        let row = ImportRow {
+            name: "Relay".to_string(),
+            base_url: "https://relay.example/".to_string(),
+            api_key: "sk-1".to_string(),
+            tags: vec!["team".to_string()],
+            apps: vec![
+                "claude".to_string(),
+                "codex".to_string(),
+                "claude".to_string(),
+            ],
+            ..Default::default()
+        };
+        let candidates = build_candidates(&row).unwrap();
+        assert_eq!(candidates.len(), 2);
+        let (app, claude) = &candidates[0];
+        assert_eq!(*app, AppType::Claude);
+        assert_eq!(
+            claude.settings_config["env"]["ANTHROPIC_BASE_URL"],
+            "https://relay.example"
+        );
+        assert_eq!(claude.meta.as_ref().unwrap().tags, vec!["team"]);
+        assert_ne!(candidates[0].1.id, candidates[1].1.id);
+
+        let missing_key = ImportRow {
+            api_key: " ".to_string(),
+            ..row.clone()
+        };
+        assert_eq!(build_candidates(&missing_key).unwrap_err(), "缺少 API Key");
+        let bad_app = ImportRow {
+            apps: vec!["cursor".to_string()],
+            ..row
+        };
+        assert!(build_candidates(&bad_app).unwrap_err().contains("cursor"));
+    }
+}
@@ -0,0 +1,176 @@
+//! 供应商能力标记与切换前校验
+//!
+//! 中转站常常只实现了部分接口能力（工具调用、图片输入、提示缓存、长上下文）。
+//! 能力记录在 ProviderMeta.capabilities，切换前与目标应用的要求对比并给出提示；
+//! 未声明的能力视为未知，不产生提示。
+
+use serde::{Deserialize, Serialize};
+
+use crate::app_config::AppType;
+use crate::i18n::{self, Locale};
+use crate::provider::Provider;
+
+/// 供应商能力（未设置表示未知）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderCapabilities {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_use: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vision: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_caching: Option<bool>,
+    /// 最大上下文窗口（tokens）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_context_tokens: Option<u64>,
+}
+
+impl ProviderCapabilities {
+    pub fn of(provider: &Provider) -> Option<&Self> {
+        provider.meta.as_ref().and_then(|m| m.capabilities.as_ref())
+    }
+}
+
+/// 提示级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CapabilitySeverity {
+    /// 缺少后应用核心功能不可用
+    Error,
+    /// 缺少后体验或费用明显变差
+    Warning,
+}
+
+/// 切换前的能力提示
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CapabilityWarning {
+    /// 能力名（toolUse / vision / promptCaching / contextWindow）
+    pub capability: String,
+    pub severity: CapabilitySeverity,
+    pub message: String,
+}
+
+/// 应用对供应商能力的要求
+struct AppRequirements {
+    name: &'static str,
+    tool_use: bool,
+    vision: bool,
+    prompt_caching: bool,
+    min_context_tokens: u64,
+}
+
+fn requirements(app_type: &AppType) -> AppRequirements {
+    match app_type {
+        AppType::Claude => AppRequirements {
+            name: "Claude Code",
+            tool_use: true,
+            vision: true,
+            prompt_caching: true,
+            min_context_tokens: 200_000,
+        },
+        AppType::Codex => AppRequirements {
+            name: "Codex",
+            tool_use: true,
+            vision: false,
+            prompt_caching: false,
+            min_context_tokens: 128_000,
+        },
+        AppType::Gemini => AppRequirements {
+            name: "Gemini CLI",
+            tool_use: true,
+            vision: false,
+            prompt_caching: false,
+            min_context_tokens: 128_000,
+        },
+    }
+}
+
+/// 对比供应商能力与目标应用的要求
+pub fn check_capabilities(
+    app_type: &AppType,
+    provider: &Provider,
+    locale: Locale,
+) -> Vec<CapabilityWarning> {
+    let Some(caps) = ProviderCapabilities::of(provider) else {
+        return Vec::new();
+    };
+    let req = requirements(app_type);
+    let mut warnings = Vec::new();
+    let mut push = |capability: &str, severity, args: &[(&str, &str)]| {
+        let mut all_args = vec![("app", req.name)];
+        all_args.extend_from_slice(args);
+        warnings.push(CapabilityWarning {
+            capability: capability.to_string(),
+            severity,
+            message: i18n::tr_with(locale, &format!("capability.{capability}"), &all_args),
+        });
+    };
+
+    if req.tool_use && caps.tool_use == Some(false) {
+        push("toolUse", CapabilitySeverity::Error, &[]);
+    }
+    if req.vision && caps.vision == Some(false) {
+        push("vision", CapabilitySeverity::Warning, &[]);
+    }
+    if req.prompt_caching && caps.prompt_caching == Some(false) {
+        push("promptCaching", CapabilitySeverity::Warning, &[]);
+    }
+    if let Some(max) = caps.max_context_tokens {
+        if max < req.min_context_tokens {
+            push(
+                "contextWindow",
+                CapabilitySeverity::Warning,
+                &[
+                    ("actual", &max.to_string()),
+                    ("required", &req.min_context_tokens.to_string()),
+                ],
+            );
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::ProviderMeta;
+    use serde_json::json;
+
+    fn provider(caps: Option<ProviderCapabilities>) -> Provider {
+        let mut provider = Provider::with_id("p".into(), "P".into(), json!({}), None);
+        provider.meta = Some(ProviderMeta {
+            capabilities: caps,
+            ..ProviderMeta::default()
+        });
+        provider
+    }
+
+    #[test]
+    fn unknown_capabilities_produce_no_warnings() {
+        assert!(check_capabilities(&AppType::Claude, &provider(None), Locale::Zh).is_empty());
+    }
+
+    #[test]
+    fn missing_tool_use_is_an_error_for_claude() {
+        let caps = ProviderCapabilities {
+            tool_use: Some(false),
+            vision: Some(false),
+            max_context_tokens: Some(32_000),
+            ..ProviderCapabilities::default()
+        };
+        let warnings =
+            check_capabilities(&AppType::Claude, &provider(Some(caps.clone())), Locale::En);
+        let names: Vec<&str> = warnings.iter().map(|w| w.capability.as_str()).collect();
+        assert_eq!(names, ["toolUse", "vision", "contextWindow"]);
+        assert_eq!(warnings[0].severity, CapabilitySeverity::Error);
+        assert!(warnings[0]
+            .message
+            .contains("Claude Code agents will break"));
+        assert!(warnings[2].message.contains("32000"));
+
+        // Codex 不要求图片输入
+        let codex = check_capabilities(&AppType::Codex, &provider(Some(caps)), Locale::En);
+        assert!(codex.iter().all(|w| w.capability != "vision"));
+    }
+}
@@ -0,0 +1,191 @@
+//! 按应用的原生文件格式导出供应商配置
+//!
+//! 与切换时写入 live 配置的内容一致，但不落到应用目录，可导出任意供应商（不限当前），
+//! 便于把现成的配置交给不使用 cc-switch 的同事：
+//! - Claude：`settings.json`
+//! - Codex：`auth.json` + `config.toml`
+//! - Gemini：`.env`（以及 config 为对象时的 `settings.json`）
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::gemini_auth::{detect_gemini_auth_type, GeminiAuthType};
+use super::transfer::strip_secrets;
+use crate::app_config::AppType;
+use crate::config::write_text_file;
+use crate::error::AppError;
+use crate::provider::Provider;
+
+/// 导出的单个配置文件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedConfigFile {
+    /// 应用期望的文件名
+    pub file_name: String,
+    pub content: String,
+    /// 写入磁盘时的完整路径
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+impl ExportedConfigFile {
+    fn new(file_name: &str, content: String) -> Self {
+        Self {
+            file_name: file_name.to_string(),
+            content,
+            path: None,
+        }
+    }
+}
+
+fn pretty_json(value: &Value) -> Result<String, AppError> {
+    serde_json::to_string_pretty(value)
+        .map(|text| text + "\n")
+        .map_err(|e| AppError::JsonSerialize { source: e })
+}
+
+/// 渲染供应商对应的配置文件；`include_key` 为 false 时清空 API Key 等凭据
+pub fn render(
+    app_type: &AppType,
+    provider: &Provider,
+    include_key: bool,
+) -> Result<Vec<ExportedConfigFile>, AppError> {
+    let mut settings = provider.settings_config.clone();
+    if !include_key {
+        strip_secrets(&mut settings);
+    }
+
+    match app_type {
+        AppType::Claude => {
+            if let Some(obj) = settings.as_object_mut() {
+                obj.remove("custom_headers");
+            }
+            Ok(vec![ExportedConfigFile::new(
+                "settings.json",
+                pretty_json(&settings)?,
+            )])
+        }
+        AppType::Codex => {
+            let auth = settings
+                .get("auth")
+                .ok_or_else(|| AppError::Config("Codex 供应商配置缺少 'auth' 字段".to_string()))?;
+            let config = settings
+                .get("config")
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| {
+                    AppError::Config("Codex 供应商配置缺少 'config' 字段或不是字符串".to_string())
+                })?;
+            crate::codex_config::validate_config_toml(config)?;
+            let mut config = config.to_string();
+            if !config.is_empty() && !config.ends_with('\n') {
+                config.push('\n');
+            }
+            Ok(vec![
+                ExportedConfigFile::new("auth.json", pretty_json(auth)?),
+                ExportedConfigFile::new("config.toml", config),
+            ])
+        }
+        AppType::Gemini => {
+            let mut env = crate::gemini_config::json_to_env(&settings)?;
+            if matches!(
+                detect_gemini_auth_type(provider),
+                GeminiAuthType::GoogleOfficial
+            ) {
+                // Google 官方使用 OAuth，不需要环境变量
+                env.clear();
+            }
+            let mut files = vec![ExportedConfigFile::new(
+                ".env",
+                crate::gemini_config::serialize_env_file(&env),
+            )];
+            if let Some(config) = settings.get("config").filter(|v| v.is_object()) {
+                files.push(ExportedConfigFile::new(
+                    "settings.json",
+                    pretty_json(config)?,
+                ));
+            }
+            Ok(files)
+        }
+    }
+}
+
+/// 把导出的文件写到 `target`
+///
+/// 只有一个文件且 `target` 不是已有目录时直接写入该路径，否则视为目录，按文件名写入其中。
+pub fn write_to(files: &mut [ExportedConfigFile], target: &Path) -> Result<(), AppError> {
+    let single_file = files.len() == 1 && !target.is_dir();
+    for file in files.iter_mut() {
+        let path: PathBuf = if single_file {
+            target.to_path_buf()
+        } else {
+            target.join(&file.file_name)
+        };
+        write_text_file(&path, &file.content)?;
+        file.path = Some(path.to_string_lossy().to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn codex() -> Provider {
+        Provider::with_id(
+            "relay".into(),
+            "Relay".into(),
+            json!({
+                "auth": { "OPENAI_API_KEY": "sk-secret-value" },
+                "config": "model_provider = \"relay\"\nmodel = \"gpt-5\"\n\n[model_providers.relay]\nbase_url = \"https://relay.example/v1\""
+            }),
+            None,
+        )
+    }
+
+    #[test]
+    fn codex_renders_auth_and_toml() {
+        let files = render(&AppType::Codex, &codex(), true).unwrap();
+        assert_eq!(files[0].file_name, "auth.json");
+        let auth: Value = serde_json::from_str(&files[0].content).unwrap();
+        assert_eq!(auth["OPENAI_API_KEY"], "sk-secret-value");
+        assert_eq!(files[1].file_name, "config.toml");
+        assert!(files[1].content.ends_with("relay.example/v1\"\n"));
+
+        let stripped = render(&AppType::Codex, &codex(), false).unwrap();
+        let auth: Value = serde_json::from_str(&stripped[0].content).unwrap();
+        assert_eq!(auth["OPENAI_API_KEY"], "");
+    }
+
+    #[test]
+    fn claude_drops_proxy_only_fields_and_writes_to_path() {
+        let provider = Provider::with_id(
+            "p".into(),
+            "P".into(),
+            json!({
+                "env": { "ANTHROPIC_BASE_URL": "https://relay.example" },
+                "custom_headers": { "X-Team": "a" }
+            }),
+            None,
+        );
+        let mut files = render(&AppType::Claude, &provider, true).unwrap();
+        let settings: Value = serde_json::from_str(&files[0].content).unwrap();
+        assert!(settings.get("custom_headers").is_none());
+        assert_eq!(
+            settings["env"]["ANTHROPIC_BASE_URL"],
+            "https://relay.example"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("colleague.json");
+        write_to(&mut files, &target).unwrap();
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), files[0].content);
+
+        let mut files = render(&AppType::Codex, &codex(), true).unwrap();
+        write_to(&mut files, dir.path()).unwrap();
+        assert!(dir.path().join("auth.json").exists());
+        assert!(dir.path().join("config.toml").exists());
+    }
+}
@@ -0,0 +1,136 @@
+//! 启动时的 Live 配置一致性检查
+//!
+//! 用户可能绕过 cc-switch 直接修改 Claude / Codex 的配置文件，导致数据库中的当前供应商
+//! 与 Live 文件实际使用的地址、Key 不一致。启动时比较两者的 Base URL 与 Key 哈希，
+//! 不一致时通过 `live-config-drift` 事件提示用户选择以哪一边为准。
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::duplicates::Fingerprint;
+use crate::app_config::AppType;
+use crate::provider::Provider;
+
+/// 当前供应商与 Live 配置不一致
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveDrift {
+    pub app_type: String,
+    /// 数据库中的当前供应商
+    pub provider_id: String,
+    pub provider_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_base_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub live_base_url: Option<String>,
+    pub base_url_matches: bool,
+    pub key_matches: bool,
+    /// Live 配置与另一个已有供应商一致时的供应商 ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub live_provider_id: Option<String>,
+}
+
+/// 以哪一边为准解决不一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LiveReconcileAction {
+    /// 用当前供应商重新写入 Live 配置
+    WriteLive,
+    /// 采用 Live 配置：与已有供应商一致时切换为该供应商，否则写回当前供应商
+    AdoptLive,
+}
+
+/// 比较当前供应商与 Live 配置，一致时返回 None
+///
+/// 只比较双方都能提取到的字段；Live 中缺少地址或 Key 时视为一致，避免误报。
+pub fn compare(
+    app_type: &AppType,
+    current: &Provider,
+    live_settings: &Value,
+    providers: &IndexMap<String, Provider>,
+) -> Option<LiveDrift> {
+    let mut live = Provider::with_id(String::new(), String::new(), live_settings.clone(), None);
+    live.meta = current.meta.clone();
+    let expected = Fingerprint::of(app_type, current);
+    let actual = Fingerprint::of(app_type, &live);
+
+    let same = |a: &Option<String>, b: &Option<String>| match (a, b) {
+        (Some(a), Some(b)) => a == b,
+        _ => true,
+    };
+    let base_url_matches = same(&expected.base_url, &actual.base_url);
+    let key_matches = same(&expected.key_hash, &actual.key_hash);
+    if base_url_matches && key_matches {
+        return None;
+    }
+
+    let live_provider_id = providers
+        .values()
+        .filter(|p| p.id != current.id)
+        .find(|p| {
+            let other = Fingerprint::of(app_type, p);
+            other.base_url.is_some()
+                && other.base_url == actual.base_url
+                && other.key_hash == actual.key_hash
+        })
+        .map(|p| p.id.clone());
+
+    Some(LiveDrift {
+        app_type: app_type.as_str().to_string(),
+        provider_id: current.id.clone(),
+        provider_name: current.name.clone(),
+        expected_base_url: expected.base_url,
+        live_base_url: actual.base_url,
+        base_url_matches,
+        key_matches,
+        live_provider_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn claude_settings(url: &str, key: &str) -> Value {
+        json!({
+            "env": {
+                "ANTHROPIC_BASE_URL": url,
+                "ANTHROPIC_AUTH_TOKEN": key
+            }
+        })
+    }
+
+    fn claude(id: &str, url: &str, key: &str) -> Provider {
+        Provider::with_id(id.into(), id.into(), claude_settings(url, key), None)
+    }
+
+    #[test]
+    fn detects_drift_and_matching_provider() {
+        let current = claude("a", "https://a.example", "sk-a");
+        let mut providers = IndexMap::new();
+        providers.insert("a".to_string(), current.clone());
+        providers.insert("b".to_string(), claude("b", "https://b.example/", "sk-b"));
+
+        let same = claude_settings("https://a.example/", "sk-a");
+        assert!(compare(&AppType::Claude, &current, &same, &providers).is_none());
+
+        let edited = claude_settings("https://b.example", "sk-b");
+        let drift = compare(&AppType::Claude, &current, &edited, &providers).unwrap();
+        assert!(!drift.base_url_matches);
+        assert!(!drift.key_matches);
+        assert_eq!(drift.live_provider_id.as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn key_change_alone_is_drift() {
+        let current = claude("a", "https://a.example", "sk-a");
+        let providers = IndexMap::new();
+        let rotated = claude_settings("https://a.example", "sk-new");
+        let drift = compare(&AppType::Claude, &current, &rotated, &providers).unwrap();
+        assert!(drift.base_url_matches);
+        assert!(!drift.key_matches);
+        assert!(drift.live_provider_id.is_none());
+    }
+}
@@ -0,0 +1,156 @@
+//! 重复供应商检测
+//!
+//! 新建或导入供应商前，与同一应用下已有供应商比较：
+//! - 相同的 Base URL（忽略大小写和末尾斜杠）且 API Key 相同（比较哈希）
+//! - 相同的名称（忽略大小写和首尾空白）
+//!
+//! 检测到重复时由用户决定是否合并，合并逻辑见 `ProviderService::merge_duplicate`。
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::app_config::AppType;
+use crate::provider::Provider;
+use crate::proxy::providers::get_adapter;
+
+/// 判定为重复的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DuplicateReason {
+    /// Base URL 与 API Key 都相同
+    SameEndpointAndKey,
+    /// 名称相同
+    SameName,
+}
+
+/// 疑似重复的已有供应商
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateMatch {
+    pub provider_id: String,
+    pub name: String,
+    pub reasons: Vec<DuplicateReason>,
+}
+
+/// 供应商的比较指纹（同时用于启动时的 Live 配置一致性检查）
+pub(super) struct Fingerprint {
+    pub(super) name: String,
+    pub(super) base_url: Option<String>,
+    pub(super) key_hash: Option<String>,
+}
+
+impl Fingerprint {
+    pub(super) fn of(app_type: &AppType, provider: &Provider) -> Self {
+        let adapter = get_adapter(app_type);
+        let base_url = adapter
+            .extract_base_url(provider)
+            .ok()
+            .map(|url| url.trim().trim_end_matches('/').to_ascii_lowercase())
+            .filter(|url| !url.is_empty());
+        let key_hash = adapter
+            .extract_auth(provider)
+            .map(|auth| auth.api_key)
+            .filter(|key| !key.trim().is_empty())
+            .map(|key| hex::encode(Sha256::digest(key.trim().as_bytes())));
+        Self {
+            name: provider.name.trim().to_lowercase(),
+            base_url,
+            key_hash,
+        }
+    }
+
+    fn reasons(&self, other: &Self) -> Vec<DuplicateReason> {
+        let mut reasons = Vec::new();
+        if self.base_url.is_some()
+            && self.key_hash.is_some()
+            && self.base_url == other.base_url
+            && self.key_hash == other.key_hash
+        {
+            reasons.push(DuplicateReason::SameEndpointAndKey);
+        }
+        if !self.name.is_empty() && self.name == other.name {
+            reasons.push(DuplicateReason::SameName);
+        }
+        reasons
+    }
+}
+
+/// 在已有供应商中查找与候选供应商疑似重复的条目（跳过 ID 相同的条目）
+pub fn find_duplicates(
+    app_type: &AppType,
+    candidate: &Provider,
+    existing: &IndexMap<String, Provider>,
+) -> Vec<DuplicateMatch> {
+    let target = Fingerprint::of(app_type, candidate);
+    existing
+        .values()
+        .filter(|p| p.id != candidate.id)
+        .filter_map(|p| {
+            let reasons = target.reasons(&Fingerprint::of(app_type, p));
+            (!reasons.is_empty()).then(|| DuplicateMatch {
+                provider_id: p.id.clone(),
+                name: p.name.clone(),
+                reasons,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn claude(id: &str, name: &str, url: &str, key: &str) -> Provider {
+        Provider::with_id(
+            id.into(),
+            name.into(),
+            json!({
+                "env": {
+                    "ANTHROPIC_BASE_URL": url,
+                    "ANTHROPIC_AUTH_TOKEN": key
+                }
+            }),
+            None,
+        )
+    }
+
+    #[test]
+    fn detects_same_endpoint_and_key() {
+        let mut existing = IndexMap::new();
+        existing.insert(
+            "a".to_string(),
+            claude("a", "Relay", "https://relay.example/", "sk-1"),
+        );
+        existing.insert(
+            "b".to_string(),
+            claude("b", "Other", "https://relay.example", "sk-2"),
+        );
+
+        let candidate = claude("", "Relay Copy", "HTTPS://relay.example", "sk-1");
+        let matches = find_duplicates(&AppType::Claude, &candidate, &existing);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].provider_id, "a");
+        assert_eq!(
+            matches[0].reasons,
+            vec![DuplicateReason::SameEndpointAndKey]
+        );
+    }
+
+    #[test]
+    fn detects_same_name_and_skips_self() {
+        let mut existing = IndexMap::new();
+        existing.insert(
+            "a".to_string(),
+            claude("a", "Relay", "https://a.example", "sk-1"),
+        );
+
+        let renamed = claude("new", " relay ", "https://b.example", "sk-2");
+        let matches = find_duplicates(&AppType::Claude, &renamed, &existing);
+        assert_eq!(matches[0].reasons, vec![DuplicateReason::SameName]);
+
+        let same = claude("a", "Relay", "https://a.example", "sk-1");
+        assert!(find_duplicates(&AppType::Claude, &same, &existing).is_empty());
+    }
+}
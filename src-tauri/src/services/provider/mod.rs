@@ -2,25 +2,51 @@
 //!
 //! Handles provider CRUD operations, switching, and configuration management.
 
+mod bulk_import;
+mod capabilities;
+mod config_export;
+mod consistency;
+mod duplicates;
 mod endpoints;
 mod gemini_auth;
 mod live;
+mod switch_preview;
+pub mod switch_queue;
+mod sync_merge;
+mod transfer;
 mod usage;
 
+use std::path::Path;
+
 use indexmap::IndexMap;
 use regex::Regex;
 use serde::Deserialize;
 use serde_json::Value;
 
 use crate::app_config::AppType;
-use crate::error::AppError;
+use crate::database::RecentProvider;
+use crate::error::{AppError, FieldError};
+use crate::events::{self, AppEvent, ProviderSwitchedPayload, SyncConflictPayload};
+use crate::i18n::Locale;
 use crate::provider::{Provider, UsageResult};
+use crate::proxy::endpoint_template::normalize_base_url;
+use crate::services::config_lock;
 use crate::services::mcp::McpService;
+use crate::services::vault;
 use crate::settings::CustomEndpoint;
 use crate::store::AppState;
 
 // Re-export sub-module functions for external access
+pub use bulk_import::{BulkImportReport, ImportFormat};
+pub use capabilities::{check_capabilities, CapabilityWarning, ProviderCapabilities};
+pub use config_export::ExportedConfigFile;
+pub use consistency::{LiveDrift, LiveReconcileAction};
+pub use duplicates::DuplicateMatch;
 pub use live::{import_default_config, read_live_settings, sync_current_to_live};
+pub use switch_preview::SwitchPreview;
+pub use switch_queue::{SwitchOutcome, SwitchQueueStatus};
+pub use sync_merge::{ConflictResolution, ProviderMergeResult};
+pub use transfer::TransferredProvider;
 
 // Internal re-exports (pub(crate))
 pub(crate) use live::write_live_snapshot;
@@ -29,6 +55,9 @@ pub(crate) use live::write_live_snapshot;
 use live::write_gemini_live;
 use usage::validate_usage_script;
 
+/// 查询最近使用列表时读取的历史条数（多取一些，抵消已删除的供应商）
+const RECENT_LOOKUP_LIMIT: usize = 50;
+
 /// Provider business logic service
 pub struct ProviderService;
 
@@ -83,6 +112,80 @@ impl ProviderService {
         }
     }
 
+    /// 规范化 Base URL（去除末尾斜杠、统一协议大小写），协议缺失等明显错误直接拒绝保存
+    fn normalize_base_url(app_type: &AppType, provider: &mut Provider) -> Result<(), AppError> {
+        let normalize = |raw: &str| {
+            normalize_base_url(raw).map_err(|message| {
+                AppError::Validation(vec![FieldError {
+                    field: "baseUrl".to_string(),
+                    message,
+                }])
+            })
+        };
+
+        match app_type {
+            AppType::Claude | AppType::Gemini => {
+                let key = if matches!(app_type, AppType::Claude) {
+                    "ANTHROPIC_BASE_URL"
+                } else {
+                    "GOOGLE_GEMINI_BASE_URL"
+                };
+                if let Some(value) = provider
+                    .settings_config
+                    .get_mut("env")
+                    .and_then(|env| env.get_mut(key))
+                {
+                    if let Some(raw) = value.as_str() {
+                        *value = Value::String(normalize(raw)?);
+                    }
+                }
+            }
+            AppType::Codex => {
+                let Some(config) = provider.settings_config.get_mut("config") else {
+                    return Ok(());
+                };
+                let Some(Ok(mut doc)) =
+                    config.as_str().map(|c| c.parse::<toml_edit::DocumentMut>())
+                else {
+                    return Ok(());
+                };
+
+                // 顶层 base_url 与 [model_providers.*] 下的 base_url
+                let mut changed = false;
+                let mut fix = |item: &mut toml_edit::Item| -> Result<(), AppError> {
+                    if let Some(raw) = item.as_str() {
+                        let normalized = normalize(raw)?;
+                        if normalized != raw {
+                            *item = toml_edit::value(normalized);
+                            changed = true;
+                        }
+                    }
+                    Ok(())
+                };
+                if let Some(item) = doc.get_mut("base_url") {
+                    fix(item)?;
+                }
+                if let Some(providers) = doc
+                    .get_mut("model_providers")
+                    .and_then(|p| p.as_table_like_mut())
+                {
+                    for (_, table) in providers.iter_mut() {
+                        if let Some(item) = table
+                            .as_table_like_mut()
+                            .and_then(|t| t.get_mut("base_url"))
+                        {
+                            fix(item)?;
+                        }
+                    }
+                }
+                if changed {
+                    *config = Value::String(doc.to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// List all providers for an app type
     pub fn list(
         state: &AppState,
@@ -103,10 +206,13 @@ impl ProviderService {
 
     /// Add a new provider
     pub fn add(state: &AppState, app_type: AppType, provider: Provider) -> Result<bool, AppError> {
+        config_lock::ensure_unlocked(&state.db)?;
         let mut provider = provider;
         // Normalize Claude model keys
         Self::normalize_provider_if_claude(&app_type, &mut provider);
+        Self::normalize_base_url(&app_type, &mut provider)?;
         Self::validate_provider_settings(&app_type, &provider)?;
+        vault::store_cloud_credentials(&app_type, &mut provider)?;
 
         // Save to database
         state.db.save_provider(app_type.as_str(), &provider)?;
@@ -130,10 +236,13 @@ impl ProviderService {
         app_type: AppType,
         provider: Provider,
     ) -> Result<bool, AppError> {
+        config_lock::ensure_unlocked(&state.db)?;
         let mut provider = provider;
         // Normalize Claude model keys
         Self::normalize_provider_if_claude(&app_type, &mut provider);
+        Self::normalize_base_url(&app_type, &mut provider)?;
         Self::validate_provider_settings(&app_type, &provider)?;
+        vault::store_cloud_credentials(&app_type, &mut provider)?;
 
         // Check if this is current provider (use effective current, not just DB)
         let effective_current =
@@ -176,6 +285,7 @@ impl ProviderService {
     ///
     /// 同时检查本地 settings 和数据库的当前供应商，防止删除任一端正在使用的供应商。
     pub fn delete(state: &AppState, app_type: AppType, id: &str) -> Result<(), AppError> {
+        config_lock::ensure_unlocked(&state.db)?;
         // Check both local settings and database
         let local_current = crate::settings::get_current_provider(&app_type);
         let db_current = state.db.get_current_provider(app_type.as_str())?;
@@ -186,7 +296,12 @@ impl ProviderService {
             ));
         }
 
-        state.db.delete_provider(app_type.as_str(), id)
+        let provider = state.db.get_provider_by_id(id, app_type.as_str())?;
+        state.db.delete_provider(app_type.as_str(), id)?;
+        if let Some(provider) = provider {
+            vault::delete_cloud_credentials(&provider);
+        }
+        Ok(())
     }
 
     /// Switch to a provider
@@ -208,23 +323,7 @@ impl ProviderService {
             .get(id)
             .ok_or_else(|| AppError::Message(format!("供应商 {id} 不存在")))?;
 
-        // Check if proxy takeover mode is active AND proxy server is actually running
-        // Both conditions must be true to use hot-switch mode
-        // Use blocking wait since this is a sync function
-        let is_app_taken_over =
-            futures::executor::block_on(state.db.get_live_backup(app_type.as_str()))
-                .ok()
-                .flatten()
-                .is_some();
-        let is_proxy_running = futures::executor::block_on(state.proxy_service.is_running());
-        let live_taken_over = state
-            .proxy_service
-            .detect_takeover_in_live_config_for_app(&app_type);
-
-        // Hot-switch only when BOTH: this app is taken over AND proxy server is actually running
-        let should_hot_switch = (is_app_taken_over || live_taken_over) && is_proxy_running;
-
-        if should_hot_switch {
+        if Self::should_hot_switch(state, &app_type) {
             // Proxy takeover mode: hot-switch only, don't write Live config
             log::info!(
                 "代理接管模式：热切换 {} 的目标供应商为 {}",
@@ -260,6 +359,445 @@ impl ProviderService {
         Self::switch_normal(state, app_type, id, &providers)
     }
 
+    /// 代理接管中且代理正在运行时只热切换，不写入 Live 配置
+    fn should_hot_switch(state: &AppState, app_type: &AppType) -> bool {
+        // Check if proxy takeover mode is active AND proxy server is actually running
+        // Both conditions must be true to use hot-switch mode
+        // Use blocking wait since this is a sync function
+        let is_app_taken_over =
+            futures::executor::block_on(state.db.get_live_backup(app_type.as_str()))
+                .ok()
+                .flatten()
+                .is_some();
+        let is_proxy_running = futures::executor::block_on(state.proxy_service.is_running());
+        let live_taken_over = state
+            .proxy_service
+            .detect_takeover_in_live_config_for_app(app_type);
+
+        // Hot-switch only when BOTH: this app is taken over AND proxy server is actually running
+        (is_app_taken_over || live_taken_over) && is_proxy_running
+    }
+
+    /// 预览切换到 `id` 时将写入的配置变更（凭据已隐藏）
+    pub fn preview_switch(
+        state: &AppState,
+        app_type: AppType,
+        id: &str,
+    ) -> Result<SwitchPreview, AppError> {
+        let providers = state.db.get_all_providers(app_type.as_str())?;
+        let provider = providers
+            .get(id)
+            .ok_or_else(|| AppError::Message(format!("供应商 {id} 不存在")))?;
+        if Self::should_hot_switch(state, &app_type) {
+            return Ok(SwitchPreview::new(&app_type, provider, true, Vec::new()));
+        }
+        let current_id = crate::settings::get_effective_current_provider(&state.db, &app_type)?;
+        let current = current_id.as_deref().and_then(|cid| providers.get(cid));
+        let files = switch_preview::diff_live(&app_type, provider, current)?;
+        Ok(SwitchPreview::new(&app_type, provider, false, files))
+    }
+
+    /// 经切换队列执行切换：与其他切换串行，目标已是当前供应商时不做修改；
+    /// 实际切换后发布 `ProviderSwitched` 事件（`source` 标识入口）
+    ///
+    /// 排队期间阻塞当前线程，异步上下文中需放到 `spawn_blocking` 里调用
+    pub fn switch_exclusive(
+        state: &AppState,
+        app_type: AppType,
+        id: &str,
+        source: &str,
+    ) -> Result<SwitchOutcome, AppError> {
+        config_lock::ensure_unlocked(&state.db)?;
+        let mut previous = None;
+        let outcome = switch_queue::run_exclusive_blocking(
+            app_type.as_str(),
+            id,
+            source,
+            || {
+                previous = crate::settings::get_effective_current_provider(&state.db, &app_type)?;
+                Ok(previous.as_deref() == Some(id))
+            },
+            || Self::switch(state, app_type.clone(), id),
+        )?;
+        if outcome == SwitchOutcome::Switched {
+            if let Err(e) = state.db.record_provider_switch(
+                app_type.as_str(),
+                id,
+                previous.as_deref(),
+                source,
+                chrono::Utc::now().timestamp(),
+            ) {
+                log::warn!("记录供应商切换历史失败: {e}");
+            }
+            events::publish(AppEvent::ProviderSwitched(ProviderSwitchedPayload {
+                app_type: app_type.as_str().to_string(),
+                provider_id: id.to_string(),
+                source: Some(source.to_string()),
+            }));
+        }
+        Ok(outcome)
+    }
+
+    /// 最近使用过的供应商（已删除的供应商不再列出）
+    pub fn recent_providers(
+        state: &AppState,
+        app_type: AppType,
+        limit: usize,
+    ) -> Result<Vec<RecentProvider>, AppError> {
+        let providers = state.db.get_all_providers(app_type.as_str())?;
+        let recent = state
+            .db
+            .get_recent_providers(app_type.as_str(), RECENT_LOOKUP_LIMIT)?;
+        Ok(recent
+            .into_iter()
+            .filter(|r| providers.contains_key(&r.provider_id))
+            .take(limit)
+            .collect())
+    }
+
+    /// "切回上一个"的目标供应商
+    ///
+    /// 优先使用最近一次切换前的供应商；该供应商已被删除时，退回到最近使用列表中
+    /// 第一个非当前的供应商。
+    pub fn previous_provider(state: &AppState, app_type: AppType) -> Result<String, AppError> {
+        let current = crate::settings::get_effective_current_provider(&state.db, &app_type)?;
+        let providers = state.db.get_all_providers(app_type.as_str())?;
+        let usable = |id: &str| current.as_deref() != Some(id) && providers.contains_key(id);
+
+        match state.db.get_previous_provider(app_type.as_str())? {
+            Some(id) if usable(&id) => Some(id),
+            _ => state
+                .db
+                .get_recent_providers(app_type.as_str(), RECENT_LOOKUP_LIMIT)?
+                .into_iter()
+                .map(|r| r.provider_id)
+                .find(|id| usable(id)),
+        }
+        .ok_or_else(|| AppError::Message("没有可切回的供应商".to_string()))
+    }
+
+    /// 切换前校验供应商能力是否满足目标应用的要求
+    pub fn check_switch_capabilities(
+        state: &AppState,
+        app_type: AppType,
+        id: &str,
+    ) -> Result<Vec<CapabilityWarning>, AppError> {
+        let provider = state
+            .db
+            .get_provider_by_id(id, app_type.as_str())?
+            .ok_or_else(|| AppError::Message(format!("供应商 {id} 不存在")))?;
+        Ok(check_capabilities(&app_type, &provider, Locale::current()))
+    }
+
+    /// 编码供应商用于设备间传输（二维码 / 粘贴）
+    pub fn export_transfer(
+        state: &AppState,
+        app_type: AppType,
+        id: &str,
+        include_key: bool,
+        passphrase: Option<&str>,
+    ) -> Result<String, AppError> {
+        let provider = state
+            .db
+            .get_provider_by_id(id, app_type.as_str())?
+            .ok_or_else(|| AppError::Message(format!("供应商 {id} 不存在")))?;
+        transfer::encode(&app_type, &provider, include_key, passphrase)
+    }
+
+    /// 按应用的原生文件格式导出供应商配置，指定 `target` 时同时写入磁盘
+    pub fn export_config(
+        state: &AppState,
+        app_type: AppType,
+        id: &str,
+        include_key: bool,
+        target: Option<&Path>,
+    ) -> Result<Vec<ExportedConfigFile>, AppError> {
+        let provider = state
+            .db
+            .get_provider_by_id(id, app_type.as_str())?
+            .ok_or_else(|| AppError::Message(format!("供应商 {id} 不存在")))?;
+        let mut files = config_export::render(&app_type, &provider, include_key)?;
+        if let Some(target) = target {
+            config_export::write_to(&mut files, target)?;
+        }
+        Ok(files)
+    }
+
+    /// 从 CSV / JSON 文本批量导入供应商
+    ///
+    /// 逐行校验与查重，通过的供应商在同一事务中写入；`dry_run` 时只返回报告。
+    pub fn bulk_import(
+        state: &AppState,
+        text: &str,
+        format: ImportFormat,
+        dry_run: bool,
+    ) -> Result<BulkImportReport, AppError> {
+        config_lock::ensure_unlocked(&state.db)?;
+        let rows = bulk_import::parse_rows(text, format)?;
+        let mut report = BulkImportReport::new(dry_run);
+        let mut known: HashMap<String, IndexMap<String, Provider>> = HashMap::new();
+        let mut batch: Vec<(AppType, Provider)> = Vec::new();
+
+        for (index, row) in rows.iter().enumerate() {
+            let row_no = index + 1;
+            let candidates = match bulk_import::build_candidates(row) {
+                Ok(candidates) => candidates,
+                Err(message) => {
+                    report.failed(row_no, None, &row.name, message);
+                    continue;
+                }
+            };
+            for (app_type, mut provider) in candidates {
+                Self::normalize_provider_if_claude(&app_type, &mut provider);
+                if let Err(e) = Self::normalize_base_url(&app_type, &mut provider)
+                    .and_then(|_| Self::validate_provider_settings(&app_type, &provider))
+                {
+                    report.failed(row_no, Some(&app_type), &provider.name, e.to_string());
+                    continue;
+                }
+
+                // 与已有供应商以及本次已接受的行查重
+                let existing = match known.entry(app_type.as_str().to_string()) {
+                    std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                    std::collections::hash_map::Entry::Vacant(entry) => {
+                        entry.insert(state.db.get_all_providers(app_type.as_str())?)
+                    }
+                };
+                if let Some(dup) = duplicates::find_duplicates(&app_type, &provider, existing)
+                    .into_iter()
+                    .next()
+                {
+                    report.skipped(row_no, &app_type, &provider.name, dup);
+                    continue;
+                }
+                existing.insert(provider.id.clone(), provider.clone());
+                report.created(row_no, &app_type, &provider);
+                batch.push((app_type, provider));
+            }
+        }
+
+        if !dry_run && !batch.is_empty() {
+            let rows: Vec<(String, Provider)> = batch
+                .iter()
+                .map(|(app_type, provider)| (app_type.as_str().to_string(), provider.clone()))
+                .collect();
+            state.db.save_providers(&rows)?;
+
+            // 与单个新增一致：应用还没有当前供应商时，导入的第一个成为当前供应商
+            for (app_type, provider) in &batch {
+                if state.db.get_current_provider(app_type.as_str())?.is_none() {
+                    state
+                        .db
+                        .set_current_provider(app_type.as_str(), &provider.id)?;
+                    write_live_snapshot(app_type, provider)?;
+                }
+            }
+        }
+
+        log::info!(
+            "批量导入供应商{}: 新增 {}，跳过 {}，失败 {}",
+            if dry_run { "（预检）" } else { "" },
+            report.created,
+            report.skipped,
+            report.failed
+        );
+        Ok(report)
+    }
+
+    /// 解码传输载荷（供导入前预览）
+    pub fn decode_transfer(
+        payload: &str,
+        passphrase: Option<&str>,
+    ) -> Result<TransferredProvider, AppError> {
+        transfer::decode(payload, passphrase)
+    }
+
+    /// 导入传输载荷为新供应商，返回新 ID
+    pub fn import_transfer(
+        state: &AppState,
+        payload: &str,
+        passphrase: Option<&str>,
+    ) -> Result<String, AppError> {
+        let TransferredProvider {
+            app, mut provider, ..
+        } = transfer::decode(payload, passphrase)?;
+        let app_type = app.parse::<AppType>()?;
+        provider.id = uuid::Uuid::new_v4().to_string();
+        let id = provider.id.clone();
+        Self::add(state, app_type.clone(), provider.clone())?;
+        Self::report_duplicates(state, &app_type, &provider);
+        Ok(id)
+    }
+
+    /// 新建或导入前查找疑似重复的已有供应商
+    pub fn find_duplicates(
+        state: &AppState,
+        app_type: AppType,
+        provider: &Provider,
+    ) -> Result<Vec<DuplicateMatch>, AppError> {
+        let existing = state.db.get_all_providers(app_type.as_str())?;
+        Ok(duplicates::find_duplicates(&app_type, provider, &existing))
+    }
+
+    /// 新建或导入后检查是否与已有供应商重复，发现时发送事件由前端提示合并
+    pub fn report_duplicates(state: &AppState, app_type: &AppType, provider: &Provider) {
+        match Self::find_duplicates(state, app_type.clone(), provider) {
+            Ok(matches) if !matches.is_empty() => {
+                crate::events::publish(crate::events::AppEvent::DuplicateProviderDetected(
+                    crate::events::DuplicateProviderPayload {
+                        app_type: app_type.as_str().to_string(),
+                        provider_id: provider.id.clone(),
+                        name: provider.name.clone(),
+                        matches,
+                    },
+                ));
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("检查重复供应商失败: {e}"),
+        }
+    }
+
+    /// 合并重复供应商：保留 `keep_id` 的配置，`remove_id` 的历史记录归入 `keep_id` 后删除
+    pub fn merge_duplicate(
+        state: &AppState,
+        app_type: AppType,
+        keep_id: &str,
+        remove_id: &str,
+    ) -> Result<(), AppError> {
+        config_lock::ensure_unlocked(&state.db)?;
+        if keep_id == remove_id {
+            return Err(AppError::InvalidInput("不能将供应商与自身合并".to_string()));
+        }
+        for id in [keep_id, remove_id] {
+            if state
+                .db
+                .get_provider_by_id(id, app_type.as_str())?
+                .is_none()
+            {
+                return Err(AppError::Message(format!("供应商 {id} 不存在")));
+            }
+        }
+
+        // 被合并的供应商正在使用时，先切换到保留的供应商
+        let current = crate::settings::get_effective_current_provider(&state.db, &app_type)?;
+        if current.as_deref() == Some(remove_id) {
+            Self::switch(state, app_type.clone(), keep_id)?;
+        }
+
+        state
+            .db
+            .merge_provider_history(app_type.as_str(), remove_id, keep_id)?;
+        log::info!(
+            "已合并重复供应商 {remove_id} -> {keep_id}（{}）",
+            app_type.as_str()
+        );
+        Ok(())
+    }
+
+    /// 同步前预览：以 `base`（上次同步时的列表）为基线，按字段合并本机与远端的供应商
+    ///
+    /// 有冲突的供应商各发送一次 `sync-conflict` 事件。
+    pub fn preview_sync_merge(
+        state: &AppState,
+        app_type: AppType,
+        base: &IndexMap<String, Provider>,
+        remote: &IndexMap<String, Provider>,
+    ) -> Result<ProviderMergeResult, AppError> {
+        let local = state.db.get_all_providers(app_type.as_str())?;
+        let result = sync_merge::merge(base, &local, remote, &[])?;
+
+        let mut per_provider: IndexMap<&str, usize> = IndexMap::new();
+        for conflict in &result.conflicts {
+            *per_provider
+                .entry(conflict.provider_id.as_str())
+                .or_default() += 1;
+        }
+        for (provider_id, count) in per_provider {
+            events::publish(AppEvent::SyncConflict(SyncConflictPayload {
+                app_type: app_type.as_str().to_string(),
+                provider_id: provider_id.to_string(),
+                message: format!("{count} 处冲突待处理"),
+            }));
+        }
+        Ok(result)
+    }
+
+    /// 按前端的选择写入同步合并结果（仍有未解决的冲突时拒绝写入）
+    ///
+    /// 正在使用的供应商被远端删除时保留本机的配置。
+    pub fn apply_sync_merge(
+        state: &AppState,
+        app_type: AppType,
+        base: &IndexMap<String, Provider>,
+        remote: &IndexMap<String, Provider>,
+        resolutions: &[ConflictResolution],
+    ) -> Result<ProviderMergeResult, AppError> {
+        config_lock::ensure_unlocked(&state.db)?;
+        let local = state.db.get_all_providers(app_type.as_str())?;
+        let mut result = sync_merge::merge(base, &local, remote, resolutions)?;
+        if !result.conflicts.is_empty() {
+            return Err(AppError::Message(format!(
+                "仍有 {} 处冲突未解决",
+                result.conflicts.len()
+            )));
+        }
+
+        // 先校验全部有变化的供应商，避免写入一半
+        let same = |a: &Provider, b: &Provider| {
+            serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+        };
+        let mut changed = Vec::new();
+        for provider in result.merged.values_mut() {
+            if local
+                .get(&provider.id)
+                .is_some_and(|existing| same(existing, provider))
+            {
+                continue;
+            }
+            Self::normalize_provider_if_claude(&app_type, provider);
+            Self::normalize_base_url(&app_type, provider)?;
+            Self::validate_provider_settings(&app_type, provider)?;
+            changed.push(provider.clone());
+        }
+
+        let current = crate::settings::get_effective_current_provider(&state.db, &app_type)?;
+        let mut removed = Vec::new();
+        for (id, existing) in &local {
+            if result.merged.contains_key(id) {
+                continue;
+            }
+            if current.as_deref() == Some(id.as_str()) {
+                log::warn!("同步合并删除了正在使用的供应商 {id}，已保留本机配置");
+                result.merged.insert(id.clone(), existing.clone());
+                continue;
+            }
+            removed.push(id.clone());
+        }
+
+        for provider in &changed {
+            if local.contains_key(&provider.id) {
+                Self::update(state, app_type.clone(), provider.clone())?;
+            } else {
+                Self::add(state, app_type.clone(), provider.clone())?;
+            }
+        }
+        for id in &removed {
+            Self::delete(state, app_type.clone(), id)?;
+        }
+        log::info!(
+            "已应用同步合并（{}）: 更新 {}，删除 {}",
+            app_type.as_str(),
+            changed.len(),
+            removed.len()
+        );
+        Ok(result)
+    }
+
+    /// 切换队列状态
+    pub fn switch_queue_status() -> SwitchQueueStatus {
+        switch_queue::status()
+    }
+
     /// Normal switch flow (non-proxy mode)
     fn switch_normal(
         state: &AppState,
@@ -308,6 +846,70 @@ impl ProviderService {
         sync_current_to_live(state)
     }
 
+    /// 检查各应用当前供应商与 Live 配置是否一致
+    ///
+    /// 代理接管中（Live 指向本地代理）或 Live 文件缺失的应用跳过检查。
+    pub fn check_live_consistency(state: &AppState) -> Result<Vec<LiveDrift>, AppError> {
+        let mut drifts = Vec::new();
+        for app_type in [AppType::Claude, AppType::Codex] {
+            if state
+                .proxy_service
+                .detect_takeover_in_live_config_for_app(&app_type)
+            {
+                continue;
+            }
+            let Some(current_id) =
+                crate::settings::get_effective_current_provider(&state.db, &app_type)?
+            else {
+                continue;
+            };
+            let Ok(live) = read_live_settings(app_type.clone()) else {
+                continue;
+            };
+            let providers = state.db.get_all_providers(app_type.as_str())?;
+            if let Some(current) = providers.get(&current_id) {
+                drifts.extend(consistency::compare(&app_type, current, &live, &providers));
+            }
+        }
+        Ok(drifts)
+    }
+
+    /// 按用户选择解决当前供应商与 Live 配置的不一致
+    pub fn reconcile_live(
+        state: &AppState,
+        app_type: AppType,
+        action: LiveReconcileAction,
+    ) -> Result<(), AppError> {
+        config_lock::ensure_unlocked(&state.db)?;
+        let current_id = crate::settings::get_effective_current_provider(&state.db, &app_type)?
+            .ok_or_else(|| AppError::Message("当前没有选中的供应商".to_string()))?;
+        let providers = state.db.get_all_providers(app_type.as_str())?;
+        let current = providers
+            .get(&current_id)
+            .ok_or_else(|| AppError::Message(format!("供应商 {current_id} 不存在")))?;
+
+        match action {
+            LiveReconcileAction::WriteLive => write_live_snapshot(&app_type, current),
+            LiveReconcileAction::AdoptLive => {
+                let live = read_live_settings(app_type.clone())?;
+                let matched = consistency::compare(&app_type, current, &live, &providers)
+                    .and_then(|drift| drift.live_provider_id);
+                match matched {
+                    Some(id) => {
+                        // Live 已是该供应商的配置，只更新当前供应商标记
+                        crate::settings::set_current_provider(&app_type, Some(&id))?;
+                        state.db.set_current_provider(app_type.as_str(), &id)
+                    }
+                    None => {
+                        let mut updated = current.clone();
+                        updated.settings_config = live;
+                        state.db.save_provider(app_type.as_str(), &updated)
+                    }
+                }
+            }
+        }
+    }
+
     /// Import default configuration from live files (re-export)
     ///
     /// Returns `Ok(true)` if imported, `Ok(false)` if skipped.
@@ -336,6 +938,7 @@ impl ProviderService {
         provider_id: &str,
         url: String,
     ) -> Result<(), AppError> {
+        config_lock::ensure_unlocked(&state.db)?;
         endpoints::add_custom_endpoint(state, app_type, provider_id, url)
     }
 
@@ -346,6 +949,7 @@ impl ProviderService {
         provider_id: &str,
         url: String,
     ) -> Result<(), AppError> {
+        config_lock::ensure_unlocked(&state.db)?;
         endpoints::remove_custom_endpoint(state, app_type, provider_id, url)
     }
 
@@ -365,6 +969,7 @@ impl ProviderService {
         app_type: AppType,
         updates: Vec<ProviderSortUpdate>,
     ) -> Result<bool, AppError> {
+        config_lock::ensure_unlocked(&state.db)?;
         let mut providers = state.db.get_all_providers(app_type.as_str())?;
 
         for update in updates {
@@ -418,6 +1023,10 @@ impl ProviderService {
     }
 
     fn validate_provider_settings(app_type: &AppType, provider: &Provider) -> Result<(), AppError> {
+        crate::config_validation::into_result(crate::config_validation::validate_provider(
+            provider,
+        ))?;
+
         match app_type {
             AppType::Claude => {
                 if !provider.settings_config.is_object() {
@@ -722,6 +1331,7 @@ impl ProviderService {
         state: &AppState,
         provider: UniversalProvider,
     ) -> Result<bool, AppError> {
+        config_lock::ensure_unlocked(&state.db)?;
         // 保存统一供应商
         state.db.save_universal_provider(&provider)?;
 
@@ -730,6 +1340,7 @@ impl ProviderService {
 
     /// 删除统一供应商
     pub fn delete_universal(state: &AppState, id: &str) -> Result<bool, AppError> {
+        config_lock::ensure_unlocked(&state.db)?;
         // 获取统一供应商（用于删除生成的子供应商）
         let provider = state.db.get_universal_provider(id)?;
 
@@ -757,6 +1368,7 @@ impl ProviderService {
 
     /// 同步统一供应商到各应用
     pub fn sync_universal_to_apps(state: &AppState, id: &str) -> Result<bool, AppError> {
+        config_lock::ensure_unlocked(&state.db)?;
         let provider = state
             .db
             .get_universal_provider(id)?
@@ -0,0 +1,458 @@
+//! 切换前的配置变更预览
+//!
+//! 按切换流程计算将写入目标应用配置文件的内容，与当前文件逐个键比较，返回变更列表
+//! （凭据类字段的值隐藏），供界面展示"settings.json 中将有 3 个键发生变化"。
+//!
+//! Live 文件中存在、但当前供应商配置中没有的键视为用户手动添加；这类键会被覆盖或删除时
+//! 标记 `userOwned`，界面需要用户明确确认后才能切换。MCP 服务器由切换后的 MCP 同步
+//! 重新写入，不参与判断。
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::gemini_auth::{detect_gemini_auth_type, GeminiAuthType};
+use crate::app_config::AppType;
+use crate::codex_config::{get_codex_auth_path, get_codex_config_path};
+use crate::config::{get_claude_settings_path, read_json_file};
+use crate::error::AppError;
+use crate::provider::Provider;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// 由 MCP 同步维护的顶层键
+const MCP_KEYS: &[&str] = &["mcpServers", "mcp_servers"];
+
+/// 变更类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfigChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// 单个键的变更
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigChange {
+    /// 以 `.` 连接的键路径，如 `env.ANTHROPIC_BASE_URL`
+    pub key: String,
+    pub kind: ConfigChangeKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Value>,
+    /// 用户手动添加的键将被覆盖或删除
+    pub user_owned: bool,
+}
+
+/// 单个配置文件的变更
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigFileDiff {
+    pub file_name: String,
+    pub path: String,
+    pub changes: Vec<ConfigChange>,
+}
+
+/// 切换预览
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchPreview {
+    pub app_type: String,
+    pub provider_id: String,
+    pub provider_name: String,
+    /// 代理接管中只热切换，不写入配置文件
+    pub hot_switch: bool,
+    /// 只包含有变更的文件
+    pub files: Vec<ConfigFileDiff>,
+    /// 存在用户手动添加的键将被覆盖，切换需要明确确认
+    pub requires_confirmation: bool,
+}
+
+impl SwitchPreview {
+    pub(super) fn new(
+        app_type: &AppType,
+        provider: &Provider,
+        hot_switch: bool,
+        files: Vec<ConfigFileDiff>,
+    ) -> Self {
+        let files: Vec<ConfigFileDiff> = files
+            .into_iter()
+            .filter(|file| !file.changes.is_empty())
+            .collect();
+        let requires_confirmation = files
+            .iter()
+            .flat_map(|file| &file.changes)
+            .any(|change| change.user_owned);
+        Self {
+            app_type: app_type.as_str().to_string(),
+            provider_id: provider.id.clone(),
+            provider_name: provider.name.clone(),
+            hot_switch,
+            files,
+            requires_confirmation,
+        }
+    }
+}
+
+/// 某个文件切换前后的内容；`managed` 为当前供应商对应的内容（无当前供应商时为空）
+struct PlannedFile {
+    file_name: &'static str,
+    path: PathBuf,
+    before: Value,
+    after: Value,
+    managed: Option<Value>,
+}
+
+fn read_optional_json(path: &Path) -> Value {
+    if path.exists() {
+        read_json_file(path).unwrap_or_else(|_| json!({}))
+    } else {
+        json!({})
+    }
+}
+
+fn toml_to_json(text: &str) -> Value {
+    toml::from_str::<toml::Table>(text)
+        .ok()
+        .and_then(|table| serde_json::to_value(table).ok())
+        .unwrap_or_else(|| json!({}))
+}
+
+fn env_to_value(env: std::collections::HashMap<String, String>) -> Value {
+    Value::Object(
+        env.into_iter()
+            .map(|(k, v)| (k, Value::String(v)))
+            .collect(),
+    )
+}
+
+fn claude_settings(provider: &Provider) -> Value {
+    let mut settings = provider.settings_config.clone();
+    if let Some(obj) = settings.as_object_mut() {
+        obj.remove("custom_headers");
+    }
+    settings
+}
+
+fn codex_files(provider: &Provider) -> Result<(Value, Value), AppError> {
+    let settings = &provider.settings_config;
+    let auth = settings
+        .get("auth")
+        .cloned()
+        .ok_or_else(|| AppError::Config("Codex 供应商配置缺少 'auth' 字段".to_string()))?;
+    let config = settings
+        .get("config")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| {
+            AppError::Config("Codex 供应商配置缺少 'config' 字段或不是字符串".to_string())
+        })?;
+    Ok((auth, toml_to_json(config)))
+}
+
+fn gemini_env(provider: &Provider) -> Result<Value, AppError> {
+    let mut env = crate::gemini_config::json_to_env(&provider.settings_config)?;
+    if matches!(
+        detect_gemini_auth_type(provider),
+        GeminiAuthType::GoogleOfficial
+    ) {
+        env.clear();
+    }
+    Ok(env_to_value(env))
+}
+
+/// 与 `write_gemini_live` 相同：config 合并到现有 settings.json，并设置认证方式
+fn gemini_settings(existing: &Value, provider: &Provider) -> Value {
+    let mut merged = existing.clone();
+    if !merged.is_object() {
+        merged = json!({});
+    }
+    let obj = merged.as_object_mut().expect("object");
+    if let Some(config) = provider
+        .settings_config
+        .get("config")
+        .and_then(|v| v.as_object())
+    {
+        for (k, v) in config {
+            obj.insert(k.clone(), v.clone());
+        }
+    }
+    let selected_type = match detect_gemini_auth_type(provider) {
+        GeminiAuthType::GoogleOfficial => "oauth-personal",
+        GeminiAuthType::Packycode | GeminiAuthType::Generic => "gemini-api-key",
+    };
+    let security = obj.entry("security").or_insert_with(|| json!({}));
+    if let Some(security) = security.as_object_mut() {
+        let auth = security.entry("auth").or_insert_with(|| json!({}));
+        if let Some(auth) = auth.as_object_mut() {
+            auth.insert("selectedType".to_string(), json!(selected_type));
+        }
+    }
+    merged
+}
+
+fn plan(
+    app_type: &AppType,
+    target: &Provider,
+    current: Option<&Provider>,
+) -> Result<Vec<PlannedFile>, AppError> {
+    match app_type {
+        AppType::Claude => {
+            let path = get_claude_settings_path();
+            Ok(vec![PlannedFile {
+                file_name: "settings.json",
+                before: read_optional_json(&path),
+                path,
+                after: claude_settings(target),
+                managed: current.map(claude_settings),
+            }])
+        }
+        AppType::Codex => {
+            let (auth, config) = codex_files(target)?;
+            let managed = current.and_then(|p| codex_files(p).ok());
+            let auth_path = get_codex_auth_path();
+            let config_path = get_codex_config_path();
+            let config_before = std::fs::read_to_string(&config_path)
+                .map(|text| toml_to_json(&text))
+                .unwrap_or_else(|_| json!({}));
+            Ok(vec![
+                PlannedFile {
+                    file_name: "auth.json",
+                    before: read_optional_json(&auth_path),
+                    path: auth_path,
+                    after: auth,
+                    managed: managed.as_ref().map(|(auth, _)| auth.clone()),
+                },
+                PlannedFile {
+                    file_name: "config.toml",
+                    before: config_before,
+                    path: config_path,
+                    after: config,
+                    managed: managed.map(|(_, config)| config),
+                },
+            ])
+        }
+        AppType::Gemini => {
+            use crate::gemini_config::{
+                get_gemini_env_path, get_gemini_settings_path, read_gemini_env,
+            };
+            let env_path = get_gemini_env_path();
+            let env_before = if env_path.exists() {
+                env_to_value(read_gemini_env()?)
+            } else {
+                json!({})
+            };
+            let settings_path = get_gemini_settings_path();
+            let settings_before = read_optional_json(&settings_path);
+            let settings_after = gemini_settings(&settings_before, target);
+            Ok(vec![
+                PlannedFile {
+                    file_name: ".env",
+                    before: env_before,
+                    path: env_path,
+                    after: gemini_env(target)?,
+                    managed: current.and_then(|p| gemini_env(p).ok()),
+                },
+                PlannedFile {
+                    file_name: "settings.json",
+                    before: settings_before,
+                    path: settings_path,
+                    after: settings_after,
+                    managed: current.map(|p| gemini_settings(&json!({}), p)),
+                },
+            ])
+        }
+    }
+}
+
+/// 计算切换到 `target` 时各配置文件的变更
+pub(super) fn diff_live(
+    app_type: &AppType,
+    target: &Provider,
+    current: Option<&Provider>,
+) -> Result<Vec<ConfigFileDiff>, AppError> {
+    Ok(plan(app_type, target, current)?
+        .into_iter()
+        .map(|file| ConfigFileDiff {
+            file_name: file.file_name.to_string(),
+            path: file.path.to_string_lossy().to_string(),
+            changes: diff_values(&file.before, &file.after, file.managed.as_ref()),
+        })
+        .collect())
+}
+
+fn flatten(prefix: &str, value: &Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, v) in map {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                flatten(&path, v, out);
+            }
+        }
+        _ if prefix.is_empty() => {}
+        _ => {
+            out.insert(prefix.to_string(), value.clone());
+        }
+    }
+}
+
+fn flattened(value: &Value) -> BTreeMap<String, Value> {
+    let mut out = BTreeMap::new();
+    flatten("", value, &mut out);
+    out
+}
+
+/// 与 `transfer::strip_secrets` 相同的判断：键名以 key / token / secret / password 结尾
+fn is_secret(path: &str) -> bool {
+    let last = path.rsplit('.').next().unwrap_or(path);
+    let key = last.to_ascii_lowercase().replace(['_', '-'], "");
+    ["key", "token", "secret", "password"]
+        .iter()
+        .any(|suffix| key.ends_with(suffix))
+}
+
+fn display(path: &str, value: &Value) -> Value {
+    if is_secret(path) && value.is_string() {
+        Value::String(REDACTED.to_string())
+    } else {
+        value.clone()
+    }
+}
+
+fn diff_values(before: &Value, after: &Value, managed: Option<&Value>) -> Vec<ConfigChange> {
+    let before = flattened(before);
+    let after = flattened(after);
+    let managed = managed.map(flattened);
+    let is_mcp = |path: &str| {
+        let top = path.split('.').next().unwrap_or(path);
+        MCP_KEYS.contains(&top)
+    };
+    // 无当前供应商时无法区分哪些键由 cc-switch 写入，不标记为用户所有
+    let user_owned = |path: &str| {
+        !is_mcp(path)
+            && managed
+                .as_ref()
+                .is_some_and(|managed| !managed.contains_key(path))
+    };
+
+    let mut changes = Vec::new();
+    for (path, old) in &before {
+        match after.get(path) {
+            Some(new) if new == old => {}
+            Some(new) => changes.push(ConfigChange {
+                key: path.clone(),
+                kind: ConfigChangeKind::Changed,
+                before: Some(display(path, old)),
+                after: Some(display(path, new)),
+                user_owned: user_owned(path),
+            }),
+            // MCP 服务器由切换后的同步重新写入
+            None if is_mcp(path) => {}
+            None => changes.push(ConfigChange {
+                key: path.clone(),
+                kind: ConfigChangeKind::Removed,
+                before: Some(display(path, old)),
+                after: None,
+                user_owned: user_owned(path),
+            }),
+        }
+    }
+    for (path, new) in after.iter().filter(|(p, _)| !before.contains_key(*p)) {
+        changes.push(ConfigChange {
+            key: path.clone(),
+            kind: ConfigChangeKind::Added,
+            before: None,
+            after: Some(display(path, new)),
+            user_owned: false,
+        });
+    }
+    changes.sort_by(|a, b| a.key.cmp(&b.key));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_user_owned_keys_and_redacts_secrets() {
+        let live = json!({
+            "env": {
+                "ANTHROPIC_BASE_URL": "https://a.example",
+                "ANTHROPIC_AUTH_TOKEN": "sk-a"
+            },
+            "permissions": { "allow": ["Bash"] },
+            "statusLine": "custom"
+        });
+        let current = json!({
+            "env": {
+                "ANTHROPIC_BASE_URL": "https://a.example",
+                "ANTHROPIC_AUTH_TOKEN": "sk-a"
+            },
+            "permissions": { "allow": ["Bash"] }
+        });
+        let target = json!({
+            "env": {
+                "ANTHROPIC_BASE_URL": "https://b.example",
+                "ANTHROPIC_AUTH_TOKEN": "sk-b",
+                "ANTHROPIC_MODEL": "claude-sonnet"
+            }
+        });
+
+        let changes = diff_values(&live, &target, Some(&current));
+        let keys: Vec<_> = changes.iter().map(|c| (c.key.as_str(), c.kind)).collect();
+        assert_eq!(
+            keys,
+            vec![
+                ("env.ANTHROPIC_AUTH_TOKEN", ConfigChangeKind::Changed),
+                ("env.ANTHROPIC_BASE_URL", ConfigChangeKind::Changed),
+                ("env.ANTHROPIC_MODEL", ConfigChangeKind::Added),
+                ("permissions.allow", ConfigChangeKind::Removed),
+                ("statusLine", ConfigChangeKind::Removed),
+            ]
+        );
+        let token = &changes[0];
+        assert_eq!(token.before, Some(json!(REDACTED)));
+        assert_eq!(token.after, Some(json!(REDACTED)));
+        // 只有不在当前供应商配置中的键算作用户手动添加
+        let owned: Vec<_> = changes
+            .iter()
+            .filter(|c| c.user_owned)
+            .map(|c| c.key.as_str())
+            .collect();
+        assert_eq!(owned, vec!["statusLine"]);
+    }
+
+    #[test]
+    fn mcp_servers_and_unchanged_files_need_no_confirmation() {
+        let live = json!({
+            "model": "gpt-5",
+            "mcp_servers": { "fs": { "command": "npx" } }
+        });
+        let target = json!({ "model": "gpt-5" });
+        assert!(diff_values(&live, &target, Some(&target)).is_empty());
+
+        let provider = Provider::with_id("p".into(), "P".into(), target.clone(), None);
+        let preview = SwitchPreview::new(
+            &AppType::Codex,
+            &provider,
+            false,
+            vec![ConfigFileDiff {
+                file_name: "config.toml".into(),
+                path: "config.toml".into(),
+                changes: diff_values(&json!({ "model": "o3" }), &target, None),
+            }],
+        );
+        assert_eq!(preview.files.len(), 1);
+        // 无当前供应商时无法判断键的归属，不要求确认
+        assert!(!preview.requires_confirmation);
+    }
+}
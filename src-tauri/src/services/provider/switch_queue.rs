@@ -0,0 +1,317 @@
+//! 供应商切换队列
+//!
+//! 托盘、主界面、深链接可能同时发起切换，交错写入 Live 配置文件会得到混合的配置。
+//! 所有切换都经过这里按先来先到的顺序逐个执行；当前供应商已是目标供应商时直接返回，不再重复写入。
+//!
+//! 排队等待通过 `tokio::sync::Notify` 完成：异步调用方（故障转移）直接 await，不占用运行时线程；
+//! 同步调用方使用 [`run_exclusive_blocking`]，须在阻塞线程（如 `spawn_blocking`）中调用。
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tokio::sync::Notify;
+
+use crate::error::AppError;
+
+/// 切换请求
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchRequestInfo {
+    pub ticket: u64,
+    pub app_type: String,
+    pub provider_id: String,
+    /// 发起方（"ui" / "tray" / "deeplink" / "failover"）
+    pub source: String,
+    pub queued_at: i64,
+}
+
+/// 切换结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SwitchOutcome {
+    Switched,
+    /// 目标已是当前供应商，未做任何修改
+    AlreadyActive,
+    Failed,
+}
+
+/// 最近一次完成的切换
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletedSwitch {
+    pub request: SwitchRequestInfo,
+    pub outcome: SwitchOutcome,
+    pub finished_at: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// 切换队列状态
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchQueueStatus {
+    /// 正在执行的切换
+    pub active: Option<SwitchRequestInfo>,
+    /// 排队中的切换（按执行顺序）
+    pub queued: Vec<SwitchRequestInfo>,
+    pub last_completed: Option<CompletedSwitch>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    next_ticket: u64,
+    active: Option<SwitchRequestInfo>,
+    waiting: VecDeque<SwitchRequestInfo>,
+    last_completed: Option<CompletedSwitch>,
+}
+
+/// `state` 只在短暂的检查与更新时加锁，不跨越等待持有
+struct SwitchQueue {
+    state: Mutex<QueueState>,
+    turn: Notify,
+}
+
+static QUEUE: Lazy<SwitchQueue> = Lazy::new(|| SwitchQueue {
+    state: Mutex::new(QueueState::default()),
+    turn: Notify::new(),
+});
+
+/// 离开队列时（包括 panic）交出执行权
+struct TurnGuard {
+    outcome: Option<(SwitchOutcome, Option<String>)>,
+}
+
+impl Drop for TurnGuard {
+    fn drop(&mut self) {
+        let mut state = QUEUE.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(request) = state.active.take() {
+            let (outcome, error) = self
+                .outcome
+                .take()
+                .unwrap_or((SwitchOutcome::Failed, Some("切换中断".to_string())));
+            state.last_completed = Some(CompletedSwitch {
+                request,
+                outcome,
+                finished_at: chrono::Utc::now().timestamp(),
+                error,
+            });
+        }
+        QUEUE.turn.notify_waiters();
+    }
+}
+
+/// 排队期间被取消（future 被丢弃）时移出队列，避免阻塞后续切换
+struct TicketGuard {
+    ticket: u64,
+}
+
+impl Drop for TicketGuard {
+    fn drop(&mut self) {
+        let mut state = QUEUE.state.lock().unwrap_or_else(|e| e.into_inner());
+        let before = state.waiting.len();
+        state.waiting.retain(|r| r.ticket != self.ticket);
+        if state.waiting.len() != before {
+            QUEUE.turn.notify_waiters();
+        }
+    }
+}
+
+/// 排队等待执行权，轮到自己时返回
+async fn wait_turn(app_type: &str, provider_id: &str, source: &str) -> Result<TurnGuard, AppError> {
+    let ticket = {
+        let mut state = QUEUE.state.lock().map_err(AppError::from)?;
+        state.next_ticket += 1;
+        let request = SwitchRequestInfo {
+            ticket: state.next_ticket,
+            app_type: app_type.to_string(),
+            provider_id: provider_id.to_string(),
+            source: source.to_string(),
+            queued_at: chrono::Utc::now().timestamp(),
+        };
+        state.waiting.push_back(request);
+        state.next_ticket
+    };
+    let _queued = TicketGuard { ticket };
+
+    // 等到自己排在队首且没有正在执行的切换
+    loop {
+        // 先登记等待再检查，避免检查与等待之间错过通知
+        let notified = QUEUE.turn.notified();
+        tokio::pin!(notified);
+        notified.as_mut().enable();
+        {
+            let mut state = QUEUE.state.lock().map_err(AppError::from)?;
+            if state.active.is_none() && state.waiting.front().map(|r| r.ticket) == Some(ticket) {
+                state.active = state.waiting.pop_front();
+                return Ok(TurnGuard { outcome: None });
+            }
+        }
+        notified.await;
+    }
+}
+
+/// 按顺序执行一次切换
+///
+/// `is_active` 在取得执行权后调用，返回 true 时跳过 `switch`；二者都在队列互斥区内执行。
+pub async fn run_exclusive(
+    app_type: &str,
+    provider_id: &str,
+    source: &str,
+    is_active: impl FnOnce() -> Result<bool, AppError>,
+    switch: impl FnOnce() -> Result<(), AppError>,
+) -> Result<SwitchOutcome, AppError> {
+    let mut guard = wait_turn(app_type, provider_id, source).await?;
+    let result = match is_active() {
+        Ok(true) => Ok(SwitchOutcome::AlreadyActive),
+        Ok(false) => switch().map(|_| SwitchOutcome::Switched),
+        Err(e) => Err(e),
+    };
+    guard.outcome = Some(match &result {
+        Ok(outcome) => (*outcome, None),
+        Err(e) => (SwitchOutcome::Failed, Some(e.to_string())),
+    });
+    result
+}
+
+/// 同步版本的 [`run_exclusive`]，排队期间阻塞当前线程，不能在异步任务中调用
+pub fn run_exclusive_blocking(
+    app_type: &str,
+    provider_id: &str,
+    source: &str,
+    is_active: impl FnOnce() -> Result<bool, AppError>,
+    switch: impl FnOnce() -> Result<(), AppError>,
+) -> Result<SwitchOutcome, AppError> {
+    futures::executor::block_on(run_exclusive(
+        app_type,
+        provider_id,
+        source,
+        is_active,
+        switch,
+    ))
+}
+
+/// 当前队列状态
+pub fn status() -> SwitchQueueStatus {
+    let state = QUEUE.state.lock().unwrap_or_else(|e| e.into_inner());
+    SwitchQueueStatus {
+        active: state.active.clone(),
+        queued: state.waiting.iter().cloned().collect(),
+        last_completed: state.last_completed.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn switches_run_one_at_a_time_and_skip_active() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let running = running.clone();
+                std::thread::spawn(move || {
+                    run_exclusive_blocking(
+                        "claude",
+                        &format!("p{i}"),
+                        "test",
+                        || Ok(false),
+                        || {
+                            assert_eq!(running.fetch_add(1, Ordering::SeqCst), 0);
+                            std::thread::sleep(std::time::Duration::from_millis(5));
+                            running.fetch_sub(1, Ordering::SeqCst);
+                            Ok(())
+                        },
+                    )
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap().unwrap(), SwitchOutcome::Switched);
+        }
+
+        let outcome =
+            run_exclusive_blocking("claude", "p0", "test", || Ok(true), || unreachable!()).unwrap();
+        assert_eq!(outcome, SwitchOutcome::AlreadyActive);
+        let status = status();
+        assert!(status.active.is_none());
+        assert_eq!(
+            status.last_completed.map(|c| c.outcome),
+            Some(SwitchOutcome::AlreadyActive)
+        );
+    }
+
+    #[tokio::test]
+    async fn async_waiters_do_not_block_the_runtime() {
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        let (started_tx, started_rx) = std::sync::mpsc::channel::<()>();
+        let holder = std::thread::spawn(move || {
+            run_exclusive_blocking(
+                "codex",
+                "slow",
+                "test",
+                || Ok(false),
+                || {
+                    started_tx.send(()).unwrap();
+                    release_rx.recv().unwrap();
+                    Ok(())
+                },
+            )
+        });
+        started_rx.recv().unwrap();
+
+        let waiter = tokio::spawn(async {
+            run_exclusive("codex", "next", "test", || Ok(false), || Ok(())).await
+        });
+        // 等待中的切换不占用运行时线程，其他任务仍可执行
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+        release_tx.send(()).unwrap();
+
+        assert_eq!(holder.join().unwrap().unwrap(), SwitchOutcome::Switched);
+        assert_eq!(waiter.await.unwrap().unwrap(), SwitchOutcome::Switched);
+    }
+
+    #[tokio::test]
+    async fn cancelled_waiter_leaves_the_queue() {
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        let (started_tx, started_rx) = std::sync::mpsc::channel::<()>();
+        let holder = std::thread::spawn(move || {
+            run_exclusive_blocking(
+                "gemini",
+                "slow",
+                "test",
+                || Ok(false),
+                || {
+                    started_tx.send(()).unwrap();
+                    release_rx.recv().unwrap();
+                    Ok(())
+                },
+            )
+        });
+        started_rx.recv().unwrap();
+
+        // 排队中的切换超时被丢弃
+        let cancelled = tokio::time::timeout(
+            std::time::Duration::from_millis(20),
+            run_exclusive("gemini", "dropped", "test", || Ok(false), || Ok(())),
+        )
+        .await;
+        assert!(cancelled.is_err());
+        assert!(status().queued.iter().all(|r| r.provider_id != "dropped"));
+
+        release_tx.send(()).unwrap();
+        assert_eq!(holder.join().unwrap().unwrap(), SwitchOutcome::Switched);
+        let next = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            run_exclusive("gemini", "next", "test", || Ok(false), || Ok(())),
+        )
+        .await
+        .expect("后续切换不应被已取消的排队阻塞");
+        assert_eq!(next.unwrap(), SwitchOutcome::Switched);
+    }
+}
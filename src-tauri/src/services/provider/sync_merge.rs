@@ -0,0 +1,459 @@
+//! 同步时的供应商三方合并
+//!
+//! 以上次同步的供应商列表为基线（base），按字段合并本机（local）与远端（remote）的修改：
+//! - 只有一方修改的字段取修改后的值，双方改成相同值时直接采用
+//! - 双方改成不同值时记为冲突（默认保留本机的值），由前端展示三方取值后选择
+//! - 一方删除、另一方未修改的供应商随之删除；一方删除而另一方修改时整条记为冲突
+//!
+//! 字段路径与配置快照一致（如 `settingsConfig/env/ANTHROPIC_BASE_URL`），数组整体视为一个值；
+//! 冲突路径为空表示整条供应商。一方是叶子值（如空对象）、另一方在同一路径下有子字段时，
+//! 该路径整体按一个值合并，双方都改动时整体记为冲突。
+
+use std::collections::BTreeMap;
+
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::services::config_snapshot::flatten;
+
+/// 单个字段（或整条供应商）的冲突
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeConflict {
+    pub provider_id: String,
+    pub provider_name: String,
+    /// 字段路径（为空表示整条供应商被一方删除、另一方修改）
+    pub field: String,
+    /// 各方的取值（不存在时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote: Option<Value>,
+}
+
+/// 冲突的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ResolutionChoice {
+    Local,
+    Remote,
+    Base,
+    /// 使用手动填写的 `value`
+    Custom,
+}
+
+/// 前端对某个冲突的选择
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConflictResolution {
+    pub provider_id: String,
+    #[serde(default)]
+    pub field: String,
+    pub choice: ResolutionChoice,
+    /// `custom` 时的取值（为空表示删除该字段或整条供应商）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
+}
+
+/// 合并结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderMergeResult {
+    /// 合并后的供应商列表（未解决的冲突暂取本机的值）
+    pub merged: IndexMap<String, Provider>,
+    /// 未解决的冲突
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl ConflictResolution {
+    fn pick(&self, conflict: &MergeConflict) -> Option<Value> {
+        match self.choice {
+            ResolutionChoice::Local => conflict.local.clone(),
+            ResolutionChoice::Remote => conflict.remote.clone(),
+            ResolutionChoice::Base => conflict.base.clone(),
+            ResolutionChoice::Custom => self.value.clone(),
+        }
+    }
+}
+
+struct Merger<'a> {
+    resolutions: &'a [ConflictResolution],
+    conflicts: Vec<MergeConflict>,
+}
+
+impl Merger<'_> {
+    /// 双方修改不一致时：有对应的选择则采用，否则记为冲突并保留本机的值
+    fn resolve(&mut self, conflict: MergeConflict) -> Option<Value> {
+        let chosen = self
+            .resolutions
+            .iter()
+            .find(|r| r.provider_id == conflict.provider_id && r.field == conflict.field);
+        match chosen {
+            Some(resolution) => resolution.pick(&conflict),
+            None => {
+                let local = conflict.local.clone();
+                self.conflicts.push(conflict);
+                local
+            }
+        }
+    }
+
+    fn merge_provider(
+        &mut self,
+        id: &str,
+        base: Option<Value>,
+        local: Option<Value>,
+        remote: Option<Value>,
+    ) -> Option<Value> {
+        let name = [&local, &remote, &base]
+            .into_iter()
+            .flatten()
+            .find_map(|v| v.get("name").and_then(Value::as_str))
+            .unwrap_or(id)
+            .to_string();
+        let conflict = |field: String, base, local, remote| MergeConflict {
+            provider_id: id.to_string(),
+            provider_name: name.clone(),
+            field,
+            base,
+            local,
+            remote,
+        };
+
+        match (base, local, remote) {
+            (base, Some(local), Some(remote)) => {
+                let (mut b, mut l, mut r) = (BTreeMap::new(), BTreeMap::new(), BTreeMap::new());
+                if let Some(base) = &base {
+                    flatten("", base, &mut b);
+                }
+                flatten("", &local, &mut l);
+                flatten("", &remote, &mut r);
+
+                let mut paths: Vec<String> = l.keys().chain(r.keys()).cloned().collect();
+                paths.sort();
+                paths.dedup();
+                let roots = shape_roots(&l, &r);
+
+                let mut merged = BTreeMap::new();
+                for path in paths {
+                    if roots.iter().any(|root| is_within(&path, root)) {
+                        continue;
+                    }
+                    let (b, l, r) = (b.get(&path), l.get(&path), r.get(&path));
+                    let value = if l == r || r == b {
+                        l.cloned()
+                    } else if l == b {
+                        r.cloned()
+                    } else {
+                        self.resolve(conflict(path.clone(), b.cloned(), l.cloned(), r.cloned()))
+                    };
+                    if let Some(value) = value {
+                        merged.insert(path, value);
+                    }
+                }
+                // 结构不一致的路径整体合并
+                for root in roots {
+                    let (b, l, r) = (subtree(&b, &root), subtree(&l, &root), subtree(&r, &root));
+                    let value = if l == r || r == b {
+                        l
+                    } else if l == b {
+                        r
+                    } else {
+                        self.resolve(conflict(root.clone(), b, l, r))
+                    };
+                    if let Some(value) = value {
+                        merged.insert(root, value);
+                    }
+                }
+                Some(unflatten(merged))
+            }
+            (None, local, None) => local,
+            (None, None, remote) => remote,
+            (Some(_), None, None) => None,
+            // 一方删除：另一方未修改则删除，否则整条冲突
+            (Some(base), None, Some(remote)) if remote == base => None,
+            (Some(base), Some(local), None) if local == base => None,
+            (Some(base), local, remote) => {
+                self.resolve(conflict(String::new(), Some(base), local, remote))
+            }
+        }
+    }
+}
+
+fn is_within(path: &str, root: &str) -> bool {
+    path == root
+        || path
+            .strip_prefix(root)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// 一方是叶子、另一方在其下有子字段的路径（只保留最外层）
+fn shape_roots(l: &BTreeMap<String, Value>, r: &BTreeMap<String, Value>) -> Vec<String> {
+    let has_children = |map: &BTreeMap<String, Value>, path: &str| {
+        map.keys().any(|k| k != path && is_within(k, path))
+    };
+    let mut roots: Vec<String> = l
+        .keys()
+        .filter(|path| has_children(r, path))
+        .chain(r.keys().filter(|path| has_children(l, path)))
+        .cloned()
+        .collect();
+    roots.sort();
+    roots.dedup();
+    let outer: Vec<String> = roots
+        .iter()
+        .filter(|path| {
+            !roots
+                .iter()
+                .any(|root| root != *path && is_within(path, root))
+        })
+        .cloned()
+        .collect();
+    outer
+}
+
+/// 某个路径下的完整取值（叶子值或由子字段还原的对象）
+fn subtree(fields: &BTreeMap<String, Value>, root: &str) -> Option<Value> {
+    if let Some(value) = fields.get(root) {
+        return Some(value.clone());
+    }
+    let prefix = format!("{root}/");
+    let children: BTreeMap<String, Value> = fields
+        .iter()
+        .filter_map(|(k, v)| Some((k.strip_prefix(&prefix)?.to_string(), v.clone())))
+        .collect();
+    (!children.is_empty()).then(|| unflatten(children))
+}
+
+/// 把 路径 → 叶子值 还原为对象
+fn unflatten(fields: BTreeMap<String, Value>) -> Value {
+    let mut root = Value::Object(Map::new());
+    for (path, value) in fields {
+        let keys: Vec<String> = path
+            .split('/')
+            .map(|k| k.replace("~1", "/").replace("~0", "~"))
+            .collect();
+        let mut node = &mut root;
+        for key in &keys[..keys.len() - 1] {
+            if !node.is_object() {
+                *node = Value::Object(Map::new());
+            }
+            node = node
+                .as_object_mut()
+                .expect("object ensured above")
+                .entry(key.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+        }
+        if !node.is_object() {
+            *node = Value::Object(Map::new());
+        }
+        if let Some(map) = node.as_object_mut() {
+            map.insert(keys[keys.len() - 1].clone(), value);
+        }
+    }
+    root
+}
+
+fn to_values(providers: &IndexMap<String, Provider>) -> Result<IndexMap<String, Value>, AppError> {
+    providers
+        .iter()
+        .map(|(id, p)| {
+            let value =
+                serde_json::to_value(p).map_err(|source| AppError::JsonSerialize { source })?;
+            Ok((id.clone(), value))
+        })
+        .collect()
+}
+
+/// 三方合并供应商列表，`resolutions` 中已选择的冲突按选择处理
+///
+/// 合并结果保持本机的顺序，远端新增的供应商排在最后。
+pub fn merge(
+    base: &IndexMap<String, Provider>,
+    local: &IndexMap<String, Provider>,
+    remote: &IndexMap<String, Provider>,
+    resolutions: &[ConflictResolution],
+) -> Result<ProviderMergeResult, AppError> {
+    let (mut base, mut local, mut remote) =
+        (to_values(base)?, to_values(local)?, to_values(remote)?);
+    let mut ids: Vec<String> = local.keys().cloned().collect();
+    for id in remote.keys().chain(base.keys()) {
+        if !ids.contains(id) {
+            ids.push(id.clone());
+        }
+    }
+
+    let mut merger = Merger {
+        resolutions,
+        conflicts: Vec::new(),
+    };
+    let mut merged = IndexMap::new();
+    for id in ids {
+        let value = merger.merge_provider(
+            &id,
+            base.shift_remove(&id),
+            local.shift_remove(&id),
+            remote.shift_remove(&id),
+        );
+        if let Some(mut value) = value {
+            // 合并不改变供应商 ID
+            if let Some(map) = value.as_object_mut() {
+                map.insert("id".to_string(), Value::String(id.clone()));
+            }
+            let provider: Provider = serde_json::from_value(value).map_err(|e| {
+                AppError::InvalidInput(format!("供应商 {id} 合并后的配置无效: {e}"))
+            })?;
+            merged.insert(id, provider);
+        }
+    }
+
+    Ok(ProviderMergeResult {
+        merged,
+        conflicts: merger.conflicts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn provider(id: &str, base_url: &str, model: &str) -> Provider {
+        Provider::with_id(
+            id.to_string(),
+            id.to_uppercase(),
+            json!({ "env": { "ANTHROPIC_BASE_URL": base_url, "ANTHROPIC_MODEL": model } }),
+            None,
+        )
+    }
+
+    fn list(providers: &[Provider]) -> IndexMap<String, Provider> {
+        providers
+            .iter()
+            .map(|p| (p.id.clone(), p.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn merges_non_overlapping_field_edits() {
+        let base = list(&[provider("a", "https://old", "m1")]);
+        let local = list(&[provider("a", "https://new", "m1")]);
+        let remote = list(&[
+            provider("a", "https://old", "m2"),
+            provider("b", "https://b", "m1"),
+        ]);
+
+        let result = merge(&base, &local, &remote, &[]).unwrap();
+        assert!(result.conflicts.is_empty());
+        let env = &result.merged["a"].settings_config["env"];
+        assert_eq!(env["ANTHROPIC_BASE_URL"], "https://new");
+        assert_eq!(env["ANTHROPIC_MODEL"], "m2");
+        assert_eq!(result.merged.keys().collect::<Vec<_>>(), ["a", "b"]);
+    }
+
+    #[test]
+    fn reports_conflicts_and_applies_resolutions() {
+        let base = list(&[provider("a", "https://old", "m1")]);
+        let local = list(&[provider("a", "https://local", "m1")]);
+        let remote = list(&[provider("a", "https://remote", "m1")]);
+
+        let result = merge(&base, &local, &remote, &[]).unwrap();
+        assert_eq!(
+            result.conflicts,
+            vec![MergeConflict {
+                provider_id: "a".into(),
+                provider_name: "A".into(),
+                field: "settingsConfig/env/ANTHROPIC_BASE_URL".into(),
+                base: Some(json!("https://old")),
+                local: Some(json!("https://local")),
+                remote: Some(json!("https://remote")),
+            }]
+        );
+        // 未解决时暂取本机的值
+        assert_eq!(
+            result.merged["a"].settings_config["env"]["ANTHROPIC_BASE_URL"],
+            "https://local"
+        );
+
+        let resolution: ConflictResolution = serde_json::from_value(json!({
+            "providerId": "a",
+            "field": "settingsConfig/env/ANTHROPIC_BASE_URL",
+            "choice": "remote"
+        }))
+        .unwrap();
+        let result = merge(&base, &local, &remote, &[resolution]).unwrap();
+        assert!(result.conflicts.is_empty());
+        assert_eq!(
+            result.merged["a"].settings_config["env"]["ANTHROPIC_BASE_URL"],
+            "https://remote"
+        );
+    }
+
+    #[test]
+    fn deletion_against_edit_is_a_conflict() {
+        let base = list(&[
+            provider("a", "https://a", "m1"),
+            provider("b", "https://b", "m1"),
+        ]);
+        // 本机删除 a（远端未改）、远端删除 b（本机修改过）
+        let local = list(&[provider("b", "https://b2", "m1")]);
+        let remote = list(&[provider("a", "https://a", "m1")]);
+
+        let result = merge(&base, &local, &remote, &[]).unwrap();
+        assert!(!result.merged.contains_key("a"));
+        assert_eq!(result.conflicts.len(), 1);
+        let conflict = &result.conflicts[0];
+        assert_eq!(
+            (conflict.provider_id.as_str(), conflict.field.as_str()),
+            ("b", "")
+        );
+        assert!(conflict.local.is_some() && conflict.remote.is_none());
+
+        let delete = ConflictResolution {
+            provider_id: "b".into(),
+            field: String::new(),
+            choice: ResolutionChoice::Remote,
+            value: None,
+        };
+        let result = merge(&base, &local, &remote, &[delete]).unwrap();
+        assert!(result.merged.is_empty() && result.conflicts.is_empty());
+    }
+
+    #[test]
+    fn leaf_against_subtree_merges_as_a_whole() {
+        let mut cleared = provider("a", "https://old", "m1");
+        cleared.settings_config = json!({ "env": {} });
+        let base = list(&[provider("a", "https://old", "m1")]);
+
+        // 本机清空 env、远端未改：采用本机的空对象
+        let result = merge(&base, &list(&[cleared.clone()]), &base, &[]).unwrap();
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.merged["a"].settings_config, json!({ "env": {} }));
+
+        // 本机清空 env、远端修改了其中的字段：整个 env 记为冲突
+        let remote = list(&[provider("a", "https://remote", "m1")]);
+        let result = merge(&base, &list(&[cleared]), &remote, &[]).unwrap();
+        assert_eq!(result.conflicts.len(), 1);
+        let conflict = &result.conflicts[0];
+        assert_eq!(conflict.field, "settingsConfig/env");
+        assert_eq!(conflict.local, Some(json!({})));
+        assert_eq!(
+            conflict.remote,
+            Some(json!({ "ANTHROPIC_BASE_URL": "https://remote", "ANTHROPIC_MODEL": "m1" }))
+        );
+        assert_eq!(result.merged["a"].settings_config, json!({ "env": {} }));
+    }
+
+    #[test]
+    fn unflatten_restores_escaped_keys() {
+        let value = json!({ "a/b": { "c~d": 1, "e": [1, 2] }, "f": {} });
+        let mut fields = BTreeMap::new();
+        flatten("", &value, &mut fields);
+        assert_eq!(unflatten(fields), value);
+    }
+}
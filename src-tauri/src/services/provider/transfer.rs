@@ -0,0 +1,246 @@
+//! 供应商设备间传输
+//!
+//! 把单个供应商编码为紧凑的文本载荷（`CCS1.` 前缀 + base64url），前端渲染为二维码，
+//! 另一台设备扫码或粘贴后导入，无需云同步。
+//!
+//! 默认不包含 API Key 等凭据；需要一并传输时必须设置口令，载荷使用
+//! PBKDF2-HMAC-SHA256 派生密钥 + AES-256-GCM 加密。
+
+use std::num::NonZeroU32;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::proxy::custom_headers::mask_secret_values;
+use crate::services::vault;
+
+const PREFIX: &str = "CCS1.";
+const FLAG_ENCRYPTED: u8 = 0b0000_0001;
+const SALT_LEN: usize = 16;
+const PBKDF2_ITERATIONS: u32 = 100_000;
+const AAD: &[u8] = b"cc-switch-transfer-v1";
+
+/// 超过此长度的载荷难以被普通二维码容纳（仍可复制粘贴）
+pub const QR_CAPACITY: usize = 2_900;
+
+/// 解码后的供应商
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferredProvider {
+    pub app: String,
+    pub provider: Provider,
+    /// 是否包含凭据
+    pub includes_key: bool,
+}
+
+/// 清空凭据类字段（保留字段本身，导入后由用户填写）
+pub(super) fn strip_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                let key = key.to_ascii_lowercase().replace(['_', '-'], "");
+                let secret = ["key", "token", "secret", "password"]
+                    .iter()
+                    .any(|suffix| key.ends_with(suffix));
+                if secret && v.is_string() {
+                    *v = Value::String(String::new());
+                } else {
+                    strip_secrets(v);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_secrets),
+        _ => {}
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        NonZeroU32::new(PBKDF2_ITERATIONS).expect("iterations > 0"),
+        salt,
+        passphrase.as_bytes(),
+        &mut key,
+    );
+    key
+}
+
+/// 编码供应商
+///
+/// `include_key` 为 true 时必须提供口令。
+pub fn encode(
+    app_type: &AppType,
+    provider: &Provider,
+    include_key: bool,
+    passphrase: Option<&str>,
+) -> Result<String, AppError> {
+    let passphrase = passphrase.filter(|p| !p.is_empty());
+    if include_key && passphrase.is_none() {
+        return Err(AppError::localized(
+            "transfer.passphrase_required",
+            "包含 API Key 时必须设置口令",
+            "A passphrase is required when including the API key",
+        ));
+    }
+
+    // 本机相关的字段不参与传输
+    let mut provider = provider.clone();
+    provider.id = String::new();
+    provider.created_at = None;
+    provider.sort_index = None;
+    provider.in_failover_queue = false;
+    if !include_key {
+        mask_secret_values(&mut provider.settings_config, "");
+    }
+
+    let mut provider_value =
+        serde_json::to_value(&provider).map_err(|e| AppError::JsonSerialize { source: e })?;
+    if !include_key {
+        strip_secrets(&mut provider_value);
+    }
+    let body = serde_json::json!({
+        "app": app_type.as_str(),
+        "provider": provider_value,
+        "includesKey": include_key,
+    });
+    let plaintext = serde_json::to_vec(&body).map_err(|e| AppError::JsonSerialize { source: e })?;
+
+    let mut bytes = Vec::with_capacity(plaintext.len() + 64);
+    match passphrase {
+        Some(passphrase) => {
+            let mut salt = [0u8; SALT_LEN];
+            SystemRandom::new()
+                .fill(&mut salt)
+                .map_err(|_| AppError::Message("生成随机数失败".to_string()))?;
+            bytes.push(FLAG_ENCRYPTED);
+            bytes.extend_from_slice(&salt);
+            bytes.extend(vault::seal(
+                &derive_key(passphrase, &salt),
+                AAD,
+                &plaintext,
+            )?);
+        }
+        None => {
+            bytes.push(0);
+            bytes.extend(plaintext);
+        }
+    }
+
+    let payload = format!("{PREFIX}{}", URL_SAFE_NO_PAD.encode(bytes));
+    if payload.len() > QR_CAPACITY {
+        log::warn!(
+            "供应商传输载荷较长（{} 字符），二维码可能无法识别",
+            payload.len()
+        );
+    }
+    Ok(payload)
+}
+
+/// 解码扫描或粘贴的载荷
+pub fn decode(payload: &str, passphrase: Option<&str>) -> Result<TransferredProvider, AppError> {
+    let invalid = || {
+        AppError::localized(
+            "transfer.invalid_payload",
+            "无法识别的供应商传输内容",
+            "Unrecognized provider transfer payload",
+        )
+    };
+
+    let encoded = payload.trim().strip_prefix(PREFIX).ok_or_else(invalid)?;
+    let bytes = URL_SAFE_NO_PAD.decode(encoded).map_err(|_| invalid())?;
+    let (&flags, rest) = bytes.split_first().ok_or_else(invalid)?;
+
+    let plaintext = if flags & FLAG_ENCRYPTED != 0 {
+        let passphrase = passphrase.filter(|p| !p.is_empty()).ok_or_else(|| {
+            AppError::localized(
+                "transfer.passphrase_missing",
+                "该内容已加密，请输入口令",
+                "This payload is encrypted; enter the passphrase",
+            )
+        })?;
+        if rest.len() < SALT_LEN {
+            return Err(invalid());
+        }
+        let (salt, sealed) = rest.split_at(SALT_LEN);
+        vault::open(&derive_key(passphrase, salt), AAD, sealed)?
+    } else {
+        rest.to_vec()
+    };
+
+    serde_json::from_slice(&plaintext).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> Provider {
+        let mut provider = Provider::with_id(
+            "relay".into(),
+            "Relay".into(),
+            json!({
+                "env": {
+                    "ANTHROPIC_BASE_URL": "https://relay.example",
+                    "ANTHROPIC_AUTH_TOKEN": "sk-secret-value",
+                    "CLAUDE_CODE_MAX_OUTPUT_TOKENS": "32000"
+                },
+                "custom_headers": {
+                    "X-Referral": { "value": "ref-secret", "secret": true }
+                }
+            }),
+            Some("https://relay.example".into()),
+        );
+        provider.sort_index = Some(3);
+        provider
+    }
+
+    #[test]
+    fn plain_payload_strips_keys() {
+        let payload = encode(&AppType::Claude, &sample(), false, None).unwrap();
+        assert!(payload.starts_with(PREFIX));
+
+        let decoded = decode(&payload, None).unwrap();
+        assert_eq!(decoded.app, "claude");
+        assert!(!decoded.includes_key);
+        let env = &decoded.provider.settings_config["env"];
+        assert_eq!(env["ANTHROPIC_AUTH_TOKEN"], "");
+        assert_eq!(env["ANTHROPIC_BASE_URL"], "https://relay.example");
+        assert_eq!(env["CLAUDE_CODE_MAX_OUTPUT_TOKENS"], "32000");
+        assert_eq!(
+            decoded.provider.settings_config["custom_headers"]["X-Referral"]["value"],
+            ""
+        );
+        assert!(decoded.provider.id.is_empty());
+        assert!(decoded.provider.sort_index.is_none());
+    }
+
+    #[test]
+    fn encrypted_payload_requires_passphrase() {
+        assert!(encode(&AppType::Claude, &sample(), true, None).is_err());
+
+        let payload = encode(&AppType::Claude, &sample(), true, Some("hunter2")).unwrap();
+        assert!(decode(&payload, None).is_err());
+        assert!(decode(&payload, Some("wrong")).is_err());
+
+        let decoded = decode(&payload, Some("hunter2")).unwrap();
+        assert!(decoded.includes_key);
+        assert_eq!(
+            decoded.provider.settings_config["env"]["ANTHROPIC_AUTH_TOKEN"],
+            "sk-secret-value"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_payloads() {
+        assert!(decode("hello", None).is_err());
+        assert!(decode("CCS1.!!!", None).is_err());
+    }
+}
@@ -6,9 +6,11 @@ use crate::app_config::AppType;
 use crate::config::{get_claude_settings_path, read_json_file, write_json_file};
 use crate::database::Database;
 use crate::provider::Provider;
+use crate::proxy::keep_warm::KeepWarmEstimate;
 use crate::proxy::server::ProxyServer;
 use crate::proxy::types::*;
 use crate::services::provider::write_live_snapshot;
+use crate::services::startup_profile::{self, Subsystem};
 use serde_json::{json, Value};
 use std::str::FromStr;
 use std::sync::Arc;
@@ -43,6 +45,12 @@ impl ProxyService {
 
     /// 启动代理服务器
     pub async fn start(&self) -> Result<ProxyServerInfo, String> {
+        if crate::instance_guard::is_attached() {
+            return Err("另一个 cc-switch 实例正在运行，只读模式下不能启动代理".to_string());
+        }
+        if !startup_profile::is_enabled(Subsystem::Proxy) {
+            return Err("代理子系统已关闭，请先在设置中开启".to_string());
+        }
         // 1. 启动时自动设置 proxy_enabled = true
         let mut global_config = self
             .db
@@ -699,6 +707,11 @@ impl ProxyService {
 
     /// 构造写入 Live 的代理地址（处理 0.0.0.0 / IPv6 等特殊情况）
     async fn build_proxy_urls(&self) -> Result<(String, String), String> {
+        // 仅本地套接字模式下 TCP 端口随机分配，CLI 无法通过固定地址访问
+        let socket = crate::settings::get_settings().proxy_local_socket;
+        if socket.enabled && socket.socket_only {
+            return Err("代理仅通过本地套接字提供服务，无法接管 Live 配置".to_string());
+        }
         let config = self
             .db
             .get_proxy_config()
@@ -1405,24 +1418,6 @@ impl ProxyService {
         Ok(())
     }
 
-    /// 代理模式下切换供应商（热切换，不写 Live）
-    pub async fn switch_proxy_target(
-        &self,
-        app_type: &str,
-        provider_id: &str,
-    ) -> Result<(), String> {
-        // 更新数据库中的 is_current 标记
-        let app_type_enum =
-            AppType::from_str(app_type).map_err(|_| format!("无效的应用类型: {app_type}"))?;
-
-        self.db
-            .set_current_provider(app_type_enum.as_str(), provider_id)
-            .map_err(|e| format!("更新当前供应商失败: {e}"))?;
-
-        log::info!("代理模式：已切换 {app_type} 的目标供应商为 {provider_id}");
-        Ok(())
-    }
-
     // ==================== Live 配置读写辅助方法 ====================
 
     /// 更新 TOML 字符串中的 base_url
@@ -1570,6 +1565,35 @@ impl ProxyService {
         }
     }
 
+    /// 获取 TPS 分桶历史（最近 1 小时，服务器未运行时为空）
+    pub async fn get_tps_history(&self) -> Result<Vec<TpsHistoryPoint>, String> {
+        if let Some(server) = self.server.read().await.as_ref() {
+            Ok(server.get_tps_history().await)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /// 各应用类型当前的 TPS（服务器未运行时为空）
+    pub async fn get_app_tps(&self) -> std::collections::HashMap<String, f64> {
+        match self.server.read().await.as_ref() {
+            Some(server) => server.get_app_tps().await,
+            None => std::collections::HashMap::new(),
+        }
+    }
+
+    /// 估算空闲保活开销（尚无活动供应商时按 1 个估算）
+    pub async fn get_keep_warm_estimate(&self) -> Result<KeepWarmEstimate, String> {
+        let target_count = match self.server.read().await.as_ref() {
+            Some(server) => server.keep_warm_target_count().await,
+            None => 0,
+        };
+        Ok(crate::proxy::keep_warm::estimate(
+            &crate::settings::get_settings(),
+            target_count.max(1),
+        ))
+    }
+
     /// 获取代理配置
     pub async fn get_config(&self) -> Result<ProxyConfig, String> {
         self.db
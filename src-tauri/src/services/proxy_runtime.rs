@@ -0,0 +1,169 @@
+//! 代理运行状态的保存与恢复
+//!
+//! 接管状态记录在 proxy_config 中，但代理是否在运行（未接管时）、实际监听端口、
+//! 临时路由覆盖与学习到的自适应并发上限只在内存中。这里定期并在退出前把这些状态写入
+//! settings 表，启动时按原样恢复，避免切换实验性设置后重启丢失当前所处的模式。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::error::AppError;
+use crate::proxy::concurrency::{self, LearnedLimit};
+use crate::proxy::session_override::{self, SessionOverride};
+use crate::services::ProxyService;
+
+const RUNTIME_STATE_KEY: &str = "proxy_runtime_state";
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// 代理运行状态
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyRuntimeState {
+    pub running: bool,
+    /// 实际监听端口（仅本地套接字模式下为随机端口）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    /// 仍然生效的临时路由覆盖
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub session_overrides: Vec<SessionOverride>,
+    /// 学习到的自适应并发上限
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub adaptive_limits: Vec<LearnedLimit>,
+}
+
+/// 采集当前运行状态
+pub async fn capture(proxy_service: &ProxyService) -> ProxyRuntimeState {
+    let running = proxy_service.is_running().await;
+    let port = if running {
+        proxy_service.get_status().await.ok().map(|s| s.port)
+    } else {
+        None
+    };
+    ProxyRuntimeState {
+        running,
+        port,
+        session_overrides: session_override::list(chrono::Utc::now().timestamp()),
+        adaptive_limits: concurrency::learned_limits(),
+    }
+}
+
+pub fn load(db: &Database) -> Result<Option<ProxyRuntimeState>, AppError> {
+    let Some(raw) = db.get_setting(RUNTIME_STATE_KEY)? else {
+        return Ok(None);
+    };
+    serde_json::from_str(&raw)
+        .map(Some)
+        .map_err(|e| AppError::Message(format!("解析代理运行状态失败: {e}")))
+}
+
+pub fn save(db: &Database, state: &ProxyRuntimeState) -> Result<(), AppError> {
+    let json = serde_json::to_string(state).map_err(|e| AppError::JsonSerialize { source: e })?;
+    db.set_setting(RUNTIME_STATE_KEY, &json)
+}
+
+/// 采集并保存（只读实例不写入，避免覆盖主实例的状态）
+pub async fn persist(db: &Database, proxy_service: &ProxyService) {
+    if crate::instance_guard::is_attached() {
+        return;
+    }
+    let state = capture(proxy_service).await;
+    if let Err(e) = save(db, &state) {
+        log::warn!("保存代理运行状态失败: {e}");
+    }
+}
+
+/// 启动时恢复上次保存的运行状态（接管状态已由 proxy_config 恢复）
+pub async fn restore(db: &Database, proxy_service: &ProxyService) {
+    let state = match load(db) {
+        Ok(Some(state)) => state,
+        Ok(None) => return,
+        Err(e) => {
+            log::warn!("{e}");
+            return;
+        }
+    };
+
+    let restored =
+        session_override::restore(state.session_overrides, chrono::Utc::now().timestamp());
+    if restored > 0 {
+        log::info!("已恢复 {restored} 条临时路由覆盖");
+    }
+    concurrency::restore_limits(&state.adaptive_limits);
+
+    if !state.running || proxy_service.is_running().await {
+        return;
+    }
+    match proxy_service.start().await {
+        Ok(info) => {
+            log::info!("✓ 已恢复代理运行状态: {}:{}", info.address, info.port);
+            if let Some(port) = state.port.filter(|port| *port != info.port) {
+                log::warn!(
+                    "代理监听端口由 {port} 变为 {}，直接连接旧端口的客户端需要更新地址",
+                    info.port
+                );
+            }
+        }
+        Err(e) => log::error!("✗ 恢复代理运行状态失败: {e}"),
+    }
+}
+
+/// 后台定期保存，异常退出时也能恢复到最近的状态
+pub async fn run(db: Arc<Database>, proxy_service: ProxyService) {
+    let mut last: Option<ProxyRuntimeState> = None;
+    loop {
+        tokio::time::sleep(SAVE_INTERVAL).await;
+        // 代理子系统关闭期间保留关闭前的状态，重新开启后据此恢复
+        if crate::instance_guard::is_attached()
+            || !crate::services::startup_profile::is_enabled(
+                crate::services::startup_profile::Subsystem::Proxy,
+            )
+        {
+            continue;
+        }
+        let state = capture(&proxy_service).await;
+        if last.as_ref() == Some(&state) {
+            continue;
+        }
+        match save(&db, &state) {
+            Ok(()) => last = Some(state),
+            Err(e) => log::warn!("保存代理运行状态失败: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::session_override::OverrideTarget;
+
+    #[test]
+    fn state_round_trips_through_settings() {
+        let db = Database::memory().unwrap();
+        assert!(load(&db).unwrap().is_none());
+
+        let state = ProxyRuntimeState {
+            running: true,
+            port: Some(15721),
+            session_overrides: vec![SessionOverride {
+                id: "o1".into(),
+                app_type: "claude".into(),
+                target: OverrideTarget::Client("aider".into()),
+                provider_id: "backup".into(),
+                expires_at: None,
+                remaining_requests: Some(3),
+                created_at: 1_000,
+            }],
+            adaptive_limits: vec![LearnedLimit {
+                app_type: "claude".into(),
+                provider_id: "relay".into(),
+                provider_name: "Relay".into(),
+                limit: 6.5,
+            }],
+        };
+        save(&db, &state).unwrap();
+        assert_eq!(load(&db).unwrap(), Some(state));
+    }
+}
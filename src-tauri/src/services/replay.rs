@@ -0,0 +1,505 @@
+//! 请求回放
+//!
+//! 按请求日志重放一段时间内的流量，用于复现与负载相关的问题、离线验证新的路由规则：
+//! - `mock`：直接发往内置模拟上游，不经过路由
+//! - `proxy`：发往本地代理，按当前的路由、故障转移与各项规则处理
+//! - `provider`：发往本地代理，并通过临时覆盖固定到指定供应商
+//!
+//! 请求日志不保存请求体，回放请求按日志中的模型、是否流式与 token 数合成，
+//! 输入按原始 token 数生成填充内容（有上限，避免回放真实供应商时消耗过多）。
+//! 请求按原始时间间隔（秒级）发出，可按倍速压缩；请求之间并发，不等待上一条完成。
+//!
+//! 回放请求在请求日志中标记为 `is_replay`，不计入用量统计、用量预测、
+//! 真实流量健康评分与延迟 SLO，也不会被再次回放；回放真实供应商同样产生费用，
+//! 因此仍计入供应商的每日 / 每月限额。
+//!
+//! 回放标记使用进程内随机生成的令牌（`x-cc-switch-replay`），客户端无法伪造；
+//! 该请求头不会转发给上游。
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::http::HeaderMap;
+use once_cell::sync::Lazy;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::task::JoinSet;
+
+use crate::app_config::AppType;
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::proxy::session_override::{self, OverrideTarget, CLIENT_HEADER};
+use crate::services::background_task::TaskHandle;
+use crate::services::proxy::ProxyService;
+
+/// 回放请求携带的客户端名称（`x-cc-switch-client`），便于在日志中区分
+pub const CLIENT_NAME: &str = "cc-switch-replay";
+/// 回放令牌请求头
+pub const REPLAY_HEADER: &str = "x-cc-switch-replay";
+
+/// 本次运行的回放令牌，只有回放发出的请求才携带
+static REPLAY_TOKEN: Lazy<String> = Lazy::new(|| uuid::Uuid::new_v4().simple().to_string());
+
+/// 请求是否由回放发出（代理据此在请求日志中标记）
+pub fn is_replay(headers: &HeaderMap) -> bool {
+    headers
+        .get(REPLAY_HEADER)
+        .is_some_and(|v| v.as_bytes() == REPLAY_TOKEN.as_bytes())
+}
+
+/// 单次回放最多的请求数
+const MAX_REQUESTS: u32 = 5000;
+/// 倍速上限
+const MAX_SPEED: f64 = 100.0;
+/// 合成输入的 token 上限
+const MAX_PROMPT_TOKENS: u32 = 2000;
+/// 合成请求的输出上限
+const MAX_OUTPUT_TOKENS: u32 = 4096;
+/// 单个请求超时
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// 回放目标
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ReplayTarget {
+    Mock,
+    Proxy,
+    #[serde(rename_all = "camelCase")]
+    Provider {
+        provider_id: String,
+    },
+}
+
+/// 回放参数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayRequest {
+    pub app_type: AppType,
+    /// 时间范围（秒级时间戳，闭区间）
+    pub start: i64,
+    pub end: i64,
+    pub target: ReplayTarget,
+    /// 倍速（1 为原始节奏）
+    #[serde(default = "default_speed")]
+    pub speed: f64,
+}
+
+fn default_speed() -> f64 {
+    1.0
+}
+
+/// 日志中的一条原始请求
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayEntry {
+    pub created_at: i64,
+    pub model: String,
+    pub is_streaming: bool,
+    pub input_tokens: u32,
+    pub output_tokens: u32,
+    pub status_code: u16,
+    pub latency_ms: u64,
+}
+
+/// 单条回放结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayResult {
+    /// 原始请求时间
+    pub original_at: i64,
+    pub model: String,
+    pub original_status: u16,
+    pub original_latency_ms: u64,
+    /// 回放的状态码（请求未完成时为空）
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Database {
+    /// 读取时间范围内的请求日志（按时间顺序）
+    pub fn get_replay_entries(
+        &self,
+        app_type: &str,
+        start: i64,
+        end: i64,
+        limit: u32,
+    ) -> Result<Vec<ReplayEntry>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn.prepare(
+            "SELECT created_at, model, is_streaming, input_tokens, output_tokens,
+                    status_code, latency_ms
+             FROM proxy_request_logs
+             WHERE app_type = ?1 AND created_at >= ?2 AND created_at <= ?3 AND is_replay = 0
+             ORDER BY created_at ASC
+             LIMIT ?4",
+        )?;
+        let rows = stmt.query_map(params![app_type, start, end, limit], |row| {
+            Ok(ReplayEntry {
+                created_at: row.get(0)?,
+                model: row.get(1)?,
+                is_streaming: row.get::<_, i64>(2)? != 0,
+                input_tokens: row.get::<_, i64>(3)? as u32,
+                output_tokens: row.get::<_, i64>(4)? as u32,
+                status_code: row.get::<_, i64>(5)? as u16,
+                latency_ms: row.get::<_, i64>(6)? as u64,
+            })
+        })?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(row?);
+        }
+        Ok(entries)
+    }
+}
+
+/// 按原始 token 数生成填充输入
+fn filler_prompt(input_tokens: u32) -> String {
+    let words = input_tokens.clamp(1, MAX_PROMPT_TOKENS) as usize;
+    let mut prompt = "Replay:".to_string();
+    for _ in 1..words {
+        prompt.push_str(" lorem");
+    }
+    prompt
+}
+
+/// 合成请求：返回相对代理根路径的地址与请求体
+fn build_request(app_type: &AppType, entry: &ReplayEntry) -> (String, Value) {
+    let prompt = filler_prompt(entry.input_tokens);
+    let max_tokens = entry.output_tokens.clamp(1, MAX_OUTPUT_TOKENS);
+    match app_type {
+        AppType::Claude => (
+            "/v1/messages".to_string(),
+            json!({
+                "model": entry.model,
+                "max_tokens": max_tokens,
+                "stream": entry.is_streaming,
+                "messages": [{ "role": "user", "content": prompt }],
+            }),
+        ),
+        AppType::Codex => (
+            "/v1/responses".to_string(),
+            json!({
+                "model": entry.model,
+                "max_output_tokens": max_tokens,
+                "stream": entry.is_streaming,
+                "input": prompt,
+            }),
+        ),
+        AppType::Gemini => {
+            let method = if entry.is_streaming {
+                "streamGenerateContent?alt=sse"
+            } else {
+                "generateContent"
+            };
+            (
+                format!("/v1beta/models/{}:{method}", entry.model),
+                json!({
+                    "contents": [{ "role": "user", "parts": [{ "text": prompt }] }],
+                    "generationConfig": { "maxOutputTokens": max_tokens },
+                }),
+            )
+        }
+    }
+}
+
+/// 相对第一条请求的发送时间
+fn schedule_offset(first: i64, created_at: i64, speed: f64) -> Duration {
+    Duration::from_secs_f64((created_at - first).max(0) as f64 / speed)
+}
+
+/// 发送一条回放请求，读完响应体后计时
+async fn send_one(
+    client: reqwest::Client,
+    url: String,
+    body: Value,
+    entry: ReplayEntry,
+) -> ReplayResult {
+    let started = Instant::now();
+    let outcome = async {
+        let response = client
+            .post(&url)
+            .header(CLIENT_HEADER, CLIENT_NAME)
+            .header(REPLAY_HEADER, REPLAY_TOKEN.as_str())
+            .json(&body)
+            .send()
+            .await?;
+        let status = response.status().as_u16();
+        response.bytes().await?;
+        Ok::<_, reqwest::Error>(status)
+    }
+    .await;
+
+    let (status, error) = match outcome {
+        Ok(status) => (Some(status), None),
+        Err(e) => (e.status().map(|s| s.as_u16()), Some(e.to_string())),
+    };
+    ReplayResult {
+        original_at: entry.created_at,
+        model: entry.model,
+        original_status: entry.status_code,
+        original_latency_ms: entry.latency_ms,
+        status,
+        latency_ms: started.elapsed().as_millis() as u64,
+        error,
+    }
+}
+
+pub struct ReplayService;
+
+impl ReplayService {
+    /// 校验参数并读取要回放的请求
+    pub fn prepare(db: &Database, request: &ReplayRequest) -> Result<Vec<ReplayEntry>, AppError> {
+        if request.end < request.start {
+            return Err(AppError::InvalidInput("结束时间早于开始时间".to_string()));
+        }
+        if !(request.speed > 0.0 && request.speed <= MAX_SPEED) {
+            return Err(AppError::InvalidInput(format!(
+                "倍速需在 0 ~ {MAX_SPEED} 之间"
+            )));
+        }
+        if let ReplayTarget::Provider { provider_id } = &request.target {
+            if db
+                .get_provider_by_id(provider_id, request.app_type.as_str())?
+                .is_none()
+            {
+                return Err(AppError::InvalidInput(format!(
+                    "供应商不存在: {provider_id}"
+                )));
+            }
+        }
+        if request.target == ReplayTarget::Mock
+            && !crate::settings::get_settings().mock_upstream.enabled
+        {
+            return Err(AppError::localized(
+                "replay.mock_disabled",
+                "内置模拟上游未开启",
+                "The built-in mock upstream is disabled",
+            ));
+        }
+
+        let entries = db.get_replay_entries(
+            request.app_type.as_str(),
+            request.start,
+            request.end,
+            MAX_REQUESTS,
+        )?;
+        if entries.is_empty() {
+            return Err(AppError::localized(
+                "replay.empty",
+                "所选时间范围内没有请求日志",
+                "No request logs in the selected time range",
+            ));
+        }
+        Ok(entries)
+    }
+
+    /// 执行回放，每完成一条推送一次结果
+    pub async fn run(
+        proxy_service: &ProxyService,
+        request: ReplayRequest,
+        entries: Vec<ReplayEntry>,
+        task: Arc<TaskHandle>,
+    ) -> Result<(), AppError> {
+        if !proxy_service.is_running().await {
+            return Err(AppError::localized(
+                "replay.proxy_stopped",
+                "请先启动代理",
+                "Start the proxy first",
+            ));
+        }
+        let config = proxy_service
+            .get_config()
+            .await
+            .map_err(AppError::Message)?;
+        let host = match config.listen_address.as_str() {
+            "0.0.0.0" | "::" => "127.0.0.1",
+            other => other,
+        };
+        let root = match request.target {
+            ReplayTarget::Mock => format!("http://{host}:{}/mock", config.listen_port),
+            _ => format!("http://{host}:{}", config.listen_port),
+        };
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .no_proxy()
+            .build()
+            .map_err(|e| AppError::Message(format!("创建 HTTP 客户端失败: {e}")))?;
+
+        let first = entries[0].created_at;
+        let span = entries[entries.len() - 1].created_at - first;
+        // 固定到指定供应商：覆盖持续到回放结束后再留出余量，结束时主动移除
+        let pinned = match &request.target {
+            ReplayTarget::Provider { provider_id } => {
+                let minutes = (schedule_offset(first, first + span, request.speed).as_secs() / 60
+                    + 10) as u32;
+                let entry = session_override::set(
+                    request.app_type.as_str(),
+                    OverrideTarget::Client(CLIENT_NAME.to_string()),
+                    provider_id,
+                    Some(minutes),
+                    Some(entries.len() as u32),
+                    chrono::Utc::now().timestamp(),
+                )
+                .map_err(AppError::Message)?;
+                Some(entry.id)
+            }
+            _ => None,
+        };
+
+        log::info!(
+            "[Replay] 回放 {} 条 {} 请求（{} 秒，{}x）-> {:?}",
+            entries.len(),
+            request.app_type.as_str(),
+            span,
+            request.speed,
+            request.target
+        );
+        task.set_total(entries.len() as u32);
+
+        let started = Instant::now();
+        let mut in_flight = JoinSet::new();
+        let mut result = Ok(());
+        for entry in entries {
+            let due = schedule_offset(first, entry.created_at, request.speed);
+            // 等待发送时间的同时收集已完成的结果
+            loop {
+                let wait = due.saturating_sub(started.elapsed());
+                if wait.is_zero() {
+                    break;
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = task.token().cancelled() => break,
+                    Some(Ok(done)) = in_flight.join_next(), if !in_flight.is_empty() => {
+                        if let Err(e) = task.push_result(&done) {
+                            result = Err(e);
+                        }
+                    }
+                }
+            }
+            if task.is_cancelled() {
+                break;
+            }
+
+            let (path, body) = build_request(&request.app_type, &entry);
+            in_flight.spawn(send_one(
+                client.clone(),
+                format!("{root}{path}"),
+                body,
+                entry,
+            ));
+        }
+
+        if task.is_cancelled() {
+            in_flight.abort_all();
+        }
+        while let Some(done) = in_flight.join_next().await {
+            if let Ok(done) = done {
+                if let Err(e) = task.push_result(&done) {
+                    result = Err(e);
+                }
+            }
+        }
+
+        if let Some(id) = pinned {
+            session_override::clear(&id);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_run_token_marks_replays() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CLIENT_HEADER, CLIENT_NAME.parse().unwrap());
+        assert!(!is_replay(&headers));
+        headers.insert(REPLAY_HEADER, "forged".parse().unwrap());
+        assert!(!is_replay(&headers));
+        headers.insert(REPLAY_HEADER, REPLAY_TOKEN.parse().unwrap());
+        assert!(is_replay(&headers));
+    }
+
+    fn entry(created_at: i64, is_streaming: bool) -> ReplayEntry {
+        ReplayEntry {
+            created_at,
+            model: "m1".to_string(),
+            is_streaming,
+            input_tokens: 50_000,
+            output_tokens: 0,
+            status_code: 200,
+            latency_ms: 900,
+        }
+    }
+
+    #[test]
+    fn builds_requests_per_app_type() {
+        let (path, body) = build_request(&AppType::Claude, &entry(0, true));
+        assert_eq!(path, "/v1/messages");
+        assert_eq!(body["max_tokens"], 1);
+        assert_eq!(body["stream"], true);
+        let prompt = body["messages"][0]["content"].as_str().unwrap();
+        assert_eq!(prompt.split(' ').count(), MAX_PROMPT_TOKENS as usize);
+
+        let (path, _) = build_request(&AppType::Gemini, &entry(0, true));
+        assert_eq!(path, "/v1beta/models/m1:streamGenerateContent?alt=sse");
+        let (path, body) = build_request(&AppType::Codex, &entry(0, false));
+        assert_eq!(path, "/v1/responses");
+        assert_eq!(body["stream"], false);
+    }
+
+    #[test]
+    fn schedule_compresses_by_speed() {
+        assert_eq!(schedule_offset(100, 160, 1.0), Duration::from_secs(60));
+        assert_eq!(schedule_offset(100, 160, 4.0), Duration::from_secs(15));
+        assert_eq!(schedule_offset(100, 90, 1.0), Duration::ZERO);
+    }
+
+    #[test]
+    fn reads_entries_in_range() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        {
+            let conn = lock_conn!(db.conn);
+            for (i, (app_type, created_at, is_replay)) in [
+                ("claude", 30, false),
+                ("claude", 10, false),
+                ("codex", 20, false),
+                ("claude", 99, false),
+                ("claude", 40, true),
+            ]
+            .iter()
+            .enumerate()
+            {
+                conn.execute(
+                    "INSERT INTO proxy_request_logs (
+                        request_id, provider_id, app_type, model, latency_ms,
+                        status_code, is_streaming, created_at, is_replay
+                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                    params![
+                        format!("r{i}"),
+                        "p1",
+                        app_type,
+                        "m1",
+                        800,
+                        200,
+                        1,
+                        created_at,
+                        is_replay
+                    ],
+                )?;
+            }
+        }
+
+        let entries = db.get_replay_entries("claude", 0, 50, 10)?;
+        let times: Vec<_> = entries.iter().map(|e| e.created_at).collect();
+        assert_eq!(times, [10, 30]);
+        assert!(entries[0].is_streaming);
+        Ok(())
+    }
+}
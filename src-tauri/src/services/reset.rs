@@ -0,0 +1,402 @@
+//! 重置应用（交接设备前清除全部数据与密钥）
+//!
+//! 依次执行：
+//! 1. 停止代理并恢复被接管的 Live 配置；
+//! 2. 各应用的 Live 配置恢复为首次启动时导入的 `default` 快照（即使用 cc-switch 之前的配置），
+//!    没有快照时从 Live 配置中移除当前供应商写入的 Key；
+//! 3. 删除系统钥匙串中的附件主密钥与数据库密钥；
+//! 4. 关闭数据库后，按名称覆写删除 cc-switch 自己创建的文件：数据目录与 `~/.cc-switch` 中的
+//!    数据库（含 -wal/-shm）、`backups/`、`db-encryption.json`、`vault.key`、settings.json 等，
+//!    以及状态导出文件与日志目录。
+//!
+//! 数据目录可能是用户选择的已有目录（同步盘等），其中的其他文件不会被删除；
+//! 删除过程不跟随符号链接，避免误删目录树以外的内容。
+//!
+//! 完成后应用应立即退出，不再执行常规的退出清理（否则会重新写出设置与数据库）。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::app_config::AppType;
+use crate::codex_config::get_codex_auth_path;
+use crate::config::{get_claude_settings_path, read_json_file, shred_file, write_json_file};
+use crate::database::encryption;
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::services::provider::write_live_snapshot;
+use crate::services::vault;
+use crate::store::AppState;
+
+/// 调用方必须原样传入的确认词，避免误触
+pub const CONFIRM_PHRASE: &str = "RESET";
+
+/// 首次启动时从 Live 配置导入的供应商 ID
+const SNAPSHOT_PROVIDER_ID: &str = "default";
+
+/// 重置结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetReport {
+    /// 已恢复为 cc-switch 之前快照的应用
+    pub live_restored: Vec<String>,
+    /// 无快照、仅移除了 Key 的应用
+    pub live_stripped: Vec<String>,
+    /// 已删除的文件与目录
+    pub removed_paths: Vec<String>,
+    /// 未能完成的步骤（不中断重置）
+    pub warnings: Vec<String>,
+}
+
+/// 从 `live` 中移除与 `written` 同名且同值的条目，返回是否有改动
+fn remove_written(live: &mut serde_json::Map<String, Value>, written: &Value) -> bool {
+    let Some(written) = written.as_object() else {
+        return false;
+    };
+    let before = live.len();
+    live.retain(|key, value| written.get(key) != Some(value));
+    live.len() != before
+}
+
+/// 从 Live 配置中移除当前供应商写入的 Key
+fn strip_live_keys(app_type: &AppType, provider: &Provider) -> Result<bool, AppError> {
+    let settings = &provider.settings_config;
+    match app_type {
+        AppType::Claude => {
+            let path = get_claude_settings_path();
+            if !path.exists() {
+                return Ok(false);
+            }
+            let mut live: Value = read_json_file(&path)?;
+            let Some(env) = live.get_mut("env").and_then(Value::as_object_mut) else {
+                return Ok(false);
+            };
+            let changed = remove_written(env, settings.get("env").unwrap_or(&Value::Null));
+            if env.is_empty() {
+                if let Some(obj) = live.as_object_mut() {
+                    obj.remove("env");
+                }
+            }
+            if changed {
+                write_json_file(&path, &live)?;
+            }
+            Ok(changed)
+        }
+        AppType::Codex => {
+            let path = get_codex_auth_path();
+            if !path.exists() {
+                return Ok(false);
+            }
+            let mut live: Value = read_json_file(&path)?;
+            let Some(auth) = live.as_object_mut() else {
+                return Ok(false);
+            };
+            let changed = remove_written(auth, settings.get("auth").unwrap_or(&Value::Null));
+            if auth.is_empty() {
+                shred_file(&path);
+            } else if changed {
+                write_json_file(&path, &live)?;
+            }
+            Ok(changed)
+        }
+        AppType::Gemini => {
+            use crate::gemini_config::{read_gemini_env, write_gemini_env_atomic};
+
+            let Some(written) = settings.get("env").and_then(Value::as_object) else {
+                return Ok(false);
+            };
+            let mut env = read_gemini_env()?;
+            let before = env.len();
+            env.retain(|key, value| written.get(key).and_then(Value::as_str) != Some(value));
+            if env.len() == before {
+                return Ok(false);
+            }
+            write_gemini_env_atomic(&env)?;
+            Ok(true)
+        }
+    }
+}
+
+/// 恢复单个应用的 Live 配置，返回 Some(true) 表示从快照恢复，Some(false) 表示仅移除 Key
+fn reset_live(state: &AppState, app_type: &AppType) -> Result<Option<bool>, AppError> {
+    let providers = state.db.get_all_providers(app_type.as_str())?;
+    if let Some(snapshot) = providers.get(SNAPSHOT_PROVIDER_ID) {
+        write_live_snapshot(app_type, snapshot)?;
+        return Ok(Some(true));
+    }
+
+    let current = crate::settings::get_effective_current_provider(&state.db, app_type)?;
+    match current.and_then(|id| providers.get(&id)) {
+        Some(provider) => Ok(strip_live_keys(app_type, provider)?.then_some(false)),
+        None => Ok(None),
+    }
+}
+
+/// cc-switch 在数据目录（及 `~/.cc-switch`）中创建的文件
+const OWNED_FILES: &[&str] = &[
+    "cc-switch.db",
+    "cc-switch.db-wal",
+    "cc-switch.db-shm",
+    "cc-switch.db-journal",
+    "cc-switch.db.converting",
+    "db-encryption.json",
+    "vault.key",
+    "status.json",
+    "settings.json",
+    "config.json",
+    "config.json.bak",
+    "skills.json",
+    "proxy.sock",
+];
+
+/// cc-switch 在数据目录中创建、内容全部归其所有的子目录
+const OWNED_DIRS: &[&str] = &["backups", "access-logs"];
+
+/// 覆写目录下全部文件后删除目录；符号链接只删除链接本身
+fn shred_dir(dir: &Path) -> Result<(), AppError> {
+    for entry in fs::read_dir(dir).map_err(|e| AppError::io(dir, e))? {
+        let entry = entry.map_err(|e| AppError::io(dir, e))?;
+        let path = entry.path();
+        let file_type = entry.file_type().map_err(|e| AppError::io(&path, e))?;
+        if file_type.is_symlink() {
+            fs::remove_file(&path).map_err(|e| AppError::io(&path, e))?;
+        } else if file_type.is_dir() {
+            shred_dir(&path)?;
+        } else {
+            shred_file(&path);
+        }
+    }
+    fs::remove_dir(dir).map_err(|e| AppError::io(dir, e))
+}
+
+fn remove_path(path: &Path, report: &mut ResetReport) {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return;
+    };
+    let result = if meta.file_type().is_symlink() {
+        fs::remove_file(path).map_err(|e| AppError::io(path, e))
+    } else if meta.is_dir() {
+        shred_dir(path)
+    } else {
+        shred_file(path);
+        Ok(())
+    };
+    match result {
+        Ok(()) => report.removed_paths.push(path.display().to_string()),
+        Err(e) => report
+            .warnings
+            .push(format!("删除 {} 失败: {e}", path.display())),
+    }
+}
+
+/// 仅删除普通文件（用户指定的路径不会被当作目录递归删除）
+fn remove_file_only(path: &Path, report: &mut ResetReport) {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_file() => remove_path(path, report),
+        Ok(_) => report
+            .warnings
+            .push(format!("{} 不是普通文件，已跳过", path.display())),
+        Err(_) => {}
+    }
+}
+
+/// 删除 `dir` 中 cc-switch 创建的文件与子目录，目录为空时一并删除
+fn remove_owned(dir: &Path, report: &mut ResetReport) {
+    for name in OWNED_FILES {
+        remove_file_only(&dir.join(name), report);
+    }
+    for name in OWNED_DIRS {
+        remove_path(&dir.join(name), report);
+    }
+    if fs::remove_dir(dir).is_ok() {
+        report.removed_paths.push(dir.display().to_string());
+    }
+}
+
+pub struct ResetService;
+
+impl ResetService {
+    /// 执行重置；`extra_paths` 为调用方额外要求删除的路径（如日志目录）
+    pub async fn reset(
+        state: &AppState,
+        confirm: &str,
+        extra_paths: Vec<PathBuf>,
+    ) -> Result<ResetReport, AppError> {
+        if confirm != CONFIRM_PHRASE {
+            return Err(AppError::localized(
+                "reset.confirm_mismatch",
+                format!("请输入 {CONFIRM_PHRASE} 确认重置"),
+                format!("Type {CONFIRM_PHRASE} to confirm the reset"),
+            ));
+        }
+
+        let mut report = ResetReport::default();
+        crate::services::local_model::LocalModelService::stop_all();
+
+        // 接管中的 Live 配置指向本地代理，先按接管前的备份恢复
+        if let Err(e) = state.proxy_service.stop_with_restore().await {
+            report.warnings.push(format!("恢复代理接管的配置失败: {e}"));
+        }
+
+        for app_type in [AppType::Claude, AppType::Codex, AppType::Gemini] {
+            match reset_live(state, &app_type) {
+                Ok(Some(true)) => report.live_restored.push(app_type.as_str().to_string()),
+                Ok(Some(false)) => report.live_stripped.push(app_type.as_str().to_string()),
+                Ok(None) => {}
+                Err(e) => report
+                    .warnings
+                    .push(format!("重置 {} 配置失败: {e}", app_type.as_str())),
+            }
+        }
+
+        for account in [vault::KEYCHAIN_ACCOUNT, encryption::KEYCHAIN_ACCOUNT] {
+            vault::keychain_delete(account);
+        }
+        // 云厂商供应商仅支持 Claude
+        if let Ok(providers) = state.db.get_all_providers(AppType::Claude.as_str()) {
+            providers.values().for_each(vault::delete_cloud_credentials);
+        }
+
+        let settings = crate::settings::get_settings();
+        let status_path = crate::services::status_export::export_path(&settings);
+        let settings_path = crate::settings::settings_file_path();
+
+        // 关闭数据库文件，之后的数据库读写只落在内存中
+        state.db.release_file()?;
+
+        remove_file_only(&status_path, &mut report);
+        remove_file_only(&settings_path, &mut report);
+
+        let mut data_dirs = vec![crate::config::get_app_config_dir()];
+        // 环境变量备份固定写在 ~/.cc-switch/backups，与自定义数据目录无关
+        if let Some(home) = dirs::home_dir() {
+            data_dirs.push(home.join(".cc-switch"));
+        }
+        data_dirs.dedup();
+        for dir in &data_dirs {
+            remove_owned(dir, &mut report);
+        }
+        // 调用方传入的路径（日志目录）由应用独占
+        for path in extra_paths {
+            remove_path(&path, &mut report);
+        }
+
+        log::warn!(
+            "应用已重置：恢复 {:?}，移除 Key {:?}，删除 {} 项",
+            report.live_restored,
+            report.live_stripped,
+            report.removed_paths.len()
+        );
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn removes_only_values_written_by_provider() {
+        let mut live = json!({
+            "ANTHROPIC_AUTH_TOKEN": "sk-provider",
+            "ANTHROPIC_BASE_URL": "https://user-edited.example",
+            "DISABLE_TELEMETRY": "1"
+        });
+        let written = json!({
+            "ANTHROPIC_AUTH_TOKEN": "sk-provider",
+            "ANTHROPIC_BASE_URL": "https://relay.example"
+        });
+
+        let map = live.as_object_mut().unwrap();
+        assert!(remove_written(map, &written));
+        assert_eq!(
+            live,
+            json!({
+                "ANTHROPIC_BASE_URL": "https://user-edited.example",
+                "DISABLE_TELEMETRY": "1"
+            })
+        );
+        assert!(!remove_written(live.as_object_mut().unwrap(), &Value::Null));
+    }
+
+    #[test]
+    fn shred_dir_removes_nested_files() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("logs");
+        fs::create_dir_all(dir.join("old")).unwrap();
+        fs::write(dir.join("cc-switch.log"), b"log").unwrap();
+        fs::write(dir.join("old/a.log"), b"log").unwrap();
+
+        let mut report = ResetReport::default();
+        remove_path(&dir, &mut report);
+        assert!(!dir.exists());
+        assert_eq!(report.removed_paths.len(), 1);
+        assert!(report.warnings.is_empty());
+
+        remove_path(&dir, &mut report);
+        assert_eq!(report.removed_paths.len(), 1);
+    }
+
+    #[test]
+    fn remove_owned_keeps_user_files() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("Dropbox");
+        fs::create_dir_all(dir.join("backups")).unwrap();
+        fs::create_dir_all(dir.join("photos")).unwrap();
+        fs::write(dir.join("cc-switch.db"), b"db").unwrap();
+        fs::write(dir.join("cc-switch.db-wal"), b"wal").unwrap();
+        fs::write(dir.join("vault.key"), b"secret").unwrap();
+        fs::write(dir.join("backups/a.db"), b"db").unwrap();
+        fs::write(dir.join("notes.txt"), b"mine").unwrap();
+
+        let mut report = ResetReport::default();
+        remove_owned(&dir, &mut report);
+        assert!(!dir.join("cc-switch.db").exists());
+        assert!(!dir.join("cc-switch.db-wal").exists());
+        assert!(!dir.join("vault.key").exists());
+        assert!(!dir.join("backups").exists());
+        assert!(dir.join("notes.txt").exists());
+        assert!(dir.join("photos").is_dir());
+
+        fs::remove_file(dir.join("notes.txt")).unwrap();
+        fs::remove_dir(dir.join("photos")).unwrap();
+        remove_owned(&dir, &mut report);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn remove_file_only_skips_directories() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("status");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("keep.txt"), b"mine").unwrap();
+
+        let mut report = ResetReport::default();
+        remove_file_only(&dir, &mut report);
+        assert!(dir.join("keep.txt").exists());
+        assert!(report.removed_paths.is_empty());
+        assert_eq!(report.warnings.len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn shred_dir_does_not_follow_symlinks() {
+        let root = tempfile::tempdir().unwrap();
+        let outside = root.path().join("outside");
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("keep.txt"), b"mine").unwrap();
+
+        let dir = root.path().join("backups");
+        fs::create_dir_all(&dir).unwrap();
+        std::os::unix::fs::symlink(&outside, dir.join("link")).unwrap();
+        std::os::unix::fs::symlink(outside.join("keep.txt"), dir.join("file-link")).unwrap();
+
+        let mut report = ResetReport::default();
+        remove_path(&dir, &mut report);
+        assert!(!dir.exists());
+        assert_eq!(fs::read(outside.join("keep.txt")).unwrap(), b"mine");
+    }
+}
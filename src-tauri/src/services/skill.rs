@@ -178,7 +178,7 @@ impl SkillService {
 
         let results: Vec<Result<Vec<Skill>>> = futures::future::join_all(fetch_tasks).await;
 
-        for (repo, result) in enabled_repos.into_iter().zip(results.into_iter()) {
+        for (repo, result) in enabled_repos.into_iter().zip(results) {
             match result {
                 Ok(repo_skills) => skills.extend(repo_skills),
                 Err(e) => log::warn!("获取仓库 {}/{} 技能失败: {}", repo.owner, repo.name, e),
@@ -190,7 +190,7 @@ impl SkillService {
 
         // 去重并排序
         Self::deduplicate_skills(&mut skills);
-        skills.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+        skills.sort_by_key(|s| s.name.to_lowercase());
 
         Ok(skills)
     }
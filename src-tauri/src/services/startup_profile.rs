@@ -0,0 +1,213 @@
+//! 子系统开关
+//!
+//! 只用 cc-switch 切换配置的用户可以单独关闭代理、定时健康检查、同步与告警：
+//! - 设置中的 `subsystems` 持久保存开关
+//! - 启动参数 `--no-proxy`、`--no-health-scheduler`、`--no-sync`、`--no-alerting`
+//!   （或 `--minimal` 全部关闭）只对本次运行生效
+//!
+//! 各后台循环每轮读取 [`is_enabled`]，运行中修改开关无需重启即可生效。
+
+use std::collections::HashSet;
+use std::sync::RwLock;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use crate::error::AppError;
+
+/// 本次运行被启动参数关闭的子系统（运行中重新开启后移除）
+static DISABLED_BY_FLAG: Lazy<RwLock<HashSet<Subsystem>>> =
+    Lazy::new(|| RwLock::new(HashSet::new()));
+
+/// 可单独关闭的子系统
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Subsystem {
+    /// 本地代理（关闭时不自动恢复、也不能启动）
+    Proxy,
+    /// 自动健康检查（切换后的快照检查）
+    HealthScheduler,
+    /// 后台同步（配置快照、Codex auth.json 修复）
+    Sync,
+    /// 告警（webhook 告警、用量预测告警）
+    Alerting,
+}
+
+impl Subsystem {
+    pub const ALL: [Self; 4] = [
+        Self::Proxy,
+        Self::HealthScheduler,
+        Self::Sync,
+        Self::Alerting,
+    ];
+
+    /// 关闭该子系统的启动参数
+    pub fn flag(&self) -> &'static str {
+        match self {
+            Self::Proxy => "--no-proxy",
+            Self::HealthScheduler => "--no-health-scheduler",
+            Self::Sync => "--no-sync",
+            Self::Alerting => "--no-alerting",
+        }
+    }
+}
+
+/// 子系统开关设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubsystemConfig {
+    #[serde(default = "default_true")]
+    pub proxy: bool,
+    #[serde(default = "default_true")]
+    pub health_scheduler: bool,
+    #[serde(default = "default_true")]
+    pub sync: bool,
+    #[serde(default = "default_true")]
+    pub alerting: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for SubsystemConfig {
+    fn default() -> Self {
+        Self {
+            proxy: true,
+            health_scheduler: true,
+            sync: true,
+            alerting: true,
+        }
+    }
+}
+
+impl SubsystemConfig {
+    pub fn get(&self, subsystem: Subsystem) -> bool {
+        match subsystem {
+            Subsystem::Proxy => self.proxy,
+            Subsystem::HealthScheduler => self.health_scheduler,
+            Subsystem::Sync => self.sync,
+            Subsystem::Alerting => self.alerting,
+        }
+    }
+
+    pub fn set(&mut self, subsystem: Subsystem, enabled: bool) {
+        match subsystem {
+            Subsystem::Proxy => self.proxy = enabled,
+            Subsystem::HealthScheduler => self.health_scheduler = enabled,
+            Subsystem::Sync => self.sync = enabled,
+            Subsystem::Alerting => self.alerting = enabled,
+        }
+    }
+}
+
+/// 子系统当前状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubsystemStatus {
+    pub subsystem: Subsystem,
+    /// 实际是否启用
+    pub enabled: bool,
+    /// 设置中的开关
+    pub configured: bool,
+    /// 是否被本次的启动参数关闭
+    pub disabled_by_flag: bool,
+}
+
+/// 解析启动参数中关闭的子系统
+fn parse_flags(args: &[String]) -> HashSet<Subsystem> {
+    if args.iter().any(|arg| arg == "--minimal") {
+        return Subsystem::ALL.into_iter().collect();
+    }
+    Subsystem::ALL
+        .into_iter()
+        .filter(|s| args.iter().any(|arg| arg == s.flag()))
+        .collect()
+}
+
+/// 读取启动参数（应用启动时调用一次）
+pub fn init_from_args(args: &[String]) {
+    let disabled = parse_flags(args);
+    if !disabled.is_empty() {
+        log::info!("启动参数关闭了子系统: {disabled:?}");
+    }
+    *DISABLED_BY_FLAG.write().unwrap_or_else(|e| e.into_inner()) = disabled;
+}
+
+fn disabled_by_flag(subsystem: Subsystem) -> bool {
+    DISABLED_BY_FLAG
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains(&subsystem)
+}
+
+/// 子系统是否启用（设置开启且未被启动参数关闭）
+pub fn is_enabled(subsystem: Subsystem) -> bool {
+    !disabled_by_flag(subsystem) && crate::settings::get_settings().subsystems.get(subsystem)
+}
+
+pub fn status() -> Vec<SubsystemStatus> {
+    let config = crate::settings::get_settings().subsystems;
+    Subsystem::ALL
+        .into_iter()
+        .map(|subsystem| {
+            let configured = config.get(subsystem);
+            let disabled_by_flag = disabled_by_flag(subsystem);
+            SubsystemStatus {
+                subsystem,
+                enabled: configured && !disabled_by_flag,
+                configured,
+                disabled_by_flag,
+            }
+        })
+        .collect()
+}
+
+/// 运行中修改开关：保存到设置；开启时同时解除本次启动参数的关闭
+pub fn set_enabled(subsystem: Subsystem, enabled: bool) -> Result<(), AppError> {
+    let mut settings = crate::settings::get_settings();
+    settings.subsystems.set(subsystem, enabled);
+    crate::settings::update_settings(settings)?;
+    if enabled {
+        DISABLED_BY_FLAG
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&subsystem);
+    }
+    log::info!(
+        "子系统 {subsystem:?} 已{}",
+        if enabled { "开启" } else { "关闭" }
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_individual_and_minimal_flags() {
+        let disabled = parse_flags(&args(&["cc-switch", "--no-proxy", "--no-alerting"]));
+        assert_eq!(
+            disabled,
+            [Subsystem::Proxy, Subsystem::Alerting]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(parse_flags(&args(&["cc-switch", "--minimal"])).len(), 4);
+        assert!(parse_flags(&args(&["cc-switch"])).is_empty());
+    }
+
+    #[test]
+    fn config_defaults_to_enabled() {
+        let mut config: SubsystemConfig = serde_json::from_str(r#"{"sync":false}"#).unwrap();
+        assert!(config.proxy && config.health_scheduler && config.alerting);
+        assert!(!config.get(Subsystem::Sync));
+        config.set(Subsystem::Sync, true);
+        assert_eq!(config, SubsystemConfig::default());
+    }
+}
@@ -0,0 +1,164 @@
+//! 状态文件导出
+//!
+//! 定期将当前供应商、健康状态、TPS 和最近错误写入 status.json，
+//! 供状态栏（SketchyBar / waybar）、tmux 插件等外部脚本直接读取，无需与应用通信。
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app_config::AppType;
+use crate::database::Database;
+use crate::services::stream_check::HealthStatus;
+use crate::services::traffic_health::blended_health;
+use crate::services::ProxyService;
+use crate::settings::AppSettings;
+
+/// 单个应用的状态
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppStatusEntry {
+    pub app_type: String,
+    pub provider_id: Option<String>,
+    pub provider_name: Option<String>,
+    /// 健康状态（最近一次检查结果混合真实流量，见 [`traffic_health`](super::traffic_health)）
+    pub health: Option<HealthStatus>,
+    pub health_checked_at: Option<i64>,
+    pub response_time_ms: Option<u64>,
+    /// 最近一次健康检查结果（未检查过为 None）
+    #[serde(default)]
+    pub probe_health: Option<HealthStatus>,
+    /// 计入健康状态的真实请求失败率（真实请求不足时为 None）
+    #[serde(default)]
+    pub traffic_failure_rate: Option<f64>,
+}
+
+/// status.json 内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusSnapshot {
+    pub generated_at: i64,
+    pub proxy_running: bool,
+    pub tps: f64,
+    pub total_requests: u64,
+    pub success_rate: f32,
+    pub last_request_at: Option<String>,
+    pub last_error: Option<String>,
+    pub apps: Vec<AppStatusEntry>,
+}
+
+/// 导出路径（未配置时使用应用配置目录下的 status.json）
+pub fn export_path(settings: &AppSettings) -> PathBuf {
+    settings
+        .status_export_path
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| crate::config::get_app_config_dir().join("status.json"))
+}
+
+/// 各应用的当前供应商与最近一次健康检查结果
+pub fn app_status_entries(db: &Database) -> Vec<AppStatusEntry> {
+    let check_config = db.get_stream_check_config().unwrap_or_default();
+    let mut apps = Vec::new();
+    for app_type in [AppType::Claude, AppType::Codex, AppType::Gemini] {
+        let app = app_type.as_str();
+        let provider_id = db.get_current_provider(app).ok().flatten();
+        let provider = provider_id
+            .as_deref()
+            .and_then(|id| db.get_provider_by_id(id, app).ok().flatten());
+        let latest = provider_id
+            .as_deref()
+            .and_then(|id| db.get_stream_check_latest(id, app, None).ok().flatten());
+        let blended = provider_id
+            .as_deref()
+            .and_then(|id| blended_health(db, id, app, &check_config).ok());
+
+        apps.push(AppStatusEntry {
+            app_type: app.to_string(),
+            provider_name: provider.map(|p| p.name),
+            provider_id,
+            health: match &blended {
+                Some(blended) => blended.status.clone(),
+                None => latest.as_ref().map(|r| r.status.clone()),
+            },
+            health_checked_at: latest.as_ref().map(|r| r.tested_at),
+            probe_health: latest.as_ref().map(|r| r.status.clone()),
+            response_time_ms: latest.and_then(|r| r.response_time_ms),
+            traffic_failure_rate: blended
+                .filter(|b| b.traffic_counted)
+                .map(|b| b.traffic.failure_rate()),
+        });
+    }
+    apps
+}
+
+/// 汇总当前状态
+pub async fn build_snapshot(db: &Database, proxy_service: &ProxyService) -> StatusSnapshot {
+    let proxy_running = proxy_service.is_running().await;
+    let status = if proxy_running {
+        proxy_service.get_status().await.ok()
+    } else {
+        None
+    };
+
+    StatusSnapshot {
+        generated_at: chrono::Utc::now().timestamp(),
+        proxy_running,
+        tps: status.as_ref().map(|s| s.tps).unwrap_or(0.0),
+        total_requests: status.as_ref().map(|s| s.total_requests).unwrap_or(0),
+        success_rate: status.as_ref().map(|s| s.success_rate).unwrap_or(0.0),
+        last_request_at: status.as_ref().and_then(|s| s.last_request_at.clone()),
+        last_error: status.and_then(|s| s.last_error),
+        apps: app_status_entries(db),
+    }
+}
+
+/// 后台导出循环：每轮重新读取设置，未开启时只等待
+pub async fn run(db: std::sync::Arc<Database>, proxy_service: ProxyService) {
+    loop {
+        let settings = crate::settings::get_settings();
+        let interval = Duration::from_secs(settings.status_export_interval_secs.max(1) as u64);
+
+        if settings.status_export_enabled {
+            let snapshot = build_snapshot(&db, &proxy_service).await;
+            let path = export_path(&settings);
+            let result = serde_json::to_vec_pretty(&snapshot)
+                .map_err(|e| crate::error::AppError::JsonSerialize { source: e })
+                .and_then(|bytes| {
+                    if let Some(parent) = path.parent() {
+                        std::fs::create_dir_all(parent)
+                            .map_err(|e| crate::error::AppError::io(parent, e))?;
+                    }
+                    crate::config::atomic_write(&path, &bytes)
+                });
+            if let Err(e) = result {
+                log::warn!("导出 status.json 失败 ({}): {e}", path.display());
+            }
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_path_prefers_configured_value() {
+        let mut settings = AppSettings {
+            status_export_path: Some("  ".to_string()),
+            ..AppSettings::default()
+        };
+        assert!(export_path(&settings).ends_with("status.json"));
+
+        settings.status_export_path = Some("/tmp/cc-switch-status.json".to_string());
+        assert_eq!(
+            export_path(&settings),
+            PathBuf::from("/tmp/cc-switch-status.json")
+        );
+    }
+}
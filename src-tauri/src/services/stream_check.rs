@@ -1,19 +1,44 @@
 //! 流式健康检查服务
 //!
 //! 使用流式 API 进行快速健康检查，只需接收首个 chunk 即判定成功。
+//! 判定后在限定时间内读完剩余内容，以便按响应中的实际用量计入探测预算。
 
 use futures::StreamExt;
 use regex::Regex;
-use reqwest::Client;
+use reqwest::{Client, Response};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{json, Value};
 use std::time::{Duration, Instant};
 
 use crate::app_config::AppType;
 use crate::error::AppError;
+use crate::i18n::{self, Locale};
 use crate::provider::Provider;
+use crate::proxy::auth_scheme::apply_auth_scheme;
+use crate::proxy::client_pool;
 use crate::proxy::custom_headers::apply_custom_headers_to_request;
-use crate::proxy::providers::{get_adapter, AuthInfo};
+use crate::proxy::endpoint_template;
+use crate::proxy::offline;
+use crate::proxy::provider_tls;
+use crate::proxy::providers::{
+    adapt_cloud_response, adapt_grpc_response, get_adapter, prepare_azure_request,
+    prepare_cloud_request, prepare_grpc_request, AuthInfo, AzureOpenAiConfig, CloudProviderConfig,
+    GrpcUpstreamConfig, LocalModelConfig,
+};
+use crate::proxy::shadow::sse_events;
+use crate::proxy::upstream_hint::{self, UpstreamErrorCode};
+use crate::proxy::usage::parser::TokenUsage;
+use crate::services::cancellation::CancelToken;
+use crate::services::health_probe::{
+    self, HealthProbe, ProbeContext, ProbeOutcome, SseCompletionProbe,
+};
+use crate::services::local_model::LocalModelService;
+
+/// 首个 chunk 之后继续读取响应的最长时间（只为拿到末尾的用量）
+const USAGE_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 探测响应最多读取的字节数
+const MAX_PROBE_BODY_BYTES: usize = 64 * 1024;
 
 /// 健康状态枚举
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -22,6 +47,10 @@ pub enum HealthStatus {
     Operational,
     Degraded,
     Failed,
+    /// 用户取消，请求已中止
+    Cancelled,
+    /// 未执行检查（如离线时的远程供应商），不代表供应商状态
+    Skipped,
 }
 
 /// 流式检查配置
@@ -37,6 +66,40 @@ pub struct StreamCheckConfig {
     pub codex_model: String,
     /// Gemini 测试模型
     pub gemini_model: String,
+    /// 切换供应商时，最近一次检查早于该时长（分钟）则立即后台检查；0 表示关闭
+    #[serde(default = "default_snapshot_stale_minutes")]
+    pub snapshot_stale_minutes: u32,
+    /// 重试退避基数（毫秒），第 n 次重试前等待 base * 2^(n-1)；0 表示立即重试
+    #[serde(default = "default_retry_backoff_base_ms")]
+    pub retry_backoff_base_ms: u64,
+    /// 重试退避上限（毫秒）
+    #[serde(default = "default_retry_backoff_max_ms")]
+    pub retry_backoff_max_ms: u64,
+    /// 可重试的 HTTP 状态码（超时、连接中断始终重试）
+    #[serde(default = "default_retryable_statuses")]
+    pub retryable_statuses: Vec<u16>,
+    /// 每个供应商每月自动探测的 token 预算（0 表示不限制，可被 meta.probeTokenBudget 覆盖）
+    #[serde(default)]
+    pub probe_token_budget: u64,
+    /// 健康状态中混合真实流量的方式
+    #[serde(default)]
+    pub traffic_health: crate::services::traffic_health::TrafficHealthConfig,
+}
+
+fn default_snapshot_stale_minutes() -> u32 {
+    60
+}
+
+fn default_retry_backoff_base_ms() -> u64 {
+    500
+}
+
+fn default_retry_backoff_max_ms() -> u64 {
+    5000
+}
+
+fn default_retryable_statuses() -> Vec<u16> {
+    vec![408, 429, 500, 502, 503, 504]
 }
 
 impl Default for StreamCheckConfig {
@@ -48,6 +111,12 @@ impl Default for StreamCheckConfig {
             claude_model: "claude-haiku-4-5-20251001".to_string(),
             codex_model: "gpt-5.1-codex@low".to_string(),
             gemini_model: "gemini-3-pro-preview".to_string(),
+            snapshot_stale_minutes: default_snapshot_stale_minutes(),
+            retry_backoff_base_ms: default_retry_backoff_base_ms(),
+            retry_backoff_max_ms: default_retry_backoff_max_ms(),
+            retryable_statuses: default_retryable_statuses(),
+            probe_token_budget: 0,
+            traffic_health: Default::default(),
         }
     }
 }
@@ -64,6 +133,120 @@ pub struct StreamCheckResult {
     pub model_used: String,
     pub tested_at: i64,
     pub retry_count: u32,
+    /// 实际检查的端点（base_url）
+    #[serde(default)]
+    pub endpoint: String,
+    /// 每次尝试的结果（仅在发生重试时记录）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attempts: Vec<StreamCheckAttempt>,
+    /// 上游错误归类（额度用尽、Key 无效等），前端据此给出处理建议
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<UpstreamErrorCode>,
+    /// 响应中带有的实际 token 消耗（供应商未返回用量时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens_used: Option<u64>,
+}
+
+/// 单次检查尝试
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamCheckAttempt {
+    /// 第几次尝试（从 0 开始）
+    pub attempt: u32,
+    pub success: bool,
+    pub http_status: Option<u16>,
+    pub message: String,
+    pub response_time_ms: Option<u64>,
+    pub tested_at: i64,
+}
+
+impl From<(u32, &StreamCheckResult)> for StreamCheckAttempt {
+    fn from((attempt, r): (u32, &StreamCheckResult)) -> Self {
+        Self {
+            attempt,
+            success: r.success,
+            http_status: r.http_status,
+            message: r.message.clone(),
+            response_time_ms: r.response_time_ms,
+            tested_at: r.tested_at,
+        }
+    }
+}
+
+impl StreamCheckResult {
+    /// 构造一个失败结果
+    pub fn failed(message: impl Into<String>) -> Self {
+        Self {
+            status: HealthStatus::Failed,
+            success: false,
+            message: message.into(),
+            response_time_ms: None,
+            http_status: None,
+            model_used: String::new(),
+            tested_at: chrono::Utc::now().timestamp(),
+            retry_count: 0,
+            endpoint: String::new(),
+            attempts: Vec::new(),
+            error_code: None,
+            tokens_used: None,
+        }
+    }
+
+    /// 由检查错误构造失败结果，上游错误归类并附上处理建议
+    pub fn from_error(error: &AppError) -> Self {
+        match error {
+            AppError::UpstreamStatus { status, body } => Self {
+                error_code: upstream_hint::classify(*status, body),
+                ..Self::failed(upstream_hint::describe(*status, body, Locale::current()))
+            },
+            _ => Self::failed(error.localized_message(Locale::current())),
+        }
+    }
+
+    /// 构造一个取消结果
+    pub fn cancelled(model_used: String) -> Self {
+        Self {
+            status: HealthStatus::Cancelled,
+            model_used,
+            ..Self::failed(i18n::tr_current("streamCheck.cancelled"))
+        }
+    }
+
+    /// 构造一个跳过结果（未发出请求）
+    pub fn skipped(message: impl Into<String>) -> Self {
+        Self {
+            status: HealthStatus::Skipped,
+            ..Self::failed(message)
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.status == HealthStatus::Cancelled
+    }
+
+    /// 检查得出了供应商状态（未取消、未跳过）
+    pub fn is_conclusive(&self) -> bool {
+        !matches!(self.status, HealthStatus::Cancelled | HealthStatus::Skipped)
+    }
+}
+
+/// 第 attempt 次重试前的等待时长（attempt 从 1 开始，按指数退避并限制上限）
+pub fn retry_delay(config: &StreamCheckConfig, attempt: u32) -> Duration {
+    let factor = 1u64 << attempt.saturating_sub(1).min(20);
+    Duration::from_millis(
+        config
+            .retry_backoff_base_ms
+            .saturating_mul(factor)
+            .min(config.retry_backoff_max_ms),
+    )
+}
+
+/// 最近一次检查结果是否已过期（无记录视为过期；stale_minutes 为 0 时永不过期）
+pub fn is_stale(latest: Option<&StreamCheckResult>, now: i64, stale_minutes: u32) -> bool {
+    if stale_minutes == 0 {
+        return false;
+    }
+    latest.is_none_or(|r| now - r.tested_at >= stale_minutes as i64 * 60)
 }
 
 /// 流式健康检查服务
@@ -76,31 +259,100 @@ impl StreamCheckService {
         provider: &Provider,
         config: &StreamCheckConfig,
     ) -> Result<StreamCheckResult, AppError> {
+        Self::check_model_with_retry(app_type, provider, config, None, &CancelToken::new()).await
+    }
+
+    /// 使用指定模型执行流式健康检查（model 为 None 时使用默认测试模型）
+    ///
+    /// `cancel` 被取消时中止进行中的请求，返回状态为 `cancelled` 的结果。
+    pub async fn check_model_with_retry(
+        app_type: &AppType,
+        provider: &Provider,
+        config: &StreamCheckConfig,
+        model: Option<&str>,
+        cancel: &CancelToken,
+    ) -> Result<StreamCheckResult, AppError> {
+        Self::run_with_retry(
+            app_type,
+            provider,
+            config,
+            model,
+            &SseCompletionProbe,
+            cancel,
+        )
+        .await
+    }
+
+    /// 使用指定探测方式执行健康检查（带重试）
+    pub async fn check_with_probe(
+        app_type: &AppType,
+        provider: &Provider,
+        config: &StreamCheckConfig,
+        probe: &dyn HealthProbe,
+        cancel: &CancelToken,
+    ) -> Result<StreamCheckResult, AppError> {
+        Self::run_with_retry(app_type, provider, config, None, probe, cancel).await
+    }
+
+    async fn run_with_retry(
+        app_type: &AppType,
+        provider: &Provider,
+        config: &StreamCheckConfig,
+        model: Option<&str>,
+        probe: &dyn HealthProbe,
+        cancel: &CancelToken,
+    ) -> Result<StreamCheckResult, AppError> {
+        // 离线时远程供应商必然失败，直接返回而不是等到超时
+        if offline::is_offline() && LocalModelConfig::of(provider).is_none() {
+            return Ok(StreamCheckResult::skipped(i18n::tr_current(
+                "streamCheck.offlineSkipped",
+            )));
+        }
+
+        let mut attempts: Vec<StreamCheckAttempt> = Vec::new();
         let mut last_result = None;
 
         for attempt in 0..=config.max_retries {
-            let result = Self::check_once(app_type, provider, config).await;
+            // 取消时丢弃进行中的请求（或重试等待），已完成的尝试保留在结果中
+            let result = cancel
+                .run(async {
+                    if attempt > 0 {
+                        tokio::time::sleep(retry_delay(config, attempt)).await;
+                    }
+                    Self::check_once(app_type, provider, config, model, probe).await
+                })
+                .await;
+            let Some(result) = result else {
+                let model = Self::model_to_test(app_type, provider, config, model, probe);
+                let cancelled = StreamCheckResult::cancelled(model);
+                attempts.push((attempt, &cancelled).into());
+                return Ok(Self::with_attempts(cancelled, attempt, attempts));
+            };
+            if probe.kind().consumes_tokens() {
+                health_probe::record_full_check(
+                    app_type.as_str(),
+                    &provider.id,
+                    chrono::Utc::now().timestamp(),
+                );
+            }
 
-            match &result {
-                Ok(r) if r.success => {
-                    return Ok(StreamCheckResult {
-                        retry_count: attempt,
-                        ..r.clone()
-                    });
-                }
+            match result {
                 Ok(r) => {
+                    attempts.push((attempt, &r).into());
                     // 失败但非异常，判断是否重试
-                    if Self::should_retry(&r.message) && attempt < config.max_retries {
-                        last_result = Some(r.clone());
+                    if !r.success
+                        && Self::should_retry_result(&r, config)
+                        && attempt < config.max_retries
+                    {
+                        last_result = Some(r);
                         continue;
                     }
-                    return Ok(StreamCheckResult {
-                        retry_count: attempt,
-                        ..r.clone()
-                    });
+                    return Ok(Self::with_attempts(r, attempt, attempts));
                 }
                 Err(e) => {
-                    if Self::should_retry(&e.to_string()) && attempt < config.max_retries {
+                    let failed = StreamCheckResult::from_error(&e);
+                    attempts.push((attempt, &failed).into());
+                    if Self::should_retry(&failed.message) && attempt < config.max_retries {
                         continue;
                     }
                     return Err(AppError::Message(e.to_string()));
@@ -108,23 +360,74 @@ impl StreamCheckService {
             }
         }
 
-        Ok(last_result.unwrap_or_else(|| StreamCheckResult {
-            status: HealthStatus::Failed,
-            success: false,
-            message: "检查失败".to_string(),
-            response_time_ms: None,
-            http_status: None,
-            model_used: String::new(),
-            tested_at: chrono::Utc::now().timestamp(),
-            retry_count: config.max_retries,
-        }))
+        let result = last_result
+            .unwrap_or_else(|| StreamCheckResult::failed(i18n::tr_current("streamCheck.failed")));
+        Ok(Self::with_attempts(result, config.max_retries, attempts))
+    }
+
+    /// 写入重试次数与每次尝试的结果（未重试时不记录尝试明细）
+    fn with_attempts(
+        result: StreamCheckResult,
+        retry_count: u32,
+        attempts: Vec<StreamCheckAttempt>,
+    ) -> StreamCheckResult {
+        StreamCheckResult {
+            retry_count,
+            attempts: if attempts.len() > 1 {
+                attempts
+            } else {
+                Vec::new()
+            },
+            ..result
+        }
+    }
+
+    /// 依次检查默认测试模型与供应商配置的附加模型（meta.streamCheckModels），
+    /// 第一个结果为默认模型
+    pub async fn check_all_models(
+        app_type: &AppType,
+        provider: &Provider,
+        config: &StreamCheckConfig,
+        cancel: &CancelToken,
+    ) -> Vec<StreamCheckResult> {
+        let default_model = Self::resolve_test_model(app_type, provider, config);
+        let mut models = vec![None];
+        if let Some(meta) = &provider.meta {
+            for model in &meta.stream_check_models {
+                let model = model.trim();
+                if !model.is_empty()
+                    && model != default_model
+                    && !models.iter().any(|m| m.as_deref() == Some(model))
+                {
+                    models.push(Some(model.to_string()));
+                }
+            }
+        }
+
+        let mut results = Vec::with_capacity(models.len());
+        for model in models {
+            if cancel.is_cancelled() {
+                break;
+            }
+            let result =
+                Self::check_model_with_retry(app_type, provider, config, model.as_deref(), cancel)
+                    .await
+                    .unwrap_or_else(|e| StreamCheckResult {
+                        model_used: model.unwrap_or_else(|| default_model.clone()),
+                        ..StreamCheckResult::from_error(&e)
+                    });
+            results.push(result);
+        }
+        results
     }
 
-    /// 单次流式检查
+    /// 单次检查
     async fn check_once(
         app_type: &AppType,
         provider: &Provider,
         config: &StreamCheckConfig,
+        model: Option<&str>,
+        probe: &dyn HealthProbe,
     ) -> Result<StreamCheckResult, AppError> {
         let start = Instant::now();
         let adapter = get_adapter(app_type);
@@ -137,57 +440,114 @@ impl StreamCheckService {
             .extract_auth(provider)
             .ok_or_else(|| AppError::Message("未找到 API Key".to_string()))?;
 
-        let client = Client::builder()
-            .timeout(Duration::from_secs(config.timeout_secs))
-            .user_agent("cc-switch/1.0")
-            .build()
-            .map_err(|e| AppError::Message(format!("创建客户端失败: {e}")))?;
+        // 与代理转发共用客户端，复用到同一中转站的连接与 TLS 会话
+        let client = client_pool::shared(provider, &base_url)?;
+        let base_url = provider_tls::rewrite_url(&base_url, provider);
+        let timeout = Duration::from_secs(config.timeout_secs);
 
-        let model_to_test = Self::resolve_test_model(app_type, provider, config);
+        let model_to_test = Self::model_to_test(app_type, provider, config, model, probe);
 
-        let result = match app_type {
-            AppType::Claude => {
-                Self::check_claude_stream(provider, &client, &base_url, &auth, &model_to_test).await
-            }
-            AppType::Codex => {
-                Self::check_codex_stream(provider, &client, &base_url, &auth, &model_to_test).await
-            }
-            AppType::Gemini => {
-                Self::check_gemini_stream(provider, &client, &base_url, &auth, &model_to_test).await
-            }
+        // 本地模型服务：使用原生接口检查，不消耗推理资源
+        let result = if let Some(local) = LocalModelConfig::of(provider) {
+            LocalModelService::native_health(local)
+                .await
+                .map(|(status, model)| ProbeOutcome {
+                    http_status: Some(status),
+                    model,
+                    tokens_used: None,
+                })
+        } else {
+            let ctx = ProbeContext {
+                app_type,
+                provider,
+                client: &client,
+                base_url: &base_url,
+                auth: &auth,
+                model: &model_to_test,
+                timeout,
+            };
+            tokio::time::timeout(timeout, probe.probe(&ctx))
+                .await
+                .unwrap_or_else(|_| {
+                    Err(AppError::UpstreamTimeout(format!(
+                        "timeout after {}s",
+                        config.timeout_secs
+                    )))
+                })
         };
 
         let response_time = start.elapsed().as_millis() as u64;
         let tested_at = chrono::Utc::now().timestamp();
 
         match result {
-            Ok((status_code, model)) => {
+            Ok(outcome) => {
                 let health_status =
                     Self::determine_status(response_time, config.degraded_threshold_ms);
                 Ok(StreamCheckResult {
                     status: health_status,
                     success: true,
-                    message: "检查成功".to_string(),
+                    message: i18n::tr_current("streamCheck.success"),
                     response_time_ms: Some(response_time),
-                    http_status: Some(status_code),
-                    model_used: model,
+                    http_status: outcome.http_status,
+                    model_used: outcome.model,
                     tested_at,
                     retry_count: 0,
+                    endpoint: base_url,
+                    attempts: Vec::new(),
+                    error_code: None,
+                    tokens_used: outcome.tokens_used,
                 })
             }
             Err(e) => Ok(StreamCheckResult {
-                status: HealthStatus::Failed,
-                success: false,
-                message: e.to_string(),
                 response_time_ms: Some(response_time),
-                http_status: None,
-                model_used: String::new(),
+                http_status: Self::parse_http_status(&e.to_string()),
+                model_used: model_to_test,
                 tested_at,
-                retry_count: 0,
+                endpoint: base_url,
+                ..StreamCheckResult::from_error(&e)
             }),
         }
     }
 
+    /// 本次检查记录的模型（不涉及模型的探测不记录测试模型）
+    fn model_to_test(
+        app_type: &AppType,
+        provider: &Provider,
+        config: &StreamCheckConfig,
+        model: Option<&str>,
+        probe: &dyn HealthProbe,
+    ) -> String {
+        match model {
+            Some(model) => model.to_string(),
+            None if probe.kind().consumes_tokens() => {
+                Self::resolve_test_model(app_type, provider, config)
+            }
+            None => String::new(),
+        }
+    }
+
+    /// 按应用类型发送流式补全请求，只需收到首个 chunk
+    pub(crate) async fn check_completion_stream(
+        app_type: &AppType,
+        provider: &Provider,
+        client: &Client,
+        base_url: &str,
+        auth: &AuthInfo,
+        model: &str,
+    ) -> Result<ProbeOutcome, AppError> {
+        match app_type {
+            AppType::Claude => {
+                Self::check_claude_stream(provider, client, base_url, auth, model).await
+            }
+            AppType::Codex => {
+                Self::check_codex_stream(provider, client, base_url, auth, model).await
+            }
+            AppType::Gemini => {
+                Self::check_gemini_stream(provider, client, base_url, auth, model).await
+            }
+        }
+    }
+
     /// Claude 流式检查
     async fn check_claude_stream(
         provider: &Provider,
@@ -195,13 +555,16 @@ impl StreamCheckService {
         base_url: &str,
         auth: &AuthInfo,
         model: &str,
-    ) -> Result<(u16, String), AppError> {
+    ) -> Result<ProbeOutcome, AppError> {
         let base = base_url.trim_end_matches('/');
-        let url = if base.ends_with("/v1") {
-            format!("{base}/messages")
-        } else {
-            format!("{base}/v1/messages")
-        };
+        let url = endpoint_template::resolve_url(provider, base, "/v1/messages", Some(model))
+            .unwrap_or_else(|| {
+                if base.ends_with("/v1") {
+                    format!("{base}/messages")
+                } else {
+                    format!("{base}/v1/messages")
+                }
+            });
 
         let body = json!({
             "model": model,
@@ -217,31 +580,52 @@ impl StreamCheckService {
             .header("Content-Type", "application/json")
             .json(&body);
 
-        let mut built = request.build().map_err(|e| AppError::Message(e.to_string()))?;
+        let mut built = request
+            .build()
+            .map_err(|e| AppError::Message(e.to_string()))?;
+        apply_auth_scheme(provider, Some(auth), &mut built)?;
+        // 云厂商托管：与代理共用同一套请求改写，直接检查对应模型端点
+        let cloud = CloudProviderConfig::of(provider);
+        if let Some(cloud) = cloud {
+            prepare_cloud_request(cloud, client, &mut built, &body)
+                .await
+                .map_err(|e| AppError::Message(e.to_string()))?;
+        }
+        // gRPC 上游：探测请求本身就是 Chat Completions 的形状，可直接编码
+        let grpc = GrpcUpstreamConfig::of(provider);
+        if let Some(grpc) = grpc {
+            prepare_grpc_request(grpc, &mut built, &body)
+                .map_err(|e| AppError::Message(e.to_string()))?;
+        }
         apply_custom_headers_to_request(provider, &mut built);
 
-        let response = client
-            .execute(built)
-            .await
-            .map_err(Self::map_request_error)?;
+        let response = client.execute(built).await.map_err(AppError::from)?;
 
         let status = response.status().as_u16();
 
         if !response.status().is_success() {
             let error_text = response.text().await.unwrap_or_default();
-            return Err(AppError::Message(format!("HTTP {status}: {error_text}")));
+            return Err(AppError::UpstreamStatus {
+                status,
+                body: error_text,
+            });
         }
+        let response = match (grpc, cloud) {
+            (Some(grpc), _) => adapt_grpc_response(grpc, response, &body)
+                .await
+                .map_err(|e| AppError::Message(e.to_string()))?,
+            // Bedrock 返回 AWS 事件流，需先还原为 SSE
+            (None, Some(cloud)) => adapt_cloud_response(cloud, response, &body),
+            (None, None) => response,
+        };
 
-        // 流式读取：只需首个 chunk
-        let mut stream = response.bytes_stream();
-        if let Some(chunk) = stream.next().await {
-            match chunk {
-                Ok(_) => Ok((status, model.to_string())),
-                Err(e) => Err(AppError::Message(format!("读取流失败: {e}"))),
-            }
-        } else {
-            Err(AppError::Message("未收到响应数据".to_string()))
-        }
+        let tokens_used =
+            Self::read_probe_stream(response, TokenUsage::from_claude_stream_events).await?;
+        Ok(ProbeOutcome {
+            http_status: Some(status),
+            model: model.to_string(),
+            tokens_used,
+        })
     }
 
     /// Codex 流式检查
@@ -251,17 +635,25 @@ impl StreamCheckService {
         base_url: &str,
         auth: &AuthInfo,
         model: &str,
-    ) -> Result<(u16, String), AppError> {
-        let base = base_url.trim_end_matches('/');
-        let url = if base.ends_with("/v1") {
-            format!("{base}/chat/completions")
-        } else {
-            format!("{base}/v1/chat/completions")
-        };
-
+    ) -> Result<ProbeOutcome, AppError> {
         // 解析模型名和推理等级 (支持 model@level 或 model#level 格式)
         let (actual_model, reasoning_effort) = Self::parse_model_with_effort(model);
 
+        let base = base_url.trim_end_matches('/');
+        let url = endpoint_template::resolve_url(
+            provider,
+            base,
+            "/v1/chat/completions",
+            Some(&actual_model),
+        )
+        .unwrap_or_else(|| {
+            if base.ends_with("/v1") {
+                format!("{base}/chat/completions")
+            } else {
+                format!("{base}/v1/chat/completions")
+            }
+        });
+
         let mut body = json!({
             "model": actual_model,
             "messages": [
@@ -285,30 +677,36 @@ impl StreamCheckService {
             .header("Content-Type", "application/json")
             .json(&body);
 
-        let mut built = request.build().map_err(|e| AppError::Message(e.to_string()))?;
+        let mut built = request
+            .build()
+            .map_err(|e| AppError::Message(e.to_string()))?;
+        apply_auth_scheme(provider, Some(auth), &mut built)?;
+        // Azure OpenAI：检查映射到的部署端点
+        if let Some(azure) = AzureOpenAiConfig::of(provider) {
+            prepare_azure_request(azure, &mut built, &body)
+                .map_err(|e| AppError::Message(e.to_string()))?;
+        }
         apply_custom_headers_to_request(provider, &mut built);
 
-        let response = client
-            .execute(built)
-            .await
-            .map_err(Self::map_request_error)?;
+        let response = client.execute(built).await.map_err(AppError::from)?;
 
         let status = response.status().as_u16();
 
         if !response.status().is_success() {
             let error_text = response.text().await.unwrap_or_default();
-            return Err(AppError::Message(format!("HTTP {status}: {error_text}")));
+            return Err(AppError::UpstreamStatus {
+                status,
+                body: error_text,
+            });
         }
 
-        let mut stream = response.bytes_stream();
-        if let Some(chunk) = stream.next().await {
-            match chunk {
-                Ok(_) => Ok((status, model.to_string())),
-                Err(e) => Err(AppError::Message(format!("读取流失败: {e}"))),
-            }
-        } else {
-            Err(AppError::Message("未收到响应数据".to_string()))
-        }
+        let tokens_used =
+            Self::read_probe_stream(response, TokenUsage::from_openai_stream_events).await?;
+        Ok(ProbeOutcome {
+            http_status: Some(status),
+            model: model.to_string(),
+            tokens_used,
+        })
     }
 
     /// Gemini 流式检查
@@ -318,9 +716,11 @@ impl StreamCheckService {
         base_url: &str,
         auth: &AuthInfo,
         model: &str,
-    ) -> Result<(u16, String), AppError> {
+    ) -> Result<ProbeOutcome, AppError> {
         let base = base_url.trim_end_matches('/');
-        let url = format!("{base}/v1/chat/completions");
+        let url =
+            endpoint_template::resolve_url(provider, base, "/v1/chat/completions", Some(model))
+                .unwrap_or_else(|| format!("{base}/v1/chat/completions"));
 
         let body = json!({
             "model": model,
@@ -336,30 +736,69 @@ impl StreamCheckService {
             .header("Content-Type", "application/json")
             .json(&body);
 
-        let mut built = request.build().map_err(|e| AppError::Message(e.to_string()))?;
+        let mut built = request
+            .build()
+            .map_err(|e| AppError::Message(e.to_string()))?;
+        apply_auth_scheme(provider, Some(auth), &mut built)?;
         apply_custom_headers_to_request(provider, &mut built);
 
-        let response = client
-            .execute(built)
-            .await
-            .map_err(Self::map_request_error)?;
+        let response = client.execute(built).await.map_err(AppError::from)?;
 
         let status = response.status().as_u16();
 
         if !response.status().is_success() {
             let error_text = response.text().await.unwrap_or_default();
-            return Err(AppError::Message(format!("HTTP {status}: {error_text}")));
+            return Err(AppError::UpstreamStatus {
+                status,
+                body: error_text,
+            });
         }
 
+        let tokens_used =
+            Self::read_probe_stream(response, TokenUsage::from_openai_stream_events).await?;
+        Ok(ProbeOutcome {
+            http_status: Some(status),
+            model: model.to_string(),
+            tokens_used,
+        })
+    }
+
+    /// 读取探测的流式响应：收到首个 chunk 即判定成功，随后在限定时间内读完剩余内容，
+    /// 从中解析实际消耗的 token（响应不带用量时返回 None）
+    async fn read_probe_stream(
+        response: Response,
+        parse_usage: fn(&[Value]) -> Option<TokenUsage>,
+    ) -> Result<Option<u64>, AppError> {
         let mut stream = response.bytes_stream();
-        if let Some(chunk) = stream.next().await {
-            match chunk {
-                Ok(_) => Ok((status, model.to_string())),
-                Err(e) => Err(AppError::Message(format!("读取流失败: {e}"))),
+        let mut body = match stream.next().await {
+            Some(Ok(chunk)) => chunk.to_vec(),
+            Some(Err(e)) => return Err(AppError::Message(format!("读取流失败: {e}"))),
+            None => return Err(AppError::Message("未收到响应数据".to_string())),
+        };
+
+        // 探测只请求 1 个 token，剩余内容很短；读取失败或超时只影响用量统计
+        let _ = tokio::time::timeout(USAGE_DRAIN_TIMEOUT, async {
+            while let Some(Ok(chunk)) = stream.next().await {
+                body.extend_from_slice(&chunk);
+                if body.len() >= MAX_PROBE_BODY_BYTES {
+                    break;
+                }
             }
-        } else {
-            Err(AppError::Message("未收到响应数据".to_string()))
-        }
+        })
+        .await;
+
+        let events = sse_events(&String::from_utf8_lossy(&body));
+        Ok(parse_usage(&events).map(|usage| {
+            [
+                usage.input_tokens,
+                usage.output_tokens,
+                usage.cache_read_tokens,
+                usage.cache_creation_tokens,
+            ]
+            .iter()
+            .map(|&t| t as u64)
+            .sum()
+        }))
     }
 
     fn determine_status(latency_ms: u64, threshold: u64) -> HealthStatus {
@@ -384,25 +823,35 @@ impl StreamCheckService {
         (model.to_string(), None)
     }
 
+    /// 失败结果是否可重试：HTTP 状态码在可重试列表中，或为超时 / 中断
+    fn should_retry_result(result: &StreamCheckResult, config: &StreamCheckConfig) -> bool {
+        match result.http_status {
+            Some(status) => config.retryable_statuses.contains(&status),
+            None => Self::should_retry(&result.message),
+        }
+    }
+
     fn should_retry(msg: &str) -> bool {
         let lower = msg.to_lowercase();
         lower.contains("timeout")
             || lower.contains("abort")
             || lower.contains("中断")
             || lower.contains("超时")
+            || lower.contains("timed out")
+            || lower.contains("タイムアウト")
     }
 
-    fn map_request_error(e: reqwest::Error) -> AppError {
-        if e.is_timeout() {
-            AppError::Message("请求超时".to_string())
-        } else if e.is_connect() {
-            AppError::Message(format!("连接失败: {e}"))
-        } else {
-            AppError::Message(e.to_string())
-        }
+    /// 从 "HTTP {status}: ..." 格式的错误信息中提取状态码
+    fn parse_http_status(msg: &str) -> Option<u16> {
+        msg.strip_prefix("HTTP ")?
+            .split(':')
+            .next()?
+            .trim()
+            .parse()
+            .ok()
     }
 
-    fn resolve_test_model(
+    pub(crate) fn resolve_test_model(
         app_type: &AppType,
         provider: &Provider,
         config: &StreamCheckConfig,
@@ -497,4 +946,93 @@ mod tests {
         assert_eq!(model, "gpt-4o-mini");
         assert_eq!(effort, None);
     }
+
+    #[tokio::test]
+    async fn probe_stream_reports_usage() {
+        let response = |body: &'static str| {
+            Response::from(axum::http::Response::builder().body(body).unwrap())
+        };
+
+        let claude = "event: message_start\n\
+            data: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":12,\"output_tokens\":1}}}\n\n\
+            event: message_delta\n\
+            data: {\"type\":\"message_delta\",\"usage\":{\"output_tokens\":1}}\n\n";
+        let tokens = StreamCheckService::read_probe_stream(
+            response(claude),
+            TokenUsage::from_claude_stream_events,
+        )
+        .await
+        .unwrap();
+        assert_eq!(tokens, Some(13));
+
+        let no_usage = "data: {\"choices\":[{\"delta\":{\"content\":\"hi\"}}]}\n\ndata: [DONE]\n\n";
+        let tokens = StreamCheckService::read_probe_stream(
+            response(no_usage),
+            TokenUsage::from_openai_stream_events,
+        )
+        .await
+        .unwrap();
+        assert_eq!(tokens, None);
+    }
+
+    #[test]
+    fn test_is_stale() {
+        let result = StreamCheckResult {
+            status: HealthStatus::Operational,
+            success: true,
+            message: String::new(),
+            response_time_ms: Some(100),
+            http_status: Some(200),
+            model_used: String::new(),
+            tested_at: 1000,
+            retry_count: 0,
+            endpoint: String::new(),
+            attempts: Vec::new(),
+            error_code: None,
+            tokens_used: None,
+        };
+        assert!(is_stale(None, 1000, 60));
+        assert!(!is_stale(Some(&result), 1000 + 59 * 60, 60));
+        assert!(is_stale(Some(&result), 1000 + 60 * 60, 60));
+        // 0 表示关闭
+        assert!(!is_stale(None, 1000, 0));
+    }
+
+    #[test]
+    fn test_retry_delay_backoff() {
+        let config = StreamCheckConfig {
+            retry_backoff_base_ms: 500,
+            retry_backoff_max_ms: 1500,
+            ..StreamCheckConfig::default()
+        };
+        assert_eq!(retry_delay(&config, 1), Duration::from_millis(500));
+        assert_eq!(retry_delay(&config, 2), Duration::from_millis(1000));
+        assert_eq!(retry_delay(&config, 3), Duration::from_millis(1500));
+        assert_eq!(retry_delay(&config, 40), Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn test_should_retry_result_by_status() {
+        let config = StreamCheckConfig::default();
+        let result = |status: Option<u16>, message: &str| StreamCheckResult {
+            http_status: status,
+            ..StreamCheckResult::failed(message)
+        };
+        assert!(StreamCheckService::should_retry_result(
+            &result(Some(503), "HTTP 503: busy"),
+            &config
+        ));
+        assert!(!StreamCheckService::should_retry_result(
+            &result(Some(401), "HTTP 401: unauthorized"),
+            &config
+        ));
+        assert!(StreamCheckService::should_retry_result(
+            &result(None, "请求超时"),
+            &config
+        ));
+        assert_eq!(
+            StreamCheckService::parse_http_status("HTTP 429: rate limited"),
+            Some(429)
+        );
+    }
 }
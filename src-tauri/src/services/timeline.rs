@@ -0,0 +1,166 @@
+//! 时间线事件记录
+//!
+//! 监听事件总线，把供应商的故障与恢复、探测预算、费用预测与延迟 SLO 告警写入时间线，
+//! 与切换历史一起组成统一的历史视图。只记录状态变化：连续失败只记一次故障，
+//! 之后第一次检查成功记一次恢复；维护窗口内的失败与用户取消的检查不计入。
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::database::{Database, TimelineKind};
+use crate::events::{self, AppEvent};
+use crate::services::probe_budget::BudgetState;
+
+/// 待写入的事件
+#[derive(Debug, Clone, PartialEq)]
+struct Recorded {
+    app_type: String,
+    provider_id: String,
+    kind: TimelineKind,
+    message: String,
+}
+
+/// 当前处于故障中的（应用类型, 供应商 ID）
+type Failing = HashSet<(String, String)>;
+
+fn to_recorded(event: &AppEvent, failing: &mut Failing) -> Option<Recorded> {
+    let (app_type, provider_id, kind, message) = match event {
+        AppEvent::HealthChanged(p) => {
+            if !p.result.is_conclusive() {
+                return None;
+            }
+            let key = (p.app_type.clone(), p.provider_id.clone());
+            if p.result.success {
+                if !failing.remove(&key) {
+                    return None;
+                }
+                (
+                    key.0,
+                    key.1,
+                    TimelineKind::Recovery,
+                    "健康检查恢复正常".to_string(),
+                )
+            } else {
+                if p.in_maintenance || !failing.insert(key.clone()) {
+                    return None;
+                }
+                let message = format!("健康检查失败: {}", p.result.message);
+                (key.0, key.1, TimelineKind::Incident, message)
+            }
+        }
+        AppEvent::BudgetWarning(status) => {
+            let message = match status.state {
+                BudgetState::Normal => return None,
+                BudgetState::Throttled => format!(
+                    "探测预算已用 {} / {} token，降低自动探测频率",
+                    status.tokens_used,
+                    status.budget.unwrap_or_default()
+                ),
+                BudgetState::Exhausted => "本月探测预算已用尽，暂停自动探测".to_string(),
+            };
+            let (app_type, provider_id) = (status.app_type.clone(), status.provider_id.clone());
+            (app_type, provider_id, TimelineKind::Budget, message)
+        }
+        AppEvent::UsageForecastWarning(forecast) => {
+            let mut message = format!("预计本月费用 ${}", forecast.projected_cost);
+            if let Some(threshold) = &forecast.threshold_usd {
+                message.push_str(&format!("，超出阈值 ${threshold}"));
+            }
+            if let Some(date) = &forecast.projected_exceed_date {
+                message.push_str(&format!("（预计 {date} 超出）"));
+            }
+            let (app_type, provider_id) = (forecast.app_type.clone(), forecast.provider_id.clone());
+            (app_type, provider_id, TimelineKind::Budget, message)
+        }
+        AppEvent::SloBurnRateWarning(status) => {
+            let message = format!(
+                "延迟 SLO（{}ms 内 {}%）错误预算剩余 {:.1}%",
+                status.slo.threshold_ms, status.slo.target_percent, status.budget_remaining_percent
+            );
+            let (app_type, provider_id) = (status.app_type.clone(), status.provider_id.clone());
+            (app_type, provider_id, TimelineKind::Budget, message)
+        }
+        _ => return None,
+    };
+    Some(Recorded {
+        app_type,
+        provider_id,
+        kind,
+        message,
+    })
+}
+
+/// 后台记录循环
+pub async fn run(db: Arc<Database>) {
+    let mut rx = events::subscribe();
+    let mut failing = Failing::new();
+    loop {
+        let event = match rx.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(n)) => {
+                log::warn!("[Timeline] 事件处理过慢，丢弃 {n} 条事件");
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        let Some(recorded) = to_recorded(&event, &mut failing) else {
+            continue;
+        };
+        if let Err(e) = db.record_timeline_event(
+            &recorded.app_type,
+            &recorded.provider_id,
+            recorded.kind,
+            &recorded.message,
+            chrono::Utc::now().timestamp(),
+        ) {
+            log::warn!("[Timeline] 写入时间线事件失败: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::HealthChangedPayload;
+    use crate::services::stream_check::{HealthStatus, StreamCheckResult};
+
+    fn health(success: bool, in_maintenance: bool) -> AppEvent {
+        AppEvent::HealthChanged(HealthChangedPayload {
+            app_type: "claude".into(),
+            provider_id: "relay".into(),
+            result: StreamCheckResult {
+                success,
+                status: if success {
+                    HealthStatus::Operational
+                } else {
+                    HealthStatus::Failed
+                },
+                ..StreamCheckResult::failed("HTTP 503")
+            },
+            in_maintenance,
+        })
+    }
+
+    #[test]
+    fn records_only_health_transitions() {
+        let mut failing = Failing::new();
+        let kinds: Vec<_> = [
+            health(true, false),
+            health(false, true),
+            health(false, false),
+            health(false, false),
+            health(true, false),
+            health(true, false),
+        ]
+        .iter()
+        .filter_map(|event| to_recorded(event, &mut failing))
+        .map(|recorded| recorded.kind)
+        .collect();
+        assert_eq!(kinds, [TimelineKind::Incident, TimelineKind::Recovery]);
+
+        let incident = to_recorded(&health(false, false), &mut failing).unwrap();
+        assert_eq!(incident.message, "健康检查失败: HTTP 503");
+    }
+}
@@ -14,6 +14,7 @@ use crate::provider::Provider;
 use crate::proxy::custom_headers::apply_custom_headers_to_request;
 use crate::proxy::providers::get_adapter;
 use crate::proxy::usage::parser::TokenUsage;
+use crate::services::cancellation::CancelToken;
 
 /// token 统计来源
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -194,6 +195,32 @@ impl TpsTestService {
         }
     }
 
+    /// 可取消的 TPS 测试：取消时中止进行中的请求并返回取消结果
+    pub async fn test_cancellable(
+        app_type: &AppType,
+        provider: &Provider,
+        timeout_secs: u64,
+        cancel: &CancelToken,
+    ) -> TpsTestResult {
+        let start = Instant::now();
+        let tested_at = chrono::Utc::now().timestamp();
+        let test = Self::test_once(app_type, provider, timeout_secs);
+        match cancel.run(test).await {
+            Some(result) => result,
+            None => TpsTestResult {
+                success: false,
+                message: "测试已取消".to_string(),
+                model_used: Self::resolve_test_model(app_type, provider),
+                http_status: None,
+                response_time_ms: start.elapsed().as_millis() as u64,
+                output_tokens: None,
+                tokens_per_second: None,
+                token_source: None,
+                tested_at,
+            },
+        }
+    }
+
     async fn call_claude(
         client: &Client,
         base_url: &str,
@@ -463,8 +490,8 @@ impl TpsTestService {
     }
 
     pub(crate) fn estimate_tokens_by_utf8_bytes(text: &str) -> u64 {
-        let bytes = text.as_bytes().len() as u64;
-        (bytes + 3) / 4
+        let bytes = text.len() as u64;
+        bytes.div_ceil(4)
     }
 
     fn resolve_test_model(app_type: &AppType, provider: &Provider) -> String {
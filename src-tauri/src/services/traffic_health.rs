@@ -0,0 +1,332 @@
+//! 结合真实流量的健康评分
+//!
+//! 探测请求往往能通过，而真实请求（尤其是工具调用）却在失败。
+//! 这里把最近一段时间内经代理转发的真实请求失败率与最近一次探测结果按权重混合：
+//! - 探测失败分：正常 0、降级 0.5、失败 1
+//! - 真实流量失败分：上游故障请求（5xx、429、超时与连接失败）占比；
+//!   4xx 多为请求本身的问题（如参数错误、鉴权失败），不计为供应商故障
+//! - 综合分 = 两者按权重加权平均，超过降级 / 失败阈值时给出对应状态
+//!
+//! 真实请求数不足 `minRequests` 时只看探测结果；没有探测结果时只看真实流量。
+//! 默认权重下，探测正常但 20% 的真实请求失败即判定为降级。
+
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::services::stream_check::{HealthStatus, StreamCheckConfig};
+
+/// 混合评分设置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrafficHealthConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// 统计真实请求的时间窗口（分钟）
+    #[serde(default = "default_window_minutes")]
+    pub window_minutes: u32,
+    /// 窗口内真实请求少于该数时不计入
+    #[serde(default = "default_min_requests")]
+    pub min_requests: u32,
+    /// 探测结果的权重
+    #[serde(default = "default_weight")]
+    pub probe_weight: u32,
+    /// 真实流量的权重
+    #[serde(default = "default_weight")]
+    pub traffic_weight: u32,
+    /// 综合失败分（百分比）达到该值判定为降级
+    #[serde(default = "default_degraded_percent")]
+    pub degraded_percent: u32,
+    /// 综合失败分（百分比）达到该值判定为失败
+    #[serde(default = "default_failed_percent")]
+    pub failed_percent: u32,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_window_minutes() -> u32 {
+    15
+}
+
+fn default_min_requests() -> u32 {
+    10
+}
+
+fn default_weight() -> u32 {
+    50
+}
+
+fn default_degraded_percent() -> u32 {
+    10
+}
+
+fn default_failed_percent() -> u32 {
+    50
+}
+
+impl Default for TrafficHealthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            window_minutes: default_window_minutes(),
+            min_requests: default_min_requests(),
+            probe_weight: default_weight(),
+            traffic_weight: default_weight(),
+            degraded_percent: default_degraded_percent(),
+            failed_percent: default_failed_percent(),
+        }
+    }
+}
+
+/// 窗口内的真实请求统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrafficStats {
+    pub requests: u64,
+    pub failures: u64,
+}
+
+impl TrafficStats {
+    pub fn failure_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.requests as f64
+        }
+    }
+}
+
+/// 混合后的健康状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlendedHealth {
+    /// 综合状态（既无探测结果、真实请求又不足时为空）
+    pub status: Option<HealthStatus>,
+    /// 最近一次探测结果
+    pub probe_status: Option<HealthStatus>,
+    pub traffic: TrafficStats,
+    /// 真实流量是否计入
+    pub traffic_counted: bool,
+    /// 综合失败分（0~1）
+    pub score: Option<f64>,
+}
+
+impl Database {
+    /// 统计 `since` 之后某个供应商经代理转发的请求数与上游故障请求数
+    ///
+    /// 超时与连接失败在请求日志中分别记为 504 与 502，因此按 5xx 与 429 统计即可覆盖；
+    /// 回放请求不计入
+    pub fn get_traffic_stats(
+        &self,
+        provider_id: &str,
+        app_type: &str,
+        since: i64,
+    ) -> Result<TrafficStats, AppError> {
+        let conn = lock_conn!(self.conn);
+        let (requests, failures) = conn.query_row(
+            "SELECT COUNT(*),
+                COALESCE(SUM(CASE WHEN status_code >= 500 OR status_code = 429 THEN 1 ELSE 0 END), 0)
+             FROM proxy_request_logs
+             WHERE provider_id = ?1 AND app_type = ?2 AND created_at >= ?3 AND is_replay = 0",
+            params![provider_id, app_type, since],
+            |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+        )?;
+        Ok(TrafficStats {
+            requests: requests as u64,
+            failures: failures as u64,
+        })
+    }
+}
+
+fn probe_score(status: &HealthStatus) -> Option<f64> {
+    match status {
+        HealthStatus::Operational => Some(0.0),
+        HealthStatus::Degraded => Some(0.5),
+        HealthStatus::Failed => Some(1.0),
+        HealthStatus::Cancelled | HealthStatus::Skipped => None,
+    }
+}
+
+/// 按权重混合探测结果与真实流量
+pub fn blend(
+    config: &TrafficHealthConfig,
+    probe: Option<&HealthStatus>,
+    traffic: TrafficStats,
+) -> BlendedHealth {
+    let traffic_counted = config.enabled
+        && config.traffic_weight > 0
+        && traffic.requests > 0
+        && traffic.requests >= config.min_requests as u64;
+
+    let mut parts = Vec::with_capacity(2);
+    if let Some(score) = probe.and_then(probe_score) {
+        // 未开启或真实流量不计入时，探测结果独立决定状态
+        let weight = if traffic_counted {
+            config.probe_weight
+        } else {
+            1
+        };
+        parts.push((score, weight as f64));
+    }
+    if traffic_counted {
+        parts.push((traffic.failure_rate(), config.traffic_weight as f64));
+    }
+
+    let total_weight: f64 = parts.iter().map(|(_, w)| w).sum();
+    let score =
+        (total_weight > 0.0).then(|| parts.iter().map(|(s, w)| s * w).sum::<f64>() / total_weight);
+
+    let status = match score {
+        None => probe.cloned().filter(|s| probe_score(s).is_some()),
+        // 只有探测结果时保持原状态（例如响应慢导致的降级）
+        Some(_) if !traffic_counted => probe.cloned(),
+        Some(score) if score * 100.0 >= config.failed_percent as f64 => Some(HealthStatus::Failed),
+        Some(score) if score * 100.0 >= config.degraded_percent as f64 => {
+            Some(HealthStatus::Degraded)
+        }
+        Some(_) => Some(HealthStatus::Operational),
+    };
+
+    BlendedHealth {
+        status,
+        probe_status: probe.cloned(),
+        traffic,
+        traffic_counted,
+        score,
+    }
+}
+
+/// 读取最近一次探测与窗口内的真实流量并混合
+pub fn blended_health(
+    db: &Database,
+    provider_id: &str,
+    app_type: &str,
+    config: &StreamCheckConfig,
+) -> Result<BlendedHealth, AppError> {
+    let probe = db
+        .get_stream_check_latest(provider_id, app_type, None)?
+        .map(|r| r.status);
+    let traffic = if config.traffic_health.enabled {
+        let since =
+            chrono::Utc::now().timestamp() - config.traffic_health.window_minutes as i64 * 60;
+        db.get_traffic_stats(provider_id, app_type, since)?
+    } else {
+        TrafficStats::default()
+    };
+    Ok(blend(&config.traffic_health, probe.as_ref(), traffic))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn traffic(requests: u64, failures: u64) -> TrafficStats {
+        TrafficStats { requests, failures }
+    }
+
+    #[test]
+    fn real_failures_degrade_passing_probe() {
+        let config = TrafficHealthConfig::default();
+        let ok = Some(&HealthStatus::Operational);
+
+        let blended = blend(&config, ok, traffic(50, 10));
+        assert_eq!(blended.status, Some(HealthStatus::Degraded));
+        assert!(blended.traffic_counted);
+
+        assert_eq!(
+            blend(&config, ok, traffic(50, 2)).status,
+            Some(HealthStatus::Operational)
+        );
+        assert_eq!(
+            blend(&config, ok, traffic(50, 50)).status,
+            Some(HealthStatus::Failed)
+        );
+    }
+
+    #[test]
+    fn sparse_traffic_keeps_probe_status() {
+        let config = TrafficHealthConfig::default();
+        let blended = blend(&config, Some(&HealthStatus::Degraded), traffic(3, 3));
+        assert_eq!(blended.status, Some(HealthStatus::Degraded));
+        assert!(!blended.traffic_counted);
+
+        assert_eq!(blend(&config, None, traffic(3, 0)).status, None);
+        assert_eq!(
+            blend(&config, None, traffic(20, 0)).status,
+            Some(HealthStatus::Operational)
+        );
+    }
+
+    #[test]
+    fn weights_are_configurable() {
+        let config = TrafficHealthConfig {
+            probe_weight: 0,
+            traffic_weight: 100,
+            ..Default::default()
+        };
+        assert_eq!(
+            blend(&config, Some(&HealthStatus::Failed), traffic(20, 0)).status,
+            Some(HealthStatus::Operational)
+        );
+
+        let disabled = TrafficHealthConfig {
+            enabled: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            blend(&disabled, Some(&HealthStatus::Operational), traffic(50, 50)).status,
+            Some(HealthStatus::Operational)
+        );
+    }
+
+    #[test]
+    fn counts_only_upstream_failures() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        {
+            let conn = lock_conn!(db.conn);
+            for (i, (status, created_at)) in [
+                (200, 100),
+                (400, 100),
+                (401, 100),
+                (429, 100),
+                (502, 100),
+                (504, 100),
+                (500, 10),
+            ]
+            .iter()
+            .enumerate()
+            {
+                conn.execute(
+                    "INSERT INTO proxy_request_logs (
+                        request_id, provider_id, app_type, model, latency_ms,
+                        status_code, created_at
+                    ) VALUES (?, ?, ?, ?, ?, ?, ?)",
+                    params![
+                        format!("r{i}"),
+                        "p1",
+                        "claude",
+                        "m1",
+                        800,
+                        status,
+                        created_at
+                    ],
+                )?;
+            }
+            // 回放请求不计入
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model, latency_ms,
+                    status_code, created_at, is_replay
+                ) VALUES ('replay', 'p1', 'claude', 'm1', 800, 502, 100, 1)",
+                [],
+            )?;
+        }
+
+        let stats = db.get_traffic_stats("p1", "claude", 50)?;
+        assert_eq!(stats, traffic(6, 3));
+        Ok(())
+    }
+}
@@ -0,0 +1,414 @@
+//! 用量预测
+//!
+//! 根据每日用量推算各供应商本月月底的 token 用量与费用：
+//! - `linear`：按本月已过天数的日均值外推
+//! - `weekday`：按最近四周同一星期几的均值外推（工作日与周末用量差异大时更准）
+//!
+//! 设置了阈值时（供应商的月度限额，或全局预测阈值），会计算预计超出的日期，
+//! 并在后台检查时发送 `usage-forecast-warning` 事件（每个供应商每月一次）。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{Datelike, Local, NaiveDate};
+use once_cell::sync::Lazy;
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::events::{self, AppEvent};
+use crate::services::startup_profile::{self, Subsystem};
+
+/// 后台检查间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// 按星期几预测时参考的历史天数
+const WEEKDAY_HISTORY_DAYS: i64 = 28;
+
+/// 已告警的（应用, 供应商, 月份），避免重复告警
+static ALERTED: Lazy<Mutex<HashSet<(String, String, String)>>> =
+    Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// 预测方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForecastMethod {
+    #[default]
+    Linear,
+    Weekday,
+}
+
+/// 用量预测设置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageForecastConfig {
+    /// 后台检查并在预计超出阈值时告警
+    #[serde(default)]
+    pub alert_enabled: bool,
+    #[serde(default)]
+    pub method: ForecastMethod,
+    /// 未设置月度限额的供应商使用的预测阈值（美元，0 表示不告警）
+    #[serde(default)]
+    pub threshold_usd: f64,
+}
+
+/// 单日用量
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DailyUsage {
+    pub date: NaiveDate,
+    pub tokens: u64,
+    pub cost: f64,
+}
+
+/// 供应商月底预测
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageForecast {
+    pub app_type: String,
+    pub provider_id: String,
+    pub provider_name: String,
+    pub method: ForecastMethod,
+    /// 月份（`YYYY-MM`）
+    pub month: String,
+    pub month_to_date_tokens: u64,
+    pub month_to_date_cost: String,
+    pub projected_tokens: u64,
+    pub projected_cost: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold_usd: Option<String>,
+    /// 预计超出阈值的日期（`YYYY-MM-DD`，本月内不会超出时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub projected_exceed_date: Option<String>,
+}
+
+/// 预测结果（纯计算部分）
+#[derive(Debug, Clone, PartialEq)]
+pub struct Projection {
+    pub month_to_date_tokens: u64,
+    pub month_to_date_cost: f64,
+    pub projected_tokens: u64,
+    pub projected_cost: f64,
+    pub exceed_date: Option<NaiveDate>,
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|next| next.pred_opt())
+        .map(|last| last.day())
+        .unwrap_or(30)
+}
+
+/// 按预测方式推算本月剩余每天的（tokens, 费用），`linear` 为本月日均值
+fn remaining_days(
+    history: &[DailyUsage],
+    today: NaiveDate,
+    method: ForecastMethod,
+    linear: (f64, f64),
+) -> Vec<(NaiveDate, f64, f64)> {
+    // 最近四周（不含今天）按星期几分组的日均值
+    let mut by_weekday: HashMap<u32, (f64, f64)> = HashMap::new();
+    let history_start = today - chrono::Duration::days(WEEKDAY_HISTORY_DAYS);
+    let mut history_days = 0;
+    for d in history
+        .iter()
+        .filter(|d| d.date >= history_start && d.date < today)
+    {
+        let entry = by_weekday
+            .entry(d.date.weekday().num_days_from_monday())
+            .or_default();
+        entry.0 += d.tokens as f64;
+        entry.1 += d.cost;
+        history_days += 1;
+    }
+    // 历史不足一周时退回线性预测
+    let use_weekday = method == ForecastMethod::Weekday && history_days >= 7;
+    let samples = (WEEKDAY_HISTORY_DAYS / 7) as f64;
+
+    (today.day() + 1..=days_in_month(today))
+        .filter_map(|day| today.with_day(day))
+        .map(|date| {
+            let (tokens, cost) = if use_weekday {
+                by_weekday
+                    .get(&date.weekday().num_days_from_monday())
+                    .map(|(t, c)| (t / samples, c / samples))
+                    .unwrap_or((0.0, 0.0))
+            } else {
+                linear
+            };
+            (date, tokens, cost)
+        })
+        .collect()
+}
+
+/// 根据每日用量推算月底用量，并计算超出阈值的日期
+pub fn project(
+    history: &[DailyUsage],
+    today: NaiveDate,
+    method: ForecastMethod,
+    threshold_usd: Option<f64>,
+) -> Projection {
+    let month_start = today.with_day(1).unwrap_or(today);
+    let (mtd_tokens, mtd_cost) = history
+        .iter()
+        .filter(|d| d.date >= month_start && d.date <= today)
+        .fold((0u64, 0f64), |(t, c), d| (t + d.tokens, c + d.cost));
+
+    let threshold = threshold_usd.filter(|t| *t > 0.0);
+    let mut exceed_date = threshold.filter(|t| mtd_cost > *t).map(|_| today);
+    let mut tokens = mtd_tokens as f64;
+    let mut cost = mtd_cost;
+    let elapsed = today.day() as f64;
+    let linear = (tokens / elapsed, cost / elapsed);
+    for (date, day_tokens, day_cost) in remaining_days(history, today, method, linear) {
+        tokens += day_tokens;
+        cost += day_cost;
+        if exceed_date.is_none() && threshold.is_some_and(|t| cost > t) {
+            exceed_date = Some(date);
+        }
+    }
+
+    Projection {
+        month_to_date_tokens: mtd_tokens,
+        month_to_date_cost: mtd_cost,
+        projected_tokens: tokens.round() as u64,
+        projected_cost: cost,
+        exceed_date,
+    }
+}
+
+impl Database {
+    /// 每日用量（按本地日期与 Provider 分组）
+    fn get_daily_usage_since(
+        &self,
+        app_type: Option<&str>,
+        since: NaiveDate,
+    ) -> Result<Vec<(String, String, String, DailyUsage)>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn.prepare(
+            "SELECT date(datetime(l.created_at, 'unixepoch', 'localtime')) as day,
+                    l.app_type, l.provider_id, COALESCE(p.name, l.provider_id),
+                    COALESCE(SUM(l.input_tokens + l.output_tokens), 0),
+                    COALESCE(SUM(CAST(l.total_cost_usd AS REAL)), 0)
+             FROM proxy_request_logs l
+             LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
+             WHERE l.is_replay = 0
+               AND (?1 IS NULL OR l.app_type = ?1)
+               AND date(datetime(l.created_at, 'unixepoch', 'localtime')) >= ?2
+             GROUP BY day, l.app_type, l.provider_id",
+        )?;
+        let rows = stmt.query_map(
+            params![app_type, since.format("%Y-%m-%d").to_string()],
+            |row| {
+                let day: String = row.get(0)?;
+                Ok((
+                    day,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, i64>(4)?,
+                    row.get::<_, f64>(5)?,
+                ))
+            },
+        )?;
+
+        let mut usage = Vec::new();
+        for row in rows {
+            let (day, app, provider_id, name, tokens, cost) = row?;
+            let Ok(date) = NaiveDate::parse_from_str(&day, "%Y-%m-%d") else {
+                continue;
+            };
+            usage.push((
+                app,
+                provider_id,
+                name,
+                DailyUsage {
+                    date,
+                    tokens: tokens.max(0) as u64,
+                    cost,
+                },
+            ));
+        }
+        Ok(usage)
+    }
+
+    /// 供应商设置的月度限额（美元）
+    fn get_monthly_limit(
+        &self,
+        provider_id: &str,
+        app_type: &str,
+    ) -> Result<Option<f64>, AppError> {
+        let conn = lock_conn!(self.conn);
+        Ok(conn
+            .query_row(
+                "SELECT meta FROM providers WHERE id = ? AND app_type = ?",
+                params![provider_id, app_type],
+                |row| row.get::<_, String>(0),
+            )
+            .ok()
+            .and_then(|meta| serde_json::from_str::<serde_json::Value>(&meta).ok())
+            .and_then(|meta| {
+                meta.get("limitMonthlyUsd")
+                    .and_then(|v| v.as_str())
+                    .and_then(|s| s.parse::<f64>().ok())
+            }))
+    }
+
+    /// 各供应商本月月底的用量预测
+    pub fn get_usage_forecast(
+        &self,
+        app_type: Option<&str>,
+        method: ForecastMethod,
+        default_threshold_usd: f64,
+    ) -> Result<Vec<UsageForecast>, AppError> {
+        let today = Local::now().date_naive();
+        let month_start = today.with_day(1).unwrap_or(today);
+        let since = month_start.min(today - chrono::Duration::days(WEEKDAY_HISTORY_DAYS));
+
+        let mut grouped: HashMap<(String, String), (String, Vec<DailyUsage>)> = HashMap::new();
+        for (app, provider_id, name, usage) in self.get_daily_usage_since(app_type, since)? {
+            grouped
+                .entry((app, provider_id))
+                .or_insert_with(|| (name, Vec::new()))
+                .1
+                .push(usage);
+        }
+
+        let month = today.format("%Y-%m").to_string();
+        let mut forecasts = Vec::new();
+        for ((app, provider_id), (name, history)) in grouped {
+            // 本月没有用量的供应商不预测
+            if !history.iter().any(|d| d.date >= month_start) {
+                continue;
+            }
+            let threshold = self
+                .get_monthly_limit(&provider_id, &app)?
+                .or(Some(default_threshold_usd))
+                .filter(|t| *t > 0.0);
+            let projection = project(&history, today, method, threshold);
+            forecasts.push(UsageForecast {
+                app_type: app,
+                provider_id,
+                provider_name: name,
+                method,
+                month: month.clone(),
+                month_to_date_tokens: projection.month_to_date_tokens,
+                month_to_date_cost: format!("{:.6}", projection.month_to_date_cost),
+                projected_tokens: projection.projected_tokens,
+                projected_cost: format!("{:.6}", projection.projected_cost),
+                threshold_usd: threshold.map(|t| format!("{t:.2}")),
+                projected_exceed_date: projection
+                    .exceed_date
+                    .map(|d| d.format("%Y-%m-%d").to_string()),
+            });
+        }
+        forecasts.sort_by(|a, b| (&a.app_type, &a.provider_id).cmp(&(&b.app_type, &b.provider_id)));
+        Ok(forecasts)
+    }
+}
+
+/// 检查一次预测，对预计超出阈值的供应商发送告警（每个供应商每月一次）
+pub fn check_alerts(db: &Database) -> Result<usize, AppError> {
+    let config = crate::settings::get_settings().usage_forecast;
+    let forecasts = db.get_usage_forecast(None, config.method, config.threshold_usd)?;
+    let mut alerted = ALERTED.lock().unwrap_or_else(|e| e.into_inner());
+    let mut sent = 0;
+    for forecast in forecasts {
+        let Some(date) = forecast.projected_exceed_date.as_deref() else {
+            continue;
+        };
+        let key = (
+            forecast.app_type.clone(),
+            forecast.provider_id.clone(),
+            forecast.month.clone(),
+        );
+        if !alerted.insert(key) {
+            continue;
+        }
+        log::warn!(
+            "[Forecast] {}/{} 预计在 {date} 超出 ${}（月底预计 ${}）",
+            forecast.app_type,
+            forecast.provider_id,
+            forecast.threshold_usd.as_deref().unwrap_or("-"),
+            forecast.projected_cost
+        );
+        events::publish(AppEvent::UsageForecastWarning(forecast));
+        sent += 1;
+    }
+    Ok(sent)
+}
+
+/// 后台定期检查预测告警（设置中关闭时跳过）
+pub async fn run(db: Arc<Database>) {
+    loop {
+        if crate::settings::get_settings().usage_forecast.alert_enabled
+            && startup_profile::is_enabled(Subsystem::Alerting)
+        {
+            let db = db.clone();
+            match tokio::task::spawn_blocking(move || check_alerts(&db)).await {
+                Ok(Err(e)) => log::warn!("[Forecast] 检查用量预测失败: {e}"),
+                Err(e) => log::warn!("[Forecast] 检查任务异常: {e}"),
+                Ok(Ok(_)) => {}
+            }
+        }
+        tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(y: i32, m: u32, d: u32, cost: f64) -> DailyUsage {
+        DailyUsage {
+            date: NaiveDate::from_ymd_opt(y, m, d).unwrap(),
+            tokens: (cost * 1000.0) as u64,
+            cost,
+        }
+    }
+
+    #[test]
+    fn linear_projection_and_exceed_date() {
+        // 10 天每天 $2，本月 30 天 → 月底 $60，第 26 天超过 $50
+        let history: Vec<_> = (1..=10).map(|d| day(2026, 6, d, 2.0)).collect();
+        let today = NaiveDate::from_ymd_opt(2026, 6, 10).unwrap();
+        let p = project(&history, today, ForecastMethod::Linear, Some(50.0));
+        assert!((p.month_to_date_cost - 20.0).abs() < 1e-9);
+        assert!((p.projected_cost - 60.0).abs() < 1e-9);
+        assert_eq!(p.projected_tokens, 60_000);
+        assert_eq!(p.exceed_date, NaiveDate::from_ymd_opt(2026, 6, 26));
+
+        let p = project(&history, today, ForecastMethod::Linear, Some(100.0));
+        assert!(p.exceed_date.is_none());
+    }
+
+    #[test]
+    fn weekday_projection_skips_quiet_days() {
+        // 2026-06-01 是周一：工作日每天 $5，周末为 0
+        let history: Vec<_> = (1..=28)
+            .map(|d| {
+                let date = NaiveDate::from_ymd_opt(2026, 6, d).unwrap();
+                let weekend = date.weekday().num_days_from_monday() >= 5;
+                day(2026, 6, d, if weekend { 0.0 } else { 5.0 })
+            })
+            .collect();
+        let today = NaiveDate::from_ymd_opt(2026, 6, 28).unwrap();
+        let p = project(&history, today, ForecastMethod::Weekday, None);
+        // 剩余 29（周一）、30（周二）两个工作日
+        assert!((p.projected_cost - (100.0 + 10.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weekday_falls_back_to_linear_without_history() {
+        let history = vec![day(2026, 2, 1, 3.0), day(2026, 2, 2, 3.0)];
+        let today = NaiveDate::from_ymd_opt(2026, 2, 2).unwrap();
+        let p = project(&history, today, ForecastMethod::Weekday, None);
+        assert!((p.projected_cost - 84.0).abs() < 1e-9);
+        assert_eq!(days_in_month(today), 28);
+    }
+}
@@ -72,6 +72,8 @@ pub struct LogFilters {
     pub status_code: Option<u16>,
     pub start_date: Option<i64>,
     pub end_date: Option<i64>,
+    /// 请求标签（精确匹配）
+    pub tag: Option<String>,
 }
 
 /// 分页请求日志响应
@@ -110,6 +112,96 @@ pub struct RequestLogDetail {
     pub status_code: u16,
     pub error_message: Option<String>,
     pub created_at: i64,
+    /// 流式输出速度（token/秒）
+    pub output_tps: Option<f64>,
+    /// 实际发送的客户端身份（User-Agent 等，JSON 对象）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_identity: Option<String>,
+    /// 发往上游的字节数（请求体）
+    #[serde(default)]
+    pub request_bytes: u64,
+    /// 从上游接收的字节数（响应体）
+    #[serde(default)]
+    pub response_bytes: u64,
+    /// 客户端通过 `X-CCSwitch-Tag` 请求头指定的标签
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+}
+
+/// 按请求标签汇总的统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagStats {
+    /// 标签（未打标签的请求为空）
+    pub tag: Option<String>,
+    pub request_count: u64,
+    pub success_count: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub total_cost: String,
+    pub avg_latency_ms: u64,
+}
+
+/// 每日流量统计（按 Provider）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyBandwidth {
+    /// 本地日期（YYYY-MM-DD）
+    pub date: String,
+    pub provider_id: String,
+    pub provider_name: String,
+    pub app_type: String,
+    pub request_count: u64,
+    /// 发往上游的字节数（请求体）
+    pub request_bytes: u64,
+    /// 从上游接收的字节数（响应体）
+    pub response_bytes: u64,
+    /// 其中流式响应接收的字节数
+    pub streaming_response_bytes: u64,
+}
+
+/// 延迟热力图单元（按 Provider、星期几与小时分组）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyHeatmapCell {
+    pub provider_id: String,
+    pub provider_name: String,
+    pub app_type: String,
+    /// 星期几（0 = 周一 … 6 = 周日）
+    pub weekday: u8,
+    /// 小时（0~23）
+    pub hour: u8,
+    pub request_count: u64,
+    pub error_count: u64,
+    /// 错误率（0~1）
+    pub error_rate: f64,
+    pub avg_latency_ms: u64,
+}
+
+/// 流式输出速度分位统计（按 Provider）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamingSpeedStats {
+    pub provider_id: String,
+    pub provider_name: String,
+    pub app_type: String,
+    pub sample_count: u64,
+    pub avg_tps: f64,
+    pub p50_tps: f64,
+    pub p90_tps: f64,
+    pub p99_tps: f64,
+    pub min_tps: f64,
+    pub max_tps: f64,
+}
+
+/// 按应用类型汇总的用量（总览面板使用）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppUsageTotals {
+    pub request_count: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub total_cost: String,
 }
 
 impl Database {
@@ -121,23 +213,18 @@ impl Database {
     ) -> Result<UsageSummary, AppError> {
         let conn = lock_conn!(self.conn);
 
-        let (where_clause, params_vec) = if start_date.is_some() || end_date.is_some() {
-            let mut conditions = Vec::new();
-            let mut params = Vec::new();
-
-            if let Some(start) = start_date {
-                conditions.push("created_at >= ?");
-                params.push(start);
-            }
-            if let Some(end) = end_date {
-                conditions.push("created_at <= ?");
-                params.push(end);
-            }
-
-            (format!("WHERE {}", conditions.join(" AND ")), params)
-        } else {
-            (String::new(), Vec::new())
-        };
+        // 回放请求不计入统计
+        let mut conditions = vec!["is_replay = 0"];
+        let mut params_vec = Vec::new();
+        if let Some(start) = start_date {
+            conditions.push("created_at >= ?");
+            params_vec.push(start);
+        }
+        if let Some(end) = end_date {
+            conditions.push("created_at <= ?");
+            params_vec.push(end);
+        }
+        let where_clause = format!("WHERE {}", conditions.join(" AND "));
 
         let sql = format!(
             "SELECT 
@@ -228,7 +315,7 @@ impl Database {
                 COALESCE(SUM(cache_creation_tokens), 0) as total_cache_creation_tokens,
                 COALESCE(SUM(cache_read_tokens), 0) as total_cache_read_tokens
             FROM proxy_request_logs
-            WHERE created_at >= ?1 AND created_at <= ?2
+            WHERE created_at >= ?1 AND created_at <= ?2 AND is_replay = 0
             GROUP BY bucket_idx
             ORDER BY bucket_idx ASC";
 
@@ -305,6 +392,7 @@ impl Database {
                 COALESCE(AVG(l.latency_ms), 0) as avg_latency
              FROM proxy_request_logs l
              LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
+             WHERE l.is_replay = 0
              GROUP BY l.provider_id, l.app_type
              ORDER BY total_cost DESC";
 
@@ -339,6 +427,43 @@ impl Database {
         Ok(stats)
     }
 
+    /// 按应用类型汇总 `since`（秒级时间戳）之后的用量
+    pub fn get_usage_by_app_since(
+        &self,
+        since: i64,
+    ) -> Result<HashMap<String, AppUsageTotals>, AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let mut stmt = conn.prepare(
+            "SELECT app_type,
+                COUNT(*),
+                COALESCE(SUM(input_tokens), 0),
+                COALESCE(SUM(output_tokens), 0),
+                COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0)
+             FROM proxy_request_logs
+             WHERE created_at >= ?1 AND is_replay = 0
+             GROUP BY app_type",
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                AppUsageTotals {
+                    request_count: row.get::<_, i64>(1)? as u64,
+                    input_tokens: row.get::<_, i64>(2)? as u64,
+                    output_tokens: row.get::<_, i64>(3)? as u64,
+                    total_cost: format!("{:.6}", row.get::<_, f64>(4)?),
+                },
+            ))
+        })?;
+
+        let mut totals = HashMap::new();
+        for row in rows {
+            let (app_type, usage) = row?;
+            totals.insert(app_type, usage);
+        }
+        Ok(totals)
+    }
+
     /// 获取模型统计
     pub fn get_model_stats(&self) -> Result<Vec<ModelStats>, AppError> {
         let conn = lock_conn!(self.conn);
@@ -349,6 +474,7 @@ impl Database {
                 COALESCE(SUM(input_tokens + output_tokens), 0) as total_tokens,
                 COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0) as total_cost
              FROM proxy_request_logs
+             WHERE is_replay = 0
              GROUP BY model
              ORDER BY total_cost DESC";
 
@@ -415,6 +541,10 @@ impl Database {
             conditions.push("l.created_at <= ?");
             params.push(Box::new(end));
         }
+        if let Some(ref tag) = filters.tag {
+            conditions.push("l.tag = ?");
+            params.push(Box::new(tag.clone()));
+        }
 
         let where_clause = if conditions.is_empty() {
             String::new()
@@ -443,7 +573,8 @@ impl Database {
                     l.input_tokens, l.output_tokens, l.cache_read_tokens, l.cache_creation_tokens,
                     l.input_cost_usd, l.output_cost_usd, l.cache_read_cost_usd, l.cache_creation_cost_usd, l.total_cost_usd,
                     l.is_streaming, l.latency_ms, l.first_token_ms, l.duration_ms,
-                    l.status_code, l.error_message, l.created_at
+                    l.status_code, l.error_message, l.created_at, l.output_tps, l.client_identity,
+                    l.request_bytes, l.response_bytes, l.tag
              FROM proxy_request_logs l
              LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
              {where_clause}
@@ -476,6 +607,11 @@ impl Database {
                 status_code: row.get::<_, i64>(18)? as u16,
                 error_message: row.get(19)?,
                 created_at: row.get(20)?,
+                output_tps: row.get(21)?,
+                client_identity: row.get(22)?,
+                request_bytes: row.get::<_, i64>(23)? as u64,
+                response_bytes: row.get::<_, i64>(24)? as u64,
+                tag: row.get(25)?,
             })
         })?;
 
@@ -514,7 +650,8 @@ impl Database {
                     input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens,
                     input_cost_usd, output_cost_usd, cache_read_cost_usd, cache_creation_cost_usd, total_cost_usd,
                     is_streaming, latency_ms, first_token_ms, duration_ms,
-                    status_code, error_message, created_at
+                    status_code, error_message, created_at, output_tps, client_identity,
+                    request_bytes, response_bytes, tag
              FROM proxy_request_logs l
              LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
              WHERE l.request_id = ?",
@@ -542,6 +679,11 @@ impl Database {
                     status_code: row.get::<_, i64>(18)? as u16,
                     error_message: row.get(19)?,
                     created_at: row.get(20)?,
+                    output_tps: row.get(21)?,
+                    client_identity: row.get(22)?,
+                    request_bytes: row.get::<_, i64>(23)? as u64,
+                    response_bytes: row.get::<_, i64>(24)? as u64,
+                    tag: row.get(25)?,
                 })
             },
         );
@@ -559,8 +701,203 @@ impl Database {
                 Ok(Some(detail))
             }
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(AppError::Database(e.to_string())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 获取流式输出速度分位统计（按 Provider 分组）
+    pub fn get_streaming_speed_stats(
+        &self,
+        app_type: Option<&str>,
+        start_date: Option<i64>,
+        end_date: Option<i64>,
+    ) -> Result<Vec<StreamingSpeedStats>, AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let mut stmt = conn.prepare(
+            "SELECT l.provider_id, COALESCE(p.name, l.provider_id), l.app_type, l.output_tps
+             FROM proxy_request_logs l
+             LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
+             WHERE l.output_tps IS NOT NULL AND l.is_replay = 0
+               AND (?1 IS NULL OR l.app_type = ?1)
+               AND (?2 IS NULL OR l.created_at >= ?2)
+               AND (?3 IS NULL OR l.created_at <= ?3)
+             ORDER BY l.app_type, l.provider_id, l.output_tps",
+        )?;
+
+        let rows = stmt.query_map(params![app_type, start_date, end_date], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, f64>(3)?,
+            ))
+        })?;
+
+        // 已按 (app_type, provider_id, output_tps) 排序，按组切分即可
+        let mut stats = Vec::new();
+        let mut current: Option<(String, String, String)> = None;
+        let mut values: Vec<f64> = Vec::new();
+
+        for row in rows {
+            let (provider_id, provider_name, app, tps) = row?;
+            let same_group = current
+                .as_ref()
+                .map(|(id, _, a)| *id == provider_id && *a == app)
+                .unwrap_or(false);
+            if !same_group {
+                if let Some((id, name, a)) = current.take() {
+                    stats.push(build_speed_stats(id, name, a, &values));
+                }
+                current = Some((provider_id, provider_name, app));
+                values.clear();
+            }
+            values.push(tps);
         }
+        if let Some((id, name, a)) = current.take() {
+            stats.push(build_speed_stats(id, name, a, &values));
+        }
+
+        Ok(stats)
+    }
+
+    /// 获取每日流量统计（按本地日期与 Provider 分组）
+    pub fn get_bandwidth_usage(
+        &self,
+        app_type: Option<&str>,
+        start_date: Option<i64>,
+        end_date: Option<i64>,
+    ) -> Result<Vec<DailyBandwidth>, AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let mut stmt = conn.prepare(
+            "SELECT date(datetime(l.created_at, 'unixepoch', 'localtime')) as day,
+                    l.provider_id, COALESCE(p.name, l.provider_id), l.app_type,
+                    COUNT(*),
+                    COALESCE(SUM(l.request_bytes), 0),
+                    COALESCE(SUM(l.response_bytes), 0),
+                    COALESCE(SUM(CASE WHEN l.is_streaming = 1 THEN l.response_bytes ELSE 0 END), 0)
+             FROM proxy_request_logs l
+             LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
+             WHERE l.is_replay = 0
+               AND (?1 IS NULL OR l.app_type = ?1)
+               AND (?2 IS NULL OR l.created_at >= ?2)
+               AND (?3 IS NULL OR l.created_at <= ?3)
+             GROUP BY day, l.app_type, l.provider_id
+             ORDER BY day DESC, l.app_type, l.provider_id",
+        )?;
+
+        let rows = stmt.query_map(params![app_type, start_date, end_date], |row| {
+            Ok(DailyBandwidth {
+                date: row.get(0)?,
+                provider_id: row.get(1)?,
+                provider_name: row.get(2)?,
+                app_type: row.get(3)?,
+                request_count: row.get::<_, i64>(4)? as u64,
+                request_bytes: row.get::<_, i64>(5)? as u64,
+                response_bytes: row.get::<_, i64>(6)? as u64,
+                streaming_response_bytes: row.get::<_, i64>(7)? as u64,
+            })
+        })?;
+
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+
+    /// 按请求标签汇总用量（未打标签的请求归为一组）
+    pub fn get_tag_stats(
+        &self,
+        app_type: Option<&str>,
+        start_date: Option<i64>,
+        end_date: Option<i64>,
+    ) -> Result<Vec<TagStats>, AppError> {
+        let conn = lock_conn!(self.conn);
+
+        let mut stmt = conn.prepare(
+            "SELECT tag,
+                    COUNT(*),
+                    COALESCE(SUM(CASE WHEN status_code >= 200 AND status_code < 300 THEN 1 ELSE 0 END), 0),
+                    COALESCE(SUM(input_tokens), 0),
+                    COALESCE(SUM(output_tokens), 0),
+                    COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0),
+                    COALESCE(AVG(latency_ms), 0)
+             FROM proxy_request_logs
+             WHERE is_replay = 0
+               AND (?1 IS NULL OR app_type = ?1)
+               AND (?2 IS NULL OR created_at >= ?2)
+               AND (?3 IS NULL OR created_at <= ?3)
+             GROUP BY tag
+             ORDER BY COUNT(*) DESC, tag",
+        )?;
+
+        let rows = stmt.query_map(params![app_type, start_date, end_date], |row| {
+            Ok(TagStats {
+                tag: row.get(0)?,
+                request_count: row.get::<_, i64>(1)? as u64,
+                success_count: row.get::<_, i64>(2)? as u64,
+                input_tokens: row.get::<_, i64>(3)? as u64,
+                output_tokens: row.get::<_, i64>(4)? as u64,
+                total_cost: format!("{:.6}", row.get::<_, f64>(5)?),
+                avg_latency_ms: row.get::<_, f64>(6)? as u64,
+            })
+        })?;
+
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+    }
+
+    /// 获取最近 `weeks` 周的延迟热力图（按星期几 × 小时分组）
+    ///
+    /// 默认按本地时间分组；指定 `utc_offset_minutes` 时按该时区分组（如北京时间为 480）。
+    pub fn get_latency_heatmap(
+        &self,
+        app_type: Option<&str>,
+        provider_id: Option<&str>,
+        weeks: u32,
+        utc_offset_minutes: Option<i32>,
+    ) -> Result<Vec<LatencyHeatmapCell>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let since = chrono::Utc::now().timestamp() - weeks.clamp(1, 52) as i64 * 7 * 86_400;
+        let modifier = match utc_offset_minutes {
+            Some(minutes) => format!("{minutes:+} minutes"),
+            None => "localtime".to_string(),
+        };
+
+        let mut stmt = conn.prepare(
+            "SELECT l.provider_id, COALESCE(p.name, l.provider_id), l.app_type,
+                    (CAST(strftime('%w', datetime(l.created_at, 'unixepoch', ?1)) AS INTEGER) + 6) % 7 as weekday,
+                    CAST(strftime('%H', datetime(l.created_at, 'unixepoch', ?1)) AS INTEGER) as hour,
+                    COUNT(*),
+                    COALESCE(SUM(CASE WHEN l.status_code >= 200 AND l.status_code < 300 THEN 0 ELSE 1 END), 0),
+                    COALESCE(AVG(l.latency_ms), 0)
+             FROM proxy_request_logs l
+             LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
+             WHERE l.created_at >= ?2 AND l.is_replay = 0
+               AND (?3 IS NULL OR l.app_type = ?3)
+               AND (?4 IS NULL OR l.provider_id = ?4)
+             GROUP BY l.app_type, l.provider_id, weekday, hour
+             ORDER BY l.app_type, l.provider_id, weekday, hour",
+        )?;
+
+        let rows = stmt.query_map(params![modifier, since, app_type, provider_id], |row| {
+            let request_count = row.get::<_, i64>(5)? as u64;
+            let error_count = row.get::<_, i64>(6)? as u64;
+            Ok(LatencyHeatmapCell {
+                provider_id: row.get(0)?,
+                provider_name: row.get(1)?,
+                app_type: row.get(2)?,
+                weekday: row.get::<_, i64>(3)? as u8,
+                hour: row.get::<_, i64>(4)? as u8,
+                request_count,
+                error_count,
+                error_rate: if request_count > 0 {
+                    error_count as f64 / request_count as f64
+                } else {
+                    0.0
+                },
+                avg_latency_ms: row.get::<_, f64>(7)? as u64,
+            })
+        })?;
+
+        rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
     }
 
     /// 检查 Provider 使用限额
@@ -596,7 +933,7 @@ impl Database {
             })
             .unwrap_or((None, None));
 
-        // 计算今日使用量
+        // 计算今日使用量（回放真实供应商同样产生费用，计入限额）
         let daily_usage: f64 = conn
             .query_row(
                 "SELECT COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0)
@@ -798,6 +1135,41 @@ impl Database {
     }
 }
 
+/// 最近秩法取分位值（输入需已升序排列）
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn build_speed_stats(
+    provider_id: String,
+    provider_name: String,
+    app_type: String,
+    sorted: &[f64],
+) -> StreamingSpeedStats {
+    let count = sorted.len();
+    let avg = if count > 0 {
+        sorted.iter().sum::<f64>() / count as f64
+    } else {
+        0.0
+    };
+    StreamingSpeedStats {
+        provider_id,
+        provider_name,
+        app_type,
+        sample_count: count as u64,
+        avg_tps: avg,
+        p50_tps: percentile(sorted, 50.0),
+        p90_tps: percentile(sorted, 90.0),
+        p99_tps: percentile(sorted, 99.0),
+        min_tps: sorted.first().copied().unwrap_or(0.0),
+        max_tps: sorted.last().copied().unwrap_or(0.0),
+    }
+}
+
 pub(crate) fn find_model_pricing_row(
     conn: &Connection,
     model_id: &str,
@@ -867,6 +1239,15 @@ mod tests {
                 ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
                 params!["req2", "p1", "claude", "claude-3", 200, 100, "0.02", 150, 200, 2000],
             )?;
+            // 回放请求不计入统计
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model,
+                    input_tokens, output_tokens, total_cost_usd,
+                    latency_ms, status_code, created_at, is_replay
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 1)",
+                params!["req3", "p1", "claude", "claude-3", 200, 100, "0.02", 150, 500, 3000],
+            )?;
         }
 
         let summary = db.get_usage_summary(None, None)?;
@@ -876,6 +1257,199 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn provider_limits_include_replay_cost() -> Result<(), AppError> {
+        let db = Database::memory()?;
+
+        {
+            let conn = lock_conn!(db.conn);
+            let now = Local::now().timestamp();
+            for (id, is_replay) in [("req1", 0), ("req2", 1)] {
+                conn.execute(
+                    "INSERT INTO proxy_request_logs (
+                        request_id, provider_id, app_type, model, total_cost_usd,
+                        latency_ms, status_code, created_at, is_replay
+                    ) VALUES (?, 'p1', 'claude', 'claude-3', '0.5', 100, 200, ?, ?)",
+                    params![id, now, is_replay],
+                )?;
+            }
+        }
+
+        let status = db.check_provider_limits("p1", "claude")?;
+        assert_eq!(status.daily_usage, "1.000000");
+        assert_eq!(status.monthly_usage, "1.000000");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_streaming_speed_stats() -> Result<(), AppError> {
+        let db = Database::memory()?;
+
+        {
+            let conn = lock_conn!(db.conn);
+            for (i, tps) in [8.0, 10.0, 12.0, 60.0].iter().enumerate() {
+                conn.execute(
+                    "INSERT INTO proxy_request_logs (
+                        request_id, provider_id, app_type, model, output_tokens,
+                        latency_ms, status_code, is_streaming, output_tps, created_at
+                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                    params![
+                        format!("req{i}"),
+                        "p1",
+                        "claude",
+                        "claude-3",
+                        100,
+                        1000,
+                        200,
+                        1,
+                        tps,
+                        1000
+                    ],
+                )?;
+            }
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model, output_tokens,
+                    latency_ms, status_code, is_streaming, output_tps, created_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                params!["req-p2", "p2", "claude", "claude-3", 100, 1000, 200, 1, 45.0, 1000],
+            )?;
+        }
+
+        let stats = db.get_streaming_speed_stats(Some("claude"), None, None)?;
+        assert_eq!(stats.len(), 2);
+        let p1 = stats.iter().find(|s| s.provider_id == "p1").unwrap();
+        assert_eq!(p1.sample_count, 4);
+        assert_eq!(p1.p50_tps, 10.0);
+        assert_eq!(p1.p90_tps, 60.0);
+        assert_eq!(p1.min_tps, 8.0);
+        assert!((p1.avg_tps - 22.5).abs() < 1e-9);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_bandwidth_usage() -> Result<(), AppError> {
+        let db = Database::memory()?;
+
+        {
+            let conn = lock_conn!(db.conn);
+            let now = Local::now().timestamp();
+            for (id, provider, streaming, sent, received) in [
+                ("req1", "p1", 1, 2_000, 50_000),
+                ("req2", "p1", 0, 1_000, 3_000),
+                ("req3", "p2", 1, 500, 8_000),
+            ] {
+                conn.execute(
+                    "INSERT INTO proxy_request_logs (
+                        request_id, provider_id, app_type, model, latency_ms, status_code,
+                        is_streaming, request_bytes, response_bytes, created_at
+                    ) VALUES (?, ?, 'claude', 'claude-3', 100, 200, ?, ?, ?, ?)",
+                    params![id, provider, streaming, sent, received, now],
+                )?;
+            }
+        }
+
+        let usage = db.get_bandwidth_usage(Some("claude"), None, None)?;
+        assert_eq!(usage.len(), 2);
+        let p1 = usage.iter().find(|u| u.provider_id == "p1").unwrap();
+        assert_eq!(p1.request_count, 2);
+        assert_eq!(p1.request_bytes, 3_000);
+        assert_eq!(p1.response_bytes, 53_000);
+        assert_eq!(p1.streaming_response_bytes, 50_000);
+        assert_eq!(p1.date, Local::now().format("%Y-%m-%d").to_string());
+        assert!(db
+            .get_bandwidth_usage(Some("codex"), None, None)?
+            .is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_tag_stats() -> Result<(), AppError> {
+        let db = Database::memory()?;
+
+        {
+            let conn = lock_conn!(db.conn);
+            for (id, tag, status, cost) in [
+                ("req1", Some("billing"), 200, "0.5"),
+                ("req2", Some("billing"), 502, "0"),
+                ("req3", None, 200, "0.25"),
+            ] {
+                conn.execute(
+                    "INSERT INTO proxy_request_logs (
+                        request_id, provider_id, app_type, model, input_tokens, output_tokens,
+                        total_cost_usd, latency_ms, status_code, tag, created_at
+                    ) VALUES (?, 'p1', 'claude', 'claude-3', 100, 50, ?, 200, ?, ?, 1000)",
+                    params![id, cost, status, tag],
+                )?;
+            }
+        }
+
+        let stats = db.get_tag_stats(None, None, None)?;
+        assert_eq!(stats.len(), 2);
+        let billing = &stats[0];
+        assert_eq!(billing.tag.as_deref(), Some("billing"));
+        assert_eq!(billing.request_count, 2);
+        assert_eq!(billing.success_count, 1);
+        assert_eq!(billing.input_tokens, 200);
+        assert_eq!(billing.total_cost, "0.500000");
+        assert_eq!(stats[1].tag, None);
+
+        let filters = LogFilters {
+            tag: Some("billing".to_string()),
+            ..Default::default()
+        };
+        let logs = db.get_request_logs(&filters, 0, 10)?;
+        assert_eq!(logs.total, 2);
+        assert_eq!(logs.data[0].tag.as_deref(), Some("billing"));
+        assert!(db.get_tag_stats(None, Some(2000), None)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_latency_heatmap() -> Result<(), AppError> {
+        let db = Database::memory()?;
+
+        // 2026-06-01 13:00 UTC 是周一，北京时间 21:00
+        let monday_13_utc = 1_780_318_800;
+        let now = chrono::Utc::now().timestamp();
+        let recent = monday_13_utc + (now - monday_13_utc) / (7 * 86_400) * 7 * 86_400;
+        {
+            let conn = lock_conn!(db.conn);
+            for (id, latency, status) in [("req1", 900, 200), ("req2", 3_100, 502)] {
+                conn.execute(
+                    "INSERT INTO proxy_request_logs (
+                        request_id, provider_id, app_type, model, latency_ms, status_code, created_at
+                    ) VALUES (?, 'p1', 'claude', 'claude-3', ?, ?, ?)",
+                    params![id, latency, status, recent],
+                )?;
+            }
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model, latency_ms, status_code, created_at
+                ) VALUES ('old', 'p1', 'claude', 'claude-3', 100, 200, ?)",
+                params![recent - 10 * 7 * 86_400],
+            )?;
+        }
+
+        let cells = db.get_latency_heatmap(Some("claude"), None, 4, Some(480))?;
+        assert_eq!(cells.len(), 1);
+        assert_eq!((cells[0].weekday, cells[0].hour), (0, 21));
+        assert_eq!(cells[0].request_count, 2);
+        assert_eq!(cells[0].error_count, 1);
+        assert!((cells[0].error_rate - 0.5).abs() < 1e-9);
+        assert_eq!(cells[0].avg_latency_ms, 2_000);
+
+        let utc = db.get_latency_heatmap(None, Some("p1"), 4, Some(0))?;
+        assert_eq!(utc[0].hour, 13);
+        assert_eq!(db.get_latency_heatmap(None, None, 12, Some(0))?.len(), 1);
+
+        Ok(())
+    }
+
     #[test]
     fn test_get_model_stats() -> Result<(), AppError> {
         let db = Database::memory()?;
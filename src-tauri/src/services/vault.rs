@@ -0,0 +1,583 @@
+//! 供应商加密备注与附件
+//!
+//! 备注与小文件使用 AES-256-GCM 加密后存入数据库。主密钥保存在系统钥匙串中
+//! （macOS Keychain / Linux Secret Service），每次读写都重新从钥匙串获取，
+//! 因此解密需要经过系统的钥匙串授权。钥匙串不可用时（如 Windows）无法使用附件功能；
+//! 早期版本写入的 `~/.cc-switch/vault.key` 仍可读取。
+//!
+//! 云厂商供应商（Bedrock / Vertex）的密钥也直接保存在钥匙串中，ProviderMeta 里只保留引用。
+//! 钥匙串不可用时（Windows、未安装 `secret-tool` 的 Linux），改为加密后写入配置目录下的
+//! `cloud-credentials.json`，密钥文件 `cloud-credentials.key` 仅所有者可读；这样导出或同步的
+//! 数据库中仍不含明文密钥，但保护强度不及钥匙串。
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+#[cfg(not(target_os = "macos"))]
+use std::process::{Command, Stdio};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use once_cell::sync::Lazy;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+#[cfg(target_os = "macos")]
+use security_framework::passwords;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+use crate::app_config::AppType;
+use crate::database::{VaultItem, VaultItemKind};
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::proxy::providers::CloudSecrets;
+use crate::store::AppState;
+
+/// 单个附件的大小上限（明文）
+pub const MAX_ITEM_BYTES: usize = 1024 * 1024;
+
+const KEYCHAIN_SERVICE: &str = "cc-switch";
+pub(crate) const KEYCHAIN_ACCOUNT: &str = "provider-vault";
+const AAD: &[u8] = b"cc-switch-vault-v1";
+
+/// 云厂商凭据在钥匙串中的账户名前缀
+const CLOUD_ACCOUNT_PREFIX: &str = "cloud-credentials";
+
+/// 钥匙串不可用时的云厂商凭据存储（账户名 -> 加密后的凭据）
+const CLOUD_FALLBACK_FILE: &str = "cloud-credentials.json";
+const CLOUD_FALLBACK_KEY_FILE: &str = "cloud-credentials.key";
+const CLOUD_AAD: &str = "cc-switch-cloud-credentials-v1";
+
+/// 已读取的云厂商凭据（引用 -> 密钥），避免每次转发都访问钥匙串
+static CLOUD_SECRETS: Lazy<Mutex<HashMap<String, CloudSecrets>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 解密后的附件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultItemContent {
+    #[serde(flatten)]
+    pub item: VaultItem,
+    /// 备注为原文，文件为 base64
+    pub content: String,
+}
+
+fn key_file_path() -> PathBuf {
+    crate::config::get_app_config_dir().join("vault.key")
+}
+
+fn non_empty(value: String) -> Option<String> {
+    let value = value.trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+/// 从系统钥匙串读取指定账户的密钥
+#[cfg(target_os = "macos")]
+pub(crate) fn keychain_get(account: &str) -> Option<String> {
+    let bytes = passwords::get_generic_password(KEYCHAIN_SERVICE, account).ok()?;
+    non_empty(String::from_utf8(bytes).ok()?)
+}
+
+/// 从系统钥匙串读取指定账户的密钥
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn keychain_get(account: &str) -> Option<String> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let output = Command::new("secret-tool")
+        .args(["lookup", "service", KEYCHAIN_SERVICE])
+        .args(["account", account])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    non_empty(String::from_utf8(output.stdout).ok()?)
+}
+
+/// 将密钥写入系统钥匙串，钥匙串不可用时返回 false
+///
+/// 密钥不经过命令行参数传递（其他进程可通过进程列表看到参数）
+#[cfg(target_os = "macos")]
+pub(crate) fn keychain_set(account: &str, _label: &str, key: &str) -> bool {
+    passwords::set_generic_password(KEYCHAIN_SERVICE, account, key.as_bytes()).is_ok()
+}
+
+/// 将密钥写入系统钥匙串，钥匙串不可用时返回 false
+///
+/// 密钥不经过命令行参数传递（其他进程可通过进程列表看到参数）
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn keychain_set(account: &str, label: &str, key: &str) -> bool {
+    if !cfg!(target_os = "linux") {
+        return false;
+    }
+    // secret-tool 从 stdin 读取密钥
+    let Ok(mut child) = Command::new("secret-tool")
+        .arg("store")
+        .arg(format!("--label={label}"))
+        .args(["service", KEYCHAIN_SERVICE, "account", account])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return false;
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        use std::io::Write;
+        if stdin.write_all(key.as_bytes()).is_err() {
+            return false;
+        }
+    }
+    child.wait().map(|s| s.success()).unwrap_or(false)
+}
+
+/// 从系统钥匙串删除指定账户的密钥
+#[cfg(target_os = "macos")]
+pub(crate) fn keychain_delete(account: &str) -> bool {
+    passwords::delete_generic_password(KEYCHAIN_SERVICE, account).is_ok()
+}
+
+/// 从系统钥匙串删除指定账户的密钥
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn keychain_delete(account: &str) -> bool {
+    if !cfg!(target_os = "linux") {
+        return false;
+    }
+    Command::new("secret-tool")
+        .args(["clear", "service", KEYCHAIN_SERVICE])
+        .args(["account", account])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// 将供应商 meta 中明文填写的云厂商密钥移入钥匙串，meta 中只保留引用
+///
+/// 只修改了其中一项密钥时，与已保存的其余密钥合并。
+pub fn store_cloud_credentials(
+    app_type: &AppType,
+    provider: &mut Provider,
+) -> Result<(), AppError> {
+    let Some(cloud) = provider.meta.as_mut().and_then(|m| m.cloud.as_mut()) else {
+        return Ok(());
+    };
+    let Some(secrets) = cloud.take_secrets() else {
+        return Ok(());
+    };
+
+    let mut stored = cloud
+        .credential_ref()
+        .and_then(|reference| cloud_secrets(reference).ok())
+        .unwrap_or_default();
+    stored.merge(secrets);
+
+    let account = format!(
+        "{CLOUD_ACCOUNT_PREFIX}:{}:{}",
+        app_type.as_str(),
+        provider.id
+    );
+    let encoded =
+        serde_json::to_string(&stored).map_err(|e| AppError::JsonSerialize { source: e })?;
+    if keychain_set(&account, "CC Switch cloud provider credentials", &encoded) {
+        // 之前可能因钥匙串不可用写入过文件
+        if let Err(e) = fallback_delete(&crate::config::get_app_config_dir(), &account) {
+            log::warn!("[Vault] 清理文件中的云厂商凭据失败: {e}");
+        }
+    } else {
+        log::info!("[Vault] 钥匙串不可用，云厂商凭据加密保存到配置目录");
+        fallback_store(&crate::config::get_app_config_dir(), &account, &stored)?;
+    }
+    CLOUD_SECRETS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(account.clone(), stored);
+    cloud.set_credential_ref(account);
+    Ok(())
+}
+
+/// 读取引用对应的云厂商密钥
+pub fn cloud_secrets(reference: &str) -> Result<CloudSecrets, AppError> {
+    if let Some(secrets) = CLOUD_SECRETS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(reference)
+    {
+        return Ok(secrets.clone());
+    }
+    let secrets: CloudSecrets = keychain_get(reference)
+        .and_then(|encoded| serde_json::from_str(&encoded).ok())
+        .or_else(|| fallback_get(&crate::config::get_app_config_dir(), reference))
+        .ok_or_else(|| {
+            AppError::localized(
+                "vault.cloud_credentials_missing",
+                "找不到已保存的云厂商凭据，请重新填写密钥",
+                "The saved cloud provider credentials were not found; please enter them again",
+            )
+        })?;
+    CLOUD_SECRETS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(reference.to_string(), secrets.clone());
+    Ok(secrets)
+}
+
+/// 删除供应商引用的云厂商密钥
+pub fn delete_cloud_credentials(provider: &Provider) {
+    let reference = provider
+        .meta
+        .as_ref()
+        .and_then(|m| m.cloud.as_ref())
+        .and_then(|c| c.credential_ref());
+    if let Some(reference) = reference {
+        CLOUD_SECRETS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(reference);
+        keychain_delete(reference);
+        if let Err(e) = fallback_delete(&crate::config::get_app_config_dir(), reference) {
+            log::warn!("[Vault] 删除云厂商凭据失败: {e}");
+        }
+    }
+}
+
+/// 写入仅所有者可读的文件
+pub(crate) fn write_private(path: &std::path::Path, data: &[u8]) -> Result<(), AppError> {
+    crate::config::atomic_write(path, data)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| AppError::io(path, e))?;
+    }
+    Ok(())
+}
+
+/// 文件存储的加密密钥（尚未生成时返回 None）
+fn fallback_key(dir: &std::path::Path) -> Result<Option<[u8; 32]>, AppError> {
+    let path = dir.join(CLOUD_FALLBACK_KEY_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(encoded) => decode_key(&encoded).map(Some),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(AppError::io(&path, e)),
+    }
+}
+
+fn fallback_entries(dir: &std::path::Path) -> HashMap<String, String> {
+    std::fs::read_to_string(dir.join(CLOUD_FALLBACK_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_fallback_entries(
+    dir: &std::path::Path,
+    entries: &HashMap<String, String>,
+) -> Result<(), AppError> {
+    let content =
+        serde_json::to_vec_pretty(entries).map_err(|e| AppError::JsonSerialize { source: e })?;
+    write_private(&dir.join(CLOUD_FALLBACK_FILE), &content)
+}
+
+/// 加密时绑定账户名，防止密文被挪用到其他供应商
+fn fallback_aad(account: &str) -> Vec<u8> {
+    format!("{CLOUD_AAD}:{account}").into_bytes()
+}
+
+fn fallback_store(
+    dir: &std::path::Path,
+    account: &str,
+    secrets: &CloudSecrets,
+) -> Result<(), AppError> {
+    let key = match fallback_key(dir)? {
+        Some(key) => key,
+        None => {
+            let key = generate_key()?;
+            write_private(
+                &dir.join(CLOUD_FALLBACK_KEY_FILE),
+                BASE64.encode(key).as_bytes(),
+            )?;
+            key
+        }
+    };
+    let plaintext =
+        serde_json::to_vec(secrets).map_err(|e| AppError::JsonSerialize { source: e })?;
+    let sealed = seal(&key, &fallback_aad(account), &plaintext)?;
+    let mut entries = fallback_entries(dir);
+    entries.insert(account.to_string(), BASE64.encode(sealed));
+    write_fallback_entries(dir, &entries)
+}
+
+fn fallback_get(dir: &std::path::Path, account: &str) -> Option<CloudSecrets> {
+    let sealed = BASE64.decode(fallback_entries(dir).get(account)?).ok()?;
+    let key = fallback_key(dir).ok()??;
+    let plaintext = open(&key, &fallback_aad(account), &sealed).ok()?;
+    serde_json::from_slice(&plaintext).ok()
+}
+
+fn fallback_delete(dir: &std::path::Path, account: &str) -> Result<(), AppError> {
+    let mut entries = fallback_entries(dir);
+    if entries.remove(account).is_some() {
+        write_fallback_entries(dir, &entries)?;
+    }
+    Ok(())
+}
+
+fn decode_key(encoded: &str) -> Result<[u8; 32], AppError> {
+    BASE64
+        .decode(encoded.trim())
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| {
+            AppError::localized(
+                "vault.key_invalid",
+                "附件主密钥格式无效",
+                "The vault master key is malformed",
+            )
+        })
+}
+
+pub(crate) fn generate_key() -> Result<[u8; 32], AppError> {
+    let mut key = [0u8; 32];
+    SystemRandom::new()
+        .fill(&mut key)
+        .map_err(|_| AppError::Message("生成随机密钥失败".to_string()))?;
+    Ok(key)
+}
+
+/// 获取主密钥（不存在时生成并保存）
+fn master_key() -> Result<[u8; 32], AppError> {
+    if let Some(encoded) = keychain_get(KEYCHAIN_ACCOUNT) {
+        return decode_key(&encoded);
+    }
+
+    // 早期版本在钥匙串不可用时把主密钥存为文件，仍然读取，以免已有附件无法解密
+    if let Ok(encoded) = std::fs::read_to_string(key_file_path()) {
+        return decode_key(&encoded);
+    }
+
+    // 不再退回到明文密钥文件：与数据库放在同一目录的密钥起不到保护作用
+    let key = generate_key()?;
+    if !keychain_set(
+        KEYCHAIN_ACCOUNT,
+        "CC Switch provider vault",
+        &BASE64.encode(key),
+    ) {
+        return Err(AppError::localized(
+            "vault.keychain_unavailable",
+            "系统钥匙串不可用，无法保存附件主密钥",
+            "The system keychain is unavailable, so the vault master key cannot be stored",
+        ));
+    }
+    Ok(key)
+}
+
+fn cipher(key: &[u8; 32]) -> Result<LessSafeKey, AppError> {
+    UnboundKey::new(&AES_256_GCM, key)
+        .map(LessSafeKey::new)
+        .map_err(|_| AppError::Message("初始化加密失败".to_string()))
+}
+
+/// 加密，输出为 nonce || 密文 || tag
+pub(crate) fn seal(key: &[u8; 32], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, AppError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| AppError::Message("生成随机数失败".to_string()))?;
+
+    let mut buffer = plaintext.to_vec();
+    cipher(key)?
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(aad),
+            &mut buffer,
+        )
+        .map_err(|_| AppError::Message("加密失败".to_string()))?;
+
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&buffer);
+    Ok(out)
+}
+
+/// 解密 [`seal`] 的输出
+pub(crate) fn open(key: &[u8; 32], aad: &[u8], sealed: &[u8]) -> Result<Vec<u8>, AppError> {
+    let decrypt_failed = || {
+        AppError::localized(
+            "vault.decrypt_failed",
+            "解密失败（密钥不匹配或数据已损坏）",
+            "Decryption failed (wrong key or corrupted data)",
+        )
+    };
+    if sealed.len() < NONCE_LEN {
+        return Err(decrypt_failed());
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| decrypt_failed())?;
+    let mut buffer = ciphertext.to_vec();
+    let plaintext = cipher(key)?
+        .open_in_place(nonce, Aad::from(aad), &mut buffer)
+        .map_err(|_| decrypt_failed())?;
+    Ok(plaintext.to_vec())
+}
+
+pub struct VaultService;
+
+impl VaultService {
+    fn add(
+        state: &AppState,
+        app_type: AppType,
+        provider_id: &str,
+        kind: VaultItemKind,
+        name: &str,
+        mime_type: Option<&str>,
+        plaintext: &[u8],
+    ) -> Result<i64, AppError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::InvalidInput("附件名称不能为空".to_string()));
+        }
+        if plaintext.len() > MAX_ITEM_BYTES {
+            return Err(AppError::localized(
+                "vault.too_large",
+                format!("附件超过大小上限（{} KB）", MAX_ITEM_BYTES / 1024),
+                format!("Attachment exceeds the {} KB limit", MAX_ITEM_BYTES / 1024),
+            ));
+        }
+        if state
+            .db
+            .get_provider_by_id(provider_id, app_type.as_str())?
+            .is_none()
+        {
+            return Err(AppError::InvalidInput(format!(
+                "供应商不存在: {provider_id}"
+            )));
+        }
+
+        let sealed = seal(&master_key()?, AAD, plaintext)?;
+        state.db.insert_vault_item(
+            app_type.as_str(),
+            provider_id,
+            kind,
+            name,
+            mime_type,
+            plaintext.len() as u64,
+            &sealed,
+            chrono::Utc::now().timestamp(),
+        )
+    }
+
+    /// 添加备注
+    pub fn add_note(
+        state: &AppState,
+        app_type: AppType,
+        provider_id: &str,
+        name: &str,
+        text: &str,
+    ) -> Result<i64, AppError> {
+        Self::add(
+            state,
+            app_type,
+            provider_id,
+            VaultItemKind::Note,
+            name,
+            None,
+            text.as_bytes(),
+        )
+    }
+
+    /// 添加文件（内容为 base64）
+    pub fn add_file(
+        state: &AppState,
+        app_type: AppType,
+        provider_id: &str,
+        name: &str,
+        mime_type: Option<&str>,
+        data_base64: &str,
+    ) -> Result<i64, AppError> {
+        let data = BASE64
+            .decode(data_base64.trim())
+            .map_err(|e| AppError::InvalidInput(format!("附件内容不是有效的 base64: {e}")))?;
+        Self::add(
+            state,
+            app_type,
+            provider_id,
+            VaultItemKind::File,
+            name,
+            mime_type,
+            &data,
+        )
+    }
+
+    /// 列出附件（仅元数据，不需要解密）
+    pub fn list(
+        state: &AppState,
+        app_type: AppType,
+        provider_id: &str,
+    ) -> Result<Vec<VaultItem>, AppError> {
+        state.db.list_vault_items(app_type.as_str(), provider_id)
+    }
+
+    /// 解密读取附件
+    pub fn read(state: &AppState, id: i64) -> Result<VaultItemContent, AppError> {
+        let (item, sealed) = state
+            .db
+            .get_vault_item(id)?
+            .ok_or_else(|| AppError::InvalidInput(format!("附件不存在: {id}")))?;
+        let plaintext = open(&master_key()?, AAD, &sealed)?;
+        let content = match item.kind {
+            VaultItemKind::Note => String::from_utf8_lossy(&plaintext).into_owned(),
+            VaultItemKind::File => BASE64.encode(&plaintext),
+        };
+        Ok(VaultItemContent { item, content })
+    }
+
+    /// 删除附件
+    pub fn delete(state: &AppState, id: i64) -> Result<bool, AppError> {
+        state.db.delete_vault_item(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seal_and_open_round_trip() {
+        let key = generate_key().unwrap();
+        let sealed = seal(&key, AAD, b"invite code: 42").unwrap();
+        assert_ne!(&sealed[NONCE_LEN..], b"invite code: 42");
+        assert_eq!(open(&key, AAD, &sealed).unwrap(), b"invite code: 42");
+
+        let other = generate_key().unwrap();
+        assert!(open(&other, AAD, &sealed).is_err());
+        assert!(open(&key, AAD, &sealed[..4]).is_err());
+    }
+
+    #[test]
+    fn cloud_credentials_fall_back_to_encrypted_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let secrets = CloudSecrets {
+            secret_access_key: Some("aws-secret".to_string()),
+            ..Default::default()
+        };
+        fallback_store(dir.path(), "cloud-credentials:claude:p1", &secrets).unwrap();
+
+        let content = std::fs::read_to_string(dir.path().join(CLOUD_FALLBACK_FILE)).unwrap();
+        assert!(!content.contains("aws-secret"));
+        assert_eq!(
+            fallback_get(dir.path(), "cloud-credentials:claude:p1"),
+            Some(secrets.clone())
+        );
+        // 密文与账户名绑定，挪到其他账户下无法解密
+        let mut entries = fallback_entries(dir.path());
+        let sealed = entries["cloud-credentials:claude:p1"].clone();
+        entries.insert("cloud-credentials:claude:p2".to_string(), sealed);
+        write_fallback_entries(dir.path(), &entries).unwrap();
+        assert_eq!(
+            fallback_get(dir.path(), "cloud-credentials:claude:p2"),
+            None
+        );
+
+        fallback_delete(dir.path(), "cloud-credentials:claude:p1").unwrap();
+        assert_eq!(
+            fallback_get(dir.path(), "cloud-credentials:claude:p1"),
+            None
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::{OnceLock, RwLock};
@@ -31,6 +32,132 @@ pub struct AppSettings {
     /// 是否在状态栏展示代理 TPS
     #[serde(default)]
     pub show_proxy_tps_in_status_bar: bool,
+    /// 是否将代理吞吐量样本持久化到数据库
+    #[serde(default)]
+    pub persist_tps_samples: bool,
+    /// 吞吐量样本保留天数
+    #[serde(default = "default_tps_sample_retention_days")]
+    pub tps_sample_retention_days: u32,
+    /// 是否在空闲时向当前供应商发送保活请求（默认关闭，补全模式会消耗少量 token）
+    #[serde(default)]
+    pub keep_warm_enabled: bool,
+    /// 空闲多少分钟后发送保活请求
+    #[serde(default = "default_keep_warm_idle_minutes")]
+    pub keep_warm_idle_minutes: u32,
+    /// 保活请求方式
+    #[serde(default)]
+    pub keep_warm_mode: KeepWarmMode,
+    /// 是否定期导出 status.json 供外部工具读取
+    #[serde(default)]
+    pub status_export_enabled: bool,
+    /// status.json 导出路径（为空时使用 `~/.cc-switch/status.json`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_export_path: Option<String>,
+    /// status.json 导出间隔（秒）
+    #[serde(default = "default_status_export_interval_secs")]
+    pub status_export_interval_secs: u32,
+    /// 告警 webhook 地址（预算告警、健康检查失败时 POST 事件 JSON；为空时不发送）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alert_webhook_url: Option<String>,
+    /// 代理转发前的上下文窗口检查方式
+    #[serde(default)]
+    pub context_guard_mode: ContextGuardMode,
+    /// 各模型的上下文窗口（tokens），键以 `*` 结尾时按前缀匹配
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub model_context_windows: BTreeMap<String, u64>,
+    /// 代理日志中请求 / 响应内容的脱敏方式
+    #[serde(default)]
+    pub log_scrub_mode: LogScrubMode,
+    /// 额外的日志脱敏正则（匹配内容替换为 [REDACTED]）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub log_scrub_patterns: Vec<String>,
+    /// 访问日志外发（syslog / OTLP / HTTP JSON 批量），为空时不发送
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_shipping: Option<crate::services::log_shipper::LogShippingConfig>,
+    /// 代理访问日志文件（按大小 / 天轮转并压缩），为空时不写入
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_log: Option<crate::services::access_log::AccessLogConfig>,
+    /// 代理请求链路追踪（OTLP 导出），为空时不记录
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tracing: Option<crate::proxy::trace::TracingConfig>,
+    /// 是否测量代理自身引入的延迟（与上游耗时分开统计）
+    #[serde(default)]
+    pub measure_proxy_overhead: bool,
+    /// 是否记录经代理转发的完整请求与回复（按会话保存到数据库）
+    #[serde(default)]
+    pub capture_transcripts: bool,
+    /// 会话记录保留天数
+    #[serde(default = "default_transcript_retention_days")]
+    pub transcript_retention_days: u32,
+    /// 是否在返回给客户端的响应中附带本次调用的用量与费用
+    #[serde(default)]
+    pub inline_usage_summary: bool,
+    /// 上游响应头透传方式
+    #[serde(default)]
+    pub response_header_mode: ResponseHeaderMode,
+    /// 额外透传的响应头（allowlist 模式下生效，`*` 结尾按前缀匹配）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub response_header_allow: Vec<String>,
+    /// 额外剥离的响应头（`*` 结尾按前缀匹配）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub response_header_strip: Vec<String>,
+    /// 代理本地套接字（Unix domain socket / Windows named pipe）监听
+    #[serde(default)]
+    pub proxy_local_socket: crate::proxy::local_socket::LocalSocketConfig,
+    /// 代理对局域网共享时的 mDNS 广播
+    #[serde(default)]
+    pub lan_discovery: crate::proxy::mdns::LanDiscoveryConfig,
+    /// 影子镜像规则（按比例复制请求到另一个供应商做对比）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shadow_rules: Vec<crate::proxy::shadow::ShadowRule>,
+    /// 检测中转站是否悄悄替换了模型（对照响应中的模型、结束原因与输出速度）
+    #[serde(default = "default_model_substitution_check")]
+    pub model_substitution_check: bool,
+    /// 配置快照间隔（分钟，0 表示不拍摄）
+    #[serde(default = "default_config_snapshot_interval_minutes")]
+    pub config_snapshot_interval_minutes: u32,
+    /// 在代理监听地址上开放 `/api` 管理接口（需令牌访问）
+    #[serde(default)]
+    pub management_api_enabled: bool,
+    /// 持久化转发中的非流式请求，代理重启后重新发送
+    #[serde(default)]
+    pub request_persistence: crate::proxy::request_journal::RequestPersistenceConfig,
+    /// 相同请求仍在返回时合并到原请求的响应上，避免重复付费
+    #[serde(default)]
+    pub request_dedup: crate::proxy::request_dedup::RequestDedupConfig,
+    /// 修复流式响应中的 BOM、CR 换行与非法 UTF-8 字节
+    #[serde(default = "default_true")]
+    pub sse_sanitize: bool,
+    /// 全局请求参数策略（数值上下限、禁止与强制的参数）
+    #[serde(default)]
+    pub parameter_policy: crate::proxy::param_policy::ParameterPolicy,
+    /// 内置模拟上游（代理监听地址下的 `/mock`）
+    #[serde(default)]
+    pub mock_upstream: crate::proxy::mock_upstream::MockUpstreamConfig,
+    /// 数据库、日志与备份目录的占用上限（超限时清理最旧的数据）
+    #[serde(default)]
+    pub disk_guard: crate::services::disk_guard::DiskGuardConfig,
+    /// 月底用量预测与超出阈值告警
+    #[serde(default)]
+    pub usage_forecast: crate::services::usage_forecast::UsageForecastConfig,
+    /// 可单独关闭的子系统（代理、定时健康检查、同步、告警）
+    #[serde(default)]
+    pub subsystems: crate::services::startup_profile::SubsystemConfig,
+    /// 结构化输出校验（JSON 模式与工具调用参数）
+    #[serde(default)]
+    pub output_validation: crate::proxy::output_validation::OutputValidationConfig,
+    /// 出站内容过滤（请求发往第三方中转前替换敏感内容）
+    #[serde(default)]
+    pub outbound_redaction: crate::proxy::outbound_redaction::OutboundRedactionConfig,
+    /// 日志级别（默认级别与各模块的覆盖级别，修改后立即生效）
+    #[serde(default)]
+    pub log_levels: crate::services::log_level::LogLevelConfig,
+    /// Codex 改写 auth.json 丢失中转站 Key 时自动补回（记入审计日志）
+    #[serde(default = "default_true")]
+    pub codex_auth_repair: bool,
+    /// 托盘图标按置顶供应商的健康状态着色，Dock / 任务栏角标显示失败数量
+    #[serde(default = "default_true")]
+    pub tray_health_indicator: bool,
     /// 是否启用 Claude 插件联动
     #[serde(default)]
     pub enable_claude_plugin_integration: bool,
@@ -40,6 +167,9 @@ pub struct AppSettings {
     /// 是否开机自启
     #[serde(default)]
     pub launch_on_startup: bool,
+    /// 检测到其他 cc-switch 实例（如开发版与安装版）时的处理方式
+    #[serde(default)]
+    pub secondary_instance_mode: crate::instance_guard::SecondaryInstanceMode,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub language: Option<String>,
 
@@ -75,15 +205,130 @@ fn default_true() -> bool {
     true
 }
 
+fn default_tps_sample_retention_days() -> u32 {
+    7
+}
+
+fn default_transcript_retention_days() -> u32 {
+    30
+}
+
+fn default_keep_warm_idle_minutes() -> u32 {
+    30
+}
+
+fn default_status_export_interval_secs() -> u32 {
+    15
+}
+
+fn default_model_substitution_check() -> bool {
+    true
+}
+
+fn default_config_snapshot_interval_minutes() -> u32 {
+    60
+}
+
+/// 保活请求方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeepWarmMode {
+    /// 仅对 base_url 发送 HEAD 请求（不消耗 token）
+    #[default]
+    Head,
+    /// 发送 max_tokens=1 的极小补全请求（消耗少量 token）
+    Completion,
+}
+
+/// 请求超过模型上下文窗口时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContextGuardMode {
+    /// 不检查
+    #[default]
+    Off,
+    /// 记录警告日志后照常转发
+    Warn,
+    /// 拒绝请求并返回错误说明
+    Reject,
+}
+
+/// 日志内容脱敏方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogScrubMode {
+    /// 原样记录
+    Off,
+    /// 替换 API Key、邮箱与自定义规则匹配的内容
+    #[default]
+    Redact,
+    /// 只记录结构（字段名、类型、长度）
+    Strict,
+}
+
+/// 上游响应头透传方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResponseHeaderMode {
+    /// 透传全部响应头（剥离列表除外）
+    #[default]
+    All,
+    /// 只透传基础响应头、调试相关响应头和自定义列表
+    Allowlist,
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
         Self {
             show_in_tray: true,
             minimize_to_tray_on_close: true,
             show_proxy_tps_in_status_bar: false,
+            persist_tps_samples: false,
+            tps_sample_retention_days: default_tps_sample_retention_days(),
+            keep_warm_enabled: false,
+            keep_warm_idle_minutes: default_keep_warm_idle_minutes(),
+            keep_warm_mode: KeepWarmMode::Head,
+            status_export_enabled: false,
+            status_export_path: None,
+            status_export_interval_secs: default_status_export_interval_secs(),
+            alert_webhook_url: None,
+            context_guard_mode: ContextGuardMode::Off,
+            model_context_windows: BTreeMap::new(),
+            log_scrub_mode: LogScrubMode::Redact,
+            log_scrub_patterns: Vec::new(),
+            log_shipping: None,
+            access_log: None,
+            tracing: None,
+            measure_proxy_overhead: false,
+            capture_transcripts: false,
+            transcript_retention_days: default_transcript_retention_days(),
+            inline_usage_summary: false,
+            response_header_mode: ResponseHeaderMode::default(),
+            response_header_allow: Vec::new(),
+            response_header_strip: Vec::new(),
+            proxy_local_socket: Default::default(),
+            lan_discovery: Default::default(),
+            shadow_rules: Vec::new(),
+            model_substitution_check: true,
+            config_snapshot_interval_minutes: default_config_snapshot_interval_minutes(),
+            management_api_enabled: false,
+            request_persistence: Default::default(),
+            request_dedup: Default::default(),
+            sse_sanitize: true,
+            parameter_policy: Default::default(),
+            mock_upstream: Default::default(),
+            disk_guard: Default::default(),
+            usage_forecast: Default::default(),
+            subsystems: Default::default(),
+            output_validation: Default::default(),
+            outbound_redaction: Default::default(),
+            log_levels: Default::default(),
+            codex_auth_repair: true,
+            tray_health_indicator: true,
             enable_claude_plugin_integration: false,
             skip_claude_onboarding: true,
             launch_on_startup: false,
+            secondary_instance_mode: Default::default(),
             language: None,
             claude_config_dir: None,
             codex_config_dir: None,
@@ -98,9 +343,13 @@ impl Default for AppSettings {
 impl AppSettings {
     fn settings_path() -> PathBuf {
         // settings.json 保留用于旧版本迁移和无数据库场景
-        dirs::home_dir()
-            .expect("无法获取用户主目录")
-            .join(".cc-switch")
+        // 便携模式 / 启动参数指定数据目录时随数据一起存放
+        crate::app_store::startup_data_dir()
+            .unwrap_or_else(|| {
+                dirs::home_dir()
+                    .expect("无法获取用户主目录")
+                    .join(".cc-switch")
+            })
             .join("settings.json")
     }
 
@@ -126,6 +375,13 @@ impl AppSettings {
             .filter(|s| !s.is_empty())
             .map(|s| s.to_string());
 
+        self.alert_webhook_url = self
+            .alert_webhook_url
+            .as_ref()
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_string());
+
         self.language = self
             .language
             .as_ref()
@@ -196,6 +452,11 @@ fn resolve_override_path(raw: &str) -> PathBuf {
     PathBuf::from(raw)
 }
 
+/// settings.json 的路径
+pub fn settings_file_path() -> PathBuf {
+    AppSettings::settings_path()
+}
+
 pub fn get_settings() -> AppSettings {
     settings_store().read().expect("读取设置锁失败").clone()
 }
@@ -203,6 +464,7 @@ pub fn get_settings() -> AppSettings {
 pub fn update_settings(mut new_settings: AppSettings) -> Result<(), AppError> {
     new_settings.normalize_paths();
     save_settings_file(&new_settings)?;
+    crate::services::log_level::apply(&new_settings.log_levels);
 
     let mut guard = settings_store().write().expect("写入设置锁失败");
     *guard = new_settings;
@@ -213,6 +475,7 @@ pub fn update_settings(mut new_settings: AppSettings) -> Result<(), AppError> {
 /// 用于导入配置等场景，确保内存缓存与文件同步
 pub fn reload_settings() -> Result<(), AppError> {
     let fresh_settings = AppSettings::load_from_file();
+    crate::services::log_level::apply(&fresh_settings.log_levels);
     let mut guard = settings_store().write().expect("写入设置锁失败");
     *guard = fresh_settings;
     Ok(())
@@ -3,7 +3,7 @@
 //! 负责系统托盘图标和菜单的创建、更新和事件处理。
 
 use tauri::menu::{CheckMenuItem, Menu, MenuBuilder, MenuItem};
-use tauri::{Emitter, Manager};
+use tauri::Manager;
 
 use crate::app_config::AppType;
 use crate::error::AppError;
@@ -271,12 +271,16 @@ pub fn switch_provider_internal(
     provider_id: String,
 ) -> Result<(), AppError> {
     if let Some(app_state) = app.try_state::<AppState>() {
-        // 在使用前先保存需要的值
         let app_type_str = app_type.as_str().to_string();
-        let provider_id_clone = provider_id.clone();
 
-        crate::commands::switch_provider(app_state.clone(), app_type_str.clone(), provider_id)
-            .map_err(AppError::Message)?;
+        crate::commands::switch_provider_from(
+            app,
+            app_state.inner(),
+            &app_type_str,
+            provider_id,
+            "tray",
+        )
+        .map_err(AppError::Message)?;
 
         // 切换成功后重新创建托盘菜单
         if let Ok(new_menu) = create_tray_menu(app, app_state.inner()) {
@@ -286,15 +290,6 @@ pub fn switch_provider_internal(
                 }
             }
         }
-
-        // 发射事件到前端，通知供应商已切换
-        let event_data = serde_json::json!({
-            "appType": app_type_str,
-            "providerId": provider_id_clone
-        });
-        if let Err(e) = app.emit("provider-switched", event_data) {
-            log::error!("发射供应商切换事件失败: {e}");
-        }
     }
     Ok(())
 }
@@ -0,0 +1,284 @@
+//! 托盘健康指示
+//!
+//! 托盘图标颜色反映置顶供应商（未置顶任何供应商时为各应用的当前供应商）的整体健康状态，
+//! Dock / 任务栏角标显示其中失败的供应商数量。后台定期按最近一次健康检查结果与代理熔断状态
+//! 刷新，收到 `health-changed` 等事件时立即刷新，主窗口未打开时同样生效。
+
+use std::time::Duration;
+
+use tauri::image::Image;
+use tauri::{AppHandle, Manager};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::app_config::AppType;
+use crate::database::Database;
+use crate::events::{self, AppEvent};
+use crate::provider::Provider;
+use crate::services::stream_check::HealthStatus;
+use crate::store::AppState;
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+const AMBER: [u8; 3] = [0xF5, 0x9E, 0x0B];
+const RED: [u8; 3] = [0xEF, 0x44, 0x44];
+
+/// 托盘显示的整体健康状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TrayHealth {
+    /// 没有可用的检查结果（或已关闭指示），使用原图标
+    Unknown,
+    Healthy,
+    Degraded,
+    Failed,
+}
+
+impl TrayHealth {
+    fn color(self) -> Option<[u8; 3]> {
+        match self {
+            Self::Unknown | Self::Healthy => None,
+            Self::Degraded => Some(AMBER),
+            Self::Failed => Some(RED),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Summary {
+    health: TrayHealth,
+    failed: usize,
+}
+
+impl Summary {
+    const IDLE: Self = Self {
+        health: TrayHealth::Unknown,
+        failed: 0,
+    };
+}
+
+/// 全部失败为 Failed，部分失败或降级为 Degraded
+fn summarize(statuses: &[HealthStatus]) -> Summary {
+    let failed = statuses
+        .iter()
+        .filter(|s| **s == HealthStatus::Failed)
+        .count();
+    let health = if statuses.is_empty() {
+        TrayHealth::Unknown
+    } else if failed == statuses.len() {
+        TrayHealth::Failed
+    } else if statuses.iter().any(|s| *s != HealthStatus::Operational) {
+        TrayHealth::Degraded
+    } else {
+        TrayHealth::Healthy
+    };
+    Summary { health, failed }
+}
+
+/// 需要关注的供应商：置顶的供应商，未置顶任何供应商时取各应用的当前供应商
+fn watched_providers(db: &Database) -> Vec<(AppType, Provider)> {
+    let mut pinned = Vec::new();
+    let mut current = Vec::new();
+    for app_type in [AppType::Claude, AppType::Codex, AppType::Gemini] {
+        let Ok(providers) = db.get_all_providers(app_type.as_str()) else {
+            continue;
+        };
+        let current_id = crate::settings::get_effective_current_provider(db, &app_type)
+            .ok()
+            .flatten();
+        for provider in providers.into_values() {
+            if provider.meta.as_ref().is_some_and(|m| m.pinned) {
+                pinned.push((app_type.clone(), provider));
+            } else if current_id.as_deref() == Some(provider.id.as_str()) {
+                current.push((app_type.clone(), provider));
+            }
+        }
+    }
+    if pinned.is_empty() {
+        current
+    } else {
+        pinned
+    }
+}
+
+/// 各供应商的状态（维护窗口内与从未检查过的供应商不计入）
+async fn watched_statuses(db: &Database) -> Vec<HealthStatus> {
+    let check_config = db.get_stream_check_config().unwrap_or_default();
+    let mut statuses = Vec::new();
+    for (app_type, provider) in watched_providers(db) {
+        if crate::services::maintenance::is_in_maintenance(&provider) {
+            continue;
+        }
+        let app = app_type.as_str();
+        // 代理熔断说明真实请求正在失败，优先于最近一次检查结果
+        let proxy_healthy = db
+            .get_provider_health(&provider.id, app)
+            .await
+            .map(|h| h.is_healthy)
+            .unwrap_or(true);
+        if !proxy_healthy {
+            statuses.push(HealthStatus::Failed);
+        } else if let Ok(blended) =
+            crate::services::traffic_health::blended_health(db, &provider.id, app, &check_config)
+        {
+            statuses.extend(blended.status);
+        }
+    }
+    statuses
+}
+
+/// 在 RGBA 图像上画一个实心圆点
+fn paint_dot(rgba: &mut [u8], width: u32, height: u32, radius: f32, color: [u8; 3]) {
+    let cx = width as f32 - radius;
+    let cy = height as f32 - radius;
+    for y in 0..height {
+        for x in 0..width {
+            let dx = x as f32 + 0.5 - cx;
+            let dy = y as f32 + 0.5 - cy;
+            if dx * dx + dy * dy > radius * radius {
+                continue;
+            }
+            let i = ((y * width + x) * 4) as usize;
+            rgba[i..i + 3].copy_from_slice(&color);
+            rgba[i + 3] = 0xFF;
+        }
+    }
+}
+
+/// 按健康状态着色：模板图标（macOS 单色图标）整体换色，彩色图标在右下角加圆点
+fn tinted(base: &Image<'_>, color: [u8; 3], template: bool) -> Image<'static> {
+    let (width, height) = (base.width(), base.height());
+    let mut rgba = base.rgba().to_vec();
+    if template {
+        for pixel in rgba.chunks_exact_mut(4) {
+            pixel[..3].copy_from_slice(&color);
+        }
+    } else {
+        let radius = width.min(height) as f32 * 0.22;
+        paint_dot(&mut rgba, width, height, radius, color);
+    }
+    Image::new_owned(rgba, width, height)
+}
+
+fn base_icon(app: &AppHandle) -> Option<Image<'static>> {
+    #[cfg(target_os = "macos")]
+    if let Some(icon) = crate::macos_tray_icon() {
+        return Some(icon);
+    }
+    app.default_window_icon().cloned().map(Image::to_owned)
+}
+
+fn apply(app: &AppHandle, summary: Summary) {
+    let template = cfg!(target_os = "macos");
+    if let (Some(tray), Some(base)) = (app.tray_by_id("main"), base_icon(app)) {
+        let color = summary.health.color();
+        let icon = match color {
+            Some(color) => tinted(&base, color, template),
+            None => base,
+        };
+        if let Err(e) = tray.set_icon(Some(icon)) {
+            log::debug!("更新托盘图标失败（忽略）: {e}");
+        }
+        if template {
+            let _ = tray.set_icon_as_template(color.is_none());
+        }
+    }
+
+    let Some(window) = app.get_webview_window("main") else {
+        return;
+    };
+    // Windows 任务栏不支持数字角标，改用红点叠加图标
+    #[cfg(target_os = "windows")]
+    let result = window.set_overlay_icon((summary.failed > 0).then(|| {
+        let mut rgba = vec![0u8; 16 * 16 * 4];
+        paint_dot(&mut rgba, 16, 16, 8.0, RED);
+        Image::new_owned(rgba, 16, 16)
+    }));
+    #[cfg(not(target_os = "windows"))]
+    let result = window.set_badge_count((summary.failed > 0).then_some(summary.failed as i64));
+    if let Err(e) = result {
+        log::debug!("更新角标失败（忽略）: {e}");
+    }
+}
+
+/// 等待下一个会影响汇总结果的事件（健康检查完成、切换供应商）
+async fn wait_for_change(rx: &mut broadcast::Receiver<AppEvent>) {
+    loop {
+        match rx.recv().await {
+            Ok(AppEvent::HealthChanged(_) | AppEvent::ProviderSwitched(_))
+            | Err(RecvError::Lagged(_)) => return,
+            Ok(_) => {}
+            Err(RecvError::Closed) => std::future::pending().await,
+        }
+    }
+}
+
+/// 后台刷新循环（设置关闭时恢复原图标并清除角标）
+pub async fn run(app: AppHandle) {
+    let mut rx = events::subscribe();
+    let mut last = Summary::IDLE;
+    loop {
+        let summary = if crate::settings::get_settings().tray_health_indicator {
+            let db = app.state::<AppState>().db.clone();
+            summarize(&watched_statuses(&db).await)
+        } else {
+            Summary::IDLE
+        };
+        if summary != last {
+            log::debug!(
+                "[TrayHealth] {:?}，失败 {} 个",
+                summary.health,
+                summary.failed
+            );
+            apply(&app, summary);
+            last = summary;
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(REFRESH_INTERVAL) => {}
+            _ = wait_for_change(&mut rx) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_reflects_worst_and_counts_failures() {
+        use HealthStatus::*;
+
+        assert_eq!(summarize(&[]), Summary::IDLE);
+        assert_eq!(
+            summarize(&[Operational, Operational]).health,
+            TrayHealth::Healthy
+        );
+        assert_eq!(
+            summarize(&[Operational, Degraded]).health,
+            TrayHealth::Degraded
+        );
+
+        let partial = summarize(&[Operational, Failed, Degraded]);
+        assert_eq!(partial.health, TrayHealth::Degraded);
+        assert_eq!(partial.failed, 1);
+
+        let down = summarize(&[Failed, Failed]);
+        assert_eq!(down.health, TrayHealth::Failed);
+        assert_eq!(down.failed, 2);
+    }
+
+    #[test]
+    fn colored_icons_get_a_corner_dot() {
+        let base = Image::new_owned(vec![0x10; 8 * 8 * 4], 8, 8);
+        let icon = tinted(&base, RED, false);
+        let pixel = |x: u32, y: u32| {
+            let i = ((y * 8 + x) * 4) as usize;
+            icon.rgba()[i..i + 4].to_vec()
+        };
+        assert_eq!(pixel(6, 6), vec![0xEF, 0x44, 0x44, 0xFF]);
+        assert_eq!(pixel(0, 0), vec![0x10; 4]);
+
+        // 模板图标保留透明度，只换颜色
+        let template = tinted(&base, AMBER, true);
+        assert_eq!(&template.rgba()[..4], &[0xF5, 0x9E, 0x0B, 0x10]);
+    }
+}
@@ -21,6 +21,8 @@ import {
   providersApi,
   settingsApi,
   type AppId,
+  type CapabilityWarning,
+  type DuplicateProviderEvent,
   type ProviderSwitchEvent,
 } from "@/lib/api";
 import { checkAllEnvConflicts, checkEnvConflicts } from "@/lib/api/env";
@@ -71,6 +73,10 @@ function App() {
   const [editingProvider, setEditingProvider] = useState<Provider | null>(null);
   const [usageProvider, setUsageProvider] = useState<Provider | null>(null);
   const [confirmDelete, setConfirmDelete] = useState<Provider | null>(null);
+  const [confirmSwitch, setConfirmSwitch] = useState<{
+    provider: Provider;
+    warnings: CapabilityWarning[];
+  } | null>(null);
   const [envConflicts, setEnvConflicts] = useState<EnvConflict[]>([]);
   const [showEnvBanner, setShowEnvBanner] = useState(false);
 
@@ -142,6 +148,68 @@ function App() {
     };
   }, [activeApp, refetch]);
 
+  // 新建或导入的供应商与已有供应商重复时，提示合并到已有供应商
+  useEffect(() => {
+    let unsubscribe: (() => void) | undefined;
+
+    const setupListener = async () => {
+      try {
+        unsubscribe = await providersApi.onDuplicatesDetected(
+          (event: DuplicateProviderEvent) => {
+            const existing = event.matches[0];
+            if (!existing) return;
+            toast.warning(
+              t("provider.duplicateDetected", {
+                name: event.name,
+                existing: existing.name,
+                defaultValue: `「${event.name}」与已有供应商「${existing.name}」重复`,
+              }),
+              {
+                duration: 10000,
+                closeButton: true,
+                action: {
+                  label: t("provider.mergeDuplicate", {
+                    defaultValue: "合并",
+                  }),
+                  onClick: async () => {
+                    try {
+                      await providersApi.mergeDuplicates(
+                        existing.providerId,
+                        event.providerId,
+                        event.appType,
+                      );
+                      await queryClient.invalidateQueries({
+                        queryKey: ["providers", event.appType],
+                      });
+                      toast.success(
+                        t("provider.duplicateMerged", {
+                          existing: existing.name,
+                          defaultValue: `已合并到「${existing.name}」`,
+                        }),
+                      );
+                    } catch (error) {
+                      toast.error(extractErrorMessage(error));
+                    }
+                  },
+                },
+              },
+            );
+          },
+        );
+      } catch (error) {
+        console.error(
+          "[App] Failed to subscribe duplicate-provider-detected event",
+          error,
+        );
+      }
+    };
+
+    setupListener();
+    return () => {
+      unsubscribe?.();
+    };
+  }, [queryClient, t]);
+
   // 监听统一供应商同步事件，刷新所有应用的供应商列表
   useEffect(() => {
     let unsubscribe: (() => void) | undefined;
@@ -286,6 +354,30 @@ function App() {
     setEditingProvider(null);
   };
 
+  // 切换前检查供应商能力，有问题时先让用户确认
+  const handleSwitchProvider = async (provider: Provider) => {
+    let warnings: CapabilityWarning[] = [];
+    try {
+      warnings = await providersApi.checkCapabilities(provider.id, activeApp);
+    } catch (error) {
+      // 检查失败不阻止切换
+      console.error("Failed to check provider capabilities", error);
+    }
+    if (warnings.length > 0) {
+      setConfirmSwitch({ provider, warnings });
+      return;
+    }
+    await switchProvider(provider);
+  };
+
+  // 确认切换到能力不足的供应商
+  const handleConfirmSwitch = async () => {
+    if (!confirmSwitch) return;
+    const { provider } = confirmSwitch;
+    setConfirmSwitch(null);
+    await switchProvider(provider);
+  };
+
   // 确认删除供应商
   const handleConfirmDelete = async () => {
     if (!confirmDelete) return;
@@ -343,7 +435,7 @@ function App() {
     }
 
     // 3️⃣ 添加复制的供应商
-    await addProvider(duplicatedProvider);
+    await addProvider(duplicatedProvider, provider.id);
   };
 
   // 导入配置成功后刷新
@@ -438,7 +530,7 @@ function App() {
                         isProxyRunning && isCurrentAppTakeoverActive
                       }
                       activeProviderId={activeProviderId}
-                      onSwitch={switchProvider}
+                      onSwitch={handleSwitchProvider}
                       onEdit={setEditingProvider}
                       onDelete={setConfirmDelete}
                       onDuplicate={handleDuplicateProvider}
@@ -746,6 +838,24 @@ function App() {
         onCancel={() => setConfirmDelete(null)}
       />
 
+      <ConfirmDialog
+        isOpen={Boolean(confirmSwitch)}
+        title={t("confirm.switchProvider")}
+        message={
+          confirmSwitch
+            ? t("confirm.switchProviderMessage", {
+                name: confirmSwitch.provider.name,
+                warnings: confirmSwitch.warnings
+                  .map((w) => `• ${w.message}`)
+                  .join("\n"),
+              })
+            : ""
+        }
+        confirmText={t("confirm.switchAnyway")}
+        onConfirm={() => void handleConfirmSwitch()}
+        onCancel={() => setConfirmSwitch(null)}
+      />
+
       <DeepLinkImportDialog />
     </div>
   );
@@ -8,6 +8,7 @@ import {
   ProviderForm,
   type ProviderFormValues,
 } from "@/components/providers/forms/ProviderForm";
+import { ProviderKeyContext } from "@/components/providers/forms/ApiKeyInput";
 import { providersApi, vscodeApi, type AppId } from "@/lib/api";
 
 interface EditProviderDialogProps {
@@ -182,15 +183,17 @@ export function EditProviderDialog({
         </Button>
       }
     >
-      <ProviderForm
-        appId={appId}
-        providerId={provider.id}
-        submitLabel={t("common.save")}
-        onSubmit={handleSubmit}
-        onCancel={() => onOpenChange(false)}
-        initialData={initialData}
-        showButtons={false}
-      />
+      <ProviderKeyContext.Provider value={{ appId, providerId: provider.id }}>
+        <ProviderForm
+          appId={appId}
+          providerId={provider.id}
+          submitLabel={t("common.save")}
+          onSubmit={handleSubmit}
+          onCancel={() => onOpenChange(false)}
+          initialData={initialData}
+          showButtons={false}
+        />
+      </ProviderKeyContext.Provider>
     </FullScreenPanel>
   );
 }
@@ -28,6 +28,18 @@ const statusConfig = {
     labelFallback: "失败",
     textColor: "text-red-600 dark:text-red-400",
   },
+  cancelled: {
+    color: "bg-gray-400",
+    labelKey: "health.cancelled",
+    labelFallback: "已取消",
+    textColor: "text-gray-500 dark:text-gray-400",
+  },
+  skipped: {
+    color: "bg-gray-400",
+    labelKey: "health.skipped",
+    labelFallback: "已跳过",
+    textColor: "text-gray-500 dark:text-gray-400",
+  },
 };
 
 export const HealthStatusIndicator: React.FC<HealthStatusIndicatorProps> = ({
@@ -1,6 +1,23 @@
-import React, { useState } from "react";
-import { Eye, EyeOff } from "lucide-react";
+import React, { createContext, useContext, useState } from "react";
+import { Copy, Eye, EyeOff } from "lucide-react";
 import { useTranslation } from "react-i18next";
+import { toast } from "sonner";
+import {
+  providersApi,
+  REDACTED_KEY,
+  type AppId,
+  type KeyRevealAction,
+} from "@/lib/api";
+import { extractErrorMessage } from "@/utils/errorUtils";
+
+/**
+ * 编辑已保存的供应商时提供其 ID，供 Key 输入框在显示或复制被隐藏的 Key 时
+ * 向后端（经系统认证）取回明文
+ */
+export const ProviderKeyContext = createContext<{
+  appId: AppId;
+  providerId: string;
+} | null>(null);
 
 interface ApiKeyInputProps {
   value: string;
@@ -22,18 +39,66 @@ const ApiKeyInput: React.FC<ApiKeyInputProps> = ({
   id = "apiKey",
 }) => {
   const { t } = useTranslation();
+  const keyOwner = useContext(ProviderKeyContext);
   const [showKey, setShowKey] = useState(false);
+  const [fetching, setFetching] = useState(false);
+
+  const isRedacted = value === REDACTED_KEY;
+
+  // 被隐藏的 Key 需经系统认证后由后端返回明文
+  const resolveKey = async (action: KeyRevealAction): Promise<string> => {
+    if (!isRedacted || !keyOwner) return value;
+    setFetching(true);
+    try {
+      return await providersApi.revealKey(
+        keyOwner.providerId,
+        keyOwner.appId,
+        action,
+      );
+    } finally {
+      setFetching(false);
+    }
+  };
 
-  const toggleShowKey = () => {
-    setShowKey(!showKey);
+  const toggleShowKey = async () => {
+    if (showKey || !isRedacted) {
+      setShowKey(!showKey);
+      return;
+    }
+    try {
+      const key = await resolveKey("reveal");
+      onChange(key);
+      setShowKey(true);
+    } catch (error) {
+      toast.error(t("apiKeyInput.revealFailed"), {
+        description: extractErrorMessage(error),
+      });
+    }
   };
 
-  const inputClass = `w-full px-3 py-2 pr-10 border rounded-lg text-sm transition-colors ${
+  const copyKey = async () => {
+    try {
+      const key = await resolveKey("copy");
+      await navigator.clipboard.writeText(key);
+      toast.success(t("apiKeyInput.copied"));
+    } catch (error) {
+      toast.error(t("apiKeyInput.revealFailed"), {
+        description: extractErrorMessage(error),
+      });
+    }
+  };
+
+  const canFetch = !isRedacted || keyOwner !== null;
+
+  const inputClass = `w-full px-3 py-2 pr-16 border rounded-lg text-sm transition-colors ${
     disabled
       ? "bg-muted border-border-default text-muted-foreground cursor-not-allowed"
       : "border-border-default bg-background text-foreground focus:outline-none focus:ring-2 focus:ring-blue-500/20 dark:focus:ring-blue-400/20"
   }`;
 
+  const buttonClass =
+    "flex items-center text-muted-foreground hover:text-foreground transition-colors disabled:opacity-50";
+
   return (
     <div className="space-y-2">
       <label htmlFor={id} className="block text-sm font-medium text-foreground">
@@ -51,15 +116,29 @@ const ApiKeyInput: React.FC<ApiKeyInputProps> = ({
           autoComplete="off"
           className={inputClass}
         />
-        {!disabled && value && (
-          <button
-            type="button"
-            onClick={toggleShowKey}
-            className="absolute inset-y-0 right-0 flex items-center pr-3 text-muted-foreground hover:text-foreground transition-colors"
-            aria-label={showKey ? t("apiKeyInput.hide") : t("apiKeyInput.show")}
-          >
-            {showKey ? <EyeOff size={16} /> : <Eye size={16} />}
-          </button>
+        {!disabled && value && canFetch && (
+          <div className="absolute inset-y-0 right-0 flex items-center gap-2 pr-3">
+            <button
+              type="button"
+              onClick={copyKey}
+              disabled={fetching}
+              className={buttonClass}
+              aria-label={t("apiKeyInput.copy")}
+            >
+              <Copy size={16} />
+            </button>
+            <button
+              type="button"
+              onClick={toggleShowKey}
+              disabled={fetching}
+              className={buttonClass}
+              aria-label={
+                showKey ? t("apiKeyInput.hide") : t("apiKeyInput.show")
+              }
+            >
+              {showKey ? <EyeOff size={16} /> : <Eye size={16} />}
+            </button>
+          </div>
         )}
       </div>
     </div>
@@ -11,6 +11,7 @@ import {
   saveStreamCheckConfig,
   type StreamCheckConfig,
 } from "@/lib/api/model-test";
+import { extractErrorMessage } from "@/utils/errorUtils";
 
 export function ModelTestConfigPanel() {
   const { t } = useTranslation();
@@ -37,7 +38,7 @@ export function ModelTestConfigPanel() {
       const data = await getStreamCheckConfig();
       setConfig(data);
     } catch (e) {
-      setError(String(e));
+      setError(extractErrorMessage(e));
     } finally {
       setIsLoading(false);
     }
@@ -51,7 +52,9 @@ export function ModelTestConfigPanel() {
         closeButton: true,
       });
     } catch (e) {
-      toast.error(t("streamCheck.configSaveFailed") + ": " + String(e));
+      toast.error(
+        t("streamCheck.configSaveFailed") + ": " + extractErrorMessage(e),
+      );
     } finally {
       setIsSaving(false);
     }
@@ -22,6 +22,7 @@ import {
 import { useModelPricing, useDeleteModelPricing } from "@/lib/query/usage";
 import { PricingEditModal } from "./PricingEditModal";
 import type { ModelPricing } from "@/types/usage";
+import { extractErrorMessage } from "@/utils/errorUtils";
 import { Plus, Pencil, Trash2, ChevronDown, ChevronRight } from "lucide-react";
 
 export function PricingConfigPanel() {
@@ -93,7 +94,7 @@ export function PricingConfigPanel() {
           <CardContent>
             <Alert variant="destructive">
               <AlertDescription>
-                {t("usage.loadPricingError")}: {String(error)}
+                {t("usage.loadPricingError")}: {extractErrorMessage(error)}
               </AlertDescription>
             </Alert>
           </CardContent>
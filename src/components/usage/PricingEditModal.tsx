@@ -13,6 +13,7 @@ import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
 import { useUpdateModelPricing } from "@/lib/query/usage";
 import type { ModelPricing } from "@/types/usage";
+import { extractErrorMessage } from "@/utils/errorUtils";
 
 interface PricingEditModalProps {
   model: ModelPricing;
@@ -81,7 +82,7 @@ export function PricingEditModal({
 
       onClose();
     } catch (error) {
-      toast.error(String(error));
+      toast.error(extractErrorMessage(error));
     }
   };
 
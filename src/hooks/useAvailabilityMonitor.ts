@@ -3,6 +3,7 @@ import { useQueryClient } from "@tanstack/react-query";
 import type { Provider } from "@/types";
 import type { AppId } from "@/lib/api";
 import { streamCheckProvider } from "@/lib/api/model-test";
+import { extractErrorMessage } from "@/utils/errorUtils";
 
 const MONITOR_INTERVAL_MS = 60_000;
 const HISTORY_LIMIT = 20;
@@ -80,7 +81,7 @@ export function useAvailabilityMonitor(
             console.warn("[availability-monitor] stream check failed", {
               appId,
               providerId,
-              error: String(e),
+              error: extractErrorMessage(e),
             });
           } finally {
             inFlightRef.current.delete(providerId);
@@ -54,8 +54,8 @@ export function useProviderActions(activeApp: AppId) {
 
   // 添加供应商
   const addProvider = useCallback(
-    async (provider: Omit<Provider, "id">) => {
-      await addProviderMutation.mutateAsync(provider);
+    async (provider: Omit<Provider, "id">, copySecretsFrom?: string) => {
+      await addProviderMutation.mutateAsync({ provider, copySecretsFrom });
     },
     [addProviderMutation],
   );
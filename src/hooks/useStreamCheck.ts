@@ -8,6 +8,7 @@ import {
 } from "@/lib/api/model-test";
 import type { AppId } from "@/lib/api";
 import { useResetCircuitBreaker } from "@/lib/query/failover";
+import { extractErrorMessage } from "@/utils/errorUtils";
 
 export function useStreamCheck(appId: AppId) {
   const { t } = useTranslation();
@@ -60,11 +61,12 @@ export function useStreamCheck(appId: AppId) {
 
         return result;
       } catch (e) {
+        const message = extractErrorMessage(e);
         toast.error(
           t("streamCheck.error", {
             name: providerName,
-            error: String(e),
-            defaultValue: `${providerName} 检查出错: ${String(e)}`,
+            error: message,
+            defaultValue: `${providerName} 检查出错: ${message}`,
           }),
         );
         return null;
@@ -3,6 +3,7 @@ import { useTranslation } from "react-i18next";
 import { toast } from "sonner";
 import { tpsTestProvider, type TpsTestResult } from "@/lib/api/model-test";
 import type { AppId } from "@/lib/api";
+import { extractErrorMessage } from "@/utils/errorUtils";
 
 export function useTpsTest(appId: AppId) {
   const { t } = useTranslation();
@@ -31,7 +32,7 @@ export function useTpsTest(appId: AppId) {
 
         return result;
       } catch (e) {
-        const message = String(e);
+        const message = extractErrorMessage(e);
         toast.error(
           t("tpsTest.errorToast", {
             name: providerName,
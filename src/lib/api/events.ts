@@ -0,0 +1,41 @@
+import { Channel, invoke } from "@tauri-apps/api/core";
+
+export type EventKind =
+  | "health-changed"
+  | "provider-switched"
+  | "tps-sample"
+  | "budget-warning"
+  | "sync-conflict"
+  | "network-status-changed"
+  | "duplicate-provider-detected";
+
+export interface AppEvent {
+  type: EventKind;
+  payload: Record<string, unknown>;
+}
+
+export interface EventFilter {
+  kinds?: EventKind[];
+  appType?: string;
+  providerId?: string;
+}
+
+export const eventsApi = {
+  /**
+   * 订阅后端事件总线，返回取消订阅函数
+   */
+  async subscribe(
+    handler: (event: AppEvent) => void,
+    filter?: EventFilter,
+  ): Promise<() => Promise<void>> {
+    const onEvent = new Channel<AppEvent>();
+    onEvent.onmessage = handler;
+    const subscriptionId = await invoke<number>("subscribe_events", {
+      filter,
+      onEvent,
+    });
+    return async () => {
+      await invoke("unsubscribe_events", { subscriptionId });
+    };
+  },
+};
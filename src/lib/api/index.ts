@@ -1,11 +1,22 @@
 export type { AppId } from "./types";
-export { providersApi, universalProvidersApi } from "./providers";
+export {
+  providersApi,
+  universalProvidersApi,
+  REDACTED_KEY,
+} from "./providers";
 export { settingsApi } from "./settings";
 export { mcpApi } from "./mcp";
 export { promptsApi } from "./prompts";
 export { usageApi } from "./usage";
 export { vscodeApi } from "./vscode";
 export { proxyApi } from "./proxy";
+export { eventsApi } from "./events";
 export * as configApi from "./config";
-export type { ProviderSwitchEvent } from "./providers";
+export type {
+  CapabilityWarning,
+  DuplicateProviderEvent,
+  KeyRevealAction,
+  ProviderSwitchEvent,
+} from "./providers";
 export type { Prompt } from "./prompts";
+export type { AppEvent, EventFilter, EventKind } from "./events";
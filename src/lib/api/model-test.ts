@@ -3,7 +3,12 @@ import type { AppId } from "./types";
 
 // ===== 流式健康检查类型 =====
 
-export type HealthStatus = "operational" | "degraded" | "failed";
+export type HealthStatus =
+  | "operational"
+  | "degraded"
+  | "failed"
+  | "cancelled"
+  | "skipped";
 
 export interface StreamCheckConfig {
   timeoutSecs: number;
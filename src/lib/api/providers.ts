@@ -12,6 +12,32 @@ export interface ProviderSortUpdate {
   sortIndex: number;
 }
 
+/** 供应商列表中替代 Key 的占位符（与后端 key_reveal::REDACTED_KEY 一致） */
+export const REDACTED_KEY = "[REDACTED]";
+
+export type KeyRevealAction = "reveal" | "copy";
+
+export interface DuplicateMatch {
+  providerId: string;
+  name: string;
+  reasons: ("sameEndpointAndKey" | "sameName")[];
+}
+
+/** 新建或导入的供应商与已有供应商疑似重复 */
+export interface DuplicateProviderEvent {
+  appType: AppId;
+  providerId: string;
+  name: string;
+  matches: DuplicateMatch[];
+}
+
+/** 切换前的能力提示（如中转站不支持工具调用） */
+export interface CapabilityWarning {
+  capability: string;
+  severity: "error" | "warning";
+  message: string;
+}
+
 export interface ProviderSwitchEvent {
   appType: AppId;
   providerId: string;
@@ -26,20 +52,53 @@ export const providersApi = {
     return await invoke("get_current_provider", { app: appId });
   },
 
-  async add(provider: Provider, appId: AppId): Promise<boolean> {
-    return await invoke("add_provider", { provider, app: appId });
+  async add(
+    provider: Provider,
+    appId: AppId,
+    copySecretsFrom?: string,
+  ): Promise<boolean> {
+    return await invoke("add_provider", {
+      provider,
+      app: appId,
+      copySecretsFrom,
+    });
   },
 
   async update(provider: Provider, appId: AppId): Promise<boolean> {
     return await invoke("update_provider", { provider, app: appId });
   },
 
+  // 经系统认证后取回供应商的 Key 明文
+  async revealKey(
+    id: string,
+    appId: AppId,
+    action: KeyRevealAction,
+  ): Promise<string> {
+    return await invoke("reveal_provider_key", { id, app: appId, action });
+  },
+
   async delete(id: string, appId: AppId): Promise<boolean> {
     return await invoke("delete_provider", { id, app: appId });
   },
 
-  async switch(id: string, appId: AppId): Promise<boolean> {
-    return await invoke("switch_provider", { id, app: appId });
+  async switch(
+    id: string,
+    appId: AppId,
+    confirmOverwrite?: boolean,
+  ): Promise<boolean> {
+    return await invoke("switch_provider", {
+      id,
+      app: appId,
+      confirmOverwrite,
+    });
+  },
+
+  // 切换前检查供应商能力是否满足应用要求
+  async checkCapabilities(
+    id: string,
+    appId: AppId,
+  ): Promise<CapabilityWarning[]> {
+    return await invoke("check_provider_capabilities", { id, app: appId });
   },
 
   async importDefault(appId: AppId): Promise<boolean> {
@@ -57,6 +116,27 @@ export const providersApi = {
     return await invoke("update_providers_sort_order", { updates, app: appId });
   },
 
+  // 合并重复供应商：removeId 的历史记录归入 keepId 后删除 removeId
+  async mergeDuplicates(
+    keepId: string,
+    removeId: string,
+    appId: AppId,
+  ): Promise<boolean> {
+    return await invoke("merge_duplicate_providers", {
+      keepId,
+      removeId,
+      app: appId,
+    });
+  },
+
+  async onDuplicatesDetected(
+    handler: (event: DuplicateProviderEvent) => void,
+  ): Promise<UnlistenFn> {
+    return await listen("duplicate-provider-detected", (event) => {
+      handler(event.payload as DuplicateProviderEvent);
+    });
+  },
+
   async onSwitched(
     handler: (event: ProviderSwitchEvent) => void,
   ): Promise<UnlistenFn> {
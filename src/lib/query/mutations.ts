@@ -11,13 +11,20 @@ export const useAddProviderMutation = (appId: AppId) => {
   const { t } = useTranslation();
 
   return useMutation({
-    mutationFn: async (providerInput: Omit<Provider, "id">) => {
+    mutationFn: async ({
+      provider: providerInput,
+      copySecretsFrom,
+    }: {
+      provider: Omit<Provider, "id">;
+      // 复制供应商时沿用原供应商的 Key（列表中的 Key 已隐藏）
+      copySecretsFrom?: string;
+    }) => {
       const newProvider: Provider = {
         ...providerInput,
         id: generateUUID(),
         createdAt: Date.now(),
       };
-      await providersApi.add(newProvider, appId);
+      await providersApi.add(newProvider, appId, copySecretsFrom);
       return newProvider;
     },
     onSuccess: async () => {
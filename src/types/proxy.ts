@@ -29,6 +29,21 @@ export interface ProxyStatus {
   // 最近 5 秒滑动窗口 TPS（输出 token/秒，空闲为 0）
   tps: number;
   active_targets?: ActiveTarget[];
+  // 代理自身开销（开启测量时提供）
+  overhead?: ProxyOverheadStats;
+}
+
+export interface LatencySummary {
+  samples: number;
+  p50Ms: number;
+  p95Ms: number;
+  maxMs: number;
+}
+
+export interface ProxyOverheadStats {
+  request: LatencySummary;
+  chunk: LatencySummary;
+  upstream: LatencySummary;
 }
 
 export interface ActiveTarget {
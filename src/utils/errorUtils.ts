@@ -37,6 +37,17 @@ export const extractErrorMessage = (error: unknown): string => {
   return "";
 };
 
+/**
+ * 提取后端 AppError 的机器可读错误码（如 DB_LOCKED、UPSTREAM_TIMEOUT）
+ * @param error 错误对象
+ * @returns 错误码，非结构化错误返回 undefined
+ */
+export const extractErrorCode = (error: unknown): string | undefined => {
+  if (!error || typeof error !== "object") return undefined;
+  const code = (error as Record<string, unknown>).code;
+  return typeof code === "string" ? code : undefined;
+};
+
 /**
  * 将已知的 MCP 相关后端错误（通常为中文硬编码）映射为 i18n 文案
  * 采用包含式匹配，尽量稳健地覆盖不同上下文的相似消息。
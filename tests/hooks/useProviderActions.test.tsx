@@ -132,7 +132,10 @@ describe("useProviderActions", () => {
     });
 
     expect(addProviderMutateAsync).toHaveBeenCalledTimes(1);
-    expect(addProviderMutateAsync).toHaveBeenCalledWith(providerInput);
+    expect(addProviderMutateAsync).toHaveBeenCalledWith({
+      provider: providerInput,
+      copySecretsFrom: undefined,
+    });
   });
 
   it("should update tray menu when calling updateProvider", async () => {